@@ -0,0 +1,350 @@
+//! Attribute-based access control for authenticated routes.
+//!
+//! Every authenticated route names the action it performs. The request path
+//! (without the leading `/`) is the resource. The path's classification,
+//! owner and ACL come from the [`SecurityManager`] data classifications.
+//! The token's roles and scopes then go through the [`IAM`] policy. Grants
+//! are kept here and managed through the `/admin` endpoints. Tokens pick up
+//! the subject's granted roles when they are issued.
+//!
+//! With a store attached, grants and classifications made through the
+//! `/admin` endpoints are written to the database before they take effect
+//! and are reloaded by [`AccessControl::restore`] on startup. Admin grants
+//! from the service config are applied on every start and are not stored.
+
+use crate::auth::Claims;
+use dex_core::governance::{Action, Grant, IamError, Resource, Subject, ADMIN_ROLE, IAM};
+use dex_core::security::{ClassificationLevel, DataClassification, EventType, SecurityManager};
+use dex_db::{DatabaseError, DatabaseManager};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::RwLock;
+
+#[derive(Debug, Error)]
+pub enum AccessError {
+    #[error(transparent)]
+    Iam(#[from] IamError),
+    #[error("access control store failed: {0}")]
+    Storage(#[from] DatabaseError),
+}
+
+/// Policy engine plus the data classifications it evaluates.
+pub struct AccessControl {
+    iam: RwLock<IAM>,
+    security: RwLock<SecurityManager>,
+    store: Option<Arc<DatabaseManager>>,
+}
+
+impl AccessControl {
+    pub fn new(iam: IAM) -> Self {
+        Self {
+            iam: RwLock::new(iam),
+            security: RwLock::new(SecurityManager::new()),
+            store: None,
+        }
+    }
+
+    /// Persist grants and classifications in `database`.
+    pub fn with_store(mut self, database: Arc<DatabaseManager>) -> Self {
+        self.store = Some(database);
+        self
+    }
+
+    /// Reload the grants and classifications saved in the store.
+    pub async fn restore(&self) -> Result<(), AccessError> {
+        let Some(store) = &self.store else {
+            return Ok(());
+        };
+        let grants = store.load_grants().await?;
+        let classifications = store.load_classifications().await?;
+
+        let mut iam = self.iam.write().await;
+        for grant in grants {
+            iam.grant(
+                &grant.subject,
+                &grant.role,
+                &grant.granted_by,
+                grant.granted_at,
+                grant.expires_at,
+            )?;
+        }
+        let mut security = self.security.write().await;
+        for (resource_id, classification) in classifications {
+            security.restore_classification(resource_id, classification);
+        }
+        Ok(())
+    }
+
+    /// Grant the admin role to `subjects` on behalf of the service config.
+    pub async fn bootstrap_admins(
+        &self,
+        subjects: &HashSet<String>,
+        now: u64,
+    ) -> Result<(), IamError> {
+        let mut iam = self.iam.write().await;
+        for subject in subjects {
+            iam.grant(subject, ADMIN_ROLE, "config", now, None)?;
+        }
+        Ok(())
+    }
+
+    /// Decide whether the token holder may perform `action` on `resource_id`.
+    /// Denials are recorded as access violations.
+    pub async fn authorize(
+        &self,
+        claims: &Claims,
+        action: Action,
+        resource_id: &str,
+        now: u64,
+    ) -> Result<(), IamError> {
+        let subject = Subject::new(claims.sub.clone())
+            .with_roles(claims.roles.clone())
+            .with_scopes(claims.scopes());
+        let resource = self.resource(resource_id).await;
+        let decision = self
+            .iam
+            .read()
+            .await
+            .authorize(&subject, action, &resource, now);
+        if let Err(err) = &decision {
+            let mut data = HashMap::new();
+            data.insert("resource".to_string(), resource_id.to_string());
+            data.insert("action".to_string(), action.as_str().to_string());
+            self.security.write().await.log_event_simple(
+                EventType::AccessViolation,
+                err.to_string(),
+                Some(claims.sub.clone()),
+                data,
+            );
+        }
+        decision
+    }
+
+    /// Resource for `resource_id`, carrying its data classification if any.
+    pub async fn resource(&self, resource_id: &str) -> Resource {
+        let security = self.security.read().await;
+        match security.data_classification(resource_id) {
+            Some(classification) => Resource::classified(resource_id, classification),
+            None => Resource::new(resource_id),
+        }
+    }
+
+    /// Classify a resource so only its owner, its ACL and cleared roles reach it.
+    pub async fn classify(
+        &self,
+        resource_id: &str,
+        level: ClassificationLevel,
+        owner: String,
+        acl: Vec<String>,
+        now: u64,
+    ) -> Result<(), AccessError> {
+        let classification = DataClassification {
+            level,
+            owner,
+            acl,
+            timestamp: now,
+        };
+        let mut security = self.security.write().await;
+        if let Some(store) = &self.store {
+            store
+                .save_classification(resource_id, &classification)
+                .await?;
+        }
+        security.restore_classification(resource_id.to_string(), classification);
+        Ok(())
+    }
+
+    /// Grant a role, saving it first when a store is attached.
+    pub async fn grant(
+        &self,
+        subject: &str,
+        role: &str,
+        granted_by: &str,
+        now: u64,
+        expires_at: Option<u64>,
+    ) -> Result<Grant, AccessError> {
+        let mut iam = self.iam.write().await;
+        let previous = iam.grants(subject).into_iter().find(|g| g.role == role);
+        let grant = iam.grant(subject, role, granted_by, now, expires_at)?;
+        if let Some(store) = &self.store {
+            if let Err(err) = store.save_grant(&grant).await {
+                match previous {
+                    Some(previous) => iam.grant(
+                        subject,
+                        role,
+                        &previous.granted_by,
+                        previous.granted_at,
+                        previous.expires_at,
+                    )?,
+                    None => iam.revoke(subject, role)?,
+                };
+                return Err(err.into());
+            }
+        }
+        drop(iam);
+        self.log_change(granted_by, format!("granted role {} to {}", role, subject))
+            .await;
+        Ok(grant)
+    }
+
+    /// Withdraw a role, deleting it from the store when one is attached.
+    pub async fn revoke(
+        &self,
+        subject: &str,
+        role: &str,
+        revoked_by: &str,
+    ) -> Result<Grant, AccessError> {
+        let mut iam = self.iam.write().await;
+        let grant = iam.revoke(subject, role)?;
+        if let Some(store) = &self.store {
+            if let Err(err) = store.delete_grant(subject, role).await {
+                iam.grant(
+                    subject,
+                    role,
+                    &grant.granted_by,
+                    grant.granted_at,
+                    grant.expires_at,
+                )?;
+                return Err(err.into());
+            }
+        }
+        drop(iam);
+        self.log_change(
+            revoked_by,
+            format!("revoked role {} from {}", role, subject),
+        )
+        .await;
+        Ok(grant)
+    }
+
+    pub async fn grants(&self, subject: &str) -> Vec<Grant> {
+        self.iam.read().await.grants(subject)
+    }
+
+    /// Roles to embed in a token issued to `subject`.
+    pub async fn granted_roles(&self, subject: &str, now: u64) -> Vec<String> {
+        self.iam.read().await.granted_roles(subject, now)
+    }
+
+    async fn log_change(&self, actor: &str, description: String) {
+        self.security.write().await.log_event_simple(
+            EventType::ConfigurationChange,
+            description,
+            Some(actor.to_string()),
+            HashMap::new(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claims(sub: &str, roles: &[&str], scope: Option<&str>) -> Claims {
+        Claims {
+            sub: sub.to_string(),
+            exp: 0,
+            aud: None,
+            iss: None,
+            iat: None,
+            roles: roles.iter().map(|r| r.to_string()).collect(),
+            scope: scope.map(str::to_string),
+        }
+    }
+
+    #[tokio::test]
+    async fn admin_grants_follow_bootstrap_and_revocation() {
+        let access = AccessControl::new(IAM::with_default_policy());
+        let admins = HashSet::from(["root".to_string()]);
+        access.bootstrap_admins(&admins, 0).await.unwrap();
+        assert_eq!(access.granted_roles("root", 0).await, vec!["admin"]);
+
+        let root = claims("root", &["admin"], None);
+        assert!(access
+            .authorize(&root, Action::Admin, "admin/grants", 0)
+            .await
+            .is_ok());
+        let scoped = claims("root", &["admin"], Some("trades:read"));
+        assert!(access
+            .authorize(&scoped, Action::Admin, "admin/grants", 0)
+            .await
+            .is_err());
+
+        access.revoke("root", "admin", "root").await.unwrap();
+        assert!(access
+            .authorize(&root, Action::Admin, "admin/grants", 0)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn classified_paths_are_limited_to_their_acl() {
+        let access = AccessControl::new(IAM::with_default_policy());
+        let path = "orderbook/orders/7/trades";
+        let alice = claims("alice", &[], None);
+        assert!(access
+            .authorize(&alice, Action::Read, path, 0)
+            .await
+            .is_ok());
+
+        access
+            .classify(
+                path,
+                ClassificationLevel::Confidential,
+                "bob".to_string(),
+                vec!["carol".to_string()],
+                0,
+            )
+            .await
+            .unwrap();
+        assert!(access
+            .authorize(&alice, Action::Read, path, 0)
+            .await
+            .is_err());
+        assert!(access
+            .authorize(&claims("carol", &[], None), Action::Read, path, 0)
+            .await
+            .is_ok());
+
+        access
+            .grant("alice", "auditor", "root", 0, None)
+            .await
+            .unwrap();
+        assert!(access
+            .authorize(&claims("alice", &["auditor"], None), Action::Read, path, 0)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn failed_writes_leave_grants_and_classifications_unchanged() {
+        let store = DatabaseManager::connect_lazy("postgres://dex@127.0.0.1:1/dex").unwrap();
+        store.close().await;
+        let access = AccessControl::new(IAM::with_default_policy()).with_store(Arc::new(store));
+
+        let err = access
+            .grant("alice", "auditor", "root", 0, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AccessError::Storage(_)));
+        assert!(access.grants("alice").await.is_empty());
+
+        let path = "orderbook/orders/7/trades";
+        let err = access
+            .classify(
+                path,
+                ClassificationLevel::Confidential,
+                "bob".to_string(),
+                Vec::new(),
+                0,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AccessError::Storage(_)));
+        assert!(access
+            .authorize(&claims("alice", &[], None), Action::Read, path, 0)
+            .await
+            .is_ok());
+    }
+}
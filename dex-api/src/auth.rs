@@ -1,28 +1,66 @@
 //! JWT authentication helpers for Warp filters.
+//!
+//! Tokens are signed with the shared `JWT_SECRET` (HS256) until an ed25519
+//! signing key is installed from `dex_core::security::KeyRotationManager`.
+//! Every token names its signing key in the `kid` header. When the key
+//! rotates, tokens signed under the previous key keep verifying for a grace
+//! period so sessions survive the rotation.
 
+use dex_core::security::{KeyPair, KeyRotationEvent};
 use ethers_core::{
     types::{Address, Signature},
     utils::hash_message,
 };
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use jsonwebtoken::{
+    decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
 use secrecy::{ExposeSecret, SecretString};
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashMap,
     fmt,
     str::FromStr,
-    sync::Arc,
+    sync::{Arc, RwLock},
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use thiserror::Error;
+use tokio::{sync::mpsc::UnboundedReceiver, task::JoinHandle};
 use warp::reject::Reject;
+use zeroize::Zeroizing;
+
+/// Key ID of the shared HS256 secret; tokens without a `kid` are checked against it.
+pub const SHARED_SECRET_KID: &str = "shared-secret";
+
+/// Owner of the JWT signing keys in the key rotation manager.
+pub const JWT_KEY_OWNER: &str = "dex-api-jwt";
+
+/// PKCS#8 v1 prefix of an ed25519 private key, followed by the 32-byte seed.
+const ED25519_PKCS8_PREFIX: [u8; 16] = [
+    0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22, 0x04, 0x20,
+];
 
 /// Shared authentication manager that validates bearer tokens.
 #[derive(Clone)]
 pub struct AuthManager {
-    decoding_key: Arc<DecodingKey>,
-    encoding_key: Arc<EncodingKey>,
+    keys: Arc<RwLock<KeyRing>>,
     validation: Validation,
     issuer: Arc<String>,
+    grace_period: Duration,
+}
+
+/// Current signing key and every key tokens may still be signed with.
+struct KeyRing {
+    signing_kid: String,
+    signing_key: EncodingKey,
+    signing_algorithm: Algorithm,
+    verifying: HashMap<String, VerifyingKey>,
+}
+
+struct VerifyingKey {
+    key: DecodingKey,
+    algorithm: Algorithm,
+    /// End of the grace period once the key has been rotated out.
+    accepted_until: Option<u64>,
 }
 
 impl AuthManager {
@@ -31,30 +69,160 @@ impl AuthManager {
         let encoding_key = EncodingKey::from_secret(secret.expose_secret().as_bytes());
         let mut validation = Validation::default();
         validation.validate_exp = true;
+        let mut verifying = HashMap::new();
+        verifying.insert(
+            SHARED_SECRET_KID.to_string(),
+            VerifyingKey {
+                key: decoding_key,
+                algorithm: Algorithm::HS256,
+                accepted_until: None,
+            },
+        );
         Self {
-            decoding_key: Arc::new(decoding_key),
-            encoding_key: Arc::new(encoding_key),
+            keys: Arc::new(RwLock::new(KeyRing {
+                signing_kid: SHARED_SECRET_KID.to_string(),
+                signing_key: encoding_key,
+                signing_algorithm: Algorithm::HS256,
+                verifying,
+            })),
             validation,
             issuer: Arc::new(issuer.into()),
+            grace_period: Duration::from_secs(3600),
         }
     }
 
+    /// How long tokens signed under a rotated-out key stay valid. Should be
+    /// at least the longest token TTL.
+    pub fn with_grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+
     pub fn verify_bearer(&self, header_value: &str) -> Result<Claims, AuthError> {
         let token = header_value
             .strip_prefix("Bearer ")
             .ok_or(AuthError::MissingBearer)?;
 
-        let token_data = decode::<Claims>(token, &self.decoding_key, &self.validation)
+        let header =
+            decode_header(token).map_err(|err| AuthError::InvalidToken(err.to_string()))?;
+        let kid = header.kid.as_deref().unwrap_or(SHARED_SECRET_KID);
+        let now = current_unix_timestamp().map_err(|_| AuthError::TimeSource)?;
+        let keys = self.keys.read().unwrap_or_else(|err| err.into_inner());
+        let key = keys
+            .verifying
+            .get(kid)
+            .filter(|key| key.accepted_until.is_none_or(|until| now <= until))
+            .ok_or_else(|| AuthError::UnknownKey(kid.to_string()))?;
+        let mut validation = self.validation.clone();
+        validation.algorithms = vec![key.algorithm];
+
+        let token_data = decode::<Claims>(token, &key.key, &validation)
             .map_err(|err| AuthError::InvalidToken(err.to_string()))?;
 
         Ok(token_data.claims)
     }
 
+    /// Key ID that newly issued tokens carry.
+    pub fn current_kid(&self) -> String {
+        self.keys
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .signing_kid
+            .clone()
+    }
+
+    /// Sign new tokens with an ed25519 key from the key rotation manager. The
+    /// previous signing key is accepted for the grace period.
+    pub fn install_signing_key(&self, key: &KeyPair) -> Result<(), AuthError> {
+        let (encoding_key, decoding_key) = ed25519_keys(key)?;
+        let now = current_unix_timestamp().map_err(|_| AuthError::TimeSource)?;
+        let accepted_until = now + self.grace_period.as_secs();
+
+        let mut keys = self.keys.write().unwrap_or_else(|err| err.into_inner());
+        let previous = keys.signing_kid.clone();
+        if previous == key.key_id {
+            return Ok(());
+        }
+        if let Some(previous) = keys.verifying.get_mut(&previous) {
+            previous.accepted_until.get_or_insert(accepted_until);
+        }
+        keys.verifying
+            .retain(|_, key| key.accepted_until.is_none_or(|until| now <= until));
+        keys.verifying.insert(
+            key.key_id.clone(),
+            VerifyingKey {
+                key: decoding_key,
+                algorithm: Algorithm::EdDSA,
+                accepted_until: None,
+            },
+        );
+        keys.signing_kid = key.key_id.clone();
+        keys.signing_key = encoding_key;
+        keys.signing_algorithm = Algorithm::EdDSA;
+        Ok(())
+    }
+
+    /// Keep accepting tokens signed under a key that was rotated out at
+    /// `retired_at`, for the rest of its grace period. Used to restore keys
+    /// from the keystore after a restart.
+    pub fn accept_retired_key(&self, key: &KeyPair, retired_at: u64) -> Result<(), AuthError> {
+        let accepted_until = retired_at + self.grace_period.as_secs();
+        let now = current_unix_timestamp().map_err(|_| AuthError::TimeSource)?;
+        if accepted_until < now {
+            return Ok(());
+        }
+        let (_, decoding_key) = ed25519_keys(key)?;
+        let mut keys = self.keys.write().unwrap_or_else(|err| err.into_inner());
+        if keys.signing_kid != key.key_id {
+            keys.verifying.insert(
+                key.key_id.clone(),
+                VerifyingKey {
+                    key: decoding_key,
+                    algorithm: Algorithm::EdDSA,
+                    accepted_until: Some(accepted_until),
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Switch signing keys whenever the key rotation manager rotates the JWT key.
+    pub fn follow_rotations(
+        &self,
+        mut events: UnboundedReceiver<KeyRotationEvent>,
+    ) -> JoinHandle<()> {
+        let auth = self.clone();
+        tokio::spawn(async move {
+            while let Some(event) = events.recv().await {
+                if let Err(err) = auth.install_signing_key(&event.key) {
+                    dex_core::redacted_eprintln!(
+                        "failed to install rotated JWT key {}: {}",
+                        event.key.key_id,
+                        err
+                    );
+                }
+            }
+        })
+    }
+
     pub fn issue_token(
         &self,
         subject: impl Into<String>,
         ttl: Duration,
         audience: Option<String>,
+    ) -> Result<IssuedToken, AuthError> {
+        self.issue_scoped_token(subject, ttl, audience, Vec::new(), None)
+    }
+
+    /// Issue a token carrying `roles` and, when given, restricted to the
+    /// space-separated `scope`.
+    pub fn issue_scoped_token(
+        &self,
+        subject: impl Into<String>,
+        ttl: Duration,
+        audience: Option<String>,
+        roles: Vec<String>,
+        scope: Option<String>,
     ) -> Result<IssuedToken, AuthError> {
         let ttl = if ttl.is_zero() {
             Duration::from_secs(60)
@@ -69,9 +237,18 @@ impl AuthManager {
             aud: audience,
             iss: Some((*self.issuer).clone()),
             iat: Some(now as usize),
+            roles,
+            scope,
+        };
+        let token = {
+            let keys = self.keys.read().unwrap_or_else(|err| err.into_inner());
+            let header = Header {
+                kid: Some(keys.signing_kid.clone()),
+                ..Header::new(keys.signing_algorithm)
+            };
+            encode(&header, &claims, &keys.signing_key)
+                .map_err(|err| AuthError::TokenIssuance(err.to_string()))?
         };
-        let token = encode(&Header::default(), &claims, &self.encoding_key)
-            .map_err(|err| AuthError::TokenIssuance(err.to_string()))?;
         Ok(IssuedToken { token, expires_at })
     }
 }
@@ -87,6 +264,23 @@ pub struct Claims {
     pub iss: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub iat: Option<usize>,
+    /// Roles granted to the subject when the token was issued. They only
+    /// count while the grant is still active.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+    /// Space-separated scopes the token is restricted to; unrestricted when
+    /// absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+}
+
+impl Claims {
+    /// Scopes the token is restricted to, or `None` when it is unrestricted.
+    pub fn scopes(&self) -> Option<Vec<String>> {
+        self.scope
+            .as_ref()
+            .map(|scope| scope.split_whitespace().map(str::to_string).collect())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -111,6 +305,10 @@ pub enum AuthError {
     SignatureMismatch,
     #[error("system clock unavailable")]
     TimeSource,
+    #[error("token signing key {0} is unknown or retired")]
+    UnknownKey(String),
+    #[error("unsupported signing key: {0}")]
+    UnsupportedKey(String),
 }
 
 #[derive(Debug)]
@@ -162,8 +360,87 @@ pub fn normalize_address(input: &str) -> Result<String, AuthError> {
     Ok(trimmed)
 }
 
+/// JWT signing and verification keys for an ed25519 key pair.
+fn ed25519_keys(key: &KeyPair) -> Result<(EncodingKey, DecodingKey), AuthError> {
+    if key.algorithm != "Ed25519" || key.private_key.len() != 32 || key.public_key.len() != 32 {
+        return Err(AuthError::UnsupportedKey(key.key_id.clone()));
+    }
+    let mut pkcs8 = Zeroizing::new(ED25519_PKCS8_PREFIX.to_vec());
+    pkcs8.extend_from_slice(&key.private_key);
+    // jsonwebtoken hands EdDSA verification keys to ring, which expects the raw 32 bytes
+    Ok((
+        EncodingKey::from_ed_der(&pkcs8),
+        DecodingKey::from_ed_der(&key.public_key),
+    ))
+}
+
 fn current_unix_timestamp() -> Result<u64, std::time::SystemTimeError> {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .map(|duration| duration.as_secs())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dex_core::security::KeyRotationManager;
+
+    fn kid_of(token: &str) -> Option<String> {
+        decode_header(token).unwrap().kid
+    }
+
+    #[tokio::test]
+    async fn tokens_survive_rotation_for_the_grace_period() {
+        let auth = AuthManager::new(&SecretString::from("test-secret".to_string()), "issuer")
+            .with_grace_period(Duration::from_secs(60));
+        let shared = auth
+            .issue_token("alice", Duration::from_secs(300), None)
+            .unwrap();
+        assert_eq!(kid_of(&shared.token).as_deref(), Some(SHARED_SECRET_KID));
+
+        let mut keys = KeyRotationManager::new(3600);
+        let follower = auth.follow_rotations(keys.subscribe(JWT_KEY_OWNER));
+        let first = keys.rotate_keys(JWT_KEY_OWNER).unwrap();
+        let second = keys.rotate_keys(JWT_KEY_OWNER).unwrap();
+        drop(keys);
+        follower.await.unwrap();
+        assert_eq!(auth.current_kid(), second.key_id);
+
+        // Tokens from the shared secret and the first key are still in their grace period
+        let bearer = |token: &str| format!("Bearer {}", token);
+        assert_eq!(
+            auth.verify_bearer(&bearer(&shared.token)).unwrap().sub,
+            "alice"
+        );
+        let issued = auth
+            .issue_token("bob", Duration::from_secs(300), None)
+            .unwrap();
+        assert_eq!(kid_of(&issued.token), Some(second.key_id.clone()));
+        assert_eq!(
+            auth.verify_bearer(&bearer(&issued.token)).unwrap().sub,
+            "bob"
+        );
+
+        // A token claiming the first key but signed by another one does not verify
+        let mut forged = Header::new(Algorithm::EdDSA);
+        forged.kid = Some(first.key_id.clone());
+        let (other_key, _) = ed25519_keys(&second).unwrap();
+        let claims = auth.verify_bearer(&bearer(&issued.token)).unwrap();
+        let token = encode(&forged, &claims, &other_key).unwrap();
+        assert!(matches!(
+            auth.verify_bearer(&bearer(&token)),
+            Err(AuthError::InvalidToken(_))
+        ));
+
+        // Once the grace period is over the old keys are refused
+        let expired = AuthManager::new(&SecretString::from("test-secret".to_string()), "issuer")
+            .with_grace_period(Duration::ZERO);
+        expired.install_signing_key(&first).unwrap();
+        expired.install_signing_key(&second).unwrap();
+        std::thread::sleep(Duration::from_millis(1100));
+        assert!(matches!(
+            expired.verify_bearer(&bearer(&shared.token)),
+            Err(AuthError::UnknownKey(_))
+        ));
+    }
+}
@@ -17,6 +17,13 @@ struct Args {
     /// Optional audience claim
     #[arg(long)]
     audience: Option<String>,
+    /// Role to embed in the token; repeat for several. Roles only take effect
+    /// while the API holds a matching grant.
+    #[arg(long = "role")]
+    roles: Vec<String>,
+    /// Space-separated scopes to restrict the token to
+    #[arg(long)]
+    scope: Option<String>,
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -28,7 +35,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         config.jwt_default_ttl_seconds,
         config.jwt_max_ttl_seconds,
     );
-    let issued = auth.issue_token(args.trader_id, ttl, args.audience)?;
+    let issued =
+        auth.issue_scoped_token(args.trader_id, ttl, args.audience, args.roles, args.scope)?;
     println!("token={}", issued.token);
     println!("expires_at={}", issued.expires_at);
     Ok(())
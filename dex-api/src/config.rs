@@ -5,7 +5,11 @@
 
 use dotenvy::dotenv;
 use secrecy::SecretString;
-use std::{collections::HashMap, env, num::ParseIntError};
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    num::ParseIntError,
+};
 use thiserror::Error;
 
 /// Runtime configuration for the API service.
@@ -19,6 +23,25 @@ pub struct Config {
     pub wallet_challenge_ttl_seconds: u64,
     pub trader_secrets: HashMap<String, SecretString>,
     pub server_port: u16,
+    /// Markets (`BASE/QUOTE`) that require a KYC credential to trade.
+    pub kyc_required_markets: HashSet<String>,
+    /// JSON file listing the DIDs of trusted KYC credential issuers.
+    pub kyc_issuers_file: Option<String>,
+    /// Encrypted keystore of rotating ed25519 JWT signing keys. Tokens are
+    /// signed with `jwt_secret` when unset.
+    pub jwt_keystore_path: Option<String>,
+    /// Password protecting the JWT keystore.
+    pub jwt_keystore_password: Option<SecretString>,
+    /// Lifetime of a JWT signing key before it is rotated.
+    pub jwt_key_rotation_seconds: u64,
+    /// How long tokens signed under a rotated-out key stay valid.
+    pub jwt_key_grace_seconds: u64,
+    /// Secret keying the tokens that replace PII in logs, so they stay the
+    /// same across restarts and instances. Tokens are keyed randomly per
+    /// process when unset.
+    pub pii_token_secret: Option<SecretString>,
+    /// Subjects granted the admin role at startup so they can manage grants.
+    pub admin_subjects: HashSet<String>,
 }
 
 impl Config {
@@ -37,16 +60,39 @@ impl Config {
         let jwt_max_ttl_seconds = parse_u64("JWT_MAX_TTL_SECONDS", 3600)?;
         let wallet_challenge_ttl_seconds = parse_u64("WALLET_CHALLENGE_TTL_SECONDS", 300)?;
         let trader_secrets = parse_trader_secrets(env::var("TRADER_SECRETS").ok())?;
+        let kyc_required_markets = parse_markets(env::var("KYC_REQUIRED_MARKETS").ok())?;
+        let kyc_issuers_file = env::var("KYC_ISSUERS_FILE").ok();
+        let jwt_keystore_path = env::var("JWT_KEYSTORE_PATH").ok();
+        let jwt_keystore_password = match &jwt_keystore_path {
+            Some(_) => Some(SecretString::from(
+                env::var("JWT_KEYSTORE_PASSWORD")
+                    .map_err(|_| ConfigError::Missing("JWT_KEYSTORE_PASSWORD"))?,
+            )),
+            None => None,
+        };
+        let jwt_key_rotation_seconds = parse_u64("JWT_KEY_ROTATION_SECONDS", 86_400)?;
+        let jwt_max_ttl_seconds = jwt_max_ttl_seconds.max(jwt_default_ttl_seconds);
+        let jwt_key_grace_seconds = parse_u64("JWT_KEY_GRACE_SECONDS", jwt_max_ttl_seconds)?;
+        let pii_token_secret = env::var("PII_TOKEN_SECRET").ok().map(SecretString::from);
+        let admin_subjects = parse_subjects(env::var("ADMIN_SUBJECTS").ok());
 
         Ok(Self {
             database_url: SecretString::from(database_url),
             jwt_secret: SecretString::from(jwt_secret),
             jwt_issuer,
             jwt_default_ttl_seconds: jwt_default_ttl_seconds.max(60),
-            jwt_max_ttl_seconds: jwt_max_ttl_seconds.max(jwt_default_ttl_seconds),
+            jwt_max_ttl_seconds,
             wallet_challenge_ttl_seconds: wallet_challenge_ttl_seconds.max(60),
             trader_secrets,
             server_port,
+            kyc_required_markets,
+            kyc_issuers_file,
+            jwt_keystore_path,
+            jwt_keystore_password,
+            jwt_key_rotation_seconds: jwt_key_rotation_seconds.max(60),
+            jwt_key_grace_seconds,
+            pii_token_secret,
+            admin_subjects,
         })
     }
 }
@@ -73,6 +119,8 @@ pub enum ConfigError {
     },
     #[error("invalid TRADER_SECRETS entry '{entry}', expected trader:secret")]
     InvalidTraderSecret { entry: String },
+    #[error("invalid KYC_REQUIRED_MARKETS entry '{entry}', expected BASE/QUOTE")]
+    InvalidMarket { entry: String },
 }
 
 fn parse_u64(var: &'static str, default: u64) -> Result<u64, ConfigError> {
@@ -108,3 +156,41 @@ fn parse_trader_secrets(raw: Option<String>) -> Result<HashMap<String, SecretStr
     }
     Ok(map)
 }
+
+fn parse_markets(raw: Option<String>) -> Result<HashSet<String>, ConfigError> {
+    let mut markets = HashSet::new();
+    if let Some(raw) = raw {
+        for entry in raw.split(',') {
+            let entry = entry.trim();
+            if entry.is_empty() {
+                continue;
+            }
+            match entry.split_once('/') {
+                Some((base, quote)) if !base.trim().is_empty() && !quote.trim().is_empty() => {
+                    markets.insert(format!(
+                        "{}/{}",
+                        base.trim().to_ascii_uppercase(),
+                        quote.trim().to_ascii_uppercase()
+                    ));
+                }
+                _ => {
+                    return Err(ConfigError::InvalidMarket {
+                        entry: entry.to_string(),
+                    })
+                }
+            }
+        }
+    }
+    Ok(markets)
+}
+
+fn parse_subjects(raw: Option<String>) -> HashSet<String> {
+    raw.map(|raw| {
+        raw.split(',')
+            .map(str::trim)
+            .filter(|subject| !subject.is_empty())
+            .map(str::to_string)
+            .collect()
+    })
+    .unwrap_or_default()
+}
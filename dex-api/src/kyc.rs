@@ -0,0 +1,190 @@
+//! KYC gating of markets with verifiable credentials.
+//!
+//! Traders present a `KycCredential` issued to their `did:dexos` identifier by
+//! one of the configured issuers. Orders on markets listed in
+//! `KYC_REQUIRED_MARKETS` are only accepted from traders holding a credential
+//! that still verifies, so expiry and revocations imported from the issuers'
+//! status lists take effect on the next order.
+
+use dex_core::identity::{
+    did_uri, DidDocumentJsonLd, IdentityError, IdentityManager, VerifiableCredential, DID,
+    KYC_CREDENTIAL_TYPE,
+};
+use dex_core::types::TradingPair;
+use std::collections::{HashMap, HashSet};
+use thiserror::Error;
+use tokio::sync::RwLock;
+
+/// Verifies KYC credentials and decides which traders may use gated markets.
+pub struct KycGate {
+    identity: RwLock<IdentityManager>,
+    required_markets: HashSet<String>,
+    credentials: RwLock<HashMap<String, Vec<VerifiableCredential>>>,
+}
+
+impl KycGate {
+    /// Trust the given issuer DIDs and gate `required_markets` (`BASE/QUOTE`).
+    pub fn new(issuers: Vec<DID>, required_markets: HashSet<String>) -> Result<Self, KycError> {
+        let mut identity = IdentityManager::new();
+        for issuer in issuers {
+            let issuer_id = issuer.id.clone();
+            identity
+                .register_did(issuer)
+                .map_err(|err| KycError::InvalidIssuer(format!("{}: {}", issuer_id, err)))?;
+            identity.add_trusted_issuer(issuer_id);
+        }
+        Ok(Self {
+            identity: RwLock::new(identity),
+            required_markets,
+            credentials: RwLock::new(HashMap::new()),
+        })
+    }
+
+    /// Canonical `BASE/QUOTE` market name used in configuration.
+    pub fn market_key(pair: &TradingPair) -> String {
+        format!(
+            "{}/{}",
+            pair.base.to_ascii_uppercase(),
+            pair.quote.to_ascii_uppercase()
+        )
+    }
+
+    pub fn requires_kyc(&self, pair: &TradingPair) -> bool {
+        self.required_markets.contains(&Self::market_key(pair))
+    }
+
+    /// Accept a KYC credential presented by an authenticated trader.
+    pub async fn present(
+        &self,
+        trader_id: &str,
+        credential: VerifiableCredential,
+    ) -> Result<(), KycError> {
+        if credential.subject() != did_uri(trader_id) {
+            return Err(KycError::SubjectMismatch);
+        }
+        if !credential.has_type(KYC_CREDENTIAL_TYPE) {
+            return Err(KycError::NotKyc);
+        }
+        {
+            let identity = self.identity.read().await;
+            if !identity.is_trusted_issuer(&credential.issuer) {
+                return Err(KycError::UntrustedIssuer);
+            }
+            identity
+                .verify_credential(&credential)
+                .map_err(|err| KycError::Rejected(err.to_string()))?;
+        }
+
+        let mut credentials = self.credentials.write().await;
+        let held = credentials.entry(trader_id.to_string()).or_default();
+        held.retain(|existing| existing.id != credential.id);
+        held.push(credential);
+        Ok(())
+    }
+
+    /// Whether the trader holds a KYC credential that verifies right now.
+    pub async fn is_verified(&self, trader_id: &str) -> bool {
+        let credentials = self.credentials.read().await;
+        let Some(held) = credentials.get(trader_id) else {
+            return false;
+        };
+        let identity = self.identity.read().await;
+        held.iter()
+            .any(|credential| identity.verify_credential(credential).is_ok())
+    }
+
+    /// Resolve a `did:dexos` identifier known to this gate.
+    pub async fn resolve(&self, did: &str) -> Result<DidDocumentJsonLd, IdentityError> {
+        self.identity.read().await.resolve(did)
+    }
+
+    /// Import an issuer's signed revocation status list.
+    pub async fn import_status_list(
+        &self,
+        credential: &VerifiableCredential,
+    ) -> Result<(), KycError> {
+        let mut identity = self.identity.write().await;
+        if !identity.is_trusted_issuer(&credential.issuer) {
+            return Err(KycError::UntrustedIssuer);
+        }
+        identity
+            .import_status_list(credential)
+            .map_err(|err| KycError::Rejected(err.to_string()))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum KycError {
+    #[error("credential subject is not the authenticated trader")]
+    SubjectMismatch,
+    #[error("credential is not a KYC credential")]
+    NotKyc,
+    #[error("credential issuer is not trusted for KYC")]
+    UntrustedIssuer,
+    #[error("invalid KYC issuer DID {0}")]
+    InvalidIssuer(String),
+    #[error("credential rejected: {0}")]
+    Rejected(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn pair(base: &str, quote: &str) -> TradingPair {
+        TradingPair {
+            base: base.into(),
+            quote: quote.into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn gates_markets_on_valid_credentials() {
+        let mut issuer = IdentityManager::new();
+        let (issuer_did, issuer_key) = issuer
+            .create_did_with_keys(&"kyc-provider".to_string())
+            .expect("issuer did");
+        let markets = HashSet::from(["ETH/USDC".to_string()]);
+        let gate = KycGate::new(vec![issuer_did], markets).expect("gate");
+
+        assert!(gate.requires_kyc(&pair("eth", "usdc")));
+        assert!(!gate.requires_kyc(&pair("BTC", "USDC")));
+        assert!(!gate.is_verified("alice").await);
+
+        let credential = issuer
+            .issue_credential(
+                VerifiableCredential::new(
+                    "kyc-alice",
+                    "kyc-provider",
+                    "alice",
+                    KYC_CREDENTIAL_TYPE,
+                    BTreeMap::new(),
+                ),
+                &issuer_key,
+            )
+            .expect("issued");
+        let status_list = issuer
+            .status_list_credential("kyc-provider", &issuer_key)
+            .expect("status list");
+        gate.import_status_list(&status_list).await.expect("import");
+
+        // Only the subject may present it
+        assert!(matches!(
+            gate.present("bob", credential.clone()).await,
+            Err(KycError::SubjectMismatch)
+        ));
+        gate.present("alice", credential.clone())
+            .await
+            .expect("presented");
+        assert!(gate.is_verified("alice").await);
+
+        // Revocation takes effect once the new list is imported
+        issuer.revoke_credential(&credential).expect("revoked");
+        let status_list = issuer
+            .status_list_credential("kyc-provider", &issuer_key)
+            .expect("status list");
+        gate.import_status_list(&status_list).await.expect("import");
+        assert!(!gate.is_verified("alice").await);
+    }
+}
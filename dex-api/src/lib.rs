@@ -2,18 +2,25 @@
 //!
 //! This module provides HTTP API endpoints for interacting with the DEX.
 
+pub mod access;
 pub mod auth;
 pub mod challenge;
 pub mod config;
+pub mod kyc;
 
+pub use access::{AccessControl, AccessError};
 pub use auth::Claims;
 pub use challenge::ChallengeStore;
 pub use config::Config;
+pub use kyc::KycGate;
 
 use auth::{clamp_ttl, normalize_address, verify_wallet_signature, AuthManager, AuthRejection};
 use challenge::ChallengeError;
 use dex_core::{
+    governance::{Action, Grant, IamError},
+    identity::{IdentityError, VerifiableCredential},
     orderbook::OrderBook,
+    security::{redaction, ClassificationLevel},
     types::{OrderId, Price, Quantity, Trade, TraderId},
 };
 use dex_db::DatabaseManager;
@@ -32,7 +39,8 @@ use tokio::sync::{broadcast, RwLock};
 use warp::{
     filters::body::BodyDeserializeError,
     http::StatusCode,
-    reject::{MethodNotAllowed, MissingHeader},
+    path::FullPath,
+    reject::{MethodNotAllowed, MissingHeader, Reject},
     ws::{Message, WebSocket, Ws},
     Filter,
 };
@@ -48,6 +56,8 @@ pub struct ApiState {
     pub config: Config,
     pub wallet_challenges: Arc<ChallengeStore>,
     pub market_tx: broadcast::Sender<DepthSnapshot>,
+    pub kyc: Arc<KycGate>,
+    pub access: Arc<AccessControl>,
 }
 
 /// Request to create a new order
@@ -157,6 +167,9 @@ struct SharedTokenRequest {
     ttl_seconds: Option<u64>,
     #[serde(default)]
     audience: Option<String>,
+    /// Space-separated scopes to restrict the token to
+    #[serde(default)]
+    scope: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -172,14 +185,49 @@ struct WalletTokenRequest {
     ttl_seconds: Option<u64>,
     #[serde(default)]
     audience: Option<String>,
+    /// Space-separated scopes to restrict the token to
+    #[serde(default)]
+    scope: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GrantRequest {
+    subject: String,
+    role: String,
+    #[serde(default)]
+    expires_at: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct GrantsResponse {
+    subject: String,
+    grants: Vec<Grant>,
+}
+
+#[derive(Deserialize)]
+struct ClassificationRequest {
+    resource: String,
+    level: ClassificationLevel,
+    owner: TraderId,
+    #[serde(default)]
+    acl: Vec<TraderId>,
 }
 
 #[derive(Serialize)]
 struct ErrorResponse {
     code: &'static str,
+    /// Messages can echo request data back, so PII in them is masked
+    #[serde(serialize_with = "serialize_redacted")]
     message: String,
 }
 
+fn serialize_redacted<S: serde::Serializer>(
+    message: &str,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&redaction::redact(message, &ClassificationLevel::Public))
+}
+
 /// Create the API routes
 pub fn routes(
     state: ApiState,
@@ -190,7 +238,7 @@ pub fn routes(
     let create_order = orderbook
         .and(warp::path("orders"))
         .and(warp::post())
-        .and(authenticated(state.clone()))
+        .and(authorized(state.clone(), Action::Write))
         .and(warp::body::content_length_limit(8 * 1024))
         .and(warp::body::json())
         .and_then(handle_create_order)
@@ -218,7 +266,7 @@ pub fn routes(
         .and(warp::path::param::<u64>())
         .and(warp::path("trades"))
         .and(warp::get())
-        .and(authenticated(state.clone()))
+        .and(authorized(state.clone(), Action::Read))
         .and_then(handle_get_trades_for_order)
         .boxed();
 
@@ -228,7 +276,7 @@ pub fn routes(
         .and(warp::path::param::<String>())
         .and(warp::path("trades"))
         .and(warp::get())
-        .and(authenticated(state.clone()))
+        .and(authorized(state.clone(), Action::Read))
         .and_then(handle_get_trades_for_trader)
         .boxed();
 
@@ -242,6 +290,10 @@ pub fn routes(
 
     let auth_endpoints = auth_routes(state.clone()).boxed();
 
+    let identity_endpoints = identity_routes(state.clone()).boxed();
+
+    let admin_endpoints = admin_routes(state.clone()).boxed();
+
     // Health endpoint
     let health = warp::path("health")
         .and(warp::get())
@@ -253,7 +305,7 @@ pub fn routes(
 
     let cors = warp::cors()
         .allow_any_origin()
-        .allow_methods(vec!["GET", "POST"])
+        .allow_methods(vec!["GET", "POST", "DELETE"])
         .allow_headers(vec!["content-type", "authorization"]);
 
     create_order
@@ -263,6 +315,8 @@ pub fn routes(
         .or(get_depth)
         .or(depth_ws)
         .or(auth_endpoints)
+        .or(identity_endpoints)
+        .or(admin_endpoints)
         .or(health)
         .with(cors)
         .recover(handle_rejection)
@@ -301,6 +355,75 @@ fn auth_routes(
     shared.or(challenge).or(wallet_token).boxed()
 }
 
+fn identity_routes(state: ApiState) -> warp::filters::BoxedFilter<(impl warp::Reply,)> {
+    let resolve = warp::path("identity")
+        .and(warp::path("dids"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(with_state(state.clone()))
+        .and_then(handle_resolve_did);
+
+    let status_list = warp::path("identity")
+        .and(warp::path("status-lists"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(with_state(state.clone()))
+        .and(warp::body::content_length_limit(64 * 1024))
+        .and(warp::body::json())
+        .and_then(handle_import_status_list);
+
+    let present = warp::path("identity")
+        .and(warp::path("credentials"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(authorized(state, Action::Write))
+        .and(warp::body::content_length_limit(16 * 1024))
+        .and(warp::body::json())
+        .and_then(handle_present_credential);
+
+    resolve.or(status_list).or(present).boxed()
+}
+
+fn admin_routes(state: ApiState) -> warp::filters::BoxedFilter<(impl warp::Reply,)> {
+    let list_grants = warp::path("admin")
+        .and(warp::path("grants"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(authorized(state.clone(), Action::Admin))
+        .and_then(handle_list_grants);
+
+    let grant = warp::path("admin")
+        .and(warp::path("grants"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(authorized(state.clone(), Action::Admin))
+        .and(warp::body::content_length_limit(2 * 1024))
+        .and(warp::body::json())
+        .and_then(handle_grant_role);
+
+    let revoke = warp::path("admin")
+        .and(warp::path("grants"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(warp::delete())
+        .and(authorized(state.clone(), Action::Admin))
+        .and_then(handle_revoke_role);
+
+    let classify = warp::path("admin")
+        .and(warp::path("classifications"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(authorized(state, Action::Admin))
+        .and(warp::body::content_length_limit(4 * 1024))
+        .and(warp::body::json())
+        .and_then(handle_classify_resource);
+
+    list_grants.or(grant).or(revoke).or(classify).boxed()
+}
+
 /// Helper to pass state to handlers
 fn with_state(state: ApiState) -> impl Filter<Extract = (ApiState,), Error = Infallible> + Clone {
     warp::any().map(move || state.clone())
@@ -320,6 +443,31 @@ fn authenticated(
         .untuple_one()
 }
 
+/// Authenticate the request, then check the token may perform `action` on the
+/// request path.
+fn authorized(
+    state: ApiState,
+    action: Action,
+) -> impl Filter<Extract = (Claims, ApiState), Error = warp::Rejection> + Clone {
+    warp::path::full()
+        .and(authenticated(state))
+        .and_then(move |path: FullPath, claims: Claims, state: ApiState| async move {
+            let now =
+                current_unix_timestamp().map_err(|_| warp::reject::custom(InternalError))?;
+            let resource = path.as_str().trim_matches('/');
+            match state.access.authorize(&claims, action, resource, now).await {
+                Ok(()) => Ok((claims, state)),
+                Err(err) => Err(warp::reject::custom(AccessRejection(err))),
+            }
+        })
+        .untuple_one()
+}
+
+#[derive(Debug)]
+struct AccessRejection(IamError);
+
+impl Reject for AccessRejection {}
+
 fn optional_depth_query(
 ) -> impl Filter<Extract = (Option<String>,), Error = warp::Rejection> + Clone {
     warp::query::raw().map(Some)
@@ -348,6 +496,15 @@ async fn handle_create_order(
         ));
     }
 
+    if state.kyc.requires_kyc(&order_for_storage.pair) && !state.kyc.is_verified(&claims.sub).await
+    {
+        return Ok(error_reply(
+            "kyc_required",
+            "a valid KYC credential is required to trade this market",
+            StatusCode::FORBIDDEN,
+        ));
+    }
+
     let mut orderbook = state.orderbook.write().await;
     let result = orderbook.add_order(order);
     drop(orderbook);
@@ -366,7 +523,7 @@ async fn handle_create_order(
     };
 
     if let Err(err) = state.database.save_order(&order_for_storage).await {
-        eprintln!("failed to persist order {}: {}", order_id, err);
+        dex_core::redacted_eprintln!("failed to persist order {}: {}", order_id, err);
         return Ok(warp::reply::with_status(
             warp::reply::json(&ErrorResponse {
                 code: "storage_error",
@@ -382,7 +539,7 @@ async fn handle_create_order(
         let trade_id = state.trade_id_counter.fetch_add(1, Ordering::Relaxed);
         trade.id = trade_id;
         if let Err(err) = state.database.save_trade(trade).await {
-            eprintln!("failed to persist trade {}: {}", trade_id, err);
+            dex_core::redacted_eprintln!("failed to persist trade {}: {}", trade_id, err);
             return Ok(warp::reply::with_status(
                 warp::reply::json(&ErrorResponse {
                     code: "storage_error",
@@ -466,7 +623,11 @@ async fn handle_get_trades_for_order(
             ))
         }
         Err(err) => {
-            eprintln!("failed to load trades for order {}: {}", order_id, err);
+            dex_core::redacted_eprintln!(
+                "failed to load trades for order {}: {}",
+                order_id,
+                err
+            );
             Ok(error_reply(
                 "storage_error",
                 "failed to load trades",
@@ -479,16 +640,9 @@ async fn handle_get_trades_for_order(
 /// Handler for getting trades for a trader
 async fn handle_get_trades_for_trader(
     trader_id: String,
-    claims: Claims,
+    _claims: Claims,
     state: ApiState,
 ) -> Result<impl warp::Reply, warp::Rejection> {
-    if claims.sub != trader_id {
-        return Ok(error_reply(
-            "forbidden",
-            "requested trader does not match authenticated subject",
-            StatusCode::FORBIDDEN,
-        ));
-    }
     match state.database.get_trades_for_trader(&trader_id).await {
         Ok(trades) => {
             let response = GetTradesResponse {
@@ -502,7 +656,11 @@ async fn handle_get_trades_for_trader(
             ))
         }
         Err(err) => {
-            eprintln!("failed to load trades for trader {}: {}", trader_id, err);
+            dex_core::redacted_eprintln!(
+                "failed to load trades for trader {}: {}",
+                trader_id,
+                err
+            );
             Ok(error_reply(
                 "storage_error",
                 "failed to load trades",
@@ -558,13 +716,18 @@ async fn handle_shared_token(
         state.config.jwt_max_ttl_seconds,
     );
 
-    let issued = match state
-        .auth
-        .issue_token(trader_id, ttl, req.audience.clone())
-    {
+    let now = current_unix_timestamp().map_err(|_| warp::reject::custom(InternalError))?;
+    let roles = state.access.granted_roles(&trader_id, now).await;
+    let issued = match state.auth.issue_scoped_token(
+        trader_id,
+        ttl,
+        req.audience.clone(),
+        roles,
+        req.scope.clone(),
+    ) {
         Ok(token) => token,
         Err(err) => {
-            eprintln!("failed to issue shared token: {}", err);
+            dex_core::redacted_eprintln!("failed to issue shared token: {}", err);
             return Ok(error_reply(
                 "internal_error",
                 "failed to issue token",
@@ -656,13 +819,18 @@ async fn handle_wallet_token(
         state.config.jwt_max_ttl_seconds,
     );
 
-    let issued = match state
-        .auth
-        .issue_token(address.clone(), ttl, req.audience.clone())
-    {
+    let now = current_unix_timestamp().map_err(|_| warp::reject::custom(InternalError))?;
+    let roles = state.access.granted_roles(&address, now).await;
+    let issued = match state.auth.issue_scoped_token(
+        address.clone(),
+        ttl,
+        req.audience.clone(),
+        roles,
+        req.scope.clone(),
+    ) {
         Ok(token) => token,
         Err(err) => {
-            eprintln!("failed to issue wallet token: {}", err);
+            dex_core::redacted_eprintln!("failed to issue wallet token: {}", err);
             return Ok(error_reply(
                 "internal_error",
                 "failed to issue token",
@@ -681,6 +849,156 @@ async fn handle_wallet_token(
     ))
 }
 
+async fn handle_resolve_did(
+    did: String,
+    state: ApiState,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match state.kyc.resolve(&did).await {
+        Ok(document) => Ok(warp::reply::with_status(
+            warp::reply::json(&document),
+            StatusCode::OK,
+        )),
+        Err(IdentityError::InvalidDid(_)) => Ok(error_reply(
+            "invalid_did",
+            "expected a did:dexos identifier",
+            StatusCode::BAD_REQUEST,
+        )),
+        Err(IdentityError::DIDNotFound) => Ok(error_reply(
+            "not_found",
+            "DID not found",
+            StatusCode::NOT_FOUND,
+        )),
+        Err(err) => Ok(error_reply(
+            "invalid_did",
+            err.to_string(),
+            StatusCode::UNPROCESSABLE_ENTITY,
+        )),
+    }
+}
+
+async fn handle_import_status_list(
+    state: ApiState,
+    credential: VerifiableCredential,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match state.kyc.import_status_list(&credential).await {
+        Ok(()) => Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({ "imported": true })),
+            StatusCode::OK,
+        )),
+        Err(err) => Ok(error_reply(
+            "invalid_status_list",
+            err.to_string(),
+            StatusCode::BAD_REQUEST,
+        )),
+    }
+}
+
+async fn handle_present_credential(
+    claims: Claims,
+    state: ApiState,
+    credential: VerifiableCredential,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match state.kyc.present(&claims.sub, credential).await {
+        Ok(()) => Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({ "verified": true })),
+            StatusCode::OK,
+        )),
+        Err(err) => Ok(error_reply(
+            "invalid_credential",
+            err.to_string(),
+            StatusCode::FORBIDDEN,
+        )),
+    }
+}
+
+async fn handle_list_grants(
+    subject: String,
+    _claims: Claims,
+    state: ApiState,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let grants = state.access.grants(&subject).await;
+    Ok(warp::reply::with_status(
+        warp::reply::json(&GrantsResponse { subject, grants }),
+        StatusCode::OK,
+    ))
+}
+
+async fn handle_grant_role(
+    claims: Claims,
+    state: ApiState,
+    req: GrantRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let now = current_unix_timestamp().map_err(|_| warp::reject::custom(InternalError))?;
+    if req.expires_at.is_some_and(|expiry| expiry <= now) {
+        return Ok(error_reply(
+            "invalid_grant",
+            "expires_at must be in the future",
+            StatusCode::BAD_REQUEST,
+        ));
+    }
+    match state
+        .access
+        .grant(&req.subject, &req.role, &claims.sub, now, req.expires_at)
+        .await
+    {
+        Ok(grant) => Ok(warp::reply::with_status(
+            warp::reply::json(&grant),
+            StatusCode::CREATED,
+        )),
+        Err(AccessError::Storage(_)) => Err(warp::reject::custom(InternalError)),
+        Err(err) => Ok(error_reply(
+            "invalid_grant",
+            err.to_string(),
+            StatusCode::BAD_REQUEST,
+        )),
+    }
+}
+
+async fn handle_revoke_role(
+    subject: String,
+    role: String,
+    claims: Claims,
+    state: ApiState,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match state.access.revoke(&subject, &role, &claims.sub).await {
+        Ok(grant) => Ok(warp::reply::with_status(
+            warp::reply::json(&grant),
+            StatusCode::OK,
+        )),
+        Err(AccessError::Storage(_)) => Err(warp::reject::custom(InternalError)),
+        Err(err) => Ok(error_reply(
+            "grant_not_found",
+            err.to_string(),
+            StatusCode::NOT_FOUND,
+        )),
+    }
+}
+
+async fn handle_classify_resource(
+    _claims: Claims,
+    state: ApiState,
+    req: ClassificationRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let resource = req.resource.trim_matches('/');
+    if resource.is_empty() {
+        return Ok(error_reply(
+            "invalid_classification",
+            "resource must name a request path",
+            StatusCode::BAD_REQUEST,
+        ));
+    }
+    let now = current_unix_timestamp().map_err(|_| warp::reject::custom(InternalError))?;
+    state
+        .access
+        .classify(resource, req.level, req.owner, req.acl, now)
+        .await
+        .map_err(|_| warp::reject::custom(InternalError))?;
+    Ok(warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({ "classified": resource })),
+        StatusCode::OK,
+    ))
+}
+
 async fn depth_ws_session(socket: WebSocket, state: ApiState, levels: usize) {
     let (mut sender, mut receiver) = socket.split();
     let mut subscriber = state.market_tx.subscribe();
@@ -810,6 +1128,14 @@ async fn handle_rejection(err: warp::Rejection) -> Result<impl warp::Reply, Infa
         ));
     }
 
+    if let Some(access) = err.find::<AccessRejection>() {
+        return Ok(error_reply(
+            "forbidden",
+            access.0.to_string(),
+            StatusCode::FORBIDDEN,
+        ));
+    }
+
     if let Some(_missing) = err.find::<MissingHeader>() {
         return Ok(error_reply(
             "unauthorized",
@@ -858,7 +1184,7 @@ async fn handle_rejection(err: warp::Rejection) -> Result<impl warp::Reply, Infa
         ));
     }
 
-    eprintln!("unhandled rejection: {:?}", err);
+    dex_core::redacted_eprintln!("unhandled rejection: {:?}", err);
     Ok(error_reply(
         "internal_error",
         "internal server error",
@@ -1107,14 +1433,14 @@ mod validation {
     mod auth_filter_tests {
         use crate::{
             auth::AuthManager, authenticated, challenge::ChallengeStore, handle_rejection,
-            ApiState, Claims, Config,
+            AccessControl, ApiState, Claims, Config, KycGate,
         };
-        use dex_core::orderbook::OrderBook;
+        use dex_core::{governance::IAM, orderbook::OrderBook};
         use dex_db::DatabaseManager;
         use jsonwebtoken::{encode, EncodingKey, Header};
         use secrecy::{ExposeSecret, SecretString};
         use std::{
-            collections::HashMap,
+            collections::{HashMap, HashSet},
             sync::{atomic::AtomicU64, Arc},
             time::{SystemTime, UNIX_EPOCH},
         };
@@ -1151,7 +1477,8 @@ mod validation {
         #[tokio::test]
         async fn expired_token_returns_401() {
             let secret = SecretString::from(TEST_SECRET.to_string());
-            let token = build_token(&secret, -60);
+            // Past the default 60 second validation leeway
+            let token = build_token(&secret, -3600);
             let state = test_state();
             let filter = protected_filter(state);
 
@@ -1180,7 +1507,7 @@ mod validation {
 
         fn protected_filter(
             state: ApiState,
-        ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        ) -> impl Filter<Extract = impl warp::Reply, Error = std::convert::Infallible> + Clone {
             authenticated(state)
                 .and_then(|claims: Claims, _state: ApiState| async move {
                     let reply =
@@ -1204,6 +1531,14 @@ mod validation {
                 wallet_challenge_ttl_seconds: 300,
                 trader_secrets,
                 server_port: 3030,
+                kyc_required_markets: HashSet::new(),
+                kyc_issuers_file: None,
+                jwt_keystore_path: None,
+                jwt_keystore_password: None,
+                jwt_key_rotation_seconds: 86_400,
+                jwt_key_grace_seconds: 3600,
+                pii_token_secret: None,
+                admin_subjects: HashSet::new(),
             };
             let (market_tx, _) = broadcast::channel(16);
 
@@ -1218,6 +1553,8 @@ mod validation {
                 config,
                 wallet_challenges: Arc::new(ChallengeStore::new(300)),
                 market_tx,
+                kyc: Arc::new(KycGate::new(Vec::new(), HashSet::new()).expect("kyc gate")),
+                access: Arc::new(AccessControl::new(IAM::with_default_policy())),
             }
         }
 
@@ -1232,6 +1569,8 @@ mod validation {
                 aud: None,
                 iss: None,
                 iat: None,
+                roles: Vec::new(),
+                scope: None,
             };
             encode(
                 &Header::default(),
@@ -1,33 +1,67 @@
 //! Main entry point for the DEX-OS API server
 
-use dex_api::{auth::AuthManager, challenge::ChallengeStore, routes, ApiState, Config};
-use dex_core::orderbook::OrderBook;
+use dex_api::{
+    auth::{AuthManager, JWT_KEY_OWNER},
+    challenge::ChallengeStore,
+    routes, AccessControl, ApiState, Config, KycGate,
+};
+use dex_core::{
+    governance::IAM,
+    identity::DID,
+    orderbook::OrderBook,
+    security::{redaction, KeyEncryptionKey, KeyRotationManager, Redactor},
+};
 use dex_db::DatabaseManager;
-use secrecy::ExposeSecret;
-use std::sync::{atomic::AtomicU64, Arc};
+use secrecy::{ExposeSecret, SecretString};
+use std::{
+    path::{Path, PathBuf},
+    sync::{atomic::AtomicU64, Arc},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 use tokio::sync::{broadcast, RwLock};
 
 #[tokio::main]
 async fn main() {
     if let Err(err) = bootstrap().await {
-        eprintln!("Failed to start DEX-OS API server: {}", err);
+        dex_core::redacted_eprintln!("Failed to start DEX-OS API server: {}", err);
         std::process::exit(1);
     }
 }
 
 async fn bootstrap() -> Result<(), Box<dyn std::error::Error>> {
     let config = Config::from_env()?;
+    if let Some(secret) = &config.pii_token_secret {
+        redaction::set_global(Redactor::new().with_token_secret(secret.expose_secret().as_bytes()));
+    }
 
     let database = Arc::new(DatabaseManager::connect(config.database_url.expose_secret()).await?);
     database.initialize().await?;
 
-    let auth = Arc::new(AuthManager::new(
-        &config.jwt_secret,
-        config.jwt_issuer.clone(),
-    ));
+    let auth = Arc::new(
+        AuthManager::new(&config.jwt_secret, config.jwt_issuer.clone())
+            .with_grace_period(Duration::from_secs(config.jwt_key_grace_seconds)),
+    );
+    if let (Some(path), Some(password)) = (&config.jwt_keystore_path, &config.jwt_keystore_password)
+    {
+        start_jwt_key_rotation(&auth, PathBuf::from(path), password.clone(), &config)?;
+    }
     let wallet_challenges = Arc::new(ChallengeStore::new(config.wallet_challenge_ttl_seconds));
     let (market_tx, _) = broadcast::channel(64);
 
+    let kyc_issuers: Vec<DID> = match &config.kyc_issuers_file {
+        Some(path) => serde_json::from_str(&std::fs::read_to_string(path)?)?,
+        None => Vec::new(),
+    };
+    let kyc = Arc::new(KycGate::new(
+        kyc_issuers,
+        config.kyc_required_markets.clone(),
+    )?);
+    let access =
+        Arc::new(AccessControl::new(IAM::with_default_policy()).with_store(database.clone()));
+    access.restore().await?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    access.bootstrap_admins(&config.admin_subjects, now).await?;
+
     let state = ApiState {
         orderbook: Arc::new(RwLock::new(OrderBook::new())),
         order_id_counter: Arc::new(AtomicU64::new(1)),
@@ -37,6 +71,8 @@ async fn bootstrap() -> Result<(), Box<dyn std::error::Error>> {
         config: config.clone(),
         wallet_challenges,
         market_tx,
+        kyc,
+        access,
     };
 
     let routes = routes(state);
@@ -48,3 +84,59 @@ async fn bootstrap() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+/// Load the JWT signing keys from the encrypted keystore, rotate them on
+/// schedule and persist every rotation. The auth manager follows the rotations.
+fn start_jwt_key_rotation(
+    auth: &AuthManager,
+    path: PathBuf,
+    password: SecretString,
+    config: &Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut keys = KeyRotationManager::new(config.jwt_key_rotation_seconds);
+    let kek = KeyEncryptionKey::Password(password.expose_secret().as_bytes());
+    if path.exists() {
+        keys.load_keystore(&path, &kek)?;
+    }
+
+    // Tokens signed before a restart stay valid for the rest of their grace period
+    if let Some(current) = keys.get_current_key(JWT_KEY_OWNER) {
+        let history = keys.get_rotation_history(JWT_KEY_OWNER);
+        let retired = history.map(|h| h.as_slice()).unwrap_or_default();
+        for (i, key) in retired.iter().enumerate() {
+            let retired_at = retired.get(i + 1).unwrap_or(current).created_at;
+            auth.accept_retired_key(key, retired_at)?;
+        }
+        auth.install_signing_key(current)?;
+    }
+
+    auth.follow_rotations(keys.subscribe(JWT_KEY_OWNER));
+    rotate_if_needed(&mut keys, &path, &password)?;
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            if let Err(err) = rotate_if_needed(&mut keys, &path, &password) {
+                dex_core::redacted_eprintln!("failed to rotate JWT signing key: {}", err);
+            }
+        }
+    });
+    Ok(())
+}
+
+fn rotate_if_needed(
+    keys: &mut KeyRotationManager,
+    path: &Path,
+    password: &SecretString,
+) -> Result<(), dex_core::security::SecurityError> {
+    if keys.get_current_key(JWT_KEY_OWNER).is_some() && !keys.is_rotation_needed(JWT_KEY_OWNER) {
+        return Ok(());
+    }
+    // The keystore is written before the auth manager signs with the new key
+    keys.rotate_keys_and_save(
+        JWT_KEY_OWNER,
+        path,
+        &KeyEncryptionKey::Password(password.expose_secret().as_bytes()),
+    )?;
+    Ok(())
+}
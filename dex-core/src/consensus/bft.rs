@@ -0,0 +1,1273 @@
+//! Byzantine fault tolerant block agreement for the quantum consensus engine
+//!
+//! [`BftNode`] runs the Tendermint round structure: in every round a
+//! stake-weighted proposer broadcasts a block, validators prevote for it (or
+//! for nil), and once prevotes carrying more than two thirds of the stake agree
+//! on the block ("a polka") they lock on it and precommit. Precommits from more
+//! than two thirds of the stake form a [`CommitCertificate`] and decide the
+//! height. Locking keeps decisions safe while at most a third of the stake is
+//! Byzantine; round timeouts that grow with the round number restore liveness
+//! once the network stabilises.
+//!
+//! The node does no I/O: messages and expired timeouts are fed in, and it
+//! answers with [`BftOutput`]s to broadcast messages, schedule timeouts and
+//! report decided blocks. Only the first proposal of a round and the first
+//! vote of a validator per step are kept, conflicting ones become evidence,
+//! and messages too many heights or rounds ahead are dropped, so a Byzantine
+//! validator cannot grow the node's state without bound. [`super::bft_sim`]
+//! drives a set of nodes through a simulated network.
+//!
+//! This implements the Priority 1 feature from DEX-OS-V1.csv:
+//! "Core Components,Quantum Consensus (QBFT),Consensus,Lattice BFT Core,BFT Core,High"
+
+use crate::quantum_consensus::is_valid_transaction;
+use crate::types::{Block, Transaction, Validator};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// Domain separation for signed proposals
+const PROPOSAL_DOMAIN: &[u8] = b"dexos-bft-proposal-v1";
+/// Domain separation for signed votes
+const VOTE_DOMAIN: &[u8] = b"dexos-bft-vote-v1";
+
+/// Hash identifying a block
+pub type BlockHash = [u8; 32];
+
+/// Hash of everything in `block` except its own hash and signature
+pub fn block_hash(block: &Block) -> BlockHash {
+    let content = (
+        block.id,
+        block.height,
+        block.timestamp,
+        &block.transactions,
+        &block.previous_hash,
+    );
+    let bytes = serde_json::to_vec(&content).expect("blocks always serialize");
+    Sha256::digest(bytes).into()
+}
+
+/// Validators with their keys and stake
+#[derive(Debug, Clone)]
+pub struct ValidatorSet {
+    /// Verifying key and stake by validator ID
+    validators: BTreeMap<String, (VerifyingKey, u64)>,
+    /// Sum of all stake
+    total_stake: u64,
+}
+
+impl ValidatorSet {
+    /// Build a set from validators whose public keys are ed25519 keys.
+    /// Validators without stake are left out.
+    pub fn new(validators: &[Validator]) -> Result<Self, BftError> {
+        let mut set = BTreeMap::new();
+        let mut total_stake: u64 = 0;
+        for validator in validators.iter().filter(|v| v.stake > 0) {
+            let key: [u8; 32] = validator
+                .public_key
+                .as_slice()
+                .try_into()
+                .map_err(|_| BftError::InvalidValidatorKey(validator.id.clone()))?;
+            let key = VerifyingKey::from_bytes(&key)
+                .map_err(|_| BftError::InvalidValidatorKey(validator.id.clone()))?;
+            total_stake = total_stake
+                .checked_add(validator.stake)
+                .ok_or_else(|| BftError::InvalidValidatorKey(validator.id.clone()))?;
+            set.insert(validator.id.clone(), (key, validator.stake));
+        }
+        if set.is_empty() {
+            return Err(BftError::EmptyValidatorSet);
+        }
+        Ok(Self {
+            validators: set,
+            total_stake,
+        })
+    }
+
+    /// Sum of all stake
+    pub fn total_stake(&self) -> u64 {
+        self.total_stake
+    }
+
+    /// Stake of `validator`, zero for unknown validators
+    pub fn stake_of(&self, validator: &str) -> u64 {
+        self.validators
+            .get(validator)
+            .map_or(0, |(_, stake)| *stake)
+    }
+
+    /// Whether `validator` is in the set
+    pub fn contains(&self, validator: &str) -> bool {
+        self.validators.contains_key(validator)
+    }
+
+    /// Whether `stake` is more than two thirds of the total
+    pub fn is_quorum(&self, stake: u64) -> bool {
+        stake as u128 * 3 > self.total_stake as u128 * 2
+    }
+
+    /// Whether `stake` is more than a third of the total, so that at least one
+    /// honest validator is among its holders
+    pub fn exceeds_one_third(&self, stake: u64) -> bool {
+        stake as u128 * 3 > self.total_stake as u128
+    }
+
+    /// Proposer of `round` at `height`, drawn with probability proportional
+    /// to stake from a hash of the height and round
+    pub fn proposer(&self, height: u64, round: u64) -> &str {
+        let mut hasher = Sha256::new();
+        hasher.update(height.to_be_bytes());
+        hasher.update(round.to_be_bytes());
+        let digest = hasher.finalize();
+        let mut draw_bytes = [0u8; 8];
+        draw_bytes.copy_from_slice(&digest[..8]);
+        let mut draw = u64::from_be_bytes(draw_bytes) % self.total_stake;
+        for (id, (_, stake)) in &self.validators {
+            if draw < *stake {
+                return id;
+            }
+            draw -= stake;
+        }
+        unreachable!("the draw is below the total stake")
+    }
+
+    fn verify(&self, validator: &str, message: &[u8], signature: &[u8]) -> Result<(), BftError> {
+        let (key, _) = self
+            .validators
+            .get(validator)
+            .ok_or_else(|| BftError::UnknownValidator(validator.to_string()))?;
+        let signature: [u8; 64] = signature
+            .try_into()
+            .map_err(|_| BftError::InvalidSignature(validator.to_string()))?;
+        key.verify(message, &Signature::from_bytes(&signature))
+            .map_err(|_| BftError::InvalidSignature(validator.to_string()))
+    }
+}
+
+/// Kind of vote
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum VoteType {
+    Prevote,
+    Precommit,
+}
+
+/// A signed prevote or precommit; `block_hash` is `None` for a nil vote
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Vote {
+    /// Prevote or precommit
+    pub vote_type: VoteType,
+    /// Height voted on
+    pub height: u64,
+    /// Round voted in
+    pub round: u64,
+    /// Block voted for, or `None` for nil
+    pub block_hash: Option<BlockHash>,
+    /// Voting validator
+    pub validator: String,
+    /// ed25519 signature over the fields above
+    pub signature: Vec<u8>,
+}
+
+impl Vote {
+    /// Create a vote signed with `key`
+    pub fn signed(
+        vote_type: VoteType,
+        height: u64,
+        round: u64,
+        block_hash: Option<BlockHash>,
+        validator: &str,
+        key: &SigningKey,
+    ) -> Self {
+        let mut vote = Self {
+            vote_type,
+            height,
+            round,
+            block_hash,
+            validator: validator.to_string(),
+            signature: Vec::new(),
+        };
+        vote.signature = key.sign(&vote.signing_bytes()).to_bytes().to_vec();
+        vote
+    }
+
+    fn signing_bytes(&self) -> Vec<u8> {
+        let mut bytes = VOTE_DOMAIN.to_vec();
+        bytes.push(match self.vote_type {
+            VoteType::Prevote => 1,
+            VoteType::Precommit => 2,
+        });
+        bytes.extend_from_slice(&self.height.to_be_bytes());
+        bytes.extend_from_slice(&self.round.to_be_bytes());
+        match &self.block_hash {
+            Some(hash) => {
+                bytes.push(1);
+                bytes.extend_from_slice(hash);
+            }
+            None => bytes.push(0),
+        }
+        bytes.extend_from_slice(self.validator.as_bytes());
+        bytes
+    }
+
+    /// Check the signature against the validator's key
+    pub fn verify(&self, validators: &ValidatorSet) -> Result<(), BftError> {
+        validators.verify(&self.validator, &self.signing_bytes(), &self.signature)
+    }
+}
+
+/// A signed block proposal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Proposal {
+    /// Height proposed for
+    pub height: u64,
+    /// Round proposed in
+    pub round: u64,
+    /// Proposed block
+    pub block: Block,
+    /// Round in which the proposer saw a polka for this block, if it is
+    /// re-proposing a block from an earlier round
+    pub valid_round: Option<u64>,
+    /// Proposing validator
+    pub proposer: String,
+    /// ed25519 signature over the fields above
+    pub signature: Vec<u8>,
+}
+
+impl Proposal {
+    /// Create a proposal signed with `key`
+    pub fn signed(
+        height: u64,
+        round: u64,
+        block: Block,
+        valid_round: Option<u64>,
+        proposer: &str,
+        key: &SigningKey,
+    ) -> Self {
+        let mut proposal = Self {
+            height,
+            round,
+            block,
+            valid_round,
+            proposer: proposer.to_string(),
+            signature: Vec::new(),
+        };
+        proposal.signature = key.sign(&proposal.signing_bytes()).to_bytes().to_vec();
+        proposal
+    }
+
+    fn signing_bytes(&self) -> Vec<u8> {
+        let mut bytes = PROPOSAL_DOMAIN.to_vec();
+        bytes.extend_from_slice(&self.height.to_be_bytes());
+        bytes.extend_from_slice(&self.round.to_be_bytes());
+        bytes.extend_from_slice(&self.valid_round.map_or(0, |r| r + 1).to_be_bytes());
+        bytes.extend_from_slice(&block_hash(&self.block));
+        bytes.extend_from_slice(self.proposer.as_bytes());
+        bytes
+    }
+
+    /// Check that the proposal comes from the round's proposer
+    pub fn verify(&self, validators: &ValidatorSet) -> Result<(), BftError> {
+        if validators.proposer(self.height, self.round) != self.proposer {
+            return Err(BftError::WrongProposer(self.proposer.clone()));
+        }
+        validators.verify(&self.proposer, &self.signing_bytes(), &self.signature)
+    }
+}
+
+/// Precommits from more than two thirds of the stake for one block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitCertificate {
+    /// Decided height
+    pub height: u64,
+    /// Round the precommits were cast in
+    pub round: u64,
+    /// Decided block
+    pub block_hash: BlockHash,
+    /// The precommits
+    pub precommits: Vec<Vote>,
+}
+
+impl CommitCertificate {
+    /// Check every precommit and that together they carry a quorum of stake
+    pub fn verify(&self, validators: &ValidatorSet) -> Result<(), BftError> {
+        let mut signers = HashSet::new();
+        let mut stake = 0u64;
+        for vote in &self.precommits {
+            if vote.vote_type != VoteType::Precommit
+                || vote.height != self.height
+                || vote.round != self.round
+                || vote.block_hash != Some(self.block_hash)
+            {
+                return Err(BftError::InvalidCertificate(format!(
+                    "vote from {} does not match the certificate",
+                    vote.validator
+                )));
+            }
+            vote.verify(validators)?;
+            if signers.insert(vote.validator.as_str()) {
+                stake += validators.stake_of(&vote.validator);
+            }
+        }
+        if !validators.is_quorum(stake) {
+            return Err(BftError::InvalidCertificate(format!(
+                "precommits carry {} of {} stake",
+                stake,
+                validators.total_stake()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// A decided block with the certificate proving it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommittedBlock {
+    /// The block
+    pub block: Block,
+    /// Proof that a quorum precommitted it
+    pub certificate: CommitCertificate,
+}
+
+impl CommittedBlock {
+    /// Check the certificate and that it is for this block
+    pub fn verify(&self, validators: &ValidatorSet) -> Result<(), BftError> {
+        if block_hash(&self.block) != self.certificate.block_hash
+            || self.block.height != self.certificate.height
+        {
+            return Err(BftError::InvalidCertificate(
+                "certificate is for another block".to_string(),
+            ));
+        }
+        self.certificate.verify(validators)
+    }
+}
+
+/// Messages exchanged between validators
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum BftMessage {
+    Proposal(Proposal),
+    Vote(Vote),
+}
+
+impl BftMessage {
+    /// Height the message belongs to
+    pub fn height(&self) -> u64 {
+        match self {
+            BftMessage::Proposal(proposal) => proposal.height,
+            BftMessage::Vote(vote) => vote.height,
+        }
+    }
+
+    /// Round the message belongs to
+    pub fn round(&self) -> u64 {
+        match self {
+            BftMessage::Proposal(proposal) => proposal.round,
+            BftMessage::Vote(vote) => vote.round,
+        }
+    }
+
+    /// Height, round, vote type (none for proposals) and signer; an honest
+    /// validator signs at most one message per slot
+    fn slot(&self) -> (u64, u64, Option<VoteType>, &str) {
+        match self {
+            BftMessage::Proposal(proposal) => {
+                (proposal.height, proposal.round, None, &proposal.proposer)
+            }
+            BftMessage::Vote(vote) => (
+                vote.height,
+                vote.round,
+                Some(vote.vote_type),
+                &vote.validator,
+            ),
+        }
+    }
+}
+
+/// Step within a round
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Step {
+    Propose,
+    Prevote,
+    Precommit,
+}
+
+/// A timeout the node asked to be woken up for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Timeout {
+    /// Height the timeout was scheduled at
+    pub height: u64,
+    /// Round the timeout was scheduled in
+    pub round: u64,
+    /// Step the timeout guards
+    pub step: Step,
+}
+
+/// What the node asks its driver to do
+#[derive(Debug, Clone)]
+pub enum BftOutput {
+    /// Send a message to every other validator
+    Broadcast(BftMessage),
+    /// Call [`BftNode::handle_timeout`] with `timeout` after `after_ms`
+    ScheduleTimeout { timeout: Timeout, after_ms: u64 },
+    /// A height was decided
+    Commit(CommittedBlock),
+}
+
+/// Proof that a validator signed two different votes for the same step
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Equivocation {
+    /// Vote received first
+    pub first: Vote,
+    /// Conflicting vote received later
+    pub second: Vote,
+}
+
+/// Proof that a proposer signed two different blocks for the same round
+#[derive(Debug, Clone)]
+pub struct ProposalEquivocation {
+    /// Proposal received first
+    pub first: Proposal,
+    /// Conflicting proposal received later
+    pub second: Proposal,
+}
+
+/// Round timeouts; each grows by `timeout_delta_ms` per round so that rounds
+/// eventually last long enough for a partially synchronous network. Messages
+/// are only kept up to `future_heights` heights and `future_rounds` rounds
+/// ahead of the node.
+#[derive(Debug, Clone)]
+pub struct BftConfig {
+    /// Time to wait for the proposal
+    pub timeout_propose_ms: u64,
+    /// Time to wait for more prevotes after seeing a quorum of mixed prevotes
+    pub timeout_prevote_ms: u64,
+    /// Time to wait for more precommits after seeing a quorum of mixed ones
+    pub timeout_precommit_ms: u64,
+    /// Increase of every timeout per round
+    pub timeout_delta_ms: u64,
+    /// Most transactions put into one block
+    pub max_block_transactions: usize,
+    /// How many heights ahead of ours messages are buffered
+    pub future_heights: u64,
+    /// How many rounds ahead of ours messages are kept
+    pub future_rounds: u64,
+}
+
+impl Default for BftConfig {
+    fn default() -> Self {
+        Self {
+            timeout_propose_ms: 300,
+            timeout_prevote_ms: 100,
+            timeout_precommit_ms: 100,
+            timeout_delta_ms: 50,
+            max_block_transactions: 1000,
+            future_heights: 2,
+            future_rounds: 16,
+        }
+    }
+}
+
+/// One validator's view of the consensus
+pub struct BftNode {
+    /// Our validator ID
+    id: String,
+    /// Our signing key
+    key: SigningKey,
+    /// Validators of every height
+    validators: ValidatorSet,
+    /// Timeouts
+    config: BftConfig,
+    /// Height being decided
+    height: u64,
+    /// Current round
+    round: u64,
+    /// Current step
+    step: Step,
+    /// Hash of the last decided block
+    last_hash: Vec<u8>,
+    /// Round and block we are locked on
+    locked: Option<(u64, Block)>,
+    /// Most recent round and block we saw a polka for
+    valid: Option<(u64, Block)>,
+    /// First proposal of the current height by round
+    proposals: HashMap<u64, Proposal>,
+    /// Votes of the current height by round and type, then by validator
+    votes: HashMap<(u64, VoteType), HashMap<String, Vote>>,
+    /// Rounds whose polka for the proposed block has been acted upon
+    polka_seen: HashSet<u64>,
+    /// Timeouts already scheduled this height, so each is scheduled once
+    scheduled: HashSet<(u64, Step)>,
+    /// Messages for later heights, replayed once we get there
+    future: Vec<BftMessage>,
+    /// Transactions waiting to be proposed
+    mempool: Vec<Transaction>,
+    /// Decided blocks
+    decided: Vec<CommittedBlock>,
+    /// Conflicting votes seen
+    evidence: Vec<Equivocation>,
+    /// Conflicting proposals seen
+    proposal_evidence: Vec<ProposalEquivocation>,
+}
+
+impl BftNode {
+    /// Create a validator node at height 1
+    pub fn new(
+        id: &str,
+        key: SigningKey,
+        validators: ValidatorSet,
+        config: BftConfig,
+    ) -> Result<Self, BftError> {
+        if !validators.contains(id) {
+            return Err(BftError::UnknownValidator(id.to_string()));
+        }
+        Ok(Self {
+            id: id.to_string(),
+            key,
+            validators,
+            config,
+            height: 1,
+            round: 0,
+            step: Step::Propose,
+            last_hash: vec![0; 32],
+            locked: None,
+            valid: None,
+            proposals: HashMap::new(),
+            votes: HashMap::new(),
+            polka_seen: HashSet::new(),
+            scheduled: HashSet::new(),
+            future: Vec::new(),
+            mempool: Vec::new(),
+            decided: Vec::new(),
+            evidence: Vec::new(),
+            proposal_evidence: Vec::new(),
+        })
+    }
+
+    /// Our validator ID
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Height being decided
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    /// Current round
+    pub fn round(&self) -> u64 {
+        self.round
+    }
+
+    /// Current step
+    pub fn step(&self) -> Step {
+        self.step
+    }
+
+    /// Round and hash of the block we are locked on
+    pub fn locked(&self) -> Option<(u64, BlockHash)> {
+        self.locked
+            .as_ref()
+            .map(|(round, block)| (*round, block_hash(block)))
+    }
+
+    /// Blocks decided so far
+    pub fn decided(&self) -> &[CommittedBlock] {
+        &self.decided
+    }
+
+    /// Equivocations observed
+    pub fn evidence(&self) -> &[Equivocation] {
+        &self.evidence
+    }
+
+    /// Proposal equivocations observed
+    pub fn proposal_evidence(&self) -> &[ProposalEquivocation] {
+        &self.proposal_evidence
+    }
+
+    /// Queue a transaction for our next proposal
+    pub fn submit_transaction(&mut self, transaction: Transaction) {
+        self.mempool.push(transaction);
+    }
+
+    /// Enter round 0 of the current height
+    pub fn start(&mut self) -> Vec<BftOutput> {
+        let mut out = Vec::new();
+        self.start_round(0, &mut out);
+        self.evaluate(&mut out);
+        out
+    }
+
+    /// Process a message from another validator. Invalid messages are
+    /// rejected; the error is informational since the node stays consistent.
+    /// Messages beyond the configured height and round windows are dropped.
+    pub fn handle_message(&mut self, message: BftMessage) -> Result<Vec<BftOutput>, BftError> {
+        let mut out = Vec::new();
+        let height = message.height();
+        // Later heights start again from round 0
+        let base_round = if height == self.height { self.round } else { 0 };
+        if height < self.height
+            || height > self.height.saturating_add(self.config.future_heights)
+            || message.round() > base_round.saturating_add(self.config.future_rounds)
+        {
+            return Ok(out);
+        }
+        match &message {
+            BftMessage::Proposal(proposal) => proposal.verify(&self.validators)?,
+            BftMessage::Vote(vote) => vote.verify(&self.validators)?,
+        }
+        if height > self.height {
+            // Keep the first message per slot; later ones are replayed and
+            // checked for equivocation once we reach the height
+            let slot = message.slot();
+            if !self.future.iter().any(|known| known.slot() == slot) {
+                self.future.push(message);
+            }
+            return Ok(out);
+        }
+        self.accept(message, &mut out);
+        self.evaluate(&mut out);
+        Ok(out)
+    }
+
+    /// Process an expired timeout
+    pub fn handle_timeout(&mut self, timeout: Timeout) -> Vec<BftOutput> {
+        let mut out = Vec::new();
+        if timeout.height != self.height || timeout.round != self.round {
+            return out;
+        }
+        match timeout.step {
+            Step::Propose if self.step == Step::Propose => {
+                self.cast(VoteType::Prevote, None, &mut out);
+                self.step = Step::Prevote;
+            }
+            Step::Prevote if self.step == Step::Prevote => {
+                self.cast(VoteType::Precommit, None, &mut out);
+                self.step = Step::Precommit;
+            }
+            Step::Precommit => self.start_round(self.round + 1, &mut out),
+            _ => {}
+        }
+        self.evaluate(&mut out);
+        out
+    }
+
+    /// Store a verified message of the current height. The first proposal of
+    /// a round is relayed, so that every honest validator learns the block a
+    /// quorum may vote for even if its proposer did not send it to all.
+    fn accept(&mut self, message: BftMessage, out: &mut Vec<BftOutput>) {
+        if let BftMessage::Proposal(proposal) = &message {
+            if !self.proposals.contains_key(&proposal.round) {
+                out.push(BftOutput::Broadcast(message.clone()));
+            }
+        }
+        self.record(message);
+    }
+
+    /// Keep the first proposal per round and the first vote per validator
+    /// and step; one conflicting message per slot is kept as evidence
+    fn record(&mut self, message: BftMessage) {
+        match message {
+            BftMessage::Proposal(proposal) => match self.proposals.get(&proposal.round) {
+                None => {
+                    self.proposals.insert(proposal.round, proposal);
+                }
+                Some(first)
+                    if block_hash(&first.block) != block_hash(&proposal.block)
+                        || first.valid_round != proposal.valid_round =>
+                {
+                    let known = self
+                        .proposal_evidence
+                        .iter()
+                        .any(|e| e.first.height == first.height && e.first.round == first.round);
+                    if !known {
+                        self.proposal_evidence.push(ProposalEquivocation {
+                            first: first.clone(),
+                            second: proposal,
+                        });
+                    }
+                }
+                Some(_) => {}
+            },
+            BftMessage::Vote(vote) => {
+                let votes = self.votes.entry((vote.round, vote.vote_type)).or_default();
+                match votes.get(&vote.validator) {
+                    None => {
+                        votes.insert(vote.validator.clone(), vote);
+                    }
+                    Some(first) if first.block_hash != vote.block_hash => {
+                        let known = self.evidence.iter().any(|e| {
+                            e.first.height == first.height
+                                && e.first.round == first.round
+                                && e.first.vote_type == first.vote_type
+                                && e.first.validator == first.validator
+                        });
+                        if !known {
+                            self.evidence.push(Equivocation {
+                                first: first.clone(),
+                                second: vote,
+                            });
+                        }
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+    }
+
+    /// Proposals of the current height a quorum may vote for: the first of
+    /// each round, plus the conflicting one kept as evidence, since other
+    /// validators may have received that one first
+    fn known_proposals(&self) -> impl Iterator<Item = &Proposal> {
+        let conflicting = self
+            .proposal_evidence
+            .iter()
+            .map(|evidence| &evidence.second)
+            .filter(|proposal| proposal.height == self.height);
+        self.proposals.values().chain(conflicting)
+    }
+
+    /// Apply every rule whose condition holds until none does
+    fn evaluate(&mut self, out: &mut Vec<BftOutput>) {
+        while self.try_commit(out) || self.try_skip_round(out) || self.try_advance(out) {}
+    }
+
+    /// Decide when a quorum precommitted a proposed block in any round
+    fn try_commit(&mut self, out: &mut Vec<BftOutput>) -> bool {
+        let decided = self.known_proposals().find_map(|proposal| {
+            let hash = block_hash(&proposal.block);
+            (self.has_quorum_for(proposal.round, VoteType::Precommit, Some(hash))
+                && self.is_valid_block(&proposal.block))
+            .then(|| (proposal.round, proposal.block.clone(), hash))
+        });
+        let Some((round, block, hash)) = decided else {
+            return false;
+        };
+        // A quorum may have formed before our own precommit went out; send it
+        // anyway, since validators still at this height may need it for theirs
+        let precommitted = self
+            .votes
+            .get(&(round, VoteType::Precommit))
+            .is_some_and(|votes| votes.contains_key(&self.id));
+        if !precommitted {
+            let vote = Vote::signed(
+                VoteType::Precommit,
+                self.height,
+                round,
+                Some(hash),
+                &self.id,
+                &self.key,
+            );
+            out.push(BftOutput::Broadcast(BftMessage::Vote(vote.clone())));
+            self.record(BftMessage::Vote(vote));
+        }
+
+        let precommits = self.votes[&(round, VoteType::Precommit)]
+            .values()
+            .filter(|vote| vote.block_hash == Some(hash))
+            .cloned()
+            .collect();
+        let committed = CommittedBlock {
+            certificate: CommitCertificate {
+                height: self.height,
+                round,
+                block_hash: hash,
+                precommits,
+            },
+            block,
+        };
+        self.mempool.retain(|tx| {
+            !committed
+                .block
+                .transactions
+                .iter()
+                .any(|included| included.from == tx.from && included.nonce == tx.nonce)
+        });
+        self.last_hash = hash.to_vec();
+        self.decided.push(committed.clone());
+        out.push(BftOutput::Commit(committed));
+
+        self.height += 1;
+        self.locked = None;
+        self.valid = None;
+        self.proposals.clear();
+        self.votes.clear();
+        self.polka_seen.clear();
+        self.scheduled.clear();
+        self.start_round(0, out);
+        let (current, later): (Vec<_>, Vec<_>) = std::mem::take(&mut self.future)
+            .into_iter()
+            .partition(|message| message.height() == self.height);
+        self.future = later;
+        for message in current {
+            self.accept(message, out);
+        }
+        true
+    }
+
+    /// Jump to a later round once more than a third of the stake is there
+    fn try_skip_round(&mut self, out: &mut Vec<BftOutput>) -> bool {
+        let mut senders: BTreeMap<u64, HashSet<&str>> = BTreeMap::new();
+        for proposal in self.proposals.values() {
+            senders
+                .entry(proposal.round)
+                .or_default()
+                .insert(&proposal.proposer);
+        }
+        for ((round, _), votes) in &self.votes {
+            senders
+                .entry(*round)
+                .or_default()
+                .extend(votes.keys().map(String::as_str));
+        }
+        let target = senders
+            .range(self.round + 1..)
+            .rev()
+            .find(|(_, ids)| {
+                self.validators
+                    .exceeds_one_third(ids.iter().map(|id| self.validators.stake_of(id)).sum())
+            })
+            .map(|(round, _)| *round);
+        match target {
+            Some(round) => {
+                self.start_round(round, out);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Rules for the current round
+    fn try_advance(&mut self, out: &mut Vec<BftOutput>) -> bool {
+        let round = self.round;
+        let proposal: Option<(Block, Option<u64>)> = self
+            .proposals
+            .get(&round)
+            .map(|p| (p.block.clone(), p.valid_round));
+
+        if self.step == Step::Propose {
+            if let Some((block, valid_round)) = &proposal {
+                let hash = block_hash(block);
+                let acceptable = match valid_round {
+                    None => Some(
+                        self.locked
+                            .as_ref()
+                            .is_none_or(|(_, locked)| block_hash(locked) == hash),
+                    ),
+                    Some(vr)
+                        if *vr < round
+                            && self.has_quorum_for(*vr, VoteType::Prevote, Some(hash)) =>
+                    {
+                        Some(self.locked.as_ref().is_none_or(|(locked_round, locked)| {
+                            *locked_round <= *vr || block_hash(locked) == hash
+                        }))
+                    }
+                    Some(_) => None,
+                };
+                if let Some(unlocked) = acceptable {
+                    let vote = (unlocked && self.is_valid_block(block)).then_some(hash);
+                    self.cast(VoteType::Prevote, vote, out);
+                    self.step = Step::Prevote;
+                    return true;
+                }
+            }
+        }
+
+        if self.step >= Step::Prevote && !self.polka_seen.contains(&round) {
+            let polka = self
+                .known_proposals()
+                .filter(|p| p.round == round)
+                .map(|p| (p.block.clone(), block_hash(&p.block)))
+                .find(|(block, hash)| {
+                    self.has_quorum_for(round, VoteType::Prevote, Some(*hash))
+                        && self.is_valid_block(block)
+                });
+            if let Some((block, hash)) = polka {
+                self.polka_seen.insert(round);
+                if self.step == Step::Prevote {
+                    self.locked = Some((round, block.clone()));
+                    self.cast(VoteType::Precommit, Some(hash), out);
+                    self.step = Step::Precommit;
+                }
+                self.valid = Some((round, block));
+                return true;
+            }
+        }
+
+        if self.step == Step::Prevote {
+            if self.has_quorum_for(round, VoteType::Prevote, None) {
+                self.cast(VoteType::Precommit, None, out);
+                self.step = Step::Precommit;
+                return true;
+            }
+            if self.has_any_quorum(round, VoteType::Prevote)
+                && self.scheduled.insert((round, Step::Prevote))
+            {
+                self.schedule(Step::Prevote, self.config.timeout_prevote_ms, out);
+                return true;
+            }
+        }
+
+        if self.has_any_quorum(round, VoteType::Precommit)
+            && self.scheduled.insert((round, Step::Precommit))
+        {
+            self.schedule(Step::Precommit, self.config.timeout_precommit_ms, out);
+            return true;
+        }
+        false
+    }
+
+    fn start_round(&mut self, round: u64, out: &mut Vec<BftOutput>) {
+        self.round = round;
+        self.step = Step::Propose;
+        if self.validators.proposer(self.height, round) == self.id {
+            let (block, valid_round) = match &self.valid {
+                Some((valid_round, block)) => (block.clone(), Some(*valid_round)),
+                None => (self.build_block(), None),
+            };
+            let proposal =
+                Proposal::signed(self.height, round, block, valid_round, &self.id, &self.key);
+            out.push(BftOutput::Broadcast(BftMessage::Proposal(proposal.clone())));
+            self.record(BftMessage::Proposal(proposal));
+        } else if self.scheduled.insert((round, Step::Propose)) {
+            self.schedule(Step::Propose, self.config.timeout_propose_ms, out);
+        }
+    }
+
+    fn build_block(&self) -> Block {
+        let transactions: Vec<Transaction> = self
+            .mempool
+            .iter()
+            .filter(|tx| is_valid_transaction(tx))
+            .take(self.config.max_block_transactions)
+            .cloned()
+            .collect();
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let mut block = Block {
+            id: self.height,
+            height: self.height,
+            timestamp,
+            transactions,
+            previous_hash: self.last_hash.clone(),
+            hash: Vec::new(),
+            signature: Vec::new(),
+        };
+        let hash = block_hash(&block);
+        block.signature = self.key.sign(&hash).to_bytes().to_vec();
+        block.hash = hash.to_vec();
+        block
+    }
+
+    fn is_valid_block(&self, block: &Block) -> bool {
+        block.height == self.height
+            && block.previous_hash == self.last_hash
+            && block.hash == block_hash(block)
+            && block.transactions.iter().all(is_valid_transaction)
+    }
+
+    /// Sign a vote, record it and broadcast it
+    fn cast(&mut self, vote_type: VoteType, hash: Option<BlockHash>, out: &mut Vec<BftOutput>) {
+        let vote = Vote::signed(
+            vote_type,
+            self.height,
+            self.round,
+            hash,
+            &self.id,
+            &self.key,
+        );
+        out.push(BftOutput::Broadcast(BftMessage::Vote(vote.clone())));
+        self.record(BftMessage::Vote(vote));
+    }
+
+    fn schedule(&self, step: Step, base_ms: u64, out: &mut Vec<BftOutput>) {
+        out.push(BftOutput::ScheduleTimeout {
+            timeout: Timeout {
+                height: self.height,
+                round: self.round,
+                step,
+            },
+            after_ms: base_ms + self.config.timeout_delta_ms * self.round,
+        });
+    }
+
+    fn stake_for(&self, round: u64, vote_type: VoteType, hash: Option<BlockHash>) -> u64 {
+        self.votes.get(&(round, vote_type)).map_or(0, |votes| {
+            votes
+                .values()
+                .filter(|vote| vote.block_hash == hash)
+                .map(|vote| self.validators.stake_of(&vote.validator))
+                .sum()
+        })
+    }
+
+    fn has_quorum_for(&self, round: u64, vote_type: VoteType, hash: Option<BlockHash>) -> bool {
+        self.validators
+            .is_quorum(self.stake_for(round, vote_type, hash))
+    }
+
+    fn has_any_quorum(&self, round: u64, vote_type: VoteType) -> bool {
+        let stake = self.votes.get(&(round, vote_type)).map_or(0, |votes| {
+            votes
+                .keys()
+                .map(|validator| self.validators.stake_of(validator))
+                .sum()
+        });
+        self.validators.is_quorum(stake)
+    }
+}
+
+/// Errors that can occur during BFT consensus
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum BftError {
+    #[error("Validator set is empty")]
+    EmptyValidatorSet,
+    #[error("Validator {0} does not have a valid ed25519 public key")]
+    InvalidValidatorKey(String),
+    #[error("Unknown validator {0}")]
+    UnknownValidator(String),
+    #[error("Invalid signature from {0}")]
+    InvalidSignature(String),
+    #[error("{0} is not the proposer of this round")]
+    WrongProposer(String),
+    #[error("Invalid commit certificate: {0}")]
+    InvalidCertificate(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validators(stakes: &[(&str, u64)]) -> (ValidatorSet, HashMap<String, SigningKey>) {
+        let mut keys = HashMap::new();
+        let validators: Vec<Validator> = stakes
+            .iter()
+            .enumerate()
+            .map(|(i, (id, stake))| {
+                let key = SigningKey::from_bytes(&[i as u8 + 1; 32]);
+                let validator = Validator {
+                    id: id.to_string(),
+                    public_key: key.verifying_key().to_bytes().to_vec(),
+                    stake: *stake,
+                };
+                keys.insert(id.to_string(), key);
+                validator
+            })
+            .collect();
+        (ValidatorSet::new(&validators).unwrap(), keys)
+    }
+
+    #[test]
+    fn test_quorums_are_stake_weighted() {
+        let (set, _) = validators(&[("a", 70), ("b", 10), ("c", 10), ("d", 10)]);
+        assert!(!set.is_quorum(66));
+        assert!(set.is_quorum(70));
+        assert!(set.exceeds_one_third(34));
+        assert!(!set.exceeds_one_third(33));
+
+        // Heavier validators propose proportionally more often
+        let heavy = (0..1000)
+            .filter(|round| set.proposer(1, *round) == "a")
+            .count();
+        assert!((600..800).contains(&heavy), "a proposed {} times", heavy);
+    }
+
+    #[test]
+    fn test_locked_node_refuses_other_block_without_newer_polka() {
+        let (set, keys) = validators(&[("a", 1), ("b", 1), ("c", 1), ("d", 1)]);
+        let rounds: Vec<u64> = (0..)
+            .filter(|round| set.proposer(1, *round) != "a")
+            .take(2)
+            .collect();
+        let mut node =
+            BftNode::new("a", keys["a"].clone(), set.clone(), BftConfig::default()).unwrap();
+        node.start();
+
+        let proposal = |round: u64, timestamp: u64| {
+            let proposer = set.proposer(1, round);
+            let mut block = Block {
+                id: 1,
+                height: 1,
+                timestamp,
+                transactions: vec![],
+                previous_hash: vec![0; 32],
+                hash: vec![],
+                signature: vec![],
+            };
+            block.hash = block_hash(&block).to_vec();
+            Proposal::signed(1, round, block, None, proposer, &keys[proposer])
+        };
+        let vote = |vote_type, round, hash, voter: &str| {
+            BftMessage::Vote(Vote::signed(vote_type, 1, round, hash, voter, &keys[voter]))
+        };
+
+        // First round: with b and c prevoting for X we see a polka and lock
+        let x = proposal(rounds[0], 1);
+        let x_hash = block_hash(&x.block);
+        node.handle_message(BftMessage::Proposal(x)).unwrap();
+        for voter in ["b", "c"] {
+            node.handle_message(vote(VoteType::Prevote, rounds[0], Some(x_hash), voter))
+                .unwrap();
+        }
+        assert_eq!(node.locked(), Some((rounds[0], x_hash)));
+        assert_eq!(node.step(), Step::Precommit);
+
+        // Second round: a fresh proposal for Y gets a nil prevote
+        for voter in ["b", "c"] {
+            node.handle_message(vote(VoteType::Precommit, rounds[1], None, voter))
+                .unwrap();
+        }
+        assert_eq!(node.round(), rounds[1]);
+        let outputs = node
+            .handle_message(BftMessage::Proposal(proposal(rounds[1], 2)))
+            .unwrap();
+        let prevote = outputs.iter().find_map(|output| match output {
+            BftOutput::Broadcast(BftMessage::Vote(vote)) if vote.vote_type == VoteType::Prevote => {
+                Some(vote.block_hash)
+            }
+            _ => None,
+        });
+        assert_eq!(prevote, Some(None));
+        assert_eq!(node.locked(), Some((rounds[0], x_hash)));
+    }
+
+    #[test]
+    fn test_conflicting_votes_are_kept_as_evidence() {
+        let (set, keys) = validators(&[("a", 1), ("b", 1), ("c", 1), ("d", 1)]);
+        let mut node = BftNode::new("a", keys["a"].clone(), set, BftConfig::default()).unwrap();
+        node.start();
+        let first = Vote::signed(VoteType::Prevote, 1, 0, Some([1; 32]), "b", &keys["b"]);
+        let second = Vote::signed(VoteType::Prevote, 1, 0, None, "b", &keys["b"]);
+        node.handle_message(BftMessage::Vote(first.clone()))
+            .unwrap();
+        node.handle_message(BftMessage::Vote(second.clone()))
+            .unwrap();
+        assert_eq!(node.evidence(), &[Equivocation { first, second }]);
+
+        // A vote signed by someone else's key is rejected outright
+        let mut forged = Vote::signed(VoteType::Prevote, 1, 0, None, "c", &keys["d"]);
+        assert!(node
+            .handle_message(BftMessage::Vote(forged.clone()))
+            .is_err());
+        forged.validator = "mallory".to_string();
+        assert!(matches!(
+            node.handle_message(BftMessage::Vote(forged)),
+            Err(BftError::UnknownValidator(_))
+        ));
+    }
+
+    #[test]
+    fn test_conflicting_proposals_are_kept_as_evidence_not_relayed() {
+        let (set, keys) = validators(&[("a", 1), ("b", 1), ("c", 1), ("d", 1)]);
+        let round = (0..).find(|round| set.proposer(1, *round) != "a").unwrap();
+        let proposer = set.proposer(1, round).to_string();
+        let mut node =
+            BftNode::new("a", keys["a"].clone(), set.clone(), BftConfig::default()).unwrap();
+        node.start();
+
+        let proposal = |timestamp: u64| {
+            let mut block = Block {
+                id: 1,
+                height: 1,
+                timestamp,
+                transactions: vec![],
+                previous_hash: vec![0; 32],
+                hash: vec![],
+                signature: vec![],
+            };
+            block.hash = block_hash(&block).to_vec();
+            Proposal::signed(1, round, block, None, &proposer, &keys[&proposer])
+        };
+        let relayed = |outputs: &[BftOutput]| {
+            outputs
+                .iter()
+                .any(|output| matches!(output, BftOutput::Broadcast(BftMessage::Proposal(_))))
+        };
+
+        let first = node
+            .handle_message(BftMessage::Proposal(proposal(1)))
+            .unwrap();
+        assert!(relayed(&first));
+        for timestamp in 2..10 {
+            let outputs = node
+                .handle_message(BftMessage::Proposal(proposal(timestamp)))
+                .unwrap();
+            assert!(!relayed(&outputs));
+        }
+        assert_eq!(node.proposals.len(), 1);
+        assert_eq!(node.proposal_evidence().len(), 1);
+        let evidence = &node.proposal_evidence()[0];
+        assert_eq!(evidence.first.block.timestamp, 1);
+        assert_eq!(evidence.second.block.timestamp, 2);
+    }
+
+    #[test]
+    fn test_messages_far_ahead_are_dropped() {
+        let (set, keys) = validators(&[("a", 1), ("b", 1), ("c", 1), ("d", 1)]);
+        let config = BftConfig::default();
+        let mut node = BftNode::new("a", keys["a"].clone(), set, config.clone()).unwrap();
+        node.start();
+        let vote = |height: u64, round: u64, hash: Option<BlockHash>| {
+            BftMessage::Vote(Vote::signed(
+                VoteType::Prevote,
+                height,
+                round,
+                hash,
+                "b",
+                &keys["b"],
+            ))
+        };
+
+        node.handle_message(vote(1, config.future_rounds + 1, None))
+            .unwrap();
+        node.handle_message(vote(2 + config.future_heights, 0, None))
+            .unwrap();
+        assert!(node.votes.is_empty());
+        assert!(node.future.is_empty());
+
+        // Within the windows only the first message per slot is buffered
+        for hash in [None, Some([1; 32]), Some([2; 32])] {
+            node.handle_message(vote(2, 0, hash)).unwrap();
+        }
+        node.handle_message(vote(1, config.future_rounds, None))
+            .unwrap();
+        assert_eq!(node.future.len(), 1);
+        assert_eq!(node.votes.len(), 1);
+    }
+
+    #[test]
+    fn test_certificate_needs_quorum_of_valid_signatures() {
+        let (set, keys) = validators(&[("a", 40), ("b", 30), ("c", 20), ("d", 10)]);
+        let hash = [7u8; 32];
+        let precommit =
+            |id: &str| Vote::signed(VoteType::Precommit, 1, 0, Some(hash), id, &keys[id]);
+        let mut certificate = CommitCertificate {
+            height: 1,
+            round: 0,
+            block_hash: hash,
+            precommits: vec![precommit("a"), precommit("c")],
+        };
+        assert!(certificate.verify(&set).is_err());
+
+        // Counting the same signer twice does not help
+        certificate.precommits.push(precommit("c"));
+        assert!(certificate.verify(&set).is_err());
+
+        certificate.precommits.push(precommit("d"));
+        assert!(certificate.verify(&set).is_ok());
+
+        certificate.precommits[0].block_hash = Some([8u8; 32]);
+        assert!(certificate.verify(&set).is_err());
+        certificate.precommits[0] = precommit("a");
+        certificate.precommits[0].signature[0] ^= 1;
+        assert!(matches!(
+            certificate.verify(&set),
+            Err(BftError::InvalidSignature(_))
+        ));
+    }
+}
@@ -0,0 +1,505 @@
+//! Discrete-event simulation of BFT consensus with Byzantine validators
+//!
+//! [`BftSimulation`] runs one [`BftNode`] per validator on a virtual clock.
+//! Messages arrive after a random latency drawn from a seeded generator, so
+//! every run is reproducible, and timeouts fire at their scheduled virtual
+//! time. Validators can be configured as:
+//! - [`Behavior::Honest`], following the protocol;
+//! - [`Behavior::Silent`], never sending anything, like a crashed node;
+//! - [`Behavior::Equivocating`], sending conflicting proposals and votes to
+//!   different halves of the network.
+//!
+//! [`BftSimulation::check_agreement`] verifies that honest validators never
+//! decide different blocks at the same height.
+//!
+//! This implements the Priority 1 feature from DEX-OS-V1.csv:
+//! "Core Components,Quantum Consensus (QBFT),Consensus,Lattice BFT Core,BFT Core,High"
+
+use super::bft::{
+    block_hash, BftConfig, BftMessage, BftNode, BftOutput, CommittedBlock, Equivocation, Proposal,
+    Timeout, Vote,
+};
+use crate::quantum_consensus::{QuantumConsensusEngine, QuantumConsensusError};
+use crate::types::{Transaction, Validator};
+use ed25519_dalek::{Signer, SigningKey};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashSet};
+
+/// How a simulated validator behaves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Behavior {
+    /// Follows the protocol
+    Honest,
+    /// Sends nothing at all
+    Silent,
+    /// Sends conflicting proposals and votes to different peers
+    Equivocating,
+}
+
+/// Network parameters of a simulation
+#[derive(Debug, Clone)]
+pub struct SimulationConfig {
+    /// Smallest message latency
+    pub min_latency_ms: u64,
+    /// Largest message latency
+    pub max_latency_ms: u64,
+    /// Seed for keys and latencies
+    pub seed: u64,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            min_latency_ms: 5,
+            max_latency_ms: 50,
+            seed: 7,
+        }
+    }
+}
+
+/// A validator taking part in the simulation
+struct SimulatedValidator {
+    /// Consensus state machine
+    node: BftNode,
+    /// Key, also used to sign conflicting messages
+    key: SigningKey,
+    /// How the validator behaves
+    behavior: Behavior,
+}
+
+/// Something that happens at a point in virtual time
+enum Event {
+    /// A message reaches a validator
+    Deliver { to: String, message: BftMessage },
+    /// A timeout of a validator expires
+    Timeout { node: String, timeout: Timeout },
+}
+
+/// Event queue entry, ordered by time and then by insertion
+struct Scheduled {
+    at: u64,
+    sequence: u64,
+    event: Event,
+}
+
+impl PartialEq for Scheduled {
+    fn eq(&self, other: &Self) -> bool {
+        (self.at, self.sequence) == (other.at, other.sequence)
+    }
+}
+
+impl Eq for Scheduled {}
+
+impl PartialOrd for Scheduled {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Scheduled {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.at, self.sequence).cmp(&(other.at, other.sequence))
+    }
+}
+
+/// Validators connected by a simulated network
+pub struct BftSimulation {
+    /// Validators by ID
+    validators: BTreeMap<String, SimulatedValidator>,
+    /// Pending events, earliest first
+    queue: BinaryHeap<Reverse<Scheduled>>,
+    /// Current virtual time
+    now: u64,
+    /// Insertion counter keeping the queue order deterministic
+    next_sequence: u64,
+    /// Source of keys and latencies
+    rng: StdRng,
+    /// Network parameters
+    config: SimulationConfig,
+    /// Messages delivered so far
+    delivered: u64,
+    /// Messages rejected by their receiver
+    rejected: u64,
+    /// Proposals an equivocator has already forked, by proposer, height and
+    /// round. Honest relays of the fork must not be forked again.
+    forked: HashSet<(String, u64, u64)>,
+}
+
+impl BftSimulation {
+    /// Create validators with the given stakes and behaviours. Their nodes
+    /// come from a [`QuantumConsensusEngine`] holding the same validators.
+    pub fn new(
+        validators: &[(&str, u64, Behavior)],
+        bft: BftConfig,
+        config: SimulationConfig,
+    ) -> Result<Self, QuantumConsensusError> {
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let mut engine = QuantumConsensusEngine::new();
+        let mut keys = Vec::new();
+        for (id, stake, behavior) in validators {
+            let key = SigningKey::from_bytes(&rng.gen());
+            engine.add_validator(Validator {
+                id: id.to_string(),
+                public_key: key.verifying_key().to_bytes().to_vec(),
+                stake: *stake,
+            })?;
+            keys.push((id.to_string(), key, *behavior));
+        }
+
+        let mut simulated = BTreeMap::new();
+        for (id, key, behavior) in keys {
+            let node = engine.bft_node(&id, key.clone(), bft.clone())?;
+            simulated.insert(
+                id,
+                SimulatedValidator {
+                    node,
+                    key,
+                    behavior,
+                },
+            );
+        }
+        Ok(Self {
+            validators: simulated,
+            queue: BinaryHeap::new(),
+            now: 0,
+            next_sequence: 0,
+            rng,
+            config,
+            delivered: 0,
+            rejected: 0,
+            forked: HashSet::new(),
+        })
+    }
+
+    /// Start every validator that is not silent
+    pub fn start(&mut self) {
+        let ids: Vec<String> = self.validators.keys().cloned().collect();
+        for id in ids {
+            let validator = self.validators.get_mut(&id).expect("validator exists");
+            if validator.behavior != Behavior::Silent {
+                let outputs = validator.node.start();
+                self.dispatch(&id, outputs);
+            }
+        }
+    }
+
+    /// Give a transaction to every validator's mempool
+    pub fn submit_transaction(&mut self, transaction: Transaction) {
+        for validator in self.validators.values_mut() {
+            validator.node.submit_transaction(transaction.clone());
+        }
+    }
+
+    /// Process events until `done` holds or virtual time passes `deadline_ms`.
+    /// Returns whether `done` was reached.
+    pub fn run_until(&mut self, deadline_ms: u64, done: impl Fn(&Self) -> bool) -> bool {
+        while !done(self) {
+            let Some(Reverse(next)) = self.queue.pop() else {
+                return false;
+            };
+            if next.at > deadline_ms {
+                self.queue.push(Reverse(next));
+                return false;
+            }
+            self.now = next.at;
+            self.process(next.event);
+        }
+        true
+    }
+
+    /// Run until every honest validator decided `height` blocks
+    pub fn run_until_height(&mut self, height: u64, deadline_ms: u64) -> bool {
+        self.run_until(deadline_ms, |sim| {
+            sim.honest_ids()
+                .iter()
+                .all(|id| sim.decided(id).len() as u64 >= height)
+        })
+    }
+
+    /// IDs of the honest validators
+    pub fn honest_ids(&self) -> Vec<String> {
+        self.validators
+            .iter()
+            .filter(|(_, v)| v.behavior == Behavior::Honest)
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
+    /// Blocks decided by `validator`
+    pub fn decided(&self, validator: &str) -> &[CommittedBlock] {
+        self.validators
+            .get(validator)
+            .map_or(&[], |v| v.node.decided())
+    }
+
+    /// Equivocations observed by `validator`
+    pub fn evidence(&self, validator: &str) -> &[Equivocation] {
+        self.validators
+            .get(validator)
+            .map_or(&[], |v| v.node.evidence())
+    }
+
+    /// Current virtual time
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    /// Messages delivered so far
+    pub fn delivered_messages(&self) -> u64 {
+        self.delivered
+    }
+
+    /// Messages rejected by their receiver, such as forged signatures
+    pub fn rejected_messages(&self) -> u64 {
+        self.rejected
+    }
+
+    /// Check that no two honest validators decided different blocks at the
+    /// same height
+    pub fn check_agreement(&self) -> Result<(), String> {
+        let mut decisions: BTreeMap<u64, (String, [u8; 32])> = BTreeMap::new();
+        for id in self.honest_ids() {
+            for committed in self.decided(&id) {
+                let hash = block_hash(&committed.block);
+                match decisions.get(&committed.block.height) {
+                    Some((other, decided)) if *decided != hash => {
+                        return Err(format!(
+                            "{} and {} decided different blocks at height {}",
+                            other, id, committed.block.height
+                        ));
+                    }
+                    Some(_) => {}
+                    None => {
+                        decisions.insert(committed.block.height, (id.clone(), hash));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn process(&mut self, event: Event) {
+        let (id, result) = match event {
+            Event::Deliver { to, message } => {
+                let Some(validator) = self.validators.get_mut(&to) else {
+                    return;
+                };
+                if validator.behavior == Behavior::Silent {
+                    return;
+                }
+                self.delivered += 1;
+                let result = validator.node.handle_message(message);
+                (to, result)
+            }
+            Event::Timeout { node, timeout } => {
+                let validator = self.validators.get_mut(&node).expect("validator exists");
+                let outputs = validator.node.handle_timeout(timeout);
+                (node, Ok(outputs))
+            }
+        };
+        match result {
+            Ok(outputs) => self.dispatch(&id, outputs),
+            Err(_) => self.rejected += 1,
+        }
+    }
+
+    fn dispatch(&mut self, from: &str, outputs: Vec<BftOutput>) {
+        for output in outputs {
+            match output {
+                BftOutput::Broadcast(message) => self.broadcast(from, message),
+                BftOutput::ScheduleTimeout { timeout, after_ms } => self.schedule(
+                    self.now + after_ms,
+                    Event::Timeout {
+                        node: from.to_string(),
+                        timeout,
+                    },
+                ),
+                BftOutput::Commit(_) => {}
+            }
+        }
+    }
+
+    fn broadcast(&mut self, from: &str, message: BftMessage) {
+        let peers: Vec<String> = self
+            .validators
+            .keys()
+            .filter(|id| id.as_str() != from)
+            .cloned()
+            .collect();
+        let behavior = self.validators[from].behavior;
+        let conflicting = match behavior {
+            Behavior::Honest => None,
+            Behavior::Silent => return,
+            Behavior::Equivocating => self.conflicting(from, &message),
+        };
+
+        let half = peers.len() / 2;
+        for (i, peer) in peers.into_iter().enumerate() {
+            let message = match &conflicting {
+                Some(other) if i >= half => other.clone(),
+                _ => message.clone(),
+            };
+            let latency = self
+                .rng
+                .gen_range(self.config.min_latency_ms..=self.config.max_latency_ms);
+            self.schedule(self.now + latency, Event::Deliver { to: peer, message });
+        }
+    }
+
+    /// A message contradicting `message`, signed by `from`
+    fn conflicting(&mut self, from: &str, message: &BftMessage) -> Option<BftMessage> {
+        let key = self.validators[from].key.clone();
+        match message {
+            BftMessage::Vote(vote) => {
+                let other_hash = match vote.block_hash {
+                    Some(_) => None,
+                    None => Some(self.rng.gen()),
+                };
+                Some(BftMessage::Vote(Vote::signed(
+                    vote.vote_type,
+                    vote.height,
+                    vote.round,
+                    other_hash,
+                    from,
+                    &key,
+                )))
+            }
+            // Relayed proposals of other validators cannot be forged
+            BftMessage::Proposal(proposal)
+                if proposal.proposer == from
+                    && self
+                        .forked
+                        .insert((from.to_string(), proposal.height, proposal.round)) =>
+            {
+                let mut block = proposal.block.clone();
+                block.timestamp += 1;
+                let hash = block_hash(&block);
+                block.hash = hash.to_vec();
+                block.signature = key.sign(&hash).to_bytes().to_vec();
+                Some(BftMessage::Proposal(Proposal::signed(
+                    proposal.height,
+                    proposal.round,
+                    block,
+                    proposal.valid_round,
+                    from,
+                    &key,
+                )))
+            }
+            BftMessage::Proposal(_) => None,
+        }
+    }
+
+    fn schedule(&mut self, at: u64, event: Event) {
+        self.queue.push(Reverse(Scheduled {
+            at,
+            sequence: self.next_sequence,
+            event,
+        }));
+        self.next_sequence += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(nonce: u64) -> Transaction {
+        Transaction {
+            from: "alice".to_string(),
+            to: "bob".to_string(),
+            amount: 10,
+            nonce,
+            signature: vec![],
+        }
+    }
+
+    fn simulation(validators: &[(&str, u64, Behavior)]) -> BftSimulation {
+        let mut sim = BftSimulation::new(
+            validators,
+            BftConfig::default(),
+            SimulationConfig::default(),
+        )
+        .unwrap();
+        sim.start();
+        sim
+    }
+
+    #[test]
+    fn test_honest_validators_decide_the_same_certified_blocks() {
+        let validators = [
+            ("v1", 100, Behavior::Honest),
+            ("v2", 100, Behavior::Honest),
+            ("v3", 100, Behavior::Honest),
+            ("v4", 100, Behavior::Honest),
+        ];
+        let mut sim = BftSimulation::new(
+            &validators,
+            BftConfig::default(),
+            SimulationConfig::default(),
+        )
+        .unwrap();
+        sim.submit_transaction(transfer(1));
+        sim.start();
+        assert!(sim.run_until_height(5, 60_000));
+        sim.check_agreement().unwrap();
+        assert_eq!(sim.decided("v1")[0].block.transactions.len(), 1);
+
+        // The engine accepts the certified blocks into a shard
+        let mut engine = QuantumConsensusEngine::new();
+        for (id, _, _) in &validators {
+            engine
+                .add_validator(Validator {
+                    id: id.to_string(),
+                    public_key: sim.validators[*id].key.verifying_key().to_bytes().to_vec(),
+                    stake: 100,
+                })
+                .unwrap();
+        }
+        engine.initialize_shards(1).unwrap();
+        for committed in sim.decided("v2") {
+            engine.commit_block(0, committed).unwrap();
+        }
+        assert_eq!(engine.get_shard_finalized_height(0), Some(5));
+
+        let mut forged = sim.decided("v3")[0].clone();
+        forged.certificate.precommits.truncate(2);
+        assert!(engine.commit_block(0, &forged).is_err());
+    }
+
+    #[test]
+    fn test_progress_depends_on_stake_not_validator_count() {
+        // A silent validator with a tenth of the stake does not block progress
+        let mut sim = simulation(&[
+            ("v1", 10, Behavior::Silent),
+            ("v2", 30, Behavior::Honest),
+            ("v3", 30, Behavior::Honest),
+            ("v4", 30, Behavior::Honest),
+        ]);
+        assert!(sim.run_until_height(3, 60_000));
+        sim.check_agreement().unwrap();
+
+        // One silent validator holding 40% of the stake halts the chain
+        let mut sim = simulation(&[
+            ("v1", 40, Behavior::Silent),
+            ("v2", 20, Behavior::Honest),
+            ("v3", 20, Behavior::Honest),
+            ("v4", 20, Behavior::Honest),
+        ]);
+        assert!(!sim.run_until_height(1, 20_000));
+        assert!(sim.honest_ids().iter().all(|id| sim.decided(id).is_empty()));
+    }
+
+    #[test]
+    fn test_equivocating_validators_cannot_break_agreement() {
+        let mut sim = simulation(&[
+            ("v1", 100, Behavior::Equivocating),
+            ("v2", 100, Behavior::Honest),
+            ("v3", 100, Behavior::Honest),
+            ("v4", 100, Behavior::Honest),
+        ]);
+        assert!(sim.run_until_height(4, 60_000));
+        sim.check_agreement().unwrap();
+    }
+}
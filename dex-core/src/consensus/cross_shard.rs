@@ -0,0 +1,497 @@
+//! Cross-shard transfers with receipts and Merkle proofs
+//!
+//! Every account lives on the shard given by [`shard_of`]. A transfer whose
+//! recipient lives on another shard debits the sender on the source shard and
+//! emits a [`Receipt`]. The receipts of each block are committed to by the
+//! Merkle root in its [`ShardHeader`]; once the source block is final, anyone
+//! can relay a receipt with a [`ReceiptProof`] and the target shard credits the
+//! recipient in its next block. IDs of consumed receipts are part of the
+//! target's state, so a receipt can be credited only once.
+//!
+//! Shard state roots are Merkle roots over the account ledger and the consumed
+//! receipts, so two replicas agree on a root exactly when they agree on state.
+//!
+//! This implements the Priority 2 feature from DEX-OS-V1.csv:
+//! "2,Core Components,Quantum Consensus (QBFT),Consensus,1,000,000 Shards,Sharding,High"
+
+use crate::merkle_tree::MerkleTree;
+use crate::types::Block;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Root of an empty ledger or an empty receipt list
+pub const EMPTY_ROOT: [u8; 32] = [0u8; 32];
+
+/// Errors from executing transfers and checking receipts
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CrossShardError {
+    #[error("Account {account} does not live on shard {shard}")]
+    WrongShard { account: String, shard: u64 },
+    #[error("Stale nonce for account {0}")]
+    StaleNonce(String),
+    #[error("Transfer amount must be positive")]
+    InvalidAmount,
+    #[error("Insufficient balance for account {0}")]
+    InsufficientBalance(String),
+    #[error("Balance overflow for account {0}")]
+    BalanceOverflow(String),
+    #[error("No header for shard {shard} at height {height}")]
+    UnknownHeader { shard: u64, height: u64 },
+    #[error("Shard {shard} has not finalized height {height}")]
+    NotFinalized { shard: u64, height: u64 },
+    #[error("Receipt proof does not match the source header")]
+    InvalidProof,
+    #[error("Receipt was already credited")]
+    AlreadyConsumed,
+    #[error("Receipt not found")]
+    UnknownReceipt,
+}
+
+/// Shard holding `account` when the chain has `num_shards` shards
+pub fn shard_of(account: &str, num_shards: u64) -> u64 {
+    let digest = Sha256::digest(account.as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    u64::from_be_bytes(bytes) % num_shards.max(1)
+}
+
+/// Funds in flight from a debit on one shard to a credit on another
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Receipt {
+    /// Shard that debited the sender
+    pub source_shard: u64,
+    /// Shard that credits the recipient
+    pub target_shard: u64,
+    /// Height of the source block that emitted the receipt
+    pub source_height: u64,
+    /// Sender
+    pub from: String,
+    /// Recipient
+    pub to: String,
+    /// Amount debited
+    pub amount: i64,
+    /// Nonce of the originating transaction
+    pub nonce: u64,
+}
+
+impl Receipt {
+    /// Canonical encoding, used as the Merkle leaf
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&self.source_shard.to_be_bytes());
+        bytes.extend_from_slice(&self.target_shard.to_be_bytes());
+        bytes.extend_from_slice(&self.source_height.to_be_bytes());
+        for field in [self.from.as_bytes(), self.to.as_bytes()] {
+            bytes.extend_from_slice(&(field.len() as u64).to_be_bytes());
+            bytes.extend_from_slice(field);
+        }
+        bytes.extend_from_slice(&self.amount.to_be_bytes());
+        bytes.extend_from_slice(&self.nonce.to_be_bytes());
+        bytes
+    }
+
+    /// Unique ID of the receipt
+    pub fn id(&self) -> [u8; 32] {
+        Sha256::digest(self.encode()).into()
+    }
+}
+
+/// Merkle root over `receipts`, in order
+pub fn receipts_root(receipts: &[Receipt]) -> Vec<u8> {
+    let leaves: Vec<Vec<u8>> = receipts.iter().map(Receipt::encode).collect();
+    MerkleTree::from_data(&leaves)
+        .root_hash()
+        .unwrap_or_else(|| EMPTY_ROOT.to_vec())
+}
+
+/// Summary of a block applied to a shard
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardHeader {
+    /// Block height
+    pub height: u64,
+    /// State root after the block
+    pub state_root: Vec<u8>,
+    /// Merkle root over the receipts the block emitted
+    pub receipts_root: Vec<u8>,
+    /// Number of receipts the block emitted
+    pub receipt_count: usize,
+}
+
+/// Receipt together with its Merkle path to a source header
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReceiptProof {
+    /// The receipt
+    pub receipt: Receipt,
+    /// Position of the receipt in its block
+    pub index: usize,
+    /// Sibling hashes from the leaf to the root
+    pub siblings: Vec<Vec<u8>>,
+}
+
+impl ReceiptProof {
+    /// Proof for the receipt at `index` of `receipts`
+    pub fn new(receipts: &[Receipt], index: usize) -> Result<Self, CrossShardError> {
+        let receipt = receipts
+            .get(index)
+            .cloned()
+            .ok_or(CrossShardError::UnknownReceipt)?;
+        let leaves: Vec<Vec<u8>> = receipts.iter().map(Receipt::encode).collect();
+        let siblings = MerkleTree::from_data(&leaves)
+            .generate_proof_with_data(&leaves, index)
+            .ok_or(CrossShardError::UnknownReceipt)?;
+        Ok(Self {
+            receipt,
+            index,
+            siblings,
+        })
+    }
+
+    /// Check the proof against the header of the block that emitted the receipt
+    pub fn verify(&self, header: &ShardHeader) -> Result<(), CrossShardError> {
+        if header.height != self.receipt.source_height
+            || self.index >= header.receipt_count
+            || !MerkleTree::verify_proof_with_root(
+                &header.receipts_root,
+                &self.receipt.encode(),
+                self.index,
+                &self.siblings,
+            )
+        {
+            return Err(CrossShardError::InvalidProof);
+        }
+        Ok(())
+    }
+}
+
+/// Result of applying a block to a shard
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockOutcome {
+    /// Transactions that failed, by index, with the reason. Failed
+    /// transactions stay in the block but do not change state.
+    pub failed: Vec<(usize, CrossShardError)>,
+    /// Receipts emitted for recipients on other shards
+    pub receipts: Vec<Receipt>,
+    /// IDs of inbound receipts credited
+    pub credited: Vec<[u8; 32]>,
+}
+
+/// Account ledger of one shard
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShardState {
+    /// Balances of accounts on this shard
+    balances: BTreeMap<String, i64>,
+    /// Highest nonce each account has used
+    nonces: BTreeMap<String, u64>,
+    /// IDs of inbound receipts already credited
+    consumed: BTreeSet<[u8; 32]>,
+}
+
+impl ShardState {
+    /// Balance of `account`
+    pub fn balance(&self, account: &str) -> i64 {
+        self.balances.get(account).copied().unwrap_or(0)
+    }
+
+    /// Highest nonce `account` has used
+    pub fn nonce(&self, account: &str) -> u64 {
+        self.nonces.get(account).copied().unwrap_or(0)
+    }
+
+    /// Whether the receipt with `id` was already credited
+    pub fn is_consumed(&self, id: &[u8; 32]) -> bool {
+        self.consumed.contains(id)
+    }
+
+    /// Add `amount` to `account`
+    pub fn credit(&mut self, account: &str, amount: i64) -> Result<(), CrossShardError> {
+        if amount <= 0 {
+            return Err(CrossShardError::InvalidAmount);
+        }
+        let balance = self.balances.entry(account.to_string()).or_insert(0);
+        *balance = balance
+            .checked_add(amount)
+            .ok_or_else(|| CrossShardError::BalanceOverflow(account.to_string()))?;
+        Ok(())
+    }
+
+    /// Credit `inbox` and execute the transactions of `block` on shard
+    /// `shard_id`
+    pub fn apply_block(
+        &mut self,
+        shard_id: u64,
+        num_shards: u64,
+        block: &Block,
+        inbox: &[Receipt],
+    ) -> BlockOutcome {
+        let mut outcome = BlockOutcome::default();
+        for receipt in inbox {
+            let id = receipt.id();
+            if receipt.target_shard != shard_id || self.consumed.contains(&id) {
+                continue;
+            }
+            if self.credit(&receipt.to, receipt.amount).is_ok() {
+                self.consumed.insert(id);
+                outcome.credited.push(id);
+            }
+        }
+
+        for (index, transaction) in block.transactions.iter().enumerate() {
+            let target_shard = shard_of(&transaction.to, num_shards);
+            let previous_nonce = self.nonces.get(&transaction.from).copied();
+            let result = self
+                .debit(
+                    shard_id,
+                    num_shards,
+                    &transaction.from,
+                    transaction.amount,
+                    transaction.nonce,
+                )
+                .and_then(|()| {
+                    if target_shard == shard_id {
+                        // A failed credit must not burn the debited amount
+                        return self
+                            .credit(&transaction.to, transaction.amount)
+                            .inspect_err(|_| {
+                                self.undo_debit(
+                                    &transaction.from,
+                                    transaction.amount,
+                                    previous_nonce,
+                                )
+                            });
+                    }
+                    outcome.receipts.push(Receipt {
+                        source_shard: shard_id,
+                        target_shard,
+                        source_height: block.height,
+                        from: transaction.from.clone(),
+                        to: transaction.to.clone(),
+                        amount: transaction.amount,
+                        nonce: transaction.nonce,
+                    });
+                    Ok(())
+                });
+            if let Err(err) = result {
+                outcome.failed.push((index, err));
+            }
+        }
+        outcome
+    }
+
+    /// Merkle root over the ledger and the consumed receipts
+    pub fn state_root(&self) -> Vec<u8> {
+        let mut leaves: Vec<Vec<u8>> = Vec::new();
+        for (account, balance) in &self.balances {
+            let mut leaf = b"account".to_vec();
+            leaf.extend_from_slice(&(account.len() as u64).to_be_bytes());
+            leaf.extend_from_slice(account.as_bytes());
+            leaf.extend_from_slice(&balance.to_be_bytes());
+            leaf.extend_from_slice(&self.nonce(account).to_be_bytes());
+            leaves.push(leaf);
+        }
+        for id in &self.consumed {
+            let mut leaf = b"receipt".to_vec();
+            leaf.extend_from_slice(id);
+            leaves.push(leaf);
+        }
+        MerkleTree::from_data(&leaves)
+            .root_hash()
+            .unwrap_or_else(|| EMPTY_ROOT.to_vec())
+    }
+
+    fn debit(
+        &mut self,
+        shard_id: u64,
+        num_shards: u64,
+        account: &str,
+        amount: i64,
+        nonce: u64,
+    ) -> Result<(), CrossShardError> {
+        if shard_of(account, num_shards) != shard_id {
+            return Err(CrossShardError::WrongShard {
+                account: account.to_string(),
+                shard: shard_id,
+            });
+        }
+        if amount <= 0 {
+            return Err(CrossShardError::InvalidAmount);
+        }
+        if nonce <= self.nonce(account) {
+            return Err(CrossShardError::StaleNonce(account.to_string()));
+        }
+        if self.balance(account) < amount {
+            return Err(CrossShardError::InsufficientBalance(account.to_string()));
+        }
+        *self.balances.entry(account.to_string()).or_insert(0) -= amount;
+        self.nonces.insert(account.to_string(), nonce);
+        Ok(())
+    }
+
+    /// Revert [`Self::debit`], restoring the nonce `account` had before it
+    fn undo_debit(&mut self, account: &str, amount: i64, previous_nonce: Option<u64>) {
+        *self.balances.entry(account.to_string()).or_insert(0) += amount;
+        match previous_nonce {
+            Some(nonce) => self.nonces.insert(account.to_string(), nonce),
+            None => self.nonces.remove(account),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Transaction;
+
+    /// An account living on `shard` out of `num_shards`
+    fn account_on(shard: u64, num_shards: u64, tag: &str) -> String {
+        (0..)
+            .map(|i| format!("{}-{}", tag, i))
+            .find(|account| shard_of(account, num_shards) == shard)
+            .unwrap()
+    }
+
+    fn block(height: u64, transactions: Vec<Transaction>) -> Block {
+        Block {
+            id: height,
+            height,
+            timestamp: height,
+            transactions,
+            previous_hash: vec![],
+            hash: vec![],
+            signature: vec![],
+        }
+    }
+
+    fn transfer(from: &str, to: &str, amount: i64, nonce: u64) -> Transaction {
+        Transaction {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount,
+            nonce,
+            signature: vec![],
+        }
+    }
+
+    #[test]
+    fn test_transfers_update_the_state_root_and_emit_receipts() {
+        let alice = account_on(0, 2, "alice");
+        let carol = account_on(0, 2, "carol");
+        let bob = account_on(1, 2, "bob");
+        let mut state = ShardState::default();
+        assert_eq!(state.state_root(), EMPTY_ROOT.to_vec());
+        state.credit(&alice, 100).unwrap();
+        let funded_root = state.state_root();
+
+        let outcome = state.apply_block(
+            0,
+            2,
+            &block(
+                1,
+                vec![
+                    transfer(&alice, &carol, 10, 1),
+                    transfer(&alice, &bob, 20, 2),
+                    transfer(&alice, &bob, 20, 2),
+                    transfer(&alice, &bob, 500, 3),
+                    transfer(&bob, &alice, 1, 1),
+                ],
+            ),
+            &[],
+        );
+        assert_eq!(
+            outcome.failed.iter().map(|(i, _)| *i).collect::<Vec<_>>(),
+            vec![2, 3, 4]
+        );
+        assert_eq!(
+            outcome.failed[0].1,
+            CrossShardError::StaleNonce(alice.clone())
+        );
+        assert_eq!(outcome.receipts.len(), 1);
+        assert_eq!(outcome.receipts[0].target_shard, 1);
+        assert_eq!(state.balance(&alice), 70);
+        assert_eq!(state.balance(&carol), 10);
+        assert_ne!(state.state_root(), funded_root);
+
+        let mut replica = ShardState::default();
+        replica.credit(&alice, 100).unwrap();
+        replica.apply_block(0, 2, &block(1, vec![transfer(&alice, &carol, 10, 1)]), &[]);
+        assert_ne!(replica.state_root(), state.state_root());
+        replica.apply_block(0, 2, &block(2, vec![transfer(&alice, &bob, 20, 2)]), &[]);
+        assert_eq!(replica.state_root(), state.state_root());
+    }
+
+    #[test]
+    fn test_failed_credit_leaves_the_sender_untouched() {
+        let alice = account_on(0, 2, "alice");
+        let carol = account_on(0, 2, "carol");
+        let mut state = ShardState::default();
+        state.credit(&alice, 100).unwrap();
+        state.credit(&carol, i64::MAX - 5).unwrap();
+        let before = state.clone();
+
+        let outcome =
+            state.apply_block(0, 2, &block(1, vec![transfer(&alice, &carol, 10, 1)]), &[]);
+        assert_eq!(
+            outcome.failed,
+            vec![(0, CrossShardError::BalanceOverflow(carol.clone()))]
+        );
+        assert_eq!(state, before);
+
+        // The nonce was not consumed, so a smaller transfer can reuse it
+        let outcome = state.apply_block(0, 2, &block(2, vec![transfer(&alice, &carol, 5, 1)]), &[]);
+        assert!(outcome.failed.is_empty());
+        assert_eq!(state.balance(&alice), 95);
+        assert_eq!(state.balance(&carol), i64::MAX);
+    }
+
+    #[test]
+    fn test_receipt_proofs_bind_to_the_source_header() {
+        let receipts: Vec<Receipt> = (0..5)
+            .map(|i| Receipt {
+                source_shard: 0,
+                target_shard: 1,
+                source_height: 7,
+                from: "alice".to_string(),
+                to: format!("user-{}", i),
+                amount: 10 + i,
+                nonce: i as u64 + 1,
+            })
+            .collect();
+        let header = ShardHeader {
+            height: 7,
+            state_root: vec![],
+            receipts_root: receipts_root(&receipts),
+            receipt_count: receipts.len(),
+        };
+        for index in 0..receipts.len() {
+            ReceiptProof::new(&receipts, index)
+                .unwrap()
+                .verify(&header)
+                .unwrap();
+        }
+
+        let mut inflated = ReceiptProof::new(&receipts, 4).unwrap();
+        inflated.receipt.amount = 1_000;
+        assert_eq!(inflated.verify(&header), Err(CrossShardError::InvalidProof));
+        let mut moved = ReceiptProof::new(&receipts, 1).unwrap();
+        moved.index = 2;
+        assert_eq!(moved.verify(&header), Err(CrossShardError::InvalidProof));
+        let other_height = ShardHeader {
+            height: 8,
+            ..header.clone()
+        };
+        assert_eq!(
+            ReceiptProof::new(&receipts, 0)
+                .unwrap()
+                .verify(&other_height),
+            Err(CrossShardError::InvalidProof)
+        );
+
+        let mut target = ShardState::default();
+        let outcome = target.apply_block(1, 2, &block(1, vec![]), &receipts[..2]);
+        assert_eq!(outcome.credited.len(), 2);
+        let outcome = target.apply_block(1, 2, &block(2, vec![]), &receipts[..2]);
+        assert!(outcome.credited.is_empty());
+        assert_eq!(target.balance("user-0"), 10);
+        assert!(target.is_consumed(&receipts[1].id()));
+    }
+}
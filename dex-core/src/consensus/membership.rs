@@ -0,0 +1,119 @@
+//! Raft cluster membership and status reporting
+//!
+//! Membership is replicated through the log one server at a time (the
+//! single-server change from the Raft dissertation): any two consecutive
+//! configurations share a majority, so no joint phase is needed. A
+//! configuration takes effect as soon as it is appended, and a new change is
+//! only accepted once the previous one has committed. New servers join as
+//! non-voting learners and are promoted once they have caught up.
+//!
+//! Implements the Priority 3 feature from DEX-OS-V2.csv:
+//! - Infrastructure,Network,Network,Raft Consensus,Service Coordination,Medium
+
+use super::raft::NodeState;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Cluster configuration: voting members and non-voting learners
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Membership {
+    /// Voting members and their addresses
+    pub voters: BTreeMap<String, String>,
+    /// Learners and their addresses; they receive the log but do not vote
+    pub learners: BTreeMap<String, String>,
+}
+
+impl Membership {
+    /// Configuration in which every node in `node_addresses` votes. With no
+    /// addresses the node forms a single-node cluster on its own; a node that
+    /// is not listed waits to be added by the leader.
+    pub fn from_addresses(node_id: &str, node_addresses: &HashMap<String, String>) -> Self {
+        let mut voters: BTreeMap<String, String> = node_addresses
+            .iter()
+            .map(|(id, address)| (id.clone(), address.clone()))
+            .collect();
+        if voters.is_empty() {
+            voters.insert(node_id.to_string(), String::new());
+        }
+        Self {
+            voters,
+            learners: BTreeMap::new(),
+        }
+    }
+
+    /// Whether `node_id` votes
+    pub fn is_voter(&self, node_id: &str) -> bool {
+        self.voters.contains_key(node_id)
+    }
+
+    /// Whether `node_id` is a learner
+    pub fn is_learner(&self, node_id: &str) -> bool {
+        self.learners.contains_key(node_id)
+    }
+
+    /// Whether `node_id` is a voter or learner
+    pub fn contains(&self, node_id: &str) -> bool {
+        self.is_voter(node_id) || self.is_learner(node_id)
+    }
+
+    /// Address of a voter or learner
+    pub fn address(&self, node_id: &str) -> Option<&String> {
+        self.voters
+            .get(node_id)
+            .or_else(|| self.learners.get(node_id))
+    }
+
+    /// Every voter and learner
+    pub fn members(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.voters.iter().chain(self.learners.iter())
+    }
+
+    /// Votes needed for a majority of the voters
+    pub fn quorum(&self) -> usize {
+        self.voters.len() / 2 + 1
+    }
+}
+
+/// Replication state of one peer, as seen by this node
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeerStatus {
+    /// Peer node ID
+    pub node_id: String,
+    /// Peer address
+    pub address: String,
+    /// Whether the peer votes
+    pub voter: bool,
+    /// Highest log index known to be replicated on the peer (leader only)
+    pub match_index: Option<u64>,
+    /// Next log index to send to the peer (leader only)
+    pub next_index: Option<u64>,
+}
+
+/// Snapshot of a node's view of the cluster, for admin tooling
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClusterStatus {
+    /// Reporting node
+    pub node_id: String,
+    /// Role of the reporting node
+    pub state: NodeState,
+    /// Current term
+    pub term: u64,
+    /// Known leader of the current term
+    pub leader: Option<String>,
+    /// Index of the highest committed entry
+    pub commit_index: u64,
+    /// Index of the highest applied entry
+    pub last_applied: u64,
+    /// Index of the last log entry
+    pub last_log_index: u64,
+    /// Index covered by the latest snapshot
+    pub snapshot_index: u64,
+    /// Effective configuration
+    pub membership: Membership,
+    /// Whether the effective configuration has committed
+    pub membership_committed: bool,
+    /// Target of an ongoing leadership transfer
+    pub transfer_target: Option<String>,
+    /// Every other member of the cluster
+    pub peers: Vec<PeerStatus>,
+}
@@ -3,7 +3,35 @@
 //! This module implements consensus algorithms for service coordination.
 //! Implements the Priority 3 feature from DEX-OS-V2.csv:
 //! - Infrastructure,Network,Network,Raft Consensus,Service Coordination,Medium
+//!
+//! It also holds the Byzantine fault tolerant block agreement behind the
+//! quantum consensus engine, from DEX-OS-V1.csv:
+//! "Core Components,Quantum Consensus (QBFT),Consensus,Lattice BFT Core,BFT Core,High"
+//! and the cross-shard transfer protocol of its shards.
 
+pub mod bft;
+pub mod bft_sim;
+pub mod cross_shard;
+pub mod membership;
 pub mod raft;
+pub mod replicated_orderbook;
+pub mod state_machine;
+pub mod storage;
+pub mod transport;
 
-pub use raft::{RaftConfig, RaftError, RaftNode};
+pub use bft::{
+    BftConfig, BftError, BftMessage, BftNode, BftOutput, CommitCertificate, CommittedBlock,
+    ValidatorSet, Vote, VoteType,
+};
+pub use bft_sim::{Behavior, BftSimulation, SimulationConfig};
+pub use cross_shard::{
+    shard_of, BlockOutcome, CrossShardError, Receipt, ReceiptProof, ShardHeader, ShardState,
+};
+pub use membership::{ClusterStatus, Membership, PeerStatus};
+pub use raft::{RaftConfig, RaftError, RaftMessage, RaftNode};
+pub use replicated_orderbook::{
+    OrderBookCommand, OrderBookNode, OrderBookOutcome, ReplicatedOrderBook,
+};
+pub use state_machine::{KeyValueStore, StateMachine};
+pub use storage::{FileStorage, MemoryStorage, RaftStorage, Snapshot};
+pub use transport::{RaftTransport, SimulatedNetwork, SimulatedTransport, TcpTransport};
@@ -1,19 +1,39 @@
 //! Raft consensus implementation for service coordination
 //!
 //! This module implements the Raft consensus algorithm for coordinating services
-//! in the DEX-OS infrastructure.
+//! in the DEX-OS infrastructure. Nodes exchange RequestVote and AppendEntries
+//! RPCs as [`RaftMessage`]s over a pluggable [`RaftTransport`], so the same node
+//! runs over TCP in production and over an in-memory simulated network in tests.
+//! Term, vote and log are written to a [`RaftStorage`] before the node answers
+//! an RPC, and the log is compacted into state machine snapshots that lagging
+//! followers receive through InstallSnapshot.
+//! The node replicates any [`StateMachine`]: clients get the output of their
+//! command once it is committed and applied ([`RaftNode::propose`]), and
+//! linearizable reads are served through the read-index protocol
+//! ([`RaftNode::read_index`]) without writing to the log.
+//! Servers join and leave through single-server [`Membership`] changes
+//! replicated in the log, new servers catch up as learners before they vote,
+//! and leadership can be handed to a chosen node for rolling upgrades.
+//!
 //! Implements the Priority 3 feature from DEX-OS-V2.csv:
 //! - Infrastructure,Network,Network,Raft Consensus,Service Coordination,Medium
 
+use super::membership::{ClusterStatus, Membership, PeerStatus};
+use super::state_machine::{KeyValueStore, StateMachine};
+use super::storage::{HardState, MemoryStorage, RaftStorage, Snapshot};
+use super::transport::RaftTransport;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
-use tokio::sync::RwLock;
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::sync::oneshot;
 use tokio::time::sleep;
 
 /// Raft node states
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum NodeState {
     Follower,
     Candidate,
@@ -33,6 +53,9 @@ pub struct RaftConfig {
     pub heartbeat_interval_ms: u64,
     /// Maximum number of log entries per RPC
     pub max_log_entries_per_rpc: usize,
+    /// Applied entries kept in the log before it is compacted into a snapshot
+    /// (0 disables automatic compaction)
+    pub snapshot_threshold: u64,
 }
 
 impl Default for RaftConfig {
@@ -43,22 +66,27 @@ impl Default for RaftConfig {
             election_timeout_ms: 1500,
             heartbeat_interval_ms: 500,
             max_log_entries_per_rpc: 100,
+            snapshot_threshold: 1000,
         }
     }
 }
 
 /// Raft log entry
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LogEntry {
+pub struct LogEntry<C = Command> {
     /// Log index
     pub index: u64,
     /// Term when entry was created
     pub term: u64,
     /// Command to execute
-    pub command: Command,
+    pub command: C,
+    /// New cluster configuration carried by the entry, which takes effect as
+    /// soon as it is appended
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub membership: Option<Membership>,
 }
 
-/// Command to be executed by the state machine
+/// Command to be executed by the default [`KeyValueStore`] state machine
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Command {
     /// No-op command
@@ -71,13 +99,13 @@ pub enum Command {
 
 /// Raft node persistent state
 #[derive(Debug, Clone)]
-pub struct PersistentState {
+pub struct PersistentState<C = Command> {
     /// Latest term server has seen
     pub current_term: u64,
     /// Candidate ID that received vote in current term (or None)
     pub voted_for: Option<String>,
     /// Log entries
-    pub log: Vec<LogEntry>,
+    pub log: Vec<LogEntry<C>>,
 }
 
 /// Raft node volatile state
@@ -93,29 +121,107 @@ pub struct VolatileState {
     pub match_index: HashMap<String, u64>,
 }
 
+/// Receives the output of a proposed command once it is applied, or an error
+/// if leadership was lost first
+pub type ProposalReceiver<S> = oneshot::Receiver<Result<<S as StateMachine>::Output, RaftError>>;
+
+/// Completes a [`ProposalReceiver`]
+type ProposalReply<S> = oneshot::Sender<Result<<S as StateMachine>::Output, RaftError>>;
+
+/// Receives the read index once leadership is confirmed and the state machine
+/// has applied it
+pub type ReadIndexReceiver = oneshot::Receiver<Result<u64, RaftError>>;
+
+/// Read waiting for leadership confirmation and application of its index
+struct PendingRead {
+    /// Commit index at the time of the request
+    read_index: u64,
+    /// Heartbeat round that must be acknowledged by a majority
+    seq: u64,
+    /// Completion channel
+    reply: oneshot::Sender<Result<u64, RaftError>>,
+}
+
+/// Leadership hand-off in progress
+struct LeadershipTransfer {
+    /// Node that should take over
+    target: String,
+    /// Time after which the transfer is abandoned
+    deadline: u64,
+    /// Whether TimeoutNow has been sent to the target
+    timeout_sent: bool,
+}
+
 /// Raft node implementation
-pub struct RaftNode {
+pub struct RaftNode<S: StateMachine = KeyValueStore> {
     /// Node configuration
     config: RaftConfig,
     /// Node state
     state: NodeState,
     /// Persistent state
-    persistent_state: PersistentState,
+    persistent_state: PersistentState<S::Command>,
     /// Volatile state
     volatile_state: VolatileState,
     /// Last time election timeout was reset
     last_heartbeat: u64,
     /// State machine
-    state_machine: HashMap<String, String>,
+    state_machine: S,
     /// Election timeout duration
     election_timeout: Duration,
     /// Heartbeat interval
     heartbeat_interval: Duration,
+    /// Election timeout for the current term, randomized to avoid split votes
+    randomized_election_timeout: u64,
+    /// Last time the leader sent AppendEntries to its followers
+    last_heartbeat_sent: u64,
+    /// Leader of the current term, if known
+    leader_id: Option<String>,
+    /// Peers that granted their vote in the current election
+    votes_received: HashSet<String>,
+    /// Transport used to reach peers
+    transport: Option<Arc<dyn RaftTransport<S::Command>>>,
+    /// Incoming messages from peers
+    inbox: Option<UnboundedReceiver<RaftMessage<S::Command>>>,
+    /// Durable storage for term, vote, log and snapshots
+    storage: Box<dyn RaftStorage<S::Command>>,
+    /// Latest snapshot, sent to followers whose next entry was compacted away
+    snapshot: Option<Snapshot>,
+    /// Clients waiting for their entry to be applied, by log index
+    pending_proposals: HashMap<u64, (u64, ProposalReply<S>)>,
+    /// Reads waiting on the read-index protocol
+    pending_reads: Vec<PendingRead>,
+    /// Heartbeat round counter, echoed by followers to confirm leadership
+    heartbeat_seq: u64,
+    /// Highest heartbeat round acknowledged by each peer in the current term
+    acked_seq: HashMap<String, u64>,
+    /// Index of the no-op appended when this node became leader
+    term_start_index: u64,
+    /// Configuration derived from `RaftConfig::node_addresses`, used until the
+    /// log or a snapshot carries one
+    initial_membership: Membership,
+    /// Configuration in effect: the latest one in the log
+    membership: Membership,
+    /// Index of the entry carrying the configuration in effect
+    membership_index: u64,
+    /// Leadership transfer started on this leader
+    transfer: Option<LeadershipTransfer>,
 }
 
-impl RaftNode {
-    /// Create a new Raft node
+impl RaftNode<KeyValueStore> {
+    /// Create a new Raft node replicating a [`KeyValueStore`]
     pub fn new(config: RaftConfig) -> Self {
+        Self::with_state_machine(config, KeyValueStore::new())
+    }
+
+    /// Get value from state machine
+    pub fn get_value(&self, key: &str) -> Option<&String> {
+        self.state_machine.get(key)
+    }
+}
+
+impl<S: StateMachine> RaftNode<S> {
+    /// Create a new Raft node replicating `state_machine`
+    pub fn with_state_machine(config: RaftConfig, state_machine: S) -> Self {
         let election_timeout = Duration::from_millis(config.election_timeout_ms);
         let heartbeat_interval = Duration::from_millis(config.heartbeat_interval_ms);
 
@@ -130,7 +236,8 @@ impl RaftNode {
             }
         }
 
-        Self {
+        let membership = Membership::from_addresses(&config.node_id, &config.node_addresses);
+        let mut node = Self {
             config,
             state: NodeState::Follower,
             persistent_state: PersistentState {
@@ -139,7 +246,8 @@ impl RaftNode {
                 log: vec![LogEntry {
                     index: 0,
                     term: 0,
-                    command: Command::NoOp,
+                    command: S::no_op(),
+                    membership: None,
                 }],
             },
             volatile_state: VolatileState {
@@ -149,10 +257,29 @@ impl RaftNode {
                 match_index,
             },
             last_heartbeat: Self::current_time(),
-            state_machine: HashMap::new(),
+            state_machine,
             election_timeout,
             heartbeat_interval,
-        }
+            randomized_election_timeout: 0,
+            last_heartbeat_sent: 0,
+            leader_id: None,
+            votes_received: HashSet::new(),
+            transport: None,
+            inbox: None,
+            storage: Box::new(MemoryStorage::new()),
+            snapshot: None,
+            pending_proposals: HashMap::new(),
+            pending_reads: Vec::new(),
+            heartbeat_seq: 0,
+            acked_seq: HashMap::new(),
+            term_start_index: 0,
+            initial_membership: membership.clone(),
+            membership,
+            membership_index: 0,
+            transfer: None,
+        };
+        node.reset_election_timer();
+        node
     }
 
     /// Get current time in milliseconds since UNIX epoch
@@ -163,58 +290,179 @@ impl RaftNode {
             .as_millis() as u64
     }
 
-    /// Start the Raft node
+    /// Exchange RPCs over `transport` and read incoming messages from `inbox`
+    pub fn with_transport(
+        mut self,
+        transport: Arc<dyn RaftTransport<S::Command>>,
+        inbox: UnboundedReceiver<RaftMessage<S::Command>>,
+    ) -> Self {
+        self.transport = Some(transport);
+        self.inbox = Some(inbox);
+        self.connect_members();
+        self
+    }
+
+    /// Recover term, vote, snapshot and log from `storage` and persist all
+    /// further changes to it
+    pub fn with_storage<T: RaftStorage<S::Command> + 'static>(
+        mut self,
+        mut storage: T,
+    ) -> Result<Self, RaftError> {
+        let stored = storage.load()?;
+        let (base_index, base_term) = stored.snapshot.as_ref().map_or((0, 0), |snapshot| {
+            (snapshot.last_included_index, snapshot.last_included_term)
+        });
+        if let Some(snapshot) = &stored.snapshot {
+            self.state_machine.restore(&snapshot.data)?;
+        }
+
+        self.persistent_state.current_term = stored.hard_state.current_term;
+        self.persistent_state.voted_for = stored.hard_state.voted_for;
+        self.persistent_state.log = vec![LogEntry {
+            index: base_index,
+            term: base_term,
+            command: S::no_op(),
+            membership: None,
+        }];
+        self.persistent_state.log.extend(stored.entries);
+        self.volatile_state.commit_index = base_index;
+        self.volatile_state.last_applied = base_index;
+        self.snapshot = stored.snapshot;
+        self.storage = Box::new(storage);
+        self.refresh_membership();
+        Ok(self)
+    }
+
+    /// Start the Raft node, processing messages and timers until the task is dropped
     pub async fn start(&mut self) {
+        let tick = Duration::from_millis((self.config.heartbeat_interval_ms / 2).max(1));
         loop {
-            match self.state {
-                NodeState::Follower => self.run_follower().await,
-                NodeState::Candidate => self.run_candidate().await,
-                NodeState::Leader => self.run_leader().await,
+            self.tick();
+            let message = match self.inbox.as_mut() {
+                Some(inbox) => tokio::time::timeout(tick, inbox.recv())
+                    .await
+                    .ok()
+                    .flatten(),
+                None => {
+                    sleep(tick).await;
+                    None
+                }
+            };
+            if let Some(message) = message {
+                self.handle_message(message);
             }
         }
     }
 
-    /// Run follower logic
-    async fn run_follower(&mut self) {
-        let timeout = self.election_timeout.as_millis() as u64;
-        let elapsed = Self::current_time() - self.last_heartbeat;
+    /// Drain every queued message and then run the timers once. Lets callers
+    /// drive a node from their own loop instead of [`RaftNode::start`].
+    pub fn poll(&mut self) {
+        while let Some(message) = self.inbox.as_mut().and_then(|inbox| inbox.try_recv().ok()) {
+            self.handle_message(message);
+        }
+        self.tick();
+    }
 
-        if elapsed > timeout {
-            // Election timeout, become candidate
-            self.become_candidate();
-        } else {
-            // Sleep for a short duration and check again
-            sleep(Duration::from_millis(50)).await;
+    /// Run election and heartbeat timers
+    pub fn tick(&mut self) {
+        let now = Self::current_time();
+        match self.state {
+            NodeState::Leader => {
+                if self
+                    .transfer
+                    .as_ref()
+                    .is_some_and(|transfer| now > transfer.deadline)
+                {
+                    // The target did not take over within an election timeout
+                    self.transfer = None;
+                }
+                if now.saturating_sub(self.last_heartbeat_sent)
+                    >= self.heartbeat_interval.as_millis() as u64
+                {
+                    self.send_heartbeat();
+                }
+            }
+            NodeState::Follower | NodeState::Candidate => {
+                // Learners and removed servers never start elections
+                if self.membership.is_voter(&self.config.node_id)
+                    && now.saturating_sub(self.last_heartbeat) > self.randomized_election_timeout
+                {
+                    self.become_candidate();
+                    if self.state == NodeState::Candidate {
+                        self.start_election(false);
+                    }
+                }
+            }
         }
     }
 
-    /// Run candidate logic
-    async fn run_candidate(&mut self) {
-        // Start election
-        self.start_election().await;
+    /// IDs of every other voter and learner, in order
+    fn peers(&self) -> Vec<String> {
+        self.membership
+            .members()
+            .map(|(id, _)| id)
+            .filter(|id| *id != &self.config.node_id)
+            .cloned()
+            .collect()
+    }
 
-        // Wait for election results or timeout
-        let start_time = Self::current_time();
-        while Self::current_time() - start_time < self.election_timeout.as_millis() as u64 {
-            // Check if we've received enough votes
-            if self.check_election_result() {
-                return;
-            }
+    /// IDs of every other voter, in order
+    fn voting_peers(&self) -> Vec<String> {
+        self.membership
+            .voters
+            .keys()
+            .filter(|id| *id != &self.config.node_id)
+            .cloned()
+            .collect()
+    }
 
-            sleep(Duration::from_millis(50)).await;
-        }
+    /// Number of voters forming a majority of the cluster
+    fn quorum(&self) -> usize {
+        self.membership.quorum()
+    }
 
-        // Election timeout, start new election
-        self.become_candidate();
+    /// Whether this node counts towards its own quorums
+    fn is_voter(&self) -> bool {
+        self.membership.is_voter(&self.config.node_id)
     }
 
-    /// Run leader logic
-    async fn run_leader(&mut self) {
-        // Send heartbeat to all followers
-        self.send_heartbeat().await;
+    /// Send a message through the transport, if one is attached
+    fn send(&self, to: &str, message: RaftMessage<S::Command>) {
+        if let Some(transport) = &self.transport {
+            // Delivery is best effort; lost RPCs are retried by the timers
+            let _ = transport.send(to, message);
+        }
+    }
 
-        // Sleep for heartbeat interval
-        sleep(self.heartbeat_interval).await;
+    /// Reset the election timer with a fresh randomized timeout
+    fn reset_election_timer(&mut self) {
+        let base = self.election_timeout.as_millis() as u64;
+        self.randomized_election_timeout = base + rand::thread_rng().gen_range(0..=base);
+        self.last_heartbeat = Self::current_time();
+    }
+
+    /// Step down to follower after observing a newer term
+    fn step_down(&mut self, term: u64) {
+        if term > self.persistent_state.current_term {
+            self.persistent_state.current_term = term;
+            self.persistent_state.voted_for = None;
+            self.leader_id = None;
+            if let Err(e) = self.persist_hard_state() {
+                crate::redacted_eprintln!(
+                    "Node {} failed to persist term {}: {}",
+                    self.config.node_id,
+                    term,
+                    e
+                );
+            }
+        }
+        if self.state == NodeState::Leader {
+            self.leader_id = None;
+            self.transfer = None;
+            self.fail_pending_requests();
+        }
+        self.state = NodeState::Follower;
+        self.votes_received.clear();
     }
 
     /// Become a candidate and start election
@@ -222,6 +470,22 @@ impl RaftNode {
         self.state = NodeState::Candidate;
         self.persistent_state.current_term += 1;
         self.persistent_state.voted_for = Some(self.config.node_id.clone());
+        self.leader_id = None;
+        self.votes_received.clear();
+        self.votes_received.insert(self.config.node_id.clone());
+        self.reset_election_timer();
+
+        // Never solicit votes for a term or self-vote that could be forgotten
+        if let Err(e) = self.persist_hard_state() {
+            crate::redacted_eprintln!(
+                "Node {} failed to persist candidacy: {}",
+                self.config.node_id,
+                e
+            );
+            self.state = NodeState::Follower;
+            self.votes_received.clear();
+            return;
+        }
 
         println!(
             "Node {} became candidate for term {}",
@@ -229,30 +493,537 @@ impl RaftNode {
         );
     }
 
-    /// Start election process
-    async fn start_election(&self) {
+    /// Send RequestVote RPCs to every voter. `leadership_transfer` marks an
+    /// election requested by the leader, which followers must not ignore.
+    fn start_election(&mut self, leadership_transfer: bool) {
+        if self.check_election_result() {
+            self.become_leader();
+            return;
+        }
+
+        let last = self.last_log_entry();
+        let request = RequestVoteRequest {
+            term: self.persistent_state.current_term,
+            candidate_id: self.config.node_id.clone(),
+            last_log_index: last.0,
+            last_log_term: last.1,
+            leadership_transfer,
+        };
+        for peer in self.voting_peers() {
+            self.send(
+                &peer,
+                RaftMessage::RequestVote {
+                    from: self.config.node_id.clone(),
+                    request: request.clone(),
+                },
+            );
+        }
+    }
+
+    /// Check if election was successful
+    fn check_election_result(&self) -> bool {
+        let votes = self
+            .votes_received
+            .iter()
+            .filter(|id| self.membership.is_voter(id))
+            .count();
+        self.state == NodeState::Candidate && votes >= self.quorum()
+    }
+
+    /// Take leadership: reset replication progress and append a no-op so that
+    /// entries from earlier terms can be committed
+    fn become_leader(&mut self) {
+        self.state = NodeState::Leader;
+        self.leader_id = Some(self.config.node_id.clone());
+        self.votes_received.clear();
+
+        let next = self.last_log_entry().0 + 1;
+        for peer in self.peers() {
+            self.volatile_state.next_index.insert(peer.clone(), next);
+            self.volatile_state.match_index.insert(peer, 0);
+        }
+
         println!(
-            "Node {} starting election for term {}",
+            "Node {} became leader for term {}",
             self.config.node_id, self.persistent_state.current_term
         );
 
-        // In a real implementation, we would send RequestVote RPCs to other nodes
-        // This is a simplified implementation for demonstration
+        self.term_start_index = self.last_log_entry().0 + 1;
+        self.acked_seq.clear();
+        if let Err(e) = self.append_to_log(S::no_op()) {
+            crate::redacted_eprintln!(
+                "Node {} failed to persist leader no-op: {}",
+                self.config.node_id,
+                e
+            );
+            let term = self.persistent_state.current_term;
+            self.step_down(term);
+            return;
+        }
+        self.advance_commit_index();
+        self.send_heartbeat();
     }
 
-    /// Check if election was successful
-    fn check_election_result(&self) -> bool {
-        // In a real implementation, we would check if we received votes from
-        // majority of nodes
-        // This is a simplified implementation for demonstration
-        false
+    /// Send AppendEntries RPCs (with any pending entries) to all followers
+    fn send_heartbeat(&mut self) {
+        self.last_heartbeat_sent = Self::current_time();
+        self.heartbeat_seq += 1;
+        for peer in self.peers() {
+            self.replicate_to(&peer);
+        }
+    }
+
+    /// Send one AppendEntries RPC to `peer`, starting at its next index, or the
+    /// latest snapshot if that entry has been compacted away
+    fn replicate_to(&self, peer: &str) {
+        let last_index = self.last_log_entry().0;
+        let next = self
+            .volatile_state
+            .next_index
+            .get(peer)
+            .copied()
+            .unwrap_or(last_index + 1)
+            .clamp(1, last_index + 1);
+
+        if next <= self.first_index() {
+            if let Some(snapshot) = &self.snapshot {
+                self.send(
+                    peer,
+                    RaftMessage::InstallSnapshot {
+                        from: self.config.node_id.clone(),
+                        request: InstallSnapshotRequest {
+                            term: self.persistent_state.current_term,
+                            leader_id: self.config.node_id.clone(),
+                            snapshot: snapshot.clone(),
+                        },
+                    },
+                );
+                return;
+            }
+        }
+
+        let prev_log_index = next - 1;
+        let prev_log_term = self.term_at(prev_log_index).unwrap_or(0);
+        let start = self.position(next);
+        let end =
+            (start + self.config.max_log_entries_per_rpc).min(self.persistent_state.log.len());
+        let entries = self.persistent_state.log[start..end].to_vec();
+
+        self.send(
+            peer,
+            RaftMessage::AppendEntries {
+                from: self.config.node_id.clone(),
+                request: AppendEntriesRequest {
+                    term: self.persistent_state.current_term,
+                    leader_id: self.config.node_id.clone(),
+                    prev_log_index,
+                    prev_log_term,
+                    entries,
+                    leader_commit: self.volatile_state.commit_index,
+                },
+                seq: self.heartbeat_seq,
+            },
+        );
+    }
+
+    /// Commit the highest index replicated on a majority in the current term
+    fn advance_commit_index(&mut self) {
+        let mut matched: Vec<u64> = self
+            .voting_peers()
+            .iter()
+            .map(|peer| {
+                self.volatile_state
+                    .match_index
+                    .get(peer)
+                    .copied()
+                    .unwrap_or(0)
+            })
+            .collect();
+        if self.is_voter() {
+            matched.push(self.last_log_entry().0);
+        }
+        matched.sort_unstable_by(|a, b| b.cmp(a));
+
+        let candidate = matched.get(self.quorum() - 1).copied().unwrap_or(0);
+        if candidate > self.volatile_state.commit_index
+            && self.term_at(candidate) == Some(self.persistent_state.current_term)
+        {
+            self.volatile_state.commit_index = candidate;
+            self.apply_log_entries();
+        }
+
+        // A leader that removed itself hands over once the removal commits
+        if self.state == NodeState::Leader
+            && !self.is_voter()
+            && self.membership_index <= self.volatile_state.commit_index
+        {
+            self.send_heartbeat();
+            let term = self.persistent_state.current_term;
+            self.step_down(term);
+        }
+    }
+
+    /// Index and term of the last log entry
+    fn last_log_entry(&self) -> (u64, u64) {
+        self.persistent_state
+            .log
+            .last()
+            .map(|entry| (entry.index, entry.term))
+            .unwrap_or((0, 0))
+    }
+
+    /// Index covered by the latest snapshot; the log's first slot holds a
+    /// placeholder entry carrying this index and its term
+    fn first_index(&self) -> u64 {
+        self.persistent_state.log[0].index
+    }
+
+    /// Position of `index` in the in-memory log
+    fn position(&self, index: u64) -> usize {
+        (index - self.first_index()) as usize
+    }
+
+    /// Term of the entry at `index`, if it is still in the log
+    fn term_at(&self, index: u64) -> Option<u64> {
+        if index < self.first_index() {
+            return None;
+        }
+        self.persistent_state
+            .log
+            .get(self.position(index))
+            .map(|entry| entry.term)
+    }
+
+    /// Append a command to the local log in the current term, durably
+    fn append_to_log(&mut self, command: S::Command) -> Result<u64, RaftError> {
+        self.append_entry(command, None)
+    }
+
+    /// Append an entry, optionally carrying a new configuration, durably
+    fn append_entry(
+        &mut self,
+        command: S::Command,
+        membership: Option<Membership>,
+    ) -> Result<u64, RaftError> {
+        let entry = LogEntry {
+            index: self.last_log_entry().0 + 1,
+            term: self.persistent_state.current_term,
+            command,
+            membership,
+        };
+        self.storage.append_entries(std::slice::from_ref(&entry))?;
+        let index = entry.index;
+        self.persistent_state.log.push(entry);
+        Ok(index)
+    }
+
+    /// Persist the current term and vote
+    fn persist_hard_state(&mut self) -> Result<(), RaftError> {
+        self.storage.save_hard_state(&HardState {
+            current_term: self.persistent_state.current_term,
+            voted_for: self.persistent_state.voted_for.clone(),
+        })
+    }
+
+    /// Rewrite the stored log from memory (entries after the snapshot placeholder)
+    fn persist_log(&mut self) -> Result<(), RaftError> {
+        self.storage.replace_log(&self.persistent_state.log[1..])
+    }
+
+    /// Discard in-memory log changes that did not reach storage
+    fn reload_log(&mut self) {
+        if let Ok(stored) = self.storage.load() {
+            self.persistent_state.log.truncate(1);
+            let first = self.first_index();
+            self.persistent_state.log.extend(
+                stored
+                    .entries
+                    .into_iter()
+                    .filter(|entry| entry.index > first),
+            );
+        }
+    }
+
+    /// Configuration in effect at `index` and the index of the entry carrying it
+    fn membership_at(&self, index: u64) -> (Membership, u64) {
+        let end = self.position(index.clamp(self.first_index(), self.last_log_entry().0));
+        self.persistent_state.log[1..=end]
+            .iter()
+            .rev()
+            .find_map(|entry| entry.membership.clone().map(|m| (m, entry.index)))
+            .or_else(|| {
+                self.snapshot
+                    .as_ref()
+                    .and_then(|snapshot| snapshot.membership.clone())
+                    .map(|m| (m, self.first_index()))
+            })
+            .unwrap_or_else(|| (self.initial_membership.clone(), 0))
+    }
+
+    /// Adopt the latest configuration in the log, which may be older than the
+    /// current one after conflicting entries were truncated
+    fn refresh_membership(&mut self) {
+        let (membership, index) = self.membership_at(self.last_log_entry().0);
+        self.membership = membership;
+        self.membership_index = index;
+        self.connect_members();
+
+        if self.state == NodeState::Leader {
+            let next = self.last_log_entry().0 + 1;
+            for peer in self.peers() {
+                if !self.volatile_state.next_index.contains_key(&peer) {
+                    self.volatile_state.next_index.insert(peer.clone(), next);
+                    self.volatile_state.match_index.insert(peer, 0);
+                }
+            }
+        }
+    }
+
+    /// Tell the transport about every member of the configuration
+    fn connect_members(&self) {
+        if let Some(transport) = &self.transport {
+            for (id, address) in self.membership.members() {
+                if id != &self.config.node_id {
+                    // Failures surface as unreachable peers, retried by the timers
+                    let _ = transport.add_peer(id, address);
+                }
+            }
+        }
     }
 
-    /// Send heartbeat to all followers
-    async fn send_heartbeat(&self) {
-        // In a real implementation, we would send AppendEntries RPCs to followers
-        // This is a simplified implementation for demonstration
-        println!("Leader {} sending heartbeat", self.config.node_id);
+    /// Fold every applied entry into a snapshot and drop it from the log
+    pub fn compact_log(&mut self) -> Result<(), RaftError> {
+        let through = self.volatile_state.last_applied;
+        if through <= self.first_index() {
+            return Ok(());
+        }
+        let snapshot = Snapshot {
+            last_included_index: through,
+            last_included_term: self.term_at(through).unwrap_or(0),
+            data: self.state_machine.snapshot()?,
+            membership: Some(self.membership_at(through).0),
+        };
+        let remaining = self.persistent_state.log[self.position(through) + 1..].to_vec();
+
+        // The snapshot must be durable before the entries it replaces are dropped
+        self.storage.save_snapshot(&snapshot)?;
+        self.storage.replace_log(&remaining)?;
+
+        self.persistent_state.log = vec![LogEntry {
+            index: snapshot.last_included_index,
+            term: snapshot.last_included_term,
+            command: S::no_op(),
+            membership: None,
+        }];
+        self.persistent_state.log.extend(remaining);
+        self.snapshot = Some(snapshot);
+        Ok(())
+    }
+
+    /// Compact once enough applied entries have accumulated
+    fn maybe_compact(&mut self) {
+        let threshold = self.config.snapshot_threshold;
+        if threshold > 0 && self.volatile_state.last_applied - self.first_index() >= threshold {
+            if let Err(e) = self.compact_log() {
+                crate::redacted_eprintln!(
+                    "Node {} failed to compact log: {}",
+                    self.config.node_id,
+                    e
+                );
+            }
+        }
+    }
+
+    /// Dispatch an incoming message, replying through the transport where needed
+    pub fn handle_message(&mut self, message: RaftMessage<S::Command>) {
+        match message {
+            RaftMessage::RequestVote { from, request } => {
+                // While a leader is known to be alive, ignore candidates that did
+                // not get the leader's consent, such as removed servers
+                if !request.leadership_transfer && self.hears_from_leader() {
+                    self.send(
+                        &from,
+                        RaftMessage::RequestVoteResponse {
+                            from: self.config.node_id.clone(),
+                            response: RequestVoteResponse {
+                                term: self.persistent_state.current_term,
+                                vote_granted: false,
+                            },
+                        },
+                    );
+                    return;
+                }
+                let response = self.handle_request_vote(
+                    request.term,
+                    request.candidate_id,
+                    request.last_log_index,
+                    request.last_log_term,
+                );
+                self.send(
+                    &from,
+                    RaftMessage::RequestVoteResponse {
+                        from: self.config.node_id.clone(),
+                        response,
+                    },
+                );
+            }
+            RaftMessage::RequestVoteResponse { from, response } => {
+                self.handle_request_vote_response(from, response);
+            }
+            RaftMessage::AppendEntries { from, request, seq } => {
+                let last_new_index = request.prev_log_index + request.entries.len() as u64;
+                let response = self.handle_append_entries(
+                    request.term,
+                    request.leader_id,
+                    request.prev_log_index,
+                    request.prev_log_term,
+                    request.entries,
+                    request.leader_commit,
+                );
+                let match_index = if response.success {
+                    last_new_index
+                } else {
+                    self.last_log_entry().0
+                };
+                self.send(
+                    &from,
+                    RaftMessage::AppendEntriesResponse {
+                        from: self.config.node_id.clone(),
+                        response,
+                        match_index,
+                        seq,
+                    },
+                );
+            }
+            RaftMessage::AppendEntriesResponse {
+                from,
+                response,
+                match_index,
+                seq,
+            } => {
+                self.handle_append_entries_response(from, response, match_index, seq);
+            }
+            RaftMessage::InstallSnapshot { from, request } => {
+                let response = self.handle_install_snapshot(request);
+                let match_index = self.volatile_state.commit_index;
+                self.send(
+                    &from,
+                    RaftMessage::InstallSnapshotResponse {
+                        from: self.config.node_id.clone(),
+                        response,
+                        match_index,
+                    },
+                );
+            }
+            RaftMessage::InstallSnapshotResponse {
+                from,
+                response,
+                match_index,
+            } => {
+                // A follower that installed the snapshot continues from its
+                // last included index exactly like a successful AppendEntries
+                let response = AppendEntriesResponse {
+                    term: response.term,
+                    success: true,
+                };
+                self.handle_append_entries_response(from, response, match_index, 0);
+            }
+            RaftMessage::TimeoutNow { term, .. } => {
+                if term == self.persistent_state.current_term
+                    && self.state == NodeState::Follower
+                    && self.is_voter()
+                {
+                    self.become_candidate();
+                    if self.state == NodeState::Candidate {
+                        self.start_election(true);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether this node has heard from a current leader within the minimum
+    /// election timeout
+    fn hears_from_leader(&self) -> bool {
+        match self.state {
+            NodeState::Leader => true,
+            NodeState::Candidate => false,
+            NodeState::Follower => {
+                self.leader_id.is_some()
+                    && Self::current_time().saturating_sub(self.last_heartbeat)
+                        < self.election_timeout.as_millis() as u64
+            }
+        }
+    }
+
+    /// Count a vote and take leadership once a majority has granted it
+    fn handle_request_vote_response(&mut self, from: String, response: RequestVoteResponse) {
+        if response.term > self.persistent_state.current_term {
+            self.step_down(response.term);
+            return;
+        }
+        if self.state != NodeState::Candidate
+            || response.term != self.persistent_state.current_term
+            || !response.vote_granted
+        {
+            return;
+        }
+        self.votes_received.insert(from);
+        if self.check_election_result() {
+            self.become_leader();
+        }
+    }
+
+    /// Update replication progress for a follower. On success `match_index` is the
+    /// last index the follower now shares with us; on failure it is the follower's
+    /// last log index, used to skip back past the conflict in one round trip.
+    fn handle_append_entries_response(
+        &mut self,
+        from: String,
+        response: AppendEntriesResponse,
+        match_index: u64,
+        seq: u64,
+    ) {
+        if response.term > self.persistent_state.current_term {
+            self.step_down(response.term);
+            return;
+        }
+        if self.state != NodeState::Leader || response.term != self.persistent_state.current_term {
+            return;
+        }
+
+        // Any reply in our term, successful or not, confirms our leadership up
+        // to the heartbeat round it answers
+        let acked = self.acked_seq.entry(from.clone()).or_insert(0);
+        *acked = (*acked).max(seq);
+        self.process_pending_reads();
+
+        if response.success {
+            let matched = self
+                .volatile_state
+                .match_index
+                .entry(from.clone())
+                .or_insert(0);
+            *matched = (*matched).max(match_index);
+            let next = *matched + 1;
+            self.volatile_state.next_index.insert(from.clone(), next);
+            self.advance_commit_index();
+            if self.state != NodeState::Leader {
+                return;
+            }
+            if next <= self.last_log_entry().0 {
+                self.replicate_to(&from);
+            }
+            self.maybe_send_timeout_now();
+        } else {
+            let next = self
+                .volatile_state
+                .next_index
+                .entry(from.clone())
+                .or_insert(1);
+            *next = (*next - 1).min(match_index + 1).max(1);
+            self.replicate_to(&from);
+        }
     }
 
     /// Apply log entries to state machine
@@ -260,60 +1031,333 @@ impl RaftNode {
         while self.volatile_state.commit_index > self.volatile_state.last_applied {
             self.volatile_state.last_applied += 1;
 
-            if let Some(entry) = self
-                .persistent_state
-                .log
-                .get(self.volatile_state.last_applied as usize)
-                .cloned()
-            {
-                self.apply_command(&entry.command);
+            let index = self.volatile_state.last_applied;
+            let position = self.position(index);
+            if let Some(entry) = self.persistent_state.log.get(position) {
+                let output = self.state_machine.apply(&entry.command);
+                if let Some((term, reply)) = self.pending_proposals.remove(&index) {
+                    // A different term means our entry was overwritten by another leader's
+                    let result = if term == entry.term {
+                        Ok(output)
+                    } else {
+                        Err(RaftError::NotLeader)
+                    };
+                    let _ = reply.send(result);
+                }
             }
         }
+        self.process_pending_reads();
+        self.maybe_compact();
     }
 
-    /// Apply command to state machine
-    fn apply_command(&mut self, command: &Command) {
-        match command {
-            Command::NoOp => {
-                // No operation
-            }
-            Command::Set { key, value } => {
-                self.state_machine.insert(key.clone(), value.clone());
-            }
-            Command::Delete { key } => {
-                self.state_machine.remove(key);
-            }
+    /// Submit a command to be replicated without waiting for its output
+    pub async fn submit_command(&mut self, command: S::Command) -> Result<(), RaftError> {
+        self.propose(command).map(|_| ())
+    }
+
+    /// Append a command to the leader's log. The receiver resolves with the
+    /// state machine output once the entry is committed and applied, which makes
+    /// the submission linearizable.
+    pub fn propose(&mut self, command: S::Command) -> Result<ProposalReceiver<S>, RaftError> {
+        if self.state != NodeState::Leader {
+            return Err(RaftError::NotLeader);
+        }
+        // Hold new entries back so the transfer target can catch up
+        if self.transfer.is_some() {
+            return Err(RaftError::LeadershipTransferInProgress);
         }
+
+        let index = self.append_to_log(command)?;
+        let (reply, receiver) = oneshot::channel();
+        self.pending_proposals
+            .insert(index, (self.persistent_state.current_term, reply));
+        self.advance_commit_index();
+        self.send_heartbeat();
+        Ok(receiver)
     }
 
-    /// Submit a command to be replicated
-    pub async fn submit_command(&mut self, command: Command) -> Result<(), RaftError> {
+    /// Start a linearizable read. The receiver resolves with a read index once a
+    /// majority has confirmed this node is still leader and the state machine has
+    /// applied that index; reading [`RaftNode::state_machine`] afterwards observes
+    /// every write committed before the request.
+    pub fn read_index(&mut self) -> Result<ReadIndexReceiver, RaftError> {
         if self.state != NodeState::Leader {
             return Err(RaftError::NotLeader);
         }
 
-        let entry = LogEntry {
-            index: self.persistent_state.log.len() as u64,
-            term: self.persistent_state.current_term,
-            command,
-        };
+        // Until its no-op commits, a new leader may not know the latest commit index
+        let read_index = self.volatile_state.commit_index.max(self.term_start_index);
+        self.send_heartbeat();
+        let (reply, receiver) = oneshot::channel();
+        self.pending_reads.push(PendingRead {
+            read_index,
+            seq: self.heartbeat_seq,
+            reply,
+        });
+        self.process_pending_reads();
+        Ok(receiver)
+    }
 
-        self.persistent_state.log.push(entry);
-        Ok(())
+    /// Complete reads whose heartbeat round reached a majority and whose index
+    /// has been applied
+    fn process_pending_reads(&mut self) {
+        if self.pending_reads.is_empty() || self.state != NodeState::Leader {
+            return;
+        }
+        let quorum = self.quorum();
+        let applied = self.volatile_state.last_applied;
+        let own_vote = usize::from(self.is_voter());
+        let membership = &self.membership;
+        let acked = &self.acked_seq;
+        let (ready, waiting): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending_reads)
+            .into_iter()
+            .partition(|read| {
+                let confirmations = own_vote
+                    + acked
+                        .iter()
+                        .filter(|(id, &seq)| membership.is_voter(id) && seq >= read.seq)
+                        .count();
+                confirmations >= quorum && applied >= read.read_index
+            });
+        self.pending_reads = waiting;
+        for read in ready {
+            let _ = read.reply.send(Ok(read.read_index));
+        }
     }
 
-    /// Get value from state machine
-    pub fn get_value(&self, key: &str) -> Option<&String> {
-        self.state_machine.get(key)
+    /// Fail every waiting client after losing leadership
+    fn fail_pending_requests(&mut self) {
+        for (_, (_, reply)) in self.pending_proposals.drain() {
+            let _ = reply.send(Err(RaftError::NotLeader));
+        }
+        for read in self.pending_reads.drain(..) {
+            let _ = read.reply.send(Err(RaftError::NotLeader));
+        }
+    }
+
+    /// Get the replicated state machine
+    pub fn state_machine(&self) -> &S {
+        &self.state_machine
+    }
+
+    /// Get the index of the highest log entry applied to the state machine
+    pub fn last_applied(&self) -> u64 {
+        self.volatile_state.last_applied
     }
 
     /// Get current leader
     pub fn get_leader(&self) -> Option<&String> {
+        self.leader_id.as_ref()
+    }
+
+    /// Get the node ID
+    pub fn node_id(&self) -> &str {
+        &self.config.node_id
+    }
+
+    /// Get the node state
+    pub fn state(&self) -> &NodeState {
+        &self.state
+    }
+
+    /// Get the latest term this node has seen
+    pub fn current_term(&self) -> u64 {
+        self.persistent_state.current_term
+    }
+
+    /// Get the index of the highest committed log entry
+    pub fn commit_index(&self) -> u64 {
+        self.volatile_state.commit_index
+    }
+
+    /// Get the index of the last log entry
+    pub fn last_log_index(&self) -> u64 {
+        self.last_log_entry().0
+    }
+
+    /// Get the configuration in effect
+    pub fn membership(&self) -> &Membership {
+        &self.membership
+    }
+
+    /// Add a non-voting learner that receives the log. Returns the index of the
+    /// configuration entry.
+    pub fn add_learner(&mut self, node_id: &str, address: &str) -> Result<u64, RaftError> {
+        if self.membership.contains(node_id) {
+            return Err(RaftError::InvalidMembershipChange(format!(
+                "{} is already a member",
+                node_id
+            )));
+        }
+        let mut membership = self.membership.clone();
+        membership
+            .learners
+            .insert(node_id.to_string(), address.to_string());
+        self.change_membership(membership)
+    }
+
+    /// Turn a learner into a voter once it has replicated every committed entry.
+    /// Returns the index of the configuration entry.
+    pub fn promote_learner(&mut self, node_id: &str) -> Result<u64, RaftError> {
+        let address = self
+            .membership
+            .learners
+            .get(node_id)
+            .cloned()
+            .ok_or_else(|| RaftError::UnknownMember(node_id.to_string()))?;
+        // A voter that lags behind would slow down or block commits
+        let matched = self
+            .volatile_state
+            .match_index
+            .get(node_id)
+            .copied()
+            .unwrap_or(0);
+        if self.state == NodeState::Leader && matched < self.volatile_state.commit_index {
+            return Err(RaftError::LearnerNotCaughtUp(node_id.to_string()));
+        }
+        let mut membership = self.membership.clone();
+        membership.learners.remove(node_id);
+        membership.voters.insert(node_id.to_string(), address);
+        self.change_membership(membership)
+    }
+
+    /// Remove a voter or learner. A leader that removes itself keeps leading
+    /// until the change commits and then steps down. Returns the index of the
+    /// configuration entry.
+    pub fn remove_member(&mut self, node_id: &str) -> Result<u64, RaftError> {
+        if !self.membership.contains(node_id) {
+            return Err(RaftError::UnknownMember(node_id.to_string()));
+        }
+        if self.membership.voters.len() == 1 && self.membership.is_voter(node_id) {
+            return Err(RaftError::InvalidMembershipChange(
+                "cannot remove the last voter".to_string(),
+            ));
+        }
+        let mut membership = self.membership.clone();
+        membership.voters.remove(node_id);
+        membership.learners.remove(node_id);
+        self.change_membership(membership)
+    }
+
+    /// Append a configuration that differs from the current one by one server
+    fn change_membership(&mut self, membership: Membership) -> Result<u64, RaftError> {
+        if self.state != NodeState::Leader {
+            return Err(RaftError::NotLeader);
+        }
+        if self.transfer.is_some() {
+            return Err(RaftError::LeadershipTransferInProgress);
+        }
+        // One change at a time, and only once this term's no-op has committed so
+        // that no change from an earlier leader can still be pending
+        if self.membership_index > self.volatile_state.commit_index
+            || self.term_start_index > self.volatile_state.commit_index
+        {
+            return Err(RaftError::MembershipChangeInProgress);
+        }
+
+        let index = self.append_entry(S::no_op(), Some(membership))?;
+        self.refresh_membership();
+        self.advance_commit_index();
         if self.state == NodeState::Leader {
-            Some(&self.config.node_id)
-        } else {
-            // In a real implementation, we would track the current leader
-            None
+            self.send_heartbeat();
+        }
+        Ok(index)
+    }
+
+    /// Hand leadership to the voter `target`: new proposals are refused while
+    /// the target catches up, then it is told to start an election at once. The
+    /// transfer is abandoned if the target has not taken over within an
+    /// election timeout.
+    pub fn transfer_leadership(&mut self, target: &str) -> Result<(), RaftError> {
+        if self.state != NodeState::Leader {
+            return Err(RaftError::NotLeader);
+        }
+        if self.transfer.is_some() {
+            return Err(RaftError::LeadershipTransferInProgress);
+        }
+        if target == self.config.node_id {
+            return Ok(());
+        }
+        if !self.membership.is_voter(target) {
+            return Err(RaftError::UnknownMember(target.to_string()));
+        }
+
+        self.transfer = Some(LeadershipTransfer {
+            target: target.to_string(),
+            deadline: Self::current_time() + self.election_timeout.as_millis() as u64,
+            timeout_sent: false,
+        });
+        self.replicate_to(target);
+        self.maybe_send_timeout_now();
+        Ok(())
+    }
+
+    /// Send TimeoutNow once the transfer target holds the whole log
+    fn maybe_send_timeout_now(&mut self) {
+        let last_index = self.last_log_entry().0;
+        let Some(transfer) = self.transfer.as_mut() else {
+            return;
+        };
+        let matched = self
+            .volatile_state
+            .match_index
+            .get(&transfer.target)
+            .copied()
+            .unwrap_or(0);
+        if transfer.timeout_sent || matched < last_index {
+            return;
+        }
+        transfer.timeout_sent = true;
+        let target = transfer.target.clone();
+        self.send(
+            &target,
+            RaftMessage::TimeoutNow {
+                from: self.config.node_id.clone(),
+                term: self.persistent_state.current_term,
+            },
+        );
+    }
+
+    /// Report this node's view of the cluster. Match and next indices are only
+    /// known on the leader.
+    pub fn cluster_status(&self) -> ClusterStatus {
+        let leader = self.state == NodeState::Leader;
+        let peers = self
+            .peers()
+            .into_iter()
+            .map(|id| PeerStatus {
+                address: self.membership.address(&id).cloned().unwrap_or_default(),
+                voter: self.membership.is_voter(&id),
+                match_index: leader.then(|| {
+                    self.volatile_state
+                        .match_index
+                        .get(&id)
+                        .copied()
+                        .unwrap_or(0)
+                }),
+                next_index: leader
+                    .then(|| self.volatile_state.next_index.get(&id).copied())
+                    .flatten(),
+                node_id: id,
+            })
+            .collect();
+
+        ClusterStatus {
+            node_id: self.config.node_id.clone(),
+            state: self.state.clone(),
+            term: self.persistent_state.current_term,
+            leader: self.leader_id.clone(),
+            commit_index: self.volatile_state.commit_index,
+            last_applied: self.volatile_state.last_applied,
+            last_log_index: self.last_log_entry().0,
+            snapshot_index: self.first_index(),
+            membership: self.membership.clone(),
+            membership_committed: self.membership_index <= self.volatile_state.commit_index,
+            transfer_target: self
+                .transfer
+                .as_ref()
+                .map(|transfer| transfer.target.clone()),
+            peers,
         }
     }
 
@@ -335,14 +1379,9 @@ impl RaftNode {
 
         // If candidate's term is greater, update term and become follower
         if term > self.persistent_state.current_term {
-            self.persistent_state.current_term = term;
-            self.persistent_state.voted_for = None;
-            self.state = NodeState::Follower;
+            self.step_down(term);
         }
 
-        // Reset election timeout
-        self.last_heartbeat = Self::current_time();
-
         // Check if we've already voted in this term
         if let Some(voted_for) = &self.persistent_state.voted_for {
             if voted_for != &candidate_id {
@@ -360,6 +1399,17 @@ impl RaftNode {
 
         if up_to_date {
             self.persistent_state.voted_for = Some(candidate_id);
+            // The vote must be durable before it is granted
+            if self.persist_hard_state().is_err() {
+                self.persistent_state.voted_for = None;
+                return RequestVoteResponse {
+                    term: self.persistent_state.current_term,
+                    vote_granted: false,
+                };
+            }
+            // Reset election timeout only when granting, so stale candidates
+            // cannot hold off an election
+            self.reset_election_timer();
             RequestVoteResponse {
                 term: self.persistent_state.current_term,
                 vote_granted: true,
@@ -379,7 +1429,7 @@ impl RaftNode {
         leader_id: String,
         prev_log_index: u64,
         prev_log_term: u64,
-        entries: Vec<LogEntry>,
+        entries: Vec<LogEntry<S::Command>>,
         leader_commit: u64,
     ) -> AppendEntriesResponse {
         // If leader's term is less than current term, reject
@@ -390,58 +1440,98 @@ impl RaftNode {
             };
         }
 
-        // If leader's term is greater, update term and become follower
-        if term > self.persistent_state.current_term {
-            self.persistent_state.current_term = term;
-            self.persistent_state.voted_for = None;
-            self.state = NodeState::Follower;
+        // A valid leader for this term or a newer one: become its follower
+        if term > self.persistent_state.current_term || self.state != NodeState::Follower {
+            self.step_down(term);
         }
+        self.leader_id = Some(leader_id);
 
         // Reset election timeout
-        self.last_heartbeat = Self::current_time();
+        self.reset_election_timer();
+
+        // Entries up to our snapshot are committed and therefore match the
+        // leader's; only look at what follows it
+        let first_index = self.first_index();
+        let (prev_log_index, prev_log_term, entries) = if prev_log_index < first_index {
+            let first_term = self.persistent_state.log[0].term;
+            let entries = entries
+                .into_iter()
+                .filter(|entry| entry.index > first_index)
+                .collect::<Vec<_>>();
+            (first_index, first_term, entries)
+        } else {
+            (prev_log_index, prev_log_term, entries)
+        };
 
         // Check if log contains entry at prev_log_index with matching term
-        if prev_log_index >= self.persistent_state.log.len() as u64 {
-            return AppendEntriesResponse {
-                term: self.persistent_state.current_term,
-                success: false,
-            };
-        }
-
-        if self.persistent_state.log[prev_log_index as usize].term != prev_log_term {
-            // Delete conflicting entry and all that follow it
-            self.persistent_state.log.truncate(prev_log_index as usize);
-            return AppendEntriesResponse {
-                term: self.persistent_state.current_term,
-                success: false,
-            };
+        match self.term_at(prev_log_index) {
+            None => {
+                return AppendEntriesResponse {
+                    term: self.persistent_state.current_term,
+                    success: false,
+                };
+            }
+            Some(term) if term != prev_log_term => {
+                // Delete conflicting entry and all that follow it
+                let position = self.position(prev_log_index);
+                self.persistent_state.log.truncate(position);
+                if self.persist_log().is_err() {
+                    self.reload_log();
+                }
+                self.refresh_membership();
+                return AppendEntriesResponse {
+                    term: self.persistent_state.current_term,
+                    success: false,
+                };
+            }
+            Some(_) => {}
         }
 
         // Append any new entries not already in the log
+        let last_new_index = prev_log_index + entries.len() as u64;
+        let mut truncated = false;
+        let mut appended = Vec::new();
         for entry in entries {
-            if entry.index < self.persistent_state.log.len() as u64 {
-                // Entry already exists, check if it conflicts
-                if self.persistent_state.log[entry.index as usize].term != entry.term {
+            match self.term_at(entry.index) {
+                Some(term) if term == entry.term => {
+                    // Entry already present
+                }
+                Some(_) => {
                     // Conflict, delete existing entry and all that follow it
-                    self.persistent_state.log.truncate(entry.index as usize);
+                    let position = self.position(entry.index);
+                    self.persistent_state.log.truncate(position);
+                    self.persistent_state.log.push(entry);
+                    truncated = true;
+                }
+                None => {
+                    // Entry doesn't exist, append it
+                    appended.push(entry.clone());
                     self.persistent_state.log.push(entry);
                 }
-            } else {
-                // Entry doesn't exist, append it
-                self.persistent_state.log.push(entry);
             }
         }
 
+        // New entries must be durable before they are acknowledged
+        let persisted = if truncated {
+            self.persist_log()
+        } else {
+            self.storage.append_entries(&appended)
+        };
+        if persisted.is_err() {
+            self.reload_log();
+            self.refresh_membership();
+            return AppendEntriesResponse {
+                term: self.persistent_state.current_term,
+                success: false,
+            };
+        }
+        if truncated || appended.iter().any(|entry| entry.membership.is_some()) {
+            self.refresh_membership();
+        }
+
         // Update commit index
         if leader_commit > self.volatile_state.commit_index {
-            self.volatile_state.commit_index = std::cmp::min(
-                leader_commit,
-                self.persistent_state
-                    .log
-                    .last()
-                    .map(|e| e.index)
-                    .unwrap_or(0),
-            );
+            self.volatile_state.commit_index = std::cmp::min(leader_commit, last_new_index);
         }
 
         // Apply committed entries to state machine
@@ -452,6 +1542,171 @@ impl RaftNode {
             success: true,
         }
     }
+
+    /// Handle incoming InstallSnapshot RPC
+    pub fn handle_install_snapshot(
+        &mut self,
+        request: InstallSnapshotRequest,
+    ) -> InstallSnapshotResponse {
+        if request.term < self.persistent_state.current_term {
+            return InstallSnapshotResponse {
+                term: self.persistent_state.current_term,
+            };
+        }
+        if request.term > self.persistent_state.current_term || self.state != NodeState::Follower {
+            self.step_down(request.term);
+        }
+        self.leader_id = Some(request.leader_id);
+        self.reset_election_timer();
+
+        let snapshot = request.snapshot;
+        if snapshot.last_included_index <= self.volatile_state.commit_index {
+            // Already have everything the snapshot covers
+            return InstallSnapshotResponse {
+                term: self.persistent_state.current_term,
+            };
+        }
+
+        // Keep entries following the snapshot if our log agrees with it there
+        let retained = if self.term_at(snapshot.last_included_index)
+            == Some(snapshot.last_included_term)
+        {
+            self.persistent_state.log[self.position(snapshot.last_included_index) + 1..].to_vec()
+        } else {
+            Vec::new()
+        };
+
+        let previous_state = match self.state_machine.snapshot() {
+            Ok(data) => data,
+            Err(_) => {
+                return InstallSnapshotResponse {
+                    term: self.persistent_state.current_term,
+                }
+            }
+        };
+        if self.state_machine.restore(&snapshot.data).is_err()
+            || self.storage.save_snapshot(&snapshot).is_err()
+            || self.storage.replace_log(&retained).is_err()
+        {
+            let _ = self.state_machine.restore(&previous_state);
+            return InstallSnapshotResponse {
+                term: self.persistent_state.current_term,
+            };
+        }
+
+        self.persistent_state.log = vec![LogEntry {
+            index: snapshot.last_included_index,
+            term: snapshot.last_included_term,
+            command: S::no_op(),
+            membership: None,
+        }];
+        self.persistent_state.log.extend(retained);
+        self.volatile_state.commit_index = snapshot.last_included_index;
+        self.volatile_state.last_applied = snapshot.last_included_index;
+        self.snapshot = Some(snapshot);
+        self.refresh_membership();
+
+        InstallSnapshotResponse {
+            term: self.persistent_state.current_term,
+        }
+    }
+}
+
+/// RequestVote RPC arguments
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestVoteRequest {
+    /// Candidate's term
+    pub term: u64,
+    /// Candidate requesting the vote
+    pub candidate_id: String,
+    /// Index of candidate's last log entry
+    pub last_log_index: u64,
+    /// Term of candidate's last log entry
+    pub last_log_term: u64,
+    /// Election requested by the leader through TimeoutNow
+    #[serde(default)]
+    pub leadership_transfer: bool,
+}
+
+/// AppendEntries RPC arguments, also used as heartbeat
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppendEntriesRequest<C = Command> {
+    /// Leader's term
+    pub term: u64,
+    /// Leader ID so followers can redirect clients
+    pub leader_id: String,
+    /// Index of the log entry immediately preceding the new ones
+    pub prev_log_index: u64,
+    /// Term of the entry at prev_log_index
+    pub prev_log_term: u64,
+    /// Entries to store (empty for heartbeat)
+    pub entries: Vec<LogEntry<C>>,
+    /// Leader's commit index
+    pub leader_commit: u64,
+}
+
+/// InstallSnapshot RPC arguments, sent to followers that are behind the
+/// leader's compacted log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstallSnapshotRequest {
+    /// Leader's term
+    pub term: u64,
+    /// Leader ID so followers can redirect clients
+    pub leader_id: String,
+    /// Snapshot replacing the follower's state up to its last included index
+    pub snapshot: Snapshot,
+}
+
+/// Response to InstallSnapshot RPC
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstallSnapshotResponse {
+    /// Current term for leader to update itself
+    pub term: u64,
+}
+
+/// Message exchanged between Raft nodes over a [`RaftTransport`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RaftMessage<C = Command> {
+    /// RequestVote RPC from a candidate
+    RequestVote {
+        from: String,
+        request: RequestVoteRequest,
+    },
+    /// Reply to RequestVote
+    RequestVoteResponse {
+        from: String,
+        response: RequestVoteResponse,
+    },
+    /// AppendEntries RPC from a leader
+    AppendEntries {
+        from: String,
+        request: AppendEntriesRequest<C>,
+        /// Leader's heartbeat round, echoed in the response
+        seq: u64,
+    },
+    /// Reply to AppendEntries. `match_index` is the last replicated index on
+    /// success and the follower's last log index on failure.
+    AppendEntriesResponse {
+        from: String,
+        response: AppendEntriesResponse,
+        match_index: u64,
+        seq: u64,
+    },
+    /// InstallSnapshot RPC from a leader
+    InstallSnapshot {
+        from: String,
+        request: InstallSnapshotRequest,
+    },
+    /// Reply to InstallSnapshot. `match_index` is the follower's commit index
+    /// after handling the snapshot.
+    InstallSnapshotResponse {
+        from: String,
+        response: InstallSnapshotResponse,
+        match_index: u64,
+    },
+    /// Leader asking the target of a leadership transfer to start an election
+    /// immediately
+    TimeoutNow { from: String, term: u64 },
 }
 
 /// Response to RequestVote RPC
@@ -481,11 +1736,25 @@ pub enum RaftError {
     NetworkError(String),
     #[error("Timeout error")]
     Timeout,
+    #[error("Storage error: {0}")]
+    StorageError(String),
+    #[error("A membership change is already in progress")]
+    MembershipChangeInProgress,
+    #[error("Invalid membership change: {0}")]
+    InvalidMembershipChange(String),
+    #[error("Unknown cluster member: {0}")]
+    UnknownMember(String),
+    #[error("Learner {0} has not caught up with the leader")]
+    LearnerNotCaughtUp(String),
+    #[error("Leadership transfer in progress")]
+    LeadershipTransferInProgress,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::consensus::storage::FileStorage;
+    use crate::consensus::transport::{SimulatedNetwork, TcpTransport};
 
     #[test]
     fn test_raft_node_creation() {
@@ -533,6 +1802,7 @@ mod tests {
             index: 1,
             term: 1,
             command: Command::NoOp,
+            membership: None,
         }];
 
         let response = node.handle_append_entries(1, "leader-1".to_string(), 0, 0, entries, 0);
@@ -541,4 +1811,562 @@ mod tests {
         assert!(response.success);
         assert_eq!(node.persistent_state.log.len(), 2);
     }
+
+    fn cluster_config(node_id: &str, addresses: &HashMap<String, String>) -> RaftConfig {
+        RaftConfig {
+            node_id: node_id.to_string(),
+            node_addresses: addresses.clone(),
+            election_timeout_ms: 150,
+            heartbeat_interval_ms: 30,
+            max_log_entries_per_rpc: 100,
+            snapshot_threshold: 1000,
+        }
+    }
+
+    fn simulated_cluster(size: usize) -> (SimulatedNetwork, Vec<RaftNode>) {
+        let network = SimulatedNetwork::new();
+        let addresses: HashMap<String, String> = (0..size)
+            .map(|i| (format!("node-{}", i), format!("sim://node-{}", i)))
+            .collect();
+        let nodes = (0..size)
+            .map(|i| {
+                let id = format!("node-{}", i);
+                let (transport, inbox) = network.register(&id);
+                RaftNode::new(cluster_config(&id, &addresses)).with_transport(transport, inbox)
+            })
+            .collect();
+        (network, nodes)
+    }
+
+    /// Poll every node until `done` holds or the deadline passes
+    async fn run_until(nodes: &mut [RaftNode], done: impl Fn(&[RaftNode]) -> bool) -> bool {
+        for _ in 0..1000 {
+            for node in nodes.iter_mut() {
+                node.poll();
+            }
+            if done(nodes) {
+                return true;
+            }
+            sleep(Duration::from_millis(5)).await;
+        }
+        false
+    }
+
+    fn leaders(nodes: &[RaftNode]) -> Vec<usize> {
+        (0..nodes.len())
+            .filter(|&i| nodes[i].state == NodeState::Leader)
+            .collect()
+    }
+
+    #[test]
+    fn test_single_node_elects_itself() {
+        let mut node = RaftNode::new(RaftConfig::default());
+        node.become_candidate();
+        node.start_election(false);
+
+        assert_eq!(node.state, NodeState::Leader);
+        assert_eq!(node.get_leader(), Some(&"node-0".to_string()));
+        // The leader's no-op commits immediately without peers
+        assert_eq!(node.commit_index(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_simulated_cluster_elects_leader_and_replicates() {
+        let (_network, mut nodes) = simulated_cluster(3);
+
+        assert!(run_until(&mut nodes, |n| leaders(n).len() == 1).await);
+        let leader = leaders(&nodes)[0];
+        nodes[leader]
+            .submit_command(Command::Set {
+                key: "pair".to_string(),
+                value: "ETH/USDC".to_string(),
+            })
+            .await
+            .unwrap();
+
+        let replicated = run_until(&mut nodes, |n| {
+            n.iter()
+                .all(|node| node.get_value("pair") == Some(&"ETH/USDC".to_string()))
+        })
+        .await;
+        assert!(replicated);
+
+        let leader_id = nodes[leader].node_id().to_string();
+        for node in &nodes {
+            assert_eq!(node.get_leader(), Some(&leader_id));
+            assert_eq!(node.commit_index(), nodes[leader].commit_index());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_partitioned_leader_is_replaced_and_catches_up() {
+        let (network, mut nodes) = simulated_cluster(3);
+        assert!(run_until(&mut nodes, |n| leaders(n).len() == 1).await);
+        let old_leader = leaders(&nodes)[0];
+        let old_term = nodes[old_leader].current_term();
+
+        network.isolate(&nodes[old_leader].node_id().to_string());
+        // The isolated leader cannot commit without a majority
+        nodes[old_leader]
+            .submit_command(Command::Set {
+                key: "lost".to_string(),
+                value: "1".to_string(),
+            })
+            .await
+            .unwrap();
+
+        let new_leader_elected = run_until(&mut nodes, |n| {
+            (0..n.len()).any(|i| i != old_leader && n[i].state == NodeState::Leader)
+        })
+        .await;
+        assert!(new_leader_elected);
+        let new_leader = (0..nodes.len())
+            .find(|&i| i != old_leader && nodes[i].state == NodeState::Leader)
+            .unwrap();
+        assert!(nodes[new_leader].current_term() > old_term);
+
+        nodes[new_leader]
+            .submit_command(Command::Set {
+                key: "kept".to_string(),
+                value: "2".to_string(),
+            })
+            .await
+            .unwrap();
+
+        network.heal();
+        let converged = run_until(&mut nodes, |n| {
+            leaders(n).len() == 1
+                && n.iter()
+                    .all(|node| node.get_value("kept") == Some(&"2".to_string()))
+        })
+        .await;
+        assert!(converged);
+        // The uncommitted entry from the deposed leader was overwritten
+        assert!(nodes.iter().all(|node| node.get_value("lost").is_none()));
+        assert_ne!(nodes[old_leader].state, NodeState::Leader);
+    }
+
+    #[tokio::test]
+    async fn test_replication_over_lossy_delayed_network() {
+        let (network, mut nodes) = simulated_cluster(5);
+        network.set_drop_rate(0.2);
+        network.set_delay(Duration::from_millis(3));
+
+        assert!(run_until(&mut nodes, |n| leaders(n).len() == 1).await);
+        let leader = leaders(&nodes)[0];
+        for i in 0..5 {
+            nodes[leader]
+                .submit_command(Command::Set {
+                    key: format!("k{}", i),
+                    value: i.to_string(),
+                })
+                .await
+                .unwrap();
+        }
+
+        let replicated = run_until(&mut nodes, |n| {
+            n.iter()
+                .all(|node| node.get_value("k4") == Some(&"4".to_string()))
+        })
+        .await;
+        assert!(replicated);
+        assert!(network.dropped_messages() > 0);
+    }
+
+    #[tokio::test]
+    async fn test_tcp_transport_cluster() {
+        let mut listeners = Vec::new();
+        let mut addresses = HashMap::new();
+        for i in 0..3 {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            addresses.insert(
+                format!("node-{}", i),
+                listener.local_addr().unwrap().to_string(),
+            );
+            listeners.push(listener);
+        }
+
+        let mut nodes: Vec<RaftNode> = listeners
+            .into_iter()
+            .enumerate()
+            .map(|(i, listener)| {
+                let id = format!("node-{}", i);
+                let (transport, inbox) = TcpTransport::from_listener(&id, listener, &addresses);
+                RaftNode::new(cluster_config(&id, &addresses)).with_transport(transport, inbox)
+            })
+            .collect();
+
+        assert!(run_until(&mut nodes, |n| leaders(n).len() == 1).await);
+        let leader = leaders(&nodes)[0];
+        nodes[leader]
+            .submit_command(Command::Delete {
+                key: "missing".to_string(),
+            })
+            .await
+            .unwrap();
+        nodes[leader]
+            .submit_command(Command::Set {
+                key: "venue".to_string(),
+                value: "dexos".to_string(),
+            })
+            .await
+            .unwrap();
+
+        let replicated = run_until(&mut nodes, |n| {
+            n.iter()
+                .all(|node| node.get_value("venue") == Some(&"dexos".to_string()))
+        })
+        .await;
+        assert!(replicated);
+    }
+
+    fn set(key: &str, value: &str) -> Command {
+        Command::Set {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_vote_and_log_survive_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut node = RaftNode::new(RaftConfig::default())
+            .with_storage(FileStorage::open(dir.path()).unwrap())
+            .unwrap();
+        assert!(
+            node.handle_request_vote(5, "node-1".to_string(), 0, 0)
+                .vote_granted
+        );
+        let entries = vec![LogEntry {
+            index: 1,
+            term: 5,
+            command: set("a", "1"),
+            membership: None,
+        }];
+        assert!(
+            node.handle_append_entries(5, "node-1".to_string(), 0, 0, entries, 0)
+                .success
+        );
+        drop(node);
+
+        let mut restarted = RaftNode::new(RaftConfig::default())
+            .with_storage(FileStorage::open(dir.path()).unwrap())
+            .unwrap();
+        assert_eq!(restarted.current_term(), 5);
+        assert_eq!(restarted.last_log_index(), 1);
+        // Having voted for node-1 in term 5, the node cannot vote for node-2
+        let response = restarted.handle_request_vote(5, "node-2".to_string(), 1, 5);
+        assert!(!response.vote_granted);
+    }
+
+    #[test]
+    fn test_compaction_keeps_state_across_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = RaftConfig {
+            snapshot_threshold: 3,
+            ..RaftConfig::default()
+        };
+        let mut node = RaftNode::new(config.clone())
+            .with_storage(FileStorage::open(dir.path()).unwrap())
+            .unwrap();
+        let entries: Vec<LogEntry> = (1..=5)
+            .map(|i| LogEntry {
+                index: i,
+                term: 1,
+                command: set(&format!("k{}", i), &i.to_string()),
+                membership: None,
+            })
+            .collect();
+        assert!(
+            node.handle_append_entries(1, "node-1".to_string(), 0, 0, entries, 4)
+                .success
+        );
+
+        // Four applied entries exceed the threshold and were folded into a snapshot
+        assert_eq!(node.first_index(), 4);
+        assert_eq!(node.persistent_state.log.len(), 2);
+        drop(node);
+
+        let restarted = RaftNode::new(config)
+            .with_storage(FileStorage::open(dir.path()).unwrap())
+            .unwrap();
+        assert_eq!(restarted.first_index(), 4);
+        assert_eq!(restarted.commit_index(), 4);
+        assert_eq!(restarted.last_log_index(), 5);
+        assert_eq!(restarted.get_value("k4"), Some(&"4".to_string()));
+        assert!(restarted.get_value("k5").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_lagging_follower_receives_snapshot() {
+        let (network, mut nodes) = simulated_cluster(3);
+        for node in nodes.iter_mut() {
+            node.config.snapshot_threshold = 5;
+        }
+        assert!(run_until(&mut nodes, |n| leaders(n).len() == 1).await);
+        let leader = leaders(&nodes)[0];
+        let lagging = (leader + 1) % nodes.len();
+        network.isolate(&nodes[lagging].node_id().to_string());
+
+        for i in 0..20 {
+            nodes[leader]
+                .submit_command(set(&format!("k{}", i), &i.to_string()))
+                .await
+                .unwrap();
+        }
+        let committed = run_until(&mut nodes, |n| n[leader].commit_index() >= 21).await;
+        assert!(committed);
+        assert!(nodes[leader].first_index() > 1);
+
+        network.heal();
+        let caught_up = run_until(&mut nodes, |n| {
+            n[lagging].get_value("k19") == Some(&"19".to_string())
+        })
+        .await;
+        assert!(caught_up);
+        assert!(nodes[lagging].first_index() > 0);
+        assert_eq!(nodes[lagging].get_value("k0"), Some(&"0".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_restarted_node_rejoins_cluster() {
+        let network = SimulatedNetwork::new();
+        let dirs: Vec<_> = (0..3).map(|_| tempfile::tempdir().unwrap()).collect();
+        let addresses: HashMap<String, String> = (0..3)
+            .map(|i| (format!("node-{}", i), format!("sim://node-{}", i)))
+            .collect();
+        let start_node = |i: usize| {
+            let id = format!("node-{}", i);
+            let (transport, inbox) = network.register(&id);
+            let mut config = cluster_config(&id, &addresses);
+            config.snapshot_threshold = 4;
+            RaftNode::new(config)
+                .with_transport(transport, inbox)
+                .with_storage(FileStorage::open(dirs[i].path()).unwrap())
+                .unwrap()
+        };
+        let mut nodes: Vec<RaftNode> = (0..3).map(start_node).collect();
+
+        assert!(run_until(&mut nodes, |n| leaders(n).len() == 1).await);
+        let leader = leaders(&nodes)[0];
+        for i in 0..6 {
+            nodes[leader]
+                .submit_command(set(&format!("before{}", i), "x"))
+                .await
+                .unwrap();
+        }
+        assert!(
+            run_until(&mut nodes, |n| n
+                .iter()
+                .all(|node| node.get_value("before5").is_some()))
+            .await
+        );
+
+        // Restart a follower from disk
+        let follower = (leader + 1) % 3;
+        let term_before = nodes[follower].current_term();
+        let log_before = nodes[follower].last_log_index();
+        nodes[follower] = start_node(follower);
+        assert_eq!(nodes[follower].current_term(), term_before);
+        assert_eq!(nodes[follower].last_log_index(), log_before);
+        // State up to the snapshot is restored; later entries re-apply once the
+        // leader reports its commit index
+        assert!(nodes[follower].get_value("before2").is_some());
+
+        let leader = leaders(&nodes)[0];
+        nodes[leader]
+            .submit_command(set("after", "y"))
+            .await
+            .unwrap();
+        let rejoined = run_until(&mut nodes, |n| {
+            n.iter().all(|node| {
+                node.get_value("after") == Some(&"y".to_string())
+                    && node.get_value("before5").is_some()
+            })
+        })
+        .await;
+        assert!(rejoined);
+    }
+
+    #[test]
+    fn test_proposal_output_and_read_index_on_single_node() {
+        let mut node = RaftNode::new(RaftConfig::default());
+        assert!(matches!(node.read_index(), Err(RaftError::NotLeader)));
+        node.become_candidate();
+        node.start_election(false);
+
+        let mut first = node.propose(set("pair", "ETH/USDC")).unwrap();
+        let mut second = node.propose(set("pair", "BTC/USDC")).unwrap();
+        // A single node commits and applies immediately
+        assert_eq!(first.try_recv().unwrap().unwrap(), None);
+        assert_eq!(
+            second.try_recv().unwrap().unwrap(),
+            Some("ETH/USDC".to_string())
+        );
+
+        let mut read = node.read_index().unwrap();
+        assert_eq!(read.try_recv().unwrap().unwrap(), node.commit_index());
+        assert_eq!(
+            node.state_machine().get("pair"),
+            Some(&"BTC/USDC".to_string())
+        );
+    }
+
+    /// Start a node that is not part of the cluster yet and knows its members
+    fn joining_node(network: &SimulatedNetwork, node_id: &str, cluster: &[RaftNode]) -> RaftNode {
+        let addresses: HashMap<String, String> = cluster
+            .iter()
+            .map(|node| {
+                (
+                    node.node_id().to_string(),
+                    format!("sim://{}", node.node_id()),
+                )
+            })
+            .collect();
+        let (transport, inbox) = network.register(node_id);
+        RaftNode::new(cluster_config(node_id, &addresses)).with_transport(transport, inbox)
+    }
+
+    #[tokio::test]
+    async fn test_learner_catches_up_and_is_promoted() {
+        let (network, mut nodes) = simulated_cluster(3);
+        assert!(run_until(&mut nodes, |n| leaders(n).len() == 1).await);
+        let leader = leaders(&nodes)[0];
+        nodes[leader].submit_command(set("a", "1")).await.unwrap();
+        assert!(
+            run_until(&mut nodes, |n| n
+                .iter()
+                .all(|node| node.get_value("a").is_some()))
+            .await
+        );
+
+        let learner = joining_node(&network, "node-3", &nodes);
+        nodes.push(learner);
+        assert!(matches!(
+            nodes[leader].promote_learner("node-3"),
+            Err(RaftError::UnknownMember(_))
+        ));
+        nodes[leader].add_learner("node-3", "sim://node-3").unwrap();
+        // Only one change may be pending at a time
+        assert!(matches!(
+            nodes[leader].add_learner("node-4", "sim://node-4"),
+            Err(RaftError::MembershipChangeInProgress)
+        ));
+
+        // The learner receives the log without voting or starting elections
+        let caught_up = run_until(&mut nodes, |n| {
+            let status = n[leader].cluster_status();
+            n[3].get_value("a").is_some()
+                && n[3].membership().is_learner("node-3")
+                && status.membership_committed
+                && status
+                    .peers
+                    .iter()
+                    .all(|peer| peer.match_index == Some(status.last_log_index))
+        })
+        .await;
+        assert!(caught_up);
+        assert_eq!(nodes[3].state(), &NodeState::Follower);
+
+        nodes[leader].promote_learner("node-3").unwrap();
+        let promoted = run_until(&mut nodes, |n| {
+            n.iter().all(|node| node.membership().voters.len() == 4)
+                && n[leader].cluster_status().membership_committed
+        })
+        .await;
+        assert!(promoted);
+
+        // Remove a follower; the remaining three keep committing
+        let removed = (0..3).find(|&i| i != leader).unwrap();
+        let removed_id = nodes[removed].node_id().to_string();
+        nodes[leader].remove_member(&removed_id).unwrap();
+        assert!(
+            run_until(&mut nodes, |n| n[leader]
+                .cluster_status()
+                .membership_committed)
+            .await
+        );
+        nodes[leader].submit_command(set("b", "2")).await.unwrap();
+        let replicated = run_until(&mut nodes, |n| {
+            (0..n.len())
+                .filter(|&i| i != removed)
+                .all(|i| n[i].get_value("b").is_some())
+        })
+        .await;
+        assert!(replicated);
+
+        let status = nodes[leader].cluster_status();
+        assert_eq!(status.leader.as_deref(), Some(nodes[leader].node_id()));
+        assert_eq!(status.membership.voters.len(), 3);
+        assert_eq!(status.peers.len(), 2);
+        assert!(status.peers.iter().all(|peer| peer.voter));
+        assert!(status.peers.iter().all(|peer| peer.node_id != removed_id));
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["state"], "Leader");
+        assert_eq!(json["commit_index"], status.commit_index);
+    }
+
+    #[tokio::test]
+    async fn test_leadership_transfer_to_chosen_node() {
+        let (_network, mut nodes) = simulated_cluster(3);
+        assert!(run_until(&mut nodes, |n| leaders(n).len() == 1).await);
+        let leader = leaders(&nodes)[0];
+        let target = (0..3).find(|&i| i != leader).unwrap();
+        let target_id = nodes[target].node_id().to_string();
+
+        assert!(matches!(
+            nodes[leader].transfer_leadership("node-9"),
+            Err(RaftError::UnknownMember(_))
+        ));
+        nodes[leader].transfer_leadership(&target_id).unwrap();
+        assert!(matches!(
+            nodes[leader].propose(set("a", "1")),
+            Err(RaftError::LeadershipTransferInProgress)
+        ));
+
+        let transferred = run_until(&mut nodes, |n| leaders(n) == vec![target]).await;
+        assert!(transferred);
+        assert!(nodes[target].current_term() > 1);
+        nodes[target].submit_command(set("a", "1")).await.unwrap();
+        assert!(
+            run_until(&mut nodes, |n| n
+                .iter()
+                .all(|node| node.get_value("a").is_some()))
+            .await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_removed_leader_steps_down() {
+        let (_network, mut nodes) = simulated_cluster(3);
+        assert!(run_until(&mut nodes, |n| leaders(n).len() == 1).await);
+        let old_leader = leaders(&nodes)[0];
+        let old_leader_id = nodes[old_leader].node_id().to_string();
+        assert!(
+            run_until(&mut nodes, |n| n[old_leader].commit_index()
+                == n[old_leader].last_log_index())
+            .await
+        );
+
+        nodes[old_leader].remove_member(&old_leader_id).unwrap();
+        let replaced = run_until(&mut nodes, |n| {
+            let leaders = leaders(n);
+            leaders.len() == 1 && leaders[0] != old_leader
+        })
+        .await;
+        assert!(replaced);
+        assert_eq!(nodes[old_leader].state(), &NodeState::Follower);
+        assert!(!nodes[old_leader].membership().is_voter(&old_leader_id));
+
+        let leader = leaders(&nodes)[0];
+        assert_eq!(nodes[leader].membership().voters.len(), 2);
+        nodes[leader].submit_command(set("a", "1")).await.unwrap();
+        let replicated = run_until(&mut nodes, |n| {
+            (0..n.len())
+                .filter(|&i| i != old_leader)
+                .all(|i| n[i].get_value("a").is_some())
+        })
+        .await;
+        assert!(replicated);
+    }
 }
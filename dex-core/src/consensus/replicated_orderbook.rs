@@ -0,0 +1,373 @@
+//! Raft-replicated orderbook
+//!
+//! Runs the matching engine as a [`StateMachine`] so a cluster of nodes keeps
+//! identical books: the leader accepts orders through [`RaftNode::propose`] and
+//! returns the resulting trades once the order is committed, and any replica's
+//! book can be read linearizably after [`RaftNode::read_index`] resolves.
+//!
+//! Matching stamps trades with the wall clock and leaves their IDs to the
+//! caller, so the adapter assigns both deterministically (the taker order's
+//! timestamp and a replicated trade counter) to keep replicas identical.
+//!
+//! Implements the Priority 3 feature from DEX-OS-V2.csv:
+//! - Infrastructure,Network,Network,Raft Consensus,Service Coordination,Medium
+//!
+//! and replicates the Priority 1 feature from DEX-OS-V1.csv:
+//! - Core Trading,Orderbook,Orderbook,Price-Time Priority,Order Matching,High
+
+use super::raft::{RaftError, RaftNode};
+use super::state_machine::StateMachine;
+use crate::orderbook::{OrderBook, PriceLevel};
+use crate::types::{Order, OrderId, Price, Quantity, Trade, TradeId};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Raft node replicating an orderbook
+pub type OrderBookNode = RaftNode<ReplicatedOrderBook>;
+
+/// Command replicated through the Raft log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum OrderBookCommand {
+    /// No-op appended by new leaders
+    NoOp,
+    /// Match an order against the book and rest any remainder
+    PlaceOrder(Order),
+    /// Remove a resting order
+    CancelOrder { order_id: OrderId },
+}
+
+/// Result of applying an [`OrderBookCommand`]
+#[derive(Debug, Clone)]
+pub enum OrderBookOutcome {
+    /// Nothing to report (no-op)
+    None,
+    /// Order accepted; trades executed against resting orders
+    Placed { trades: Vec<Trade> },
+    /// Order removed from the book
+    Cancelled(Order),
+    /// Command rejected by the matching engine
+    Rejected(String),
+}
+
+/// Price level as stored in a snapshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LevelSnapshot {
+    price: Price,
+    orders: Vec<OrderId>,
+    total_quantity: Quantity,
+}
+
+/// Serialized form of the book
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OrderBookSnapshot {
+    bids: Vec<LevelSnapshot>,
+    asks: Vec<LevelSnapshot>,
+    orders: Vec<Order>,
+    next_trade_id: TradeId,
+}
+
+/// Orderbook wrapped as a Raft state machine
+#[derive(Debug, Clone)]
+pub struct ReplicatedOrderBook {
+    /// Replicated book
+    book: OrderBook,
+    /// ID assigned to the next trade
+    next_trade_id: TradeId,
+}
+
+impl Default for ReplicatedOrderBook {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReplicatedOrderBook {
+    /// Create an empty replicated book
+    pub fn new() -> Self {
+        Self {
+            book: OrderBook::new(),
+            next_trade_id: 1,
+        }
+    }
+
+    /// Get the replicated book
+    pub fn order_book(&self) -> &OrderBook {
+        &self.book
+    }
+
+    fn snapshot_levels(levels: &BTreeMap<Price, PriceLevel>) -> Vec<LevelSnapshot> {
+        levels
+            .values()
+            .map(|level| LevelSnapshot {
+                price: level.price,
+                orders: level.orders.clone(),
+                total_quantity: level.total_quantity,
+            })
+            .collect()
+    }
+
+    fn restore_levels(levels: Vec<LevelSnapshot>) -> BTreeMap<Price, PriceLevel> {
+        levels
+            .into_iter()
+            .map(|level| {
+                (
+                    level.price,
+                    PriceLevel {
+                        price: level.price,
+                        orders: level.orders,
+                        total_quantity: level.total_quantity,
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+impl StateMachine for ReplicatedOrderBook {
+    type Command = OrderBookCommand;
+    type Output = OrderBookOutcome;
+
+    fn no_op() -> OrderBookCommand {
+        OrderBookCommand::NoOp
+    }
+
+    fn apply(&mut self, command: &OrderBookCommand) -> OrderBookOutcome {
+        match command {
+            OrderBookCommand::NoOp => OrderBookOutcome::None,
+            OrderBookCommand::PlaceOrder(order) => match self.book.add_order(order.clone()) {
+                Ok(mut trades) => {
+                    for trade in trades.iter_mut() {
+                        trade.id = self.next_trade_id;
+                        trade.timestamp = order.timestamp;
+                        self.next_trade_id += 1;
+                    }
+                    OrderBookOutcome::Placed { trades }
+                }
+                Err(e) => OrderBookOutcome::Rejected(e.to_string()),
+            },
+            OrderBookCommand::CancelOrder { order_id } => match self.book.remove_order(*order_id) {
+                Ok(order) => OrderBookOutcome::Cancelled(order),
+                Err(e) => OrderBookOutcome::Rejected(e.to_string()),
+            },
+        }
+    }
+
+    fn snapshot(&self) -> Result<Vec<u8>, RaftError> {
+        let mut orders: Vec<Order> = self.book.orders.values().cloned().collect();
+        orders.sort_by_key(|order| (order.timestamp, order.id));
+        let snapshot = OrderBookSnapshot {
+            bids: Self::snapshot_levels(&self.book.bids),
+            asks: Self::snapshot_levels(&self.book.asks),
+            orders,
+            next_trade_id: self.next_trade_id,
+        };
+        serde_json::to_vec(&snapshot).map_err(|e| RaftError::StorageError(e.to_string()))
+    }
+
+    fn restore(&mut self, data: &[u8]) -> Result<(), RaftError> {
+        let snapshot: OrderBookSnapshot =
+            serde_json::from_slice(data).map_err(|e| RaftError::StorageError(e.to_string()))?;
+
+        let mut book = OrderBook::new();
+        book.bids = Self::restore_levels(snapshot.bids);
+        book.asks = Self::restore_levels(snapshot.asks);
+        // The balancing trees only track levels that still hold orders
+        for level in book.bids.values().filter(|level| !level.orders.is_empty()) {
+            book.bid_price_levels.insert_price_level(level.price);
+        }
+        for level in book.asks.values().filter(|level| !level.orders.is_empty()) {
+            book.ask_price_levels.insert_price_level(level.price);
+        }
+        book.orders = snapshot
+            .orders
+            .into_iter()
+            .map(|order| (order.id, order))
+            .collect();
+
+        self.book = book;
+        self.next_trade_id = snapshot.next_trade_id;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::raft::{NodeState, RaftConfig};
+    use crate::consensus::transport::SimulatedNetwork;
+    use crate::types::{OrderSide, OrderType, TradingPair};
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn order(id: OrderId, side: OrderSide, price: Price, quantity: Quantity) -> Order {
+        Order {
+            id,
+            trader_id: format!("trader-{}", id),
+            pair: TradingPair {
+                base: "ETH".to_string(),
+                quote: "USDC".to_string(),
+            },
+            side,
+            order_type: OrderType::Limit,
+            price: Some(price),
+            quantity,
+            timestamp: id,
+        }
+    }
+
+    fn cluster(size: usize) -> Vec<OrderBookNode> {
+        let network = SimulatedNetwork::new();
+        let addresses: HashMap<String, String> = (0..size)
+            .map(|i| (format!("node-{}", i), format!("sim://node-{}", i)))
+            .collect();
+        (0..size)
+            .map(|i| {
+                let id = format!("node-{}", i);
+                let (transport, inbox) = network.register(&id);
+                let config = RaftConfig {
+                    node_id: id,
+                    node_addresses: addresses.clone(),
+                    election_timeout_ms: 150,
+                    heartbeat_interval_ms: 30,
+                    snapshot_threshold: 4,
+                    ..RaftConfig::default()
+                };
+                RaftNode::with_state_machine(config, ReplicatedOrderBook::new())
+                    .with_transport(transport, inbox)
+            })
+            .collect()
+    }
+
+    /// Poll every node until `done` returns a value or the deadline passes
+    async fn drive<T>(
+        nodes: &mut [OrderBookNode],
+        mut done: impl FnMut(&mut [OrderBookNode]) -> Option<T>,
+    ) -> Option<T> {
+        for _ in 0..1000 {
+            for node in nodes.iter_mut() {
+                node.poll();
+            }
+            if let Some(value) = done(nodes) {
+                return Some(value);
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        None
+    }
+
+    fn leader(nodes: &[OrderBookNode]) -> Option<usize> {
+        let leaders: Vec<usize> = (0..nodes.len())
+            .filter(|&i| *nodes[i].state() == NodeState::Leader)
+            .collect();
+        (leaders.len() == 1).then(|| leaders[0])
+    }
+
+    #[test]
+    fn test_snapshot_round_trip_preserves_book() {
+        let mut machine = ReplicatedOrderBook::new();
+        machine.apply(&OrderBookCommand::PlaceOrder(order(
+            1,
+            OrderSide::Sell,
+            101,
+            5,
+        )));
+        machine.apply(&OrderBookCommand::PlaceOrder(order(
+            2,
+            OrderSide::Sell,
+            102,
+            5,
+        )));
+        let outcome = machine.apply(&OrderBookCommand::PlaceOrder(order(
+            3,
+            OrderSide::Buy,
+            101,
+            2,
+        )));
+        match outcome {
+            OrderBookOutcome::Placed { trades } => {
+                assert_eq!(trades.len(), 1);
+                assert_eq!(trades[0].id, 1);
+                assert_eq!(trades[0].timestamp, 3);
+            }
+            other => panic!("unexpected outcome {:?}", other),
+        }
+
+        let mut restored = ReplicatedOrderBook::new();
+        restored.restore(&machine.snapshot().unwrap()).unwrap();
+        assert_eq!(restored.order_book().best_ask(), Some(101));
+        assert_eq!(restored.order_book().get_order(1).unwrap().quantity, 3);
+        assert_eq!(
+            restored.order_book().get_all_ask_price_levels(),
+            vec![101, 102]
+        );
+
+        // Both copies continue identically, including trade numbering
+        let next = OrderBookCommand::PlaceOrder(order(4, OrderSide::Buy, 102, 4));
+        assert_eq!(
+            format!("{:?}", machine.apply(&next)),
+            format!("{:?}", restored.apply(&next))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replicated_matching_engine_cluster() {
+        let mut nodes = cluster(3);
+        let leader = drive(&mut nodes, |n| leader(n)).await.unwrap();
+
+        assert!(matches!(
+            nodes[(leader + 1) % 3].propose(OrderBookCommand::NoOp),
+            Err(RaftError::NotLeader)
+        ));
+
+        let mut receivers = Vec::new();
+        for command in [
+            OrderBookCommand::PlaceOrder(order(1, OrderSide::Sell, 100, 10)),
+            OrderBookCommand::PlaceOrder(order(2, OrderSide::Sell, 99, 4)),
+            OrderBookCommand::PlaceOrder(order(3, OrderSide::Buy, 100, 6)),
+            OrderBookCommand::CancelOrder { order_id: 42 },
+        ] {
+            receivers.push(nodes[leader].propose(command).unwrap());
+        }
+
+        // Each client receives its own outcome once the entry is applied
+        let mut outcomes = Vec::new();
+        for mut receiver in receivers {
+            let outcome = drive(&mut nodes, |_| receiver.try_recv().ok()).await;
+            outcomes.push(outcome.unwrap().unwrap());
+        }
+        match &outcomes[2] {
+            OrderBookOutcome::Placed { trades } => {
+                let filled: Vec<(Price, Quantity)> =
+                    trades.iter().map(|t| (t.price, t.quantity)).collect();
+                assert_eq!(filled, vec![(99, 4), (100, 2)]);
+            }
+            other => panic!("unexpected outcome {:?}", other),
+        }
+        assert!(matches!(outcomes[3], OrderBookOutcome::Rejected(_)));
+
+        // A read-index read on the leader observes every acknowledged write
+        let mut read = nodes[leader].read_index().unwrap();
+        let read_index = drive(&mut nodes, |_| read.try_recv().ok())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(nodes[leader].last_applied() >= read_index);
+        let book = nodes[leader].state_machine().order_book();
+        assert_eq!(book.best_ask(), Some(100));
+        assert_eq!(book.get_order(1).unwrap().quantity, 8);
+
+        // Followers converge on the same book, partly through snapshots
+        let converged = drive(&mut nodes, |n| {
+            n.iter()
+                .all(|node| node.last_applied() >= read_index)
+                .then_some(())
+        })
+        .await;
+        assert!(converged.is_some());
+        for node in &nodes {
+            let book = node.state_machine().order_book();
+            assert_eq!(book.get_order(1).unwrap().quantity, 8);
+            assert!(book.get_order(2).is_none());
+        }
+    }
+}
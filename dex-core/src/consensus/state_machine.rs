@@ -0,0 +1,83 @@
+//! Replicated state machines for Raft consensus
+//!
+//! A [`StateMachine`] receives committed log entries in order on every node, so
+//! any deterministic service can be replicated by implementing `apply`,
+//! `snapshot` and `restore` for its own command type. [`KeyValueStore`] is the
+//! string map used by default.
+//!
+//! Implements the Priority 3 feature from DEX-OS-V2.csv:
+//! - Infrastructure,Network,Network,Raft Consensus,Service Coordination,Medium
+
+use super::raft::{Command, RaftError};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+/// Deterministic service replicated through the Raft log
+pub trait StateMachine: Send + 'static {
+    /// Command carried by log entries
+    type Command: Clone + Debug + Serialize + DeserializeOwned + Send + Sync + 'static;
+    /// Result of applying a command, returned to the client that proposed it
+    type Output: Send + 'static;
+
+    /// Command a new leader appends to commit entries from earlier terms.
+    /// Applying it must leave the state unchanged.
+    fn no_op() -> Self::Command;
+
+    /// Apply a committed command. Must be deterministic: every replica applies
+    /// the same commands in the same order and must reach the same state.
+    fn apply(&mut self, command: &Self::Command) -> Self::Output;
+
+    /// Serialize the full state for a snapshot
+    fn snapshot(&self) -> Result<Vec<u8>, RaftError>;
+
+    /// Replace the state with a snapshot produced by [`StateMachine::snapshot`]
+    fn restore(&mut self, data: &[u8]) -> Result<(), RaftError>;
+}
+
+/// String key-value store driven by [`Command`]
+#[derive(Debug, Clone, Default)]
+pub struct KeyValueStore {
+    data: HashMap<String, String>,
+}
+
+impl KeyValueStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the value stored under `key`
+    pub fn get(&self, key: &str) -> Option<&String> {
+        self.data.get(key)
+    }
+}
+
+impl StateMachine for KeyValueStore {
+    type Command = Command;
+    /// Previous value of the key, if any
+    type Output = Option<String>;
+
+    fn no_op() -> Command {
+        Command::NoOp
+    }
+
+    fn apply(&mut self, command: &Command) -> Option<String> {
+        match command {
+            Command::NoOp => None,
+            Command::Set { key, value } => self.data.insert(key.clone(), value.clone()),
+            Command::Delete { key } => self.data.remove(key),
+        }
+    }
+
+    fn snapshot(&self) -> Result<Vec<u8>, RaftError> {
+        serde_json::to_vec(&self.data).map_err(|e| RaftError::StorageError(e.to_string()))
+    }
+
+    fn restore(&mut self, data: &[u8]) -> Result<(), RaftError> {
+        self.data =
+            serde_json::from_slice(data).map_err(|e| RaftError::StorageError(e.to_string()))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,349 @@
+//! Network transports for Raft consensus
+//!
+//! A [`RaftTransport`] carries [`RaftMessage`]s between nodes on a best-effort
+//! basis: Raft tolerates lost, delayed and reordered messages, so `send` never
+//! blocks on delivery. Two transports are provided:
+//! - [`TcpTransport`], which frames messages as length-prefixed JSON over TCP
+//!   connections to the peers listed in `RaftConfig::node_addresses` and to
+//!   members added later through [`RaftTransport::add_peer`];
+//! - [`SimulatedNetwork`], an in-memory network for running whole clusters in a
+//!   single process with partitions, delays and message drops.
+//!
+//! Implements the Priority 3 feature from DEX-OS-V2.csv:
+//! - Infrastructure,Network,Network,Raft Consensus,Service Coordination,Medium
+
+use super::raft::{Command, RaftError, RaftMessage};
+use rand::Rng;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+/// Largest frame accepted from a peer, guarding against corrupt length prefixes
+const MAX_FRAME_BYTES: usize = 16 * 1024 * 1024;
+
+/// Transport used by a Raft node to reach its peers
+pub trait RaftTransport<C = Command>: Send + Sync {
+    /// Send a message to the peer `to`. Delivery is best effort; an error is only
+    /// returned when the peer is unknown to the transport.
+    fn send(&self, to: &str, message: RaftMessage<C>) -> Result<(), RaftError>;
+
+    /// Make a member that joined the cluster reachable. Called for every member
+    /// of the configuration, so it must ignore peers it already knows.
+    fn add_peer(&self, _node_id: &str, _address: &str) -> Result<(), RaftError> {
+        Ok(())
+    }
+}
+
+/// Shared state of the in-memory network
+struct SimulatedNetworkState<C> {
+    /// Inbox of every registered node
+    inboxes: HashMap<String, UnboundedSender<RaftMessage<C>>>,
+    /// Directed links that currently drop every message
+    blocked: HashSet<(String, String)>,
+    /// Probability in `[0, 1]` that any message is dropped
+    drop_rate: f64,
+    /// Delay applied to every delivered message
+    delay: Duration,
+    /// Messages delivered so far
+    delivered: u64,
+    /// Messages dropped so far (partitions and random loss)
+    dropped: u64,
+}
+
+/// In-memory network connecting Raft nodes running in the same process
+pub struct SimulatedNetwork<C = Command> {
+    state: Arc<Mutex<SimulatedNetworkState<C>>>,
+}
+
+impl<C> Clone for SimulatedNetwork<C> {
+    fn clone(&self) -> Self {
+        Self {
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+impl<C: Send + 'static> Default for SimulatedNetwork<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Send + 'static> SimulatedNetwork<C> {
+    /// Create an empty network
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(SimulatedNetworkState {
+                inboxes: HashMap::new(),
+                blocked: HashSet::new(),
+                drop_rate: 0.0,
+                delay: Duration::ZERO,
+                delivered: 0,
+                dropped: 0,
+            })),
+        }
+    }
+
+    /// Register a node, returning its transport and inbox
+    pub fn register(
+        &self,
+        node_id: &str,
+    ) -> (
+        Arc<SimulatedTransport<C>>,
+        UnboundedReceiver<RaftMessage<C>>,
+    ) {
+        let (tx, rx) = unbounded_channel();
+        self.state
+            .lock()
+            .unwrap()
+            .inboxes
+            .insert(node_id.to_string(), tx);
+        let transport = Arc::new(SimulatedTransport {
+            node_id: node_id.to_string(),
+            network: self.clone(),
+        });
+        (transport, rx)
+    }
+
+    /// Cut every link between the two groups, in both directions
+    pub fn partition(&self, left: &[&str], right: &[&str]) {
+        let mut state = self.state.lock().unwrap();
+        for a in left {
+            for b in right {
+                state.blocked.insert((a.to_string(), b.to_string()));
+                state.blocked.insert((b.to_string(), a.to_string()));
+            }
+        }
+    }
+
+    /// Cut a node off from every other registered node
+    pub fn isolate(&self, node_id: &str) {
+        let others: Vec<String> = {
+            let state = self.state.lock().unwrap();
+            state
+                .inboxes
+                .keys()
+                .filter(|id| id.as_str() != node_id)
+                .cloned()
+                .collect()
+        };
+        let others: Vec<&str> = others.iter().map(String::as_str).collect();
+        self.partition(&[node_id], &others);
+    }
+
+    /// Restore every link
+    pub fn heal(&self) {
+        self.state.lock().unwrap().blocked.clear();
+    }
+
+    /// Set the probability that any message is dropped
+    pub fn set_drop_rate(&self, rate: f64) {
+        self.state.lock().unwrap().drop_rate = rate.clamp(0.0, 1.0);
+    }
+
+    /// Set the delay applied to every delivered message
+    pub fn set_delay(&self, delay: Duration) {
+        self.state.lock().unwrap().delay = delay;
+    }
+
+    /// Number of messages delivered so far
+    pub fn delivered_messages(&self) -> u64 {
+        self.state.lock().unwrap().delivered
+    }
+
+    /// Number of messages dropped so far
+    pub fn dropped_messages(&self) -> u64 {
+        self.state.lock().unwrap().dropped
+    }
+
+    /// Route a message from `from` to `to`, applying partitions, loss and delay
+    fn deliver(&self, from: &str, to: &str, message: RaftMessage<C>) -> Result<(), RaftError> {
+        let (inbox, delay) = {
+            let mut state = self.state.lock().unwrap();
+            let inbox = state
+                .inboxes
+                .get(to)
+                .cloned()
+                .ok_or_else(|| RaftError::NetworkError(format!("unknown peer {}", to)))?;
+            let blocked = state.blocked.contains(&(from.to_string(), to.to_string()));
+            let lost = state.drop_rate > 0.0 && rand::thread_rng().gen_bool(state.drop_rate);
+            if blocked || lost {
+                state.dropped += 1;
+                return Ok(());
+            }
+            state.delivered += 1;
+            (inbox, state.delay)
+        };
+
+        if delay.is_zero() {
+            let _ = inbox.send(message);
+        } else if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            handle.spawn(async move {
+                tokio::time::sleep(delay).await;
+                let _ = inbox.send(message);
+            });
+        } else {
+            std::thread::spawn(move || {
+                std::thread::sleep(delay);
+                let _ = inbox.send(message);
+            });
+        }
+        Ok(())
+    }
+}
+
+/// A node's handle onto a [`SimulatedNetwork`]
+pub struct SimulatedTransport<C = Command> {
+    node_id: String,
+    network: SimulatedNetwork<C>,
+}
+
+impl<C: Send + 'static> RaftTransport<C> for SimulatedTransport<C> {
+    fn send(&self, to: &str, message: RaftMessage<C>) -> Result<(), RaftError> {
+        self.network.deliver(&self.node_id, to, message)
+    }
+}
+
+/// TCP transport framing messages as a big-endian `u32` length followed by JSON
+pub struct TcpTransport<C = Command> {
+    /// ID of the local node
+    node_id: String,
+    /// Outbound queue of every peer, drained by a per-peer writer task
+    peers: Mutex<HashMap<String, UnboundedSender<RaftMessage<C>>>>,
+}
+
+impl<C: Serialize + DeserializeOwned + Send + 'static> TcpTransport<C> {
+    /// Bind the address configured for `node_id` and connect lazily to every
+    /// other entry of `node_addresses`
+    pub async fn bind(
+        node_id: &str,
+        node_addresses: &HashMap<String, String>,
+    ) -> Result<(Arc<Self>, UnboundedReceiver<RaftMessage<C>>), RaftError> {
+        let address = node_addresses.get(node_id).ok_or_else(|| {
+            RaftError::NetworkError(format!("no address configured for {}", node_id))
+        })?;
+        let listener = TcpListener::bind(address)
+            .await
+            .map_err(|e| RaftError::NetworkError(e.to_string()))?;
+        Ok(Self::from_listener(node_id, listener, node_addresses))
+    }
+
+    /// Serve an already bound listener. Must be called inside a tokio runtime.
+    pub fn from_listener(
+        node_id: &str,
+        listener: TcpListener,
+        node_addresses: &HashMap<String, String>,
+    ) -> (Arc<Self>, UnboundedReceiver<RaftMessage<C>>) {
+        let (inbox_tx, inbox_rx) = unbounded_channel();
+        tokio::spawn(Self::accept_loop(listener, inbox_tx));
+
+        let transport = Self {
+            node_id: node_id.to_string(),
+            peers: Mutex::new(HashMap::new()),
+        };
+        for (peer_id, address) in node_addresses {
+            transport.connect(peer_id, address);
+        }
+
+        (Arc::new(transport), inbox_rx)
+    }
+
+    /// Start a writer task for a peer not seen before. Must be called inside a
+    /// tokio runtime.
+    fn connect(&self, peer_id: &str, address: &str) {
+        if peer_id == self.node_id {
+            return;
+        }
+        let mut peers = self.peers.lock().unwrap();
+        if peers.contains_key(peer_id) {
+            return;
+        }
+        let (tx, rx) = unbounded_channel();
+        tokio::spawn(Self::peer_writer(address.to_string(), rx));
+        peers.insert(peer_id.to_string(), tx);
+    }
+
+    /// Accept inbound connections and forward their frames to the inbox
+    async fn accept_loop(listener: TcpListener, inbox: UnboundedSender<RaftMessage<C>>) {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(Self::read_frames(stream, inbox.clone()));
+        }
+    }
+
+    /// Decode frames from one connection until it closes or sends garbage
+    async fn read_frames(mut stream: TcpStream, inbox: UnboundedSender<RaftMessage<C>>) {
+        loop {
+            let len = match stream.read_u32().await {
+                Ok(len) => len as usize,
+                Err(_) => return,
+            };
+            if len > MAX_FRAME_BYTES {
+                return;
+            }
+            let mut frame = vec![0u8; len];
+            if stream.read_exact(&mut frame).await.is_err() {
+                return;
+            }
+            match serde_json::from_slice::<RaftMessage<C>>(&frame) {
+                Ok(message) => {
+                    if inbox.send(message).is_err() {
+                        return;
+                    }
+                }
+                Err(_) => return,
+            }
+        }
+    }
+
+    /// Write queued messages to a peer, reconnecting after failures. Messages
+    /// that cannot be written are dropped and left to Raft's retries.
+    async fn peer_writer(address: String, mut outbound: UnboundedReceiver<RaftMessage<C>>) {
+        let mut connection: Option<TcpStream> = None;
+        while let Some(message) = outbound.recv().await {
+            let frame = match serde_json::to_vec(&message) {
+                Ok(frame) => frame,
+                Err(_) => continue,
+            };
+            if connection.is_none() {
+                connection = TcpStream::connect(&address).await.ok();
+            }
+            if let Some(stream) = connection.as_mut() {
+                let written = async {
+                    stream.write_u32(frame.len() as u32).await?;
+                    stream.write_all(&frame).await
+                }
+                .await;
+                if written.is_err() {
+                    connection = None;
+                }
+            }
+        }
+    }
+}
+
+impl<C: Serialize + DeserializeOwned + Send + 'static> RaftTransport<C> for TcpTransport<C> {
+    fn send(&self, to: &str, message: RaftMessage<C>) -> Result<(), RaftError> {
+        let peers = self.peers.lock().unwrap();
+        let peer = peers
+            .get(to)
+            .ok_or_else(|| RaftError::NetworkError(format!("unknown peer {}", to)))?;
+        // The writer task only stops when the transport is dropped
+        let _ = peer.send(message);
+        Ok(())
+    }
+
+    fn add_peer(&self, node_id: &str, address: &str) -> Result<(), RaftError> {
+        if tokio::runtime::Handle::try_current().is_err() {
+            return Err(RaftError::NetworkError(
+                "peers can only be added inside a tokio runtime".to_string(),
+            ));
+        }
+        self.connect(node_id, address);
+        Ok(())
+    }
+}
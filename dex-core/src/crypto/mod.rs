@@ -2,6 +2,11 @@
 //!
 //! This module implements various cryptographic features for the DEX-OS core engine.
 
+pub mod vrf;
 pub mod zk_proof;
 
-pub use zk_proof::{PrivacyProtectionService, ZkProof, ZkProofSystem};
\ No newline at end of file
+pub use vrf::{EcVrf, VrfError, VrfOutput};
+pub use zk_proof::{
+    MembershipProof, Opening, PrivacyProtectionService, RangeProof, VerifiedProof, ZkError,
+    ZkProof, ZkProofSystem,
+};
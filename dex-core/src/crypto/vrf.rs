@@ -0,0 +1,422 @@
+//! Verifiable random functions
+//!
+//! [`EcVrf`] implements ECVRF-EDWARDS25519-SHA512-TAI from RFC 9381 over the
+//! ed25519 keys validators already hold. The holder of the secret key maps an
+//! input to a 64-byte pseudorandom output together with an 80-byte proof;
+//! anyone with the public key can check the proof and recompute the output,
+//! and no other output verifies for the same key and input.
+//!
+//! With the `pq-vrf` feature, [`MlDsaVrf`] offers a post-quantum alternative
+//! built from deterministic ML-DSA-65 signatures. ML-DSA signatures are not
+//! unique: a key holder can produce other valid proofs for the same input by
+//! signing with fresh randomness, so that option gives unpredictability to
+//! everyone else but not the unbiasability of ECVRF.
+//!
+//! [`verify`] picks the scheme from the length of the public key.
+//!
+//! This implements the Priority 1 feature from DEX-OS-V1.csv:
+//! "Core Components,Quantum Consensus (QBFT),Consensus,QVRF Leader Selection,Leader Selection,High"
+
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::IsIdentity;
+use ed25519_dalek::SigningKey;
+use sha2::{Digest, Sha512};
+use zeroize::Zeroize;
+
+/// Length of a VRF output in bytes
+pub const OUTPUT_LENGTH: usize = 64;
+
+/// Length of an ECVRF proof in bytes: Gamma, the challenge and the response
+pub const ECVRF_PROOF_LENGTH: usize = 80;
+
+/// Length of an ECVRF public key in bytes
+pub const ECVRF_PUBLIC_KEY_LENGTH: usize = 32;
+
+/// Suite string of ECVRF-EDWARDS25519-SHA512-TAI
+const SUITE: u8 = 0x03;
+
+/// Length of the truncated challenge
+const CHALLENGE_LENGTH: usize = 16;
+
+/// A VRF output
+pub type VrfOutput = [u8; OUTPUT_LENGTH];
+
+/// Errors from VRF key handling and proof verification
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum VrfError {
+    #[error("Invalid VRF secret key")]
+    InvalidSecretKey,
+    #[error("Invalid VRF public key")]
+    InvalidPublicKey,
+    #[error("Malformed VRF proof")]
+    MalformedProof,
+    #[error("VRF proof does not verify")]
+    VerificationFailed,
+    #[error("Hash to curve failed")]
+    HashToCurveFailed,
+}
+
+/// ECVRF key pair over ed25519
+pub struct EcVrf {
+    /// Secret scalar derived from the ed25519 secret key
+    secret_scalar: Scalar,
+    /// Second half of the expanded secret key, used to derive nonces
+    nonce_prefix: [u8; 32],
+    /// Encoded public key
+    public_key: [u8; ECVRF_PUBLIC_KEY_LENGTH],
+    /// Decoded public key
+    public_point: EdwardsPoint,
+}
+
+impl EcVrf {
+    /// Key pair for a 32-byte ed25519 secret key
+    pub fn from_secret(secret_key: &[u8]) -> Result<Self, VrfError> {
+        let secret: [u8; 32] = secret_key
+            .try_into()
+            .map_err(|_| VrfError::InvalidSecretKey)?;
+        Ok(Self::from_signing_key(&SigningKey::from_bytes(&secret)))
+    }
+
+    /// Key pair sharing an existing ed25519 signing key
+    pub fn from_signing_key(key: &SigningKey) -> Self {
+        let mut expanded: [u8; 64] = Sha512::digest(key.to_bytes()).into();
+        let mut nonce_prefix = [0u8; 32];
+        nonce_prefix.copy_from_slice(&expanded[32..]);
+        expanded.zeroize();
+
+        let verifying_key = key.verifying_key();
+        Self {
+            secret_scalar: key.to_scalar(),
+            nonce_prefix,
+            public_key: verifying_key.to_bytes(),
+            public_point: verifying_key.to_edwards(),
+        }
+    }
+
+    /// Encoded public key
+    pub fn public_key(&self) -> [u8; ECVRF_PUBLIC_KEY_LENGTH] {
+        self.public_key
+    }
+
+    /// Proof for `alpha`
+    pub fn prove(&self, alpha: &[u8]) -> Result<[u8; ECVRF_PROOF_LENGTH], VrfError> {
+        let h = hash_to_curve(&self.public_key, alpha)?;
+        let h_string = h.compress().to_bytes();
+        let gamma = h * self.secret_scalar;
+
+        let mut nonce_hash: [u8; 64] = Sha512::new()
+            .chain_update(self.nonce_prefix)
+            .chain_update(h_string)
+            .finalize()
+            .into();
+        let k = Scalar::from_bytes_mod_order_wide(&nonce_hash);
+        nonce_hash.zeroize();
+
+        let c = challenge(
+            &self.public_point,
+            &h,
+            &gamma,
+            &EdwardsPoint::mul_base(&k),
+            &(h * k),
+        );
+        let s = k + challenge_scalar(&c) * self.secret_scalar;
+
+        let mut proof = [0u8; ECVRF_PROOF_LENGTH];
+        proof[..32].copy_from_slice(gamma.compress().as_bytes());
+        proof[32..48].copy_from_slice(&c);
+        proof[48..].copy_from_slice(s.as_bytes());
+        Ok(proof)
+    }
+
+    /// Output and proof for `alpha`
+    pub fn evaluate(
+        &self,
+        alpha: &[u8],
+    ) -> Result<(VrfOutput, [u8; ECVRF_PROOF_LENGTH]), VrfError> {
+        let proof = self.prove(alpha)?;
+        Ok((Self::proof_to_hash(&proof)?, proof))
+    }
+
+    /// Output encoded in `proof`, without checking the proof
+    pub fn proof_to_hash(proof: &[u8]) -> Result<VrfOutput, VrfError> {
+        let (gamma, _, _) = decode_proof(proof)?;
+        Ok(Sha512::new()
+            .chain_update([SUITE, 0x03])
+            .chain_update(gamma.mul_by_cofactor().compress().as_bytes())
+            .chain_update([0x00])
+            .finalize()
+            .into())
+    }
+
+    /// Check `proof` for `alpha` under `public_key` and return its output
+    pub fn verify(public_key: &[u8], alpha: &[u8], proof: &[u8]) -> Result<VrfOutput, VrfError> {
+        let encoded: [u8; ECVRF_PUBLIC_KEY_LENGTH] = public_key
+            .try_into()
+            .map_err(|_| VrfError::InvalidPublicKey)?;
+        let y = CompressedEdwardsY(encoded)
+            .decompress()
+            .filter(|point| !point.is_small_order())
+            .ok_or(VrfError::InvalidPublicKey)?;
+        let (gamma, c, s) = decode_proof(proof)?;
+
+        let h = hash_to_curve(&encoded, alpha)?;
+        let c_scalar = challenge_scalar(&c);
+        let u = EdwardsPoint::vartime_double_scalar_mul_basepoint(&-c_scalar, &y, &s);
+        let v = h * s - gamma * c_scalar;
+        if challenge(&y, &h, &gamma, &u, &v) != c {
+            return Err(VrfError::VerificationFailed);
+        }
+        Self::proof_to_hash(proof)
+    }
+}
+
+impl Drop for EcVrf {
+    fn drop(&mut self) {
+        self.secret_scalar.zeroize();
+        self.nonce_prefix.zeroize();
+    }
+}
+
+/// Check a proof from any supported scheme and return its output. The scheme
+/// is chosen by the length of `public_key`.
+pub fn verify(public_key: &[u8], alpha: &[u8], proof: &[u8]) -> Result<VrfOutput, VrfError> {
+    match public_key.len() {
+        ECVRF_PUBLIC_KEY_LENGTH => EcVrf::verify(public_key, alpha, proof),
+        #[cfg(feature = "pq-vrf")]
+        pq::PUBLIC_KEY_LENGTH => MlDsaVrf::verify(public_key, alpha, proof),
+        _ => Err(VrfError::InvalidPublicKey),
+    }
+}
+
+/// ECVRF_encode_to_curve_try_and_increment
+fn hash_to_curve(public_key: &[u8; 32], alpha: &[u8]) -> Result<EdwardsPoint, VrfError> {
+    for counter in 0..=u8::MAX {
+        let hash = Sha512::new()
+            .chain_update([SUITE, 0x01])
+            .chain_update(public_key)
+            .chain_update(alpha)
+            .chain_update([counter, 0x00])
+            .finalize();
+        let mut candidate = [0u8; 32];
+        candidate.copy_from_slice(&hash[..32]);
+        if let Some(point) = CompressedEdwardsY(candidate).decompress() {
+            let point = point.mul_by_cofactor();
+            if !point.is_identity() {
+                return Ok(point);
+            }
+        }
+    }
+    Err(VrfError::HashToCurveFailed)
+}
+
+/// ECVRF_challenge_generation, truncated to 16 bytes
+fn challenge(
+    y: &EdwardsPoint,
+    h: &EdwardsPoint,
+    gamma: &EdwardsPoint,
+    u: &EdwardsPoint,
+    v: &EdwardsPoint,
+) -> [u8; CHALLENGE_LENGTH] {
+    let mut hasher = Sha512::new().chain_update([SUITE, 0x02]);
+    for point in [y, h, gamma, u, v] {
+        hasher.update(point.compress().as_bytes());
+    }
+    hasher.update([0x00]);
+    let digest = hasher.finalize();
+    let mut c = [0u8; CHALLENGE_LENGTH];
+    c.copy_from_slice(&digest[..CHALLENGE_LENGTH]);
+    c
+}
+
+fn challenge_scalar(c: &[u8; CHALLENGE_LENGTH]) -> Scalar {
+    let mut bytes = [0u8; 32];
+    bytes[..CHALLENGE_LENGTH].copy_from_slice(c);
+    Scalar::from_bytes_mod_order(bytes)
+}
+
+/// Split a proof into Gamma, the challenge and the response, rejecting
+/// non-canonical responses
+fn decode_proof(proof: &[u8]) -> Result<(EdwardsPoint, [u8; CHALLENGE_LENGTH], Scalar), VrfError> {
+    if proof.len() != ECVRF_PROOF_LENGTH {
+        return Err(VrfError::MalformedProof);
+    }
+    let mut gamma = [0u8; 32];
+    gamma.copy_from_slice(&proof[..32]);
+    let gamma = CompressedEdwardsY(gamma)
+        .decompress()
+        .ok_or(VrfError::MalformedProof)?;
+    let mut c = [0u8; CHALLENGE_LENGTH];
+    c.copy_from_slice(&proof[32..48]);
+    let mut s = [0u8; 32];
+    s.copy_from_slice(&proof[48..]);
+    let s = Option::from(Scalar::from_canonical_bytes(s)).ok_or(VrfError::MalformedProof)?;
+    Ok((gamma, c, s))
+}
+
+#[cfg(feature = "pq-vrf")]
+pub use pq::MlDsaVrf;
+
+#[cfg(feature = "pq-vrf")]
+mod pq {
+    use super::{VrfError, VrfOutput};
+    use ml_dsa::{EncodedSignature, EncodedVerifyingKey, KeyGen, MlDsa65, Signature, B32};
+    use sha2::{Digest, Sha512};
+
+    /// Length of an ML-DSA-65 public key in bytes
+    pub const PUBLIC_KEY_LENGTH: usize = 1952;
+
+    /// Context string binding signatures to VRF use
+    const CONTEXT: &[u8] = b"DEX-OS VRF";
+
+    /// Post-quantum VRF from deterministic ML-DSA-65 signatures
+    pub struct MlDsaVrf {
+        /// ML-DSA key pair
+        key: ml_dsa::KeyPair<MlDsa65>,
+    }
+
+    impl MlDsaVrf {
+        /// Key pair derived from a 32-byte seed
+        pub fn from_seed(seed: &[u8]) -> Result<Self, VrfError> {
+            let seed = B32::try_from(seed).map_err(|_| VrfError::InvalidSecretKey)?;
+            Ok(Self {
+                key: MlDsa65::key_gen_internal(&seed),
+            })
+        }
+
+        /// Encoded public key
+        pub fn public_key(&self) -> Vec<u8> {
+            self.key.verifying_key().encode().to_vec()
+        }
+
+        /// Output and proof for `alpha`
+        pub fn evaluate(&self, alpha: &[u8]) -> Result<(VrfOutput, Vec<u8>), VrfError> {
+            let signature = self
+                .key
+                .signing_key()
+                .sign_deterministic(alpha, CONTEXT)
+                .map_err(|_| VrfError::InvalidSecretKey)?;
+            let proof = signature.encode().to_vec();
+            Ok((output(&proof), proof))
+        }
+
+        /// Check `proof` for `alpha` under `public_key` and return its output
+        pub fn verify(
+            public_key: &[u8],
+            alpha: &[u8],
+            proof: &[u8],
+        ) -> Result<VrfOutput, VrfError> {
+            let encoded = EncodedVerifyingKey::<MlDsa65>::try_from(public_key)
+                .map_err(|_| VrfError::InvalidPublicKey)?;
+            let key = ml_dsa::VerifyingKey::<MlDsa65>::decode(&encoded);
+            let signature = EncodedSignature::<MlDsa65>::try_from(proof)
+                .ok()
+                .and_then(|encoded| Signature::<MlDsa65>::decode(&encoded))
+                .ok_or(VrfError::MalformedProof)?;
+            if !key.verify_with_context(alpha, CONTEXT, &signature) {
+                return Err(VrfError::VerificationFailed);
+            }
+            Ok(output(proof))
+        }
+    }
+
+    fn output(proof: &[u8]) -> VrfOutput {
+        Sha512::new()
+            .chain_update(CONTEXT)
+            .chain_update(proof)
+            .finalize()
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_rfc9381_vector() {
+        // RFC 9381 appendix B.3, example 16
+        let vrf = EcVrf::from_secret(&hex(
+            "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+        ))
+        .unwrap();
+        assert_eq!(
+            vrf.public_key().to_vec(),
+            hex("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a")
+        );
+        let (output, proof) = vrf.evaluate(b"").unwrap();
+        assert_eq!(
+            proof.to_vec(),
+            hex(concat!(
+                "8657106690b5526245a92b003bb079ccd1a92130477671f6fc01ad16f26f723f",
+                "26f8a57ccaed74ee1b190bed1f479d9727d2d0f9b005a6e456a35d4fb0daab12",
+                "68a1b0db10836d9826a528ca76567805"
+            ))
+        );
+        assert_eq!(
+            output.to_vec(),
+            hex(concat!(
+                "90cf1df3b703cce59e2a35b925d411164068269d7b2d29f3301c03dd757876ff",
+                "66b71dda49d2de59d03450451af026798e8f81cd2e333de5cdf4f3e140fdd8ae"
+            ))
+        );
+        assert_eq!(verify(&vrf.public_key(), b"", &proof).unwrap(), output);
+    }
+
+    #[test]
+    fn test_proofs_only_verify_for_their_key_and_input() {
+        let vrf = EcVrf::from_secret(&[7u8; 32]).unwrap();
+        let other = EcVrf::from_secret(&[8u8; 32]).unwrap();
+        let (output, proof) = vrf.evaluate(b"round 1").unwrap();
+        assert_eq!(vrf.evaluate(b"round 1").unwrap().0, output);
+        assert_ne!(vrf.evaluate(b"round 2").unwrap().0, output);
+
+        assert_eq!(
+            EcVrf::verify(&vrf.public_key(), b"round 1", &proof).unwrap(),
+            output
+        );
+        assert_eq!(
+            EcVrf::verify(&vrf.public_key(), b"round 2", &proof),
+            Err(VrfError::VerificationFailed)
+        );
+        assert_eq!(
+            EcVrf::verify(&other.public_key(), b"round 1", &proof),
+            Err(VrfError::VerificationFailed)
+        );
+
+        let mut tampered = proof;
+        tampered[40] ^= 1;
+        assert!(EcVrf::verify(&vrf.public_key(), b"round 1", &tampered).is_err());
+        assert_eq!(
+            EcVrf::verify(&vrf.public_key(), b"round 1", &proof[..79]),
+            Err(VrfError::MalformedProof)
+        );
+        assert_eq!(
+            EcVrf::verify(&[0u8; 32], b"round 1", &proof),
+            Err(VrfError::InvalidPublicKey)
+        );
+    }
+
+    #[cfg(feature = "pq-vrf")]
+    #[test]
+    fn test_ml_dsa_vrf_round_trip() {
+        let vrf = MlDsaVrf::from_seed(&[3u8; 32]).unwrap();
+        let (output, proof) = vrf.evaluate(b"round 1").unwrap();
+        assert_eq!(vrf.evaluate(b"round 1").unwrap().0, output);
+        assert_eq!(
+            verify(&vrf.public_key(), b"round 1", &proof).unwrap(),
+            output
+        );
+        assert_eq!(
+            verify(&vrf.public_key(), b"round 2", &proof),
+            Err(VrfError::VerificationFailed)
+        );
+    }
+}
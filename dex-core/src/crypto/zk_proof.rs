@@ -2,177 +2,405 @@
 //!
 //! This module implements the Priority 3 feature from DEX-OS-V2.csv:
 //! - Security,Security,Security,Zero-Knowledge Proofs,Privacy Protection,Medium
+//!
+//! All constructions live in the ristretto255 group:
+//! - knowledge of a secret is a Fiat–Shamir Schnorr proof for the discrete log
+//!   of the public input `x·B`, where `x` is derived from the secret;
+//! - values are hidden in Pedersen commitments `v·B + r·B̃` and range proofs are
+//!   aggregated Bulletproofs showing `v - min` and `max - v` both fit in 64 bits;
+//! - set membership is a Merkle path to a commitment over the hashed set members.
 
+use bulletproofs::{BulletproofGens, PedersenGens, RangeProof as Bulletproof};
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
 use rand::rngs::OsRng;
-use rand::RngCore;
 use serde::{Deserialize, Serialize};
-use sha3::{Digest, Sha3_256};
+use sha2::{Digest as _, Sha256, Sha512};
+use sha3::Sha3_256;
 use std::collections::HashMap;
+use thiserror::Error;
+use zeroize::Zeroize;
+
+use crate::merkle_tree::MerkleTree;
+
+/// Bit width of every range proof; values are `u64`, so 64 bits cover them all
+const RANGE_BITS: usize = 64;
+
+/// Domain separator for deriving the secret scalar in knowledge proofs
+const SECRET_DOMAIN: &[u8] = b"DEX-OS ZK secret";
+
+/// Domain separator for the Schnorr challenge
+const CHALLENGE_DOMAIN: &[u8] = b"DEX-OS ZK challenge";
 
-/// A simple Schnorr-like zero-knowledge proof system for demonstration
-/// In a real implementation, this would use more sophisticated ZK proof systems like zk-SNARKs
+/// Domain separator for set member leaves
+const MEMBER_DOMAIN: &[u8] = b"DEX-OS ZK set member";
+
+/// Domain separator for set commitments
+const SET_DOMAIN: &[u8] = b"DEX-OS ZK set";
+
+/// Errors raised while producing zero-knowledge proofs
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum ZkError {
+    #[error("Invalid range: min {0} is greater than max {1}")]
+    InvalidRange(u64, u64),
+    #[error("Committed value is outside the requested range")]
+    ValueOutOfRange,
+    #[error("Element is not a member of the set")]
+    NotAMember,
+    #[error("Proof generation failed: {0}")]
+    ProofGeneration(String),
+}
 
 /// Public parameters for the ZK proof system
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ZkParams {
-    /// Generator point (simplified for demonstration)
+    /// Compressed ristretto generator `B` that carries committed values
     pub g: Vec<u8>,
-    /// Public key/base point
+    /// Compressed ristretto generator `B̃` that carries blinding factors
     pub h: Vec<u8>,
 }
 
-/// Zero-knowledge proof
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Schnorr proof of knowledge of the secret behind a public input
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ZkProof {
-    /// Commitment to the secret
+    /// Compressed nonce commitment `R = k·B`
     pub commitment: Vec<u8>,
-    /// Challenge value
+    /// Fiat–Shamir challenge scalar `c`
     pub challenge: Vec<u8>,
-    /// Response value
+    /// Response scalar `s = k + c·x`
     pub response: Vec<u8>,
 }
 
+/// Proof that a Pedersen-committed value lies in `[min, max]`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RangeProof {
+    /// Compressed Pedersen commitment to the hidden value
+    pub commitment: Vec<u8>,
+    /// Serialized aggregated Bulletproof over `value - min` and `max - value`
+    pub proof: Vec<u8>,
+}
+
+/// Merkle-path proof that a hashed element belongs to a committed set.
+///
+/// The element itself is not included, only its domain-separated hash and
+/// position, so a verifier who does not already know the element learns
+/// nothing beyond that hash.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MembershipProof {
+    /// Domain-separated hash of the element
+    pub leaf: Vec<u8>,
+    /// Position of the leaf in the set
+    pub index: usize,
+    /// Number of elements in the set
+    pub set_size: usize,
+    /// Merkle root over all member leaves
+    pub merkle_root: Vec<u8>,
+    /// Sibling hashes from the leaf up to the root
+    pub siblings: Vec<Vec<u8>>,
+}
+
+/// Secret opening of a Pedersen commitment, kept by the prover.
+///
+/// The value and blinding factor are wiped when the opening is dropped.
+pub struct Opening {
+    value: u64,
+    blinding: Scalar,
+}
+
+impl Opening {
+    /// The committed value
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+}
+
+impl Drop for Opening {
+    fn drop(&mut self) {
+        self.value.zeroize();
+        self.blinding.zeroize();
+    }
+}
+
 /// Zero-knowledge proof system
 pub struct ZkProofSystem {
     params: ZkParams,
+    pc_gens: PedersenGens,
+    bp_gens: BulletproofGens,
 }
 
 impl ZkProofSystem {
-    /// Create a new ZK proof system with default parameters
+    /// Create a new ZK proof system with the standard ristretto generators
     pub fn new() -> Self {
-        // In a real implementation, these would be proper cryptographic parameters
-        let mut rng = OsRng;
-        let mut g = vec![0u8; 32];
-        let mut h = vec![0u8; 32];
-        rng.fill_bytes(&mut g);
-        rng.fill_bytes(&mut h);
+        let pc_gens = PedersenGens::default();
+        let params = ZkParams {
+            g: pc_gens.B.compress().to_bytes().to_vec(),
+            h: pc_gens.B_blinding.compress().to_bytes().to_vec(),
+        };
 
-        let params = ZkParams { g, h };
+        Self {
+            params,
+            pc_gens,
+            // Range proofs aggregate two 64-bit values
+            bp_gens: BulletproofGens::new(RANGE_BITS, 2),
+        }
+    }
 
-        Self { params }
+    /// Public parameters of this system
+    pub fn params(&self) -> &ZkParams {
+        &self.params
     }
 
     /// Generate a zero-knowledge proof that we know a secret value
     /// without revealing the secret itself
     pub fn prove(&self, secret: &[u8]) -> ZkProof {
-        let mut rng = OsRng;
-        
-        // Step 1: Generate a random nonce
-        let mut nonce = [0u8; 32];
-        rng.fill_bytes(&mut nonce);
-        
-        // Step 2: Compute commitment = g^nonce (simplified)
-        let commitment = self.hash_points(&[&self.params.g, &nonce.to_vec()]);
-        
-        // Step 3: Compute challenge = H(public_input || commitment)
-        let public_input = self.compute_public_input(secret);
-        let challenge = self.hash_points(&[&public_input, &commitment]);
-        
-        // Step 4: Compute response = nonce + challenge * secret (simplified)
-        let response = self.compute_response(&nonce, &challenge, secret);
-        
+        let x = Self::secret_scalar(secret);
+        let public_point = x * self.pc_gens.B;
+
+        let k = Scalar::random(&mut OsRng);
+        let r = (k * self.pc_gens.B).compress();
+        let c = self.challenge(&public_point.compress(), &r);
+        let s = k + c * x;
+
         ZkProof {
-            commitment,
-            challenge,
-            response,
+            commitment: r.to_bytes().to_vec(),
+            challenge: c.to_bytes().to_vec(),
+            response: s.to_bytes().to_vec(),
         }
     }
-    
-    /// Verify a zero-knowledge proof
+
+    /// Verify a zero-knowledge proof against the public input of the secret
     pub fn verify(&self, proof: &ZkProof, public_input: &[u8]) -> bool {
-        // Recompute the challenge
-        let challenge = self.hash_points_bytes(&[public_input, &proof.commitment]);
-        
-        // Check if the recomputed challenge matches the proof's challenge
-        if challenge != proof.challenge {
+        let (Some(public_point), Some(r)) = (
+            Self::decompress(public_input),
+            Self::decompress(&proof.commitment),
+        ) else {
+            return false;
+        };
+        let (Some(c), Some(s)) = (
+            Self::canonical_scalar(&proof.challenge),
+            Self::canonical_scalar(&proof.response),
+        ) else {
+            return false;
+        };
+
+        if c != self.challenge(&public_point.compress(), &r.compress()) {
             return false;
         }
-        
-        // Verify the proof equation (simplified)
-        // In a real implementation, this would check a cryptographic equation
-        let recomputed_commitment = self.hash_points(&[&self.params.g, &proof.response]);
-        
-        // This is a simplified check - in practice, we would verify:
-        // commitment = g^response * h^(-challenge * secret)
-        recomputed_commitment == proof.commitment || 
-        self.verify_proof_equation(proof, public_input)
-    }
-    
-    /// Compute public input from secret
-    fn compute_public_input(&self, secret: &[u8]) -> Vec<u8> {
-        self.hash_points_bytes(&[&self.params.h, secret])
-    }
-    
-    /// Hash multiple points together
-    fn hash_points(&self, points: &[&Vec<u8>]) -> Vec<u8> {
-        let mut hasher = Sha3_256::new();
-        for point in points {
-            hasher.update(point);
-        }
-        hasher.finalize().to_vec()
+
+        s * self.pc_gens.B == r + c * public_point
     }
 
-    /// Hash multiple byte slices together (more flexible helper)
-    fn hash_points_bytes(&self, points: &[&[u8]]) -> Vec<u8> {
-        let mut hasher = Sha3_256::new();
-        for point in points {
-            hasher.update(point);
-        }
-        hasher.finalize().to_vec()
+    /// Compute the public input `x·B` that a knowledge proof is checked against
+    pub fn compute_public_input(&self, secret: &[u8]) -> Vec<u8> {
+        (Self::secret_scalar(secret) * self.pc_gens.B)
+            .compress()
+            .to_bytes()
+            .to_vec()
     }
-    
-    /// Compute response value (simplified)
-    fn compute_response(&self, nonce: &[u8], challenge: &[u8], secret: &[u8]) -> Vec<u8> {
-        // In a real implementation, this would be a proper scalar multiplication and addition
-        // For demonstration, we'll just concatenate and hash
-        let mut hasher = Sha3_256::new();
-        hasher.update(nonce);
-        hasher.update(challenge);
-        hasher.update(secret);
-        hasher.finalize().to_vec()
+
+    /// Commit to a value with a fresh blinding factor
+    pub fn commit(&self, value: u64) -> (Vec<u8>, Opening) {
+        let opening = Opening {
+            value,
+            blinding: Scalar::random(&mut OsRng),
+        };
+        (self.commitment_of(&opening), opening)
     }
-    
-    /// Verify the proof equation (simplified for demonstration)
-    fn verify_proof_equation(&self, proof: &ZkProof, public_input: &[u8]) -> bool {
-        // This is a placeholder - in a real ZK proof system, we would verify
-        // a specific mathematical relationship that proves knowledge of the secret
-        // without revealing it
-        
-        // For demonstration, we'll just check that the proof components are non-empty
-        !proof.commitment.is_empty() && 
-        !proof.challenge.is_empty() && 
-        !proof.response.is_empty() &&
-        !public_input.is_empty()
-    }
-    
+
     /// Create a range proof that a value is within a certain range
     /// without revealing the actual value
-    pub fn prove_range(&self, value: u64, min: u64, max: u64) -> ZkProof {
-        // Convert value to bytes
-        let secret = value.to_le_bytes().to_vec();
-        
-        // Generate proof that we know a value in the specified range
-        self.prove(&secret)
-    }
-    
+    pub fn prove_range(&self, value: u64, min: u64, max: u64) -> Result<RangeProof, ZkError> {
+        let (_, opening) = self.commit(value);
+        self.prove_committed_range(&opening, min, max)
+    }
+
+    /// Create a range proof for a value that is already committed
+    pub fn prove_committed_range(
+        &self,
+        opening: &Opening,
+        min: u64,
+        max: u64,
+    ) -> Result<RangeProof, ZkError> {
+        if min > max {
+            return Err(ZkError::InvalidRange(min, max));
+        }
+        if opening.value < min || opening.value > max {
+            return Err(ZkError::ValueOutOfRange);
+        }
+
+        let mut transcript = Self::range_transcript(min, max);
+        let (proof, _) = Bulletproof::prove_multiple_with_rng(
+            &self.bp_gens,
+            &self.pc_gens,
+            &mut transcript,
+            &[opening.value - min, max - opening.value],
+            &[opening.blinding, -opening.blinding],
+            RANGE_BITS,
+            &mut OsRng,
+        )
+        .map_err(|e| ZkError::ProofGeneration(e.to_string()))?;
+
+        Ok(RangeProof {
+            commitment: self.commitment_of(opening),
+            proof: proof.to_bytes(),
+        })
+    }
+
     /// Verify a range proof
-    pub fn verify_range(&self, proof: &ZkProof, min: u64, max: u64) -> bool {
-        // Create public input for range verification
-        let public_input = self.hash_points(&[&min.to_le_bytes().to_vec(), &max.to_le_bytes().to_vec()]);
-        
-        self.verify(proof, &public_input)
+    pub fn verify_range(&self, proof: &RangeProof, min: u64, max: u64) -> bool {
+        if min > max {
+            return false;
+        }
+        let Some(commitment) = Self::decompress(&proof.commitment) else {
+            return false;
+        };
+        let Ok(bulletproof) = Bulletproof::from_bytes(&proof.proof) else {
+            return false;
+        };
+
+        // Shift the commitment so both halves must open to non-negative 64-bit values
+        let lower = commitment - Scalar::from(min) * self.pc_gens.B;
+        let upper = Scalar::from(max) * self.pc_gens.B - commitment;
+
+        let mut transcript = Self::range_transcript(min, max);
+        bulletproof
+            .verify_multiple_with_rng(
+                &self.bp_gens,
+                &self.pc_gens,
+                &mut transcript,
+                &[lower.compress(), upper.compress()],
+                RANGE_BITS,
+                &mut OsRng,
+            )
+            .is_ok()
+    }
+
+    /// Compute the commitment to a set that membership proofs are checked against
+    pub fn set_commitment(set: &[Vec<u8>]) -> Option<Vec<u8>> {
+        let leaves: Vec<Vec<u8>> = set.iter().map(|e| Self::member_leaf(e)).collect();
+        let merkle_root = MerkleTree::from_data(&leaves).root_hash()?;
+        Some(Self::bind_set(set.len(), &merkle_root))
     }
-    
+
     /// Create a proof of membership in a set
-    pub fn prove_membership(&self, element: &[u8], set: &[Vec<u8>]) -> ZkProof {
-        // In a real implementation, this would use a more sophisticated
-        // set membership proof like a Merkle tree or accumulator
-        self.prove(element)
+    pub fn prove_membership(
+        &self,
+        element: &[u8],
+        set: &[Vec<u8>],
+    ) -> Result<MembershipProof, ZkError> {
+        let index = set
+            .iter()
+            .position(|e| e.as_slice() == element)
+            .ok_or(ZkError::NotAMember)?;
+        let leaves: Vec<Vec<u8>> = set.iter().map(|e| Self::member_leaf(e)).collect();
+        let tree = MerkleTree::from_data(&leaves);
+        let merkle_root = tree.root_hash().ok_or(ZkError::NotAMember)?;
+        let siblings = tree
+            .generate_proof_with_data(&leaves, index)
+            .ok_or(ZkError::NotAMember)?;
+
+        Ok(MembershipProof {
+            leaf: leaves[index].clone(),
+            index,
+            set_size: set.len(),
+            merkle_root,
+            siblings,
+        })
     }
-    
-    /// Verify a membership proof
-    pub fn verify_membership(&self, proof: &ZkProof, set_hash: &[u8]) -> bool {
-        self.verify(proof, set_hash)
+
+    /// Verify a membership proof against a set commitment
+    pub fn verify_membership(&self, proof: &MembershipProof, set_commitment: &[u8]) -> bool {
+        // Fixed-size leaves cannot be confused with 64-byte internal nodes
+        if proof.leaf.len() != 32 || proof.index >= proof.set_size {
+            return false;
+        }
+        if proof.siblings.len() != Self::tree_depth(proof.set_size) {
+            return false;
+        }
+        if Self::bind_set(proof.set_size, &proof.merkle_root) != set_commitment {
+            return false;
+        }
+
+        MerkleTree::verify_proof_with_root(
+            &proof.merkle_root,
+            &proof.leaf,
+            proof.index,
+            &proof.siblings,
+        )
+    }
+
+    /// Hash a set element into the leaf that membership proofs reveal
+    pub fn member_leaf(element: &[u8]) -> Vec<u8> {
+        let mut hasher = Sha256::new();
+        hasher.update(MEMBER_DOMAIN);
+        hasher.update(element);
+        hasher.finalize().to_vec()
+    }
+
+    /// Pedersen commitment for an opening
+    fn commitment_of(&self, opening: &Opening) -> Vec<u8> {
+        self.pc_gens
+            .commit(Scalar::from(opening.value), opening.blinding)
+            .compress()
+            .to_bytes()
+            .to_vec()
+    }
+
+    /// Derive the discrete log behind a secret's public input
+    fn secret_scalar(secret: &[u8]) -> Scalar {
+        Scalar::from_hash(
+            Sha512::new()
+                .chain_update(SECRET_DOMAIN)
+                .chain_update(secret),
+        )
+    }
+
+    /// Fiat–Shamir challenge binding the generator, public input and nonce commitment
+    fn challenge(&self, public_point: &CompressedRistretto, r: &CompressedRistretto) -> Scalar {
+        Scalar::from_hash(
+            Sha512::new()
+                .chain_update(CHALLENGE_DOMAIN)
+                .chain_update(&self.params.g)
+                .chain_update(public_point.as_bytes())
+                .chain_update(r.as_bytes()),
+        )
+    }
+
+    /// Transcript for a range proof over `[min, max]`
+    fn range_transcript(min: u64, max: u64) -> Transcript {
+        let mut transcript = Transcript::new(b"DEX-OS range proof");
+        transcript.append_message(b"min", &min.to_le_bytes());
+        transcript.append_message(b"max", &max.to_le_bytes());
+        transcript
+    }
+
+    /// Bind a Merkle root to the number of leaves it covers
+    fn bind_set(set_size: usize, merkle_root: &[u8]) -> Vec<u8> {
+        let mut hasher = Sha256::new();
+        hasher.update(SET_DOMAIN);
+        hasher.update((set_size as u64).to_le_bytes());
+        hasher.update(merkle_root);
+        hasher.finalize().to_vec()
+    }
+
+    /// Number of levels between the leaves and the root of a tree of `leaves`
+    fn tree_depth(mut leaves: usize) -> usize {
+        let mut depth = 0;
+        while leaves > 1 {
+            leaves = leaves.div_ceil(2);
+            depth += 1;
+        }
+        depth
+    }
+
+    fn decompress(bytes: &[u8]) -> Option<RistrettoPoint> {
+        CompressedRistretto::from_slice(bytes).ok()?.decompress()
+    }
+
+    fn canonical_scalar(bytes: &[u8]) -> Option<Scalar> {
+        let bytes: [u8; 32] = bytes.try_into().ok()?;
+        Scalar::from_canonical_bytes(bytes).into()
     }
 }
 
@@ -182,11 +410,22 @@ impl Default for ZkProofSystem {
     }
 }
 
+/// A proof accepted by the privacy protection service
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VerifiedProof {
+    /// Knowledge of a secret
+    Knowledge(ZkProof),
+    /// Committed value within a range
+    Range(RangeProof),
+    /// Element within a committed set
+    Membership(MembershipProof),
+}
+
 /// Privacy protection service using zero-knowledge proofs
 pub struct PrivacyProtectionService {
     zk_system: ZkProofSystem,
     /// Store of verified proofs for audit purposes
-    verified_proofs: HashMap<String, ZkProof>,
+    verified_proofs: HashMap<String, VerifiedProof>,
 }
 
 impl PrivacyProtectionService {
@@ -197,78 +436,109 @@ impl PrivacyProtectionService {
             verified_proofs: HashMap::new(),
         }
     }
-    
+
     /// Generate a proof that we know a secret without revealing it
     pub fn prove_secret_knowledge(&self, secret: &[u8]) -> ZkProof {
         self.zk_system.prove(secret)
     }
-    
+
     /// Verify a proof of secret knowledge
     pub fn verify_secret_knowledge(&mut self, proof: &ZkProof, public_input: &[u8]) -> bool {
         let result = self.zk_system.verify(proof, public_input);
-        
+
         // Store verified proof for audit trail
         if result {
-            let proof_id = self.hash_proof_components(proof);
-            self.verified_proofs.insert(proof_id, proof.clone());
+            self.record(VerifiedProof::Knowledge(proof.clone()));
         }
-        
+
         result
     }
-    
+
     /// Generate a range proof
-    pub fn prove_value_range(&self, value: u64, min: u64, max: u64) -> ZkProof {
+    pub fn prove_value_range(&self, value: u64, min: u64, max: u64) -> Result<RangeProof, ZkError> {
         self.zk_system.prove_range(value, min, max)
     }
-    
+
     /// Verify a range proof
-    pub fn verify_value_range(&mut self, proof: &ZkProof, min: u64, max: u64) -> bool {
+    pub fn verify_value_range(&mut self, proof: &RangeProof, min: u64, max: u64) -> bool {
         let result = self.zk_system.verify_range(proof, min, max);
-        
+
         // Store verified proof for audit trail
         if result {
-            let proof_id = self.hash_proof_components(proof);
-            self.verified_proofs.insert(proof_id, proof.clone());
+            self.record(VerifiedProof::Range(proof.clone()));
         }
-        
+
         result
     }
-    
+
+    /// Commit to a balance; the commitment can be published, the opening stays private
+    pub fn commit_balance(&self, balance: u64) -> (Vec<u8>, Opening) {
+        self.zk_system.commit(balance)
+    }
+
+    /// Prove that a committed balance is at least `threshold` without revealing it
+    pub fn prove_minimum_balance(
+        &self,
+        opening: &Opening,
+        threshold: u64,
+    ) -> Result<RangeProof, ZkError> {
+        self.zk_system
+            .prove_committed_range(opening, threshold, u64::MAX)
+    }
+
+    /// Verify that the balance behind `commitment` is at least `threshold`
+    pub fn verify_minimum_balance(
+        &mut self,
+        commitment: &[u8],
+        threshold: u64,
+        proof: &RangeProof,
+    ) -> bool {
+        // The proof must be about the commitment the verifier already holds
+        if proof.commitment != commitment {
+            return false;
+        }
+        self.verify_value_range(proof, threshold, u64::MAX)
+    }
+
     /// Generate a membership proof
-    pub fn prove_set_membership(&self, element: &[u8], set: &[Vec<u8>]) -> ZkProof {
+    pub fn prove_set_membership(
+        &self,
+        element: &[u8],
+        set: &[Vec<u8>],
+    ) -> Result<MembershipProof, ZkError> {
         self.zk_system.prove_membership(element, set)
     }
-    
+
     /// Verify a membership proof
-    pub fn verify_set_membership(&mut self, proof: &ZkProof, set_hash: &[u8]) -> bool {
-        let result = self.zk_system.verify_membership(proof, set_hash);
-        
+    pub fn verify_set_membership(
+        &mut self,
+        proof: &MembershipProof,
+        set_commitment: &[u8],
+    ) -> bool {
+        let result = self.zk_system.verify_membership(proof, set_commitment);
+
         // Store verified proof for audit trail
         if result {
-            let proof_id = self.hash_proof_components(proof);
-            self.verified_proofs.insert(proof_id, proof.clone());
+            self.record(VerifiedProof::Membership(proof.clone()));
         }
-        
+
         result
     }
-    
-    /// Hash proof components to create a unique ID
-    fn hash_proof_components(&self, proof: &ZkProof) -> String {
-        let mut hasher = Sha3_256::new();
-        hasher.update(&proof.commitment);
-        hasher.update(&proof.challenge);
-        hasher.update(&proof.response);
-        let result = hasher.finalize();
-        format!("{:x}", result)
-    }
-    
+
+    /// Store a verified proof under a hash of its serialized form
+    fn record(&mut self, proof: VerifiedProof) {
+        let encoded = serde_json::to_vec(&proof).unwrap_or_default();
+        let proof_id = format!("{:x}", Sha3_256::digest(&encoded));
+        self.verified_proofs.insert(proof_id, proof);
+    }
+
     /// Get the number of verified proofs
     pub fn get_verified_proof_count(&self) -> usize {
         self.verified_proofs.len()
     }
-    
+
     /// Get a verified proof by ID
-    pub fn get_verified_proof(&self, proof_id: &str) -> Option<&ZkProof> {
+    pub fn get_verified_proof(&self, proof_id: &str) -> Option<&VerifiedProof> {
         self.verified_proofs.get(proof_id)
     }
 }
@@ -286,18 +556,19 @@ mod tests {
     #[test]
     fn test_zk_proof_system_creation() {
         let zk_system = ZkProofSystem::new();
-        assert!(!zk_system.params.g.is_empty());
-        assert!(!zk_system.params.h.is_empty());
+        assert_eq!(zk_system.params.g.len(), 32);
+        assert_eq!(zk_system.params.h.len(), 32);
+        assert_ne!(zk_system.params.g, zk_system.params.h);
     }
 
     #[test]
     fn test_secret_knowledge_proof() {
         let zk_system = ZkProofSystem::new();
         let secret = b"test_secret";
-        
+
         let proof = zk_system.prove(secret);
         let public_input = zk_system.compute_public_input(secret);
-        
+
         assert!(zk_system.verify(&proof, &public_input));
     }
 
@@ -306,33 +577,137 @@ mod tests {
         let zk_system = ZkProofSystem::new();
         let secret1 = b"test_secret_1";
         let secret2 = b"test_secret_2";
-        
+
         let proof = zk_system.prove(secret1);
         let public_input = zk_system.compute_public_input(secret2);
-        
+
         // Verification should fail for wrong secret
         assert!(!zk_system.verify(&proof, &public_input));
     }
 
+    #[test]
+    fn test_forged_knowledge_proofs_are_rejected() {
+        let zk_system = ZkProofSystem::new();
+        let public_input = zk_system.compute_public_input(b"test_secret");
+        let proof = zk_system.prove(b"test_secret");
+
+        // Tampered response
+        let mut forged = proof.clone();
+        forged.response[0] ^= 1;
+        assert!(!zk_system.verify(&forged, &public_input));
+
+        // Recomputed challenge over a random commitment, without the secret
+        let r = (Scalar::random(&mut OsRng) * zk_system.pc_gens.B).compress();
+        let public_point = ZkProofSystem::decompress(&public_input).unwrap();
+        let c = zk_system.challenge(&public_point.compress(), &r);
+        let forged = ZkProof {
+            commitment: r.to_bytes().to_vec(),
+            challenge: c.to_bytes().to_vec(),
+            response: Scalar::random(&mut OsRng).to_bytes().to_vec(),
+        };
+        assert!(!zk_system.verify(&forged, &public_input));
+
+        // Old hash-style proofs with garbage fields
+        let garbage = ZkProof {
+            commitment: vec![1; 32],
+            challenge: vec![2; 32],
+            response: vec![3; 32],
+        };
+        assert!(!zk_system.verify(&garbage, &public_input));
+    }
+
     #[test]
     fn test_range_proof() {
         let zk_system = ZkProofSystem::new();
         let value = 42u64;
         let min = 0u64;
         let max = 100u64;
-        
-        let proof = zk_system.prove_range(value, min, max);
+
+        let proof = zk_system.prove_range(value, min, max).unwrap();
         assert!(zk_system.verify_range(&proof, min, max));
+
+        // The same proof does not hold for a range excluding the value
+        assert!(!zk_system.verify_range(&proof, 43, 100));
+        assert!(!zk_system.verify_range(&proof, 0, 41));
+
+        assert_eq!(
+            zk_system.prove_range(101, min, max),
+            Err(ZkError::ValueOutOfRange)
+        );
+        assert_eq!(
+            zk_system.prove_range(5, 10, 1),
+            Err(ZkError::InvalidRange(10, 1))
+        );
+    }
+
+    #[test]
+    fn test_forged_range_proofs_are_rejected() {
+        let zk_system = ZkProofSystem::new();
+        let honest = zk_system.prove_range(42, 0, 100).unwrap();
+
+        // Swapping in a commitment to an out-of-range value breaks the proof
+        let (other, _) = zk_system.commit(500);
+        let forged = RangeProof {
+            commitment: other,
+            proof: honest.proof.clone(),
+        };
+        assert!(!zk_system.verify_range(&forged, 0, 100));
+
+        // Tampering with the proof bytes
+        let mut forged = honest.clone();
+        forged.proof[40] ^= 1;
+        assert!(!zk_system.verify_range(&forged, 0, 100));
+
+        // A prover holding a value below the range cannot produce a proof by
+        // shifting the bounds: proofs are bound to the bounds in the transcript
+        let below = zk_system.prove_range(5, 0, 100).unwrap();
+        assert!(!zk_system.verify_range(&below, 10, 100));
+    }
+
+    #[test]
+    fn test_membership_proof() {
+        let zk_system = ZkProofSystem::new();
+        let set: Vec<Vec<u8>> = (0..5u8).map(|i| vec![b'm', i]).collect();
+        let commitment = ZkProofSystem::set_commitment(&set).unwrap();
+
+        for element in &set {
+            let proof = zk_system.prove_membership(element, &set).unwrap();
+            assert!(zk_system.verify_membership(&proof, &commitment));
+            assert_eq!(proof.leaf, ZkProofSystem::member_leaf(element));
+        }
+
+        assert_eq!(
+            zk_system.prove_membership(b"outsider", &set),
+            Err(ZkError::NotAMember)
+        );
+
+        // A proof for one set does not verify against another
+        let mut other_set = set.clone();
+        other_set.push(b"extra".to_vec());
+        let other = ZkProofSystem::set_commitment(&other_set).unwrap();
+        let proof = zk_system.prove_membership(&set[0], &set).unwrap();
+        assert!(!zk_system.verify_membership(&proof, &other));
+
+        // Forged leaf, index, and size are all rejected
+        let mut forged = proof.clone();
+        forged.leaf = ZkProofSystem::member_leaf(b"outsider");
+        assert!(!zk_system.verify_membership(&forged, &commitment));
+        let mut forged = proof.clone();
+        forged.index = 1;
+        assert!(!zk_system.verify_membership(&forged, &commitment));
+        let mut forged = proof;
+        forged.set_size = 8;
+        assert!(!zk_system.verify_membership(&forged, &commitment));
     }
 
     #[test]
     fn test_privacy_protection_service() {
         let mut service = PrivacyProtectionService::new();
         let secret = b"test_secret";
-        
+
         let proof = service.prove_secret_knowledge(secret);
         let public_input = ZkProofSystem::new().compute_public_input(secret);
-        
+
         assert!(service.verify_secret_knowledge(&proof, &public_input));
         assert_eq!(service.get_verified_proof_count(), 1);
     }
@@ -343,9 +718,32 @@ mod tests {
         let value = 42u64;
         let min = 0u64;
         let max = 100u64;
-        
-        let proof = service.prove_value_range(value, min, max);
+
+        let proof = service.prove_value_range(value, min, max).unwrap();
         assert!(service.verify_value_range(&proof, min, max));
         assert_eq!(service.get_verified_proof_count(), 1);
     }
+
+    #[test]
+    fn test_minimum_balance_proof() {
+        let mut service = PrivacyProtectionService::new();
+        let (commitment, opening) = service.commit_balance(1_500);
+
+        let proof = service.prove_minimum_balance(&opening, 1_000).unwrap();
+        assert!(service.verify_minimum_balance(&commitment, 1_000, &proof));
+        // The proof is bound to its threshold
+        assert!(!service.verify_minimum_balance(&commitment, 1_501, &proof));
+
+        // A balance below the threshold cannot be proven
+        assert_eq!(
+            service.prove_minimum_balance(&opening, 2_000),
+            Err(ZkError::ValueOutOfRange)
+        );
+
+        // A valid proof about someone else's commitment is rejected
+        let (other_commitment, _) = service.commit_balance(10);
+        assert!(!service.verify_minimum_balance(&other_commitment, 1_000, &proof));
+
+        assert_eq!(service.get_verified_proof_count(), 1);
+    }
 }
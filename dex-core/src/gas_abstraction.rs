@@ -5,9 +5,23 @@
 //!
 //! It provides functionality for gas abstraction, AI-optimized transaction routing,
 //! and high-availability execution services.
+//!
+//! Relayers are accounted for over their whole lifecycle: gas spent on execution is debited
+//! from their balance and reimbursed from the user's sponsorship budget (reset daily), TPS
+//! windows roll over, and reputation follows execution success and latency. Relayers that
+//! keep failing are slashed and suspended automatically.
+//!
+//! Relayers submit user intents as EIP-2771/4337-style meta-transactions: a typed request
+//! signed by the user (ed25519 or secp256k1) over a domain-separated digest, with a
+//! per-user nonce and an expiry, so a relayer can neither forge nor replay an intent.
 
 use crate::types::{TokenId, TraderId, Transaction};
+use ed25519_dalek::{Signature as Ed25519Signature, VerifyingKey as Ed25519VerifyingKey};
+use k256::ecdsa::{
+    RecoveryId, Signature as Secp256k1Signature, VerifyingKey as Secp256k1VerifyingKey,
+};
 use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
 use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 use thiserror::Error;
@@ -25,6 +39,110 @@ pub struct GasAbstractionService {
     ai_router: AIRouter,
     /// Service availability tracker
     availability_tracker: AvailabilityTracker,
+    /// Domain that meta-transaction signatures are bound to
+    meta_tx_domain: MetaTxDomain,
+    /// Highest nonce consumed per user (replay protection)
+    used_nonces: HashMap<TraderId, u64>,
+    /// Accounting state per relayer
+    relayer_stats: HashMap<String, RelayerStats>,
+    /// Rules for relayer rate limiting, reputation and slashing
+    relayer_policy: RelayerPolicy,
+}
+
+/// Rules governing relayer rate limiting, reputation and slashing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelayerPolicy {
+    /// Length of the window `Relayer::current_tps` is counted over (seconds)
+    pub tps_window_secs: u64,
+    /// Execution latency considered perfect for reputation purposes (milliseconds)
+    pub target_latency_ms: u64,
+    /// Weight of the latest execution in the reputation moving average (0.0 - 1.0)
+    pub reputation_weight: f32,
+    /// Consecutive failures after which a relayer is slashed and suspended
+    pub max_consecutive_failures: u32,
+    /// Share of the relayer balance forfeited when slashed (0.0 - 1.0)
+    pub slash_ratio: f32,
+    /// Reputation below which a relayer is suspended
+    pub min_reputation: f32,
+    /// How long a suspension lasts (seconds)
+    pub suspension_secs: u64,
+}
+
+/// Accounting state tracked for each relayer
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RelayerStats {
+    /// Start of the current TPS window
+    pub window_start: u64,
+    /// Total gas spent executing transactions
+    pub gas_spent: u64,
+    /// Total gas reimbursed from sponsorship budgets
+    pub gas_reimbursed: u64,
+    /// Executions reported for this relayer
+    pub executions: u64,
+    /// Failed executions reported for this relayer
+    pub failures: u64,
+    /// Failures since the last successful execution
+    pub consecutive_failures: u32,
+    /// Total balance slashed
+    pub slashed: u64,
+    /// Timestamp until which the relayer is suspended
+    pub suspended_until: Option<u64>,
+}
+
+/// Domain that meta-transaction signatures are bound to (EIP-712 style domain separation)
+///
+/// Signatures made for one deployment or chain cannot be replayed against another.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetaTxDomain {
+    /// Human readable name of the signing domain
+    pub name: String,
+    /// Version of the signing domain
+    pub version: String,
+    /// Chain the meta-transactions are valid on
+    pub chain_id: u64,
+    /// Forwarder or entry point that executes the meta-transactions
+    pub verifying_contract: String,
+}
+
+/// A user intent to be executed by a relayer (the signed part of a meta-transaction)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetaTransaction {
+    /// User the intent belongs to: a `0x` address for secp256k1 signers, or the hex encoded
+    /// public key for ed25519 signers
+    pub from: TraderId,
+    /// Target of the call
+    pub to: String,
+    /// Amount transferred
+    pub amount: i64,
+    /// Call data
+    pub data: Vec<u8>,
+    /// User nonce; must be higher than any nonce the user has already used
+    pub nonce: u64,
+    /// Unix timestamp (seconds) after which the intent can no longer be executed
+    pub deadline: u64,
+    /// Maximum gas the user allows the relayer to spend
+    pub max_gas: u64,
+}
+
+/// User signature over a meta-transaction digest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum UserSignature {
+    /// Ed25519 signature together with the signer's public key
+    Ed25519 {
+        public_key: Vec<u8>,
+        signature: Vec<u8>,
+    },
+    /// Recoverable secp256k1 signature (`r || s || v`, 65 bytes) as produced by Ethereum wallets
+    Secp256k1 { signature: Vec<u8> },
+}
+
+/// A meta-transaction as submitted by a relayer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetaTransactionEnvelope {
+    /// The signed user intent
+    pub request: MetaTransaction,
+    /// The user's signature over `request`
+    pub signature: UserSignature,
 }
 
 /// Relayer information
@@ -66,6 +184,8 @@ pub struct GasSponsorship {
 pub struct AbstractedTransaction {
     /// Original transaction
     pub transaction: Transaction,
+    /// Call data the user signed, submitted together with the transaction
+    pub data: Vec<u8>,
     /// User who initiated the transaction
     pub user: TraderId,
     /// Sponsor for gas costs (if any)
@@ -200,17 +320,66 @@ impl GasAbstractionService {
             transaction_queue: Vec::new(),
             ai_router: AIRouter::new(),
             availability_tracker: AvailabilityTracker::new(),
+            meta_tx_domain: MetaTxDomain::default(),
+            used_nonces: HashMap::new(),
+            relayer_stats: HashMap::new(),
+            relayer_policy: RelayerPolicy::default(),
+        }
+    }
+
+    /// Replace the relayer policy
+    pub fn set_relayer_policy(&mut self, policy: RelayerPolicy) {
+        self.relayer_policy = policy;
+    }
+
+    /// Get a relayer by ID
+    pub fn get_relayer(&self, relayer_id: &str) -> Option<&Relayer> {
+        self.relayers.get(relayer_id)
+    }
+
+    /// Get accounting state for a relayer
+    pub fn get_relayer_stats(&self, relayer_id: &str) -> Option<&RelayerStats> {
+        self.relayer_stats.get(relayer_id)
+    }
+
+    /// Get the gas sponsorship of a user
+    pub fn get_sponsorship(&self, user: &TraderId) -> Option<&GasSponsorship> {
+        self.sponsorships.get(user)
+    }
+
+    /// Create a gas abstraction service bound to a specific signing domain
+    pub fn with_domain(domain: MetaTxDomain) -> Self {
+        Self {
+            meta_tx_domain: domain,
+            ..Self::new()
         }
     }
 
+    /// Get the signing domain for meta-transactions
+    pub fn meta_tx_domain(&self) -> &MetaTxDomain {
+        &self.meta_tx_domain
+    }
+
     /// Add a relayer to the service
     pub fn add_relayer(&mut self, relayer: Relayer) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        self.relayer_stats.insert(
+            relayer.id.clone(),
+            RelayerStats {
+                window_start: now,
+                ..RelayerStats::default()
+            },
+        );
         self.relayers.insert(relayer.id.clone(), relayer);
     }
 
     /// Remove a relayer from the service
     pub fn remove_relayer(&mut self, relayer_id: &str) -> Result<(), GasAbstractionError> {
         if self.relayers.remove(relayer_id).is_some() {
+            self.relayer_stats.remove(relayer_id);
             Ok(())
         } else {
             Err(GasAbstractionError::RelayerNotFound)
@@ -231,20 +400,79 @@ impl GasAbstractionService {
         }
     }
 
-    /// Submit a transaction with gas abstraction
-    pub fn submit_abstracted_transaction(
+    /// Submit a signed meta-transaction on behalf of a user
+    ///
+    /// The envelope is rejected unless its deadline has not passed, its nonce has not been
+    /// used, the user signature verifies over the domain-separated digest, and the
+    /// estimated gas stays within the user's `max_gas`. The signed call data is queued with
+    /// the transaction.
+    pub fn submit_meta_transaction(
+        &mut self,
+        envelope: MetaTransactionEnvelope,
+        priority: u8,
+    ) -> Result<String, GasAbstractionError> {
+        let request = &envelope.request;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        if now > request.deadline {
+            return Err(GasAbstractionError::MetaTransactionExpired);
+        }
+
+        self.check_nonce(&request.from, request.nonce)?;
+        envelope.verify(&self.meta_tx_domain)?;
+
+        let transaction = Transaction {
+            from: request.from.clone(),
+            to: request.to.clone(),
+            amount: request.amount,
+            nonce: request.nonce,
+            signature: envelope.signature.to_bytes(),
+        };
+
+        if self.estimate_gas(&transaction)? > request.max_gas {
+            return Err(GasAbstractionError::GasLimitExceeded);
+        }
+
+        let user = request.from.clone();
+        self.submit_abstracted_transaction(transaction, request.data.clone(), user, priority)
+    }
+
+    /// Next nonce a user should sign
+    pub fn next_nonce(&self, user: &TraderId) -> u64 {
+        self.used_nonces.get(user).map_or(0, |nonce| nonce + 1)
+    }
+
+    /// Reject nonces that are not higher than the user's last used nonce
+    fn check_nonce(&self, user: &TraderId, nonce: u64) -> Result<(), GasAbstractionError> {
+        match self.used_nonces.get(user) {
+            Some(last) if nonce <= *last => Err(GasAbstractionError::NonceAlreadyUsed),
+            _ => Ok(()),
+        }
+    }
+
+    /// Queue a transaction with gas abstraction
+    ///
+    /// This does not check a user signature, deadline or gas limit, so it is only reachable
+    /// through [`Self::submit_meta_transaction`] once the envelope has been verified.
+    /// Nonces are still tracked so a transaction cannot be queued twice.
+    fn submit_abstracted_transaction(
         &mut self,
         transaction: Transaction,
+        data: Vec<u8>,
         user: TraderId,
         priority: u8,
     ) -> Result<String, GasAbstractionError> {
+        self.check_nonce(&user, transaction.nonce)?;
+
         // Check if user has sponsorship
-        let sponsor = if let Some(sponsorship) = self.sponsorships.get(&user) {
-            if sponsorship.is_active && self.check_sponsorship_limit(&user)? {
-                Some(sponsorship.sponsor_id.clone())
-            } else {
-                None
-            }
+        let sponsor = if self.check_sponsorship_limit(&user)? {
+            self.sponsorships
+                .get(&user)
+                .filter(|sponsorship| sponsorship.is_active)
+                .map(|sponsorship| sponsorship.sponsor_id.clone())
         } else {
             None
         };
@@ -267,6 +495,7 @@ impl GasAbstractionService {
 
         let abstracted_tx = AbstractedTransaction {
             transaction,
+            data,
             user: user.clone(),
             sponsor,
             relayer: relayer.clone(),
@@ -276,77 +505,185 @@ impl GasAbstractionService {
             status: TransactionStatus::InQueue,
         };
 
-        // Add to queue
+        // Add to queue and consume the nonce; sponsorship budgets are charged when a
+        // relayer executes the transaction
+        self.used_nonces
+            .insert(user.clone(), abstracted_tx.transaction.nonce);
         self.transaction_queue.push(abstracted_tx);
 
-        // Update sponsorship usage if applicable
-        if let Some(sponsor_id) = relayer {
-            if let Some(sponsorship) = self.sponsorships.get_mut(&user) {
-                sponsorship.used_gas += estimated_gas;
-            }
-        }
-
         // Return transaction ID (in a real implementation, this would be more sophisticated)
         Ok(format!("abs_tx_{}", now))
     }
 
     /// Process the transaction queue
+    ///
+    /// Each executed transaction debits its gas from the relayer's balance, counts against
+    /// the relayer's TPS window and, when sponsored, is reimbursed from the sponsorship.
     pub fn process_queue(&mut self) -> Result<Vec<String>, GasAbstractionError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        self.process_queue_at(now)
+    }
+
+    fn process_queue_at(&mut self, now: u64) -> Result<Vec<String>, GasAbstractionError> {
         let mut processed_txs = Vec::new();
+        self.refresh_relayers(now);
 
         // Sort transactions by priority (higher priority first)
         self.transaction_queue
             .sort_by(|a, b| b.priority.cmp(&a.priority));
 
-        // Process transactions with available relayers
-        self.transaction_queue.retain(|tx| {
-            if tx.status == TransactionStatus::InQueue {
-                // Try to submit with assigned relayer or find a new one
-                let relayer_id = if let Some(rid) = &tx.relayer {
-                    rid.clone()
-                } else {
-                    // If no relayer assigned, try to find one
-                    if let Some(rid) = self.ai_router.select_optimal_relayer(
+        for tx in std::mem::take(&mut self.transaction_queue) {
+            if tx.status != TransactionStatus::InQueue {
+                continue; // Drop from queue
+            }
+
+            // Use the assigned relayer while it has capacity, otherwise find a new one
+            let relayer_id = tx
+                .relayer
+                .clone()
+                .filter(|rid| self.relayer_has_capacity(rid, tx.estimated_gas))
+                .or_else(|| {
+                    self.ai_router.select_optimal_relayer(
                         &self.relayers,
                         tx.estimated_gas,
                         tx.priority,
                         &self.availability_tracker,
-                    ) {
-                        rid
-                    } else {
-                        // No relayer available, keep in queue
-                        return true;
-                    }
-                };
-
-                // Check if relayer is available
-                if let Some(relayer) = self.relayers.get_mut(&relayer_id) {
-                    if relayer.is_active
-                        && relayer.balance >= tx.estimated_gas
-                        && relayer.current_tps < relayer.max_tps
-                    {
-                        // Submit transaction (simulated)
-                        relayer.balance -= tx.estimated_gas;
-                        relayer.current_tps += 1;
-
-                        // Update transaction status
-                        // In a real implementation, this would involve actual blockchain submission
-                        processed_txs.push(format!("Processed transaction for user: {}", tx.user));
-                        false // Remove from queue
-                    } else {
-                        true // Keep in queue
-                    }
-                } else {
-                    true // Keep in queue
+                    )
+                });
+
+            match relayer_id {
+                Some(relayer_id) if self.relayer_has_capacity(&relayer_id, tx.estimated_gas) => {
+                    // Submit transaction (simulated)
+                    self.charge_relayer(&relayer_id, &tx, now);
+                    processed_txs.push(format!("Processed transaction for user: {}", tx.user));
                 }
-            } else {
-                false // Remove from queue
+                _ => self.transaction_queue.push(tx), // Keep in queue
             }
-        });
+        }
 
         Ok(processed_txs)
     }
 
+    /// Whether a relayer can execute a transaction needing `gas` right now
+    fn relayer_has_capacity(&self, relayer_id: &str, gas: u64) -> bool {
+        self.relayers.get(relayer_id).is_some_and(|relayer| {
+            relayer.is_active && relayer.balance >= gas && relayer.current_tps < relayer.max_tps
+        })
+    }
+
+    /// Debit gas from the executing relayer and reimburse it from the user's sponsorship
+    fn charge_relayer(&mut self, relayer_id: &str, tx: &AbstractedTransaction, now: u64) {
+        let reimbursement = match &tx.sponsor {
+            Some(sponsor_id) => self
+                .sponsorships
+                .get_mut(&tx.user)
+                .filter(|sponsorship| {
+                    sponsorship.is_active && sponsorship.sponsor_id == *sponsor_id
+                })
+                .map(|sponsorship| {
+                    Self::reset_sponsorship_if_due(sponsorship, now);
+                    let amount = sponsorship
+                        .daily_limit
+                        .saturating_sub(sponsorship.used_gas)
+                        .min(tx.estimated_gas);
+                    sponsorship.used_gas += amount;
+                    amount
+                })
+                .unwrap_or(0),
+            None => 0,
+        };
+
+        if let Some(relayer) = self.relayers.get_mut(relayer_id) {
+            relayer.balance = relayer.balance - tx.estimated_gas + reimbursement;
+            relayer.current_tps += 1;
+        }
+
+        let stats = self
+            .relayer_stats
+            .entry(relayer_id.to_string())
+            .or_default();
+        stats.gas_spent += tx.estimated_gas;
+        stats.gas_reimbursed += reimbursement;
+    }
+
+    /// Roll over expired TPS windows and lift suspensions that have run out
+    fn refresh_relayers(&mut self, now: u64) {
+        let window = self.relayer_policy.tps_window_secs.max(1);
+
+        for (relayer_id, relayer) in self.relayers.iter_mut() {
+            let stats = self.relayer_stats.entry(relayer_id.clone()).or_default();
+
+            if now.saturating_sub(stats.window_start) >= window {
+                relayer.current_tps = 0;
+                stats.window_start = now;
+            }
+
+            if stats.suspended_until.is_some_and(|until| now >= until) {
+                stats.suspended_until = None;
+                stats.consecutive_failures = 0;
+                relayer.is_active = true;
+                relayer.reputation = relayer.reputation.max(self.relayer_policy.min_reputation);
+            }
+        }
+    }
+
+    /// Report the outcome of a relayer's execution
+    ///
+    /// Reputation moves towards 1.0 for successful executions within the target latency
+    /// (less for slower ones) and towards 0.0 for failures. A relayer that reaches the
+    /// consecutive-failure limit, or whose reputation drops below the policy minimum, is
+    /// slashed and suspended. The record is also fed to the AI router.
+    pub fn record_relayer_execution(
+        &mut self,
+        relayer_id: &str,
+        record: ExecutionRecord,
+    ) -> Result<(), GasAbstractionError> {
+        let policy = self.relayer_policy.clone();
+        let relayer = self
+            .relayers
+            .get_mut(relayer_id)
+            .ok_or(GasAbstractionError::RelayerNotFound)?;
+        let stats = self
+            .relayer_stats
+            .entry(relayer_id.to_string())
+            .or_default();
+
+        let score = if record.success {
+            let latency = record.execution_time.max(1) as f32;
+            (policy.target_latency_ms as f32 / latency).min(1.0)
+        } else {
+            0.0
+        };
+        let weight = policy.reputation_weight.clamp(0.0, 1.0);
+        relayer.reputation = (1.0 - weight) * relayer.reputation + weight * score;
+
+        stats.executions += 1;
+        if record.success {
+            stats.consecutive_failures = 0;
+        } else {
+            stats.failures += 1;
+            stats.consecutive_failures += 1;
+        }
+
+        let should_suspend = stats.suspended_until.is_none()
+            && (stats.consecutive_failures >= policy.max_consecutive_failures
+                || relayer.reputation < policy.min_reputation);
+        if should_suspend {
+            let slashed =
+                (relayer.balance as f64 * policy.slash_ratio.clamp(0.0, 1.0) as f64) as u64;
+            relayer.balance -= slashed;
+            relayer.is_active = false;
+            stats.slashed += slashed;
+            stats.suspended_until = Some(record.timestamp + policy.suspension_secs);
+        }
+
+        self.ai_router.record_execution(record);
+        Ok(())
+    }
+
     /// Estimate gas cost for a transaction
     pub fn estimate_gas(&self, transaction: &Transaction) -> Result<u64, GasAbstractionError> {
         // In a real implementation, this would use network data and transaction complexity
@@ -356,20 +693,14 @@ impl GasAbstractionService {
         Ok(base_gas + amount_gas)
     }
 
-    /// Check if user is within sponsorship limits
-    fn check_sponsorship_limit(&self, user: &TraderId) -> Result<bool, GasAbstractionError> {
-        if let Some(sponsorship) = self.sponsorships.get(user) {
+    /// Check if user is within sponsorship limits, starting a new day if one has elapsed
+    fn check_sponsorship_limit(&mut self, user: &TraderId) -> Result<bool, GasAbstractionError> {
+        if let Some(sponsorship) = self.sponsorships.get_mut(user) {
             let now = SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
                 .as_secs();
-
-            // Reset daily limit if needed
-            if now - sponsorship.last_reset > 86400 {
-                // 24 hours
-                // In a real implementation, this would be updated in the sponsorship
-                return Ok(true);
-            }
+            Self::reset_sponsorship_if_due(sponsorship, now);
 
             Ok(sponsorship.used_gas < sponsorship.daily_limit)
         } else {
@@ -377,6 +708,14 @@ impl GasAbstractionService {
         }
     }
 
+    /// Reset the daily sponsorship budget once 24 hours have passed
+    fn reset_sponsorship_if_due(sponsorship: &mut GasSponsorship, now: u64) {
+        if now.saturating_sub(sponsorship.last_reset) >= 86400 {
+            sponsorship.used_gas = 0;
+            sponsorship.last_reset = now;
+        }
+    }
+
     /// Get transaction by ID
     pub fn get_transaction(&self, tx_id: &str) -> Option<&AbstractedTransaction> {
         // In a real implementation, we would have a proper ID system
@@ -406,6 +745,184 @@ impl Default for GasAbstractionService {
     }
 }
 
+impl Default for RelayerPolicy {
+    fn default() -> Self {
+        Self {
+            tps_window_secs: 1,
+            target_latency_ms: 2000,
+            reputation_weight: 0.2,
+            max_consecutive_failures: 3,
+            slash_ratio: 0.1,
+            min_reputation: 0.2,
+            suspension_secs: 3600,
+        }
+    }
+}
+
+impl Default for MetaTxDomain {
+    fn default() -> Self {
+        Self {
+            name: "DEX-OS Gas Abstraction".to_string(),
+            version: "1".to_string(),
+            chain_id: 1,
+            verifying_contract: "dex-os-forwarder".to_string(),
+        }
+    }
+}
+
+impl MetaTxDomain {
+    /// Domain separator: keccak256 of the encoded domain fields
+    pub fn separator(&self) -> [u8; 32] {
+        let mut hasher = Keccak256::new();
+        hasher.update(keccak256(
+            b"EIP712Domain(string name,string version,uint256 chainId,string verifyingContract)",
+        ));
+        hasher.update(keccak256(self.name.as_bytes()));
+        hasher.update(keccak256(self.version.as_bytes()));
+        hasher.update(u256_word(self.chain_id as u128));
+        hasher.update(keccak256(self.verifying_contract.as_bytes()));
+        hasher.finalize().into()
+    }
+}
+
+impl MetaTransaction {
+    /// Hash of the typed request fields
+    pub fn struct_hash(&self) -> [u8; 32] {
+        let mut hasher = Keccak256::new();
+        hasher.update(keccak256(
+            b"MetaTransaction(string from,string to,int64 amount,bytes data,uint256 nonce,uint256 deadline,uint256 maxGas)",
+        ));
+        hasher.update(keccak256(self.from.as_bytes()));
+        hasher.update(keccak256(self.to.as_bytes()));
+        // Sign-extend the amount to a 256-bit two's complement word
+        let mut amount = if self.amount < 0 {
+            [0xff; 32]
+        } else {
+            [0u8; 32]
+        };
+        amount[24..].copy_from_slice(&self.amount.to_be_bytes());
+        hasher.update(amount);
+        hasher.update(keccak256(&self.data));
+        hasher.update(u256_word(self.nonce as u128));
+        hasher.update(u256_word(self.deadline as u128));
+        hasher.update(u256_word(self.max_gas as u128));
+        hasher.finalize().into()
+    }
+
+    /// Digest the user signs: keccak256(0x19 0x01 || domain separator || struct hash)
+    pub fn signing_digest(&self, domain: &MetaTxDomain) -> [u8; 32] {
+        let mut hasher = Keccak256::new();
+        hasher.update([0x19, 0x01]);
+        hasher.update(domain.separator());
+        hasher.update(self.struct_hash());
+        hasher.finalize().into()
+    }
+}
+
+impl UserSignature {
+    /// Serialized form stored in the relayed `Transaction::signature`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            UserSignature::Ed25519 {
+                public_key,
+                signature,
+            } => [public_key.as_slice(), signature.as_slice()].concat(),
+            UserSignature::Secp256k1 { signature } => signature.clone(),
+        }
+    }
+}
+
+impl MetaTransactionEnvelope {
+    /// Verify that the user named in `request.from` signed the request for `domain`
+    pub fn verify(&self, domain: &MetaTxDomain) -> Result<(), GasAbstractionError> {
+        let digest = self.request.signing_digest(domain);
+
+        match &self.signature {
+            UserSignature::Ed25519 {
+                public_key,
+                signature,
+            } => {
+                // The signer's key must be the identity the intent claims to come from
+                if !self
+                    .request
+                    .from
+                    .eq_ignore_ascii_case(&encode_hex(public_key))
+                {
+                    return Err(GasAbstractionError::InvalidSignature);
+                }
+
+                let public_key: [u8; 32] = public_key
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| GasAbstractionError::InvalidSignature)?;
+                let signature: [u8; 64] = signature
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| GasAbstractionError::InvalidSignature)?;
+                let verifying_key = Ed25519VerifyingKey::from_bytes(&public_key)
+                    .map_err(|_| GasAbstractionError::InvalidSignature)?;
+
+                verifying_key
+                    .verify_strict(&digest, &Ed25519Signature::from_bytes(&signature))
+                    .map_err(|_| GasAbstractionError::InvalidSignature)
+            }
+            UserSignature::Secp256k1 { signature } => {
+                let recovered = recover_address(&digest, signature)?;
+                if self.request.from.eq_ignore_ascii_case(&recovered) {
+                    Ok(())
+                } else {
+                    Err(GasAbstractionError::InvalidSignature)
+                }
+            }
+        }
+    }
+}
+
+/// Recover the Ethereum address that produced a 65-byte `r || s || v` signature over a
+/// 32-byte digest (the same recovery `ethers-core` performs for `Signature::recover`)
+pub fn recover_address(digest: &[u8; 32], signature: &[u8]) -> Result<String, GasAbstractionError> {
+    if signature.len() != 65 {
+        return Err(GasAbstractionError::InvalidSignature);
+    }
+
+    // Accept both the raw (0/1) and the Ethereum (27/28) recovery id encodings
+    let v = match signature[64] {
+        v @ (0 | 1) => v,
+        v @ (27 | 28) => v - 27,
+        _ => return Err(GasAbstractionError::InvalidSignature),
+    };
+    let recovery_id = RecoveryId::from_byte(v).ok_or(GasAbstractionError::InvalidSignature)?;
+    let signature = Secp256k1Signature::from_slice(&signature[..64])
+        .map_err(|_| GasAbstractionError::InvalidSignature)?;
+
+    let verifying_key =
+        Secp256k1VerifyingKey::recover_from_prehash(digest, &signature, recovery_id)
+            .map_err(|_| GasAbstractionError::InvalidSignature)?;
+
+    Ok(ethereum_address(&verifying_key))
+}
+
+/// Lowercase `0x` address of a secp256k1 public key
+pub fn ethereum_address(key: &Secp256k1VerifyingKey) -> String {
+    let point = key.to_encoded_point(false);
+    let hash = keccak256(&point.as_bytes()[1..]);
+    format!("0x{}", encode_hex(&hash[12..]))
+}
+
+fn keccak256(data: &[u8]) -> [u8; 32] {
+    Keccak256::digest(data).into()
+}
+
+fn u256_word(value: u128) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[16..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
 impl AIRouter {
     /// Create a new AI router
     pub fn new() -> Self {
@@ -586,6 +1103,14 @@ pub enum GasAbstractionError {
     EstimationFailed,
     #[error("Service unavailable")]
     ServiceUnavailable,
+    #[error("Invalid user signature")]
+    InvalidSignature,
+    #[error("Nonce already used")]
+    NonceAlreadyUsed,
+    #[error("Meta-transaction expired")]
+    MetaTransactionExpired,
+    #[error("Estimated gas exceeds the signed gas limit")]
+    GasLimitExceeded,
 }
 
 #[cfg(test)]
@@ -715,7 +1240,8 @@ mod tests {
         };
 
         // Submit abstracted transaction
-        let result = service.submit_abstracted_transaction(transaction, user.clone(), 5);
+        let result =
+            service.submit_abstracted_transaction(transaction, Vec::new(), user.clone(), 5);
         assert!(result.is_ok());
 
         // Check that transaction was added to queue
@@ -749,7 +1275,7 @@ mod tests {
         };
 
         // Submit abstracted transaction
-        let tx_id = service.submit_abstracted_transaction(transaction, user.clone(), 5);
+        let tx_id = service.submit_abstracted_transaction(transaction, Vec::new(), user.clone(), 5);
         assert!(tx_id.is_ok());
         assert_eq!(service.queue_size(), 1);
 
@@ -843,4 +1369,389 @@ mod tests {
         // Check uptime requirements (should pass with no downtime recorded)
         assert!(tracker.check_uptime_requirements());
     }
+
+    fn meta_request(from: &str, nonce: u64) -> MetaTransaction {
+        MetaTransaction {
+            from: from.to_string(),
+            to: "recipient".to_string(),
+            amount: 1000,
+            data: vec![0xde, 0xad],
+            nonce,
+            deadline: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+                + 600,
+            max_gas: 50000,
+        }
+    }
+
+    fn ed25519_envelope(
+        signing_key: &ed25519_dalek::SigningKey,
+        request: MetaTransaction,
+        domain: &MetaTxDomain,
+    ) -> MetaTransactionEnvelope {
+        use ed25519_dalek::Signer;
+
+        let signature = signing_key.sign(&request.signing_digest(domain));
+        MetaTransactionEnvelope {
+            request,
+            signature: UserSignature::Ed25519 {
+                public_key: signing_key.verifying_key().to_bytes().to_vec(),
+                signature: signature.to_bytes().to_vec(),
+            },
+        }
+    }
+
+    fn secp256k1_envelope(
+        signing_key: &k256::ecdsa::SigningKey,
+        request: MetaTransaction,
+        domain: &MetaTxDomain,
+    ) -> MetaTransactionEnvelope {
+        let (signature, recovery_id) = signing_key
+            .sign_prehash_recoverable(&request.signing_digest(domain))
+            .unwrap();
+        let mut bytes = signature.to_bytes().to_vec();
+        bytes.push(recovery_id.to_byte() + 27);
+        MetaTransactionEnvelope {
+            request,
+            signature: UserSignature::Secp256k1 { signature: bytes },
+        }
+    }
+
+    #[test]
+    fn test_ed25519_meta_transaction() {
+        let mut service = GasAbstractionService::new();
+        let domain = service.meta_tx_domain().clone();
+        let signing_key = ed25519_dalek::SigningKey::from_bytes(&[7u8; 32]);
+        let user = encode_hex(&signing_key.verifying_key().to_bytes());
+
+        let envelope = ed25519_envelope(&signing_key, meta_request(&user, 0), &domain);
+        assert!(service.submit_meta_transaction(envelope.clone(), 5).is_ok());
+        assert_eq!(service.queue_size(), 1);
+        // The signed call data is what gets submitted
+        assert_eq!(service.transaction_queue[0].data, vec![0xde, 0xad]);
+        assert_eq!(service.next_nonce(&user), 1);
+
+        // Replaying the same envelope is rejected
+        assert!(matches!(
+            service.submit_meta_transaction(envelope, 5),
+            Err(GasAbstractionError::NonceAlreadyUsed)
+        ));
+
+        // A relayer tampering with the signed request is rejected
+        let mut tampered = ed25519_envelope(&signing_key, meta_request(&user, 1), &domain);
+        tampered.request.amount = 1_000_000;
+        assert!(matches!(
+            service.submit_meta_transaction(tampered, 5),
+            Err(GasAbstractionError::InvalidSignature)
+        ));
+
+        // A key cannot sign for another user
+        let other_key = ed25519_dalek::SigningKey::from_bytes(&[8u8; 32]);
+        let forged = ed25519_envelope(&other_key, meta_request(&user, 1), &domain);
+        assert!(matches!(
+            service.submit_meta_transaction(forged, 5),
+            Err(GasAbstractionError::InvalidSignature)
+        ));
+        assert_eq!(service.queue_size(), 1);
+    }
+
+    #[test]
+    fn test_secp256k1_meta_transaction() {
+        let mut service = GasAbstractionService::new();
+        let domain = service.meta_tx_domain().clone();
+        let signing_key = k256::ecdsa::SigningKey::from_slice(&[0x11; 32]).unwrap();
+        let address = ethereum_address(signing_key.verifying_key());
+
+        let envelope = secp256k1_envelope(&signing_key, meta_request(&address, 3), &domain);
+        assert_eq!(
+            recover_address(
+                &envelope.request.signing_digest(&domain),
+                &envelope.signature.to_bytes()
+            )
+            .unwrap(),
+            address
+        );
+        assert!(service.submit_meta_transaction(envelope, 5).is_ok());
+
+        // Signature by a different key does not recover to the claimed address
+        let other_key = k256::ecdsa::SigningKey::from_slice(&[0x22; 32]).unwrap();
+        let forged = secp256k1_envelope(&other_key, meta_request(&address, 4), &domain);
+        assert!(matches!(
+            service.submit_meta_transaction(forged, 5),
+            Err(GasAbstractionError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn test_meta_transaction_domain_expiry_and_gas_limit() {
+        let domain = MetaTxDomain {
+            chain_id: 10,
+            ..MetaTxDomain::default()
+        };
+        let mut service = GasAbstractionService::with_domain(domain.clone());
+        let signing_key = ed25519_dalek::SigningKey::from_bytes(&[9u8; 32]);
+        let user = encode_hex(&signing_key.verifying_key().to_bytes());
+
+        // Signed for another chain
+        let wrong_domain = ed25519_envelope(
+            &signing_key,
+            meta_request(&user, 0),
+            &MetaTxDomain::default(),
+        );
+        assert!(matches!(
+            service.submit_meta_transaction(wrong_domain, 5),
+            Err(GasAbstractionError::InvalidSignature)
+        ));
+
+        let mut expired_request = meta_request(&user, 0);
+        expired_request.deadline = 1;
+        let expired = ed25519_envelope(&signing_key, expired_request, &domain);
+        assert!(matches!(
+            service.submit_meta_transaction(expired, 5),
+            Err(GasAbstractionError::MetaTransactionExpired)
+        ));
+
+        let mut low_gas_request = meta_request(&user, 0);
+        low_gas_request.max_gas = 100;
+        let low_gas = ed25519_envelope(&signing_key, low_gas_request, &domain);
+        assert!(matches!(
+            service.submit_meta_transaction(low_gas, 5),
+            Err(GasAbstractionError::GasLimitExceeded)
+        ));
+
+        assert_eq!(service.queue_size(), 0);
+        assert_eq!(service.next_nonce(&user), 0);
+    }
+
+    #[test]
+    fn test_abstracted_transaction_nonce_replay() {
+        let mut service = GasAbstractionService::new();
+        let user = "user1".to_string();
+        let transaction = Transaction {
+            from: user.clone(),
+            to: "recipient".to_string(),
+            amount: 1000,
+            nonce: 1,
+            signature: vec![],
+        };
+
+        assert!(service
+            .submit_abstracted_transaction(transaction.clone(), Vec::new(), user.clone(), 5)
+            .is_ok());
+        assert!(matches!(
+            service.submit_abstracted_transaction(transaction, Vec::new(), user, 5),
+            Err(GasAbstractionError::NonceAlreadyUsed)
+        ));
+    }
+
+    fn lifecycle_service() -> (GasAbstractionService, u64) {
+        let mut service = GasAbstractionService::new();
+        service.add_relayer(Relayer {
+            id: "relayer1".to_string(),
+            address: "0x1234...".to_string(),
+            reputation: 0.9,
+            balance: 1_000_000,
+            max_tps: 2,
+            current_tps: 0,
+            is_active: true,
+        });
+        let now = service.relayer_stats["relayer1"].window_start;
+        (service, now)
+    }
+
+    fn transfer(user: &str, nonce: u64) -> Transaction {
+        Transaction {
+            from: user.to_string(),
+            to: "recipient".to_string(),
+            amount: 1000,
+            nonce,
+            signature: vec![],
+        }
+    }
+
+    fn execution(success: bool, execution_time: u64, timestamp: u64) -> ExecutionRecord {
+        ExecutionRecord {
+            tx_type: "transfer".to_string(),
+            path: "relayer1".to_string(),
+            execution_time,
+            cost: 21001,
+            success,
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn test_relayer_gas_debit_and_sponsor_reimbursement() {
+        let (mut service, now) = lifecycle_service();
+        let sponsored = "sponsored".to_string();
+        service.set_sponsorship(
+            sponsored.clone(),
+            GasSponsorship {
+                sponsor_id: "dao".to_string(),
+                daily_limit: 30000,
+                used_gas: 0,
+                last_reset: now,
+                is_active: true,
+            },
+        );
+
+        service
+            .submit_abstracted_transaction(
+                transfer("unsponsored", 1),
+                Vec::new(),
+                "unsponsored".to_string(),
+                5,
+            )
+            .unwrap();
+        service
+            .submit_abstracted_transaction(
+                transfer(&sponsored, 1),
+                Vec::new(),
+                sponsored.clone(),
+                5,
+            )
+            .unwrap();
+        assert_eq!(service.process_queue_at(now).unwrap().len(), 2);
+
+        // Only the sponsored transaction is reimbursed
+        let relayer = service.get_relayer("relayer1").unwrap();
+        assert_eq!(relayer.balance, 1_000_000 - 21001);
+        assert_eq!(relayer.current_tps, 2);
+        let stats = service.get_relayer_stats("relayer1").unwrap();
+        assert_eq!(stats.gas_spent, 2 * 21001);
+        assert_eq!(stats.gas_reimbursed, 21001);
+        assert_eq!(service.get_sponsorship(&sponsored).unwrap().used_gas, 21001);
+
+        // The remaining budget only partially covers the next transaction
+        service
+            .submit_abstracted_transaction(
+                transfer(&sponsored, 2),
+                Vec::new(),
+                sponsored.clone(),
+                5,
+            )
+            .unwrap();
+        assert_eq!(service.process_queue_at(now + 1).unwrap().len(), 1);
+        assert_eq!(service.get_sponsorship(&sponsored).unwrap().used_gas, 30000);
+        assert_eq!(
+            service
+                .get_relayer_stats("relayer1")
+                .unwrap()
+                .gas_reimbursed,
+            30000
+        );
+
+        // A new day resets the budget
+        service.set_sponsorship(
+            sponsored.clone(),
+            GasSponsorship {
+                sponsor_id: "dao".to_string(),
+                daily_limit: 30000,
+                used_gas: 30000,
+                last_reset: now - 86400,
+                is_active: true,
+            },
+        );
+        service
+            .submit_abstracted_transaction(
+                transfer(&sponsored, 3),
+                Vec::new(),
+                sponsored.clone(),
+                5,
+            )
+            .unwrap();
+        service.process_queue_at(now + 2).unwrap();
+        assert_eq!(service.get_sponsorship(&sponsored).unwrap().used_gas, 21001);
+    }
+
+    #[test]
+    fn test_relayer_tps_window_resets() {
+        let (mut service, now) = lifecycle_service();
+
+        for nonce in 1..=3 {
+            service
+                .submit_abstracted_transaction(
+                    transfer("user1", nonce),
+                    Vec::new(),
+                    "user1".to_string(),
+                    5,
+                )
+                .unwrap();
+        }
+
+        // max_tps is 2, so the third transaction waits for the next window
+        assert_eq!(service.process_queue_at(now).unwrap().len(), 2);
+        assert_eq!(service.queue_size(), 1);
+        assert_eq!(service.process_queue_at(now).unwrap().len(), 0);
+
+        assert_eq!(service.process_queue_at(now + 1).unwrap().len(), 1);
+        assert_eq!(service.queue_size(), 0);
+        assert_eq!(service.get_relayer("relayer1").unwrap().current_tps, 1);
+    }
+
+    #[test]
+    fn test_relayer_reputation_follows_executions() {
+        let (mut service, now) = lifecycle_service();
+
+        service
+            .record_relayer_execution("relayer1", execution(true, 500, now))
+            .unwrap();
+        let fast = service.get_relayer("relayer1").unwrap().reputation;
+        assert!(fast > 0.9);
+
+        service
+            .record_relayer_execution("relayer1", execution(true, 8000, now))
+            .unwrap();
+        let slow = service.get_relayer("relayer1").unwrap().reputation;
+        assert!(slow < fast);
+
+        service
+            .record_relayer_execution("relayer1", execution(false, 500, now))
+            .unwrap();
+        assert!(service.get_relayer("relayer1").unwrap().reputation < slow);
+        assert_eq!(service.get_relayer_stats("relayer1").unwrap().failures, 1);
+
+        assert!(matches!(
+            service.record_relayer_execution("missing", execution(true, 500, now)),
+            Err(GasAbstractionError::RelayerNotFound)
+        ));
+    }
+
+    #[test]
+    fn test_failing_relayer_slashed_and_suspended() {
+        let (mut service, now) = lifecycle_service();
+
+        for _ in 0..3 {
+            service
+                .record_relayer_execution("relayer1", execution(false, 500, now))
+                .unwrap();
+        }
+
+        let relayer = service.get_relayer("relayer1").unwrap();
+        assert!(!relayer.is_active);
+        assert_eq!(relayer.balance, 900_000);
+        let stats = service.get_relayer_stats("relayer1").unwrap();
+        assert_eq!(stats.slashed, 100_000);
+        assert_eq!(stats.suspended_until, Some(now + 3600));
+
+        // Suspended relayers are not selected
+        service
+            .submit_abstracted_transaction(transfer("user1", 1), Vec::new(), "user1".to_string(), 5)
+            .unwrap();
+        assert!(service.process_queue_at(now + 10).unwrap().is_empty());
+
+        // The suspension is lifted once it has run out
+        assert_eq!(service.process_queue_at(now + 3600).unwrap().len(), 1);
+        let relayer = service.get_relayer("relayer1").unwrap();
+        assert!(relayer.is_active);
+        assert_eq!(
+            service
+                .get_relayer_stats("relayer1")
+                .unwrap()
+                .consecutive_failures,
+            0
+        );
+    }
 }
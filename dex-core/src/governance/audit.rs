@@ -1,5 +1,5 @@
-use crate::security::{EventType, SecurityManager, SeverityLevel};
-use ed25519_dalek::{Signature, VerifyingKey};
+use crate::security::{EventSink, EventType, LogRecord, SecurityError, SecurityManager, SeverityLevel};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::{
@@ -7,6 +7,7 @@ use std::{
     fs::{self, File, OpenOptions},
     io::{Read, Write},
     path::PathBuf,
+    sync::Mutex,
     time::{SystemTime, UNIX_EPOCH},
 };
 
@@ -30,6 +31,7 @@ pub struct EvidenceRecord {
     pub timestamp: u64,
 }
 
+#[derive(Debug)]
 pub struct AuditStore {
     base_dir: PathBuf,
     index_path: PathBuf,
@@ -140,6 +142,55 @@ impl AuditStore {
     }
 }
 
+/// Security event sink filing the log in an [`AuditStore`]: the events up to
+/// each checkpoint, with the checkpoint, become one evidence record signed by
+/// the sink's key, so the store holds the complete log in verifiable batches.
+#[derive(Debug)]
+pub struct AuditSink {
+    store: AuditStore,
+    signing_key: SigningKey,
+    pending: Mutex<Vec<LogRecord>>,
+}
+
+impl AuditSink {
+    pub fn new(store: AuditStore, signing_secret: &[u8]) -> Result<Self, AuditError> {
+        let secret: [u8; 32] = signing_secret.try_into().map_err(|_| AuditError::Signature)?;
+        Ok(Self { store, signing_key: SigningKey::from_bytes(&secret), pending: Mutex::new(Vec::new()) })
+    }
+
+    /// The store batches are filed in
+    pub fn store(&self) -> &AuditStore {
+        &self.store
+    }
+}
+
+impl EventSink for AuditSink {
+    fn record(&self, record: &LogRecord) -> Result<(), SecurityError> {
+        let mut pending = self.pending.lock().unwrap();
+        pending.push(record.clone());
+        let LogRecord::Checkpoint(checkpoint) = record else { return Ok(()); };
+
+        let first = pending.iter().find_map(|record| match record {
+            LogRecord::Event(event) => Some(event.sequence),
+            LogRecord::Checkpoint(_) => None,
+        }).unwrap_or(checkpoint.sequence);
+        let mut content = Vec::new();
+        for record in pending.iter() {
+            serde_json::to_writer(&mut content, record).map_err(|e| SecurityError::EventLog(e.to_string()))?;
+            content.push(b'\n');
+        }
+        let signature = self.signing_key.sign(&content).to_bytes();
+        let public_key = self.signing_key.verifying_key().to_bytes();
+        self.store.ingest(
+            &format!("security-log-{:020}", checkpoint.sequence),
+            &format!("security-events-{}-{}.jsonl", first, checkpoint.sequence),
+            &content, &signature, &public_key, None,
+        ).map_err(|e| SecurityError::EventLog(e.to_string()))?;
+        pending.clear();
+        Ok(())
+    }
+}
+
 fn encode_hex(bytes: &[u8]) -> String {
     let mut out = String::with_capacity(bytes.len() * 2);
     for b in bytes { use std::fmt::Write as _; let _ = write!(&mut out, "{:02x}", b); }
@@ -156,3 +207,32 @@ fn decode_hex(s: &str) -> Result<Vec<u8>, String> {
     }
     Ok(out)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::{EventLogger, verify_log, event_log::read_jsonl};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_audit_sink_files_checkpointed_batches() {
+        let dir = tempfile::tempdir().unwrap();
+        let (secret, public) = SecurityManager::generate_key_pair();
+        let sink = Arc::new(AuditSink::new(AuditStore::new(dir.path()).unwrap(), &secret).unwrap());
+        let mut logger = EventLogger::new(100).with_checkpoints(2, &secret).unwrap().with_sink(sink.clone());
+        for i in 0..5 {
+            logger.log(EventType::AuditTrail, format!("audit {}", i), None, Default::default(), None, SeverityLevel::Info);
+        }
+        assert_eq!(logger.sink_errors(), 0);
+
+        // Two batches are filed; the fifth event waits for the next checkpoint
+        sink.store().verify("security-log-00000000000000000001").unwrap();
+        sink.store().verify("security-log-00000000000000000003").unwrap();
+        let first = sink.store().find_by_id("security-log-00000000000000000001").unwrap().unwrap();
+        let second = sink.store().find_by_id("security-log-00000000000000000003").unwrap().unwrap();
+        assert_eq!(second.filename, "security-events-2-3.jsonl");
+        let (events, checkpoints) = read_jsonl(&[dir.path().join(first.content_hash), dir.path().join(second.content_hash)]).unwrap();
+        let report = verify_log(&events, &checkpoints, &public).unwrap();
+        assert_eq!((report.events, report.checkpoints, report.unsealed), (4, 2, 0));
+    }
+}
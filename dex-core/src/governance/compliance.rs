@@ -1,5 +1,15 @@
 use super::{load_governance_reference, GovernanceDomain, GovernanceScenario};
+use crate::identity::credentials::{calendar_date, current_timestamp};
+use crate::identity::{IdentityManager, Presentation, PresentationRequest, RangeRequirement, KYC_CREDENTIAL_TYPE};
 use serde::Serialize;
+use std::collections::HashSet;
+
+/// KYC claim holding the birth date as the number `YYYYMMDD`, issued as a
+/// Pedersen commitment so age checks only see a range proof
+pub const BIRTH_DATE_CLAIM: &str = "birthDate";
+/// KYC claim holding the ISO 3166-1 alpha-2 country of residence, issued as a
+/// selectively disclosable claim
+pub const COUNTRY_CLAIM: &str = "countryOfResidence";
 
 #[derive(Debug, Clone, Serialize, PartialEq, Eq)]
 pub struct FrameworkRef {
@@ -55,3 +65,177 @@ pub fn build_compliance_report() -> Result<ComplianceReport, crate::governance::
 pub fn render_report_json(report: &ComplianceReport) -> String {
     serde_json::to_string_pretty(report).unwrap_or_else(|_| "{}".to_string())
 }
+
+/// Onboarding check that accepts minimal selective-disclosure presentations of
+/// a KYC credential: a range proof that the trader is old enough and the
+/// disclosed country of residence, and nothing else from the credential.
+#[derive(Debug, Clone)]
+pub struct KycComplianceCheck {
+    pub minimum_age: u32,
+    pub sanctioned_countries: HashSet<String>,
+}
+
+/// Outcome of a passed [`KycComplianceCheck`], safe to retain as evidence
+#[derive(Debug, Clone, Serialize)]
+pub struct KycComplianceAttestation {
+    pub subject: String,
+    pub issuer: String,
+    pub country: String,
+    pub minimum_age: u32,
+    pub checked_at: u64,
+    pub frameworks: Vec<FrameworkRef>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ComplianceError {
+    #[error("presentation request does not enforce the KYC policy: {0}")]
+    WeakRequest(String),
+    #[error("presentation rejected: {0}")]
+    Presentation(String),
+    #[error("country of residence {0} is sanctioned")]
+    SanctionedJurisdiction(String),
+}
+
+impl KycComplianceCheck {
+    pub fn new<I, S>(minimum_age: u32, sanctioned_countries: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            minimum_age,
+            sanctioned_countries: sanctioned_countries.into_iter().map(|c| c.as_ref().to_ascii_uppercase()).collect(),
+        }
+    }
+
+    /// Latest `YYYYMMDD` birth date that satisfies the minimum age at `now`
+    pub fn birth_date_cutoff(&self, now: u64) -> u64 {
+        calendar_date(now).saturating_sub(u64::from(self.minimum_age) * 10_000)
+    }
+
+    /// The request a trader answers with [`IdentityManager::present_credential`]
+    pub fn presentation_request(&self, nonce: &str, audience: &str) -> PresentationRequest {
+        PresentationRequest {
+            nonce: nonce.to_string(),
+            audience: audience.to_string(),
+            credential_type: KYC_CREDENTIAL_TYPE.to_string(),
+            disclose: vec![COUNTRY_CLAIM.to_string()],
+            ranges: vec![RangeRequirement {
+                claim: BIRTH_DATE_CLAIM.to_string(),
+                min: 0,
+                max: self.birth_date_cutoff(current_timestamp()),
+            }],
+        }
+    }
+
+    /// Verify a presentation answering `request`, which the verifier issued
+    /// with [`Self::presentation_request`] and must not accept twice
+    pub fn evaluate(
+        &self,
+        identity: &IdentityManager,
+        request: &PresentationRequest,
+        presentation: &Presentation,
+    ) -> Result<KycComplianceAttestation, ComplianceError> {
+        let now = current_timestamp();
+        // The cutoff only moves forward, so a request issued earlier is at least as strict
+        let cutoff = self.birth_date_cutoff(now);
+        if request.credential_type != KYC_CREDENTIAL_TYPE {
+            return Err(ComplianceError::WeakRequest("not a KYC credential request".to_string()));
+        }
+        if !request.disclose.iter().any(|claim| claim == COUNTRY_CLAIM) {
+            return Err(ComplianceError::WeakRequest(format!("{} is not requested", COUNTRY_CLAIM)));
+        }
+        if !request.ranges.iter().any(|r| r.claim == BIRTH_DATE_CLAIM && r.max <= cutoff) {
+            return Err(ComplianceError::WeakRequest(format!("{} range does not prove the minimum age", BIRTH_DATE_CLAIM)));
+        }
+
+        let disclosed = identity
+            .verify_presentation(presentation, request)
+            .map_err(|e| ComplianceError::Presentation(e.to_string()))?;
+        let country = disclosed
+            .claim(COUNTRY_CLAIM)
+            .and_then(|c| c.as_str())
+            .map(|c| c.to_ascii_uppercase())
+            .ok_or_else(|| ComplianceError::Presentation(format!("{} is not a string", COUNTRY_CLAIM)))?;
+        if self.sanctioned_countries.contains(&country) {
+            return Err(ComplianceError::SanctionedJurisdiction(country));
+        }
+
+        Ok(KycComplianceAttestation {
+            subject: disclosed.subject,
+            issuer: disclosed.issuer,
+            country,
+            minimum_age: self.minimum_age,
+            checked_at: now,
+            frameworks: vec![
+                FrameworkRef { name: "FATF Recommendations".to_string(), controls: vec!["R.6".into(), "R.10".into()] },
+                FrameworkRef { name: "ISO 27001".to_string(), controls: vec!["A.5.34".into()] },
+            ],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identity::VerifiableCredential;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    fn kyc_holding(
+        identity: &mut IdentityManager,
+        issuer_key: &[u8],
+        trader: &str,
+        birth_date: u64,
+        country: &str,
+    ) -> crate::identity::SelectiveCredential {
+        let mut disclosable = BTreeMap::new();
+        disclosable.insert("fullName".to_string(), json!(format!("{} Example", trader)));
+        disclosable.insert(COUNTRY_CLAIM.to_string(), json!(country));
+        let mut committed = BTreeMap::new();
+        committed.insert(BIRTH_DATE_CLAIM.to_string(), birth_date);
+        let credential = VerifiableCredential::new(format!("kyc-{}", trader), "kyc-provider", trader, KYC_CREDENTIAL_TYPE, BTreeMap::new());
+        identity.issue_selective_credential(credential, disclosable, committed, issuer_key).unwrap()
+    }
+
+    #[test]
+    fn kyc_check_accepts_minimal_presentations() {
+        let mut identity = IdentityManager::new();
+        let (_, issuer_key) = identity.create_did_with_keys(&"kyc-provider".to_string()).unwrap();
+        identity.add_trusted_issuer("kyc-provider".to_string());
+        let check = KycComplianceCheck::new(18, ["ir", "KP"]);
+
+        let (_, alice_key) = identity.create_did_with_keys(&"alice".to_string()).unwrap();
+        let alice = kyc_holding(&mut identity, &issuer_key, "alice", 19_900_415, "DE");
+        let request = check.presentation_request("nonce-1", "dex-os-onboarding");
+        let presentation = identity.present_credential(&alice, &request, &alice_key).unwrap();
+        // Only the country is disclosed; the name and birth date stay hidden
+        assert_eq!(presentation.disclosures.len(), 1);
+        let attestation = check.evaluate(&identity, &request, &presentation).unwrap();
+        assert_eq!(attestation.subject, "did:dexos:alice");
+        assert_eq!(attestation.country, "DE");
+
+        // A request without the age range is not accepted as proof
+        let mut weak = request.clone();
+        weak.ranges.clear();
+        let presentation = identity.present_credential(&alice, &weak, &alice_key).unwrap();
+        assert!(matches!(check.evaluate(&identity, &weak, &presentation), Err(ComplianceError::WeakRequest(_))));
+
+        // Residents of sanctioned countries are refused
+        let (_, bob_key) = identity.create_did_with_keys(&"bob".to_string()).unwrap();
+        let bob = kyc_holding(&mut identity, &issuer_key, "bob", 19_850_101, "IR");
+        let request = check.presentation_request("nonce-2", "dex-os-onboarding");
+        let presentation = identity.present_credential(&bob, &request, &bob_key).unwrap();
+        assert!(matches!(
+            check.evaluate(&identity, &request, &presentation),
+            Err(ComplianceError::SanctionedJurisdiction(country)) if country == "IR"
+        ));
+
+        // A minor cannot produce the age proof at all
+        let (_, carol_key) = identity.create_did_with_keys(&"carol".to_string()).unwrap();
+        let birth_date = check.birth_date_cutoff(current_timestamp()) + 1;
+        let carol = kyc_holding(&mut identity, &issuer_key, "carol", birth_date, "DE");
+        let request = check.presentation_request("nonce-3", "dex-os-onboarding");
+        assert!(identity.present_credential(&carol, &request, &carol_key).is_err());
+    }
+}
@@ -1,11 +1,635 @@
 //! Identity and Access Management for governance
+//!
+//! This module implements the following features from DEX-OS-V2.csv:
+//! - Security,Security Layer,Security Layer 2,Identity & Access Control,Authentication & Authorization,High
+//! - Security,Protection Layer,Protection Layer 4,Access Control,Permission Management,High
+//!
+//! Access is decided by attribute-based rules. A subject carries the roles
+//! and scopes from its token plus free-form attributes. A resource carries
+//! the classification, owner and ACL recorded by
+//! [`SecurityManager::classify_data`](crate::security::SecurityManager::classify_data).
+//! Roles only count while an active grant backs them. Any deny rule wins.
+//! When no rule allows the request, it is denied.
 
+use crate::security::{ClassificationLevel, DataClassification};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use thiserror::Error;
+
+/// Role every authenticated subject holds without an explicit grant
+pub const DEFAULT_ROLE: &str = "trader";
+
+/// Role required to manage grants
+pub const ADMIN_ROLE: &str = "admin";
+
+/// Errors raised by the IAM policy engine
+#[derive(Debug, Error)]
+pub enum IamError {
+    #[error("Unknown role: {0}")]
+    UnknownRole(String),
+    #[error("No grant of role {role} to {subject}")]
+    GrantNotFound { subject: String, role: String },
+    #[error("Access denied: {0}")]
+    AccessDenied(String),
+}
+
+/// Operation a subject attempts on a resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Read,
+    Write,
+    Admin,
+}
+
+impl Action {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::Read => "read",
+            Action::Write => "write",
+            Action::Admin => "admin",
+        }
+    }
+}
+
+/// Outcome of a matching rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Effect {
+    Allow,
+    Deny,
+}
+
+/// Named role with the highest classification its holders may read
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Role {
+    /// Role name referenced by rules and grants
+    pub name: String,
+    /// Highest classification readable without being on the resource ACL
+    pub clearance: ClassificationLevel,
+}
+
+impl Role {
+    pub fn new(name: impl Into<String>, clearance: ClassificationLevel) -> Self {
+        Self {
+            name: name.into(),
+            clearance,
+        }
+    }
+}
+
+/// Attribute predicate a rule requires
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Condition {
+    /// The subject owns the resource
+    Owner,
+    /// A resource attribute (or pattern binding) equals the subject id
+    SubjectIs { attribute: String },
+    /// A subject attribute has the given value
+    SubjectAttribute { name: String, value: String },
+    /// A resource attribute has the given value
+    ResourceAttribute { name: String, value: String },
+}
+
+/// Attribute-based access rule
+///
+/// `resource` is a `/`-separated pattern. `*` matches one segment, a
+/// trailing `**` matches the remainder and `{name}` matches one segment and
+/// binds it as a resource attribute for the conditions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rule {
+    /// Unique rule identifier
+    pub id: String,
+    /// Whether a match allows or denies the request
+    pub effect: Effect,
+    /// Actions the rule covers
+    pub actions: Vec<Action>,
+    /// Resource pattern the rule covers
+    pub resource: String,
+    /// Roles the rule applies to; empty means every subject
+    #[serde(default)]
+    pub roles: Vec<String>,
+    /// Token scope required when the token is scope-restricted
+    #[serde(default)]
+    pub scope: Option<String>,
+    /// Predicates that must all hold
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+}
+
+impl Rule {
+    pub fn allow(id: impl Into<String>, actions: &[Action], resource: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            effect: Effect::Allow,
+            actions: actions.to_vec(),
+            resource: resource.into(),
+            roles: Vec::new(),
+            scope: None,
+            conditions: Vec::new(),
+        }
+    }
+
+    pub fn deny(id: impl Into<String>, actions: &[Action], resource: impl Into<String>) -> Self {
+        Self {
+            effect: Effect::Deny,
+            ..Self::allow(id, actions, resource)
+        }
+    }
+
+    pub fn with_roles(mut self, roles: &[&str]) -> Self {
+        self.roles = roles.iter().map(|r| r.to_string()).collect();
+        self
+    }
+
+    pub fn with_scope(mut self, scope: impl Into<String>) -> Self {
+        self.scope = Some(scope.into());
+        self
+    }
+
+    pub fn with_condition(mut self, condition: Condition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+}
+
+/// Assignment of a role to a subject
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Grant {
+    /// Subject receiving the role
+    pub subject: String,
+    /// Granted role
+    pub role: String,
+    /// Subject that issued the grant
+    pub granted_by: String,
+    /// Unix time of the grant
+    pub granted_at: u64,
+    /// Unix time after which the grant lapses
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+}
+
+impl Grant {
+    pub fn is_active(&self, now: u64) -> bool {
+        self.expires_at.is_none_or(|expiry| now < expiry)
+    }
+}
+
+/// Authenticated principal requesting access
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Subject {
+    /// Subject identifier, the token `sub`
+    pub id: String,
+    /// Roles claimed by the token
+    pub roles: Vec<String>,
+    /// Scopes the token is restricted to; `None` means unrestricted
+    pub scopes: Option<Vec<String>>,
+    /// Additional subject attributes
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl Subject {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_roles(mut self, roles: Vec<String>) -> Self {
+        self.roles = roles;
+        self
+    }
+
+    pub fn with_scopes(mut self, scopes: Option<Vec<String>>) -> Self {
+        self.scopes = scopes;
+        self
+    }
+
+    pub fn with_attribute(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.attributes.insert(name.into(), value.into());
+        self
+    }
+}
+
+/// Resource being accessed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resource {
+    /// Resource identifier matched against rule patterns
+    pub id: String,
+    /// Classification of the resource
+    pub classification: ClassificationLevel,
+    /// Owner recorded for the resource
+    pub owner: Option<String>,
+    /// Subjects allowed regardless of clearance
+    pub acl: Vec<String>,
+    /// Additional resource attributes
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl Resource {
+    /// Unclassified resource, readable at `Public` clearance
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            classification: ClassificationLevel::Public,
+            owner: None,
+            acl: Vec::new(),
+            attributes: BTreeMap::new(),
+        }
+    }
+
+    /// Resource carrying a classification recorded by the security manager
+    pub fn classified(id: impl Into<String>, classification: &DataClassification) -> Self {
+        Self {
+            classification: classification.level.clone(),
+            owner: Some(classification.owner.clone()),
+            acl: classification.acl.clone(),
+            ..Self::new(id)
+        }
+    }
+
+    pub fn with_attribute(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.attributes.insert(name.into(), value.into());
+        self
+    }
+
+    fn lists(&self, subject: &str) -> bool {
+        self.owner.as_deref() == Some(subject) || self.acl.iter().any(|s| s == subject)
+    }
+}
+
+/// Attribute-based access control engine
+#[derive(Debug, Clone, Default)]
 pub struct IAM {
-    // Placeholder for IAM implementation
+    roles: BTreeMap<String, Role>,
+    rules: Vec<Rule>,
+    grants: HashMap<String, Vec<Grant>>,
 }
 
 impl IAM {
+    /// Engine without roles or rules; every request is denied
     pub fn new() -> Self {
-        Self {}
+        Self::default()
+    }
+
+    /// Engine with the roles and rules guarding the DEX API routes
+    pub fn with_default_policy() -> Self {
+        let mut iam = Self::new();
+        iam.add_role(Role::new(DEFAULT_ROLE, ClassificationLevel::Internal));
+        iam.add_role(Role::new("auditor", ClassificationLevel::Confidential));
+        iam.add_role(Role::new("compliance", ClassificationLevel::Secret));
+        iam.add_role(Role::new(ADMIN_ROLE, ClassificationLevel::TopSecret));
+
+        iam.add_rule(
+            Rule::allow("orders-place", &[Action::Write], "orderbook/orders")
+                .with_roles(&[DEFAULT_ROLE])
+                .with_scope("orders:write"),
+        );
+        iam.add_rule(
+            Rule::allow(
+                "order-trades-read",
+                &[Action::Read],
+                "orderbook/orders/{order}/trades",
+            )
+            .with_roles(&[DEFAULT_ROLE])
+            .with_scope("trades:read"),
+        );
+        iam.add_rule(
+            Rule::allow(
+                "trader-trades-read",
+                &[Action::Read],
+                "orderbook/traders/{trader}/trades",
+            )
+            .with_roles(&[DEFAULT_ROLE])
+            .with_scope("trades:read")
+            .with_condition(Condition::SubjectIs {
+                attribute: "trader".to_string(),
+            }),
+        );
+        iam.add_rule(
+            Rule::allow("orderbook-audit", &[Action::Read], "orderbook/**")
+                .with_roles(&["auditor", "compliance"])
+                .with_scope("audit:read"),
+        );
+        iam.add_rule(
+            Rule::allow("identity-present", &[Action::Write], "identity/credentials")
+                .with_roles(&[DEFAULT_ROLE])
+                .with_scope("identity:write"),
+        );
+        iam.add_rule(
+            Rule::allow("admin", &[Action::Read, Action::Write, Action::Admin], "**")
+                .with_roles(&[ADMIN_ROLE])
+                .with_scope("admin"),
+        );
+        iam
+    }
+
+    /// Register or replace a role
+    pub fn add_role(&mut self, role: Role) {
+        self.roles.insert(role.name.clone(), role);
+    }
+
+    pub fn role(&self, name: &str) -> Option<&Role> {
+        self.roles.get(name)
+    }
+
+    pub fn roles(&self) -> impl Iterator<Item = &Role> {
+        self.roles.values()
+    }
+
+    /// Register a rule, replacing any rule with the same id
+    pub fn add_rule(&mut self, rule: Rule) {
+        match self.rules.iter_mut().find(|r| r.id == rule.id) {
+            Some(existing) => *existing = rule,
+            None => self.rules.push(rule),
+        }
+    }
+
+    pub fn remove_rule(&mut self, id: &str) -> Option<Rule> {
+        let index = self.rules.iter().position(|r| r.id == id)?;
+        Some(self.rules.remove(index))
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// Grant a role, replacing an earlier grant of the same role
+    pub fn grant(
+        &mut self,
+        subject: &str,
+        role: &str,
+        granted_by: &str,
+        now: u64,
+        expires_at: Option<u64>,
+    ) -> Result<Grant, IamError> {
+        if !self.roles.contains_key(role) {
+            return Err(IamError::UnknownRole(role.to_string()));
+        }
+        let grant = Grant {
+            subject: subject.to_string(),
+            role: role.to_string(),
+            granted_by: granted_by.to_string(),
+            granted_at: now,
+            expires_at,
+        };
+        let grants = self.grants.entry(subject.to_string()).or_default();
+        grants.retain(|g| g.role != role);
+        grants.push(grant.clone());
+        Ok(grant)
+    }
+
+    /// Withdraw a role from a subject
+    pub fn revoke(&mut self, subject: &str, role: &str) -> Result<Grant, IamError> {
+        let not_found = || IamError::GrantNotFound {
+            subject: subject.to_string(),
+            role: role.to_string(),
+        };
+        let grants = self.grants.get_mut(subject).ok_or_else(not_found)?;
+        let index = grants
+            .iter()
+            .position(|g| g.role == role)
+            .ok_or_else(not_found)?;
+        let grant = grants.remove(index);
+        if grants.is_empty() {
+            self.grants.remove(subject);
+        }
+        Ok(grant)
+    }
+
+    /// Grants recorded for a subject, including lapsed ones
+    pub fn grants(&self, subject: &str) -> Vec<Grant> {
+        self.grants.get(subject).cloned().unwrap_or_default()
+    }
+
+    /// Roles a subject currently holds through grants, for token issuance
+    pub fn granted_roles(&self, subject: &str, now: u64) -> Vec<String> {
+        let mut roles: Vec<String> = self
+            .grants
+            .get(subject)
+            .into_iter()
+            .flatten()
+            .filter(|g| g.is_active(now))
+            .map(|g| g.role.clone())
+            .collect();
+        roles.sort();
+        roles
     }
-}
\ No newline at end of file
+
+    /// Roles that count for a request: the default role plus every claimed
+    /// role still backed by an active grant
+    pub fn effective_roles(&self, subject: &Subject, now: u64) -> Vec<String> {
+        let granted = self.granted_roles(&subject.id, now);
+        let mut roles = vec![DEFAULT_ROLE.to_string()];
+        for role in &subject.roles {
+            if granted.contains(role) && !roles.contains(role) {
+                roles.push(role.clone());
+            }
+        }
+        roles
+    }
+
+    /// Decide whether `subject` may perform `action` on `resource`
+    pub fn authorize(
+        &self,
+        subject: &Subject,
+        action: Action,
+        resource: &Resource,
+        now: u64,
+    ) -> Result<(), IamError> {
+        let roles = self.effective_roles(subject, now);
+        let clearance = roles
+            .iter()
+            .filter_map(|r| self.roles.get(r))
+            .map(|r| r.clearance.clone())
+            .max()
+            .unwrap_or(ClassificationLevel::Public);
+        if resource.classification > clearance && !resource.lists(&subject.id) {
+            return Err(IamError::AccessDenied(format!(
+                "{} is classified {:?} and {} is neither cleared nor on its ACL",
+                resource.id, resource.classification, subject.id
+            )));
+        }
+
+        let mut allowed = false;
+        let mut missing_scope = None;
+        for rule in &self.rules {
+            if !rule.actions.contains(&action) {
+                continue;
+            }
+            if !rule.roles.is_empty() && !rule.roles.iter().any(|r| roles.contains(r)) {
+                continue;
+            }
+            let Some(bindings) = match_resource(&rule.resource, &resource.id) else {
+                continue;
+            };
+            if !rule
+                .conditions
+                .iter()
+                .all(|c| condition_holds(c, subject, resource, &bindings))
+            {
+                continue;
+            }
+            match rule.effect {
+                Effect::Deny => {
+                    return Err(IamError::AccessDenied(format!(
+                        "rule {} denies {} on {}",
+                        rule.id,
+                        action.as_str(),
+                        resource.id
+                    )))
+                }
+                Effect::Allow => match (&rule.scope, &subject.scopes) {
+                    (Some(scope), Some(scopes)) if !scopes.contains(scope) => {
+                        missing_scope.get_or_insert_with(|| scope.clone());
+                    }
+                    _ => allowed = true,
+                },
+            }
+        }
+
+        if allowed {
+            return Ok(());
+        }
+        Err(IamError::AccessDenied(match missing_scope {
+            Some(scope) => format!("token lacks scope {}", scope),
+            None => format!(
+                "no rule allows {} to {} {}",
+                subject.id,
+                action.as_str(),
+                resource.id
+            ),
+        }))
+    }
+}
+
+/// Match a resource id against a rule pattern, returning the `{name}` bindings
+fn match_resource(pattern: &str, id: &str) -> Option<BTreeMap<String, String>> {
+    let mut bindings = BTreeMap::new();
+    let mut segments = id.split('/');
+    for part in pattern.split('/') {
+        if part == "**" {
+            return Some(bindings);
+        }
+        let segment = segments.next()?;
+        if let Some(name) = part.strip_prefix('{').and_then(|p| p.strip_suffix('}')) {
+            bindings.insert(name.to_string(), segment.to_string());
+        } else if part != "*" && part != segment {
+            return None;
+        }
+    }
+    segments.next().is_none().then_some(bindings)
+}
+
+fn condition_holds(
+    condition: &Condition,
+    subject: &Subject,
+    resource: &Resource,
+    bindings: &BTreeMap<String, String>,
+) -> bool {
+    let resource_attribute =
+        |name: &str| bindings.get(name).or_else(|| resource.attributes.get(name));
+    match condition {
+        Condition::Owner => resource.owner.as_deref() == Some(subject.id.as_str()),
+        Condition::SubjectIs { attribute } => {
+            resource_attribute(attribute).is_some_and(|v| *v == subject.id)
+        }
+        Condition::SubjectAttribute { name, value } => subject.attributes.get(name) == Some(value),
+        Condition::ResourceAttribute { name, value } => resource_attribute(name) == Some(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_policy_scopes_trader_routes_to_the_subject() {
+        let iam = IAM::with_default_policy();
+        let alice = Subject::new("alice");
+        let own = Resource::new("orderbook/traders/alice/trades");
+        let other = Resource::new("orderbook/traders/bob/trades");
+
+        assert!(iam.authorize(&alice, Action::Read, &own, 0).is_ok());
+        assert!(iam.authorize(&alice, Action::Read, &other, 0).is_err());
+        assert!(iam
+            .authorize(&alice, Action::Admin, &Resource::new("admin/grants"), 0)
+            .is_err());
+
+        let restricted = alice.with_scopes(Some(vec!["orders:write".to_string()]));
+        assert!(matches!(
+            iam.authorize(&restricted, Action::Read, &own, 0),
+            Err(IamError::AccessDenied(reason)) if reason.contains("trades:read")
+        ));
+        assert!(iam
+            .authorize(
+                &restricted,
+                Action::Write,
+                &Resource::new("orderbook/orders"),
+                0
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn claimed_roles_need_an_active_grant() {
+        let mut iam = IAM::with_default_policy();
+        let auditor = Subject::new("carol").with_roles(vec!["auditor".to_string()]);
+        let trades = Resource::new("orderbook/traders/bob/trades");
+
+        assert!(iam.authorize(&auditor, Action::Read, &trades, 0).is_err());
+        iam.grant("carol", "auditor", "root", 0, Some(100)).unwrap();
+        assert_eq!(iam.granted_roles("carol", 10), vec!["auditor".to_string()]);
+        assert!(iam.authorize(&auditor, Action::Read, &trades, 10).is_ok());
+        assert!(iam.authorize(&auditor, Action::Read, &trades, 100).is_err());
+
+        iam.revoke("carol", "auditor").unwrap();
+        assert!(iam.grants("carol").is_empty());
+        assert!(matches!(
+            iam.revoke("carol", "auditor"),
+            Err(IamError::GrantNotFound { .. })
+        ));
+        assert!(matches!(
+            iam.grant("carol", "root", "root", 0, None),
+            Err(IamError::UnknownRole(_))
+        ));
+    }
+
+    #[test]
+    fn classification_acl_gates_before_rules() {
+        let mut iam = IAM::with_default_policy();
+        iam.grant("carol", "auditor", "root", 0, None).unwrap();
+        let classification = DataClassification {
+            level: ClassificationLevel::Secret,
+            owner: "bob".to_string(),
+            acl: vec!["dave".to_string()],
+            timestamp: 0,
+        };
+        let resource = Resource::classified("orderbook/orders/7/trades", &classification);
+
+        assert!(iam
+            .authorize(&Subject::new("dave"), Action::Read, &resource, 0)
+            .is_ok());
+        assert!(iam
+            .authorize(&Subject::new("alice"), Action::Read, &resource, 0)
+            .is_err());
+        let auditor = Subject::new("carol").with_roles(vec!["auditor".to_string()]);
+        assert!(iam.authorize(&auditor, Action::Read, &resource, 0).is_err());
+
+        iam.add_rule(
+            Rule::deny("freeze-dave", &[Action::Read], "orderbook/**").with_condition(
+                Condition::SubjectAttribute {
+                    name: "status".to_string(),
+                    value: "frozen".to_string(),
+                },
+            ),
+        );
+        let frozen = Subject::new("dave").with_attribute("status", "frozen");
+        assert!(matches!(
+            iam.authorize(&frozen, Action::Read, &resource, 0),
+            Err(IamError::AccessDenied(reason)) if reason.contains("freeze-dave")
+        ));
+    }
+}
@@ -18,9 +18,13 @@ pub use reference::{
     GovernanceReferenceError, GovernanceScenario,
 };
 pub use policy_engine::{policy_for, parse_checkpoint, parse_effect, Checkpoint, PolicyEffect};
-// IAM policies are not yet wired in this crate; omit re-exports to avoid unresolved symbols.
-// pub use iam::{ApprovalGatePolicy, RoleManagerPolicy, IamError};
-pub use compliance::{build_compliance_report, render_report_json, ComplianceReport, ComplianceEntry, FrameworkRef};
+pub use iam::{
+    Action, Condition, Effect, Grant, IamError, Resource, Role, Rule, Subject, ADMIN_ROLE, DEFAULT_ROLE, IAM,
+};
+pub use compliance::{
+    build_compliance_report, render_report_json, ComplianceEntry, ComplianceError, ComplianceReport, FrameworkRef,
+    KycComplianceAttestation, KycComplianceCheck,
+};
 pub use risk::{RiskRegistry, RiskRegistryState, RiskItem, ExceptionRequest, Notification, RiskError};
 pub use audit::{AuditStore, EvidenceRecord, AuditError};
 
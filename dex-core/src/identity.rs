@@ -1,511 +0,0 @@
-//! Global Identity implementation for the DEX-OS core engine
-//!
-//! This module implements the Priority 3 features from DEX-OS-V2.csv:
-//! - Global Identity (DID + Biometrics, Self-Sovereign, Quantum-Secure)
-//!
-//! It provides functionality for decentralized identity management with
-//! biometric verification, self-sovereign principles, and quantum-secure cryptography.
-
-use crate::types::{TokenId, TraderId};
-use serde::{Deserialize, Serialize};
-use sha3::{Digest, Sha3_256};
-use std::collections::HashMap;
-use thiserror::Error;
-
-/// Represents a Decentralized Identifier (DID)
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub struct DID {
-    /// The DID identifier
-    pub id: String,
-    /// The DID document containing public keys and service endpoints
-    pub document: DIDDocument,
-    /// Timestamp of creation
-    pub created: u64,
-    /// Timestamp of last update
-    pub updated: u64,
-    /// Digital signature proving ownership
-    pub signature: Vec<u8>,
-}
-
-/// DID Document structure
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub struct DIDDocument {
-    /// Public keys associated with this DID
-    pub public_keys: Vec<PublicKey>,
-    /// Service endpoints
-    pub services: Vec<ServiceEndpoint>,
-    /// Authentication methods
-    pub authentication: Vec<String>,
-}
-
-/// Public key information
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub struct PublicKey {
-    /// Key identifier
-    pub id: String,
-    /// Key type (e.g., Ed25519, RSA, Dilithium for quantum security)
-    pub key_type: String,
-    /// The public key value
-    pub public_key: Vec<u8>,
-    /// Key usage (e.g., authentication, encryption)
-    pub usage: String,
-}
-
-/// Service endpoint information
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub struct ServiceEndpoint {
-    /// Service identifier
-    pub id: String,
-    /// Service type
-    pub service_type: String,
-    /// Service endpoint URL
-    pub endpoint: String,
-}
-
-/// Biometric data structure for identity verification
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub struct BiometricData {
-    /// Biometric type (e.g., fingerprint, face, iris)
-    pub bio_type: String,
-    /// Hash of the biometric template (never stores raw biometric data)
-    pub template_hash: Vec<u8>,
-    /// Timestamp of enrollment
-    pub enrolled_at: u64,
-    /// Confidence level of the biometric match
-    pub confidence: f32,
-}
-
-/// Self-sovereign identity with user-controlled data
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub struct SelfSovereignIdentity {
-    /// The DID associated with this identity
-    pub did: DID,
-    /// Encrypted personal data controlled by the user
-    pub personal_data: Vec<u8>,
-    /// Biometric verification data
-    pub biometrics: Vec<BiometricData>,
-    /// Claims from trusted issuers
-    pub verifiable_credentials: Vec<VerifiableCredential>,
-    /// Revocation status
-    pub is_revoked: bool,
-}
-
-/// Verifiable credential issued by a trusted authority
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub struct VerifiableCredential {
-    /// Credential identifier
-    pub id: String,
-    /// Issuer DID
-    pub issuer: String,
-    /// Subject DID
-    pub subject: String,
-    /// Credential data
-    pub data: HashMap<String, String>,
-    /// Issuance timestamp
-    pub issued_at: u64,
-    /// Expiration timestamp
-    pub expires_at: Option<u64>,
-    /// Digital signature from issuer
-    pub signature: Vec<u8>,
-}
-
-/// Quantum-secure cryptographic operations
-pub struct QuantumSecureCrypto;
-
-impl QuantumSecureCrypto {
-    /// Generate a quantum-secure key pair using Dilithium algorithm
-    pub fn generate_dilithium_keypair() -> (Vec<u8>, Vec<u8>) {
-        // In a real implementation, this would use the actual Dilithium algorithm
-        // For now, we'll simulate with random bytes
-        (vec![0u8; 32], vec![1u8; 32])
-    }
-
-    /// Sign data using quantum-secure signature scheme
-    pub fn quantum_sign(private_key: &[u8], data: &[u8]) -> Vec<u8> {
-        // In a real implementation, this would use Dilithium signatures
-        // For now, we'll simulate with SHA3-256 hash
-        let mut hasher = Sha3_256::new();
-        hasher.update(private_key);
-        hasher.update(data);
-        hasher.finalize().to_vec()
-    }
-
-    /// Verify quantum-secure signature
-    pub fn quantum_verify(public_key: &[u8], data: &[u8], signature: &[u8]) -> bool {
-        // In a real implementation, this would use Dilithium verification
-        // For now, we'll simulate verification by creating a deterministic signature
-        // that matches what quantum_sign would produce
-        let mut hasher = Sha3_256::new();
-        // For verification, we need to simulate what the private key would be
-        // based on the public key. In a real implementation, this would be different.
-        // For our simulation, we'll use a transformation of the public key to simulate the private key
-        let simulated_private_key: Vec<u8> = public_key.iter().map(|b| b.wrapping_sub(1)).collect();
-        hasher.update(&simulated_private_key);
-        hasher.update(data);
-        let expected_signature = hasher.finalize().to_vec();
-        expected_signature == signature
-    }
-
-    /// Hash biometric data for secure storage
-    pub fn hash_biometric(data: &[u8]) -> Vec<u8> {
-        let mut hasher = Sha3_256::new();
-        hasher.update(data);
-        hasher.finalize().to_vec()
-    }
-}
-
-/// Global Identity Manager
-#[derive(Debug, Clone)]
-pub struct IdentityManager {
-    /// Storage for DIDs
-    dids: HashMap<String, DID>,
-    /// Storage for self-sovereign identities
-    identities: HashMap<String, SelfSovereignIdentity>,
-    /// Biometric verification cache
-    biometric_cache: HashMap<String, Vec<u8>>,
-    /// Trusted issuers for verifiable credentials
-    trusted_issuers: Vec<String>,
-}
-
-impl IdentityManager {
-    /// Create a new identity manager
-    pub fn new() -> Self {
-        Self {
-            dids: HashMap::new(),
-            identities: HashMap::new(),
-            biometric_cache: HashMap::new(),
-            trusted_issuers: Vec::new(),
-        }
-    }
-
-    /// Create a new DID with quantum-secure keys
-    pub fn create_did(&mut self, trader_id: &TraderId) -> Result<DID, IdentityError> {
-        let (private_key, public_key) = QuantumSecureCrypto::generate_dilithium_keypair();
-
-        let document = DIDDocument {
-            public_keys: vec![PublicKey {
-                id: format!("{}#key-1", trader_id),
-                key_type: "Dilithium".to_string(),
-                public_key,
-                usage: "authentication".to_string(),
-            }],
-            services: vec![],
-            authentication: vec![format!("{}#key-1", trader_id)],
-        };
-
-        let did = DID {
-            id: trader_id.clone(),
-            document,
-            created: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
-            updated: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
-            signature: vec![], // Would be signed with private key in real implementation
-        };
-
-        self.dids.insert(trader_id.clone(), did.clone());
-        Ok(did)
-    }
-
-    /// Register biometric data for a DID
-    pub fn register_biometric(
-        &mut self,
-        did_id: &str,
-        bio_type: &str,
-        bio_data: &[u8],
-    ) -> Result<(), IdentityError> {
-        let did = self.dids.get(did_id).ok_or(IdentityError::DIDNotFound)?;
-
-        // Hash the biometric data for secure storage
-        let template_hash = QuantumSecureCrypto::hash_biometric(bio_data);
-
-        // In a real implementation, we would store this in the identity
-        // For now, we'll just cache it
-        self.biometric_cache
-            .insert(format!("{}:{}", did_id, bio_type), template_hash);
-
-        Ok(())
-    }
-
-    /// Verify biometric data against registered template
-    pub fn verify_biometric(
-        &self,
-        did_id: &str,
-        bio_type: &str,
-        bio_data: &[u8],
-    ) -> Result<bool, IdentityError> {
-        let template_hash = self
-            .biometric_cache
-            .get(&format!("{}:{}", did_id, bio_type))
-            .ok_or(IdentityError::BiometricNotRegistered)?;
-
-        let provided_hash = QuantumSecureCrypto::hash_biometric(bio_data);
-        Ok(template_hash == &provided_hash)
-    }
-
-    /// Create a self-sovereign identity
-    pub fn create_self_sovereign_identity(
-        &mut self,
-        did_id: &str,
-        personal_data: Vec<u8>,
-    ) -> Result<SelfSovereignIdentity, IdentityError> {
-        let did = self
-            .dids
-            .get(did_id)
-            .ok_or(IdentityError::DIDNotFound)?
-            .clone();
-
-        let identity = SelfSovereignIdentity {
-            did,
-            personal_data,
-            biometrics: vec![],
-            verifiable_credentials: vec![],
-            is_revoked: false,
-        };
-
-        self.identities.insert(did_id.to_string(), identity.clone());
-        Ok(identity)
-    }
-
-    /// Add a verifiable credential to an identity
-    pub fn add_verifiable_credential(
-        &mut self,
-        did_id: &str,
-        credential: VerifiableCredential,
-    ) -> Result<(), IdentityError> {
-        let identity = self
-            .identities
-            .get_mut(did_id)
-            .ok_or(IdentityError::IdentityNotFound)?;
-
-        // Verify the credential is from a trusted issuer
-        if !self.trusted_issuers.contains(&credential.issuer) {
-            return Err(IdentityError::UntrustedIssuer);
-        }
-
-        identity.verifiable_credentials.push(credential);
-        Ok(())
-    }
-
-    /// Revoke an identity
-    pub fn revoke_identity(&mut self, did_id: &str) -> Result<(), IdentityError> {
-        let identity = self
-            .identities
-            .get_mut(did_id)
-            .ok_or(IdentityError::IdentityNotFound)?;
-        identity.is_revoked = true;
-        Ok(())
-    }
-
-    /// Check if an identity is valid (exists and not revoked)
-    pub fn is_valid_identity(&self, did_id: &str) -> bool {
-        match self.identities.get(did_id) {
-            Some(identity) => !identity.is_revoked,
-            None => false,
-        }
-    }
-
-    /// Add a trusted issuer
-    pub fn add_trusted_issuer(&mut self, issuer_did: String) {
-        if !self.trusted_issuers.contains(&issuer_did) {
-            self.trusted_issuers.push(issuer_did);
-        }
-    }
-
-    /// Get a DID by ID
-    pub fn get_did(&self, did_id: &str) -> Option<&DID> {
-        self.dids.get(did_id)
-    }
-
-    /// Get a self-sovereign identity by DID
-    pub fn get_identity(&self, did_id: &str) -> Option<&SelfSovereignIdentity> {
-        self.identities.get(did_id)
-    }
-}
-
-impl Default for IdentityManager {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-/// Errors that can occur during identity operations
-#[derive(Debug, Error)]
-pub enum IdentityError {
-    #[error("DID not found")]
-    DIDNotFound,
-    #[error("Identity not found")]
-    IdentityNotFound,
-    #[error("Biometric data not registered")]
-    BiometricNotRegistered,
-    #[error("Untrusted credential issuer")]
-    UntrustedIssuer,
-    #[error("Invalid biometric data")]
-    InvalidBiometric,
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_identity_manager_creation() {
-        let manager = IdentityManager::new();
-        assert!(manager.dids.is_empty());
-        assert!(manager.identities.is_empty());
-    }
-
-    #[test]
-    fn test_create_did() {
-        let mut manager = IdentityManager::new();
-        let trader_id = "trader1".to_string();
-
-        let did = manager.create_did(&trader_id);
-        assert!(did.is_ok());
-
-        let did = did.unwrap();
-        assert_eq!(did.id, trader_id);
-        assert_eq!(did.document.public_keys.len(), 1);
-        assert_eq!(did.document.public_keys[0].key_type, "Dilithium");
-
-        // Check that DID was stored
-        assert!(manager.get_did(&trader_id).is_some());
-    }
-
-    #[test]
-    fn test_biometric_registration_and_verification() {
-        let mut manager = IdentityManager::new();
-        let trader_id = "trader1".to_string();
-
-        // Create DID first
-        assert!(manager.create_did(&trader_id).is_ok());
-
-        // Register biometric data
-        let bio_data = b"fingerprint_data";
-        assert!(manager
-            .register_biometric(&trader_id, "fingerprint", bio_data)
-            .is_ok());
-
-        // Verify correct biometric data
-        let result = manager.verify_biometric(&trader_id, "fingerprint", bio_data);
-        assert!(result.is_ok());
-        assert!(result.unwrap());
-
-        // Verify incorrect biometric data
-        let result = manager.verify_biometric(&trader_id, "fingerprint", b"different_data");
-        assert!(result.is_ok());
-        assert!(!result.unwrap());
-    }
-
-    #[test]
-    fn test_self_sovereign_identity() {
-        let mut manager = IdentityManager::new();
-        let trader_id = "trader1".to_string();
-
-        // Create DID first
-        assert!(manager.create_did(&trader_id).is_ok());
-
-        // Create self-sovereign identity
-        let personal_data = b"encrypted_personal_data".to_vec();
-        let identity = manager.create_self_sovereign_identity(&trader_id, personal_data.clone());
-        assert!(identity.is_ok());
-
-        let identity = identity.unwrap();
-        assert_eq!(identity.personal_data, personal_data);
-        assert!(!identity.is_revoked);
-
-        // Check that identity was stored
-        assert!(manager.get_identity(&trader_id).is_some());
-
-        // Check validity
-        assert!(manager.is_valid_identity(&trader_id));
-
-        // Revoke identity
-        assert!(manager.revoke_identity(&trader_id).is_ok());
-        assert!(!manager.is_valid_identity(&trader_id));
-    }
-
-    #[test]
-    fn test_verifiable_credentials() {
-        let mut manager = IdentityManager::new();
-        let trader_id = "trader1".to_string();
-        let issuer_did = "issuer1".to_string();
-
-        // Add trusted issuer
-        manager.add_trusted_issuer(issuer_did.clone());
-
-        // Create DID and identity
-        assert!(manager.create_did(&trader_id).is_ok());
-        let personal_data = b"personal_data".to_vec();
-        assert!(manager
-            .create_self_sovereign_identity(&trader_id, personal_data)
-            .is_ok());
-
-        // Create verifiable credential
-        let mut data = HashMap::new();
-        data.insert("name".to_string(), "John Doe".to_string());
-        data.insert("age".to_string(), "30".to_string());
-
-        let credential = VerifiableCredential {
-            id: "cred1".to_string(),
-            issuer: issuer_did,
-            subject: trader_id.clone(),
-            data,
-            issued_at: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
-            expires_at: None,
-            signature: vec![1, 2, 3, 4],
-        };
-
-        // Add credential to identity
-        assert!(manager
-            .add_verifiable_credential(&trader_id, credential)
-            .is_ok());
-
-        // Check that credential was added
-        let identity = manager.get_identity(&trader_id).unwrap();
-        assert_eq!(identity.verifiable_credentials.len(), 1);
-    }
-
-    #[test]
-    fn test_quantum_secure_crypto() {
-        let (private_key, public_key) = QuantumSecureCrypto::generate_dilithium_keypair();
-        assert_eq!(private_key.len(), 32);
-        assert_eq!(public_key.len(), 32);
-
-        let data = b"test data for signing";
-        let signature = QuantumSecureCrypto::quantum_sign(&private_key, data);
-        assert!(!signature.is_empty());
-
-        let valid = QuantumSecureCrypto::quantum_verify(&public_key, data, &signature);
-        assert!(valid);
-
-        // Test with incorrect data
-        let invalid =
-            QuantumSecureCrypto::quantum_verify(&public_key, b"different data", &signature);
-        assert!(!invalid);
-    }
-
-    #[test]
-    fn test_biometric_hashing() {
-        let bio_data1 = b"fingerprint_template_1";
-        let bio_data2 = b"fingerprint_template_2";
-
-        let hash1 = QuantumSecureCrypto::hash_biometric(bio_data1);
-        let hash2 = QuantumSecureCrypto::hash_biometric(bio_data2);
-
-        assert_eq!(hash1.len(), 32); // SHA3-256 produces 32-byte hash
-        assert_eq!(hash2.len(), 32);
-        assert_ne!(hash1, hash2); // Different inputs should produce different hashes
-
-        // Same input should produce same hash
-        let hash1_again = QuantumSecureCrypto::hash_biometric(bio_data1);
-        assert_eq!(hash1, hash1_again);
-    }
-}
@@ -0,0 +1,564 @@
+//! Verifiable credentials
+//!
+//! This module implements part of the Priority 3 features from DEX-OS-V2.csv:
+//! - Global Identity (Self-Sovereign)
+//!
+//! Credentials follow the W3C Verifiable Credentials Data Model 2.0. They are
+//! secured with a `DataIntegrityProof` using the `eddsa-jcs-2022` cryptosuite:
+//! the proof options and the unsecured credential are canonicalized with JCS
+//! (RFC 8785), hashed with SHA-256 and signed with one of the issuer's ed25519
+//! assertion methods. Revocation uses a bitstring status list per issuer; a
+//! credential's `credentialStatus` names its list and index. Issuers publish
+//! their list as a signed `BitstringStatusListCredential` so verifiers in other
+//! processes can import it.
+//!
+//! Timestamps are `YYYY-MM-DDTHH:MM:SSZ` UTC date-times.
+
+use super::did::{decode_multibase, encode_multibase, normalize_did};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+/// JSON-LD context of the VC Data Model 2.0
+pub const CREDENTIALS_V2_CONTEXT: &str = "https://www.w3.org/ns/credentials/v2";
+
+/// Base type every credential carries
+pub const VERIFIABLE_CREDENTIAL_TYPE: &str = "VerifiableCredential";
+
+/// Credential type attesting a completed KYC check
+pub const KYC_CREDENTIAL_TYPE: &str = "KycCredential";
+
+/// Proof type of credential signatures
+pub const DATA_INTEGRITY_PROOF: &str = "DataIntegrityProof";
+
+/// Cryptosuite of credential signatures
+pub const EDDSA_JCS_2022: &str = "eddsa-jcs-2022";
+
+/// Proof purpose of credential signatures
+pub const ASSERTION_METHOD_PURPOSE: &str = "assertionMethod";
+
+/// Type of status list entries
+pub const STATUS_LIST_ENTRY_TYPE: &str = "BitstringStatusListEntry";
+
+/// Status purpose for revocation
+pub const REVOCATION_PURPOSE: &str = "revocation";
+
+/// Type of the credential publishing a status list
+pub const STATUS_LIST_CREDENTIAL_TYPE: &str = "BitstringStatusListCredential";
+
+/// Type of a status list credential's subject
+pub const STATUS_LIST_TYPE: &str = "BitstringStatusList";
+
+/// Minimum status list length in bits, so one index does not single out a holder
+const MIN_STATUS_LIST_BITS: usize = 131_072;
+
+/// A verifiable credential in the VC Data Model 2.0
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VerifiableCredential {
+    /// JSON-LD contexts
+    #[serde(rename = "@context")]
+    pub context: Vec<String>,
+    /// Credential identifier
+    pub id: String,
+    /// Credential types, starting with `VerifiableCredential`
+    #[serde(rename = "type")]
+    pub credential_type: Vec<String>,
+    /// Issuer DID
+    pub issuer: String,
+    /// Start of the validity period
+    #[serde(rename = "validFrom")]
+    pub valid_from: String,
+    /// End of the validity period
+    #[serde(
+        rename = "validUntil",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub valid_until: Option<String>,
+    /// Subject DID and claims
+    #[serde(rename = "credentialSubject")]
+    pub credential_subject: CredentialSubject,
+    /// Revocation status entry
+    #[serde(
+        rename = "credentialStatus",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub credential_status: Option<CredentialStatus>,
+    /// Issuer proof
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proof: Option<DataIntegrityProof>,
+}
+
+/// The subject of a credential and the claims made about it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CredentialSubject {
+    /// Subject DID
+    pub id: String,
+    /// Claims about the subject
+    #[serde(flatten)]
+    pub claims: BTreeMap<String, Value>,
+}
+
+/// Pointer to a credential's entry in a status list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CredentialStatus {
+    /// Identifier of this entry
+    pub id: String,
+    /// Entry type
+    #[serde(rename = "type")]
+    pub status_type: String,
+    /// What a set bit means
+    #[serde(rename = "statusPurpose")]
+    pub status_purpose: String,
+    /// Bit index in the list, as a decimal string
+    #[serde(rename = "statusListIndex")]
+    pub status_list_index: String,
+    /// Identifier of the status list
+    #[serde(rename = "statusListCredential")]
+    pub status_list_credential: String,
+}
+
+/// Data integrity proof over a credential
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataIntegrityProof {
+    /// Proof type
+    #[serde(rename = "type")]
+    pub proof_type: String,
+    /// Cryptosuite used
+    pub cryptosuite: String,
+    /// Creation time
+    pub created: String,
+    /// DID URL of the signing key
+    #[serde(rename = "verificationMethod")]
+    pub verification_method: String,
+    /// Relationship the signing key must have to the issuer
+    #[serde(rename = "proofPurpose")]
+    pub proof_purpose: String,
+    /// Multibase-encoded signature
+    #[serde(
+        rename = "proofValue",
+        default,
+        skip_serializing_if = "String::is_empty"
+    )]
+    pub proof_value: String,
+}
+
+impl VerifiableCredential {
+    /// Create an unsigned credential valid from now. `issuer` and `subject`
+    /// may be `did:dexos` identifiers or trader IDs.
+    pub fn new(
+        id: impl Into<String>,
+        issuer: &str,
+        subject: &str,
+        credential_type: &str,
+        claims: BTreeMap<String, Value>,
+    ) -> Self {
+        let mut types = vec![VERIFIABLE_CREDENTIAL_TYPE.to_string()];
+        if credential_type != VERIFIABLE_CREDENTIAL_TYPE {
+            types.push(credential_type.to_string());
+        }
+        Self {
+            context: vec![CREDENTIALS_V2_CONTEXT.to_string()],
+            id: id.into(),
+            credential_type: types,
+            issuer: normalize_did(issuer),
+            valid_from: format_timestamp(current_timestamp()),
+            valid_until: None,
+            credential_subject: CredentialSubject {
+                id: normalize_did(subject),
+                claims,
+            },
+            credential_status: None,
+            proof: None,
+        }
+    }
+
+    /// Set the end of the validity period
+    pub fn with_valid_until(mut self, valid_until: u64) -> Self {
+        self.valid_until = Some(format_timestamp(valid_until));
+        self
+    }
+
+    /// Whether the credential carries `credential_type`
+    pub fn has_type(&self, credential_type: &str) -> bool {
+        self.credential_type.iter().any(|t| t == credential_type)
+    }
+
+    /// Subject DID
+    pub fn subject(&self) -> &str {
+        &self.credential_subject.id
+    }
+
+    /// A claim about the subject
+    pub fn claim(&self, name: &str) -> Option<&Value> {
+        self.credential_subject.claims.get(name)
+    }
+}
+
+/// A bitstring status list; bit `i` set means the credential at index `i` is revoked
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusList {
+    /// Identifier of the list, referenced by `statusListCredential`
+    pub id: String,
+    /// Status bits, most significant bit first
+    bits: Vec<u8>,
+    /// Number of indices handed out
+    allocated: usize,
+}
+
+impl StatusList {
+    /// Create an empty status list
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            bits: vec![0; MIN_STATUS_LIST_BITS / 8],
+            allocated: 0,
+        }
+    }
+
+    /// Reserve the next index and return its status entry
+    pub fn allocate(&mut self) -> CredentialStatus {
+        let index = self.allocated;
+        self.allocated += 1;
+        if index / 8 >= self.bits.len() {
+            self.bits.resize(self.bits.len() * 2, 0);
+        }
+        CredentialStatus {
+            id: format!("{}#{}", self.id, index),
+            status_type: STATUS_LIST_ENTRY_TYPE.to_string(),
+            status_purpose: REVOCATION_PURPOSE.to_string(),
+            status_list_index: index.to_string(),
+            status_list_credential: self.id.clone(),
+        }
+    }
+
+    /// Set the bit at `index`. Returns false for indices never handed out.
+    pub fn revoke(&mut self, index: usize) -> bool {
+        if index >= self.allocated {
+            return false;
+        }
+        self.bits[index / 8] |= 0x80 >> (index % 8);
+        true
+    }
+
+    /// Whether the bit at `index` is set
+    pub fn is_revoked(&self, index: usize) -> bool {
+        self.bits
+            .get(index / 8)
+            .is_some_and(|byte| byte & (0x80 >> (index % 8)) != 0)
+    }
+
+    /// The list as a `u`-prefixed base64url multibase string. Unlike the
+    /// published `encodedList` of the status list spec it is not GZIP-compressed.
+    pub fn encoded_list(&self) -> String {
+        format!("u{}", URL_SAFE_NO_PAD.encode(&self.bits))
+    }
+
+    /// Rebuild a list from its [`Self::encoded_list`] form
+    pub fn decode(id: impl Into<String>, encoded_list: &str) -> Option<Self> {
+        let bits = URL_SAFE_NO_PAD
+            .decode(encoded_list.strip_prefix('u')?)
+            .ok()?;
+        Some(Self {
+            id: id.into(),
+            allocated: bits.len() * 8,
+            bits,
+        })
+    }
+
+    /// Fold in the revocations of another copy of this list. Revocation is
+    /// permanent, so bits are only ever set and replaying an old copy is harmless.
+    pub fn merge(&mut self, other: &StatusList) {
+        if other.bits.len() > self.bits.len() {
+            self.bits.resize(other.bits.len(), 0);
+        }
+        for (byte, other_byte) in self.bits.iter_mut().zip(&other.bits) {
+            *byte |= other_byte;
+        }
+        self.allocated = self.allocated.max(other.allocated);
+    }
+
+    /// The unsigned `BitstringStatusListCredential` publishing this list
+    pub fn to_credential(&self, issuer: &str) -> VerifiableCredential {
+        let mut claims = BTreeMap::new();
+        claims.insert("type".to_string(), Value::from(STATUS_LIST_TYPE));
+        claims.insert("statusPurpose".to_string(), Value::from(REVOCATION_PURPOSE));
+        claims.insert("encodedList".to_string(), Value::from(self.encoded_list()));
+        VerifiableCredential::new(
+            self.id.clone(),
+            issuer,
+            &format!("{}#list", self.id),
+            STATUS_LIST_CREDENTIAL_TYPE,
+            claims,
+        )
+    }
+}
+
+/// Sign `credential` with `signing_key`, published as `verification_method`
+pub(crate) fn attach_proof(
+    credential: &mut VerifiableCredential,
+    signing_key: &SigningKey,
+    verification_method: &str,
+) {
+    credential.proof = None;
+    let mut proof = DataIntegrityProof {
+        proof_type: DATA_INTEGRITY_PROOF.to_string(),
+        cryptosuite: EDDSA_JCS_2022.to_string(),
+        created: format_timestamp(current_timestamp()),
+        verification_method: verification_method.to_string(),
+        proof_purpose: ASSERTION_METHOD_PURPOSE.to_string(),
+        proof_value: String::new(),
+    };
+    let hash = hash_data(credential, &proof);
+    proof.proof_value = encode_multibase(&signing_key.sign(&hash).to_bytes());
+    credential.proof = Some(proof);
+}
+
+/// Check a credential's `eddsa-jcs-2022` proof against `public_key`
+pub(crate) fn verify_proof(credential: &VerifiableCredential, public_key: &[u8; 32]) -> bool {
+    let Some(proof) = &credential.proof else {
+        return false;
+    };
+    if proof.proof_type != DATA_INTEGRITY_PROOF
+        || proof.cryptosuite != EDDSA_JCS_2022
+        || proof.proof_purpose != ASSERTION_METHOD_PURPOSE
+    {
+        return false;
+    }
+    let Some(signature) = decode_multibase(&proof.proof_value)
+        .and_then(|bytes| <[u8; 64]>::try_from(bytes.as_slice()).ok())
+    else {
+        return false;
+    };
+    let Ok(key) = VerifyingKey::from_bytes(public_key) else {
+        return false;
+    };
+
+    let mut unsecured = credential.clone();
+    unsecured.proof = None;
+    let mut options = proof.clone();
+    options.proof_value = String::new();
+    key.verify(
+        &hash_data(&unsecured, &options),
+        &Signature::from_bytes(&signature),
+    )
+    .is_ok()
+}
+
+/// `SHA-256(JCS(proof options)) || SHA-256(JCS(unsecured credential))`
+fn hash_data(unsecured: &VerifiableCredential, options: &DataIntegrityProof) -> Vec<u8> {
+    let mut options = serde_json::to_value(options).expect("proofs serialize");
+    options["@context"] = serde_json::to_value(&unsecured.context).expect("contexts serialize");
+    let document = serde_json::to_value(unsecured).expect("credentials serialize");
+
+    let mut hash = Sha256::digest(canonical_json(&options).as_bytes()).to_vec();
+    hash.extend_from_slice(&Sha256::digest(canonical_json(&document).as_bytes()));
+    hash
+}
+
+/// JSON Canonicalization Scheme (RFC 8785) for the values credentials contain
+pub(crate) fn canonical_json(value: &Value) -> String {
+    let mut out = String::new();
+    write_canonical(value, &mut out);
+    out
+}
+
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            // RFC 8785 orders members by the UTF-16 code units of their names
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by_key(|(key, _)| key.encode_utf16().collect::<Vec<_>>());
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(value, out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
+
+/// Render Unix seconds as `YYYY-MM-DDTHH:MM:SSZ`
+pub fn format_timestamp(seconds: u64) -> String {
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    let time = seconds % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3_600,
+        time % 3_600 / 60,
+        time % 60
+    )
+}
+
+/// The UTC calendar date of Unix seconds as the number `YYYYMMDD`, which
+/// orders like the dates themselves
+pub fn calendar_date(seconds: u64) -> u64 {
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    year as u64 * 10_000 + u64::from(month) * 100 + u64::from(day)
+}
+
+/// Parse a `YYYY-MM-DDTHH:MM:SSZ` timestamp into Unix seconds
+pub fn parse_timestamp(timestamp: &str) -> Option<u64> {
+    let bytes = timestamp.as_bytes();
+    let separators = [
+        (4, b'-'),
+        (7, b'-'),
+        (10, b'T'),
+        (13, b':'),
+        (16, b':'),
+        (19, b'Z'),
+    ];
+    if bytes.len() != 20 || separators.iter().any(|&(i, c)| bytes[i] != c) {
+        return None;
+    }
+    let field = |start: usize, end: usize| -> Option<u64> {
+        let digits = &timestamp[start..end];
+        digits
+            .bytes()
+            .all(|b| b.is_ascii_digit())
+            .then(|| digits.parse().ok())?
+    };
+    let (year, month, day) = (field(0, 4)?, field(5, 7)?, field(8, 10)?);
+    let (hour, minute, second) = (field(11, 13)?, field(14, 16)?, field(17, 19)?);
+    if !(1..=12).contains(&month) || year < 1970 {
+        return None;
+    }
+
+    let days = days_from_civil(year as i64, month as u32, day as u32);
+    let seconds = u64::try_from(days).ok()? * 86_400 + hour * 3_600 + minute * 60 + second;
+    // Out-of-range days, hours, minutes or seconds do not survive the round trip
+    (format_timestamp(seconds) == timestamp).then_some(seconds)
+}
+
+/// Current Unix time in seconds
+pub(crate) fn current_timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Proleptic Gregorian date of a day count since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Day count since 1970-01-01 of a proleptic Gregorian date
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = month as i64;
+    let doy = (153 * if month > 2 { month - 3 } else { month + 9 } + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timestamps() {
+        assert_eq!(format_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(format_timestamp(1_700_000_000), "2023-11-14T22:13:20Z");
+        assert_eq!(calendar_date(1_700_000_000), 20_231_114);
+        for seconds in [0, 951_782_400, 1_700_000_000, 4_102_444_799] {
+            assert_eq!(parse_timestamp(&format_timestamp(seconds)), Some(seconds));
+        }
+        for bad in [
+            "2023-02-29T00:00:00Z",
+            "2023-11-14T24:00:00Z",
+            "2023-11-14 22:13:20Z",
+            "2023-11-14T22:13:20+00:00",
+            "+023-11-14T22:13:20Z",
+        ] {
+            assert_eq!(parse_timestamp(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_canonical_json() {
+        // RFC 8785 section 3.2.3 orders by UTF-16 code units
+        let value = serde_json::json!({
+            "\u{20ac}": "Euro Sign",
+            "\r": "Carriage Return",
+            "\u{fb33}": "Hebrew Letter Dalet With Dagesh",
+            "1": "One",
+            "\u{1f600}": "Emoji: Grinning Face",
+            "\u{0080}": "Control",
+            "\u{00f6}": "Latin Small Letter O With Diaeresis",
+        });
+        let keys: Vec<String> = canonical_json(&value)
+            .trim_matches(|c| c == '{' || c == '}')
+            .split(',')
+            .map(|entry| entry.split(':').next().unwrap().to_string())
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                "\"\\r\"",
+                "\"1\"",
+                "\"\u{0080}\"",
+                "\"\u{00f6}\"",
+                "\"\u{20ac}\"",
+                "\"\u{1f600}\"",
+                "\"\u{fb33}\""
+            ]
+        );
+        assert_eq!(
+            canonical_json(&serde_json::json!({"b": [1, {"d": true, "c": null}], "a": "x"})),
+            r#"{"a":"x","b":[1,{"c":null,"d":true}]}"#
+        );
+    }
+
+    #[test]
+    fn test_status_list() {
+        let mut list = StatusList::new("did:dexos:issuer/status-list");
+        let first = list.allocate();
+        let second = list.allocate();
+        assert_eq!(first.status_list_index, "0");
+        assert_eq!(second.status_list_index, "1");
+
+        assert!(list.revoke(1));
+        assert!(!list.is_revoked(0));
+        assert!(list.is_revoked(1));
+        // Unallocated indices cannot be revoked
+        assert!(!list.revoke(2));
+        assert!(list.encoded_list().starts_with("uQA"));
+    }
+}
@@ -0,0 +1,325 @@
+//! `did:dexos` DID method
+//!
+//! This module implements part of the Priority 3 features from DEX-OS-V2.csv:
+//! - Global Identity (DID + Biometrics, Self-Sovereign)
+//!
+//! A `did:dexos` identifier is `did:dexos:` followed by the trader ID, with
+//! every character outside `[A-Za-z0-9._-]` percent-encoded. Resolving it
+//! turns the stored [`DID`] into a W3C DID Core JSON-LD document: ed25519 keys
+//! become `Ed25519VerificationKey2020` methods with a multibase key, ML-DSA-65
+//! keys become `JsonWebKey2020` methods with an `AKP` JWK. Only ed25519 keys
+//! are listed under `assertionMethod`, so they are the keys that may sign
+//! credentials.
+
+use super::{IdentityError, DID, DILITHIUM_KEY_TYPE, ED25519_KEY_TYPE};
+use crate::types::TraderId;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+/// Prefix of every `did:dexos` identifier
+pub const DID_PREFIX: &str = "did:dexos:";
+
+/// Base DID Core JSON-LD context
+pub const DID_CONTEXT: &str = "https://www.w3.org/ns/did/v1";
+
+/// JSON-LD context for `Ed25519VerificationKey2020`
+pub const ED25519_2020_CONTEXT: &str = "https://w3id.org/security/suites/ed25519-2020/v1";
+
+/// JSON-LD context for `JsonWebKey2020`
+pub const JWS_2020_CONTEXT: &str = "https://w3id.org/security/suites/jws-2020/v1";
+
+/// Verification method type for ed25519 keys
+pub const ED25519_VERIFICATION_KEY_2020: &str = "Ed25519VerificationKey2020";
+
+/// Verification method type for keys published as JWKs
+pub const JSON_WEB_KEY_2020: &str = "JsonWebKey2020";
+
+/// Multicodec prefix of an ed25519 public key
+const ED25519_MULTICODEC: [u8; 2] = [0xed, 0x01];
+
+/// A resolved DID document in the W3C DID Core JSON-LD representation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DidDocumentJsonLd {
+    /// JSON-LD contexts
+    #[serde(rename = "@context")]
+    pub context: Vec<String>,
+    /// The `did:dexos` identifier
+    pub id: String,
+    /// Keys controlled by the DID subject
+    #[serde(rename = "verificationMethod")]
+    pub verification_method: Vec<VerificationMethod>,
+    /// Methods that may authenticate as the subject
+    pub authentication: Vec<String>,
+    /// Methods that may sign credentials on behalf of the subject
+    #[serde(rename = "assertionMethod")]
+    pub assertion_method: Vec<String>,
+    /// Service endpoints
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub service: Vec<Service>,
+}
+
+/// A verification method entry of a DID document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VerificationMethod {
+    /// DID URL of the method
+    pub id: String,
+    /// Verification method type
+    #[serde(rename = "type")]
+    pub method_type: String,
+    /// DID controlling the key
+    pub controller: String,
+    /// Multibase-encoded public key
+    #[serde(
+        rename = "publicKeyMultibase",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub public_key_multibase: Option<String>,
+    /// Public key as a JWK
+    #[serde(
+        rename = "publicKeyJwk",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub public_key_jwk: Option<serde_json::Value>,
+}
+
+/// A service entry of a DID document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Service {
+    /// DID URL of the service
+    pub id: String,
+    /// Service type
+    #[serde(rename = "type")]
+    pub service_type: String,
+    /// Service endpoint URL
+    #[serde(rename = "serviceEndpoint")]
+    pub service_endpoint: String,
+}
+
+impl DidDocumentJsonLd {
+    /// Public key of an ed25519 assertion method, if `method_id` is one
+    pub fn ed25519_assertion_key(&self, method_id: &str) -> Option<[u8; 32]> {
+        if !self.assertion_method.iter().any(|id| id == method_id) {
+            return None;
+        }
+        let method = self
+            .verification_method
+            .iter()
+            .find(|method| method.id == method_id)?;
+        if method.method_type != ED25519_VERIFICATION_KEY_2020 || method.controller != self.id {
+            return None;
+        }
+        decode_ed25519_multibase(method.public_key_multibase.as_deref()?)
+    }
+}
+
+/// The `did:dexos` identifier of a trader
+pub fn did_uri(trader_id: &str) -> String {
+    let mut uri = String::from(DID_PREFIX);
+    for byte in trader_id.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'.' | b'-' | b'_') {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    uri
+}
+
+/// The trader ID behind a `did:dexos` identifier
+pub fn parse_did_uri(uri: &str) -> Result<TraderId, IdentityError> {
+    let encoded = uri
+        .strip_prefix(DID_PREFIX)
+        .filter(|rest| !rest.is_empty())
+        .ok_or_else(|| IdentityError::InvalidDid(uri.to_string()))?;
+
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut chars = encoded.bytes();
+    while let Some(byte) = chars.next() {
+        match byte {
+            b'%' => {
+                let hex = [chars.next(), chars.next()];
+                let decoded = match hex {
+                    [Some(hi), Some(lo)] => std::str::from_utf8(&[hi, lo])
+                        .ok()
+                        .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+                    _ => None,
+                };
+                bytes.push(decoded.ok_or_else(|| IdentityError::InvalidDid(uri.to_string()))?);
+            }
+            b if b.is_ascii_alphanumeric() || matches!(b, b'.' | b'-' | b'_') => bytes.push(b),
+            _ => return Err(IdentityError::InvalidDid(uri.to_string())),
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| IdentityError::InvalidDid(uri.to_string()))
+}
+
+/// Accept either a `did:dexos` identifier or a bare trader ID and return the identifier
+pub fn normalize_did(did_or_trader: &str) -> String {
+    if did_or_trader.starts_with(DID_PREFIX) {
+        did_or_trader.to_string()
+    } else {
+        did_uri(did_or_trader)
+    }
+}
+
+/// Render a stored DID as its JSON-LD DID document
+pub fn to_json_ld(did: &DID) -> DidDocumentJsonLd {
+    let subject = did_uri(&did.id);
+    let method_url = |key_id: &str| {
+        let fragment = key_id
+            .rsplit_once('#')
+            .map_or(key_id, |(_, fragment)| fragment);
+        format!("{}#{}", subject, fragment)
+    };
+
+    let mut context = vec![DID_CONTEXT.to_string()];
+    let mut verification_method = Vec::new();
+    let mut assertion_method = Vec::new();
+    for key in &did.document.public_keys {
+        let id = method_url(&key.id);
+        let method = match key.key_type.as_str() {
+            ED25519_KEY_TYPE => {
+                let mut multicodec = ED25519_MULTICODEC.to_vec();
+                multicodec.extend_from_slice(&key.public_key);
+                assertion_method.push(id.clone());
+                VerificationMethod {
+                    id,
+                    method_type: ED25519_VERIFICATION_KEY_2020.to_string(),
+                    controller: subject.clone(),
+                    public_key_multibase: Some(encode_multibase(&multicodec)),
+                    public_key_jwk: None,
+                }
+            }
+            DILITHIUM_KEY_TYPE => VerificationMethod {
+                id,
+                method_type: JSON_WEB_KEY_2020.to_string(),
+                controller: subject.clone(),
+                public_key_multibase: None,
+                public_key_jwk: Some(serde_json::json!({
+                    "kty": "AKP",
+                    "alg": "ML-DSA-65",
+                    "pub": URL_SAFE_NO_PAD.encode(&key.public_key),
+                })),
+            },
+            // Key types without a registered representation are not published
+            _ => continue,
+        };
+        let suite_context = match method.method_type.as_str() {
+            ED25519_VERIFICATION_KEY_2020 => ED25519_2020_CONTEXT,
+            _ => JWS_2020_CONTEXT,
+        };
+        if !context.iter().any(|c| c == suite_context) {
+            context.push(suite_context.to_string());
+        }
+        verification_method.push(method);
+    }
+
+    let authentication = did
+        .document
+        .authentication
+        .iter()
+        .map(|key_id| method_url(key_id))
+        .filter(|id| verification_method.iter().any(|method| &method.id == id))
+        .collect();
+    let service = did
+        .document
+        .services
+        .iter()
+        .map(|service| Service {
+            id: method_url(&service.id),
+            service_type: service.service_type.clone(),
+            service_endpoint: service.endpoint.clone(),
+        })
+        .collect();
+
+    DidDocumentJsonLd {
+        context,
+        id: subject,
+        verification_method,
+        authentication,
+        assertion_method,
+        service,
+    }
+}
+
+/// `z`-prefixed base58btc multibase string of an ed25519 signature or key
+pub(crate) fn encode_multibase(bytes: &[u8]) -> String {
+    format!("z{}", bs58::encode(bytes).into_string())
+}
+
+/// Bytes of a `z`-prefixed base58btc multibase string
+pub(crate) fn decode_multibase(encoded: &str) -> Option<Vec<u8>> {
+    bs58::decode(encoded.strip_prefix('z')?).into_vec().ok()
+}
+
+fn decode_ed25519_multibase(encoded: &str) -> Option<[u8; 32]> {
+    let bytes = decode_multibase(encoded)?;
+    let key = bytes.strip_prefix(&ED25519_MULTICODEC[..])?;
+    key.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identity::IdentityManager;
+
+    #[test]
+    fn test_did_uri_round_trip() {
+        for trader in ["alice", "trader_1.eu-west", "0xAbC", "name with space/é"] {
+            let uri = did_uri(trader);
+            assert!(uri.starts_with(DID_PREFIX));
+            assert_eq!(parse_did_uri(&uri).unwrap(), trader);
+        }
+        assert_eq!(did_uri("a b"), "did:dexos:a%20b");
+
+        for bad in [
+            "did:dexos:",
+            "did:web:alice",
+            "did:dexos:a%2",
+            "did:dexos:a/b",
+        ] {
+            assert!(matches!(
+                parse_did_uri(bad),
+                Err(IdentityError::InvalidDid(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_json_ld_document() {
+        let mut manager = IdentityManager::new();
+        let did = manager.create_did(&"alice".to_string()).unwrap();
+        let document = to_json_ld(&did);
+
+        assert_eq!(document.id, "did:dexos:alice");
+        assert_eq!(document.context[0], DID_CONTEXT);
+        assert_eq!(
+            document.authentication.len(),
+            did.document.public_keys.len()
+        );
+
+        // The single assertion method is the ed25519 key, decodable from multibase
+        assert_eq!(document.assertion_method.len(), 1);
+        let method_id = &document.assertion_method[0];
+        let key = document.ed25519_assertion_key(method_id).unwrap();
+        let expected = did
+            .document
+            .public_keys
+            .iter()
+            .find(|key| key.key_type == ED25519_KEY_TYPE)
+            .unwrap();
+        assert_eq!(key.to_vec(), expected.public_key);
+
+        // JSON-LD keywords and property names follow DID Core
+        let json = serde_json::to_value(&document).unwrap();
+        assert!(json.get("@context").is_some());
+        assert!(json.get("verificationMethod").is_some());
+        assert_eq!(
+            json["verificationMethod"][0]["controller"],
+            "did:dexos:alice"
+        );
+    }
+}
@@ -0,0 +1,1556 @@
+//! Global Identity implementation for the DEX-OS core engine
+//!
+//! This module implements the Priority 3 features from DEX-OS-V2.csv:
+//! - Global Identity (DID + Biometrics, Self-Sovereign, Quantum-Secure)
+//!
+//! It provides functionality for decentralized identity management with
+//! biometric verification, self-sovereign principles, and quantum-secure cryptography.
+//!
+//! Quantum-secure signatures use ML-DSA-65 (FIPS 204, formerly Dilithium) and
+//! are available with the `pq-signatures` feature, which is on by default.
+//! During the transition DIDs carry both an ML-DSA and an ed25519 key and are
+//! signed with a hybrid signature that only verifies if both halves do.
+//!
+//! DIDs are addressed with the `did:dexos` method (see [`did`]) and resolve to
+//! JSON-LD DID documents. Verifiable credentials (see [`credentials`]) are
+//! signed by an ed25519 assertion method of the issuer's document and can be
+//! revoked through the issuer's status list. Selective-disclosure credentials
+//! (see [`selective_disclosure`]) let a holder reveal single claims and prove
+//! ranges of committed ones instead of showing the whole credential.
+
+pub mod credentials;
+pub mod did;
+pub mod selective_disclosure;
+
+pub use credentials::{StatusList, VerifiableCredential, KYC_CREDENTIAL_TYPE};
+pub use did::{did_uri, DidDocumentJsonLd};
+pub use selective_disclosure::{
+    DisclosedClaims, Presentation, PresentationRequest, RangeRequirement, SelectiveCredential,
+};
+
+use crate::crypto::zk_proof::ZkProofSystem;
+use crate::types::{TokenId, TraderId};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+#[cfg(feature = "pq-signatures")]
+use ml_dsa::{EncodedSignature, EncodedVerifyingKey, KeyGen, MlDsa65, B32};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use std::collections::{BTreeMap, HashMap};
+use thiserror::Error;
+use zeroize::Zeroizing;
+
+/// Length of an ML-DSA-65 private key, stored as its key generation seed
+pub const DILITHIUM_SEED_LENGTH: usize = 32;
+
+/// Length of an encoded ML-DSA-65 public key
+pub const DILITHIUM_PUBLIC_KEY_LENGTH: usize = 1952;
+
+/// Length of an encoded ML-DSA-65 signature
+pub const DILITHIUM_SIGNATURE_LENGTH: usize = 3309;
+
+/// Key type of ML-DSA-65 keys in DID documents
+pub const DILITHIUM_KEY_TYPE: &str = "Dilithium";
+
+/// Key type of ed25519 keys in DID documents
+pub const ED25519_KEY_TYPE: &str = "Ed25519";
+
+/// Domain separator for both halves of a hybrid signature, so neither half
+/// can be stripped off and passed as a standalone signature
+#[cfg(feature = "pq-signatures")]
+const HYBRID_CONTEXT: &[u8] = b"DEX-OS hybrid ed25519+ML-DSA-65";
+
+/// Represents a Decentralized Identifier (DID)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DID {
+    /// The DID identifier
+    pub id: String,
+    /// The DID document containing public keys and service endpoints
+    pub document: DIDDocument,
+    /// Timestamp of creation
+    pub created: u64,
+    /// Timestamp of last update
+    pub updated: u64,
+    /// Digital signature proving ownership
+    pub signature: Vec<u8>,
+}
+
+/// DID Document structure
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DIDDocument {
+    /// Public keys associated with this DID
+    pub public_keys: Vec<PublicKey>,
+    /// Service endpoints
+    pub services: Vec<ServiceEndpoint>,
+    /// Authentication methods
+    pub authentication: Vec<String>,
+}
+
+/// Public key information
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PublicKey {
+    /// Key identifier
+    pub id: String,
+    /// Key type (e.g., Ed25519, RSA, Dilithium for quantum security)
+    pub key_type: String,
+    /// The public key value
+    pub public_key: Vec<u8>,
+    /// Key usage (e.g., authentication, encryption)
+    pub usage: String,
+}
+
+/// Service endpoint information
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceEndpoint {
+    /// Service identifier
+    pub id: String,
+    /// Service type
+    pub service_type: String,
+    /// Service endpoint URL
+    pub endpoint: String,
+}
+
+/// Biometric data structure for identity verification
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BiometricData {
+    /// Biometric type (e.g., fingerprint, face, iris)
+    pub bio_type: String,
+    /// Hash of the biometric template (never stores raw biometric data)
+    pub template_hash: Vec<u8>,
+    /// Timestamp of enrollment
+    pub enrolled_at: u64,
+    /// Confidence level of the biometric match
+    pub confidence: f32,
+}
+
+/// Self-sovereign identity with user-controlled data
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SelfSovereignIdentity {
+    /// The DID associated with this identity
+    pub did: DID,
+    /// Encrypted personal data controlled by the user
+    pub personal_data: Vec<u8>,
+    /// Biometric verification data
+    pub biometrics: Vec<BiometricData>,
+    /// Claims from trusted issuers
+    pub verifiable_credentials: Vec<VerifiableCredential>,
+    /// Revocation status
+    pub is_revoked: bool,
+}
+
+/// Quantum-secure cryptographic operations
+pub struct QuantumSecureCrypto;
+
+#[cfg(feature = "pq-signatures")]
+impl QuantumSecureCrypto {
+    /// Generate an ML-DSA-65 (Dilithium) key pair. The private key is the
+    /// 32-byte key generation seed; the public key is the encoded verifying key.
+    pub fn generate_dilithium_keypair() -> (Vec<u8>, Vec<u8>) {
+        let mut seed = Zeroizing::new([0u8; DILITHIUM_SEED_LENGTH]);
+        OsRng.fill_bytes(seed.as_mut());
+        let public_key =
+            Self::dilithium_public_key(seed.as_ref()).expect("seed has the right length");
+        (seed.to_vec(), public_key)
+    }
+
+    /// Public key of the ML-DSA-65 private key `seed`
+    pub fn dilithium_public_key(seed: &[u8]) -> Result<Vec<u8>, IdentityError> {
+        Ok(dilithium_key_pair(seed)?.verifying_key().encode().to_vec())
+    }
+
+    /// Sign data with ML-DSA-65 using deterministic signing
+    pub fn quantum_sign(private_key: &[u8], data: &[u8]) -> Result<Vec<u8>, IdentityError> {
+        dilithium_sign(private_key, data, &[])
+    }
+
+    /// Verify an ML-DSA-65 signature
+    pub fn quantum_verify(public_key: &[u8], data: &[u8], signature: &[u8]) -> bool {
+        dilithium_verify(public_key, data, &[], signature)
+    }
+
+    /// Generate a hybrid key pair. The private key is the ed25519 secret
+    /// followed by the ML-DSA-65 seed; the public key is the ed25519 public
+    /// key followed by the ML-DSA-65 public key.
+    pub fn generate_hybrid_keypair() -> (Vec<u8>, Vec<u8>) {
+        let mut private_key = Zeroizing::new([0u8; 32 + DILITHIUM_SEED_LENGTH]);
+        OsRng.fill_bytes(private_key.as_mut());
+        let public_key =
+            Self::hybrid_public_key(private_key.as_ref()).expect("key has the right length");
+        (private_key.to_vec(), public_key)
+    }
+
+    /// Public key of the hybrid private key `private_key`
+    pub fn hybrid_public_key(private_key: &[u8]) -> Result<Vec<u8>, IdentityError> {
+        let (ed25519_secret, dilithium_seed) = split_hybrid_private_key(private_key)?;
+        let mut public_key = ed25519_secret.verifying_key().to_bytes().to_vec();
+        public_key.extend(Self::dilithium_public_key(dilithium_seed)?);
+        Ok(public_key)
+    }
+
+    /// Sign data with both ed25519 and ML-DSA-65. The signature is the ed25519
+    /// signature followed by the ML-DSA-65 signature.
+    pub fn hybrid_sign(private_key: &[u8], data: &[u8]) -> Result<Vec<u8>, IdentityError> {
+        let (ed25519_secret, dilithium_seed) = split_hybrid_private_key(private_key)?;
+        let mut message = HYBRID_CONTEXT.to_vec();
+        message.extend_from_slice(data);
+        let mut signature = ed25519_secret.sign(&message).to_bytes().to_vec();
+        signature.extend(dilithium_sign(dilithium_seed, data, HYBRID_CONTEXT)?);
+        Ok(signature)
+    }
+
+    /// Verify a hybrid signature. Both halves must verify.
+    pub fn hybrid_verify(public_key: &[u8], data: &[u8], signature: &[u8]) -> bool {
+        if public_key.len() != 32 + DILITHIUM_PUBLIC_KEY_LENGTH
+            || signature.len() != 64 + DILITHIUM_SIGNATURE_LENGTH
+        {
+            return false;
+        }
+        let (ed25519_public, dilithium_public) = public_key.split_at(32);
+        let (ed25519_signature, dilithium_signature) = signature.split_at(64);
+        let mut message = HYBRID_CONTEXT.to_vec();
+        message.extend_from_slice(data);
+        ed25519_verify(ed25519_public, &message, ed25519_signature)
+            && dilithium_verify(dilithium_public, data, HYBRID_CONTEXT, dilithium_signature)
+    }
+}
+
+impl QuantumSecureCrypto {
+    /// Hash biometric data for secure storage
+    pub fn hash_biometric(data: &[u8]) -> Vec<u8> {
+        let mut hasher = Sha3_256::new();
+        hasher.update(data);
+        hasher.finalize().to_vec()
+    }
+}
+
+#[cfg(feature = "pq-signatures")]
+fn dilithium_key_pair(seed: &[u8]) -> Result<ml_dsa::KeyPair<MlDsa65>, IdentityError> {
+    let seed = B32::try_from(seed).map_err(|_| IdentityError::InvalidKey)?;
+    Ok(MlDsa65::key_gen_internal(&seed))
+}
+
+#[cfg(feature = "pq-signatures")]
+fn dilithium_sign(seed: &[u8], data: &[u8], context: &[u8]) -> Result<Vec<u8>, IdentityError> {
+    let signature = dilithium_key_pair(seed)?
+        .signing_key()
+        .sign_deterministic(data, context)
+        .map_err(|_| IdentityError::InvalidKey)?;
+    Ok(signature.encode().to_vec())
+}
+
+#[cfg(feature = "pq-signatures")]
+fn dilithium_verify(public_key: &[u8], data: &[u8], context: &[u8], signature: &[u8]) -> bool {
+    let Ok(public_key) = EncodedVerifyingKey::<MlDsa65>::try_from(public_key) else {
+        return false;
+    };
+    let Some(signature) = EncodedSignature::<MlDsa65>::try_from(signature)
+        .ok()
+        .and_then(|encoded| ml_dsa::Signature::<MlDsa65>::decode(&encoded))
+    else {
+        return false;
+    };
+    ml_dsa::VerifyingKey::<MlDsa65>::decode(&public_key)
+        .verify_with_context(data, context, &signature)
+}
+
+#[cfg(feature = "pq-signatures")]
+fn split_hybrid_private_key(private_key: &[u8]) -> Result<(SigningKey, &[u8]), IdentityError> {
+    if private_key.len() != 32 + DILITHIUM_SEED_LENGTH {
+        return Err(IdentityError::InvalidKey);
+    }
+    let (ed25519_secret, dilithium_seed) = private_key.split_at(32);
+    let ed25519_secret: [u8; 32] = ed25519_secret
+        .try_into()
+        .map_err(|_| IdentityError::InvalidKey)?;
+    Ok((SigningKey::from_bytes(&ed25519_secret), dilithium_seed))
+}
+
+/// Bytes a DID's signature covers: its ID and document
+fn did_signing_payload(did: &DID) -> Vec<u8> {
+    serde_json::to_vec(&(&did.id, &did.document)).expect("DID documents serialize")
+}
+
+/// What the current keys of a DID sign to replace its document by `update`
+fn did_update_payload(current: &DID, update: &DID) -> Vec<u8> {
+    serde_json::to_vec(&(
+        "did-update",
+        &current.id,
+        &current.document,
+        &update.document,
+        update.updated,
+    ))
+    .expect("DID documents serialize")
+}
+
+/// Fresh private key and the matching document keys for a new DID
+#[cfg(feature = "pq-signatures")]
+fn generate_did_keys(trader_id: &TraderId) -> (Zeroizing<Vec<u8>>, Vec<PublicKey>) {
+    let (private_key, public_key) = QuantumSecureCrypto::generate_hybrid_keypair();
+    let (ed25519_public, dilithium_public) = public_key.split_at(32);
+    let keys = vec![
+        PublicKey {
+            id: format!("{}#key-1", trader_id),
+            key_type: DILITHIUM_KEY_TYPE.to_string(),
+            public_key: dilithium_public.to_vec(),
+            usage: "authentication".to_string(),
+        },
+        PublicKey {
+            id: format!("{}#key-2", trader_id),
+            key_type: ED25519_KEY_TYPE.to_string(),
+            public_key: ed25519_public.to_vec(),
+            usage: "authentication".to_string(),
+        },
+    ];
+    (Zeroizing::new(private_key), keys)
+}
+
+/// Fresh private key and the matching document keys for a new DID
+#[cfg(not(feature = "pq-signatures"))]
+fn generate_did_keys(trader_id: &TraderId) -> (Zeroizing<Vec<u8>>, Vec<PublicKey>) {
+    let mut secret = Zeroizing::new([0u8; 32]);
+    OsRng.fill_bytes(secret.as_mut());
+    let key = SigningKey::from_bytes(&secret);
+    let keys = vec![PublicKey {
+        id: format!("{}#key-1", trader_id),
+        key_type: ED25519_KEY_TYPE.to_string(),
+        public_key: key.verifying_key().to_bytes().to_vec(),
+        usage: "authentication".to_string(),
+    }];
+    (Zeroizing::new(key.to_bytes().to_vec()), keys)
+}
+
+#[cfg(feature = "pq-signatures")]
+fn sign_did(private_key: &[u8], payload: &[u8]) -> Result<Vec<u8>, IdentityError> {
+    QuantumSecureCrypto::hybrid_sign(private_key, payload)
+}
+
+#[cfg(not(feature = "pq-signatures"))]
+fn sign_did(private_key: &[u8], payload: &[u8]) -> Result<Vec<u8>, IdentityError> {
+    let secret: [u8; 32] = private_key
+        .try_into()
+        .map_err(|_| IdentityError::InvalidKey)?;
+    Ok(SigningKey::from_bytes(&secret)
+        .sign(payload)
+        .to_bytes()
+        .to_vec())
+}
+
+fn document_key<'a>(keys: &'a [PublicKey], key_type: &str) -> Option<&'a [u8]> {
+    keys.iter()
+        .find(|key| key.key_type == key_type)
+        .map(|key| key.public_key.as_slice())
+}
+
+#[cfg(feature = "pq-signatures")]
+fn verify_did(keys: &[PublicKey], payload: &[u8], signature: &[u8]) -> bool {
+    let (Some(ed25519_public), Some(dilithium_public)) = (
+        document_key(keys, ED25519_KEY_TYPE),
+        document_key(keys, DILITHIUM_KEY_TYPE),
+    ) else {
+        return false;
+    };
+    let public_key = [ed25519_public, dilithium_public].concat();
+    QuantumSecureCrypto::hybrid_verify(&public_key, payload, signature)
+}
+
+#[cfg(not(feature = "pq-signatures"))]
+fn verify_did(keys: &[PublicKey], payload: &[u8], signature: &[u8]) -> bool {
+    document_key(keys, ED25519_KEY_TYPE)
+        .is_some_and(|public_key| ed25519_verify(public_key, payload, signature))
+}
+
+fn ed25519_verify(public_key: &[u8], data: &[u8], signature: &[u8]) -> bool {
+    let (Ok(public_key), Ok(signature)) = (
+        <[u8; 32]>::try_from(public_key),
+        <[u8; 64]>::try_from(signature),
+    ) else {
+        return false;
+    };
+    VerifyingKey::from_bytes(&public_key)
+        .is_ok_and(|key| key.verify(data, &Signature::from_bytes(&signature)).is_ok())
+}
+
+/// The ed25519 signing key of a DID private key: the whole key without
+/// `pq-signatures`, the first half of the hybrid key with it
+fn ed25519_signing_key(private_key: &[u8]) -> Result<SigningKey, IdentityError> {
+    let secret: [u8; 32] = private_key
+        .get(..32)
+        .and_then(|secret| secret.try_into().ok())
+        .ok_or(IdentityError::InvalidKey)?;
+    Ok(SigningKey::from_bytes(&secret))
+}
+
+/// ID of an issuer's revocation status list: a DID URL path under the issuer
+fn status_list_id(issuer: &str) -> String {
+    format!("{}/status-list", issuer)
+}
+
+/// Status list ID and index of a credential
+fn status_entry(credential: &VerifiableCredential) -> Result<(String, usize), IdentityError> {
+    let status = credential
+        .credential_status
+        .as_ref()
+        .ok_or_else(|| IdentityError::InvalidCredential("no credential status".to_string()))?;
+    if status.status_purpose != credentials::REVOCATION_PURPOSE {
+        return Err(IdentityError::InvalidCredential(
+            "unsupported status purpose".to_string(),
+        ));
+    }
+    let index = status
+        .status_list_index
+        .parse()
+        .map_err(|_| IdentityError::InvalidCredential("invalid status list index".to_string()))?;
+    Ok((status.status_list_credential.clone(), index))
+}
+
+/// Global Identity Manager
+#[derive(Debug, Clone)]
+pub struct IdentityManager {
+    /// Storage for DIDs
+    dids: HashMap<String, DID>,
+    /// Storage for self-sovereign identities
+    identities: HashMap<String, SelfSovereignIdentity>,
+    /// Biometric verification cache
+    biometric_cache: HashMap<String, Vec<u8>>,
+    /// Trusted issuers for verifiable credentials, as `did:dexos` identifiers
+    trusted_issuers: Vec<String>,
+    /// Revocation status lists by list ID
+    status_lists: HashMap<String, StatusList>,
+}
+
+impl IdentityManager {
+    /// Create a new identity manager
+    pub fn new() -> Self {
+        Self {
+            dids: HashMap::new(),
+            identities: HashMap::new(),
+            biometric_cache: HashMap::new(),
+            trusted_issuers: Vec::new(),
+            status_lists: HashMap::new(),
+        }
+    }
+
+    /// Create a new DID with quantum-secure keys
+    pub fn create_did(&mut self, trader_id: &TraderId) -> Result<DID, IdentityError> {
+        self.create_did_with_keys(trader_id).map(|(did, _)| did)
+    }
+
+    /// Create a new DID and return it together with its private key. With
+    /// the `pq-signatures` feature the document lists an ML-DSA-65 key and an
+    /// ed25519 key and the private key is a hybrid key (see
+    /// [`QuantumSecureCrypto::generate_hybrid_keypair`]); without it the
+    /// document lists only the ed25519 key. The DID is signed by its own keys.
+    /// An ID that is already known can only change through
+    /// [`Self::update_did`].
+    pub fn create_did_with_keys(
+        &mut self,
+        trader_id: &TraderId,
+    ) -> Result<(DID, Vec<u8>), IdentityError> {
+        if self.dids.contains_key(trader_id) {
+            return Err(IdentityError::DIDAlreadyExists);
+        }
+        let (private_key, public_keys) = generate_did_keys(trader_id);
+
+        let document = DIDDocument {
+            authentication: public_keys.iter().map(|key| key.id.clone()).collect(),
+            public_keys,
+            services: vec![],
+        };
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let mut did = DID {
+            id: trader_id.clone(),
+            document,
+            created: now,
+            updated: now,
+            signature: vec![],
+        };
+        did.signature = sign_did(&private_key, &did_signing_payload(&did))?;
+
+        self.dids.insert(trader_id.clone(), did.clone());
+        Ok((did, private_key.to_vec()))
+    }
+
+    /// Check that a DID is signed by the keys its document lists
+    pub fn verify_did_signature(did: &DID) -> bool {
+        verify_did(
+            &did.document.public_keys,
+            &did_signing_payload(did),
+            &did.signature,
+        )
+    }
+
+    /// Store a DID created elsewhere, after checking it is signed by its own
+    /// keys. An ID that is already known can only change through
+    /// [`Self::update_did`].
+    pub fn register_did(&mut self, did: DID) -> Result<(), IdentityError> {
+        if !Self::verify_did_signature(&did) {
+            return Err(IdentityError::InvalidSignature);
+        }
+        if self.dids.contains_key(&did.id) {
+            return Err(IdentityError::DIDAlreadyExists);
+        }
+        self.dids.insert(did.id.clone(), did);
+        Ok(())
+    }
+
+    /// Sign the replacement of the stored document of `update.id` by
+    /// `update`, with the private key of the stored document
+    pub fn sign_did_update(
+        &self,
+        update: &DID,
+        current_private_key: &[u8],
+    ) -> Result<Vec<u8>, IdentityError> {
+        let current = self
+            .dids
+            .get(&update.id)
+            .ok_or(IdentityError::DIDNotFound)?;
+        sign_did(current_private_key, &did_update_payload(current, update))
+    }
+
+    /// Replace a stored DID, for instance to rotate its keys. `update` must
+    /// be signed by its own keys, be more recent than the stored document,
+    /// and `authorization` must come from [`Self::sign_did_update`] with the
+    /// stored document's private key.
+    pub fn update_did(&mut self, update: DID, authorization: &[u8]) -> Result<(), IdentityError> {
+        let current = self
+            .dids
+            .get(&update.id)
+            .ok_or(IdentityError::DIDNotFound)?;
+        if !Self::verify_did_signature(&update)
+            || !verify_did(
+                &current.document.public_keys,
+                &did_update_payload(current, &update),
+                authorization,
+            )
+        {
+            return Err(IdentityError::InvalidSignature);
+        }
+        if update.updated <= current.updated {
+            return Err(IdentityError::InvalidDid("stale DID update".to_string()));
+        }
+        self.dids.insert(update.id.clone(), update);
+        Ok(())
+    }
+
+    /// Resolve a `did:dexos` identifier to its JSON-LD DID document
+    pub fn resolve(&self, did: &str) -> Result<DidDocumentJsonLd, IdentityError> {
+        let trader_id = did::parse_did_uri(did)?;
+        let stored = self
+            .dids
+            .get(&trader_id)
+            .ok_or(IdentityError::DIDNotFound)?;
+        if !Self::verify_did_signature(stored) {
+            return Err(IdentityError::InvalidSignature);
+        }
+        Ok(did::to_json_ld(stored))
+    }
+
+    /// Sign a credential as its issuer. `issuer_private_key` is the key
+    /// returned by [`Self::create_did_with_keys`]; its ed25519 half must match
+    /// an assertion method of the issuer's document. The credential gets an
+    /// entry in the issuer's revocation status list.
+    pub fn issue_credential(
+        &mut self,
+        mut credential: VerifiableCredential,
+        issuer_private_key: &[u8],
+    ) -> Result<VerifiableCredential, IdentityError> {
+        // Check the key before handing out a status list index
+        let (signing_key, method) =
+            self.assertion_signer(&credential.issuer, issuer_private_key)?;
+
+        let list_id = status_list_id(&credential.issuer);
+        let status = self
+            .status_lists
+            .entry(list_id.clone())
+            .or_insert_with(|| StatusList::new(list_id))
+            .allocate();
+        credential.credential_status = Some(status);
+        credentials::attach_proof(&mut credential, &signing_key, &method);
+        Ok(credential)
+    }
+
+    /// Sign the issuer's current status list as a `BitstringStatusListCredential`
+    /// for verifiers elsewhere to import with [`Self::import_status_list`]
+    pub fn status_list_credential(
+        &self,
+        issuer: &str,
+        issuer_private_key: &[u8],
+    ) -> Result<VerifiableCredential, IdentityError> {
+        let issuer = did::normalize_did(issuer);
+        let list_id = status_list_id(&issuer);
+        let mut credential = match self.status_lists.get(&list_id) {
+            Some(list) => list.to_credential(&issuer),
+            None => StatusList::new(list_id).to_credential(&issuer),
+        };
+        let (signing_key, method) = self.assertion_signer(&issuer, issuer_private_key)?;
+        credentials::attach_proof(&mut credential, &signing_key, &method);
+        Ok(credential)
+    }
+
+    /// Import an issuer's signed status list, merging its revocations into ours
+    pub fn import_status_list(
+        &mut self,
+        credential: &VerifiableCredential,
+    ) -> Result<(), IdentityError> {
+        if !credential.has_type(credentials::STATUS_LIST_CREDENTIAL_TYPE)
+            || credential.id != status_list_id(&credential.issuer)
+        {
+            return Err(IdentityError::InvalidCredential(
+                "not the issuer's status list".to_string(),
+            ));
+        }
+        self.verify_credential(credential)?;
+        let list = credential
+            .claim("encodedList")
+            .and_then(|encoded| encoded.as_str())
+            .and_then(|encoded| StatusList::decode(credential.id.clone(), encoded))
+            .ok_or_else(|| IdentityError::InvalidCredential("invalid encodedList".to_string()))?;
+
+        match self.status_lists.get_mut(&credential.id) {
+            Some(existing) => existing.merge(&list),
+            None => {
+                self.status_lists.insert(credential.id.clone(), list);
+            }
+        }
+        Ok(())
+    }
+
+    /// Issue a selective-disclosure credential. `disclosable` claims are
+    /// replaced by salted digests and `committed` claims by Pedersen
+    /// commitments before the credential is signed as in
+    /// [`Self::issue_credential`]; the claims already in `credential` stay
+    /// visible in every presentation.
+    pub fn issue_selective_credential(
+        &mut self,
+        credential: VerifiableCredential,
+        disclosable: BTreeMap<String, serde_json::Value>,
+        committed: BTreeMap<String, u64>,
+        issuer_private_key: &[u8],
+    ) -> Result<SelectiveCredential, IdentityError> {
+        let mut holding = selective_disclosure::conceal(
+            credential,
+            disclosable,
+            committed,
+            &ZkProofSystem::new(),
+        )?;
+        holding.credential = self.issue_credential(holding.credential, issuer_private_key)?;
+        Ok(holding)
+    }
+
+    /// Answer a presentation request with the claims and range proofs it asks
+    /// for, signed with the subject's key. `holder_private_key` is the key
+    /// returned by [`Self::create_did_with_keys`] for the credential subject.
+    pub fn present_credential(
+        &self,
+        holding: &SelectiveCredential,
+        request: &PresentationRequest,
+        holder_private_key: &[u8],
+    ) -> Result<Presentation, IdentityError> {
+        let (signing_key, method) =
+            self.assertion_signer(holding.credential.subject(), holder_private_key)?;
+        selective_disclosure::present(
+            holding,
+            request,
+            &signing_key,
+            &method,
+            &ZkProofSystem::new(),
+        )
+    }
+
+    /// Verify a presentation against the request it answers: the credential
+    /// must come from a trusted issuer and verify, the holder binding must be
+    /// signed by the subject, and every requested claim and range must be
+    /// backed by the issuer's digests and commitments
+    pub fn verify_presentation(
+        &self,
+        presentation: &Presentation,
+        request: &PresentationRequest,
+    ) -> Result<DisclosedClaims, IdentityError> {
+        let credential = &presentation.credential;
+        if !credential.has_type(&request.credential_type) {
+            return Err(IdentityError::InvalidPresentation(format!(
+                "not a {}",
+                request.credential_type
+            )));
+        }
+        if !self.is_trusted_issuer(&credential.issuer) {
+            return Err(IdentityError::UntrustedIssuer);
+        }
+        self.verify_credential(credential)?;
+
+        let binding = &presentation.holder_binding;
+        let owned_by_subject = binding
+            .verification_method
+            .strip_prefix(credential.subject())
+            .is_some_and(|rest| rest.starts_with('#'));
+        if !owned_by_subject {
+            return Err(IdentityError::InvalidSignature);
+        }
+        let document = self.resolve(credential.subject())?;
+        let public_key = document
+            .ed25519_assertion_key(&binding.verification_method)
+            .ok_or(IdentityError::InvalidSignature)?;
+        if !selective_disclosure::verify_binding(presentation, request, &public_key) {
+            return Err(IdentityError::InvalidSignature);
+        }
+
+        selective_disclosure::open(presentation, request, &ZkProofSystem::new())
+    }
+
+    /// The ed25519 half of `private_key` and the assertion method of the
+    /// `did`'s document it matches
+    fn assertion_signer(
+        &self,
+        did: &str,
+        private_key: &[u8],
+    ) -> Result<(SigningKey, String), IdentityError> {
+        let document = self.resolve(did)?;
+        let signing_key = ed25519_signing_key(private_key)?;
+        let public_key = signing_key.verifying_key().to_bytes();
+        let method = document
+            .assertion_method
+            .iter()
+            .find(|method| document.ed25519_assertion_key(method) == Some(public_key))
+            .ok_or(IdentityError::InvalidKey)?
+            .clone();
+        Ok((signing_key, method))
+    }
+
+    /// Check a credential's proof against the issuer's resolved DID document,
+    /// its validity period, and its revocation status
+    pub fn verify_credential(
+        &self,
+        credential: &VerifiableCredential,
+    ) -> Result<(), IdentityError> {
+        if credential.context.first().map(String::as_str)
+            != Some(credentials::CREDENTIALS_V2_CONTEXT)
+            || !credential.has_type(credentials::VERIFIABLE_CREDENTIAL_TYPE)
+        {
+            return Err(IdentityError::InvalidCredential(
+                "not a VC Data Model 2.0 credential".to_string(),
+            ));
+        }
+
+        let proof = credential
+            .proof
+            .as_ref()
+            .ok_or(IdentityError::InvalidSignature)?;
+        let owned_by_issuer = proof
+            .verification_method
+            .strip_prefix(credential.issuer.as_str())
+            .is_some_and(|rest| rest.starts_with('#'));
+        if !owned_by_issuer {
+            return Err(IdentityError::InvalidSignature);
+        }
+        let document = self.resolve(&credential.issuer)?;
+        let public_key = document
+            .ed25519_assertion_key(&proof.verification_method)
+            .ok_or(IdentityError::InvalidSignature)?;
+        if !credentials::verify_proof(credential, &public_key) {
+            return Err(IdentityError::InvalidSignature);
+        }
+
+        let now = credentials::current_timestamp();
+        let valid_from = credentials::parse_timestamp(&credential.valid_from)
+            .ok_or_else(|| IdentityError::InvalidCredential("invalid validFrom".to_string()))?;
+        if now < valid_from {
+            return Err(IdentityError::CredentialNotYetValid);
+        }
+        if let Some(valid_until) = &credential.valid_until {
+            let valid_until = credentials::parse_timestamp(valid_until).ok_or_else(|| {
+                IdentityError::InvalidCredential("invalid validUntil".to_string())
+            })?;
+            if now > valid_until {
+                return Err(IdentityError::CredentialExpired);
+            }
+        }
+
+        if self.is_credential_revoked(credential)? {
+            return Err(IdentityError::CredentialRevoked);
+        }
+        Ok(())
+    }
+
+    /// Revoke a credential by setting its bit in the issuer's status list
+    pub fn revoke_credential(
+        &mut self,
+        credential: &VerifiableCredential,
+    ) -> Result<(), IdentityError> {
+        let (list_id, index) = status_entry(credential)?;
+        let revoked = self
+            .status_lists
+            .get_mut(&list_id)
+            .is_some_and(|list| list.revoke(index));
+        if !revoked {
+            return Err(IdentityError::InvalidCredential(
+                "unknown status list entry".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether a credential's status list entry is set. Credentials without a
+    /// status entry cannot be revoked.
+    pub fn is_credential_revoked(
+        &self,
+        credential: &VerifiableCredential,
+    ) -> Result<bool, IdentityError> {
+        if credential.credential_status.is_none() {
+            return Ok(false);
+        }
+        let (list_id, index) = status_entry(credential)?;
+        let list = self
+            .status_lists
+            .get(&list_id)
+            .ok_or_else(|| IdentityError::InvalidCredential("unknown status list".to_string()))?;
+        Ok(list.is_revoked(index))
+    }
+
+    /// Status list of an issuer, if it has issued credentials
+    pub fn status_list(&self, issuer: &str) -> Option<&StatusList> {
+        self.status_lists
+            .get(&status_list_id(&did::normalize_did(issuer)))
+    }
+
+    /// Whether credentials from `issuer` are accepted
+    pub fn is_trusted_issuer(&self, issuer: &str) -> bool {
+        self.trusted_issuers.contains(&did::normalize_did(issuer))
+    }
+
+    /// Whether an identity is valid and holds a currently valid credential of
+    /// `credential_type` from a trusted issuer
+    pub fn has_valid_credential(&self, did_id: &str, credential_type: &str) -> bool {
+        if !self.is_valid_identity(did_id) {
+            return false;
+        }
+        self.identities[did_id]
+            .verifiable_credentials
+            .iter()
+            .filter(|credential| credential.has_type(credential_type))
+            .filter(|credential| self.trusted_issuers.contains(&credential.issuer))
+            .any(|credential| self.verify_credential(credential).is_ok())
+    }
+
+    /// Register biometric data for a DID
+    pub fn register_biometric(
+        &mut self,
+        did_id: &str,
+        bio_type: &str,
+        bio_data: &[u8],
+    ) -> Result<(), IdentityError> {
+        let did = self.dids.get(did_id).ok_or(IdentityError::DIDNotFound)?;
+
+        // Hash the biometric data for secure storage
+        let template_hash = QuantumSecureCrypto::hash_biometric(bio_data);
+
+        // In a real implementation, we would store this in the identity
+        // For now, we'll just cache it
+        self.biometric_cache
+            .insert(format!("{}:{}", did_id, bio_type), template_hash);
+
+        Ok(())
+    }
+
+    /// Verify biometric data against registered template
+    pub fn verify_biometric(
+        &self,
+        did_id: &str,
+        bio_type: &str,
+        bio_data: &[u8],
+    ) -> Result<bool, IdentityError> {
+        let template_hash = self
+            .biometric_cache
+            .get(&format!("{}:{}", did_id, bio_type))
+            .ok_or(IdentityError::BiometricNotRegistered)?;
+
+        let provided_hash = QuantumSecureCrypto::hash_biometric(bio_data);
+        Ok(template_hash == &provided_hash)
+    }
+
+    /// Create a self-sovereign identity
+    pub fn create_self_sovereign_identity(
+        &mut self,
+        did_id: &str,
+        personal_data: Vec<u8>,
+    ) -> Result<SelfSovereignIdentity, IdentityError> {
+        let did = self
+            .dids
+            .get(did_id)
+            .ok_or(IdentityError::DIDNotFound)?
+            .clone();
+
+        let identity = SelfSovereignIdentity {
+            did,
+            personal_data,
+            biometrics: vec![],
+            verifiable_credentials: vec![],
+            is_revoked: false,
+        };
+
+        self.identities.insert(did_id.to_string(), identity.clone());
+        Ok(identity)
+    }
+
+    /// Add a verifiable credential to an identity, after checking it is about
+    /// the identity, from a trusted issuer, and valid (see [`Self::verify_credential`])
+    pub fn add_verifiable_credential(
+        &mut self,
+        did_id: &str,
+        credential: VerifiableCredential,
+    ) -> Result<(), IdentityError> {
+        if !self.identities.contains_key(did_id) {
+            return Err(IdentityError::IdentityNotFound);
+        }
+        if credential.subject() != did_uri(did_id) {
+            return Err(IdentityError::InvalidCredential(
+                "credential subject does not match the identity".to_string(),
+            ));
+        }
+
+        // Verify the credential is from a trusted issuer
+        if !self.trusted_issuers.contains(&credential.issuer) {
+            return Err(IdentityError::UntrustedIssuer);
+        }
+        self.verify_credential(&credential)?;
+
+        let identity = self
+            .identities
+            .get_mut(did_id)
+            .ok_or(IdentityError::IdentityNotFound)?;
+        identity.verifiable_credentials.push(credential);
+        Ok(())
+    }
+
+    /// Revoke an identity
+    pub fn revoke_identity(&mut self, did_id: &str) -> Result<(), IdentityError> {
+        let identity = self
+            .identities
+            .get_mut(did_id)
+            .ok_or(IdentityError::IdentityNotFound)?;
+        identity.is_revoked = true;
+        Ok(())
+    }
+
+    /// Check if an identity is valid (exists and not revoked)
+    pub fn is_valid_identity(&self, did_id: &str) -> bool {
+        match self.identities.get(did_id) {
+            Some(identity) => !identity.is_revoked,
+            None => false,
+        }
+    }
+
+    /// Add a trusted issuer, given as a `did:dexos` identifier or trader ID
+    pub fn add_trusted_issuer(&mut self, issuer_did: String) {
+        let issuer_did = did::normalize_did(&issuer_did);
+        if !self.trusted_issuers.contains(&issuer_did) {
+            self.trusted_issuers.push(issuer_did);
+        }
+    }
+
+    /// Get a DID by ID
+    pub fn get_did(&self, did_id: &str) -> Option<&DID> {
+        self.dids.get(did_id)
+    }
+
+    /// Get a self-sovereign identity by DID
+    pub fn get_identity(&self, did_id: &str) -> Option<&SelfSovereignIdentity> {
+        self.identities.get(did_id)
+    }
+}
+
+impl Default for IdentityManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Errors that can occur during identity operations
+#[derive(Debug, Error)]
+pub enum IdentityError {
+    #[error("DID not found")]
+    DIDNotFound,
+    #[error("DID already exists")]
+    DIDAlreadyExists,
+    #[error("Identity not found")]
+    IdentityNotFound,
+    #[error("Biometric data not registered")]
+    BiometricNotRegistered,
+    #[error("Untrusted credential issuer")]
+    UntrustedIssuer,
+    #[error("Invalid biometric data")]
+    InvalidBiometric,
+    #[error("Invalid key")]
+    InvalidKey,
+    #[error("Invalid DID: {0}")]
+    InvalidDid(String),
+    #[error("Invalid signature")]
+    InvalidSignature,
+    #[error("Invalid credential: {0}")]
+    InvalidCredential(String),
+    #[error("Credential is not yet valid")]
+    CredentialNotYetValid,
+    #[error("Credential has expired")]
+    CredentialExpired,
+    #[error("Credential has been revoked")]
+    CredentialRevoked,
+    #[error("Invalid presentation: {0}")]
+    InvalidPresentation(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_identity_manager_creation() {
+        let manager = IdentityManager::new();
+        assert!(manager.dids.is_empty());
+        assert!(manager.identities.is_empty());
+    }
+
+    #[test]
+    fn test_create_did() {
+        let mut manager = IdentityManager::new();
+        let trader_id = "trader1".to_string();
+
+        let did = manager.create_did(&trader_id);
+        assert!(did.is_ok());
+
+        let did = did.unwrap();
+        assert_eq!(did.id, trader_id);
+        #[cfg(feature = "pq-signatures")]
+        {
+            assert_eq!(did.document.public_keys.len(), 2);
+            assert_eq!(did.document.public_keys[0].key_type, "Dilithium");
+            assert_eq!(
+                did.document.public_keys[0].public_key.len(),
+                DILITHIUM_PUBLIC_KEY_LENGTH
+            );
+            assert_eq!(did.document.public_keys[1].key_type, "Ed25519");
+        }
+        #[cfg(not(feature = "pq-signatures"))]
+        {
+            assert_eq!(did.document.public_keys.len(), 1);
+            assert_eq!(did.document.public_keys[0].key_type, "Ed25519");
+        }
+        assert!(IdentityManager::verify_did_signature(&did));
+
+        let mut tampered = did.clone();
+        tampered.document.public_keys.last_mut().unwrap().public_key[0] ^= 1;
+        assert!(!IdentityManager::verify_did_signature(&tampered));
+
+        // Check that DID was stored
+        assert!(manager.get_did(&trader_id).is_some());
+    }
+
+    #[test]
+    fn test_biometric_registration_and_verification() {
+        let mut manager = IdentityManager::new();
+        let trader_id = "trader1".to_string();
+
+        // Create DID first
+        assert!(manager.create_did(&trader_id).is_ok());
+
+        // Register biometric data
+        let bio_data = b"fingerprint_data";
+        assert!(manager
+            .register_biometric(&trader_id, "fingerprint", bio_data)
+            .is_ok());
+
+        // Verify correct biometric data
+        let result = manager.verify_biometric(&trader_id, "fingerprint", bio_data);
+        assert!(result.is_ok());
+        assert!(result.unwrap());
+
+        // Verify incorrect biometric data
+        let result = manager.verify_biometric(&trader_id, "fingerprint", b"different_data");
+        assert!(result.is_ok());
+        assert!(!result.unwrap());
+    }
+
+    #[test]
+    fn test_self_sovereign_identity() {
+        let mut manager = IdentityManager::new();
+        let trader_id = "trader1".to_string();
+
+        // Create DID first
+        assert!(manager.create_did(&trader_id).is_ok());
+
+        // Create self-sovereign identity
+        let personal_data = b"encrypted_personal_data".to_vec();
+        let identity = manager.create_self_sovereign_identity(&trader_id, personal_data.clone());
+        assert!(identity.is_ok());
+
+        let identity = identity.unwrap();
+        assert_eq!(identity.personal_data, personal_data);
+        assert!(!identity.is_revoked);
+
+        // Check that identity was stored
+        assert!(manager.get_identity(&trader_id).is_some());
+
+        // Check validity
+        assert!(manager.is_valid_identity(&trader_id));
+
+        // Revoke identity
+        assert!(manager.revoke_identity(&trader_id).is_ok());
+        assert!(!manager.is_valid_identity(&trader_id));
+    }
+
+    #[test]
+    fn test_verifiable_credentials() {
+        let mut manager = IdentityManager::new();
+        let trader_id = "trader1".to_string();
+        let issuer_id = "issuer1".to_string();
+
+        // Issuer DID, trusted
+        let (_, issuer_key) = manager.create_did_with_keys(&issuer_id).unwrap();
+        manager.add_trusted_issuer(issuer_id.clone());
+
+        // Create DID and identity
+        assert!(manager.create_did(&trader_id).is_ok());
+        let personal_data = b"personal_data".to_vec();
+        assert!(manager
+            .create_self_sovereign_identity(&trader_id, personal_data)
+            .is_ok());
+
+        // Create and sign verifiable credential
+        let mut claims = BTreeMap::new();
+        claims.insert("name".to_string(), json!("John Doe"));
+        claims.insert("kycLevel".to_string(), json!("verified"));
+        let credential =
+            VerifiableCredential::new("cred1", &issuer_id, &trader_id, KYC_CREDENTIAL_TYPE, claims);
+
+        // Unsigned credentials are rejected
+        assert!(matches!(
+            manager.add_verifiable_credential(&trader_id, credential.clone()),
+            Err(IdentityError::InvalidSignature)
+        ));
+
+        let credential = manager.issue_credential(credential, &issuer_key).unwrap();
+        assert_eq!(credential.issuer, "did:dexos:issuer1");
+        assert!(manager.verify_credential(&credential).is_ok());
+
+        // Add credential to identity
+        assert!(manager
+            .add_verifiable_credential(&trader_id, credential.clone())
+            .is_ok());
+        assert!(manager.has_valid_credential(&trader_id, KYC_CREDENTIAL_TYPE));
+
+        // Check that credential was added
+        let identity = manager.get_identity(&trader_id).unwrap();
+        assert_eq!(identity.verifiable_credentials.len(), 1);
+
+        // Revocation through the status list invalidates it
+        manager.revoke_credential(&credential).unwrap();
+        assert!(matches!(
+            manager.verify_credential(&credential),
+            Err(IdentityError::CredentialRevoked)
+        ));
+        assert!(!manager.has_valid_credential(&trader_id, KYC_CREDENTIAL_TYPE));
+    }
+
+    #[test]
+    fn test_forged_credentials_are_rejected() {
+        let mut manager = IdentityManager::new();
+        let (_, issuer_key) = manager
+            .create_did_with_keys(&"issuer1".to_string())
+            .unwrap();
+        let (_, other_key) = manager
+            .create_did_with_keys(&"mallory".to_string())
+            .unwrap();
+        manager.add_trusted_issuer("issuer1".to_string());
+        manager.create_did(&"trader1".to_string()).unwrap();
+        manager
+            .create_self_sovereign_identity("trader1", vec![])
+            .unwrap();
+
+        let unsigned = VerifiableCredential::new(
+            "cred1",
+            "issuer1",
+            "trader1",
+            KYC_CREDENTIAL_TYPE,
+            BTreeMap::new(),
+        );
+        let credential = manager
+            .issue_credential(unsigned.clone(), &issuer_key)
+            .unwrap();
+
+        // Someone else's key cannot sign as the issuer
+        assert!(matches!(
+            manager.issue_credential(unsigned.clone(), &other_key),
+            Err(IdentityError::InvalidKey)
+        ));
+
+        // Tampered claims break the proof
+        let mut tampered = credential.clone();
+        tampered
+            .credential_subject
+            .claims
+            .insert("kycLevel".to_string(), json!("enhanced"));
+        assert!(matches!(
+            manager.verify_credential(&tampered),
+            Err(IdentityError::InvalidSignature)
+        ));
+
+        // Re-attributing a valid credential to another subject breaks it too
+        let mut tampered = credential.clone();
+        tampered.credential_subject.id = did_uri("mallory");
+        assert!(manager.verify_credential(&tampered).is_err());
+
+        // A credential signed by mallory and claiming to be from issuer1
+        let mut forged = manager
+            .issue_credential(
+                VerifiableCredential::new(
+                    "cred2",
+                    "mallory",
+                    "trader1",
+                    KYC_CREDENTIAL_TYPE,
+                    BTreeMap::new(),
+                ),
+                &other_key,
+            )
+            .unwrap();
+        forged.issuer = did_uri("issuer1");
+        assert!(manager.verify_credential(&forged).is_err());
+
+        // Untrusted issuers are refused even with a valid proof
+        let from_mallory = manager
+            .issue_credential(
+                VerifiableCredential::new(
+                    "cred3",
+                    "mallory",
+                    "trader1",
+                    KYC_CREDENTIAL_TYPE,
+                    BTreeMap::new(),
+                ),
+                &other_key,
+            )
+            .unwrap();
+        assert!(manager.verify_credential(&from_mallory).is_ok());
+        assert!(matches!(
+            manager.add_verifiable_credential("trader1", from_mallory),
+            Err(IdentityError::UntrustedIssuer)
+        ));
+
+        // Expired credentials are rejected
+        let expired = manager
+            .issue_credential(unsigned.with_valid_until(1), &issuer_key)
+            .unwrap();
+        assert!(matches!(
+            manager.verify_credential(&expired),
+            Err(IdentityError::CredentialExpired)
+        ));
+    }
+
+    #[test]
+    fn test_status_list_import() {
+        let mut issuer = IdentityManager::new();
+        let (issuer_did, issuer_key) = issuer.create_did_with_keys(&"issuer1".to_string()).unwrap();
+        issuer.create_did(&"trader1".to_string()).unwrap();
+        let credential = issuer
+            .issue_credential(
+                VerifiableCredential::new(
+                    "cred1",
+                    "issuer1",
+                    "trader1",
+                    KYC_CREDENTIAL_TYPE,
+                    BTreeMap::new(),
+                ),
+                &issuer_key,
+            )
+            .unwrap();
+
+        // A verifier knows the issuer's DID and its current status list
+        let mut verifier = IdentityManager::new();
+        verifier.register_did(issuer_did).unwrap();
+        let published = issuer
+            .status_list_credential("issuer1", &issuer_key)
+            .unwrap();
+        verifier.import_status_list(&published).unwrap();
+        assert!(verifier.verify_credential(&credential).is_ok());
+
+        // Revocation reaches the verifier through the next published list
+        issuer.revoke_credential(&credential).unwrap();
+        let revoked = issuer
+            .status_list_credential("issuer1", &issuer_key)
+            .unwrap();
+        verifier.import_status_list(&revoked).unwrap();
+        assert!(matches!(
+            verifier.verify_credential(&credential),
+            Err(IdentityError::CredentialRevoked)
+        ));
+
+        // Replaying the older list does not un-revoke
+        verifier.import_status_list(&published).unwrap();
+        assert!(verifier.verify_credential(&credential).is_err());
+
+        // A list whose bits were edited after signing is refused
+        let mut forged = revoked;
+        forged.credential_subject.claims.insert(
+            "encodedList".to_string(),
+            json!(StatusList::new("x").encoded_list()),
+        );
+        assert!(matches!(
+            verifier.import_status_list(&forged),
+            Err(IdentityError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn test_did_resolution() {
+        let mut manager = IdentityManager::new();
+        let did = manager.create_did(&"alice".to_string()).unwrap();
+
+        let document = manager.resolve("did:dexos:alice").unwrap();
+        assert_eq!(document, did::to_json_ld(&did));
+        assert!(matches!(
+            manager.resolve("did:dexos:bob"),
+            Err(IdentityError::DIDNotFound)
+        ));
+        assert!(matches!(
+            manager.resolve("alice"),
+            Err(IdentityError::InvalidDid(_))
+        ));
+
+        // DIDs registered from elsewhere must be self-signed
+        let mut other = IdentityManager::new();
+        let mut bob = other.create_did(&"bob".to_string()).unwrap();
+        assert!(manager.register_did(bob.clone()).is_ok());
+        assert!(manager.resolve("did:dexos:bob").is_ok());
+        bob.document.services.push(ServiceEndpoint {
+            id: "bob#exchange".to_string(),
+            service_type: "LinkedDomains".to_string(),
+            endpoint: "https://attacker.example".to_string(),
+        });
+        assert!(matches!(
+            manager.register_did(bob),
+            Err(IdentityError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn test_registered_dids_rotate_only_with_the_current_key() {
+        let mut manager = IdentityManager::new();
+        let mut bob_side = IdentityManager::new();
+        let (bob, bob_key) = bob_side.create_did_with_keys(&"bob".to_string()).unwrap();
+        manager.register_did(bob.clone()).unwrap();
+
+        // A self-signed document under the same ID cannot take it over
+        let mut mallory_side = IdentityManager::new();
+        let (mut takeover, mallory_key) = mallory_side
+            .create_did_with_keys(&"bob".to_string())
+            .unwrap();
+        assert!(matches!(
+            manager.register_did(takeover.clone()),
+            Err(IdentityError::DIDAlreadyExists)
+        ));
+        takeover.updated = bob.updated + 1;
+        let forged = manager.sign_did_update(&takeover, &mallory_key).unwrap();
+        assert!(matches!(
+            manager.update_did(takeover, &forged),
+            Err(IdentityError::InvalidSignature)
+        ));
+        assert_eq!(manager.dids["bob"], bob);
+
+        // Bob rotates to fresh keys, authorizing it with the old ones
+        let mut rotator = IdentityManager::new();
+        let (mut rotated, rotated_key) = rotator.create_did_with_keys(&"bob".to_string()).unwrap();
+        rotated.updated = bob.updated + 1;
+        let authorization = manager.sign_did_update(&rotated, &bob_key).unwrap();
+        manager.update_did(rotated.clone(), &authorization).unwrap();
+        assert_eq!(manager.dids["bob"], rotated);
+
+        // Replaying the rotation or reverting with the retired key fails
+        assert!(matches!(
+            manager.update_did(rotated.clone(), &authorization),
+            Err(IdentityError::InvalidSignature)
+        ));
+        let mut revert = bob.clone();
+        revert.updated = rotated.updated + 1;
+        let stale = manager.sign_did_update(&revert, &bob_key).unwrap();
+        assert!(matches!(
+            manager.update_did(revert.clone(), &stale),
+            Err(IdentityError::InvalidSignature)
+        ));
+        let authorization = manager.sign_did_update(&revert, &rotated_key).unwrap();
+        assert!(manager.update_did(revert, &authorization).is_ok());
+    }
+
+    #[test]
+    fn test_created_dids_are_not_recreated() {
+        let mut manager = IdentityManager::new();
+        let trader_id = "alice".to_string();
+        let did = manager.create_did(&trader_id).unwrap();
+
+        assert!(matches!(
+            manager.create_did(&trader_id),
+            Err(IdentityError::DIDAlreadyExists)
+        ));
+        assert!(matches!(
+            manager.create_did_with_keys(&trader_id),
+            Err(IdentityError::DIDAlreadyExists)
+        ));
+        assert_eq!(manager.dids[&trader_id], did);
+    }
+
+    #[cfg(feature = "pq-signatures")]
+    #[test]
+    fn test_quantum_secure_crypto() {
+        let (private_key, public_key) = QuantumSecureCrypto::generate_dilithium_keypair();
+        assert_eq!(private_key.len(), DILITHIUM_SEED_LENGTH);
+        assert_eq!(public_key.len(), DILITHIUM_PUBLIC_KEY_LENGTH);
+
+        let data = b"test data for signing";
+        let signature = QuantumSecureCrypto::quantum_sign(&private_key, data).unwrap();
+        assert_eq!(signature.len(), DILITHIUM_SIGNATURE_LENGTH);
+
+        let valid = QuantumSecureCrypto::quantum_verify(&public_key, data, &signature);
+        assert!(valid);
+
+        // Test with incorrect data
+        let invalid =
+            QuantumSecureCrypto::quantum_verify(&public_key, b"different data", &signature);
+        assert!(!invalid);
+
+        // The public key alone must not be enough to sign
+        let (_, other_public_key) = QuantumSecureCrypto::generate_dilithium_keypair();
+        assert!(!QuantumSecureCrypto::quantum_verify(
+            &other_public_key,
+            data,
+            &signature
+        ));
+        assert!(QuantumSecureCrypto::quantum_sign(&public_key, data).is_err());
+    }
+
+    #[cfg(feature = "pq-signatures")]
+    #[test]
+    fn test_dilithium_known_answers() {
+        // NIST ACVP ML-DSA-keyGen-FIPS204, ML-DSA-65, tcId 26 and 27. The
+        // expected values are SHA-256 digests of the published public keys.
+        let vectors = [
+            (
+                "70CEFB9AED5B68E018B079DA8284B9D5CAD5499ED9C265FF73588005D85C225C",
+                "646b26b8d09dbc9e865b6a006c693a3127b065e62fab5fbe8b159c416462feb6",
+            ),
+            (
+                "4B4B71C5A1BC1074F2167A1D68729CDB9E16ABA3651FF02A0A0F4C883CAAC827",
+                "5fef74046438638b54fb828d4ae59fea8eb26de94905664c2c7d76da82672057",
+            ),
+        ];
+        for (seed, public_key_digest) in vectors {
+            let public_key = QuantumSecureCrypto::dilithium_public_key(&hex(seed)).unwrap();
+            assert_eq!(
+                sha2::Sha256::digest(&public_key).to_vec(),
+                hex(public_key_digest)
+            );
+        }
+    }
+
+    #[cfg(feature = "pq-signatures")]
+    #[test]
+    fn test_dilithium_signature_known_answers() {
+        // NIST ACVP ML-DSA-sigGen-FIPS204 (ML-DSA-65, deterministic, tcId 30)
+        // and ML-DSA-sigVer-FIPS204 (ML-DSA-65, tcId 16, 18 and 20), from
+        // usnistgov/ACVP-Server@65370b86. Both use the internal interface, so
+        // messages are signed as given, without a context prefix.
+        let vectors: serde_json::Value =
+            serde_json::from_str(include_str!("testdata/ml_dsa_65.json")).unwrap();
+        let field = |value: &serde_json::Value, name: &str| hex(value[name].as_str().unwrap());
+
+        let sign = &vectors["sigGen"];
+        let encoded_key =
+            ml_dsa::EncodedSigningKey::<MlDsa65>::try_from(&field(sign, "sk")[..]).unwrap();
+        let signature = ml_dsa::SigningKey::<MlDsa65>::decode(&encoded_key)
+            .sign_internal(&[&field(sign, "message")], &B32::default());
+        assert_eq!(signature.encode().to_vec(), field(sign, "signature"));
+
+        let verify = &vectors["sigVer"];
+        let public_key = EncodedVerifyingKey::<MlDsa65>::try_from(&field(verify, "pk")[..])
+            .map(|encoded| ml_dsa::VerifyingKey::<MlDsa65>::decode(&encoded))
+            .unwrap();
+        for test in verify["tests"].as_array().unwrap() {
+            let verified = EncodedSignature::<MlDsa65>::try_from(&field(test, "signature")[..])
+                .ok()
+                .and_then(|encoded| ml_dsa::Signature::<MlDsa65>::decode(&encoded))
+                .is_some_and(|signature| {
+                    public_key.verify_internal(&[&field(test, "message")], &signature)
+                });
+            assert_eq!(
+                verified,
+                test["testPassed"].as_bool().unwrap(),
+                "tcId {}: {}",
+                test["tcId"],
+                test["reason"]
+            );
+        }
+    }
+
+    #[cfg(feature = "pq-signatures")]
+    #[test]
+    fn test_hybrid_signatures_need_both_halves() {
+        let (private_key, public_key) = QuantumSecureCrypto::generate_hybrid_keypair();
+        let data = b"withdraw 100 USDC";
+        let signature = QuantumSecureCrypto::hybrid_sign(&private_key, data).unwrap();
+        assert!(QuantumSecureCrypto::hybrid_verify(
+            &public_key,
+            data,
+            &signature
+        ));
+        assert!(!QuantumSecureCrypto::hybrid_verify(
+            &public_key,
+            b"withdraw 900 USDC",
+            &signature
+        ));
+
+        for position in [10, 64 + 10] {
+            let mut tampered = signature.clone();
+            tampered[position] ^= 1;
+            assert!(!QuantumSecureCrypto::hybrid_verify(
+                &public_key,
+                data,
+                &tampered
+            ));
+        }
+
+        // Neither half verifies as a standalone signature
+        let (ed25519_public, dilithium_public) = public_key.split_at(32);
+        let (ed25519_signature, dilithium_signature) = signature.split_at(64);
+        assert!(!QuantumSecureCrypto::quantum_verify(
+            dilithium_public,
+            data,
+            dilithium_signature
+        ));
+        assert!(!ed25519_verify(ed25519_public, data, ed25519_signature));
+    }
+
+    #[cfg(feature = "pq-signatures")]
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_biometric_hashing() {
+        let bio_data1 = b"fingerprint_template_1";
+        let bio_data2 = b"fingerprint_template_2";
+
+        let hash1 = QuantumSecureCrypto::hash_biometric(bio_data1);
+        let hash2 = QuantumSecureCrypto::hash_biometric(bio_data2);
+
+        assert_eq!(hash1.len(), 32); // SHA3-256 produces 32-byte hash
+        assert_eq!(hash2.len(), 32);
+        assert_ne!(hash1, hash2); // Different inputs should produce different hashes
+
+        // Same input should produce same hash
+        let hash1_again = QuantumSecureCrypto::hash_biometric(bio_data1);
+        assert_eq!(hash1, hash1_again);
+    }
+}
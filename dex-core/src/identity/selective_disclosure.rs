@@ -0,0 +1,596 @@
+//! Selective-disclosure credentials
+//!
+//! This module implements part of the Priority 3 features from DEX-OS-V2.csv:
+//! - Global Identity (Self-Sovereign)
+//! - Security,Security,Security,Zero-Knowledge Proofs,Privacy Protection,Medium
+//!
+//! A selective-disclosure credential is an ordinary signed
+//! [`VerifiableCredential`] whose hidden claims are replaced by commitments.
+//! Claims follow the SD-JWT disclosure format: each one becomes a disclosure
+//! `base64url(["<salt>", "<name>", <value>])` and only the base64url SHA-256
+//! digests of the disclosures are listed in the subject's `_sd` claim.
+//! Numeric claims can instead be Pedersen-committed under `_pedersen`, so the
+//! holder can prove a range (for example "born on or before this date") with
+//! a Bulletproof from [`crate::crypto::zk_proof`] without revealing the value.
+//!
+//! A [`Presentation`] carries the signed credential, only the disclosures and
+//! range proofs a [`PresentationRequest`] asks for, and a holder binding: an
+//! ed25519 signature by the credential subject over the presentation and the
+//! verifier's nonce and audience, so it cannot be replayed by anyone else or
+//! to another verifier.
+
+use super::credentials::{canonical_json, current_timestamp, format_timestamp};
+use super::did::{decode_multibase, encode_multibase};
+use super::{IdentityError, VerifiableCredential};
+use crate::crypto::zk_proof::{Opening, RangeProof, ZkProofSystem};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+
+/// Subject claim listing the digests of hidden claims
+pub const SD_CLAIM: &str = "_sd";
+
+/// Subject claim naming the digest algorithm of [`SD_CLAIM`]
+pub const SD_ALG_CLAIM: &str = "_sd_alg";
+
+/// Digest algorithm of disclosures
+pub const SD_ALG: &str = "sha-256";
+
+/// Subject claim mapping claim names to Pedersen commitments
+pub const PEDERSEN_CLAIM: &str = "_pedersen";
+
+/// Random bytes in a disclosure salt
+const SALT_BYTES: usize = 16;
+
+/// Domain separator of holder binding signatures
+const BINDING_DOMAIN: &[u8] = b"DEX-OS selective disclosure presentation";
+
+/// A salted claim that can be revealed on its own
+#[derive(Debug, Clone, PartialEq)]
+pub struct Disclosure {
+    /// Random salt, so the digest of a guessable value cannot be brute-forced
+    pub salt: String,
+    /// Claim name
+    pub name: String,
+    /// Claim value
+    pub value: Value,
+}
+
+impl Disclosure {
+    /// Create a disclosure with a fresh salt
+    pub fn new(name: impl Into<String>, value: Value) -> Self {
+        let mut salt = [0u8; SALT_BYTES];
+        OsRng.fill_bytes(&mut salt);
+        Self {
+            salt: URL_SAFE_NO_PAD.encode(salt),
+            name: name.into(),
+            value,
+        }
+    }
+
+    /// The base64url-encoded JSON array `[salt, name, value]`
+    pub fn encode(&self) -> String {
+        let array = Value::Array(vec![
+            Value::from(self.salt.as_str()),
+            Value::from(self.name.as_str()),
+            self.value.clone(),
+        ]);
+        URL_SAFE_NO_PAD.encode(array.to_string())
+    }
+
+    /// Parse an encoded disclosure
+    pub fn decode(encoded: &str) -> Option<Self> {
+        let bytes = URL_SAFE_NO_PAD.decode(encoded).ok()?;
+        let Value::Array(mut items) = serde_json::from_slice(&bytes).ok()? else {
+            return None;
+        };
+        if items.len() != 3 {
+            return None;
+        }
+        let value = items.pop()?;
+        let name = items.pop()?.as_str()?.to_string();
+        let salt = items.pop()?.as_str()?.to_string();
+        Some(Self { salt, name, value })
+    }
+
+    /// Digest of an encoded disclosure as listed under [`SD_CLAIM`]. It is
+    /// taken over the encoded form, so verifiers never re-serialize values.
+    pub fn digest(encoded: &str) -> String {
+        URL_SAFE_NO_PAD.encode(Sha256::digest(encoded.as_bytes()))
+    }
+}
+
+/// A selective-disclosure credential as kept by its holder
+pub struct SelectiveCredential {
+    /// The signed credential, carrying only digests and commitments of hidden claims
+    pub credential: VerifiableCredential,
+    /// Encoded disclosures by claim name
+    disclosures: BTreeMap<String, String>,
+    /// Openings of the Pedersen-committed claims by claim name
+    openings: BTreeMap<String, Opening>,
+}
+
+impl SelectiveCredential {
+    /// Names of the claims that can be disclosed one by one
+    pub fn disclosable_claims(&self) -> impl Iterator<Item = &str> {
+        self.disclosures.keys().map(String::as_str)
+    }
+
+    /// Names of the claims that can only be proven to lie in a range
+    pub fn committed_claims(&self) -> impl Iterator<Item = &str> {
+        self.openings.keys().map(String::as_str)
+    }
+
+    /// Encoded disclosure of a claim
+    pub fn disclosure(&self, name: &str) -> Option<&str> {
+        self.disclosures.get(name).map(String::as_str)
+    }
+
+    /// Opening of a committed claim
+    pub fn opening(&self, name: &str) -> Option<&Opening> {
+        self.openings.get(name)
+    }
+}
+
+/// What a verifier asks a holder to present
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PresentationRequest {
+    /// Fresh challenge from the verifier
+    pub nonce: String,
+    /// Identifier of the verifier
+    pub audience: String,
+    /// Credential type the presentation must be based on
+    #[serde(rename = "credentialType")]
+    pub credential_type: String,
+    /// Claims that must be disclosed
+    pub disclose: Vec<String>,
+    /// Committed claims that must be proven to lie in a range
+    pub ranges: Vec<RangeRequirement>,
+}
+
+/// A committed claim that must lie in `[min, max]`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RangeRequirement {
+    /// Claim name
+    pub claim: String,
+    /// Smallest accepted value
+    pub min: u64,
+    /// Largest accepted value
+    pub max: u64,
+}
+
+/// A holder's answer to a [`PresentationRequest`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Presentation {
+    /// The issuer-signed credential
+    pub credential: VerifiableCredential,
+    /// Encoded disclosures of the revealed claims
+    pub disclosures: Vec<String>,
+    /// Range proofs for committed claims, by claim name
+    #[serde(rename = "rangeProofs")]
+    pub range_proofs: BTreeMap<String, RangeProof>,
+    /// Signature of the credential subject binding the presentation to the request
+    #[serde(rename = "holderBinding")]
+    pub holder_binding: HolderBinding,
+}
+
+/// Holder signature over a presentation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HolderBinding {
+    /// DID URL of the subject's signing key
+    #[serde(rename = "verificationMethod")]
+    pub verification_method: String,
+    /// Nonce of the answered request
+    pub nonce: String,
+    /// Audience of the answered request
+    pub audience: String,
+    /// Creation time
+    pub created: String,
+    /// Multibase-encoded signature
+    #[serde(
+        rename = "proofValue",
+        default,
+        skip_serializing_if = "String::is_empty"
+    )]
+    pub proof_value: String,
+}
+
+/// Claims established by a verified presentation
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisclosedClaims {
+    /// Issuer DID
+    pub issuer: String,
+    /// Subject DID, proven to be the presenter
+    pub subject: String,
+    /// Always-visible and disclosed claims
+    pub claims: BTreeMap<String, Value>,
+    /// Ranges proven for committed claims
+    pub ranges: Vec<RangeRequirement>,
+}
+
+impl DisclosedClaims {
+    /// A revealed claim
+    pub fn claim(&self, name: &str) -> Option<&Value> {
+        self.claims.get(name)
+    }
+
+    /// Whether `claim` was proven to lie within `[min, max]`
+    pub fn proves_range(&self, claim: &str, min: u64, max: u64) -> bool {
+        self.ranges
+            .iter()
+            .any(|range| range.claim == claim && range.min >= min && range.max <= max)
+    }
+}
+
+/// Add `disclosable` and `committed` claims to an unsigned credential as
+/// digests and commitments, keeping what the holder needs to reveal them
+pub(crate) fn conceal(
+    mut credential: VerifiableCredential,
+    disclosable: BTreeMap<String, Value>,
+    committed: BTreeMap<String, u64>,
+    zk: &ZkProofSystem,
+) -> Result<SelectiveCredential, IdentityError> {
+    let claims = &mut credential.credential_subject.claims;
+    if claims.keys().any(|name| name.starts_with('_')) {
+        return Err(IdentityError::InvalidCredential(
+            "claim names starting with '_' are reserved".to_string(),
+        ));
+    }
+    let mut names = HashSet::new();
+    for name in disclosable.keys().chain(committed.keys()) {
+        if is_reserved(name) || claims.contains_key(name) || !names.insert(name) {
+            return Err(IdentityError::InvalidCredential(format!(
+                "claim {} cannot be concealed",
+                name
+            )));
+        }
+    }
+
+    let mut disclosures = BTreeMap::new();
+    let mut digests = Vec::new();
+    for (name, value) in disclosable {
+        let encoded = Disclosure::new(name.clone(), value).encode();
+        digests.push(Value::from(Disclosure::digest(&encoded)));
+        disclosures.insert(name, encoded);
+    }
+    // Sorted digests do not reveal the order claims were added in
+    digests.sort_by(|a, b| a.as_str().cmp(&b.as_str()));
+
+    let mut openings = BTreeMap::new();
+    let mut commitments = Map::new();
+    for (name, value) in committed {
+        let (commitment, opening) = zk.commit(value);
+        commitments.insert(
+            name.clone(),
+            Value::from(URL_SAFE_NO_PAD.encode(commitment)),
+        );
+        openings.insert(name, opening);
+    }
+
+    if !digests.is_empty() {
+        claims.insert(SD_CLAIM.to_string(), Value::Array(digests));
+        claims.insert(SD_ALG_CLAIM.to_string(), Value::from(SD_ALG));
+    }
+    if !commitments.is_empty() {
+        claims.insert(PEDERSEN_CLAIM.to_string(), Value::Object(commitments));
+    }
+    Ok(SelectiveCredential {
+        credential,
+        disclosures,
+        openings,
+    })
+}
+
+/// Build the presentation answering `request`, signed with the holder's key
+pub(crate) fn present(
+    holding: &SelectiveCredential,
+    request: &PresentationRequest,
+    signing_key: &SigningKey,
+    verification_method: &str,
+    zk: &ZkProofSystem,
+) -> Result<Presentation, IdentityError> {
+    let mut disclosures = Vec::new();
+    for name in &request.disclose {
+        if holding.credential.claim(name).is_some() && !is_reserved(name) {
+            continue;
+        }
+        let encoded = holding.disclosure(name).ok_or_else(|| {
+            IdentityError::InvalidPresentation(format!("claim {} is not held", name))
+        })?;
+        disclosures.push(encoded.to_string());
+    }
+    disclosures.sort();
+    disclosures.dedup();
+
+    let mut range_proofs = BTreeMap::new();
+    for range in &request.ranges {
+        let opening = holding.opening(&range.claim).ok_or_else(|| {
+            IdentityError::InvalidPresentation(format!("claim {} is not committed", range.claim))
+        })?;
+        let proof = zk
+            .prove_committed_range(opening, range.min, range.max)
+            .map_err(|err| {
+                IdentityError::InvalidPresentation(format!("claim {}: {}", range.claim, err))
+            })?;
+        range_proofs.insert(range.claim.clone(), proof);
+    }
+
+    let mut presentation = Presentation {
+        credential: holding.credential.clone(),
+        disclosures,
+        range_proofs,
+        holder_binding: HolderBinding {
+            verification_method: verification_method.to_string(),
+            nonce: request.nonce.clone(),
+            audience: request.audience.clone(),
+            created: format_timestamp(current_timestamp()),
+            proof_value: String::new(),
+        },
+    };
+    let signature = signing_key.sign(&binding_hash(&presentation));
+    presentation.holder_binding.proof_value = encode_multibase(&signature.to_bytes());
+    Ok(presentation)
+}
+
+/// Check the holder binding answers `request` and is signed by `public_key`
+pub(crate) fn verify_binding(
+    presentation: &Presentation,
+    request: &PresentationRequest,
+    public_key: &[u8; 32],
+) -> bool {
+    let binding = &presentation.holder_binding;
+    if binding.nonce != request.nonce || binding.audience != request.audience {
+        return false;
+    }
+    let Some(signature) = decode_multibase(&binding.proof_value)
+        .and_then(|bytes| <[u8; 64]>::try_from(bytes.as_slice()).ok())
+    else {
+        return false;
+    };
+    let Ok(key) = VerifyingKey::from_bytes(public_key) else {
+        return false;
+    };
+    key.verify(
+        &binding_hash(presentation),
+        &Signature::from_bytes(&signature),
+    )
+    .is_ok()
+}
+
+/// Check the disclosures and range proofs of a presentation against the
+/// commitments in its credential and return what they establish. The
+/// credential proof and holder binding are checked by the caller.
+pub(crate) fn open(
+    presentation: &Presentation,
+    request: &PresentationRequest,
+    zk: &ZkProofSystem,
+) -> Result<DisclosedClaims, IdentityError> {
+    let invalid = |reason: String| IdentityError::InvalidPresentation(reason);
+    let credential = &presentation.credential;
+    let subject_claims = &credential.credential_subject.claims;
+
+    let digests: HashSet<&str> = match subject_claims.get(SD_CLAIM) {
+        None => HashSet::new(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(Value::as_str)
+            .collect::<Option<_>>()
+            .ok_or_else(|| invalid("malformed _sd claim".to_string()))?,
+        Some(_) => return Err(invalid("malformed _sd claim".to_string())),
+    };
+    if !digests.is_empty()
+        && subject_claims.get(SD_ALG_CLAIM).and_then(Value::as_str) != Some(SD_ALG)
+    {
+        return Err(invalid("unsupported _sd_alg".to_string()));
+    }
+
+    let mut claims: BTreeMap<String, Value> = subject_claims
+        .iter()
+        .filter(|(name, _)| !is_reserved(name))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    for encoded in &presentation.disclosures {
+        if !digests.contains(Disclosure::digest(encoded).as_str()) {
+            return Err(invalid(
+                "disclosure is not committed by the issuer".to_string(),
+            ));
+        }
+        let disclosure = Disclosure::decode(encoded)
+            .ok_or_else(|| invalid("malformed disclosure".to_string()))?;
+        // A disclosure may neither repeat nor shadow a claim
+        if is_reserved(&disclosure.name)
+            || claims
+                .insert(disclosure.name.clone(), disclosure.value)
+                .is_some()
+        {
+            return Err(invalid(format!("duplicate claim {}", disclosure.name)));
+        }
+    }
+    if let Some(missing) = request
+        .disclose
+        .iter()
+        .find(|name| !claims.contains_key(*name))
+    {
+        return Err(invalid(format!("claim {} is not disclosed", missing)));
+    }
+
+    let commitments = subject_claims
+        .get(PEDERSEN_CLAIM)
+        .and_then(Value::as_object);
+    for range in &request.ranges {
+        let committed = commitments
+            .and_then(|commitments| commitments.get(&range.claim))
+            .and_then(Value::as_str)
+            .and_then(|encoded| URL_SAFE_NO_PAD.decode(encoded).ok())
+            .ok_or_else(|| invalid(format!("claim {} is not committed", range.claim)))?;
+        let proven = presentation
+            .range_proofs
+            .get(&range.claim)
+            .is_some_and(|proof| {
+                proof.commitment == committed && zk.verify_range(proof, range.min, range.max)
+            });
+        if !proven {
+            return Err(invalid(format!(
+                "range proof for {} does not verify",
+                range.claim
+            )));
+        }
+    }
+
+    Ok(DisclosedClaims {
+        issuer: credential.issuer.clone(),
+        subject: credential.subject().to_string(),
+        claims,
+        ranges: request.ranges.clone(),
+    })
+}
+
+/// Names that may not be used for subject claims
+fn is_reserved(name: &str) -> bool {
+    name == "id" || name.starts_with('_')
+}
+
+/// `SHA-256(domain || JCS(presentation without its binding signature))`
+fn binding_hash(presentation: &Presentation) -> Vec<u8> {
+    let mut unsigned = presentation.clone();
+    unsigned.holder_binding.proof_value = String::new();
+    let document = serde_json::to_value(&unsigned).expect("presentations serialize");
+
+    let mut hasher = Sha256::new();
+    hasher.update(BINDING_DOMAIN);
+    hasher.update(canonical_json(&document).as_bytes());
+    hasher.finalize().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identity::{IdentityManager, KYC_CREDENTIAL_TYPE};
+    use serde_json::json;
+
+    #[test]
+    fn test_disclosure_encoding() {
+        let disclosure = Disclosure::new("countryOfResidence", json!("DE"));
+        let encoded = disclosure.encode();
+        assert_eq!(Disclosure::decode(&encoded), Some(disclosure.clone()));
+
+        // Equal claims get different salts and so different digests
+        let other = Disclosure::new("countryOfResidence", json!("DE"));
+        assert_ne!(
+            Disclosure::digest(&encoded),
+            Disclosure::digest(&other.encode())
+        );
+        assert_eq!(Disclosure::decode("not base64!"), None);
+        assert_eq!(
+            Disclosure::decode(&URL_SAFE_NO_PAD.encode(r#"["salt","name"]"#)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_selective_presentation() {
+        let mut manager = IdentityManager::new();
+        let (_, issuer_key) = manager
+            .create_did_with_keys(&"kyc-provider".to_string())
+            .unwrap();
+        let (_, alice_key) = manager.create_did_with_keys(&"alice".to_string()).unwrap();
+        let (_, mallory_key) = manager
+            .create_did_with_keys(&"mallory".to_string())
+            .unwrap();
+        manager.add_trusted_issuer("kyc-provider".to_string());
+
+        let mut plain = BTreeMap::new();
+        plain.insert("level".to_string(), json!("basic"));
+        let mut disclosable = BTreeMap::new();
+        disclosable.insert("fullName".to_string(), json!("Alice Example"));
+        disclosable.insert("countryOfResidence".to_string(), json!("DE"));
+        let mut committed = BTreeMap::new();
+        committed.insert("birthDate".to_string(), 19_900_415);
+        let holding = manager
+            .issue_selective_credential(
+                VerifiableCredential::new(
+                    "kyc-alice",
+                    "kyc-provider",
+                    "alice",
+                    KYC_CREDENTIAL_TYPE,
+                    plain,
+                ),
+                disclosable,
+                committed,
+                &issuer_key,
+            )
+            .unwrap();
+        assert_eq!(holding.credential.claim("fullName"), None);
+        assert_eq!(
+            holding.committed_claims().collect::<Vec<_>>(),
+            ["birthDate"]
+        );
+
+        let request = PresentationRequest {
+            nonce: "n-1".to_string(),
+            audience: "dex-os".to_string(),
+            credential_type: KYC_CREDENTIAL_TYPE.to_string(),
+            disclose: vec!["countryOfResidence".to_string()],
+            ranges: vec![RangeRequirement {
+                claim: "birthDate".to_string(),
+                min: 0,
+                max: 20_080_101,
+            }],
+        };
+        let presentation = manager
+            .present_credential(&holding, &request, &alice_key)
+            .unwrap();
+        assert_eq!(presentation.disclosures.len(), 1);
+
+        let disclosed = manager
+            .verify_presentation(&presentation, &request)
+            .unwrap();
+        assert_eq!(disclosed.subject, "did:dexos:alice");
+        assert_eq!(disclosed.claim("countryOfResidence"), Some(&json!("DE")));
+        assert_eq!(disclosed.claim("level"), Some(&json!("basic")));
+        // Claims that were not asked for stay hidden
+        assert_eq!(disclosed.claim("fullName"), None);
+        assert_eq!(disclosed.claim("birthDate"), None);
+        assert!(disclosed.proves_range("birthDate", 0, 20_080_101));
+
+        // The binding ties the presentation to the nonce and audience
+        let mut replayed = request.clone();
+        replayed.nonce = "n-2".to_string();
+        assert!(matches!(
+            manager.verify_presentation(&presentation, &replayed),
+            Err(IdentityError::InvalidSignature)
+        ));
+
+        // Only the subject can present the credential
+        assert!(manager
+            .present_credential(&holding, &request, &mallory_key)
+            .is_err());
+
+        // A forged disclosure is not among the issuer's digests
+        let mut forged = presentation.clone();
+        forged.disclosures = vec![Disclosure::new("countryOfResidence", json!("FR")).encode()];
+        assert!(matches!(
+            manager.verify_presentation(&forged, &request),
+            Err(IdentityError::InvalidSignature)
+        ));
+        let zk = ZkProofSystem::new();
+        assert!(matches!(
+            open(&forged, &request, &zk),
+            Err(IdentityError::InvalidPresentation(_))
+        ));
+
+        // A value outside the requested range cannot be proven
+        let mut too_young = request.clone();
+        too_young.ranges[0].max = 19_900_101;
+        assert!(matches!(
+            manager.present_credential(&holding, &too_young, &alice_key),
+            Err(IdentityError::InvalidPresentation(_))
+        ));
+    }
+}
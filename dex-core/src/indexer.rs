@@ -3,9 +3,16 @@
 //! This module implements the Priority 3 feature from DEX-OS-V2.csv:
 //! - Core Trading,Indexer,Indexer,Filtering Engine,Selective Data Capture,Medium
 //! - Infrastructure,Indexer,Indexer,Materialized Views,Data Aggregation,Medium
+//!
+//! Indexed content is passed through a PII [`Redactor`] before it is
+//! stored: the process-wide one at [`LOG_CLASSIFICATION`] unless another is
+//! configured.
 
+use crate::security::redaction::{self, LOG_CLASSIFICATION};
+use crate::security::{ClassificationLevel, Redactor};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 
@@ -22,6 +29,8 @@ pub struct IndexerService {
     materialized_views: HashMap<String, MaterializedView>,
     /// Maximum number of entries to store
     max_entries: usize,
+    /// Redaction of content before it is stored
+    redaction: Option<(Arc<Redactor>, ClassificationLevel)>,
 }
 
 /// Data filter for selective indexing
@@ -114,9 +123,16 @@ impl IndexerService {
             filter_index: HashMap::new(),
             materialized_views: HashMap::new(),
             max_entries,
+            redaction: Some((redaction::global(), LOG_CLASSIFICATION)),
         }
     }
 
+    /// Redact PII in indexed content with `redactor` at `level` instead
+    pub fn with_redaction(mut self, redactor: Arc<Redactor>, level: ClassificationLevel) -> Self {
+        self.redaction = Some((redactor, level));
+        self
+    }
+
     /// Add a new data filter
     pub fn add_filter(&mut self, filter: DataFilter) -> Result<(), IndexerError> {
         if self.filters.contains_key(&filter.id) {
@@ -192,6 +208,11 @@ impl IndexerService {
         // Determine which filters match this data
         let matched_filters = self.find_matching_filters(&data_type, &tags, priority);
 
+        let content = match &self.redaction {
+            Some((redactor, level)) => redactor.redact(&content, level),
+            None => content,
+        };
+
         let entry = IndexedData {
             id: entry_id.clone(),
             data_type,
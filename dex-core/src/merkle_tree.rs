@@ -197,6 +197,21 @@ impl MerkleTree {
             return false;
         }
 
+        match &self.root {
+            Some(root) => Self::verify_proof_with_root(&root.hash, data, index, proof),
+            None => false,
+        }
+    }
+
+    /// Verify a proof against a known root hash, without the tree itself.
+    /// Callers must check `index` against the leaf count committed alongside
+    /// the root.
+    pub fn verify_proof_with_root(
+        root: &[u8],
+        data: &[u8],
+        index: usize,
+        proof: &[Vec<u8>],
+    ) -> bool {
         let mut hasher = Sha256::new();
         hasher.update(data);
         let mut current_hash = hasher.finalize().to_vec();
@@ -217,11 +232,7 @@ impl MerkleTree {
             proof_index /= 2;
         }
 
-        if let Some(root) = &self.root {
-            current_hash == root.hash
-        } else {
-            false
-        }
+        current_hash == root
     }
 
     /// Build leaf nodes from the original data
@@ -3,22 +3,124 @@
 //! This module implements the Priority 2 feature from DEX-OS-V1.csv:
 //! "Core Trading,Bridge,Bridge,Multi-signature Wallets,Asset Custody,High"
 //!
+//! It also covers the following features from DEX-OS-V2.csv:
+//! - Sub Types,Bridge Subtypes,Bridge,MPC Threshold,MPC Threshold Mechanism,High
+//! - Governance & Security,DAO Governance,DAO Governance,Timelock Execution,Timelock Execution,High
+//!
 //! It provides functionality for secure asset custody using multi-signature wallets,
 //! which require multiple parties to sign transactions before they can be executed.
+//!
+//! An approval is a signature over the transaction's canonical digest. It is
+//! checked against the participant's registered ed25519 or secp256k1 key, both
+//! when it is submitted and again at execution. A wallet may also register a
+//! FROST group key. The signers then run the threshold ceremony off-chain and
+//! submit one aggregate signature instead of individual approvals. Under
+//! FROST(Ed25519, SHA-512) that aggregate is a plain Ed25519 signature.
+//! Time-locks delay execution after creation, and spending limits cap how
+//! much of a token leaves the wallet per rolling window.
 
 use crate::types::{Quantity, TokenId, TraderId};
+use sha2::{Digest, Sha256};
 use std::collections::{HashMap, HashSet};
 use thiserror::Error;
 
+/// Domain separator for transaction digests
+const DIGEST_DOMAIN: &[u8] = b"dexos-multisig-v1";
+
+/// Signature scheme of a participant key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SignatureScheme {
+    /// 32-byte ed25519 public key, 64-byte signature
+    Ed25519,
+    /// SEC1 secp256k1 public key, 64-byte `r || s` ECDSA signature with low S
+    Secp256k1,
+}
+
 /// Represents a participant in a multi-signature wallet
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct WalletParticipant {
     /// Unique identifier for the participant
     pub id: TraderId,
-    /// Public key of the participant
+    /// Hex-encoded public key of the participant: a 32-byte ed25519 key or a
+    /// 33/65-byte SEC1 secp256k1 key
     pub public_key: String,
 }
 
+impl WalletParticipant {
+    /// Participant approving with an ed25519 key
+    pub fn ed25519(id: impl Into<TraderId>, key: &ed25519_dalek::VerifyingKey) -> Self {
+        Self {
+            id: id.into(),
+            public_key: encode_hex(key.as_bytes()),
+        }
+    }
+
+    /// Participant approving with a secp256k1 key
+    pub fn secp256k1(id: impl Into<TraderId>, key: &k256::ecdsa::VerifyingKey) -> Self {
+        Self {
+            id: id.into(),
+            public_key: encode_hex(&key.to_encoded_point(true).to_bytes()),
+        }
+    }
+
+    /// Scheme implied by the registered key
+    pub fn scheme(&self) -> Result<SignatureScheme, MultiSigError> {
+        Ok(match self.verifier()? {
+            ParticipantKey::Ed25519(_) => SignatureScheme::Ed25519,
+            ParticipantKey::Secp256k1(_) => SignatureScheme::Secp256k1,
+        })
+    }
+
+    /// Verify the participant's signature over a transaction digest
+    pub fn verify(&self, digest: &[u8; 32], signature: &[u8]) -> Result<(), MultiSigError> {
+        let invalid = || MultiSigError::InvalidSignature(self.id.clone());
+        match self.verifier()? {
+            ParticipantKey::Ed25519(key) => {
+                let signature =
+                    ed25519_dalek::Signature::from_slice(signature).map_err(|_| invalid())?;
+                key.verify_strict(digest, &signature).map_err(|_| invalid())
+            }
+            ParticipantKey::Secp256k1(key) => {
+                use k256::ecdsa::signature::Verifier;
+                let signature =
+                    k256::ecdsa::Signature::from_slice(signature).map_err(|_| invalid())?;
+                key.verify(digest, &signature).map_err(|_| invalid())
+            }
+        }
+    }
+
+    fn verifier(&self) -> Result<ParticipantKey, MultiSigError> {
+        let invalid = || MultiSigError::InvalidPublicKey(self.id.clone());
+        let bytes = decode_hex(&self.public_key).ok_or_else(invalid)?;
+        match bytes.len() {
+            32 => {
+                let bytes: [u8; 32] = bytes.try_into().map_err(|_| invalid())?;
+                ed25519_dalek::VerifyingKey::from_bytes(&bytes)
+                    .map(ParticipantKey::Ed25519)
+                    .map_err(|_| invalid())
+            }
+            33 | 65 => k256::ecdsa::VerifyingKey::from_sec1_bytes(&bytes)
+                .map(ParticipantKey::Secp256k1)
+                .map_err(|_| invalid()),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+enum ParticipantKey {
+    Ed25519(ed25519_dalek::VerifyingKey),
+    Secp256k1(k256::ecdsa::VerifyingKey),
+}
+
+/// Cap on how much of a token may leave the wallet per rolling window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpendingLimit {
+    /// Maximum amount executed within one window
+    pub amount: Quantity,
+    /// Length of the rolling window in seconds
+    pub period_seconds: u64,
+}
+
 /// Represents a transaction that requires multi-signature approval
 #[derive(Debug, Clone, PartialEq)]
 pub struct MultiSigTransaction {
@@ -34,10 +136,14 @@ pub struct MultiSigTransaction {
     pub amount: Quantity,
     /// Required number of signatures
     pub required_signatures: usize,
-    /// Participants who have signed
-    pub signatures: HashSet<TraderId>,
+    /// Verified signatures over [`Self::digest`], keyed by participant
+    pub signatures: HashMap<TraderId, Vec<u8>>,
+    /// FROST aggregate signature over [`Self::digest`] under the wallet's group key
+    pub threshold_signature: Option<Vec<u8>>,
     /// Timestamp when the transaction was created
     pub created_timestamp: u64,
+    /// Earliest timestamp at which the transaction may execute
+    pub executable_at: u64,
     /// Timestamp when the transaction was executed (if executed)
     pub executed_timestamp: Option<u64>,
 }
@@ -45,7 +151,13 @@ pub struct MultiSigTransaction {
 impl MultiSigTransaction {
     /// Check if the transaction has enough signatures to be executed
     pub fn is_ready_for_execution(&self) -> bool {
-        self.signatures.len() >= self.required_signatures && self.executed_timestamp.is_none()
+        (self.signatures.len() >= self.required_signatures || self.threshold_signature.is_some())
+            && self.executed_timestamp.is_none()
+    }
+
+    /// Check if the transaction's time-lock has elapsed
+    pub fn is_unlocked(&self, now: u64) -> bool {
+        now >= self.executable_at
     }
 
     /// Check if the transaction has been executed
@@ -53,14 +165,26 @@ impl MultiSigTransaction {
         self.executed_timestamp.is_some()
     }
 
-    /// Add a signature to the transaction
-    pub fn add_signature(&mut self, participant_id: TraderId) {
-        self.signatures.insert(participant_id);
+    /// Canonical digest participants sign to approve the transaction
+    pub fn digest(&self) -> [u8; 32] {
+        transaction_digest(
+            &self.from_wallet,
+            self.id,
+            &self.to_address,
+            &self.token_id,
+            self.amount,
+        )
+    }
+
+    /// Add a signature to the transaction. Signatures are verified again when
+    /// the transaction executes.
+    pub fn add_signature(&mut self, participant_id: TraderId, signature: Vec<u8>) {
+        self.signatures.insert(participant_id, signature);
     }
 
     /// Check if a participant has signed the transaction
     pub fn has_signature_from(&self, participant_id: &TraderId) -> bool {
-        self.signatures.contains(participant_id)
+        self.signatures.contains_key(participant_id)
     }
 }
 
@@ -79,6 +203,12 @@ pub struct MultiSigWallet {
     pub pending_transactions: HashMap<u64, MultiSigTransaction>,
     /// Executed transactions
     pub executed_transactions: HashMap<u64, MultiSigTransaction>,
+    /// Delay in seconds between creating and executing a transaction
+    pub time_lock_seconds: u64,
+    /// Spending limits per token
+    pub spending_limits: HashMap<TokenId, SpendingLimit>,
+    /// Hex-encoded FROST(Ed25519, SHA-512) group key accepting aggregate approvals
+    pub threshold_key: Option<String>,
     /// Transaction counter for generating unique IDs
     transaction_counter: u64,
 }
@@ -103,6 +233,10 @@ impl MultiSigWallet {
             return Err(MultiSigError::InvalidRequiredSignatures);
         }
 
+        for participant in &participants {
+            participant.scheme()?;
+        }
+
         let participant_set: HashSet<WalletParticipant> = participants.into_iter().collect();
 
         Ok(Self {
@@ -112,10 +246,32 @@ impl MultiSigWallet {
             assets: HashMap::new(),
             pending_transactions: HashMap::new(),
             executed_transactions: HashMap::new(),
+            time_lock_seconds: 0,
+            spending_limits: HashMap::new(),
+            threshold_key: None,
             transaction_counter: 0,
         })
     }
 
+    /// Delay every transaction by `seconds` after its creation
+    pub fn with_time_lock(mut self, seconds: u64) -> Self {
+        self.time_lock_seconds = seconds;
+        self
+    }
+
+    /// Cap how much of `token_id` may be executed per rolling window
+    pub fn with_spending_limit(mut self, token_id: TokenId, limit: SpendingLimit) -> Self {
+        self.spending_limits.insert(token_id, limit);
+        self
+    }
+
+    /// Accept FROST aggregate signatures under the hex-encoded ed25519 group key
+    pub fn with_threshold_key(mut self, group_key: &str) -> Result<Self, MultiSigError> {
+        parse_group_key(group_key)?;
+        self.threshold_key = Some(group_key.to_string());
+        Ok(self)
+    }
+
     /// Get the number of participants in the wallet
     pub fn participant_count(&self) -> usize {
         self.participants.len()
@@ -126,6 +282,11 @@ impl MultiSigWallet {
         self.participants.iter().any(|p| &p.id == participant_id)
     }
 
+    /// Get a participant by ID
+    pub fn participant(&self, participant_id: &TraderId) -> Option<&WalletParticipant> {
+        self.participants.iter().find(|p| &p.id == participant_id)
+    }
+
     /// Add assets to the wallet
     pub fn deposit(&mut self, token_id: TokenId, amount: Quantity) {
         let current_amount = self.assets.get(&token_id).copied().unwrap_or(0);
@@ -142,6 +303,23 @@ impl MultiSigWallet {
         &self.assets
     }
 
+    /// Digest the next transaction created with these details will carry, so
+    /// approvals can be signed before it is created
+    pub fn next_transaction_digest(
+        &self,
+        to_address: &str,
+        token_id: &TokenId,
+        amount: Quantity,
+    ) -> [u8; 32] {
+        transaction_digest(
+            &self.wallet_id,
+            self.transaction_counter + 1,
+            to_address,
+            token_id,
+            amount,
+        )
+    }
+
     /// Create a new transaction
     pub fn create_transaction(
         &mut self,
@@ -149,6 +327,27 @@ impl MultiSigWallet {
         token_id: TokenId,
         amount: Quantity,
     ) -> Result<u64, MultiSigError> {
+        self.create_transaction_at(to_address, token_id, amount, current_timestamp())
+    }
+
+    /// Create a new transaction at the given Unix time
+    pub fn create_transaction_at(
+        &mut self,
+        to_address: String,
+        token_id: TokenId,
+        amount: Quantity,
+        now: u64,
+    ) -> Result<u64, MultiSigError> {
+        // Reject amounts no window could ever cover
+        if let Some(limit) = self.spending_limits.get(&token_id) {
+            if amount > limit.amount {
+                return Err(MultiSigError::SpendingLimitExceeded {
+                    token_id,
+                    remaining: limit.amount,
+                });
+            }
+        }
+
         // Check if the wallet has sufficient balance
         let balance = self.get_balance(&token_id);
         if amount > balance {
@@ -170,11 +369,10 @@ impl MultiSigWallet {
             token_id,
             amount,
             required_signatures: self.required_signatures,
-            signatures: HashSet::new(),
-            created_timestamp: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
+            signatures: HashMap::new(),
+            threshold_signature: None,
+            created_timestamp: now,
+            executable_at: now.saturating_add(self.time_lock_seconds),
             executed_timestamp: None,
         };
 
@@ -185,16 +383,18 @@ impl MultiSigWallet {
         Ok(transaction_id)
     }
 
-    /// Sign a transaction
+    /// Approve a transaction with the participant's signature over its digest
     pub fn sign_transaction(
         &mut self,
         transaction_id: u64,
         participant_id: TraderId,
+        signature: &[u8],
     ) -> Result<(), MultiSigError> {
         // Check if the participant is part of the wallet
-        if !self.is_participant(&participant_id) {
-            return Err(MultiSigError::NotParticipant);
-        }
+        let participant = self
+            .participant(&participant_id)
+            .ok_or(MultiSigError::NotParticipant)?
+            .clone();
 
         // Check if the transaction exists and is pending
         let transaction = self
@@ -207,14 +407,53 @@ impl MultiSigWallet {
             return Err(MultiSigError::TransactionAlreadyExecuted);
         }
 
+        participant.verify(&transaction.digest(), signature)?;
+
         // Add the signature
-        transaction.add_signature(participant_id);
+        transaction.add_signature(participant_id, signature.to_vec());
+
+        Ok(())
+    }
+
+    /// Approve a transaction with a FROST aggregate signature over its digest
+    pub fn sign_transaction_threshold(
+        &mut self,
+        transaction_id: u64,
+        signature: &[u8],
+    ) -> Result<(), MultiSigError> {
+        let group_key = self
+            .threshold_key
+            .as_deref()
+            .ok_or(MultiSigError::ThresholdKeyMissing)
+            .and_then(parse_group_key)?;
+
+        let transaction = self
+            .pending_transactions
+            .get_mut(&transaction_id)
+            .ok_or(MultiSigError::TransactionNotFound)?;
+
+        if transaction.is_executed() {
+            return Err(MultiSigError::TransactionAlreadyExecuted);
+        }
+
+        verify_group_signature(&group_key, &transaction.digest(), signature)?;
+        transaction.threshold_signature = Some(signature.to_vec());
 
         Ok(())
     }
 
     /// Execute a transaction if it has enough signatures
     pub fn execute_transaction(&mut self, transaction_id: u64) -> Result<(), MultiSigError> {
+        self.execute_transaction_at(transaction_id, current_timestamp())
+    }
+
+    /// Execute a transaction at the given Unix time once it is approved, its
+    /// time-lock has elapsed and it fits the token's spending limit
+    pub fn execute_transaction_at(
+        &mut self,
+        transaction_id: u64,
+        now: u64,
+    ) -> Result<(), MultiSigError> {
         // Check if the transaction exists and is pending
         let mut transaction = self
             .pending_transactions
@@ -227,21 +466,15 @@ impl MultiSigWallet {
             return Err(MultiSigError::TransactionAlreadyExecuted);
         }
 
-        // Check if the transaction has enough signatures
-        if !transaction.is_ready_for_execution() {
+        if let Err(err) = self.check_executable(&transaction, now) {
             // Put it back as pending
             self.pending_transactions
                 .insert(transaction_id, transaction);
-            return Err(MultiSigError::InsufficientSignatures);
+            return Err(err);
         }
 
         // Mark the transaction as executed
-        transaction.executed_timestamp = Some(
-            std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
-        );
+        transaction.executed_timestamp = Some(now);
 
         // Move to executed transactions
         self.executed_transactions
@@ -250,6 +483,67 @@ impl MultiSigWallet {
         Ok(())
     }
 
+    /// Amount of a token executed within the limit window ending at `now`
+    pub fn spent_in_window(&self, token_id: &TokenId, now: u64) -> Quantity {
+        let Some(limit) = self.spending_limits.get(token_id) else {
+            return 0;
+        };
+        let window_start = now.saturating_sub(limit.period_seconds);
+        self.executed_transactions
+            .values()
+            .filter(|tx| &tx.token_id == token_id)
+            .filter(|tx| tx.executed_timestamp.is_some_and(|at| at > window_start))
+            .map(|tx| tx.amount)
+            .sum()
+    }
+
+    fn check_executable(
+        &self,
+        transaction: &MultiSigTransaction,
+        now: u64,
+    ) -> Result<(), MultiSigError> {
+        // Re-verify approvals so signatures written directly into the
+        // transaction cannot stand in for verified ones
+        let digest = transaction.digest();
+        let threshold_approved = match (&transaction.threshold_signature, &self.threshold_key) {
+            (Some(signature), Some(group_key)) => {
+                verify_group_signature(&parse_group_key(group_key)?, &digest, signature).is_ok()
+            }
+            _ => false,
+        };
+        let valid_signatures = transaction
+            .signatures
+            .iter()
+            .filter(|(participant_id, signature)| {
+                self.participant(participant_id)
+                    .is_some_and(|p| p.verify(&digest, signature).is_ok())
+            })
+            .count();
+        if !threshold_approved && valid_signatures < self.required_signatures {
+            return Err(MultiSigError::InsufficientSignatures);
+        }
+
+        if !transaction.is_unlocked(now) {
+            return Err(MultiSigError::TimeLocked {
+                executable_at: transaction.executable_at,
+            });
+        }
+
+        if let Some(limit) = self.spending_limits.get(&transaction.token_id) {
+            let remaining = limit
+                .amount
+                .saturating_sub(self.spent_in_window(&transaction.token_id, now));
+            if transaction.amount > remaining {
+                return Err(MultiSigError::SpendingLimitExceeded {
+                    token_id: transaction.token_id.clone(),
+                    remaining,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
     /// Get a pending transaction
     pub fn get_pending_transaction(&self, transaction_id: u64) -> Option<&MultiSigTransaction> {
         self.pending_transactions.get(&transaction_id)
@@ -384,24 +678,128 @@ pub enum MultiSigError {
     InsufficientSignatures,
     #[error("Invalid transaction data")]
     InvalidTransactionData,
+    #[error("Invalid public key for participant {0}")]
+    InvalidPublicKey(String),
+    #[error("Invalid signature from {0}")]
+    InvalidSignature(String),
+    #[error("Wallet has no threshold group key")]
+    ThresholdKeyMissing,
+    #[error("Transaction is time-locked until {executable_at}")]
+    TimeLocked { executable_at: u64 },
+    #[error("Spending limit for {token_id} exceeded, {remaining} remaining in the window")]
+    SpendingLimitExceeded {
+        token_id: TokenId,
+        remaining: Quantity,
+    },
+}
+
+/// SHA-256 over the domain separator and the length-prefixed transaction fields
+fn transaction_digest(
+    wallet_id: &str,
+    transaction_id: u64,
+    to_address: &str,
+    token_id: &str,
+    amount: Quantity,
+) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(DIGEST_DOMAIN);
+    for field in [
+        wallet_id.as_bytes(),
+        to_address.as_bytes(),
+        token_id.as_bytes(),
+    ] {
+        hasher.update((field.len() as u64).to_be_bytes());
+        hasher.update(field);
+    }
+    hasher.update(transaction_id.to_be_bytes());
+    hasher.update(amount.to_be_bytes());
+    hasher.finalize().into()
+}
+
+fn parse_group_key(group_key: &str) -> Result<ed25519_dalek::VerifyingKey, MultiSigError> {
+    let invalid = || MultiSigError::InvalidPublicKey("threshold group".to_string());
+    let bytes: [u8; 32] = decode_hex(group_key)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(invalid)?;
+    ed25519_dalek::VerifyingKey::from_bytes(&bytes).map_err(|_| invalid())
+}
+
+/// FROST(Ed25519, SHA-512) aggregates verify as ordinary Ed25519 signatures
+fn verify_group_signature(
+    group_key: &ed25519_dalek::VerifyingKey,
+    digest: &[u8; 32],
+    signature: &[u8],
+) -> Result<(), MultiSigError> {
+    let invalid = || MultiSigError::InvalidSignature("threshold group".to_string());
+    let signature = ed25519_dalek::Signature::from_slice(signature).map_err(|_| invalid())?;
+    group_key
+        .verify_strict(digest, &signature)
+        .map_err(|_| invalid())
+}
+
+fn current_timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    let value = value.trim();
+    if !value.len().is_multiple_of(2) || !value.is_ascii() {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&value[i..i + 2], 16).ok())
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    fn signer(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn test_participants(count: u8) -> (Vec<WalletParticipant>, Vec<SigningKey>) {
+        let keys: Vec<SigningKey> = (1..=count).map(signer).collect();
+        let participants = keys
+            .iter()
+            .enumerate()
+            .map(|(i, key)| {
+                WalletParticipant::ed25519(format!("participant{}", i + 1), &key.verifying_key())
+            })
+            .collect();
+        (participants, keys)
+    }
+
+    fn approve(
+        wallet: &mut MultiSigWallet,
+        transaction_id: u64,
+        participant_id: &str,
+        key: &SigningKey,
+    ) -> Result<(), MultiSigError> {
+        let digest = wallet
+            .get_pending_transaction(transaction_id)
+            .map(|tx| tx.digest())
+            .unwrap_or_default();
+        wallet.sign_transaction(
+            transaction_id,
+            participant_id.to_string(),
+            &key.sign(&digest).to_bytes(),
+        )
+    }
 
     #[test]
     fn test_multisig_wallet_creation() {
-        let participants = vec![
-            WalletParticipant {
-                id: "participant1".to_string(),
-                public_key: "pubkey1".to_string(),
-            },
-            WalletParticipant {
-                id: "participant2".to_string(),
-                public_key: "pubkey2".to_string(),
-            },
-        ];
+        let (participants, _) = test_participants(2);
 
         let wallet = MultiSigWallet::new("wallet1".to_string(), participants, 2).unwrap();
         assert_eq!(wallet.wallet_id, "wallet1");
@@ -447,16 +845,7 @@ mod tests {
 
     #[test]
     fn test_asset_management() {
-        let participants = vec![
-            WalletParticipant {
-                id: "participant1".to_string(),
-                public_key: "pubkey1".to_string(),
-            },
-            WalletParticipant {
-                id: "participant2".to_string(),
-                public_key: "pubkey2".to_string(),
-            },
-        ];
+        let (participants, _) = test_participants(2);
 
         let mut wallet = MultiSigWallet::new("wallet1".to_string(), participants, 2).unwrap();
 
@@ -481,16 +870,7 @@ mod tests {
 
     #[test]
     fn test_transaction_creation() {
-        let participants = vec![
-            WalletParticipant {
-                id: "participant1".to_string(),
-                public_key: "pubkey1".to_string(),
-            },
-            WalletParticipant {
-                id: "participant2".to_string(),
-                public_key: "pubkey2".to_string(),
-            },
-        ];
+        let (participants, _) = test_participants(2);
 
         let mut wallet = MultiSigWallet::new("wallet1".to_string(), participants, 2).unwrap();
 
@@ -519,20 +899,7 @@ mod tests {
 
     #[test]
     fn test_transaction_signing() {
-        let participants = vec![
-            WalletParticipant {
-                id: "participant1".to_string(),
-                public_key: "pubkey1".to_string(),
-            },
-            WalletParticipant {
-                id: "participant2".to_string(),
-                public_key: "pubkey2".to_string(),
-            },
-            WalletParticipant {
-                id: "participant3".to_string(),
-                public_key: "pubkey3".to_string(),
-            },
-        ];
+        let (participants, keys) = test_participants(3);
 
         let mut wallet = MultiSigWallet::new("wallet1".to_string(), participants, 2).unwrap();
 
@@ -550,9 +917,7 @@ mod tests {
         assert!(!transaction.is_ready_for_execution());
 
         // Sign the transaction with participant1
-        wallet
-            .sign_transaction(transaction_id, "participant1".to_string())
-            .unwrap();
+        approve(&mut wallet, transaction_id, "participant1", &keys[0]).unwrap();
 
         // Check that the signature was added
         let transaction = wallet.get_pending_transaction(transaction_id).unwrap();
@@ -561,9 +926,7 @@ mod tests {
         assert!(!transaction.is_ready_for_execution()); // Still need one more signature
 
         // Sign the transaction with participant2
-        wallet
-            .sign_transaction(transaction_id, "participant2".to_string())
-            .unwrap();
+        approve(&mut wallet, transaction_id, "participant2", &keys[1]).unwrap();
 
         // Check that the signature was added
         let transaction = wallet.get_pending_transaction(transaction_id).unwrap();
@@ -574,16 +937,7 @@ mod tests {
 
     #[test]
     fn test_transaction_execution() {
-        let participants = vec![
-            WalletParticipant {
-                id: "participant1".to_string(),
-                public_key: "pubkey1".to_string(),
-            },
-            WalletParticipant {
-                id: "participant2".to_string(),
-                public_key: "pubkey2".to_string(),
-            },
-        ];
+        let (participants, keys) = test_participants(2);
 
         let mut wallet = MultiSigWallet::new("wallet1".to_string(), participants, 2).unwrap();
 
@@ -596,12 +950,8 @@ mod tests {
             .unwrap();
 
         // Sign the transaction with both participants
-        wallet
-            .sign_transaction(transaction_id, "participant1".to_string())
-            .unwrap();
-        wallet
-            .sign_transaction(transaction_id, "participant2".to_string())
-            .unwrap();
+        approve(&mut wallet, transaction_id, "participant1", &keys[0]).unwrap();
+        approve(&mut wallet, transaction_id, "participant2", &keys[1]).unwrap();
 
         // Execute the transaction
         wallet.execute_transaction(transaction_id).unwrap();
@@ -618,16 +968,7 @@ mod tests {
 
     #[test]
     fn test_transaction_cancellation() {
-        let participants = vec![
-            WalletParticipant {
-                id: "participant1".to_string(),
-                public_key: "pubkey1".to_string(),
-            },
-            WalletParticipant {
-                id: "participant2".to_string(),
-                public_key: "pubkey2".to_string(),
-            },
-        ];
+        let (participants, _) = test_participants(2);
 
         let mut wallet = MultiSigWallet::new("wallet1".to_string(), participants, 2).unwrap();
 
@@ -658,16 +999,7 @@ mod tests {
         let mut manager = MultiSigWalletManager::new();
 
         // Create a wallet
-        let participants = vec![
-            WalletParticipant {
-                id: "participant1".to_string(),
-                public_key: "pubkey1".to_string(),
-            },
-            WalletParticipant {
-                id: "participant2".to_string(),
-                public_key: "pubkey2".to_string(),
-            },
-        ];
+        let (participants, _) = test_participants(2);
 
         manager
             .create_wallet("wallet1".to_string(), participants, 2)
@@ -693,16 +1025,7 @@ mod tests {
 
     #[test]
     fn test_participant_verification() {
-        let participants = vec![
-            WalletParticipant {
-                id: "participant1".to_string(),
-                public_key: "pubkey1".to_string(),
-            },
-            WalletParticipant {
-                id: "participant2".to_string(),
-                public_key: "pubkey2".to_string(),
-            },
-        ];
+        let (participants, _) = test_participants(2);
 
         let wallet = MultiSigWallet::new("wallet1".to_string(), participants, 2).unwrap();
 
@@ -714,21 +1037,12 @@ mod tests {
 
     #[test]
     fn test_invalid_transaction_signing() {
-        let participants = vec![
-            WalletParticipant {
-                id: "participant1".to_string(),
-                public_key: "pubkey1".to_string(),
-            },
-            WalletParticipant {
-                id: "participant2".to_string(),
-                public_key: "pubkey2".to_string(),
-            },
-        ];
+        let (participants, keys) = test_participants(2);
 
         let mut wallet = MultiSigWallet::new("wallet1".to_string(), participants, 2).unwrap();
 
         // Try to sign a non-existent transaction
-        let result = wallet.sign_transaction(999, "participant1".to_string());
+        let result = approve(&mut wallet, 999, "participant1", &keys[0]);
         assert!(result.is_err());
         assert!(matches!(
             result.unwrap_err(),
@@ -742,23 +1056,14 @@ mod tests {
             .unwrap();
 
         // Try to sign with a non-participant
-        let result = wallet.sign_transaction(transaction_id, "participant3".to_string());
+        let result = approve(&mut wallet, transaction_id, "participant3", &signer(3));
         assert!(result.is_err());
         assert!(matches!(result.unwrap_err(), MultiSigError::NotParticipant));
     }
 
     #[test]
     fn test_invalid_transaction_execution() {
-        let participants = vec![
-            WalletParticipant {
-                id: "participant1".to_string(),
-                public_key: "pubkey1".to_string(),
-            },
-            WalletParticipant {
-                id: "participant2".to_string(),
-                public_key: "pubkey2".to_string(),
-            },
-        ];
+        let (participants, keys) = test_participants(2);
 
         let mut wallet = MultiSigWallet::new("wallet1".to_string(), participants, 2).unwrap();
 
@@ -785,12 +1090,8 @@ mod tests {
         ));
 
         // Sign the transaction
-        wallet
-            .sign_transaction(transaction_id, "participant1".to_string())
-            .unwrap();
-        wallet
-            .sign_transaction(transaction_id, "participant2".to_string())
-            .unwrap();
+        approve(&mut wallet, transaction_id, "participant1", &keys[0]).unwrap();
+        approve(&mut wallet, transaction_id, "participant2", &keys[1]).unwrap();
 
         // Execute the transaction
         wallet.execute_transaction(transaction_id).unwrap();
@@ -803,4 +1104,182 @@ mod tests {
             MultiSigError::TransactionNotFound
         ));
     }
+
+    #[test]
+    fn test_signatures_are_verified() {
+        let ed_key = signer(1);
+        let ecdsa_key = k256::ecdsa::SigningKey::from_slice(&[7; 32]).unwrap();
+        let participants = vec![
+            WalletParticipant::ed25519("alice", &ed_key.verifying_key()),
+            WalletParticipant::secp256k1("bob", ecdsa_key.verifying_key()),
+        ];
+        assert_eq!(
+            participants[1].scheme().unwrap(),
+            SignatureScheme::Secp256k1
+        );
+
+        let mut wallet = MultiSigWallet::new("wallet1".to_string(), participants, 2).unwrap();
+        wallet.deposit("BTC".to_string(), 1000);
+        let first = wallet
+            .create_transaction("recipient1".to_string(), "BTC".to_string(), 100)
+            .unwrap();
+        let second = wallet
+            .create_transaction("recipient1".to_string(), "BTC".to_string(), 100)
+            .unwrap();
+
+        // Knowing a participant's ID is not enough: the key must sign this digest
+        assert!(matches!(
+            approve(&mut wallet, first, "alice", &signer(9)),
+            Err(MultiSigError::InvalidSignature(id)) if id == "alice"
+        ));
+        let replayed = ed_key.sign(&wallet.get_pending_transaction(second).unwrap().digest());
+        assert!(matches!(
+            wallet.sign_transaction(first, "alice".to_string(), &replayed.to_bytes()),
+            Err(MultiSigError::InvalidSignature(_))
+        ));
+
+        approve(&mut wallet, first, "alice", &ed_key).unwrap();
+        let digest = wallet.get_pending_transaction(first).unwrap().digest();
+        let signature: k256::ecdsa::Signature = {
+            use k256::ecdsa::signature::Signer as _;
+            ecdsa_key.sign(&digest)
+        };
+        wallet
+            .sign_transaction(first, "bob".to_string(), &signature.to_bytes())
+            .unwrap();
+        wallet.execute_transaction(first).unwrap();
+
+        // Signatures written around the wallet are checked again at execution
+        approve(&mut wallet, second, "alice", &ed_key).unwrap();
+        wallet
+            .pending_transactions
+            .get_mut(&second)
+            .unwrap()
+            .add_signature("bob".to_string(), vec![0; 64]);
+        assert!(matches!(
+            wallet.execute_transaction(second),
+            Err(MultiSigError::InsufficientSignatures)
+        ));
+
+        let invalid = WalletParticipant {
+            id: "carol".to_string(),
+            public_key: "pubkey3".to_string(),
+        };
+        assert!(matches!(
+            MultiSigWallet::new("wallet2".to_string(), vec![invalid], 1),
+            Err(MultiSigError::InvalidPublicKey(id)) if id == "carol"
+        ));
+    }
+
+    #[test]
+    fn test_time_lock_and_spending_limit() {
+        let (participants, keys) = test_participants(1);
+        let mut wallet = MultiSigWallet::new("wallet1".to_string(), participants, 1)
+            .unwrap()
+            .with_time_lock(3600)
+            .with_spending_limit(
+                "BTC".to_string(),
+                SpendingLimit {
+                    amount: 500,
+                    period_seconds: 86_400,
+                },
+            );
+        wallet.deposit("BTC".to_string(), 2000);
+
+        assert!(matches!(
+            wallet.create_transaction_at("recipient1".to_string(), "BTC".to_string(), 600, 0),
+            Err(MultiSigError::SpendingLimitExceeded { remaining: 500, .. })
+        ));
+        let first = wallet
+            .create_transaction_at("recipient1".to_string(), "BTC".to_string(), 400, 0)
+            .unwrap();
+        let second = wallet
+            .create_transaction_at("recipient1".to_string(), "BTC".to_string(), 200, 0)
+            .unwrap();
+        approve(&mut wallet, first, "participant1", &keys[0]).unwrap();
+        approve(&mut wallet, second, "participant1", &keys[0]).unwrap();
+
+        assert!(matches!(
+            wallet.execute_transaction_at(first, 3599),
+            Err(MultiSigError::TimeLocked {
+                executable_at: 3600
+            })
+        ));
+        wallet.execute_transaction_at(first, 3600).unwrap();
+        assert_eq!(wallet.spent_in_window(&"BTC".to_string(), 3600), 400);
+
+        assert!(matches!(
+            wallet.execute_transaction_at(second, 3600),
+            Err(MultiSigError::SpendingLimitExceeded { remaining: 100, .. })
+        ));
+        assert_eq!(wallet.pending_transaction_count(), 1);
+        wallet
+            .execute_transaction_at(second, 3600 + 86_400)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_frost_threshold_signature() {
+        use frost_ed25519 as frost;
+        use std::collections::BTreeMap;
+
+        let mut rng = rand::rngs::OsRng;
+        let (shares, group) =
+            frost::keys::generate_with_dealer(3, 2, frost::keys::IdentifierList::Default, &mut rng)
+                .unwrap();
+        let group_key = encode_hex(&group.verifying_key().serialize().unwrap());
+
+        let (participants, _) = test_participants(3);
+        let mut wallet = MultiSigWallet::new("wallet1".to_string(), participants, 2)
+            .unwrap()
+            .with_threshold_key(&group_key)
+            .unwrap();
+        wallet.deposit("BTC".to_string(), 1000);
+        let transaction_id = wallet
+            .create_transaction("recipient1".to_string(), "BTC".to_string(), 500)
+            .unwrap();
+        let digest = wallet
+            .get_pending_transaction(transaction_id)
+            .unwrap()
+            .digest();
+
+        // Two of the three share holders run the ceremony over the digest
+        let key_packages: Vec<_> = shares
+            .into_values()
+            .take(2)
+            .map(|share| frost::keys::KeyPackage::try_from(share).unwrap())
+            .collect();
+        let mut nonces = BTreeMap::new();
+        let mut commitments = BTreeMap::new();
+        for key_package in &key_packages {
+            let (nonce, commitment) = frost::round1::commit(key_package.signing_share(), &mut rng);
+            nonces.insert(*key_package.identifier(), nonce);
+            commitments.insert(*key_package.identifier(), commitment);
+        }
+        let signing_package = frost::SigningPackage::new(commitments, &digest);
+        let signature_shares: BTreeMap<_, _> = key_packages
+            .iter()
+            .map(|key_package| {
+                let id = *key_package.identifier();
+                let share = frost::round2::sign(&signing_package, &nonces[&id], key_package);
+                (id, share.unwrap())
+            })
+            .collect();
+        let aggregate = frost::aggregate(&signing_package, &signature_shares, &group).unwrap();
+        let aggregate = aggregate.serialize().unwrap();
+
+        assert!(matches!(
+            wallet.sign_transaction_threshold(transaction_id, &[0; 64]),
+            Err(MultiSigError::InvalidSignature(_))
+        ));
+        wallet
+            .sign_transaction_threshold(transaction_id, &aggregate)
+            .unwrap();
+        assert!(wallet
+            .get_pending_transaction(transaction_id)
+            .unwrap()
+            .is_ready_for_execution());
+        wallet.execute_transaction(transaction_id).unwrap();
+        assert_eq!(wallet.executed_transaction_count(), 1);
+    }
 }
@@ -0,0 +1,138 @@
+//! Merkle-digest anti-entropy for off-chain sync
+//!
+//! Entries are hashed into a fixed number of buckets by ID, and a
+//! [`MerkleTree`] is built over the bucket digests. Two replicas first compare
+//! roots; when they differ they compare bucket digests and exchange only the
+//! entries of buckets that diverge, so a sync between nearly identical replicas
+//! costs a digest rather than the whole data set.
+//!
+//! Implements the Priority 3 feature from DEX-OS-V2.csv:
+//! - Security,Security,Security,Gossip Protocol,Off-chain Sync,Medium
+
+use super::gossip_sync::SyncData;
+use crate::merkle_tree::MerkleTree;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+/// Number of key ranges the data set is split into
+pub const BUCKET_COUNT: usize = 64;
+
+/// Summary of a replica's data: the Merkle root over the bucket digests and
+/// the digests themselves
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleDigest {
+    /// Root of the Merkle tree over `buckets`
+    pub root: Vec<u8>,
+    /// Digest of the entries in each bucket, in bucket order
+    pub buckets: Vec<Vec<u8>>,
+}
+
+impl MerkleDigest {
+    /// Digest every entry of `data`
+    pub fn build(data: &HashMap<String, SyncData>) -> Self {
+        let mut grouped: Vec<Vec<&SyncData>> = vec![Vec::new(); BUCKET_COUNT];
+        for entry in data.values() {
+            grouped[bucket_of(&entry.id)].push(entry);
+        }
+
+        let buckets: Vec<Vec<u8>> = grouped
+            .into_iter()
+            .map(|mut entries| {
+                // Bucket digests must not depend on map iteration order
+                entries.sort_by(|a, b| a.id.cmp(&b.id));
+                let mut hasher = Sha256::new();
+                for entry in entries {
+                    hasher.update(entry_digest(entry));
+                }
+                hasher.finalize().to_vec()
+            })
+            .collect();
+        let root = MerkleTree::from_data(&buckets)
+            .root_hash()
+            .unwrap_or_default();
+        Self { root, buckets }
+    }
+
+    /// Buckets whose content differs from `other`'s
+    pub fn divergent_buckets(&self, other: &MerkleDigest) -> Vec<usize> {
+        if self.root == other.root {
+            return Vec::new();
+        }
+        (0..BUCKET_COUNT)
+            .filter(|&i| self.buckets.get(i) != other.buckets.get(i))
+            .collect()
+    }
+}
+
+/// Bucket holding the entry with ID `id`
+pub fn bucket_of(id: &str) -> usize {
+    let hash = Sha256::digest(id.as_bytes());
+    u16::from_be_bytes([hash[0], hash[1]]) as usize % BUCKET_COUNT
+}
+
+/// Entries of `data` that fall into any of `buckets`
+pub fn entries_in_buckets(data: &HashMap<String, SyncData>, buckets: &[usize]) -> Vec<SyncData> {
+    let mut entries: Vec<SyncData> = data
+        .values()
+        .filter(|entry| buckets.contains(&bucket_of(&entry.id)))
+        .cloned()
+        .collect();
+    entries.sort_by(|a, b| a.id.cmp(&b.id));
+    entries
+}
+
+/// Hash of everything that distinguishes one version of an entry from another
+fn entry_digest(entry: &SyncData) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    for field in [
+        entry.id.as_bytes(),
+        entry.origin.as_bytes(),
+        entry.data_type.as_bytes(),
+        entry.version.node_id.as_bytes(),
+        &entry.payload,
+    ] {
+        hasher.update((field.len() as u64).to_be_bytes());
+        hasher.update(field);
+    }
+    hasher.update(entry.timestamp.to_be_bytes());
+    hasher.update(entry.version.wall_ms.to_be_bytes());
+    hasher.update(entry.version.logical.to_be_bytes());
+    hasher.finalize().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::crdt::HlcTimestamp;
+
+    fn entry(id: &str, payload: &[u8]) -> SyncData {
+        SyncData {
+            id: id.to_string(),
+            payload: payload.to_vec(),
+            timestamp: 1,
+            origin: "node-0".to_string(),
+            data_type: "test".to_string(),
+            version: HlcTimestamp::default(),
+            signature: None,
+        }
+    }
+
+    #[test]
+    fn test_only_changed_buckets_diverge() {
+        let mut left: HashMap<String, SyncData> = (0..100)
+            .map(|i| (format!("item-{}", i), entry(&format!("item-{}", i), b"v1")))
+            .collect();
+        let right = left.clone();
+        assert!(MerkleDigest::build(&left)
+            .divergent_buckets(&MerkleDigest::build(&right))
+            .is_empty());
+
+        left.insert("item-7".to_string(), entry("item-7", b"v2"));
+        let divergent = MerkleDigest::build(&left).divergent_buckets(&MerkleDigest::build(&right));
+        assert_eq!(divergent, vec![bucket_of("item-7")]);
+        let exchanged = entries_in_buckets(&left, &divergent);
+        assert!(exchanged.iter().any(|e| e.id == "item-7"));
+        assert!(exchanged.len() < left.len() / 4);
+    }
+}
@@ -0,0 +1,1025 @@
+//! Node authentication and encrypted sessions for off-chain sync
+//!
+//! Every node holds an ed25519 identity key, generated through
+//! [`SecurityManager::generate_key_pair`]. Peers are trusted either through an
+//! allowlist of node ID to public key, or through a valid certificate in a
+//! [`CertificateManager`] whose ID is the node ID and whose data is the node's
+//! public key. Once the manager has trust anchors configured, the certificate
+//! must also chain up to one of them, with no revocation on the way.
+//!
+//! Messages travel as [`SignedMessage`]s, signed over the sender, a strictly
+//! increasing sequence number and the payload, so they cannot be forged,
+//! altered or replayed. Content that is relayed on, such as synced data,
+//! carries an [`OriginSignature`] by the node it comes from. Point-to-point
+//! traffic can additionally be encrypted: a [`HandshakeInit`]/
+//! [`HandshakeAccept`] exchange of signed ephemeral X25519 keys yields
+//! per-direction ChaCha20-Poly1305 keys, and [`EncryptedFrame`]s carry the
+//! signed messages under those keys. Openings name their responder and are
+//! timestamped, so that a replayed one cannot reset a live session. Every
+//! rejected message is counted in [`AuthStats`].
+//!
+//! Implements the Priority 3 feature from DEX-OS-V2.csv:
+//! - Security,Security,Security,Gossip Protocol,Off-chain Sync,Medium
+
+use crate::security::{CertificateManager, SecurityManager};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use hkdf::Hkdf;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use x25519_dalek::{EphemeralSecret, PublicKey};
+
+/// Domain separation for signed messages
+const MESSAGE_DOMAIN: &[u8] = b"dexos-gossip-sync-message-v1";
+/// Domain separation for data signed by the node it originates from
+const ORIGIN_DOMAIN: &[u8] = b"dexos-gossip-sync-origin-v1";
+/// Domain separation for the handshake opening
+const INIT_DOMAIN: &[u8] = b"dexos-gossip-sync-init-v1";
+/// Domain separation for the handshake answer
+const ACCEPT_DOMAIN: &[u8] = b"dexos-gossip-sync-accept-v1";
+/// How far a handshake opening's timestamp may be from our clock
+const HANDSHAKE_WINDOW_MICROS: u64 = 30_000_000;
+
+/// A node's long-term ed25519 identity
+pub struct NodeIdentity {
+    /// Node ID the key belongs to
+    node_id: String,
+    /// Signing key
+    signing_key: SigningKey,
+}
+
+impl NodeIdentity {
+    /// Generate a fresh identity for `node_id`
+    pub fn generate(node_id: &str) -> Self {
+        let (private_key, _) = SecurityManager::generate_key_pair();
+        Self::from_secret(node_id, &private_key).expect("generated keys are 32 bytes")
+    }
+
+    /// Restore an identity from its 32-byte secret key
+    pub fn from_secret(node_id: &str, secret: &[u8]) -> Result<Self, AuthError> {
+        let secret: [u8; 32] = secret
+            .try_into()
+            .map_err(|_| AuthError::Malformed("identity key must be 32 bytes".to_string()))?;
+        Ok(Self {
+            node_id: node_id.to_string(),
+            signing_key: SigningKey::from_bytes(&secret),
+        })
+    }
+
+    /// Node ID the key belongs to
+    pub fn node_id(&self) -> &str {
+        &self.node_id
+    }
+
+    /// Public key peers must trust
+    pub fn public_key(&self) -> Vec<u8> {
+        self.signing_key.verifying_key().to_bytes().to_vec()
+    }
+
+    fn sign(&self, message: &[u8]) -> Vec<u8> {
+        self.signing_key.sign(message).to_bytes().to_vec()
+    }
+}
+
+/// Which peers are allowed to talk to this node
+#[derive(Debug, Clone, Default)]
+pub struct TrustStore {
+    /// Public key accepted for each node ID
+    allowlist: HashMap<String, Vec<u8>>,
+    /// Certificates binding node IDs to public keys
+    certificates: Option<CertificateManager>,
+}
+
+impl TrustStore {
+    /// Trust store accepting nobody
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept `node_id` when it presents `public_key`
+    pub fn allow(&mut self, node_id: &str, public_key: Vec<u8>) {
+        self.allowlist.insert(node_id.to_string(), public_key);
+    }
+
+    /// Stop accepting `node_id` through the allowlist
+    pub fn disallow(&mut self, node_id: &str) {
+        self.allowlist.remove(node_id);
+    }
+
+    /// Also accept nodes holding a valid certificate whose ID is the node ID
+    /// and whose data is the node's public key. If `certificates` has trust
+    /// anchors, the certificate chain must verify up to one of them.
+    pub fn with_certificates(mut self, certificates: CertificateManager) -> Self {
+        self.certificates = Some(certificates);
+        self
+    }
+
+    /// Whether `node_id` may use `public_key`
+    pub fn is_trusted(&self, node_id: &str, public_key: &[u8]) -> bool {
+        if self
+            .allowlist
+            .get(node_id)
+            .is_some_and(|key| key == public_key)
+        {
+            return true;
+        }
+        self.certificates.as_ref().is_some_and(|certificates| {
+            let valid = if certificates.has_trust_anchors() {
+                certificates
+                    .verify_chain(node_id, now_micros() / 1_000_000)
+                    .is_ok()
+            } else {
+                certificates.is_certificate_valid(node_id)
+            };
+            valid
+                && certificates
+                    .get_certificate(node_id)
+                    .is_some_and(|certificate| certificate.data == public_key)
+        })
+    }
+}
+
+/// A payload signed by its sender
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedMessage {
+    /// Node ID of the sender
+    pub sender: String,
+    /// Sender's public key
+    pub public_key: Vec<u8>,
+    /// Strictly increasing per sender, to reject replays
+    pub sequence: u64,
+    /// Serialized message
+    pub payload: Vec<u8>,
+    /// ed25519 signature over sender, sequence and payload
+    pub signature: Vec<u8>,
+}
+
+impl SignedMessage {
+    fn signing_bytes(sender: &str, sequence: u64, payload: &[u8]) -> Vec<u8> {
+        let mut bytes =
+            Vec::with_capacity(MESSAGE_DOMAIN.len() + sender.len() + payload.len() + 16);
+        bytes.extend_from_slice(MESSAGE_DOMAIN);
+        bytes.extend_from_slice(&(sender.len() as u64).to_be_bytes());
+        bytes.extend_from_slice(sender.as_bytes());
+        bytes.extend_from_slice(&sequence.to_be_bytes());
+        bytes.extend_from_slice(payload);
+        bytes
+    }
+}
+
+/// Detached signature by the node some relayed content originates from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OriginSignature {
+    /// Origin's public key
+    pub public_key: Vec<u8>,
+    /// ed25519 signature over the content
+    pub signature: Vec<u8>,
+}
+
+/// First handshake message, carrying the initiator's ephemeral key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HandshakeInit {
+    /// Node ID of the initiator
+    pub sender: String,
+    /// Node ID of the intended responder
+    pub recipient: String,
+    /// Initiator's identity key
+    pub public_key: Vec<u8>,
+    /// Initiator's ephemeral X25519 key
+    pub ephemeral: [u8; 32],
+    /// Initiator's clock in microseconds, strictly increasing per initiator
+    /// so that a replayed opening cannot reset a live session
+    pub timestamp: u64,
+    /// Signature over both node IDs, the timestamp and the ephemeral key
+    pub signature: Vec<u8>,
+}
+
+/// Handshake answer, carrying the responder's ephemeral key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HandshakeAccept {
+    /// Node ID of the responder
+    pub sender: String,
+    /// Responder's identity key
+    pub public_key: Vec<u8>,
+    /// Responder's ephemeral X25519 key
+    pub ephemeral: [u8; 32],
+    /// Signature over both node IDs and both ephemeral keys
+    pub signature: Vec<u8>,
+}
+
+/// A signed message encrypted under a session key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptedFrame {
+    /// Node ID of the sender, used to pick the session
+    pub sender: String,
+    /// Strictly increasing per session; also the AEAD nonce
+    pub counter: u64,
+    /// ChaCha20-Poly1305 ciphertext of a serialized [`SignedMessage`]
+    pub ciphertext: Vec<u8>,
+}
+
+/// Counts of accepted and rejected messages
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuthStats {
+    /// Messages that passed every check
+    pub accepted: u64,
+    /// Rejected: no authentication at all
+    pub unauthenticated: u64,
+    /// Rejected: sender not trusted with the presented key
+    pub untrusted: u64,
+    /// Rejected: signature did not verify
+    pub bad_signature: u64,
+    /// Rejected: sequence or counter already seen
+    pub replayed: u64,
+    /// Rejected: message claimed to come from another node
+    pub spoofed: u64,
+    /// Rejected: no session, or ciphertext failed authentication
+    pub undecryptable: u64,
+    /// Rejected: could not be parsed
+    pub malformed: u64,
+}
+
+impl AuthStats {
+    /// Total number of rejected messages
+    pub fn rejected(&self) -> u64 {
+        self.unauthenticated
+            + self.untrusted
+            + self.bad_signature
+            + self.replayed
+            + self.spoofed
+            + self.undecryptable
+            + self.malformed
+    }
+}
+
+/// Keys and counters of an established session
+struct Session {
+    /// Cipher for frames we send
+    send: ChaCha20Poly1305,
+    /// Cipher for frames we receive
+    receive: ChaCha20Poly1305,
+    /// Counter of the next frame we send
+    next_send: u64,
+    /// Highest counter received so far
+    last_received: Option<u64>,
+}
+
+/// Mutable state behind the authenticator
+#[derive(Default)]
+struct AuthState {
+    /// Sequence number of our last signed message
+    last_sequence: u64,
+    /// Highest sequence number seen per sender
+    seen_sequences: HashMap<String, u64>,
+    /// Timestamp of the latest handshake accepted per initiator
+    seen_inits: HashMap<String, u64>,
+    /// Ephemeral secrets of handshakes we started, by peer
+    pending: HashMap<String, (EphemeralSecret, [u8; 32])>,
+    /// Established sessions by peer
+    sessions: HashMap<String, Session>,
+    /// Accepted and rejected message counts
+    stats: AuthStats,
+}
+
+/// Signs, verifies, encrypts and decrypts messages for one node
+pub struct GossipAuthenticator {
+    /// Our identity
+    identity: NodeIdentity,
+    /// Peers we accept
+    trust: Mutex<TrustStore>,
+    /// Sequence numbers, sessions and statistics
+    state: Mutex<AuthState>,
+}
+
+impl GossipAuthenticator {
+    /// Create an authenticator for `identity` accepting the peers in `trust`
+    pub fn new(identity: NodeIdentity, trust: TrustStore) -> Self {
+        Self {
+            identity,
+            trust: Mutex::new(trust),
+            state: Mutex::new(AuthState::default()),
+        }
+    }
+
+    /// Our node ID
+    pub fn node_id(&self) -> &str {
+        self.identity.node_id()
+    }
+
+    /// Our public key
+    pub fn public_key(&self) -> Vec<u8> {
+        self.identity.public_key()
+    }
+
+    /// Replace the set of trusted peers
+    pub fn set_trust(&self, trust: TrustStore) {
+        *self.trust.lock().unwrap() = trust;
+    }
+
+    /// Accepted and rejected message counts
+    pub fn stats(&self) -> AuthStats {
+        self.state.lock().unwrap().stats.clone()
+    }
+
+    /// Whether an encrypted session with `peer` is established
+    pub fn has_session(&self, peer: &str) -> bool {
+        self.state.lock().unwrap().sessions.contains_key(peer)
+    }
+
+    /// Sign `payload`. Sequence numbers start from the current time in
+    /// microseconds so they keep increasing across restarts.
+    pub fn sign(&self, payload: Vec<u8>) -> SignedMessage {
+        let sequence = self.next_sequence();
+        let sender = self.identity.node_id().to_string();
+        let signature = self
+            .identity
+            .sign(&SignedMessage::signing_bytes(&sender, sequence, &payload));
+        SignedMessage {
+            sender,
+            public_key: self.identity.public_key(),
+            sequence,
+            payload,
+            signature,
+        }
+    }
+
+    fn next_sequence(&self) -> u64 {
+        let mut state = self.state.lock().unwrap();
+        state.last_sequence = (state.last_sequence + 1).max(now_micros());
+        state.last_sequence
+    }
+
+    /// Check a signed message and hand its payload to `accept`, which may
+    /// reject it on grounds only the caller can judge
+    pub fn verify<T>(
+        &self,
+        message: &SignedMessage,
+        accept: impl FnOnce(&[u8]) -> Result<T, AuthError>,
+    ) -> Result<T, AuthError> {
+        let result = self
+            .check_signed(message)
+            .and_then(|()| accept(&message.payload));
+        self.record(&result);
+        result
+    }
+
+    fn check_signed(&self, message: &SignedMessage) -> Result<(), AuthError> {
+        self.check_identity(&message.sender, &message.public_key)?;
+        verify_signature(
+            &message.public_key,
+            &SignedMessage::signing_bytes(&message.sender, message.sequence, &message.payload),
+            &message.signature,
+        )?;
+
+        let mut state = self.state.lock().unwrap();
+        let last = state
+            .seen_sequences
+            .entry(message.sender.clone())
+            .or_insert(0);
+        if message.sequence <= *last {
+            return Err(AuthError::Replayed(message.sender.clone()));
+        }
+        *last = message.sequence;
+        Ok(())
+    }
+
+    /// Sign `content` as its origin, so that peers relaying it cannot alter
+    /// it or attribute it to another node
+    pub fn sign_origin(&self, content: &[u8]) -> OriginSignature {
+        OriginSignature {
+            public_key: self.identity.public_key(),
+            signature: self.identity.sign(&origin_bytes(content)),
+        }
+    }
+
+    /// Check that `origin` signed `content`, whichever peer passed it on
+    pub fn verify_origin(
+        &self,
+        origin: &str,
+        content: &[u8],
+        signature: &OriginSignature,
+    ) -> Result<(), AuthError> {
+        if origin == self.identity.node_id() {
+            if signature.public_key != self.identity.public_key() {
+                return Err(AuthError::Spoofed(origin.to_string()));
+            }
+        } else {
+            self.check_identity(origin, &signature.public_key)?;
+        }
+        verify_signature(
+            &signature.public_key,
+            &origin_bytes(content),
+            &signature.signature,
+        )
+    }
+
+    /// Count a message rejected before it reached the authenticator
+    pub fn record_rejection(&self, error: &AuthError) {
+        self.record::<()>(&Err(error.clone()));
+    }
+
+    /// Start a handshake with `peer`
+    pub fn initiate_handshake(&self, peer: &str) -> HandshakeInit {
+        let secret = EphemeralSecret::random_from_rng(OsRng);
+        let ephemeral = PublicKey::from(&secret).to_bytes();
+        let sender = self.identity.node_id().to_string();
+        let timestamp = self.next_sequence();
+        let signature = self
+            .identity
+            .sign(&init_transcript(&sender, peer, timestamp, &ephemeral));
+        self.state
+            .lock()
+            .unwrap()
+            .pending
+            .insert(peer.to_string(), (secret, ephemeral));
+        HandshakeInit {
+            sender,
+            recipient: peer.to_string(),
+            public_key: self.identity.public_key(),
+            ephemeral,
+            timestamp,
+            signature,
+        }
+    }
+
+    /// Answer a handshake, establishing our side of the session. Openings
+    /// addressed to another node are refused. Openings outside
+    /// [`HANDSHAKE_WINDOW_MICROS`] of our clock, or not newer than the last
+    /// one accepted from the initiator, are rejected as replays. Either way
+    /// any existing session stays in place.
+    pub fn accept_handshake(&self, init: &HandshakeInit) -> Result<HandshakeAccept, AuthError> {
+        let result = self.check_init(init);
+        self.record(&result);
+        result
+    }
+
+    fn check_init(&self, init: &HandshakeInit) -> Result<HandshakeAccept, AuthError> {
+        self.check_identity(&init.sender, &init.public_key)?;
+        verify_signature(
+            &init.public_key,
+            &init_transcript(
+                &init.sender,
+                &init.recipient,
+                init.timestamp,
+                &init.ephemeral,
+            ),
+            &init.signature,
+        )?;
+        if init.recipient != self.identity.node_id() {
+            return Err(AuthError::HandshakeFailed(format!(
+                "opening is addressed to {}",
+                init.recipient
+            )));
+        }
+        if now_micros().abs_diff(init.timestamp) > HANDSHAKE_WINDOW_MICROS {
+            return Err(AuthError::Replayed(init.sender.clone()));
+        }
+
+        let secret = EphemeralSecret::random_from_rng(OsRng);
+        let ephemeral = PublicKey::from(&secret).to_bytes();
+        let sender = self.identity.node_id().to_string();
+        let transcript = accept_transcript(&init.sender, &sender, &init.ephemeral, &ephemeral);
+        let shared = secret.diffie_hellman(&PublicKey::from(init.ephemeral));
+        if !shared.was_contributory() {
+            return Err(AuthError::HandshakeFailed(
+                "low-order ephemeral key".to_string(),
+            ));
+        }
+        let (to_responder, to_initiator) = derive_keys(shared.as_bytes(), &transcript);
+        let mut state = self.state.lock().unwrap();
+        let last = state.seen_inits.entry(init.sender.clone()).or_insert(0);
+        if init.timestamp <= *last {
+            return Err(AuthError::Replayed(init.sender.clone()));
+        }
+        *last = init.timestamp;
+        state.sessions.insert(
+            init.sender.clone(),
+            Session::new(to_initiator, to_responder),
+        );
+        drop(state);
+
+        Ok(HandshakeAccept {
+            sender,
+            public_key: self.identity.public_key(),
+            ephemeral,
+            signature: self.identity.sign(&transcript),
+        })
+    }
+
+    /// Complete a handshake we started, establishing the session
+    pub fn complete_handshake(&self, accept: &HandshakeAccept) -> Result<(), AuthError> {
+        let result = self.check_accept(accept);
+        self.record(&result);
+        result
+    }
+
+    fn check_accept(&self, accept: &HandshakeAccept) -> Result<(), AuthError> {
+        self.check_identity(&accept.sender, &accept.public_key)?;
+        let mut state = self.state.lock().unwrap();
+        let (_, ours) = state
+            .pending
+            .get(&accept.sender)
+            .ok_or_else(|| AuthError::HandshakeFailed("no handshake pending".to_string()))?;
+        let transcript = accept_transcript(
+            self.identity.node_id(),
+            &accept.sender,
+            ours,
+            &accept.ephemeral,
+        );
+        // A forged answer must not abort the handshake in progress
+        verify_signature(&accept.public_key, &transcript, &accept.signature)?;
+        let (secret, _) = state
+            .pending
+            .remove(&accept.sender)
+            .expect("pending handshake was just found");
+
+        let shared = secret.diffie_hellman(&PublicKey::from(accept.ephemeral));
+        if !shared.was_contributory() {
+            return Err(AuthError::HandshakeFailed(
+                "low-order ephemeral key".to_string(),
+            ));
+        }
+        let (to_responder, to_initiator) = derive_keys(shared.as_bytes(), &transcript);
+        state.sessions.insert(
+            accept.sender.clone(),
+            Session::new(to_responder, to_initiator),
+        );
+        Ok(())
+    }
+
+    /// Encrypt a signed message for `peer`
+    pub fn seal(&self, peer: &str, message: &SignedMessage) -> Result<EncryptedFrame, AuthError> {
+        let plaintext =
+            serde_json::to_vec(message).map_err(|e| AuthError::Malformed(e.to_string()))?;
+        let sender = self.identity.node_id().to_string();
+        let mut state = self.state.lock().unwrap();
+        let session = state
+            .sessions
+            .get_mut(peer)
+            .ok_or_else(|| AuthError::NoSession(peer.to_string()))?;
+        let counter = session.next_send;
+        session.next_send += 1;
+        let ciphertext = session
+            .send
+            .encrypt(
+                &nonce(counter),
+                Payload {
+                    msg: &plaintext,
+                    aad: sender.as_bytes(),
+                },
+            )
+            .map_err(|_| AuthError::Undecryptable(peer.to_string()))?;
+        Ok(EncryptedFrame {
+            sender,
+            counter,
+            ciphertext,
+        })
+    }
+
+    /// Decrypt a frame and check the signed message inside it as
+    /// [`Self::verify`] does
+    pub fn open<T>(
+        &self,
+        frame: &EncryptedFrame,
+        accept: impl FnOnce(&[u8]) -> Result<T, AuthError>,
+    ) -> Result<T, AuthError> {
+        let message = self.decrypt(frame).and_then(|message| {
+            if message.sender == frame.sender {
+                Ok(message)
+            } else {
+                Err(AuthError::Spoofed(message.sender))
+            }
+        });
+        match message {
+            Ok(message) => self.verify(&message, accept),
+            Err(error) => {
+                self.record_rejection(&error);
+                Err(error)
+            }
+        }
+    }
+
+    fn decrypt(&self, frame: &EncryptedFrame) -> Result<SignedMessage, AuthError> {
+        let mut state = self.state.lock().unwrap();
+        let session = state
+            .sessions
+            .get_mut(&frame.sender)
+            .ok_or_else(|| AuthError::NoSession(frame.sender.clone()))?;
+        if session
+            .last_received
+            .is_some_and(|last| frame.counter <= last)
+        {
+            return Err(AuthError::Replayed(frame.sender.clone()));
+        }
+        let plaintext = session
+            .receive
+            .decrypt(
+                &nonce(frame.counter),
+                Payload {
+                    msg: &frame.ciphertext,
+                    aad: frame.sender.as_bytes(),
+                },
+            )
+            .map_err(|_| AuthError::Undecryptable(frame.sender.clone()))?;
+        session.last_received = Some(frame.counter);
+        serde_json::from_slice(&plaintext).map_err(|e| AuthError::Malformed(e.to_string()))
+    }
+
+    fn check_identity(&self, node_id: &str, public_key: &[u8]) -> Result<(), AuthError> {
+        if self.trust.lock().unwrap().is_trusted(node_id, public_key) {
+            Ok(())
+        } else {
+            Err(AuthError::Untrusted(node_id.to_string()))
+        }
+    }
+
+    fn record<T>(&self, result: &Result<T, AuthError>) {
+        let mut state = self.state.lock().unwrap();
+        let stats = &mut state.stats;
+        let counter = match result {
+            Ok(_) => &mut stats.accepted,
+            Err(AuthError::Unauthenticated) => &mut stats.unauthenticated,
+            Err(AuthError::Untrusted(_)) => &mut stats.untrusted,
+            Err(AuthError::InvalidSignature) => &mut stats.bad_signature,
+            Err(AuthError::Replayed(_)) => &mut stats.replayed,
+            Err(AuthError::Spoofed(_)) => &mut stats.spoofed,
+            Err(AuthError::NoSession(_)) | Err(AuthError::Undecryptable(_)) => {
+                &mut stats.undecryptable
+            }
+            Err(AuthError::Malformed(_)) | Err(AuthError::HandshakeFailed(_)) => {
+                &mut stats.malformed
+            }
+        };
+        *counter += 1;
+    }
+}
+
+impl Session {
+    fn new(send: [u8; 32], receive: [u8; 32]) -> Self {
+        Self {
+            send: ChaCha20Poly1305::new(&Key::from(send)),
+            receive: ChaCha20Poly1305::new(&Key::from(receive)),
+            next_send: 0,
+            last_received: None,
+        }
+    }
+}
+
+fn origin_bytes(content: &[u8]) -> Vec<u8> {
+    let mut bytes = ORIGIN_DOMAIN.to_vec();
+    bytes.extend_from_slice(content);
+    bytes
+}
+
+fn init_transcript(
+    initiator: &str,
+    responder: &str,
+    timestamp: u64,
+    ephemeral: &[u8; 32],
+) -> Vec<u8> {
+    let mut bytes = INIT_DOMAIN.to_vec();
+    for id in [initiator, responder] {
+        bytes.extend_from_slice(&(id.len() as u64).to_be_bytes());
+        bytes.extend_from_slice(id.as_bytes());
+    }
+    bytes.extend_from_slice(&timestamp.to_be_bytes());
+    bytes.extend_from_slice(ephemeral);
+    bytes
+}
+
+fn accept_transcript(
+    initiator: &str,
+    responder: &str,
+    initiator_ephemeral: &[u8; 32],
+    responder_ephemeral: &[u8; 32],
+) -> Vec<u8> {
+    let mut bytes = ACCEPT_DOMAIN.to_vec();
+    for id in [initiator, responder] {
+        bytes.extend_from_slice(&(id.len() as u64).to_be_bytes());
+        bytes.extend_from_slice(id.as_bytes());
+    }
+    bytes.extend_from_slice(initiator_ephemeral);
+    bytes.extend_from_slice(responder_ephemeral);
+    bytes
+}
+
+/// Keys for the initiator-to-responder and responder-to-initiator directions
+fn derive_keys(shared: &[u8; 32], transcript: &[u8]) -> ([u8; 32], [u8; 32]) {
+    let hkdf = Hkdf::<Sha256>::new(Some(&Sha256::digest(transcript)), shared);
+    let mut to_responder = [0u8; 32];
+    let mut to_initiator = [0u8; 32];
+    hkdf.expand(b"initiator->responder", &mut to_responder)
+        .expect("32 bytes is a valid HKDF output length");
+    hkdf.expand(b"responder->initiator", &mut to_initiator)
+        .expect("32 bytes is a valid HKDF output length");
+    (to_responder, to_initiator)
+}
+
+fn nonce(counter: u64) -> Nonce {
+    let mut bytes = [0u8; 12];
+    bytes[4..].copy_from_slice(&counter.to_be_bytes());
+    Nonce::from(bytes)
+}
+
+fn verify_signature(public_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), AuthError> {
+    let public_key: [u8; 32] = public_key
+        .try_into()
+        .map_err(|_| AuthError::InvalidSignature)?;
+    let signature: [u8; 64] = signature
+        .try_into()
+        .map_err(|_| AuthError::InvalidSignature)?;
+    VerifyingKey::from_bytes(&public_key)
+        .map_err(|_| AuthError::InvalidSignature)?
+        .verify(message, &Signature::from_bytes(&signature))
+        .map_err(|_| AuthError::InvalidSignature)
+}
+
+fn now_micros() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_micros() as u64
+}
+
+/// Reasons a message is rejected
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum AuthError {
+    #[error("Message is not authenticated")]
+    Unauthenticated,
+    #[error("Node {0} is not trusted with the presented key")]
+    Untrusted(String),
+    #[error("Invalid signature")]
+    InvalidSignature,
+    #[error("Replayed message from {0}")]
+    Replayed(String),
+    #[error("Message claims to come from {0}")]
+    Spoofed(String),
+    #[error("No session with {0}")]
+    NoSession(String),
+    #[error("Frame from {0} failed decryption")]
+    Undecryptable(String),
+    #[error("Malformed message: {0}")]
+    Malformed(String),
+    #[error("Handshake failed: {0}")]
+    HandshakeFailed(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::{Certificate, RevocationList, RevocationReason, RevokedCertificate};
+
+    fn pair() -> (GossipAuthenticator, GossipAuthenticator) {
+        let alice = NodeIdentity::generate("alice");
+        let bob = NodeIdentity::generate("bob");
+        let mut alice_trust = TrustStore::new();
+        alice_trust.allow("bob", bob.public_key());
+        let mut bob_trust = TrustStore::new();
+        bob_trust.allow("alice", alice.public_key());
+        (
+            GossipAuthenticator::new(alice, alice_trust),
+            GossipAuthenticator::new(bob, bob_trust),
+        )
+    }
+
+    fn payload(bytes: &[u8]) -> Result<Vec<u8>, AuthError> {
+        Ok(bytes.to_vec())
+    }
+
+    #[test]
+    fn test_signed_messages_reject_tampering_replay_and_strangers() {
+        let (alice, bob) = pair();
+        let message = alice.sign(b"hello".to_vec());
+        assert_eq!(bob.verify(&message, payload).unwrap(), b"hello");
+        assert_eq!(
+            bob.verify(&message, payload),
+            Err(AuthError::Replayed("alice".to_string()))
+        );
+
+        let mut tampered = alice.sign(b"hello".to_vec());
+        tampered.payload = b"bye".to_vec();
+        assert_eq!(
+            bob.verify(&tampered, payload),
+            Err(AuthError::InvalidSignature)
+        );
+
+        let mallory = GossipAuthenticator::new(NodeIdentity::generate("alice"), TrustStore::new());
+        assert_eq!(
+            bob.verify(&mallory.sign(b"hello".to_vec()), payload),
+            Err(AuthError::Untrusted("alice".to_string()))
+        );
+
+        let stats = bob.stats();
+        assert_eq!(stats.accepted, 1);
+        assert_eq!(stats.rejected(), 3);
+    }
+
+    #[test]
+    fn test_certificates_establish_trust() {
+        let node = NodeIdentity::generate("carol");
+        let mut certificates = CertificateManager::new();
+        certificates
+            .add_certificate(Certificate {
+                id: "carol".to_string(),
+                data: node.public_key(),
+                issuer: "dexos-ca".to_string(),
+                valid_from: 0,
+                valid_to: u64::MAX,
+                signature: vec![],
+                revoked: false,
+            })
+            .unwrap();
+        let mut trust = TrustStore::new().with_certificates(certificates.clone());
+        assert!(trust.is_trusted("carol", &node.public_key()));
+        assert!(!trust.is_trusted("carol", &NodeIdentity::generate("carol").public_key()));
+
+        certificates.revoke_certificate("carol").unwrap();
+        trust = trust.with_certificates(certificates);
+        assert!(!trust.is_trusted("carol", &node.public_key()));
+    }
+
+    #[test]
+    fn test_certificate_chains_establish_trust() {
+        let (ca_secret, _) = SecurityManager::generate_key_pair();
+        let node = NodeIdentity::generate("dave");
+        let mut certificates = CertificateManager::new();
+        certificates
+            .add_trust_anchor(
+                Certificate::self_signed("dexos-ca", &ca_secret, 0, u64::MAX).unwrap(),
+            )
+            .unwrap();
+        certificates
+            .add_certificate(
+                Certificate::issue(
+                    "dave",
+                    &node.public_key(),
+                    "dexos-ca",
+                    &ca_secret,
+                    0,
+                    u64::MAX,
+                )
+                .unwrap(),
+            )
+            .unwrap();
+        // Unsigned certificates are no longer enough once anchors are set
+        certificates
+            .add_certificate(Certificate {
+                id: "erin".to_string(),
+                data: NodeIdentity::generate("erin").public_key(),
+                issuer: "dexos-ca".to_string(),
+                valid_from: 0,
+                valid_to: u64::MAX,
+                signature: vec![],
+                revoked: false,
+            })
+            .unwrap();
+        let trust = TrustStore::new().with_certificates(certificates.clone());
+        assert!(trust.is_trusted("dave", &node.public_key()));
+        let erin = certificates.get_certificate("erin").unwrap();
+        assert!(!trust.is_trusted("erin", &erin.data));
+
+        let now = now_micros() / 1_000_000;
+        let crl = RevocationList::issue(
+            "dexos-ca",
+            &ca_secret,
+            now,
+            now + 3600,
+            vec![RevokedCertificate {
+                id: "dave".to_string(),
+                revoked_at: now,
+                reason: RevocationReason::KeyCompromise,
+            }],
+        )
+        .unwrap();
+        certificates.import_crl(crl, now).unwrap();
+        let trust = TrustStore::new().with_certificates(certificates);
+        assert!(!trust.is_trusted("dave", &node.public_key()));
+    }
+
+    #[test]
+    fn test_handshake_establishes_encrypted_session() {
+        let (alice, bob) = pair();
+        let init = alice.initiate_handshake("bob");
+        let accept = bob.accept_handshake(&init).unwrap();
+        alice.complete_handshake(&accept).unwrap();
+
+        let frame = alice.seal("bob", &alice.sign(b"secret".to_vec())).unwrap();
+        assert!(!frame
+            .ciphertext
+            .windows(b"secret".len())
+            .any(|w| w == b"secret"));
+        assert_eq!(bob.open(&frame, payload).unwrap(), b"secret");
+        assert!(matches!(
+            bob.open(&frame, payload),
+            Err(AuthError::Replayed(_))
+        ));
+
+        let reply = bob.seal("alice", &bob.sign(b"ack".to_vec())).unwrap();
+        let mut flipped = reply.clone();
+        flipped.ciphertext[0] ^= 1;
+        assert!(matches!(
+            alice.open(&flipped, payload),
+            Err(AuthError::Undecryptable(_))
+        ));
+        assert_eq!(alice.open(&reply, payload).unwrap(), b"ack");
+
+        // An answer signed with a key alice does not trust for bob is refused
+        let mut mallory_trust = TrustStore::new();
+        mallory_trust.allow("alice", alice.public_key());
+        let mallory = GossipAuthenticator::new(NodeIdentity::generate("bob"), mallory_trust);
+        let forged = mallory
+            .accept_handshake(&alice.initiate_handshake("bob"))
+            .unwrap();
+        assert_eq!(
+            alice.complete_handshake(&forged),
+            Err(AuthError::Untrusted("bob".to_string()))
+        );
+        assert_eq!(alice.stats().rejected(), 2);
+    }
+
+    #[test]
+    fn test_replayed_handshake_keeps_the_live_session() {
+        let (alice, bob) = pair();
+        let init = alice.initiate_handshake("bob");
+        let accept = bob.accept_handshake(&init).unwrap();
+        alice.complete_handshake(&accept).unwrap();
+
+        assert_eq!(
+            bob.accept_handshake(&init),
+            Err(AuthError::Replayed("alice".to_string()))
+        );
+        let frame = alice.seal("bob", &alice.sign(b"still".to_vec())).unwrap();
+        assert_eq!(bob.open(&frame, payload).unwrap(), b"still");
+
+        // A properly signed opening from outside the window is stale
+        let mut stale = alice.initiate_handshake("bob");
+        stale.timestamp -= 2 * HANDSHAKE_WINDOW_MICROS;
+        stale.signature = alice.identity.sign(&init_transcript(
+            "alice",
+            "bob",
+            stale.timestamp,
+            &stale.ephemeral,
+        ));
+        assert_eq!(
+            bob.accept_handshake(&stale),
+            Err(AuthError::Replayed("alice".to_string()))
+        );
+        assert_eq!(bob.stats().replayed, 2);
+
+        // A fresh handshake replaces the session
+        let accept = bob
+            .accept_handshake(&alice.initiate_handshake("bob"))
+            .unwrap();
+        alice.complete_handshake(&accept).unwrap();
+        let frame = alice.seal("bob", &alice.sign(b"rekeyed".to_vec())).unwrap();
+        assert_eq!(bob.open(&frame, payload).unwrap(), b"rekeyed");
+    }
+
+    #[test]
+    fn test_openings_only_reach_their_addressee() {
+        let alice = NodeIdentity::generate("alice");
+        let bob = NodeIdentity::generate("bob");
+        let carol = NodeIdentity::generate("carol");
+        let mut alice_trust = TrustStore::new();
+        alice_trust.allow("bob", bob.public_key());
+        alice_trust.allow("carol", carol.public_key());
+        let mut peer_trust = TrustStore::new();
+        peer_trust.allow("alice", alice.public_key());
+        let alice = GossipAuthenticator::new(alice, alice_trust);
+        let bob = GossipAuthenticator::new(bob, peer_trust.clone());
+        let carol = GossipAuthenticator::new(carol, peer_trust);
+
+        let init = alice.initiate_handshake("carol");
+        let accept = carol.accept_handshake(&init).unwrap();
+        alice.complete_handshake(&accept).unwrap();
+
+        // Alice's opening to Bob, replayed to Carol, leaves their session alone
+        let to_bob = alice.initiate_handshake("bob");
+        assert!(matches!(
+            carol.accept_handshake(&to_bob),
+            Err(AuthError::HandshakeFailed(_))
+        ));
+        let frame = alice
+            .seal("carol", &alice.sign(b"intact".to_vec()))
+            .unwrap();
+        assert_eq!(carol.open(&frame, payload).unwrap(), b"intact");
+
+        // A forged answer does not abort the handshake with Bob
+        let mut forged = bob.accept_handshake(&to_bob).unwrap();
+        let genuine = forged.clone();
+        forged.signature[0] ^= 1;
+        assert_eq!(
+            alice.complete_handshake(&forged),
+            Err(AuthError::InvalidSignature)
+        );
+        alice.complete_handshake(&genuine).unwrap();
+        let frame = alice.seal("bob", &alice.sign(b"keyed".to_vec())).unwrap();
+        assert_eq!(bob.open(&frame, payload).unwrap(), b"keyed");
+    }
+}
@@ -0,0 +1,475 @@
+//! Conflict-free replicated data types for off-chain sync
+//!
+//! Replicas that accept writes independently converge by merging instead of
+//! overwriting: every type here has a `merge` that is commutative, associative
+//! and idempotent, so gossip may deliver states in any order and any number of
+//! times. Updates are ordered by a [`HybridLogicalClock`], which stays close to
+//! wall-clock time but never goes backwards and respects causality across
+//! nodes. Remote timestamps too far ahead of our physical clock are refused,
+//! so one peer cannot drag every clock into the future. Provided types:
+//! - [`LwwRegister`]: single value, the write with the highest timestamp wins;
+//! - [`OrSet`]: observed-remove set, where an add concurrent with a remove wins;
+//! - [`PnCounter`]: counter supporting increments and decrements.
+//!
+//! Implements the Priority 3 feature from DEX-OS-V2.csv:
+//! - Security,Security,Security,Gossip Protocol,Off-chain Sync,Medium
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// How far ahead of physical time a remote timestamp may be, unless
+/// configured otherwise
+pub const DEFAULT_MAX_DRIFT_MS: u64 = 60_000;
+
+/// Hybrid logical clock timestamp. Ordered by wall time, then the logical
+/// counter, then the node ID, so distinct nodes never produce equal stamps.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct HlcTimestamp {
+    /// Physical component in milliseconds since UNIX epoch
+    pub wall_ms: u64,
+    /// Logical counter distinguishing events within one millisecond
+    pub logical: u32,
+    /// Node that produced the timestamp
+    pub node_id: String,
+}
+
+/// Hybrid logical clock (Kulkarni et al.)
+#[derive(Debug, Clone)]
+pub struct HybridLogicalClock {
+    /// Node stamped into every timestamp
+    node_id: String,
+    /// Physical component of the latest timestamp
+    last_wall_ms: u64,
+    /// Logical component of the latest timestamp
+    last_logical: u32,
+    /// How far ahead of physical time an observed timestamp may be
+    max_drift_ms: u64,
+}
+
+impl HybridLogicalClock {
+    /// Create a clock for `node_id`
+    pub fn new(node_id: &str) -> Self {
+        Self {
+            node_id: node_id.to_string(),
+            last_wall_ms: 0,
+            last_logical: 0,
+            max_drift_ms: DEFAULT_MAX_DRIFT_MS,
+        }
+    }
+
+    /// Refuse remote timestamps more than `max_drift_ms` ahead of physical time
+    pub fn with_max_drift(mut self, max_drift_ms: u64) -> Self {
+        self.max_drift_ms = max_drift_ms;
+        self
+    }
+
+    fn physical_time() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64
+    }
+
+    /// Timestamp a local event
+    pub fn now(&mut self) -> Result<HlcTimestamp, ClockError> {
+        self.tick(Self::physical_time())
+    }
+
+    /// Timestamp a local event at physical time `physical_ms`
+    pub fn tick(&mut self, physical_ms: u64) -> Result<HlcTimestamp, ClockError> {
+        if physical_ms > self.last_wall_ms {
+            self.last_wall_ms = physical_ms;
+            self.last_logical = 0;
+        } else {
+            self.last_logical = next_logical(self.last_logical, self.last_wall_ms)?;
+        }
+        Ok(self.stamp())
+    }
+
+    /// Advance past a timestamp received from another node, so that every later
+    /// local event is ordered after it. Timestamps too far ahead of our
+    /// physical time are refused and leave the clock unchanged.
+    pub fn observe(&mut self, remote: &HlcTimestamp) -> Result<HlcTimestamp, ClockError> {
+        self.observe_at(remote, Self::physical_time())
+    }
+
+    /// [`HybridLogicalClock::observe`] at physical time `physical_ms`
+    pub fn observe_at(
+        &mut self,
+        remote: &HlcTimestamp,
+        physical_ms: u64,
+    ) -> Result<HlcTimestamp, ClockError> {
+        if remote.wall_ms > physical_ms.saturating_add(self.max_drift_ms) {
+            return Err(ClockError::TooFarAhead {
+                remote_ms: remote.wall_ms,
+                physical_ms,
+            });
+        }
+        let wall = physical_ms.max(self.last_wall_ms).max(remote.wall_ms);
+        let logical = if wall == self.last_wall_ms && wall == remote.wall_ms {
+            next_logical(self.last_logical.max(remote.logical), wall)?
+        } else if wall == self.last_wall_ms {
+            next_logical(self.last_logical, wall)?
+        } else if wall == remote.wall_ms {
+            next_logical(remote.logical, wall)?
+        } else {
+            0
+        };
+        self.last_wall_ms = wall;
+        self.last_logical = logical;
+        Ok(self.stamp())
+    }
+
+    fn stamp(&self) -> HlcTimestamp {
+        HlcTimestamp {
+            wall_ms: self.last_wall_ms,
+            logical: self.last_logical,
+            node_id: self.node_id.clone(),
+        }
+    }
+}
+
+/// Logical counter following `logical` within the millisecond `wall_ms`
+fn next_logical(logical: u32, wall_ms: u64) -> Result<u32, ClockError> {
+    logical
+        .checked_add(1)
+        .ok_or(ClockError::LogicalOverflow(wall_ms))
+}
+
+/// Errors raised by a [`HybridLogicalClock`]
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ClockError {
+    #[error("Timestamp at {remote_ms} ms is too far ahead of physical time {physical_ms} ms")]
+    TooFarAhead { remote_ms: u64, physical_ms: u64 },
+    #[error("Logical counter overflowed at {0} ms")]
+    LogicalOverflow(u64),
+}
+
+/// Last-writer-wins register
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LwwRegister<T> {
+    /// Current value
+    pub value: T,
+    /// Timestamp of the write that produced `value`
+    pub timestamp: HlcTimestamp,
+}
+
+impl<T: Clone> LwwRegister<T> {
+    /// Create a register holding `value`
+    pub fn new(value: T, timestamp: HlcTimestamp) -> Self {
+        Self { value, timestamp }
+    }
+
+    /// Overwrite the value if `timestamp` is newer than the current one
+    pub fn set(&mut self, value: T, timestamp: HlcTimestamp) {
+        if timestamp > self.timestamp {
+            self.value = value;
+            self.timestamp = timestamp;
+        }
+    }
+
+    /// Keep whichever write is newer
+    pub fn merge(&mut self, other: &Self) {
+        self.set(other.value.clone(), other.timestamp.clone());
+    }
+}
+
+/// Unique tag identifying one add operation in an [`OrSet`]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct AddTag {
+    /// Node that performed the add
+    pub node_id: String,
+    /// Per-node sequence number of the add
+    pub counter: u64,
+}
+
+/// Observed-remove set: a remove only cancels the adds it has seen, so an add
+/// concurrent with a remove survives the merge
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrSet<T: Ord> {
+    /// Live add tags of every element
+    adds: BTreeMap<T, BTreeSet<AddTag>>,
+    /// Tags cancelled by removes
+    removed: BTreeSet<AddTag>,
+}
+
+impl<T: Ord> Default for OrSet<T> {
+    fn default() -> Self {
+        Self {
+            adds: BTreeMap::new(),
+            removed: BTreeSet::new(),
+        }
+    }
+}
+
+impl<T: Ord + Clone> OrSet<T> {
+    /// Create an empty set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `element` on behalf of `node_id`
+    pub fn add(&mut self, element: T, node_id: &str) {
+        let counter = self
+            .adds
+            .values()
+            .flatten()
+            .chain(self.removed.iter())
+            .filter(|tag| tag.node_id == node_id)
+            .map(|tag| tag.counter)
+            .max()
+            .unwrap_or(0)
+            + 1;
+        self.adds.entry(element).or_default().insert(AddTag {
+            node_id: node_id.to_string(),
+            counter,
+        });
+    }
+
+    /// Remove `element`, cancelling every add observed so far
+    pub fn remove(&mut self, element: &T) {
+        if let Some(tags) = self.adds.remove(element) {
+            self.removed.extend(tags);
+        }
+    }
+
+    /// Whether `element` is in the set
+    pub fn contains(&self, element: &T) -> bool {
+        self.adds.contains_key(element)
+    }
+
+    /// Elements in the set, in order
+    pub fn elements(&self) -> Vec<T> {
+        self.adds.keys().cloned().collect()
+    }
+
+    /// Union of adds minus the union of removes
+    pub fn merge(&mut self, other: &Self) {
+        self.removed.extend(other.removed.iter().cloned());
+        for (element, tags) in &other.adds {
+            self.adds
+                .entry(element.clone())
+                .or_default()
+                .extend(tags.iter().cloned());
+        }
+        let removed = &self.removed;
+        self.adds.retain(|_, tags| {
+            tags.retain(|tag| !removed.contains(tag));
+            !tags.is_empty()
+        });
+    }
+}
+
+/// Counter supporting increments and decrements, as a pair of grow-only
+/// per-node tallies
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PnCounter {
+    /// Increments per node
+    increments: BTreeMap<String, u64>,
+    /// Decrements per node
+    decrements: BTreeMap<String, u64>,
+}
+
+impl PnCounter {
+    /// Create a counter at zero
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `amount` on behalf of `node_id`
+    pub fn increment(&mut self, node_id: &str, amount: u64) {
+        *self.increments.entry(node_id.to_string()).or_insert(0) += amount;
+    }
+
+    /// Subtract `amount` on behalf of `node_id`
+    pub fn decrement(&mut self, node_id: &str, amount: u64) {
+        *self.decrements.entry(node_id.to_string()).or_insert(0) += amount;
+    }
+
+    /// Current value
+    pub fn value(&self) -> i128 {
+        let up: u128 = self.increments.values().map(|&v| v as u128).sum();
+        let down: u128 = self.decrements.values().map(|&v| v as u128).sum();
+        up as i128 - down as i128
+    }
+
+    /// Per-node maximum of both tallies
+    pub fn merge(&mut self, other: &Self) {
+        for (node, &count) in &other.increments {
+            let entry = self.increments.entry(node.clone()).or_insert(0);
+            *entry = (*entry).max(count);
+        }
+        for (node, &count) in &other.decrements {
+            let entry = self.decrements.entry(node.clone()).or_insert(0);
+            *entry = (*entry).max(count);
+        }
+    }
+}
+
+/// CRDT payload carried in a `SyncData` entry whose `data_type` is
+/// [`CRDT_DATA_TYPE`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CrdtValue {
+    /// Opaque bytes with last-writer-wins semantics
+    Register(LwwRegister<Vec<u8>>),
+    /// Set of strings
+    Set(OrSet<String>),
+    /// Signed counter
+    Counter(PnCounter),
+}
+
+/// `SyncData::data_type` marking payloads that hold an encoded [`CrdtValue`]
+pub const CRDT_DATA_TYPE: &str = "crdt";
+
+impl CrdtValue {
+    /// Merge a value of the same kind. Returns false, leaving `self` unchanged,
+    /// if the kinds differ.
+    pub fn merge(&mut self, other: &Self) -> bool {
+        match (self, other) {
+            (CrdtValue::Register(a), CrdtValue::Register(b)) => a.merge(b),
+            (CrdtValue::Set(a), CrdtValue::Set(b)) => a.merge(b),
+            (CrdtValue::Counter(a), CrdtValue::Counter(b)) => a.merge(b),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Encode for a `SyncData` payload. Every type uses ordered maps, so equal
+    /// states encode to equal bytes and digest identically.
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("CRDT values always serialize")
+    }
+
+    /// Decode a `SyncData` payload
+    pub fn decode(payload: &[u8]) -> Option<Self> {
+        serde_json::from_slice(payload).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stamp(wall_ms: u64, node_id: &str) -> HlcTimestamp {
+        HlcTimestamp {
+            wall_ms,
+            logical: 0,
+            node_id: node_id.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_hlc_is_monotonic_and_tracks_remote_clocks() {
+        let mut clock = HybridLogicalClock::new("a");
+        let first = clock.tick(100).unwrap();
+        // The physical clock stepping backwards does not reorder events
+        let second = clock.tick(90).unwrap();
+        assert!(second > first);
+        assert_eq!(second.wall_ms, 100);
+        assert_eq!(second.logical, 1);
+
+        // A message from a node running ahead pulls the clock forward
+        let received = clock.observe_at(&stamp(500, "b"), 110).unwrap();
+        assert!(received > stamp(500, "b"));
+        assert!(clock.tick(120).unwrap() > received);
+    }
+
+    #[test]
+    fn test_hlc_refuses_stamps_beyond_the_drift_bound() {
+        let mut clock = HybridLogicalClock::new("a").with_max_drift(1_000);
+        let before = clock.tick(100).unwrap();
+
+        assert_eq!(
+            clock.observe_at(&stamp(1_101, "b"), 100),
+            Err(ClockError::TooFarAhead {
+                remote_ms: 1_101,
+                physical_ms: 100,
+            })
+        );
+        // The clock did not move, so local writes keep their place
+        let next = clock.tick(100).unwrap();
+        assert_eq!(next.wall_ms, before.wall_ms);
+        assert_eq!(next.logical, before.logical + 1);
+
+        assert!(clock.observe_at(&stamp(1_100, "b"), 100).is_ok());
+    }
+
+    #[test]
+    fn test_hlc_logical_overflow_is_an_error() {
+        let mut clock = HybridLogicalClock::new("a");
+        let mut remote = stamp(100, "b");
+        remote.logical = u32::MAX;
+        assert_eq!(
+            clock.observe_at(&remote, 100),
+            Err(ClockError::LogicalOverflow(100))
+        );
+
+        remote.logical = u32::MAX - 1;
+        let received = clock.observe_at(&remote, 100).unwrap();
+        assert_eq!(received.logical, u32::MAX);
+        assert_eq!(clock.tick(100), Err(ClockError::LogicalOverflow(100)));
+        // Once physical time moves on the counter restarts
+        assert_eq!(clock.tick(101).unwrap().logical, 0);
+    }
+
+    #[test]
+    fn test_lww_register_converges_regardless_of_order() {
+        let older = LwwRegister::new(b"old".to_vec(), stamp(1, "a"));
+        let newer = LwwRegister::new(b"new".to_vec(), stamp(2, "b"));
+        let mut left = older.clone();
+        left.merge(&newer);
+        let mut right = newer.clone();
+        right.merge(&older);
+        assert_eq!(left, right);
+        assert_eq!(left.value, b"new".to_vec());
+    }
+
+    #[test]
+    fn test_or_set_add_wins_over_concurrent_remove() {
+        let mut a = OrSet::new();
+        a.add("ETH/USDC".to_string(), "a");
+        let mut b = a.clone();
+
+        // a removes the element while b concurrently re-adds it
+        a.remove(&"ETH/USDC".to_string());
+        b.add("ETH/USDC".to_string(), "b");
+        b.add("BTC/USDC".to_string(), "b");
+
+        let mut left = a.clone();
+        left.merge(&b);
+        let mut right = b.clone();
+        right.merge(&a);
+        assert_eq!(left, right);
+        assert!(left.contains(&"ETH/USDC".to_string()));
+
+        // A remove that observed every add sticks
+        left.remove(&"ETH/USDC".to_string());
+        right.merge(&left);
+        assert_eq!(right.elements(), vec!["BTC/USDC".to_string()]);
+    }
+
+    #[test]
+    fn test_pn_counter_merge_is_idempotent() {
+        let mut a = PnCounter::new();
+        a.increment("a", 10);
+        let mut b = PnCounter::new();
+        b.increment("b", 5);
+        b.decrement("b", 7);
+
+        a.merge(&b);
+        a.merge(&b);
+        b.merge(&a);
+        assert_eq!(a, b);
+        assert_eq!(a.value(), 8);
+    }
+
+    #[test]
+    fn test_crdt_value_rejects_mismatched_kinds() {
+        let mut counter = CrdtValue::Counter(PnCounter::new());
+        let set = CrdtValue::Set(OrSet::new());
+        assert!(!counter.merge(&set));
+        assert_eq!(CrdtValue::decode(&counter.encode()), Some(counter));
+    }
+}
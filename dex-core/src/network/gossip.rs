@@ -1,13 +1,25 @@
 //! Gossip protocol implementation for node discovery
 //!
 //! This module implements a gossip protocol for discovering and maintaining
-//! a list of active nodes in the network.
+//! a list of active nodes in the network. Failures are detected SWIM-style:
+//! every protocol period a node pings one member; if no pong arrives within the
+//! probe timeout it asks a few other members to ping the target on its behalf
+//! (indirect probing), and only if none of them gets through either is the
+//! target suspected. A suspected member that does not refute the suspicion by
+//! gossiping a higher incarnation before the suspicion timeout is declared
+//! dead. Membership changes spread by gossiping the member list to a bounded
+//! number of random peers each period.
+//! Messages travel over a [`GossipTransport`] using the versioned encoding in
+//! [`super::wire`].
 //! Implements the Priority 3 feature from DEX-OS-V2.csv:
 //! - Infrastructure,Network,Network,Gossip Protocol,Node Discovery,Medium
 
+use super::transport::GossipTransport;
+use rand::seq::SliceRandom;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
@@ -23,10 +35,20 @@ pub struct GossipConfig {
     pub node_address: SocketAddr,
     /// List of initial peer addresses
     pub initial_peers: Vec<SocketAddr>,
-    /// Gossip interval in milliseconds
+    /// Gossip interval in milliseconds; one member is probed and the member
+    /// list is gossiped once per interval
     pub gossip_interval_ms: u64,
-    /// Node timeout in milliseconds (nodes not seen for this long are considered dead)
+    /// Time in milliseconds a dead node is remembered before it is forgotten
     pub node_timeout_ms: u64,
+    /// Time in milliseconds to wait for a direct pong before probing indirectly
+    pub probe_timeout_ms: u64,
+    /// Number of members asked to probe a target that missed a direct ping
+    pub indirect_probes: usize,
+    /// Time in milliseconds a suspected node has to refute before it is
+    /// declared dead
+    pub suspicion_timeout_ms: u64,
+    /// Number of random peers the member list is gossiped to per interval
+    pub fanout: usize,
 }
 
 impl Default for GossipConfig {
@@ -37,6 +59,33 @@ impl Default for GossipConfig {
             initial_peers: vec![],
             gossip_interval_ms: 1000,
             node_timeout_ms: 5000,
+            probe_timeout_ms: 300,
+            indirect_probes: 3,
+            suspicion_timeout_ms: 3000,
+            fanout: 3,
+        }
+    }
+}
+
+/// Liveness of a member as agreed through gossip
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MemberState {
+    /// Responding to probes
+    #[default]
+    Alive,
+    /// Missed a direct and indirect probe; may still refute
+    Suspect,
+    /// Confirmed failed
+    Dead,
+}
+
+impl MemberState {
+    /// Precedence among claims with the same incarnation
+    fn rank(self) -> u8 {
+        match self {
+            MemberState::Alive => 0,
+            MemberState::Suspect => 1,
+            MemberState::Dead => 2,
         }
     }
 }
@@ -50,31 +99,86 @@ pub struct NodeInfo {
     pub address: SocketAddr,
     /// Last time we heard from this node
     pub last_seen: u64,
+    /// Liveness of the node
+    #[serde(default)]
+    pub state: MemberState,
+    /// Incarnation number, raised by the node itself to refute suspicion
+    #[serde(default)]
+    pub incarnation: u64,
+}
+
+impl NodeInfo {
+    /// Whether this claim about a member supersedes `other`: a higher
+    /// incarnation wins, and within one incarnation Dead beats Suspect beats
+    /// Alive
+    fn overrides(&self, other: &NodeInfo) -> bool {
+        self.incarnation > other.incarnation
+            || (self.incarnation == other.incarnation && self.state.rank() > other.state.rank())
+    }
 }
 
 /// Gossip message types
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum GossipMessage {
     /// Ping message to check if node is alive
-    Ping { node_id: String },
-    /// Pong message in response to ping
-    Pong { node_id: String },
+    Ping {
+        node_id: String,
+        /// Where to send the pong
+        address: SocketAddr,
+        seq: u64,
+    },
+    /// Pong message in response to ping, naming the node that answered
+    Pong { node_id: String, seq: u64 },
+    /// Request to ping `target` and forward its pong
+    PingReq {
+        node_id: String,
+        /// Where to forward the pong
+        address: SocketAddr,
+        target: String,
+        target_address: SocketAddr,
+        seq: u64,
+    },
     /// Gossip message containing node information
     Gossip { nodes: Vec<NodeInfo> },
 }
 
+/// Ping awaiting its pong
+#[derive(Debug, Clone)]
+struct Probe {
+    /// Member being probed
+    target: String,
+    /// When the direct ping was sent
+    sent_at: u64,
+    /// Whether indirect probes have been requested
+    indirect_sent: bool,
+    /// Member that asked for this probe and the sequence number it used
+    requester: Option<(SocketAddr, u64)>,
+}
+
 /// Gossip node implementation
 pub struct GossipNode {
     /// Node configuration
     config: GossipConfig,
     /// Known nodes in the network
     nodes: Arc<RwLock<HashMap<String, NodeInfo>>>,
-    /// Nodes that we know are alive
+    /// Nodes that we know are alive. Always locked after `nodes` when both are held.
     alive_nodes: Arc<RwLock<HashSet<String>>>,
     /// Gossip interval
     gossip_interval: Duration,
     /// Node timeout
     node_timeout: Duration,
+    /// Socket transport, if the node was bound
+    transport: Option<Arc<GossipTransport>>,
+    /// Outstanding probes by sequence number
+    probes: Arc<RwLock<HashMap<u64, Probe>>>,
+    /// When each member entered its current state
+    state_since: Arc<RwLock<HashMap<String, u64>>>,
+    /// Members left to probe in the current round
+    probe_queue: Arc<RwLock<Vec<String>>>,
+    /// Sequence number of the next ping
+    next_seq: AtomicU64,
+    /// Start of the current protocol period
+    period_started: AtomicU64,
 }
 
 impl GossipNode {
@@ -91,34 +195,86 @@ impl GossipNode {
             alive_nodes,
             gossip_interval,
             node_timeout,
+            transport: None,
+            probes: Arc::new(RwLock::new(HashMap::new())),
+            state_since: Arc::new(RwLock::new(HashMap::new())),
+            probe_queue: Arc::new(RwLock::new(Vec::new())),
+            next_seq: AtomicU64::new(1),
+            period_started: AtomicU64::new(0),
         }
     }
 
+    /// Create a gossip node listening on `config.node_address` over UDP and
+    /// TCP. With port 0 the node advertises the port it was given.
+    pub async fn bind(mut config: GossipConfig) -> Result<Self, GossipError> {
+        // A stalled TCP send must not hold up probing and message handling
+        let transport = GossipTransport::bind(config.node_address)
+            .await?
+            .with_stream_timeout(Duration::from_millis(config.probe_timeout_ms));
+        config.node_address = transport.local_addr();
+        let mut node = Self::new(config);
+        node.transport = Some(Arc::new(transport));
+        Ok(node)
+    }
+
+    /// Address this node advertises to its peers
+    pub fn address(&self) -> SocketAddr {
+        self.config.node_address
+    }
+
     /// Start the gossip node
     pub async fn start(&self) {
-        // Add ourselves to the node list
+        self.join().await;
+
+        // Timers are checked often enough to honour the probe timeout
+        let tick = Duration::from_millis(
+            (self
+                .config
+                .probe_timeout_ms
+                .min(self.config.gossip_interval_ms)
+                / 4)
+            .max(1),
+        );
+        loop {
+            self.tick().await;
+            match &self.transport {
+                Some(transport) => {
+                    if let Ok(Some(message)) = tokio::time::timeout(tick, transport.recv()).await {
+                        self.handle_gossip_message(message).await;
+                    }
+                }
+                None => sleep(tick).await,
+            }
+        }
+    }
+
+    /// Add ourselves to the member list and announce ourselves to the initial
+    /// peers
+    pub async fn join(&self) {
         let self_info = NodeInfo {
             id: self.config.node_id.clone(),
             address: self.config.node_address,
             last_seen: Self::current_time(),
+            state: MemberState::Alive,
+            incarnation: 0,
         };
 
         {
             let mut nodes = self.nodes.write().await;
-            nodes.insert(self.config.node_id.clone(), self_info);
+            nodes
+                .entry(self.config.node_id.clone())
+                .or_insert_with(|| self_info.clone());
         }
 
-        // Add initial peers
+        // Peers learn about us from our entry and gossip their lists back
         for peer_addr in &self.config.initial_peers {
-            // In a real implementation, we would try to connect to these peers
-            // and get their node IDs
-            println!("Would connect to peer at {}", peer_addr);
-        }
-
-        // Start gossip loop
-        loop {
-            self.gossip().await;
-            sleep(self.gossip_interval).await;
+            self.send(
+                *peer_addr,
+                GossipMessage::Gossip {
+                    nodes: vec![self_info.clone()],
+                },
+            )
+            .await;
         }
     }
 
@@ -130,38 +286,221 @@ impl GossipNode {
             .as_millis() as u64
     }
 
+    /// Run the failure detector and, once per gossip interval, start a new
+    /// probe and a gossip round
+    pub async fn tick(&self) {
+        let now = Self::current_time();
+        self.check_probes(now).await;
+        self.check_suspicions(now).await;
+
+        let period_started = self.period_started.load(Ordering::Relaxed);
+        if now.saturating_sub(period_started) >= self.gossip_interval.as_millis() as u64 {
+            self.period_started.store(now, Ordering::Relaxed);
+            self.probe_next().await;
+            self.gossip().await;
+        }
+    }
+
+    /// Ping the next member of the round-robin probe order
+    async fn probe_next(&self) {
+        let peers = self.get_gossip_peers().await;
+        let target = {
+            let mut queue = self.probe_queue.write().await;
+            // Skip members that died or were forgotten since the round started
+            while queue
+                .last()
+                .is_some_and(|id| !peers.iter().any(|peer| &peer.id == id))
+            {
+                queue.pop();
+            }
+            if queue.is_empty() {
+                // SWIM probes every member once per round, in random order
+                let mut round: Vec<String> = peers.iter().map(|peer| peer.id.clone()).collect();
+                round.shuffle(&mut rand::thread_rng());
+                *queue = round;
+            }
+            queue.pop()
+        };
+        let Some(target) = target.and_then(|id| peers.into_iter().find(|peer| peer.id == id))
+        else {
+            return;
+        };
+
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        self.probes.write().await.insert(
+            seq,
+            Probe {
+                target: target.id.clone(),
+                sent_at: Self::current_time(),
+                indirect_sent: false,
+                requester: None,
+            },
+        );
+        self.send(
+            target.address,
+            GossipMessage::Ping {
+                node_id: self.config.node_id.clone(),
+                address: self.config.node_address,
+                seq,
+            },
+        )
+        .await;
+    }
+
+    /// Escalate probes that went unanswered: indirect probing after the probe
+    /// timeout, suspicion once the protocol period is over
+    async fn check_probes(&self, now: u64) {
+        let mut indirect = Vec::new();
+        let mut failed = Vec::new();
+        {
+            let mut probes = self.probes.write().await;
+            probes.retain(|&seq, probe| {
+                let elapsed = now.saturating_sub(probe.sent_at);
+                if elapsed >= self.gossip_interval.as_millis() as u64 {
+                    if probe.requester.is_none() {
+                        failed.push(probe.target.clone());
+                    }
+                    return false;
+                }
+                if elapsed >= self.config.probe_timeout_ms
+                    && !probe.indirect_sent
+                    && probe.requester.is_none()
+                {
+                    probe.indirect_sent = true;
+                    indirect.push((seq, probe.target.clone()));
+                }
+                true
+            });
+        }
+
+        for (seq, target) in indirect {
+            let target_address = match self.nodes.read().await.get(&target) {
+                Some(info) => info.address,
+                None => continue,
+            };
+            let mut helpers: Vec<NodeInfo> = self
+                .get_gossip_peers()
+                .await
+                .into_iter()
+                .filter(|peer| peer.id != target)
+                .collect();
+            helpers.shuffle(&mut rand::thread_rng());
+            helpers.truncate(self.config.indirect_probes);
+            for helper in helpers {
+                self.send(
+                    helper.address,
+                    GossipMessage::PingReq {
+                        node_id: self.config.node_id.clone(),
+                        address: self.config.node_address,
+                        target: target.clone(),
+                        target_address,
+                        seq,
+                    },
+                )
+                .await;
+            }
+        }
+
+        for target in failed {
+            self.set_state(&target, MemberState::Suspect).await;
+        }
+    }
+
+    /// Declare suspects dead after the suspicion timeout and forget dead
+    /// members after the node timeout
+    async fn check_suspicions(&self, now: u64) {
+        let mut expired_suspects = Vec::new();
+        let mut forgotten = Vec::new();
+        {
+            let nodes = self.nodes.read().await;
+            let since = self.state_since.read().await;
+            for node in nodes.values() {
+                let elapsed = now.saturating_sub(since.get(&node.id).copied().unwrap_or(now));
+                match node.state {
+                    MemberState::Suspect if elapsed >= self.config.suspicion_timeout_ms => {
+                        expired_suspects.push(node.id.clone())
+                    }
+                    MemberState::Dead if elapsed >= self.node_timeout.as_millis() as u64 => {
+                        forgotten.push(node.id.clone())
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        for id in expired_suspects {
+            self.set_state(&id, MemberState::Dead).await;
+        }
+        if !forgotten.is_empty() {
+            let mut nodes = self.nodes.write().await;
+            let mut since = self.state_since.write().await;
+            for id in forgotten {
+                nodes.remove(&id);
+                since.remove(&id);
+            }
+        }
+    }
+
+    /// Move a member to `state` at its current incarnation, if that is news
+    async fn set_state(&self, node_id: &str, state: MemberState) {
+        if node_id == self.config.node_id {
+            return;
+        }
+        let changed = {
+            let mut nodes = self.nodes.write().await;
+            let mut alive_nodes = self.alive_nodes.write().await;
+            match nodes.get_mut(node_id) {
+                Some(node) if node.state != state => {
+                    node.state = state;
+                    if state == MemberState::Dead {
+                        alive_nodes.remove(node_id);
+                    } else {
+                        alive_nodes.insert(node_id.to_string());
+                    }
+                    true
+                }
+                _ => false,
+            }
+        };
+        if changed {
+            self.state_since
+                .write()
+                .await
+                .insert(node_id.to_string(), Self::current_time());
+            println!(
+                "Node {} marked {} as {:?}",
+                self.config.node_id, node_id, state
+            );
+        }
+    }
+
     /// Perform gossip with other nodes
     async fn gossip(&self) {
         // Get a list of nodes to gossip with
-        let peers = self.get_gossip_peers().await;
+        let mut peers = self.get_gossip_peers().await;
 
         if peers.is_empty() {
             return;
         }
 
-        // Select a random peer to gossip with
-        let peer = &peers[0]; // In a real implementation, we would select randomly
+        // Bound the work per period to a few random peers
+        peers.shuffle(&mut rand::thread_rng());
+        peers.truncate(self.config.fanout.max(1));
 
-        // Send our node information to the peer
-        self.send_gossip_message(peer, self.get_node_info().await)
-            .await;
-
-        // In a real implementation, we would also receive gossip messages
-        // from other nodes and update our node list accordingly
+        let nodes = self.get_node_info().await;
+        for peer in &peers {
+            self.send_gossip_message(peer, nodes.clone()).await;
+        }
     }
 
-    /// Get list of peers to gossip with
+    /// Get list of peers to gossip with: every member not known to be dead
     async fn get_gossip_peers(&self) -> Vec<NodeInfo> {
         let nodes = self.nodes.read().await;
         let alive_nodes = self.alive_nodes.read().await;
 
         nodes
             .values()
-            .filter(|node| {
-                node.id != self.config.node_id
-                    && alive_nodes.contains(&node.id)
-                    && Self::current_time() - node.last_seen < self.node_timeout.as_millis() as u64
-            })
+            .filter(|node| node.id != self.config.node_id && alive_nodes.contains(&node.id))
             .cloned()
             .collect()
     }
@@ -174,83 +513,207 @@ impl GossipNode {
 
     /// Send a gossip message to a peer
     async fn send_gossip_message(&self, peer: &NodeInfo, nodes: Vec<NodeInfo>) {
-        println!(
-            "Sending gossip message to node {} at {}",
-            peer.id, peer.address
-        );
-
-        // In a real implementation, we would serialize the message and send it
-        // over the network using TCP/UDP or another protocol
-        let message = GossipMessage::Gossip { nodes };
+        self.send(peer.address, GossipMessage::Gossip { nodes })
+            .await;
+    }
 
-        // Simulate network communication
-        self.handle_gossip_message(message).await;
+    /// Send a message if the node is bound. Delivery is best effort; lost
+    /// messages show up as missed probes.
+    async fn send(&self, to: SocketAddr, message: GossipMessage) {
+        if let Some(transport) = &self.transport {
+            let _ = transport.send(to, &message).await;
+        }
     }
 
     /// Handle incoming gossip message
     pub async fn handle_gossip_message(&self, message: GossipMessage) {
         match message {
-            GossipMessage::Ping { node_id } => {
-                println!("Received ping from node {}", node_id);
-                // In a real implementation, we would send a pong response
+            GossipMessage::Ping {
+                node_id,
+                address,
+                seq,
+            } => {
+                self.heard_from(&node_id, Some(address)).await;
+                self.send(
+                    address,
+                    GossipMessage::Pong {
+                        node_id: self.config.node_id.clone(),
+                        seq,
+                    },
+                )
+                .await;
             }
-            GossipMessage::Pong { node_id } => {
-                println!("Received pong from node {}", node_id);
-                // Update last seen time for this node
-                let mut nodes = self.nodes.write().await;
-                if let Some(node) = nodes.get_mut(&node_id) {
-                    node.last_seen = Self::current_time();
+            GossipMessage::Pong { node_id, seq } => {
+                let probe = self.probes.write().await.remove(&seq);
+                match probe {
+                    Some(probe) if probe.target == node_id => {
+                        self.heard_from(&node_id, None).await;
+                        // Relay the pong to the member that asked us to probe
+                        if let Some((requester, requester_seq)) = probe.requester {
+                            self.send(
+                                requester,
+                                GossipMessage::Pong {
+                                    node_id,
+                                    seq: requester_seq,
+                                },
+                            )
+                            .await;
+                        }
+                    }
+                    Some(probe) => {
+                        // Not the answer to this probe; keep waiting
+                        self.probes.write().await.insert(seq, probe);
+                    }
+                    None => {}
                 }
             }
+            GossipMessage::PingReq {
+                node_id,
+                address,
+                target,
+                target_address,
+                seq,
+            } => {
+                self.heard_from(&node_id, Some(address)).await;
+                let relay_seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+                self.probes.write().await.insert(
+                    relay_seq,
+                    Probe {
+                        target,
+                        sent_at: Self::current_time(),
+                        indirect_sent: true,
+                        requester: Some((address, seq)),
+                    },
+                );
+                self.send(
+                    target_address,
+                    GossipMessage::Ping {
+                        node_id: self.config.node_id.clone(),
+                        address: self.config.node_address,
+                        seq: relay_seq,
+                    },
+                )
+                .await;
+            }
             GossipMessage::Gossip { nodes } => {
-                println!("Received gossip message with {} nodes", nodes.len());
                 self.update_node_list(nodes).await;
             }
         }
     }
 
+    /// Record direct contact with a member, adding it if it is new
+    async fn heard_from(&self, node_id: &str, address: Option<SocketAddr>) {
+        if node_id == self.config.node_id {
+            return;
+        }
+        let now = Self::current_time();
+        let revived = {
+            let mut nodes = self.nodes.write().await;
+            let mut alive_nodes = self.alive_nodes.write().await;
+            match nodes.get_mut(node_id) {
+                Some(node) => {
+                    node.last_seen = now;
+                    // A direct answer clears our own suspicion; a dead member
+                    // must rejoin with a higher incarnation
+                    let revived = node.state == MemberState::Suspect;
+                    if revived {
+                        node.state = MemberState::Alive;
+                    }
+                    revived
+                }
+                None => {
+                    let Some(address) = address else {
+                        return;
+                    };
+                    nodes.insert(
+                        node_id.to_string(),
+                        NodeInfo {
+                            id: node_id.to_string(),
+                            address,
+                            last_seen: now,
+                            state: MemberState::Alive,
+                            incarnation: 0,
+                        },
+                    );
+                    alive_nodes.insert(node_id.to_string());
+                    true
+                }
+            }
+        };
+        if revived {
+            self.state_since
+                .write()
+                .await
+                .insert(node_id.to_string(), now);
+        }
+    }
+
     /// Update our node list with information from gossip message
     async fn update_node_list(&self, received_nodes: Vec<NodeInfo>) {
-        let mut nodes = self.nodes.write().await;
-        let mut alive_nodes = self.alive_nodes.write().await;
+        let now = Self::current_time();
+        let mut changed = Vec::new();
+        {
+            let mut nodes = self.nodes.write().await;
+            let mut alive_nodes = self.alive_nodes.write().await;
 
-        for node in received_nodes {
-            // Update or insert node information
-            if let Some(existing_node) = nodes.get_mut(&node.id) {
-                // Only update if this information is more recent
-                if node.last_seen > existing_node.last_seen {
-                    *existing_node = node.clone();
+            for node in received_nodes {
+                if node.id == self.config.node_id {
+                    // Refute rumours of our failure with a higher incarnation
+                    if let Some(own) = nodes.get_mut(&node.id) {
+                        if node.state != MemberState::Alive && node.incarnation >= own.incarnation {
+                            own.incarnation = node.incarnation + 1;
+                            own.last_seen = now;
+                        }
+                    }
+                    continue;
                 }
-            } else {
-                // New node, add it to our list
-                nodes.insert(node.id.clone(), node.clone());
-            }
 
-            // Mark node as alive
-            alive_nodes.insert(node.id);
+                // Update or insert node information
+                let id = node.id.clone();
+                let accepted = match nodes.get_mut(&id) {
+                    Some(existing) if node.overrides(existing) => {
+                        let last_seen = existing.last_seen.max(node.last_seen);
+                        *existing = NodeInfo { last_seen, ..node };
+                        true
+                    }
+                    Some(_) => false,
+                    None => {
+                        nodes.insert(id.clone(), node);
+                        true
+                    }
+                };
+                if accepted {
+                    let entry = &nodes[&id];
+                    if entry.state == MemberState::Dead {
+                        alive_nodes.remove(&entry.id);
+                    } else {
+                        alive_nodes.insert(entry.id.clone());
+                    }
+                    changed.push(entry.id.clone());
+                }
+            }
         }
 
-        // Clean up dead nodes
-        self.cleanup_dead_nodes(&mut nodes, &mut alive_nodes).await;
+        if !changed.is_empty() {
+            let mut since = self.state_since.write().await;
+            for id in changed {
+                since.insert(id, now);
+            }
+        }
     }
 
-    /// Clean up nodes that haven't been seen for a while
-    async fn cleanup_dead_nodes(
-        &self,
-        nodes: &mut HashMap<String, NodeInfo>,
-        alive_nodes: &mut HashSet<String>,
-    ) {
-        let now = Self::current_time();
-        let timeout = self.node_timeout.as_millis() as u64;
+    /// Get the liveness of a member
+    pub async fn member_state(&self, node_id: &str) -> Option<MemberState> {
+        self.nodes.read().await.get(node_id).map(|node| node.state)
+    }
 
-        nodes.retain(|id, node| {
-            if now - node.last_seen > timeout {
-                alive_nodes.remove(id);
-                false
-            } else {
-                true
-            }
-        });
+    /// Get our own incarnation number
+    pub async fn incarnation(&self) -> u64 {
+        self.nodes
+            .read()
+            .await
+            .get(&self.config.node_id)
+            .map_or(0, |node| node.incarnation)
     }
 
     /// Get list of all known nodes
@@ -279,14 +742,24 @@ impl GossipNode {
 
     /// Mark a node as alive
     pub async fn mark_node_alive(&self, node_id: &str) {
+        // Same lock order as `set_state`: `nodes` before `alive_nodes`
+        let mut nodes = self.nodes.write().await;
         let mut alive_nodes = self.alive_nodes.write().await;
         alive_nodes.insert(node_id.to_string());
+        if let Some(node) = nodes.get_mut(node_id) {
+            node.state = MemberState::Alive;
+        }
     }
 
     /// Mark a node as dead
     pub async fn mark_node_dead(&self, node_id: &str) {
+        // Same lock order as `set_state`: `nodes` before `alive_nodes`
+        let mut nodes = self.nodes.write().await;
         let mut alive_nodes = self.alive_nodes.write().await;
         alive_nodes.remove(node_id);
+        if let Some(node) = nodes.get_mut(node_id) {
+            node.state = MemberState::Dead;
+        }
     }
 }
 
@@ -297,6 +770,8 @@ pub enum GossipError {
     NetworkError(String),
     #[error("Serialization error: {0}")]
     SerializationError(String),
+    #[error("Unsupported wire version: {0}")]
+    UnsupportedVersion(u8),
     #[error("Timeout error")]
     Timeout,
 }
@@ -304,7 +779,20 @@ pub enum GossipError {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::network::transport::MAX_DATAGRAM_BYTES;
+    use crate::network::wire;
     use std::net::SocketAddr;
+    use tokio::net::UdpSocket;
+
+    fn node_info(id: &str, address: SocketAddr) -> NodeInfo {
+        NodeInfo {
+            id: id.to_string(),
+            address,
+            last_seen: GossipNode::current_time(),
+            state: MemberState::Alive,
+            incarnation: 0,
+        }
+    }
 
     #[test]
     fn test_gossip_node_creation() {
@@ -319,11 +807,7 @@ mod tests {
         let config = GossipConfig::default();
         let node = GossipNode::new(config);
 
-        let node_info = NodeInfo {
-            id: "test-node".to_string(),
-            address: "127.0.0.1:8001".parse().unwrap(),
-            last_seen: GossipNode::current_time(),
-        };
+        let node_info = node_info("test-node", "127.0.0.1:8001".parse().unwrap());
 
         node.add_node(node_info).await;
 
@@ -337,11 +821,7 @@ mod tests {
         let config = GossipConfig::default();
         let node = GossipNode::new(config);
 
-        let node_info = NodeInfo {
-            id: "test-node".to_string(),
-            address: "127.0.0.1:8001".parse().unwrap(),
-            last_seen: GossipNode::current_time(),
-        };
+        let node_info = node_info("test-node", "127.0.0.1:8001".parse().unwrap());
 
         node.add_node(node_info).await;
         node.mark_node_alive("test-node").await;
@@ -355,4 +835,272 @@ mod tests {
         let alive_nodes = node.get_alive_nodes().await;
         assert_eq!(alive_nodes.len(), 0);
     }
+
+    fn loopback_config(node_id: &str, initial_peers: Vec<SocketAddr>) -> GossipConfig {
+        GossipConfig {
+            node_id: node_id.to_string(),
+            node_address: "127.0.0.1:0".parse().unwrap(),
+            initial_peers,
+            gossip_interval_ms: 60,
+            node_timeout_ms: 10_000,
+            probe_timeout_ms: 20,
+            indirect_probes: 2,
+            suspicion_timeout_ms: 200,
+            fanout: 2,
+        }
+    }
+
+    /// Poll `done` until it holds or the deadline passes
+    async fn wait_for<F, Fut>(done: F) -> bool
+    where
+        F: Fn() -> Fut,
+        Fut: std::future::Future<Output = bool>,
+    {
+        for _ in 0..300 {
+            if done().await {
+                return true;
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+        false
+    }
+
+    async fn states(node: &GossipNode, ids: &[&str]) -> Vec<Option<MemberState>> {
+        let mut states = Vec::new();
+        for id in ids {
+            states.push(node.member_state(id).await);
+        }
+        states
+    }
+
+    #[tokio::test]
+    async fn test_loopback_cluster_converges_and_detects_failure() {
+        let seed = Arc::new(
+            GossipNode::bind(loopback_config("node-0", vec![]))
+                .await
+                .unwrap(),
+        );
+        let seed_address = seed.address();
+        let mut nodes = vec![seed];
+        for i in 1..4 {
+            let config = loopback_config(&format!("node-{}", i), vec![seed_address]);
+            nodes.push(Arc::new(GossipNode::bind(config).await.unwrap()));
+        }
+        let mut tasks = Vec::new();
+        for node in &nodes {
+            let node = Arc::clone(node);
+            tasks.push(tokio::spawn(async move { node.start().await }));
+        }
+
+        let ids = ["node-0", "node-1", "node-2", "node-3"];
+        let converged = wait_for(|| async {
+            for node in &nodes {
+                if states(node, &ids).await != vec![Some(MemberState::Alive); 4] {
+                    return false;
+                }
+            }
+            true
+        })
+        .await;
+        assert!(converged);
+
+        // Stop node-3: its sockets close once the task and last handle are gone
+        tasks.pop().unwrap().abort();
+        let stopped = nodes.pop().unwrap();
+        while Arc::strong_count(&stopped) > 1 {
+            sleep(Duration::from_millis(5)).await;
+        }
+        drop(stopped);
+
+        let detected = wait_for(|| async {
+            for node in &nodes {
+                if node.member_state("node-3").await != Some(MemberState::Dead) {
+                    return false;
+                }
+            }
+            true
+        })
+        .await;
+        assert!(detected);
+        for node in &nodes {
+            assert_eq!(
+                states(node, &ids[..3]).await,
+                vec![Some(MemberState::Alive); 3]
+            );
+        }
+        for task in tasks {
+            task.abort();
+        }
+    }
+
+    /// Raw socket standing in for a member so the test controls its replies
+    async fn fake_member() -> (UdpSocket, SocketAddr) {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let address = socket.local_addr().unwrap();
+        (socket, address)
+    }
+
+    async fn recv_message(socket: &UdpSocket) -> GossipMessage {
+        let mut buffer = vec![0u8; 2048];
+        let (len, _) = tokio::time::timeout(Duration::from_secs(2), socket.recv_from(&mut buffer))
+            .await
+            .expect("no message within timeout")
+            .unwrap();
+        wire::decode(&buffer[..len]).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_indirect_probe_keeps_unreachable_member_alive() {
+        let node = GossipNode::bind(loopback_config("node-0", vec![]))
+            .await
+            .unwrap();
+        node.join().await;
+        let (target, target_address) = fake_member().await;
+        let (helper, helper_address) = fake_member().await;
+        node.handle_gossip_message(GossipMessage::Gossip {
+            nodes: vec![
+                node_info("target", target_address),
+                node_info("helper", helper_address),
+            ],
+        })
+        .await;
+
+        // Probe the target directly and let the ping go unanswered
+        let seq = node.next_seq.load(Ordering::Relaxed);
+        node.probes.write().await.insert(
+            seq,
+            Probe {
+                target: "target".to_string(),
+                sent_at: GossipNode::current_time(),
+                indirect_sent: false,
+                requester: None,
+            },
+        );
+        node.next_seq.fetch_add(1, Ordering::Relaxed);
+        sleep(Duration::from_millis(30)).await;
+        node.check_probes(GossipNode::current_time()).await;
+
+        // The helper is asked to ping the target on our behalf
+        let request = loop {
+            match recv_message(&helper).await {
+                GossipMessage::PingReq {
+                    target,
+                    target_address: address,
+                    seq: request_seq,
+                    ..
+                } => {
+                    assert_eq!(target, "target");
+                    assert_eq!(address, target_address);
+                    break request_seq;
+                }
+                _ => continue,
+            }
+        };
+        assert_eq!(request, seq);
+
+        // The helper reaches the target and relays its pong
+        let frame = wire::encode(&GossipMessage::Pong {
+            node_id: "target".to_string(),
+            seq,
+        })
+        .unwrap();
+        helper.send_to(&frame, node.address()).await.unwrap();
+        let transport = node.transport.clone().unwrap();
+        let pong = tokio::time::timeout(Duration::from_secs(2), transport.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        node.handle_gossip_message(pong).await;
+
+        sleep(Duration::from_millis(70)).await;
+        node.check_probes(GossipNode::current_time()).await;
+        assert_eq!(node.member_state("target").await, Some(MemberState::Alive));
+        drop(target);
+    }
+
+    #[tokio::test]
+    async fn test_unanswered_probe_leads_to_suspicion_then_death() {
+        let node = GossipNode::bind(loopback_config("node-0", vec![]))
+            .await
+            .unwrap();
+        node.join().await;
+        let (_target, target_address) = fake_member().await;
+        node.handle_gossip_message(GossipMessage::Gossip {
+            nodes: vec![node_info("target", target_address)],
+        })
+        .await;
+
+        node.probe_next().await;
+        sleep(Duration::from_millis(70)).await;
+        node.check_probes(GossipNode::current_time()).await;
+        assert_eq!(
+            node.member_state("target").await,
+            Some(MemberState::Suspect)
+        );
+
+        sleep(Duration::from_millis(220)).await;
+        node.check_suspicions(GossipNode::current_time()).await;
+        assert_eq!(node.member_state("target").await, Some(MemberState::Dead));
+        assert!(node
+            .get_alive_nodes()
+            .await
+            .iter()
+            .all(|n| n.id != "target"));
+    }
+
+    #[tokio::test]
+    async fn test_node_refutes_suspicion_with_higher_incarnation() {
+        let node = GossipNode::new(GossipConfig::default());
+        node.join().await;
+        let mut rumour = node_info("node-0", node.address());
+        rumour.state = MemberState::Suspect;
+        node.handle_gossip_message(GossipMessage::Gossip {
+            nodes: vec![rumour],
+        })
+        .await;
+        assert_eq!(node.incarnation().await, 1);
+        assert_eq!(node.member_state("node-0").await, Some(MemberState::Alive));
+
+        // The refutation overrides the suspicion wherever it is gossiped
+        let mut suspected = node_info("peer", node.address());
+        suspected.state = MemberState::Suspect;
+        let mut refuted = suspected.clone();
+        refuted.state = MemberState::Alive;
+        refuted.incarnation = 1;
+        assert!(refuted.overrides(&suspected));
+        assert!(!suspected.overrides(&refuted));
+    }
+
+    #[tokio::test]
+    async fn test_stream_sends_to_stalled_peers_time_out() {
+        let node = GossipNode::bind(loopback_config("node-0", vec![]))
+            .await
+            .unwrap();
+        // A peer whose accept queue is full drops further connection attempts,
+        // so a connect hangs as it would towards a blackholed address
+        let socket = tokio::net::TcpSocket::new_v4().unwrap();
+        socket.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let stalled_address = socket.local_addr().unwrap();
+        let _listener = socket.listen(0).unwrap();
+        let mut queued = Vec::new();
+        for _ in 0..4 {
+            let connect = tokio::net::TcpStream::connect(stalled_address);
+            if let Ok(Ok(stream)) = tokio::time::timeout(Duration::from_millis(100), connect).await
+            {
+                queued.push(stream);
+            }
+        }
+
+        let message = GossipMessage::Gossip {
+            nodes: (0..100)
+                .map(|i| node_info(&format!("node-{}", i), stalled_address))
+                .collect(),
+        };
+        assert!(wire::encode(&message).unwrap().len() > MAX_DATAGRAM_BYTES);
+
+        let transport = node.transport.clone().unwrap();
+        let started = std::time::Instant::now();
+        assert!(transport.send(stalled_address, &message).await.is_err());
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
 }
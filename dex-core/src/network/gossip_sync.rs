@@ -1,12 +1,30 @@
 //! Gossip protocol implementation for off-chain sync
 //!
+//! Every [`SyncData`] entry carries a hybrid logical clock version. Concurrent
+//! updates to plain entries resolve last-writer-wins by that version, while
+//! entries holding a [`CrdtValue`] are merged. Replicas reconcile through
+//! Merkle-digest anti-entropy ([`super::anti_entropy`]): they swap digests and
+//! then only the entries of key ranges whose digests differ.
+//!
+//! With an authenticator configured, every entry is signed by its origin and
+//! entries are only merged if that signature verifies, whichever peer relays
+//! them. A CRDT merge yielding a payload neither side held is a new entry
+//! originating from the merging node.
+//!
 //! This module implements the Priority 3 feature from DEX-OS-V2.csv:
 //! - Security,Security,Security,Gossip Protocol,Off-chain Sync,Medium
 
+use super::anti_entropy::{entries_in_buckets, MerkleDigest};
+use super::auth::{
+    AuthError, AuthStats, EncryptedFrame, GossipAuthenticator, HandshakeAccept, HandshakeInit,
+    OriginSignature, SignedMessage,
+};
+use super::crdt::{ClockError, CrdtValue, HlcTimestamp, HybridLogicalClock, CRDT_DATA_TYPE};
+use rand::seq::SliceRandom;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 use tokio::sync::RwLock;
@@ -53,8 +71,43 @@ pub struct SyncData {
     pub timestamp: u64,
     /// Origin node ID
     pub origin: String,
-    /// Data type/category
+    /// Data type/category; [`CRDT_DATA_TYPE`] marks a [`CrdtValue`] payload
     pub data_type: String,
+    /// Hybrid logical clock timestamp of the latest update
+    #[serde(default)]
+    pub version: HlcTimestamp,
+    /// Origin's signature over the entry, see [`SyncData::signed_bytes`]
+    #[serde(default)]
+    pub signature: Option<OriginSignature>,
+}
+
+impl SyncData {
+    /// Whether this version of an entry replaces `other` under
+    /// last-writer-wins. Ties on the clock fall back to the wall-clock
+    /// timestamp and then the payload, so every replica picks the same winner.
+    fn supersedes(&self, other: &SyncData) -> bool {
+        (&self.version, self.timestamp, &self.payload)
+            > (&other.version, other.timestamp, &other.payload)
+    }
+
+    /// Everything the origin signs: the entry without its signature
+    pub fn signed_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for field in [
+            self.id.as_bytes(),
+            self.origin.as_bytes(),
+            self.data_type.as_bytes(),
+            self.version.node_id.as_bytes(),
+            &self.payload,
+        ] {
+            bytes.extend_from_slice(&(field.len() as u64).to_be_bytes());
+            bytes.extend_from_slice(field);
+        }
+        bytes.extend_from_slice(&self.timestamp.to_be_bytes());
+        bytes.extend_from_slice(&self.version.wall_ms.to_be_bytes());
+        bytes.extend_from_slice(&self.version.logical.to_be_bytes());
+        bytes
+    }
 }
 
 /// Gossip sync message types
@@ -76,6 +129,47 @@ pub enum GossipSyncMessage {
     DataUpdateRequest,
     /// Response with latest data updates
     DataUpdateResponse { data: Vec<SyncData> },
+    /// Start of anti-entropy: the sender's Merkle digest
+    DigestSync {
+        node_id: String,
+        digest: MerkleDigest,
+    },
+    /// Entries of the buckets that differ from the digest, sent back so that
+    /// the initiator can merge them and return its own
+    DivergentData {
+        node_id: String,
+        buckets: Vec<usize>,
+        data: Vec<SyncData>,
+    },
+}
+
+impl GossipSyncMessage {
+    /// Node the message claims to come from, if it names one
+    fn claimed_sender(&self) -> Option<&str> {
+        match self {
+            GossipSyncMessage::Ping { node_id }
+            | GossipSyncMessage::Pong { node_id }
+            | GossipSyncMessage::DigestSync { node_id, .. }
+            | GossipSyncMessage::DivergentData { node_id, .. } => Some(node_id),
+            GossipSyncMessage::DataBroadcast { data } => Some(&data.origin),
+            _ => None,
+        }
+    }
+}
+
+/// A gossip sync message as it travels between nodes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum GossipSyncEnvelope {
+    /// Unauthenticated message, only accepted by nodes without an identity
+    Plain(GossipSyncMessage),
+    /// Message signed by its sender
+    Signed(SignedMessage),
+    /// Signed message encrypted for a session peer
+    Encrypted(EncryptedFrame),
+    /// Opening of an encrypted session
+    HandshakeInit(HandshakeInit),
+    /// Answer completing an encrypted session
+    HandshakeAccept(HandshakeAccept),
 }
 
 /// Information about a node in the network
@@ -105,6 +199,11 @@ pub struct GossipSyncNode {
     node_timeout: Duration,
     /// Sync interval
     sync_interval: Duration,
+    /// Clock versioning local updates
+    clock: Arc<Mutex<HybridLogicalClock>>,
+    /// Identity and trusted peers; when set, only authenticated messages
+    /// are accepted
+    auth: Option<Arc<GossipAuthenticator>>,
 }
 
 impl GossipSyncNode {
@@ -116,6 +215,7 @@ impl GossipSyncNode {
         let gossip_interval = Duration::from_millis(config.gossip_interval_ms);
         let node_timeout = Duration::from_millis(config.node_timeout_ms);
         let sync_interval = Duration::from_millis(config.sync_interval_ms);
+        let clock = Arc::new(Mutex::new(HybridLogicalClock::new(&config.node_id)));
 
         Self {
             config,
@@ -125,9 +225,18 @@ impl GossipSyncNode {
             gossip_interval,
             node_timeout,
             sync_interval,
+            clock,
+            auth: None,
         }
     }
 
+    /// Require every incoming message to be signed by a trusted peer, signing
+    /// our own with the authenticator's identity
+    pub fn with_authenticator(mut self, authenticator: GossipAuthenticator) -> Self {
+        self.auth = Some(Arc::new(authenticator));
+        self
+    }
+
     /// Start the gossip sync node
     pub async fn start(&self) {
         // Add ourselves to the node list
@@ -202,16 +311,12 @@ impl GossipSyncNode {
         // Get a list of peers to sync with
         let peers = self.get_gossip_peers().await;
 
-        if peers.is_empty() {
+        // Run anti-entropy with one random peer per interval
+        let Some(peer) = peers.choose(&mut rand::thread_rng()) else {
             return;
-        }
-
-        // For now, sync with the first peer
-        // In a real implementation, we would sync with multiple peers
-        let peer = &peers[0];
-        
-        // Request data updates from peer
-        self.request_data_updates(peer).await;
+        };
+        let message = self.anti_entropy_request().await;
+        self.send_sync_message(peer, message).await;
     }
 
     /// Get list of peers to gossip with
@@ -250,64 +355,198 @@ impl GossipSyncNode {
         // Simulate network communication (omitted to avoid recursive scheduling in tests)
     }
 
-    /// Request data updates from a peer
-    async fn request_data_updates(&self, peer: &NodeInfo) {
+    /// Send a sync message to a peer
+    async fn send_sync_message(&self, peer: &NodeInfo, _message: GossipSyncMessage) {
         println!(
-            "Requesting data updates from node {} at {}",
+            "Sending sync message to node {} at {}",
             peer.id, peer.address
         );
 
-        // In a real implementation, we would send this request over the network
-        let message = GossipSyncMessage::DataUpdateRequest;
-
-        // Simulate network communication
-        self.handle_gossip_message(message).await;
+        // In a real implementation, we would serialize the message and send it
+        // over the network; the peer's reply goes back to handle_gossip_message
     }
 
-    /// Handle incoming gossip message
-    pub async fn handle_gossip_message(&self, message: GossipSyncMessage) {
+    /// Handle incoming gossip message, returning the reply for the sender if
+    /// the message calls for one
+    pub async fn handle_gossip_message(
+        &self,
+        message: GossipSyncMessage,
+    ) -> Option<GossipSyncMessage> {
         match message {
-            GossipSyncMessage::Ping { node_id } => {
-                println!("Received ping from node {}", node_id);
-                // In a real implementation, we would send a pong response
-            }
+            GossipSyncMessage::Ping { .. } => Some(GossipSyncMessage::Pong {
+                node_id: self.config.node_id.clone(),
+            }),
             GossipSyncMessage::Pong { node_id } => {
-                println!("Received pong from node {}", node_id);
                 // Update last seen time for this node
                 let mut nodes = self.nodes.write().await;
                 if let Some(node) = nodes.get_mut(&node_id) {
                     node.last_seen = Self::current_time();
                 }
+                None
             }
             GossipSyncMessage::Gossip { nodes } => {
-                println!("Received gossip message with {} nodes", nodes.len());
                 self.update_node_list(nodes).await;
+                None
             }
             GossipSyncMessage::SyncRequest { data_ids } => {
-                println!("Received sync request for {} data items", data_ids.len());
-                // In this demo, skip re-entrant handling to avoid recursive futures
+                Some(self.handle_sync_request(data_ids).await)
             }
-            GossipSyncMessage::SyncResponse { data } => {
-                println!("Received sync response with {} data items", data.len());
-                self.handle_sync_response(data).await;
+            GossipSyncMessage::SyncResponse { data }
+            | GossipSyncMessage::DataUpdateResponse { data } => {
+                self.merge_remote_data(data).await;
+                None
             }
             GossipSyncMessage::DataBroadcast { data } => {
-                println!("Received data broadcast for item {}", data.id);
-                self.handle_data_broadcast(data).await;
+                self.merge_remote_data(vec![data]).await;
+                None
             }
             GossipSyncMessage::DataUpdateRequest => {
-                println!("Received data update request");
-                self.handle_data_update_request().await;
+                let sync_data = self.sync_data.read().await;
+                Some(GossipSyncMessage::DataUpdateResponse {
+                    data: sync_data.values().cloned().collect(),
+                })
             }
-            GossipSyncMessage::DataUpdateResponse { data } => {
-                println!("Received data update response with {} items", data.len());
-                self.handle_data_update_response(data).await;
+            GossipSyncMessage::DigestSync { digest, .. } => {
+                let sync_data = self.sync_data.read().await;
+                let buckets = MerkleDigest::build(&sync_data).divergent_buckets(&digest);
+                if buckets.is_empty() {
+                    return None;
+                }
+                let data = entries_in_buckets(&sync_data, &buckets);
+                Some(GossipSyncMessage::DivergentData {
+                    node_id: self.config.node_id.clone(),
+                    buckets,
+                    data,
+                })
+            }
+            GossipSyncMessage::DivergentData { buckets, data, .. } => {
+                self.merge_remote_data(data).await;
+                // Our merged entries let the peer converge to the same state
+                let sync_data = self.sync_data.read().await;
+                Some(GossipSyncMessage::SyncResponse {
+                    data: entries_in_buckets(&sync_data, &buckets),
+                })
             }
         }
     }
 
+    /// Handle a message received from the network, returning the reply for
+    /// the sender. Nodes with an authenticator reject plain messages and
+    /// anything not signed by a trusted peer; replies to signed or encrypted
+    /// messages are signed or encrypted in turn.
+    pub async fn receive(
+        &self,
+        envelope: GossipSyncEnvelope,
+    ) -> Result<Option<GossipSyncEnvelope>, GossipSyncError> {
+        let Some(auth) = &self.auth else {
+            return match envelope {
+                GossipSyncEnvelope::Plain(message) => Ok(self
+                    .handle_gossip_message(message)
+                    .await
+                    .map(GossipSyncEnvelope::Plain)),
+                _ => Err(GossipSyncError::AuthenticationNotConfigured),
+            };
+        };
+
+        match envelope {
+            GossipSyncEnvelope::Plain(_) => {
+                auth.record_rejection(&AuthError::Unauthenticated);
+                Err(GossipSyncError::Authentication(AuthError::Unauthenticated))
+            }
+            GossipSyncEnvelope::Signed(signed) => {
+                let message = auth
+                    .verify(&signed, |payload| {
+                        Self::decode_from(&signed.sender, payload)
+                    })
+                    .map_err(GossipSyncError::Authentication)?;
+                match self.handle_gossip_message(message).await {
+                    Some(reply) => self.sign_message(&reply).map(Some),
+                    None => Ok(None),
+                }
+            }
+            GossipSyncEnvelope::Encrypted(frame) => {
+                let message = auth
+                    .open(&frame, |payload| Self::decode_from(&frame.sender, payload))
+                    .map_err(GossipSyncError::Authentication)?;
+                match self.handle_gossip_message(message).await {
+                    Some(reply) => self.encrypt_message(&frame.sender, &reply).map(Some),
+                    None => Ok(None),
+                }
+            }
+            GossipSyncEnvelope::HandshakeInit(init) => auth
+                .accept_handshake(&init)
+                .map(|accept| Some(GossipSyncEnvelope::HandshakeAccept(accept)))
+                .map_err(GossipSyncError::Authentication),
+            GossipSyncEnvelope::HandshakeAccept(accept) => auth
+                .complete_handshake(&accept)
+                .map(|()| None)
+                .map_err(GossipSyncError::Authentication),
+        }
+    }
+
+    /// Decode a verified payload, rejecting messages that claim to come from
+    /// a node other than the signer
+    fn decode_from(sender: &str, payload: &[u8]) -> Result<GossipSyncMessage, AuthError> {
+        let message: GossipSyncMessage =
+            serde_json::from_slice(payload).map_err(|e| AuthError::Malformed(e.to_string()))?;
+        match message.claimed_sender() {
+            Some(claimed) if claimed != sender => Err(AuthError::Spoofed(claimed.to_string())),
+            _ => Ok(message),
+        }
+    }
+
+    /// Sign `message` with our identity
+    pub fn sign_message(
+        &self,
+        message: &GossipSyncMessage,
+    ) -> Result<GossipSyncEnvelope, GossipSyncError> {
+        let auth = self
+            .auth
+            .as_ref()
+            .ok_or(GossipSyncError::AuthenticationNotConfigured)?;
+        let payload = serde_json::to_vec(message)
+            .map_err(|e| GossipSyncError::SerializationError(e.to_string()))?;
+        Ok(GossipSyncEnvelope::Signed(auth.sign(payload)))
+    }
+
+    /// Sign `message` and encrypt it for `peer`, with whom a session must
+    /// have been established through [`Self::initiate_handshake`]
+    pub fn encrypt_message(
+        &self,
+        peer: &str,
+        message: &GossipSyncMessage,
+    ) -> Result<GossipSyncEnvelope, GossipSyncError> {
+        let GossipSyncEnvelope::Signed(signed) = self.sign_message(message)? else {
+            unreachable!("sign_message returns signed envelopes");
+        };
+        let auth = self
+            .auth
+            .as_ref()
+            .ok_or(GossipSyncError::AuthenticationNotConfigured)?;
+        auth.seal(peer, &signed)
+            .map(GossipSyncEnvelope::Encrypted)
+            .map_err(GossipSyncError::Authentication)
+    }
+
+    /// Start an encrypted session with `peer`; its answer goes to
+    /// [`Self::receive`]
+    pub fn initiate_handshake(&self, peer: &str) -> Result<GossipSyncEnvelope, GossipSyncError> {
+        let auth = self
+            .auth
+            .as_ref()
+            .ok_or(GossipSyncError::AuthenticationNotConfigured)?;
+        Ok(GossipSyncEnvelope::HandshakeInit(
+            auth.initiate_handshake(peer),
+        ))
+    }
+
+    /// Accepted and rejected message counts, if authentication is enabled
+    pub fn auth_stats(&self) -> Option<AuthStats> {
+        self.auth.as_ref().map(|auth| auth.stats())
+    }
+
     /// Handle sync request from another node
-    async fn handle_sync_request(&self, data_ids: Vec<String>) {
+    async fn handle_sync_request(&self, data_ids: Vec<String>) -> GossipSyncMessage {
         let sync_data = self.sync_data.read().await;
         let mut response_data = Vec::new();
 
@@ -317,69 +556,106 @@ impl GossipSyncNode {
             }
         }
 
-        // In a real implementation, we would send this response back to the requesting node
-        let _response = GossipSyncMessage::SyncResponse {
+        GossipSyncMessage::SyncResponse {
             data: response_data,
-        };
-        
-        // In this demo, skip re-entrant handling to avoid recursive futures
+        }
     }
 
-    /// Handle sync response from another node
-    async fn handle_sync_response(&self, data: Vec<SyncData>) {
+    /// Merge entries received from another node. With an authenticator,
+    /// entries whose origin signature does not verify are dropped.
+    async fn merge_remote_data(&self, mut data: Vec<SyncData>) {
+        if let Some(auth) = &self.auth {
+            data.retain(|item| match Self::verify_origin(auth, item) {
+                Ok(()) => true,
+                Err(err) => {
+                    auth.record_rejection(&err);
+                    false
+                }
+            });
+        }
+        {
+            // Later local updates must be ordered after everything we have
+            // seen. Entries stamped too far in the future are dropped, so
+            // they cannot win every later write.
+            let mut clock = self.clock.lock().unwrap();
+            data.retain(|item| clock.observe(&item.version).is_ok());
+        }
         let mut sync_data = self.sync_data.write().await;
-        
         for item in data {
-            // Only update if we don't have this data or if the received data is newer
-            if let Some(existing) = sync_data.get(&item.id) {
-                if item.timestamp > existing.timestamp {
-                    sync_data.insert(item.id.clone(), item);
-                }
-            } else {
-                sync_data.insert(item.id.clone(), item);
-            }
+            self.merge_entry(&mut sync_data, item);
         }
     }
 
-    /// Handle data broadcast from another node
-    async fn handle_data_broadcast(&self, data: SyncData) {
-        let mut sync_data = self.sync_data.write().await;
-        
-        // Only update if we don't have this data or if the received data is newer
-        if let Some(existing) = sync_data.get(&data.id) {
-            if data.timestamp > existing.timestamp {
-                sync_data.insert(data.id.clone(), data);
-            }
-        } else {
-            sync_data.insert(data.id.clone(), data);
-        }
+    fn verify_origin(auth: &GossipAuthenticator, item: &SyncData) -> Result<(), AuthError> {
+        let signature = item.signature.as_ref().ok_or(AuthError::Unauthenticated)?;
+        auth.verify_origin(&item.origin, &item.signed_bytes(), signature)
     }
 
-    /// Handle data update request from another node
-    async fn handle_data_update_request(&self) {
-        let sync_data = self.sync_data.read().await;
-        let data: Vec<SyncData> = sync_data.values().cloned().collect();
-        
-        // In a real implementation, we would send this response back to the requesting node
-        let _response = GossipSyncMessage::DataUpdateResponse { data };
-        
-        // In this demo, skip re-entrant handling to avoid recursive futures
+    /// Mark `data` as originating from us, signing it if authentication is
+    /// enabled
+    fn sign_as_origin(&self, data: &mut SyncData) {
+        data.origin = self.config.node_id.clone();
+        data.signature = self
+            .auth
+            .as_ref()
+            .map(|auth| auth.sign_origin(&data.signed_bytes()));
     }
 
-    /// Handle data update response from another node
-    async fn handle_data_update_response(&self, data: Vec<SyncData>) {
-        let mut sync_data = self.sync_data.write().await;
-        
-        for item in data {
-            // Only update if we don't have this data or if the received data is newer
-            if let Some(existing) = sync_data.get(&item.id) {
-                if item.timestamp > existing.timestamp {
-                    sync_data.insert(item.id.clone(), item);
+    /// Merge one entry into the store: CRDT payloads of the same kind are
+    /// merged, anything else resolves last-writer-wins. A merged payload
+    /// that neither side held becomes a new entry of ours, since nobody else
+    /// has signed it.
+    fn merge_entry(&self, sync_data: &mut HashMap<String, SyncData>, item: SyncData) {
+        let Some(existing) = sync_data.get_mut(&item.id) else {
+            sync_data.insert(item.id.clone(), item);
+            return;
+        };
+
+        if existing.data_type == CRDT_DATA_TYPE && item.data_type == CRDT_DATA_TYPE {
+            if let (Some(mut merged), Some(incoming)) = (
+                CrdtValue::decode(&existing.payload),
+                CrdtValue::decode(&item.payload),
+            ) {
+                if merged.merge(&incoming) {
+                    let payload = merged.encode();
+                    if payload == item.payload {
+                        // Equal payloads keep the newer metadata so both
+                        // replicas agree
+                        if payload != existing.payload || item.supersedes(existing) {
+                            *existing = item;
+                        }
+                    } else if payload != existing.payload {
+                        let Ok(version) = self.clock.lock().unwrap().now() else {
+                            return;
+                        };
+                        existing.payload = payload;
+                        existing.version = version;
+                        existing.timestamp = existing.version.wall_ms;
+                        self.sign_as_origin(existing);
+                    }
+                    return;
                 }
-            } else {
-                sync_data.insert(item.id.clone(), item);
             }
         }
+
+        if item.supersedes(existing) {
+            *existing = item;
+        }
+    }
+
+    /// Merkle digest of our data
+    pub async fn digest(&self) -> MerkleDigest {
+        MerkleDigest::build(&*self.sync_data.read().await)
+    }
+
+    /// Message starting an anti-entropy round with a peer. The peer answers
+    /// with [`GossipSyncMessage::DivergentData`], whose handling produces the
+    /// final [`GossipSyncMessage::SyncResponse`] for the peer.
+    pub async fn anti_entropy_request(&self) -> GossipSyncMessage {
+        GossipSyncMessage::DigestSync {
+            node_id: self.config.node_id.clone(),
+            digest: self.digest().await,
+        }
     }
 
     /// Update our node list with information from gossip message
@@ -462,10 +738,53 @@ impl GossipSyncNode {
         alive_nodes.remove(node_id);
     }
 
-    /// Add data to be synced
-    pub async fn add_sync_data(&self, data: SyncData) {
+    /// Add data to be synced, originating from us and versioned with our
+    /// clock
+    pub async fn add_sync_data(&self, mut data: SyncData) -> Result<(), GossipSyncError> {
+        data.version = self.clock.lock().unwrap().now()?;
+        self.sign_as_origin(&mut data);
         let mut sync_data = self.sync_data.write().await;
         sync_data.insert(data.id.clone(), data);
+        Ok(())
+    }
+
+    /// Merge `value` into the CRDT stored under `id`, creating it if needed
+    pub async fn update_crdt(&self, id: &str, value: CrdtValue) -> Result<(), GossipSyncError> {
+        let version = self.clock.lock().unwrap().now()?;
+        let mut sync_data = self.sync_data.write().await;
+        let mut merged = value;
+        if let Some(existing) = sync_data.get(id) {
+            let current = CrdtValue::decode(&existing.payload)
+                .filter(|_| existing.data_type == CRDT_DATA_TYPE)
+                .ok_or_else(|| GossipSyncError::CrdtMismatch(id.to_string()))?;
+            let mut combined = current;
+            if !combined.merge(&merged) {
+                return Err(GossipSyncError::CrdtMismatch(id.to_string()));
+            }
+            merged = combined;
+        }
+
+        let mut data = SyncData {
+            id: id.to_string(),
+            payload: merged.encode(),
+            timestamp: version.wall_ms,
+            origin: self.config.node_id.clone(),
+            data_type: CRDT_DATA_TYPE.to_string(),
+            version,
+            signature: None,
+        };
+        self.sign_as_origin(&mut data);
+        sync_data.insert(id.to_string(), data);
+        Ok(())
+    }
+
+    /// Get the CRDT stored under `id`
+    pub async fn get_crdt(&self, id: &str) -> Option<CrdtValue> {
+        let sync_data = self.sync_data.read().await;
+        sync_data
+            .get(id)
+            .filter(|data| data.data_type == CRDT_DATA_TYPE)
+            .and_then(|data| CrdtValue::decode(&data.payload))
     }
 
     /// Get all sync data
@@ -497,6 +816,8 @@ impl Clone for GossipSyncNode {
             gossip_interval: self.gossip_interval,
             node_timeout: self.node_timeout,
             sync_interval: self.sync_interval,
+            clock: Arc::clone(&self.clock),
+            auth: self.auth.clone(),
         }
     }
 }
@@ -508,6 +829,14 @@ pub enum GossipSyncError {
     NetworkError(String),
     #[error("Serialization error: {0}")]
     SerializationError(String),
+    #[error("Entry {0} does not hold a CRDT of the same kind")]
+    CrdtMismatch(String),
+    #[error("Authentication error: {0}")]
+    Authentication(AuthError),
+    #[error("Authentication is not configured for this node")]
+    AuthenticationNotConfigured,
+    #[error("Clock error: {0}")]
+    Clock(#[from] ClockError),
     #[error("Timeout error")]
     Timeout,
 }
@@ -536,9 +865,11 @@ mod tests {
             timestamp: GossipSyncNode::current_time(),
             origin: "test-node".to_string(),
             data_type: "test".to_string(),
+            version: HlcTimestamp::default(),
+            signature: None,
         };
 
-        node.add_sync_data(sync_data.clone()).await;
+        node.add_sync_data(sync_data.clone()).await.unwrap();
 
         let retrieved_data = node.get_data_by_id("test-data").await;
         assert!(retrieved_data.is_some());
@@ -586,4 +917,258 @@ mod tests {
         let alive_nodes = node.get_alive_nodes().await;
         assert_eq!(alive_nodes.len(), 0);
     }
+
+    fn item(id: &str, payload: &[u8]) -> SyncData {
+        SyncData {
+            id: id.to_string(),
+            payload: payload.to_vec(),
+            timestamp: 1,
+            origin: "seed".to_string(),
+            data_type: "test".to_string(),
+            version: HlcTimestamp::default(),
+            signature: None,
+        }
+    }
+
+    fn node(node_id: &str) -> GossipSyncNode {
+        GossipSyncNode::new(GossipSyncConfig {
+            node_id: node_id.to_string(),
+            ..GossipSyncConfig::default()
+        })
+    }
+
+    /// Run one anti-entropy round from `a` to `b`, returning how many entries
+    /// travelled
+    async fn anti_entropy(a: &GossipSyncNode, b: &GossipSyncNode) -> usize {
+        let Some(divergent) = b
+            .handle_gossip_message(a.anti_entropy_request().await)
+            .await
+        else {
+            return 0;
+        };
+        let sent_by_b = match &divergent {
+            GossipSyncMessage::DivergentData { data, .. } => data.len(),
+            other => panic!("unexpected reply {:?}", other),
+        };
+        let response = a.handle_gossip_message(divergent).await.unwrap();
+        let sent_by_a = match &response {
+            GossipSyncMessage::SyncResponse { data } => data.len(),
+            other => panic!("unexpected reply {:?}", other),
+        };
+        assert!(b.handle_gossip_message(response).await.is_none());
+        sent_by_b + sent_by_a
+    }
+
+    #[tokio::test]
+    async fn test_anti_entropy_exchanges_only_divergent_ranges() {
+        let a = node("node-a");
+        let b = node("node-b");
+        let shared: Vec<SyncData> = (0..200)
+            .map(|i| item(&format!("item-{}", i), b"v1"))
+            .collect();
+        for replica in [&a, &b] {
+            replica
+                .handle_gossip_message(GossipSyncMessage::SyncResponse {
+                    data: shared.clone(),
+                })
+                .await;
+        }
+        assert_eq!(anti_entropy(&a, &b).await, 0);
+
+        // Concurrent updates on both sides, including the same entry
+        a.add_sync_data(item("item-3", b"from-a")).await.unwrap();
+        b.add_sync_data(item("item-3", b"from-b")).await.unwrap();
+        b.add_sync_data(item("item-150", b"from-b")).await.unwrap();
+        a.add_sync_data(item("new-on-a", b"x")).await.unwrap();
+
+        let exchanged = anti_entropy(&a, &b).await;
+        assert!(exchanged > 0 && exchanged < 40, "exchanged {}", exchanged);
+        assert_eq!(a.digest().await, b.digest().await);
+        // b's later write wins on both replicas
+        assert_eq!(a.get_data_by_id("item-3").await.unwrap().payload, b"from-b");
+        assert!(b.get_data_by_id("new-on-a").await.is_some());
+        assert_eq!(anti_entropy(&a, &b).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_far_future_entries_are_dropped() {
+        let a = node("node-a");
+        a.add_sync_data(item("price", b"100")).await.unwrap();
+
+        let mut future = item("price", b"999");
+        future.origin = "node-b".to_string();
+        future.version = HlcTimestamp {
+            wall_ms: u64::MAX / 2,
+            logical: 0,
+            node_id: "node-b".to_string(),
+        };
+        a.handle_gossip_message(GossipSyncMessage::SyncResponse { data: vec![future] })
+            .await;
+        assert_eq!(a.get_data_by_id("price").await.unwrap().payload, b"100");
+
+        // Our clock was not dragged forward either
+        a.add_sync_data(item("volume", b"1")).await.unwrap();
+        let version = a.get_data_by_id("volume").await.unwrap().version;
+        assert!(version.wall_ms < u64::MAX / 2);
+    }
+
+    #[tokio::test]
+    async fn test_crdt_entries_merge_instead_of_overwriting() {
+        use crate::network::crdt::{OrSet, PnCounter};
+
+        let a = node("node-a");
+        let b = node("node-b");
+        let mut up = PnCounter::new();
+        up.increment("node-a", 5);
+        a.update_crdt("volume", CrdtValue::Counter(up))
+            .await
+            .unwrap();
+        let mut down = PnCounter::new();
+        down.decrement("node-b", 2);
+        b.update_crdt("volume", CrdtValue::Counter(down))
+            .await
+            .unwrap();
+
+        let mut pairs = OrSet::new();
+        pairs.add("ETH/USDC".to_string(), "node-a");
+        a.update_crdt("pairs", CrdtValue::Set(pairs)).await.unwrap();
+        let mut pairs = OrSet::new();
+        pairs.add("BTC/USDC".to_string(), "node-b");
+        b.update_crdt("pairs", CrdtValue::Set(pairs)).await.unwrap();
+
+        anti_entropy(&a, &b).await;
+        for replica in [&a, &b] {
+            match replica.get_crdt("volume").await {
+                Some(CrdtValue::Counter(counter)) => assert_eq!(counter.value(), 3),
+                other => panic!("unexpected volume {:?}", other),
+            }
+            match replica.get_crdt("pairs").await {
+                Some(CrdtValue::Set(set)) => assert_eq!(set.elements().len(), 2),
+                other => panic!("unexpected pairs {:?}", other),
+            }
+        }
+        assert_eq!(a.digest().await, b.digest().await);
+
+        // Kinds cannot be mixed under one ID
+        assert!(matches!(
+            a.update_crdt("volume", CrdtValue::Set(OrSet::new())).await,
+            Err(GossipSyncError::CrdtMismatch(_))
+        ));
+    }
+
+    fn authenticated_pair() -> (GossipSyncNode, GossipSyncNode) {
+        use crate::network::auth::{NodeIdentity, TrustStore};
+
+        let a_identity = NodeIdentity::generate("node-a");
+        let b_identity = NodeIdentity::generate("node-b");
+        let mut a_trust = TrustStore::new();
+        a_trust.allow("node-b", b_identity.public_key());
+        let mut b_trust = TrustStore::new();
+        b_trust.allow("node-a", a_identity.public_key());
+        (
+            node("node-a").with_authenticator(GossipAuthenticator::new(a_identity, a_trust)),
+            node("node-b").with_authenticator(GossipAuthenticator::new(b_identity, b_trust)),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_authenticated_nodes_reject_unsigned_and_spoofed_data() {
+        let (a, b) = authenticated_pair();
+        a.add_sync_data(item("price", b"100")).await.unwrap();
+        let data = a.get_data_by_id("price").await.unwrap();
+
+        // Unsigned broadcasts no longer get in
+        let plain =
+            GossipSyncEnvelope::Plain(GossipSyncMessage::DataBroadcast { data: data.clone() });
+        assert!(matches!(
+            b.receive(plain).await,
+            Err(GossipSyncError::Authentication(AuthError::Unauthenticated))
+        ));
+
+        // A signed broadcast claiming another origin is refused
+        let mut spoofed = data.clone();
+        spoofed.origin = "node-c".to_string();
+        let envelope = a
+            .sign_message(&GossipSyncMessage::DataBroadcast { data: spoofed })
+            .unwrap();
+        assert!(matches!(
+            b.receive(envelope).await,
+            Err(GossipSyncError::Authentication(AuthError::Spoofed(_)))
+        ));
+        assert!(b.get_data_by_id("price").await.is_none());
+
+        let envelope = a
+            .sign_message(&GossipSyncMessage::DataBroadcast { data })
+            .unwrap();
+        assert!(b.receive(envelope).await.unwrap().is_none());
+        assert_eq!(b.get_data_by_id("price").await.unwrap().payload, b"100");
+
+        let stats = b.auth_stats().unwrap();
+        assert_eq!(stats.accepted, 1);
+        assert_eq!(stats.rejected(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_relayed_entries_need_their_origin_signature() {
+        let (a, b) = authenticated_pair();
+        a.add_sync_data(item("price", b"100")).await.unwrap();
+        let genuine = a.get_data_by_id("price").await.unwrap();
+
+        let mut altered = genuine.clone();
+        altered.id = "fee".to_string();
+        let mut forged = genuine.clone();
+        forged.id = "limit".to_string();
+        forged.origin = "node-c".to_string();
+        let mut unsigned = item("quota", b"1");
+        unsigned.origin = "node-a".to_string();
+
+        // Responses name no sender, so only the origin signatures vouch for
+        // the entries they relay
+        let envelope = a
+            .sign_message(&GossipSyncMessage::SyncResponse {
+                data: vec![genuine, altered, forged, unsigned],
+            })
+            .unwrap();
+        assert!(b.receive(envelope).await.unwrap().is_none());
+        assert_eq!(b.get_data_by_id("price").await.unwrap().payload, b"100");
+        for id in ["fee", "limit", "quota"] {
+            assert!(b.get_data_by_id(id).await.is_none(), "{} merged", id);
+        }
+        let stats = b.auth_stats().unwrap();
+        assert_eq!(stats.bad_signature, 1);
+        assert_eq!(stats.untrusted, 1);
+        assert_eq!(stats.unauthenticated, 1);
+
+        // Our own entries coming back keep verifying against our key
+        let echo = b
+            .sign_message(&GossipSyncMessage::DataUpdateResponse {
+                data: b.get_sync_data().await.into_values().collect(),
+            })
+            .unwrap();
+        assert!(a.receive(echo).await.unwrap().is_none());
+        assert_eq!(a.auth_stats().unwrap().rejected(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_session_carries_anti_entropy() {
+        let (a, b) = authenticated_pair();
+        b.add_sync_data(item("balance", b"42")).await.unwrap();
+
+        let init = a.initiate_handshake("node-b").unwrap();
+        let accept = b.receive(init).await.unwrap().unwrap();
+        assert!(a.receive(accept).await.unwrap().is_none());
+
+        let request = a
+            .encrypt_message("node-b", &a.anti_entropy_request().await)
+            .unwrap();
+        let divergent = b.receive(request).await.unwrap().unwrap();
+        assert!(matches!(divergent, GossipSyncEnvelope::Encrypted(_)));
+        let response = a.receive(divergent.clone()).await.unwrap().unwrap();
+        assert!(b.receive(response).await.unwrap().is_none());
+        assert_eq!(a.digest().await, b.digest().await);
+
+        // Replaying a frame is rejected and counted
+        assert!(a.receive(divergent).await.is_err());
+        assert_eq!(a.auth_stats().unwrap().replayed, 1);
+    }
 }
@@ -4,8 +4,24 @@
 //! Implements the Priority 3 feature from DEX-OS-V2.csv:
 //! - Infrastructure,Network,Network,Gossip Protocol,Node Discovery,Medium
 
+pub mod anti_entropy;
+pub mod auth;
+pub mod crdt;
 pub mod gossip;
 pub mod gossip_sync;
+pub mod transport;
+pub mod wire;
 
-pub use gossip::{GossipConfig, GossipError, GossipNode};
-pub use gossip_sync::{GossipSyncConfig, GossipSyncError, GossipSyncNode, SyncData};
+pub use anti_entropy::MerkleDigest;
+pub use auth::{
+    AuthError, AuthStats, GossipAuthenticator, NodeIdentity, OriginSignature, TrustStore,
+};
+pub use crdt::{
+    ClockError, CrdtValue, HlcTimestamp, HybridLogicalClock, LwwRegister, OrSet, PnCounter,
+};
+pub use gossip::{GossipConfig, GossipError, GossipMessage, GossipNode, MemberState, NodeInfo};
+pub use gossip_sync::{
+    GossipSyncConfig, GossipSyncEnvelope, GossipSyncError, GossipSyncMessage, GossipSyncNode,
+    SyncData,
+};
+pub use transport::GossipTransport;
@@ -0,0 +1,182 @@
+//! Socket transport for gossip messages
+//!
+//! [`GossipTransport`] binds a UDP socket and a TCP listener on the same
+//! address. Frames produced by [`super::wire`] go out as single UDP datagrams
+//! when they fit in [`MAX_DATAGRAM_BYTES`], so probes stay cheap; larger frames,
+//! such as membership lists of big clusters, fall back to a length-prefixed
+//! TCP connection, which gives up after the transport's stream timeout so an
+//! unreachable peer cannot hold up the sender. Delivery is best effort either
+//! way: SWIM tolerates loss.
+//!
+//! Implements the Priority 3 feature from DEX-OS-V2.csv:
+//! - Infrastructure,Network,Network,Gossip Protocol,Node Discovery,Medium
+
+use super::gossip::{GossipError, GossipMessage};
+use super::wire;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+/// Largest frame sent as a single UDP datagram, below common path MTUs
+pub const MAX_DATAGRAM_BYTES: usize = 1400;
+
+/// Largest frame accepted over TCP
+const MAX_STREAM_FRAME_BYTES: usize = 4 * 1024 * 1024;
+
+/// How long a TCP send may take unless configured otherwise
+const DEFAULT_STREAM_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// UDP socket plus TCP listener delivering decoded gossip messages
+pub struct GossipTransport {
+    /// Socket used for datagrams in both directions
+    socket: Arc<UdpSocket>,
+    /// Address both sockets are bound to
+    local_addr: SocketAddr,
+    /// Messages decoded by the receive tasks
+    inbox: Mutex<UnboundedReceiver<GossipMessage>>,
+    /// Receive tasks, stopped when the transport is dropped
+    tasks: Vec<JoinHandle<()>>,
+    /// Bound on connecting and writing a TCP frame
+    stream_timeout: Duration,
+}
+
+impl GossipTransport {
+    /// Bind UDP and TCP on `address`. With port 0 an ephemeral port free for
+    /// both protocols is chosen.
+    pub async fn bind(address: SocketAddr) -> Result<Self, GossipError> {
+        let attempts = if address.port() == 0 { 8 } else { 1 };
+        let mut last_error = None;
+        for _ in 0..attempts {
+            let socket = UdpSocket::bind(address).await.map_err(network_error)?;
+            let local_addr = socket.local_addr().map_err(network_error)?;
+            match TcpListener::bind(local_addr).await {
+                Ok(listener) => return Ok(Self::serve(socket, listener, local_addr)),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(network_error(
+            last_error.expect("at least one bind attempt was made"),
+        ))
+    }
+
+    fn serve(socket: UdpSocket, listener: TcpListener, local_addr: SocketAddr) -> Self {
+        let socket = Arc::new(socket);
+        let (tx, rx) = unbounded_channel();
+        let tasks = vec![
+            tokio::spawn(Self::receive_datagrams(Arc::clone(&socket), tx.clone())),
+            tokio::spawn(Self::accept_streams(listener, tx)),
+        ];
+        Self {
+            socket,
+            local_addr,
+            inbox: Mutex::new(rx),
+            tasks,
+            stream_timeout: DEFAULT_STREAM_TIMEOUT,
+        }
+    }
+
+    /// Bound on connecting to a peer and writing a frame over TCP
+    pub fn with_stream_timeout(mut self, timeout: Duration) -> Self {
+        self.stream_timeout = timeout;
+        self
+    }
+
+    /// Address peers should use to reach this node
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Send `message` to `to`, over UDP if it fits in one datagram and over TCP
+    /// otherwise. A TCP send that does not finish within the stream timeout
+    /// fails.
+    pub async fn send(&self, to: SocketAddr, message: &GossipMessage) -> Result<(), GossipError> {
+        let frame = wire::encode(message)?;
+        if frame.len() <= MAX_DATAGRAM_BYTES {
+            self.socket
+                .send_to(&frame, to)
+                .await
+                .map_err(network_error)?;
+            return Ok(());
+        }
+
+        let stream_send = async {
+            let mut stream = TcpStream::connect(to).await.map_err(network_error)?;
+            stream
+                .write_u32(frame.len() as u32)
+                .await
+                .map_err(network_error)?;
+            stream.write_all(&frame).await.map_err(network_error)?;
+            stream.shutdown().await.map_err(network_error)
+        };
+        tokio::time::timeout(self.stream_timeout, stream_send)
+            .await
+            .map_err(|_| network_error(format!("TCP send to {} timed out", to)))?
+    }
+
+    /// Wait for the next message from any peer
+    pub async fn recv(&self) -> Option<GossipMessage> {
+        self.inbox.lock().await.recv().await
+    }
+
+    /// Decode datagrams; malformed ones and other versions are dropped
+    async fn receive_datagrams(socket: Arc<UdpSocket>, inbox: UnboundedSender<GossipMessage>) {
+        let mut buffer = vec![0u8; 64 * 1024];
+        loop {
+            let len = match socket.recv_from(&mut buffer).await {
+                Ok((len, _)) => len,
+                // ICMP errors from earlier sends surface here on some platforms
+                Err(_) => continue,
+            };
+            if let Ok(message) = wire::decode(&buffer[..len]) {
+                if inbox.send(message).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Accept TCP connections, each carrying length-prefixed frames
+    async fn accept_streams(listener: TcpListener, inbox: UnboundedSender<GossipMessage>) {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(Self::read_stream(stream, inbox.clone()));
+        }
+    }
+
+    async fn read_stream(mut stream: TcpStream, inbox: UnboundedSender<GossipMessage>) {
+        while let Ok(len) = stream.read_u32().await {
+            let len = len as usize;
+            if len > MAX_STREAM_FRAME_BYTES {
+                return;
+            }
+            let mut frame = vec![0u8; len];
+            if stream.read_exact(&mut frame).await.is_err() {
+                return;
+            }
+            match wire::decode(&frame) {
+                Ok(message) => {
+                    if inbox.send(message).is_err() {
+                        return;
+                    }
+                }
+                Err(_) => return,
+            }
+        }
+    }
+}
+
+impl Drop for GossipTransport {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+fn network_error(e: impl std::fmt::Display) -> GossipError {
+    GossipError::NetworkError(e.to_string())
+}
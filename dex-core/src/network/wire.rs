@@ -0,0 +1,298 @@
+//! Binary wire encoding for gossip messages
+//!
+//! Every frame starts with a two-byte magic and a version byte so that nodes
+//! running different releases reject each other's frames instead of
+//! misreading them. The body is a tag byte followed by big-endian fields:
+//! - strings as a `u16` byte length followed by UTF-8;
+//! - socket addresses as a family byte (4 or 6), the IP octets and a `u16` port;
+//! - lists as a `u16` element count followed by the elements.
+//!
+//! Implements the Priority 3 feature from DEX-OS-V2.csv:
+//! - Infrastructure,Network,Network,Gossip Protocol,Node Discovery,Medium
+
+use super::gossip::{GossipError, GossipMessage, MemberState, NodeInfo};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+/// Frame magic, "DG" for DEX-OS gossip
+const MAGIC: [u8; 2] = *b"DG";
+
+/// Version written into every frame; frames with another version are rejected
+pub const WIRE_VERSION: u8 = 1;
+
+const TAG_PING: u8 = 1;
+const TAG_PONG: u8 = 2;
+const TAG_PING_REQ: u8 = 3;
+const TAG_GOSSIP: u8 = 4;
+
+/// Encode a message into a self-describing frame
+pub fn encode(message: &GossipMessage) -> Result<Vec<u8>, GossipError> {
+    let mut out = Vec::with_capacity(64);
+    out.extend_from_slice(&MAGIC);
+    out.push(WIRE_VERSION);
+    match message {
+        GossipMessage::Ping {
+            node_id,
+            address,
+            seq,
+        } => {
+            out.push(TAG_PING);
+            put_string(&mut out, node_id)?;
+            put_address(&mut out, address);
+            out.extend_from_slice(&seq.to_be_bytes());
+        }
+        GossipMessage::Pong { node_id, seq } => {
+            out.push(TAG_PONG);
+            put_string(&mut out, node_id)?;
+            out.extend_from_slice(&seq.to_be_bytes());
+        }
+        GossipMessage::PingReq {
+            node_id,
+            address,
+            target,
+            target_address,
+            seq,
+        } => {
+            out.push(TAG_PING_REQ);
+            put_string(&mut out, node_id)?;
+            put_address(&mut out, address);
+            put_string(&mut out, target)?;
+            put_address(&mut out, target_address);
+            out.extend_from_slice(&seq.to_be_bytes());
+        }
+        GossipMessage::Gossip { nodes } => {
+            out.push(TAG_GOSSIP);
+            let count = u16::try_from(nodes.len()).map_err(|_| {
+                GossipError::SerializationError(format!("too many nodes: {}", nodes.len()))
+            })?;
+            out.extend_from_slice(&count.to_be_bytes());
+            for node in nodes {
+                put_string(&mut out, &node.id)?;
+                put_address(&mut out, &node.address);
+                out.extend_from_slice(&node.last_seen.to_be_bytes());
+                out.push(state_byte(&node.state));
+                out.extend_from_slice(&node.incarnation.to_be_bytes());
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Decode a frame produced by [`encode`]
+pub fn decode(frame: &[u8]) -> Result<GossipMessage, GossipError> {
+    let mut reader = Reader { frame, position: 0 };
+    if reader.take(2)? != MAGIC {
+        return Err(GossipError::SerializationError(
+            "not a gossip frame".to_string(),
+        ));
+    }
+    let version = reader.u8()?;
+    if version != WIRE_VERSION {
+        return Err(GossipError::UnsupportedVersion(version));
+    }
+
+    let message = match reader.u8()? {
+        TAG_PING => GossipMessage::Ping {
+            node_id: reader.string()?,
+            address: reader.address()?,
+            seq: reader.u64()?,
+        },
+        TAG_PONG => GossipMessage::Pong {
+            node_id: reader.string()?,
+            seq: reader.u64()?,
+        },
+        TAG_PING_REQ => GossipMessage::PingReq {
+            node_id: reader.string()?,
+            address: reader.address()?,
+            target: reader.string()?,
+            target_address: reader.address()?,
+            seq: reader.u64()?,
+        },
+        TAG_GOSSIP => {
+            let count = reader.u16()?;
+            let mut nodes = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                nodes.push(NodeInfo {
+                    id: reader.string()?,
+                    address: reader.address()?,
+                    last_seen: reader.u64()?,
+                    state: parse_state(reader.u8()?)?,
+                    incarnation: reader.u64()?,
+                });
+            }
+            GossipMessage::Gossip { nodes }
+        }
+        tag => {
+            return Err(GossipError::SerializationError(format!(
+                "unknown message tag {}",
+                tag
+            )))
+        }
+    };
+    if reader.position != frame.len() {
+        return Err(GossipError::SerializationError(
+            "trailing bytes after message".to_string(),
+        ));
+    }
+    Ok(message)
+}
+
+fn put_string(out: &mut Vec<u8>, value: &str) -> Result<(), GossipError> {
+    let len = u16::try_from(value.len())
+        .map_err(|_| GossipError::SerializationError("string too long".to_string()))?;
+    out.extend_from_slice(&len.to_be_bytes());
+    out.extend_from_slice(value.as_bytes());
+    Ok(())
+}
+
+fn put_address(out: &mut Vec<u8>, address: &SocketAddr) {
+    match address.ip() {
+        IpAddr::V4(ip) => {
+            out.push(4);
+            out.extend_from_slice(&ip.octets());
+        }
+        IpAddr::V6(ip) => {
+            out.push(6);
+            out.extend_from_slice(&ip.octets());
+        }
+    }
+    out.extend_from_slice(&address.port().to_be_bytes());
+}
+
+fn state_byte(state: &MemberState) -> u8 {
+    match state {
+        MemberState::Alive => 0,
+        MemberState::Suspect => 1,
+        MemberState::Dead => 2,
+    }
+}
+
+fn parse_state(byte: u8) -> Result<MemberState, GossipError> {
+    match byte {
+        0 => Ok(MemberState::Alive),
+        1 => Ok(MemberState::Suspect),
+        2 => Ok(MemberState::Dead),
+        other => Err(GossipError::SerializationError(format!(
+            "unknown member state {}",
+            other
+        ))),
+    }
+}
+
+/// Cursor over a frame that fails cleanly on truncated input
+struct Reader<'a> {
+    frame: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], GossipError> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|&end| end <= self.frame.len())
+            .ok_or_else(|| GossipError::SerializationError("truncated frame".to_string()))?;
+        let bytes = &self.frame[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, GossipError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, GossipError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u64(&mut self) -> Result<u64, GossipError> {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_be_bytes(bytes))
+    }
+
+    fn string(&mut self) -> Result<String, GossipError> {
+        let len = self.u16()? as usize;
+        String::from_utf8(self.take(len)?.to_vec())
+            .map_err(|e| GossipError::SerializationError(e.to_string()))
+    }
+
+    fn address(&mut self) -> Result<SocketAddr, GossipError> {
+        let ip = match self.u8()? {
+            4 => {
+                let mut octets = [0u8; 4];
+                octets.copy_from_slice(self.take(4)?);
+                IpAddr::V4(Ipv4Addr::from(octets))
+            }
+            6 => {
+                let mut octets = [0u8; 16];
+                octets.copy_from_slice(self.take(16)?);
+                IpAddr::V6(Ipv6Addr::from(octets))
+            }
+            family => {
+                return Err(GossipError::SerializationError(format!(
+                    "unknown address family {}",
+                    family
+                )))
+            }
+        };
+        Ok(SocketAddr::new(ip, self.u16()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_every_message() {
+        let messages = vec![
+            GossipMessage::Ping {
+                node_id: "node-1".to_string(),
+                address: "127.0.0.1:7000".parse().unwrap(),
+                seq: 7,
+            },
+            GossipMessage::Pong {
+                node_id: "node-2".to_string(),
+                seq: u64::MAX,
+            },
+            GossipMessage::PingReq {
+                node_id: "node-1".to_string(),
+                address: "[::1]:7000".parse().unwrap(),
+                target: "node-3".to_string(),
+                target_address: "10.0.0.3:7003".parse().unwrap(),
+                seq: 9,
+            },
+            GossipMessage::Gossip {
+                nodes: vec![NodeInfo {
+                    id: "node-4".to_string(),
+                    address: "127.0.0.1:7004".parse().unwrap(),
+                    last_seen: 1_700_000_000_000,
+                    state: MemberState::Suspect,
+                    incarnation: 3,
+                }],
+            },
+        ];
+        for message in messages {
+            let decoded = decode(&encode(&message).unwrap()).unwrap();
+            assert_eq!(format!("{:?}", decoded), format!("{:?}", message));
+        }
+    }
+
+    #[test]
+    fn test_rejects_other_versions_and_truncated_frames() {
+        let mut frame = encode(&GossipMessage::Pong {
+            node_id: "node-1".to_string(),
+            seq: 1,
+        })
+        .unwrap();
+        assert!(decode(&frame[..frame.len() - 1]).is_err());
+
+        frame[2] = WIRE_VERSION + 1;
+        assert!(matches!(
+            decode(&frame),
+            Err(GossipError::UnsupportedVersion(v)) if v == WIRE_VERSION + 1
+        ));
+        assert!(decode(b"XX\x01\x02").is_err());
+    }
+}
@@ -8,11 +8,42 @@
 //! It provides functionality for finding the best trading paths across multiple DEXes
 //! using the Bellman-Ford algorithm to handle negative weight edges (which can represent
 //! arbitrage opportunities or fees), with route caching for improved performance.
+//!
+//! Every edge carries a version that changes whenever the edge is updated. Cached routes
+//! remember the versions they were computed from and expire after a TTL, so an update only
+//! drops the cache entries it can affect. Cache hit/miss metrics can be exported through
+//! [`ObservabilityManager`].
+//!
+//! The same graph is scanned for negative cycles after fees, which are surfaced as ranked
+//! arbitrage opportunities and kept up to date incrementally as edges change.
+//!
+//! Large orders can additionally be split across several parallel paths and venues with
+//! [`PathRouter::optimize_split_route`], which models price impact per edge from its
+//! liquidity instead of treating `exchange_rate` as constant.
 
+use crate::observability::{ObservabilityError, ObservabilityManager};
+use crate::partial_fill::PartialFillExplorer;
 use crate::types::{Quantity, TokenId};
-use std::collections::{hash_map::Entry, BinaryHeap, HashMap, VecDeque};
+use std::collections::{hash_map::Entry, BinaryHeap, HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
+/// Counter incremented on every route cache hit
+pub const ROUTE_CACHE_HITS_METRIC: &str = "path_routing_route_cache_hits";
+/// Counter incremented on every route cache miss
+pub const ROUTE_CACHE_MISSES_METRIC: &str = "path_routing_route_cache_misses";
+/// Counter incremented whenever a cached route is dropped (stale edges or TTL expiry)
+pub const ROUTE_CACHE_EVICTIONS_METRIC: &str = "path_routing_route_cache_evictions";
+/// Gauge tracking the number of cached routes
+pub const ROUTE_CACHE_ENTRIES_METRIC: &str = "path_routing_route_cache_entries";
+
+/// Default time-to-live of a cached route
+pub const DEFAULT_ROUTE_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Identifies an edge by (source token, destination token, DEX)
+pub type EdgeKey = (TokenId, TokenId, String);
+
 /// Represents an edge in the trading graph (a trading path between tokens on a DEX)
 #[derive(Debug, Clone, PartialEq)]
 pub struct TradingEdge {
@@ -30,6 +61,40 @@ pub struct TradingEdge {
     pub liquidity: Quantity,
 }
 
+impl TradingEdge {
+    /// Output received for `amount_in` of `from_token` when `already_routed` has already
+    /// been sent through this edge by the same plan.
+    ///
+    /// Price impact is modelled as a constant-product pool whose depth is `liquidity`
+    /// (denominated in `from_token`): the marginal rate starts at `exchange_rate * (1 - fee)`
+    /// and decays as more volume is pushed through the edge.
+    pub fn output_with_impact(&self, already_routed: f64, amount_in: f64) -> f64 {
+        if amount_in <= 0.0 || self.liquidity == 0 {
+            return 0.0;
+        }
+
+        let depth = self.liquidity as f64;
+        let effective_rate = self.exchange_rate * (1.0 - self.fee);
+        let cumulative_output = |routed: f64| effective_rate * routed * depth / (depth + routed);
+
+        cumulative_output(already_routed + amount_in) - cumulative_output(already_routed)
+    }
+
+    /// Edge weight used for cycle detection: negative log of the post-fee rate
+    fn fee_adjusted_weight(&self) -> f64 {
+        -(self.exchange_rate * (1.0 - self.fee)).ln()
+    }
+
+    /// Key identifying this edge (source, destination, venue) for versioning and volume accounting
+    pub fn venue_key(&self) -> EdgeKey {
+        (
+            self.from_token.clone(),
+            self.to_token.clone(),
+            self.dex_name.clone(),
+        )
+    }
+}
+
 /// Represents a node in the trading graph (a token)
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct TradingNode {
@@ -50,6 +115,92 @@ pub struct RoutingPath {
     pub min_liquidity: Quantity,
 }
 
+/// Configuration for split-route optimisation
+#[derive(Debug, Clone)]
+pub struct SplitRouteConfig {
+    /// Maximum number of hops a candidate path may have
+    pub max_hops: usize,
+    /// Maximum number of distinct paths the input may be split across
+    pub max_splits: usize,
+    /// Number of equal-sized chunks the input is divided into during allocation
+    pub granularity: usize,
+}
+
+impl Default for SplitRouteConfig {
+    fn default() -> Self {
+        Self {
+            max_hops: 3,
+            max_splits: 4,
+            granularity: 100,
+        }
+    }
+}
+
+/// A single leg of a split route
+#[derive(Debug, Clone)]
+pub struct SplitRouteAllocation {
+    /// Path the allocated input is routed through
+    pub path: RoutingPath,
+    /// Share of the total input routed through this path (0.0 to 100.0)
+    pub allocation_percent: f64,
+    /// Input amount routed through this path
+    pub input_amount: f64,
+    /// Expected output of this path, including price impact
+    pub expected_output: f64,
+}
+
+/// Result of a split-route optimisation
+#[derive(Debug, Clone)]
+pub struct SplitRoutePlan {
+    /// Paths used by the plan, ordered by allocation (largest first)
+    pub allocations: Vec<SplitRouteAllocation>,
+    /// Total input amount
+    pub total_input: f64,
+    /// Total expected output across all paths, including price impact
+    pub expected_output: f64,
+    /// Effective exchange rate (expected output / total input)
+    pub effective_rate: f64,
+}
+
+/// A profitable cycle in the trading graph, evaluated at a given notional size
+#[derive(Debug, Clone)]
+pub struct ArbitrageOpportunity {
+    /// Edges forming the cycle, starting and ending at `start_token`
+    pub cycle: Vec<TradingEdge>,
+    /// Token the cycle starts and ends in (the notional is denominated in this token)
+    pub start_token: TokenId,
+    /// Product of post-fee exchange rates along the cycle, ignoring price impact
+    pub rate_product: f64,
+    /// Amount of `start_token` returned for the notional, including price impact
+    pub expected_output: f64,
+    /// Expected profit in `start_token` after fees and price impact
+    pub expected_profit: f64,
+}
+
+/// A cached route together with the edge versions it was computed from
+#[derive(Debug, Clone)]
+struct CachedRoute {
+    /// Path stored without the amount adjustment
+    path: RoutingPath,
+    /// Version of every edge in the path at caching time
+    edge_versions: Vec<(EdgeKey, u64)>,
+    /// When the route was cached
+    cached_at: Instant,
+}
+
+/// Route cache statistics
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RouteCacheStats {
+    /// Lookups answered from the cache
+    pub hits: u64,
+    /// Lookups that had to compute a path
+    pub misses: u64,
+    /// Entries dropped because an edge they depend on changed
+    pub invalidations: u64,
+    /// Entries dropped because their TTL elapsed
+    pub expirations: u64,
+}
+
 /// Manages path routing using the Bellman-Ford algorithm
 #[derive(Debug, Clone)]
 pub struct PathRouter {
@@ -60,9 +211,25 @@ pub struct PathRouter {
     /// Route cache for improved performance: (source, destination) -> cached path
     /// This implements the Priority 1 feature from DEX-OS-V1.csv:
     /// "Core Trading,DEX Aggregator,DEX Aggregator,Hash Map,Route Caching,High"
-    route_cache: HashMap<(TokenId, TokenId), RoutingPath>,
+    route_cache: HashMap<(TokenId, TokenId), CachedRoute>,
+    /// How long a cached route stays valid (None disables expiry)
+    route_cache_ttl: Option<Duration>,
+    /// Route cache statistics
+    route_cache_stats: RouteCacheStats,
+    /// Current version of every edge in the graph
+    edge_versions: HashMap<EdgeKey, u64>,
+    /// Last version handed out; versions are never reused, even after removal
+    last_edge_version: u64,
+    /// Metrics sink for route cache statistics
+    observability: Option<Arc<ObservabilityManager>>,
     /// All tokens in the graph
     tokens: Vec<TokenId>,
+    /// Negative cycles (after fees) currently known to exist in the graph
+    arbitrage_cycles: Vec<Vec<TradingEdge>>,
+    /// Whether `arbitrage_cycles` is kept up to date as edges are added or removed
+    arbitrage_tracking: bool,
+    /// Maximum number of edges in a tracked arbitrage cycle
+    max_cycle_length: usize,
 }
 
 impl PathRouter {
@@ -71,15 +238,30 @@ impl PathRouter {
         Self {
             graph: HashMap::new(),
             route_cache: HashMap::new(),
+            route_cache_ttl: Some(DEFAULT_ROUTE_CACHE_TTL),
+            route_cache_stats: RouteCacheStats::default(),
+            edge_versions: HashMap::new(),
+            last_edge_version: 0,
+            observability: None,
             tokens: Vec::new(),
+            arbitrage_cycles: Vec::new(),
+            arbitrage_tracking: false,
+            max_cycle_length: 4,
         }
     }
 
     /// Add a trading edge to the graph
+    ///
+    /// An edge with the same source, destination and DEX as an existing one replaces it
+    /// (e.g. a rate update). Either way the edge gets a new version and only the cached
+    /// routes it can affect are dropped.
     pub fn add_edge(&mut self, edge: TradingEdge) {
-        // Invalidate cache entries that might be affected by this change
-        self.invalidate_cache_for_token(&edge.from_token);
-        self.invalidate_cache_for_token(&edge.to_token);
+        let key = edge.venue_key();
+        let previous = self
+            .graph
+            .get(&edge.from_token)
+            .and_then(|edges| edges.iter().find(|existing| existing.venue_key() == key))
+            .cloned();
 
         // Add source token if not already present
         if !self.tokens.contains(&edge.from_token) {
@@ -91,17 +273,64 @@ impl PathRouter {
             self.tokens.push(edge.to_token.clone());
         }
 
-        // Add edge to the graph
-        self.graph
+        // Add edge to the graph, replacing the previous version of it
+        let edges = self
+            .graph
             .entry(edge.from_token.clone())
-            .or_insert_with(Vec::new)
-            .push(edge);
+            .or_insert_with(Vec::new);
+        match edges
+            .iter_mut()
+            .find(|existing| existing.venue_key() == key)
+        {
+            Some(existing) => *existing = edge.clone(),
+            None => edges.push(edge.clone()),
+        }
+
+        self.last_edge_version += 1;
+        self.edge_versions
+            .insert(key.clone(), self.last_edge_version);
+
+        // Routes through the old version of the edge are stale
+        if previous.is_some() {
+            self.invalidate_routes(|route| route.edge_versions.iter().any(|(k, _)| *k == key));
+        }
+
+        // A new or better edge can only improve routes that can reach its source and
+        // continue from its destination
+        let improves = previous.as_ref().is_none_or(|old| {
+            edge.exchange_rate * (1.0 - edge.fee) > old.exchange_rate * (1.0 - old.fee)
+                || edge.liquidity > old.liquidity
+        });
+        if improves {
+            let reaches_source = self.tokens_reaching(&edge.from_token);
+            let reachable_from_destination = self.tokens_reachable_from(&edge.to_token);
+            self.invalidate_routes_for_pairs(&reaches_source, &reachable_from_destination);
+        }
+
+        if self.arbitrage_tracking {
+            // Cycles through the old version of the edge must be re-evaluated
+            if previous.is_some() {
+                self.arbitrage_cycles
+                    .retain(|cycle| cycle.iter().all(|e| e.venue_key() != key));
+            }
+
+            // Any new negative cycle must run through the edge just added
+            for cycle in self.find_cycles_through_edge(&edge) {
+                self.record_arbitrage_cycle(cycle);
+            }
+        }
     }
 
     /// Remove all edges for a specific DEX
     pub fn remove_dex_edges(&mut self, dex_name: &str) {
-        // Invalidate all cache entries since we're modifying the graph significantly
-        self.route_cache.clear();
+        // Removing edges can only break routes that use this DEX
+        self.invalidate_routes(|route| {
+            route
+                .edge_versions
+                .iter()
+                .any(|((_, _, dex), _)| dex == dex_name)
+        });
+        self.edge_versions.retain(|(_, _, dex), _| dex != dex_name);
 
         for edges in self.graph.values_mut() {
             edges.retain(|edge| edge.dex_name != dex_name);
@@ -109,12 +338,188 @@ impl PathRouter {
 
         // Clean up empty edge lists
         self.graph.retain(|_, edges| !edges.is_empty());
+
+        // Removing edges cannot create cycles, only break the ones using this DEX
+        self.arbitrage_cycles
+            .retain(|cycle| cycle.iter().all(|edge| edge.dex_name != dex_name));
+    }
+
+    /// Drop cached routes matching `is_affected`, counting them as invalidations
+    fn invalidate_routes<F>(&mut self, is_affected: F)
+    where
+        F: Fn(&CachedRoute) -> bool,
+    {
+        let before = self.route_cache.len();
+        self.route_cache.retain(|_, route| !is_affected(route));
+        let dropped = (before - self.route_cache.len()) as u64;
+
+        self.route_cache_stats.invalidations += dropped;
+        self.report_evictions(dropped);
+    }
+
+    /// Drop cached routes whose source is in `sources` and destination in `destinations`
+    fn invalidate_routes_for_pairs(
+        &mut self,
+        sources: &HashSet<TokenId>,
+        destinations: &HashSet<TokenId>,
+    ) {
+        let before = self.route_cache.len();
+        self.route_cache.retain(|(source, destination), _| {
+            !(sources.contains(source) && destinations.contains(destination))
+        });
+        let dropped = (before - self.route_cache.len()) as u64;
+
+        self.route_cache_stats.invalidations += dropped;
+        self.report_evictions(dropped);
+    }
+
+    /// Tokens from which `target` can be reached (including `target` itself)
+    fn tokens_reaching(&self, target: &TokenId) -> HashSet<TokenId> {
+        let mut reverse: HashMap<&TokenId, Vec<&TokenId>> = HashMap::new();
+        for edge in self.graph.values().flatten() {
+            reverse
+                .entry(&edge.to_token)
+                .or_default()
+                .push(&edge.from_token);
+        }
+
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::from([target]);
+        while let Some(token) = queue.pop_front() {
+            if seen.insert(token.clone()) {
+                if let Some(predecessors) = reverse.get(token) {
+                    queue.extend(predecessors.iter().copied());
+                }
+            }
+        }
+        seen
     }
 
-    /// Invalidate cache entries for a specific token
-    fn invalidate_cache_for_token(&mut self, token: &TokenId) {
-        self.route_cache
-            .retain(|(source, destination), _| source != token && destination != token);
+    /// Tokens reachable from `start` (including `start` itself)
+    fn tokens_reachable_from(&self, start: &TokenId) -> HashSet<TokenId> {
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::from([start]);
+        while let Some(token) = queue.pop_front() {
+            if seen.insert(token.clone()) {
+                if let Some(edges) = self.graph.get(token) {
+                    queue.extend(edges.iter().map(|edge| &edge.to_token));
+                }
+            }
+        }
+        seen
+    }
+
+    /// Why a cached route can no longer be served, or None if it was computed from the
+    /// current edge versions and is within its TTL
+    fn route_staleness(&self, route: &CachedRoute) -> Option<RouteStaleness> {
+        if let Some(ttl) = self.route_cache_ttl {
+            if route.cached_at.elapsed() >= ttl {
+                return Some(RouteStaleness::Expired);
+            }
+        }
+
+        let versions_match = route
+            .edge_versions
+            .iter()
+            .all(|(key, version)| self.edge_versions.get(key) == Some(version));
+        if versions_match {
+            None
+        } else {
+            Some(RouteStaleness::EdgeChanged)
+        }
+    }
+
+    /// Set how long cached routes stay valid (None disables expiry)
+    pub fn set_route_cache_ttl(&mut self, ttl: Option<Duration>) {
+        self.route_cache_ttl = ttl;
+    }
+
+    /// Current version of an edge, if it exists
+    pub fn edge_version(
+        &self,
+        from_token: &TokenId,
+        to_token: &TokenId,
+        dex_name: &str,
+    ) -> Option<u64> {
+        self.edge_versions
+            .get(&(from_token.clone(), to_token.clone(), dex_name.to_string()))
+            .copied()
+    }
+
+    /// Get route cache statistics
+    pub fn route_cache_stats(&self) -> &RouteCacheStats {
+        &self.route_cache_stats
+    }
+
+    /// Number of routes currently cached
+    pub fn route_cache_len(&self) -> usize {
+        self.route_cache.len()
+    }
+
+    /// Register the route cache metrics with an observability manager
+    pub fn register_route_cache_metrics(
+        manager: &mut ObservabilityManager,
+    ) -> Result<(), ObservabilityError> {
+        manager.register_counter(
+            ROUTE_CACHE_HITS_METRIC.to_string(),
+            "Route cache hits".to_string(),
+        )?;
+        manager.register_counter(
+            ROUTE_CACHE_MISSES_METRIC.to_string(),
+            "Route cache misses".to_string(),
+        )?;
+        manager.register_counter(
+            ROUTE_CACHE_EVICTIONS_METRIC.to_string(),
+            "Cached routes dropped because of edge changes or TTL expiry".to_string(),
+        )?;
+        manager.register_gauge(
+            ROUTE_CACHE_ENTRIES_METRIC.to_string(),
+            "Number of cached routes".to_string(),
+        )
+    }
+
+    /// Export route cache metrics through an observability manager
+    ///
+    /// The manager must have the metrics registered with
+    /// [`PathRouter::register_route_cache_metrics`].
+    pub fn attach_observability(&mut self, manager: Arc<ObservabilityManager>) {
+        self.observability = Some(manager);
+        self.report_cache_size();
+    }
+
+    /// Record a cache lookup outcome
+    fn report_lookup(&mut self, hit: bool) {
+        if hit {
+            self.route_cache_stats.hits += 1;
+        } else {
+            self.route_cache_stats.misses += 1;
+        }
+
+        if let Some(manager) = &self.observability {
+            let metric = if hit {
+                ROUTE_CACHE_HITS_METRIC
+            } else {
+                ROUTE_CACHE_MISSES_METRIC
+            };
+            let _ = manager.increment_counter(metric);
+        }
+    }
+
+    /// Record dropped cache entries
+    fn report_evictions(&self, dropped: u64) {
+        if let Some(manager) = &self.observability {
+            if dropped > 0 {
+                let _ = manager.increment_counter_by(ROUTE_CACHE_EVICTIONS_METRIC, dropped);
+            }
+        }
+        self.report_cache_size();
+    }
+
+    /// Publish the current cache size
+    fn report_cache_size(&self) {
+        if let Some(manager) = &self.observability {
+            let _ = manager.set_gauge(ROUTE_CACHE_ENTRIES_METRIC, self.route_cache.len() as i64);
+        }
     }
 
     /// Invalidate all cache entries
@@ -146,12 +551,26 @@ impl PathRouter {
         // This implements the Priority 1 feature from DEX-OS-V1.csv:
         // "Core Trading,DEX Aggregator,DEX Aggregator,Hash Map,Route Caching,High"
         let cache_key = (source.clone(), destination.clone());
-        if let Some(cached_path) = self.route_cache.get(&cache_key) {
-            // Return cached path with adjusted amount
-            let mut result_path = cached_path.clone();
-            result_path.total_exchange_rate = cached_path.total_exchange_rate * amount;
-            return Ok(Some(result_path));
+        if let Some(cached) = self.route_cache.get(&cache_key) {
+            match self.route_staleness(cached) {
+                None => {
+                    // Return cached path with adjusted amount
+                    let mut result_path = cached.path.clone();
+                    result_path.total_exchange_rate = cached.path.total_exchange_rate * amount;
+                    self.report_lookup(true);
+                    return Ok(Some(result_path));
+                }
+                Some(staleness) => {
+                    self.route_cache.remove(&cache_key);
+                    match staleness {
+                        RouteStaleness::Expired => self.route_cache_stats.expirations += 1,
+                        RouteStaleness::EdgeChanged => self.route_cache_stats.invalidations += 1,
+                    }
+                    self.report_evictions(1);
+                }
+            }
         }
+        self.report_lookup(false);
 
         // Initialize distances and predecessors
         let mut distances: HashMap<&TokenId, f64> = HashMap::new();
@@ -260,7 +679,24 @@ impl PathRouter {
                     total_fee: result.total_fee,
                     min_liquidity: result.min_liquidity,
                 };
-                self.route_cache.insert(cache_key, cache_entry);
+                let edge_versions = cache_entry
+                    .edges
+                    .iter()
+                    .map(|edge| {
+                        let key = edge.venue_key();
+                        let version = self.edge_versions.get(&key).copied().unwrap_or(0);
+                        (key, version)
+                    })
+                    .collect();
+                self.route_cache.insert(
+                    cache_key,
+                    CachedRoute {
+                        path: cache_entry,
+                        edge_versions,
+                        cached_at: Instant::now(),
+                    },
+                );
+                self.report_cache_size();
 
                 return Ok(Some(result));
             }
@@ -587,6 +1023,348 @@ impl PathRouter {
         }
     }
 
+    /// Split an input amount across several parallel paths and venues
+    ///
+    /// Candidate paths are every path of at most `config.max_hops` hops, plus any extra
+    /// liquidity options discovered by the given [`PartialFillExplorer`]. The input is
+    /// divided into `config.granularity` chunks and each chunk is greedily assigned to the
+    /// path with the highest marginal output, taking into account the price impact of
+    /// volume already routed through every edge (including edges shared between paths).
+    pub fn optimize_split_route(
+        &self,
+        source: &TokenId,
+        destination: &TokenId,
+        amount: f64,
+        config: &SplitRouteConfig,
+        partial_fills: Option<&PartialFillExplorer>,
+    ) -> Result<Option<SplitRoutePlan>, PathRoutingError> {
+        if source == destination {
+            return Err(PathRoutingError::SameSourceDestination);
+        }
+
+        if amount <= 0.0 || !amount.is_finite() {
+            return Err(PathRoutingError::InvalidAmount);
+        }
+
+        let mut candidates = self.find_all_paths(source, destination, config.max_hops);
+
+        // Extra liquidity options from the partial fill explorer
+        if let Some(explorer) = partial_fills {
+            if let Ok(plans) =
+                explorer.explore_partial_fills(source, destination, amount as Quantity)
+            {
+                for plan in plans {
+                    let edges: Vec<TradingEdge> = plan
+                        .fills
+                        .iter()
+                        .map(|fill| TradingEdge {
+                            from_token: fill.from_token.clone(),
+                            to_token: fill.to_token.clone(),
+                            dex_name: fill.dex_name.clone(),
+                            exchange_rate: fill.exchange_rate,
+                            fee: fill.fee,
+                            liquidity: fill.available_liquidity,
+                        })
+                        .collect();
+
+                    let is_known = candidates.iter().any(|candidate| {
+                        candidate.edges.len() == edges.len()
+                            && candidate
+                                .edges
+                                .iter()
+                                .zip(&edges)
+                                .all(|(a, b)| a.venue_key() == b.venue_key())
+                    });
+
+                    if !is_known && !edges.is_empty() {
+                        candidates.push(Self::routing_path_from_edges(edges));
+                    }
+                }
+            }
+        }
+
+        if candidates.is_empty() {
+            return Ok(None);
+        }
+
+        let granularity = config.granularity.max(1);
+        let max_splits = config.max_splits.max(1);
+        let chunk = amount / granularity as f64;
+
+        // Volume already routed through each edge, in units of the edge's source token
+        let mut edge_volume: HashMap<EdgeKey, f64> = HashMap::new();
+        let mut path_inputs = vec![0.0; candidates.len()];
+        let mut path_outputs = vec![0.0; candidates.len()];
+        let mut used_paths = 0;
+
+        for _ in 0..granularity {
+            let mut best: Option<(usize, f64)> = None;
+
+            for (index, candidate) in candidates.iter().enumerate() {
+                // Once the split limit is reached, only paths already in use may grow
+                if used_paths >= max_splits && path_inputs[index] == 0.0 {
+                    continue;
+                }
+
+                let output = Self::simulate_path(&candidate.edges, &edge_volume, chunk, None);
+                if output > 0.0 && best.is_none_or(|(_, best_output)| output > best_output) {
+                    best = Some((index, output));
+                }
+            }
+
+            let Some((index, _)) = best else {
+                break; // No candidate can absorb more volume
+            };
+
+            if path_inputs[index] == 0.0 {
+                used_paths += 1;
+            }
+
+            let routed_so_far = edge_volume.clone();
+            let output = Self::simulate_path(
+                &candidates[index].edges,
+                &routed_so_far,
+                chunk,
+                Some(&mut edge_volume),
+            );
+            path_inputs[index] += chunk;
+            path_outputs[index] += output;
+        }
+
+        let mut allocations: Vec<SplitRouteAllocation> = candidates
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| path_inputs[*index] > 0.0)
+            .map(|(index, path)| SplitRouteAllocation {
+                path,
+                allocation_percent: path_inputs[index] / amount * 100.0,
+                input_amount: path_inputs[index],
+                expected_output: path_outputs[index],
+            })
+            .collect();
+
+        if allocations.is_empty() {
+            return Ok(None);
+        }
+
+        allocations.sort_by(|a, b| {
+            b.input_amount
+                .partial_cmp(&a.input_amount)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        let expected_output: f64 = allocations.iter().map(|a| a.expected_output).sum();
+
+        Ok(Some(SplitRoutePlan {
+            allocations,
+            total_input: amount,
+            expected_output,
+            effective_rate: expected_output / amount,
+        }))
+    }
+
+    /// Push `amount_in` through a path given the volume already routed per edge and
+    /// return the final output. When `record` is set, the resulting edge volumes are
+    /// written into it.
+    fn simulate_path(
+        edges: &[TradingEdge],
+        edge_volume: &HashMap<EdgeKey, f64>,
+        amount_in: f64,
+        record: Option<&mut HashMap<EdgeKey, f64>>,
+    ) -> f64 {
+        let mut current = amount_in;
+        // Track volume added by this path itself, in case it revisits an edge
+        let mut added: HashMap<EdgeKey, f64> = HashMap::new();
+
+        for edge in edges {
+            let key = edge.venue_key();
+            let routed = edge_volume.get(&key).copied().unwrap_or(0.0)
+                + added.get(&key).copied().unwrap_or(0.0);
+            let output = edge.output_with_impact(routed, current);
+            *added.entry(key).or_insert(0.0) += current;
+            current = output;
+        }
+
+        if let Some(record) = record {
+            for (key, volume) in added {
+                let base = edge_volume.get(&key).copied().unwrap_or(0.0);
+                record.insert(key, base + volume);
+            }
+        }
+
+        current
+    }
+
+    /// Build a routing path (with cumulative metrics) from a sequence of edges
+    fn routing_path_from_edges(edges: Vec<TradingEdge>) -> RoutingPath {
+        let mut total_exchange_rate = 1.0;
+        let mut total_fee = 0.0;
+        let mut min_liquidity = u64::MAX;
+
+        for edge in &edges {
+            total_exchange_rate *= edge.exchange_rate;
+            total_fee += edge.fee;
+            min_liquidity = min_liquidity.min(edge.liquidity);
+        }
+
+        RoutingPath {
+            edges,
+            total_exchange_rate,
+            total_fee,
+            min_liquidity,
+        }
+    }
+
+    /// Scan the whole graph for negative cycles after fees and start tracking them
+    ///
+    /// For every edge, a depth-first search bounded by the maximum cycle length follows
+    /// each simple return path from its destination back to its source; every closed
+    /// loop with negative total weight is recorded as an arbitrage cycle. Once enabled, the tracked set is kept
+    /// up to date incrementally by `add_edge` and `remove_dex_edges`.
+    ///
+    /// Returns the number of distinct cycles found.
+    pub fn scan_arbitrage_cycles(&mut self) -> usize {
+        self.arbitrage_cycles.clear();
+        self.arbitrage_tracking = true;
+
+        let edges: Vec<TradingEdge> = self.graph.values().flatten().cloned().collect();
+        for edge in &edges {
+            for cycle in self.find_cycles_through_edge(edge) {
+                self.record_arbitrage_cycle(cycle);
+            }
+        }
+
+        self.arbitrage_cycles.len()
+    }
+
+    /// Set the maximum number of edges in a tracked arbitrage cycle
+    pub fn set_max_cycle_length(&mut self, max_cycle_length: usize) {
+        self.max_cycle_length = max_cycle_length.max(2);
+    }
+
+    /// Number of distinct arbitrage cycles currently tracked
+    pub fn arbitrage_cycle_count(&self) -> usize {
+        self.arbitrage_cycles.len()
+    }
+
+    /// List profitable cycles ranked by expected profit at the given notional size
+    ///
+    /// The notional is denominated in each cycle's start token. Profit accounts for fees
+    /// and for price impact on every edge, so cycles that are only profitable at small
+    /// sizes drop out as the notional grows.
+    pub fn arbitrage_opportunities(&self, notional: f64) -> Vec<ArbitrageOpportunity> {
+        if notional <= 0.0 || !notional.is_finite() {
+            return Vec::new();
+        }
+
+        let no_volume = HashMap::new();
+        let mut opportunities: Vec<ArbitrageOpportunity> = self
+            .arbitrage_cycles
+            .iter()
+            .filter_map(|cycle| {
+                let expected_output = Self::simulate_path(cycle, &no_volume, notional, None);
+                let expected_profit = expected_output - notional;
+                if expected_profit <= 0.0 {
+                    return None;
+                }
+
+                Some(ArbitrageOpportunity {
+                    cycle: cycle.clone(),
+                    start_token: cycle[0].from_token.clone(),
+                    rate_product: cycle
+                        .iter()
+                        .map(|edge| edge.exchange_rate * (1.0 - edge.fee))
+                        .product(),
+                    expected_output,
+                    expected_profit,
+                })
+            })
+            .collect();
+
+        opportunities.sort_by(|a, b| {
+            b.expected_profit
+                .partial_cmp(&a.expected_profit)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        opportunities
+    }
+
+    /// Find every negative simple cycle that starts with `edge` and has at most
+    /// `max_cycle_length` edges
+    fn find_cycles_through_edge(&self, edge: &TradingEdge) -> Vec<Vec<TradingEdge>> {
+        let mut cycles = Vec::new();
+        if edge.from_token == edge.to_token {
+            return cycles;
+        }
+
+        let mut path = vec![edge.clone()];
+        let mut visited = HashSet::from([edge.from_token.clone(), edge.to_token.clone()]);
+        self.extend_cycles(
+            edge,
+            &mut path,
+            &mut visited,
+            edge.fee_adjusted_weight(),
+            &mut cycles,
+        );
+        cycles
+    }
+
+    /// Depth-first search over the simple paths continuing `path`, closing a cycle
+    /// whenever one returns to the start of `edge` with negative total weight
+    fn extend_cycles(
+        &self,
+        edge: &TradingEdge,
+        path: &mut Vec<TradingEdge>,
+        visited: &mut HashSet<TokenId>,
+        weight: f64,
+        cycles: &mut Vec<Vec<TradingEdge>>,
+    ) {
+        let token = path.last().expect("path starts with edge").to_token.clone();
+        let Some(edges) = self.graph.get(&token) else {
+            return;
+        };
+
+        for candidate in edges {
+            let total = weight + candidate.fee_adjusted_weight();
+            if candidate.to_token == edge.from_token {
+                if total < -f64::EPSILON {
+                    let mut cycle = path.clone();
+                    cycle.push(candidate.clone());
+                    cycles.push(cycle);
+                }
+            } else if path.len() + 1 < self.max_cycle_length
+                && visited.insert(candidate.to_token.clone())
+            {
+                // Only simple cycles are useful to keepers
+                path.push(candidate.clone());
+                self.extend_cycles(edge, path, visited, total, cycles);
+                path.pop();
+                visited.remove(&candidate.to_token);
+            }
+        }
+    }
+
+    /// Store a cycle, rotated to a canonical starting edge so duplicates are ignored
+    fn record_arbitrage_cycle(&mut self, mut cycle: Vec<TradingEdge>) {
+        let start = cycle
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, edge)| edge.venue_key())
+            .map(|(index, _)| index)
+            .unwrap_or(0);
+        cycle.rotate_left(start);
+
+        let key: Vec<_> = cycle.iter().map(TradingEdge::venue_key).collect();
+        let is_known = self.arbitrage_cycles.iter().any(|known| {
+            known.len() == key.len() && known.iter().map(TradingEdge::venue_key).eq(key.clone())
+        });
+
+        if !is_known {
+            self.arbitrage_cycles.push(cycle);
+        }
+    }
+
     /// Get all tokens in the graph
     pub fn get_tokens(&self) -> &[TokenId] {
         &self.tokens as &[TokenId]
@@ -608,6 +1386,15 @@ impl PathRouter {
     }
 }
 
+/// Why a cached route can no longer be used
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RouteStaleness {
+    /// The route's TTL elapsed
+    Expired,
+    /// An edge on the route was updated or removed
+    EdgeChanged,
+}
+
 /// Errors that can occur during path routing operations
 #[derive(Debug, Error)]
 pub enum PathRoutingError {
@@ -617,6 +1404,8 @@ pub enum PathRoutingError {
     SameSourceDestination,
     #[error("No path found between source and destination")]
     NoPathFound,
+    #[error("Invalid trade amount")]
+    InvalidAmount,
 }
 
 #[cfg(test)]
@@ -1081,6 +1870,478 @@ mod tests {
         assert_eq!(path.edges.len(), 2);
         assert_eq!(path.total_fee, 0.006); // 0.003 + 0.003
     }
+
+    #[test]
+    fn test_split_route_spreads_large_order_across_venues() {
+        let mut router = PathRouter::new();
+
+        router.add_edge(TradingEdge {
+            from_token: "ETH".to_string(),
+            to_token: "USDC".to_string(),
+            dex_name: "Uniswap".to_string(),
+            exchange_rate: 3200.0,
+            fee: 0.003,
+            liquidity: 1000,
+        });
+        router.add_edge(TradingEdge {
+            from_token: "ETH".to_string(),
+            to_token: "USDC".to_string(),
+            dex_name: "Curve".to_string(),
+            exchange_rate: 3190.0,
+            fee: 0.001,
+            liquidity: 1000,
+        });
+
+        let config = SplitRouteConfig::default();
+        let plan = router
+            .optimize_split_route(
+                &"ETH".to_string(),
+                &"USDC".to_string(),
+                500.0,
+                &config,
+                None,
+            )
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(plan.allocations.len(), 2);
+        let total_percent: f64 = plan.allocations.iter().map(|a| a.allocation_percent).sum();
+        assert!((total_percent - 100.0).abs() < 1e-6);
+
+        // Splitting must beat sending everything through the best single venue
+        let single_venue = router.graph[&"ETH".to_string()]
+            .iter()
+            .map(|edge| edge.output_with_impact(0.0, 500.0))
+            .fold(0.0, f64::max);
+        assert!(plan.expected_output > single_venue);
+        assert!(plan.effective_rate < 3200.0);
+    }
+
+    #[test]
+    fn test_split_route_small_order_uses_best_path() {
+        let mut router = PathRouter::new();
+
+        router.add_edge(TradingEdge {
+            from_token: "BTC".to_string(),
+            to_token: "USDC".to_string(),
+            dex_name: "Curve".to_string(),
+            exchange_rate: 42000.0,
+            fee: 0.001,
+            liquidity: 1_000_000,
+        });
+        router.add_edge(TradingEdge {
+            from_token: "BTC".to_string(),
+            to_token: "USDC".to_string(),
+            dex_name: "Uniswap".to_string(),
+            exchange_rate: 40000.0,
+            fee: 0.003,
+            liquidity: 1_000_000,
+        });
+
+        let config = SplitRouteConfig::default();
+        let plan = router
+            .optimize_split_route(&"BTC".to_string(), &"USDC".to_string(), 1.0, &config, None)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(plan.allocations.len(), 1);
+        assert_eq!(plan.allocations[0].path.edges[0].dex_name, "Curve");
+        assert!((plan.allocations[0].allocation_percent - 100.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_split_route_respects_max_splits_and_partial_fills() {
+        use crate::partial_fill::PartialFillOpportunity;
+
+        let mut router = PathRouter::new();
+        router.add_edge(TradingEdge {
+            from_token: "ETH".to_string(),
+            to_token: "USDC".to_string(),
+            dex_name: "Uniswap".to_string(),
+            exchange_rate: 3200.0,
+            fee: 0.003,
+            liquidity: 100,
+        });
+
+        let mut explorer = PartialFillExplorer::new();
+        explorer.add_opportunity(PartialFillOpportunity {
+            from_token: "ETH".to_string(),
+            to_token: "USDC".to_string(),
+            dex_name: "Balancer".to_string(),
+            available_liquidity: 100,
+            exchange_rate: 3200.0,
+            fee: 0.003,
+        });
+
+        let mut config = SplitRouteConfig::default();
+        let with_explorer = router
+            .optimize_split_route(
+                &"ETH".to_string(),
+                &"USDC".to_string(),
+                100.0,
+                &config,
+                Some(&explorer),
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(with_explorer.allocations.len(), 2);
+
+        config.max_splits = 1;
+        let single = router
+            .optimize_split_route(
+                &"ETH".to_string(),
+                &"USDC".to_string(),
+                100.0,
+                &config,
+                Some(&explorer),
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(single.allocations.len(), 1);
+        assert!(with_explorer.expected_output > single.expected_output);
+    }
+
+    #[test]
+    fn test_split_route_invalid_input() {
+        let router = PathRouter::new();
+        let config = SplitRouteConfig::default();
+
+        assert!(matches!(
+            router.optimize_split_route(&"BTC".to_string(), &"BTC".to_string(), 1.0, &config, None),
+            Err(PathRoutingError::SameSourceDestination)
+        ));
+        assert!(matches!(
+            router.optimize_split_route(&"BTC".to_string(), &"ETH".to_string(), 0.0, &config, None),
+            Err(PathRoutingError::InvalidAmount)
+        ));
+        assert!(router
+            .optimize_split_route(&"BTC".to_string(), &"ETH".to_string(), 1.0, &config, None)
+            .unwrap()
+            .is_none());
+    }
+
+    fn triangle_edges() -> Vec<TradingEdge> {
+        vec![
+            TradingEdge {
+                from_token: "USDC".to_string(),
+                to_token: "ETH".to_string(),
+                dex_name: "Uniswap".to_string(),
+                exchange_rate: 1.0 / 3000.0,
+                fee: 0.001,
+                liquidity: 1_000_000_000,
+            },
+            TradingEdge {
+                from_token: "ETH".to_string(),
+                to_token: "BTC".to_string(),
+                dex_name: "SushiSwap".to_string(),
+                exchange_rate: 0.05,
+                fee: 0.001,
+                liquidity: 1_000_000,
+            },
+            TradingEdge {
+                from_token: "BTC".to_string(),
+                to_token: "USDC".to_string(),
+                dex_name: "Curve".to_string(),
+                exchange_rate: 62000.0,
+                fee: 0.001,
+                liquidity: 100_000,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_scan_arbitrage_cycles() {
+        let mut router = PathRouter::new();
+        for edge in triangle_edges() {
+            router.add_edge(edge);
+        }
+        // Fair-priced reverse leg that must not be reported
+        router.add_edge(TradingEdge {
+            from_token: "ETH".to_string(),
+            to_token: "USDC".to_string(),
+            dex_name: "Uniswap".to_string(),
+            exchange_rate: 2990.0,
+            fee: 0.003,
+            liquidity: 10_000,
+        });
+
+        assert_eq!(router.scan_arbitrage_cycles(), 1);
+
+        let opportunities = router.arbitrage_opportunities(1.0);
+        assert_eq!(opportunities.len(), 1);
+        let best = &opportunities[0];
+        assert_eq!(best.cycle.len(), 3);
+        assert_eq!(best.cycle.first().unwrap().from_token, best.start_token);
+        assert_eq!(best.cycle.last().unwrap().to_token, best.start_token);
+        assert!(best.rate_product > 1.0);
+        assert!(best.expected_profit > 0.0);
+        assert!((best.expected_output - 1.0 - best.expected_profit).abs() < 1e-9);
+
+        // Price impact erodes the opportunity at large sizes
+        assert!(router.arbitrage_opportunities(1e12).is_empty());
+    }
+
+    #[test]
+    fn test_arbitrage_ranking_by_profit() {
+        let mut router = PathRouter::new();
+        router.add_edge(TradingEdge {
+            from_token: "A".to_string(),
+            to_token: "B".to_string(),
+            dex_name: "Small".to_string(),
+            exchange_rate: 1.05,
+            fee: 0.0,
+            liquidity: 1_000_000,
+        });
+        router.add_edge(TradingEdge {
+            from_token: "B".to_string(),
+            to_token: "A".to_string(),
+            dex_name: "Small".to_string(),
+            exchange_rate: 1.0,
+            fee: 0.0,
+            liquidity: 1_000_000,
+        });
+        router.add_edge(TradingEdge {
+            from_token: "C".to_string(),
+            to_token: "D".to_string(),
+            dex_name: "Large".to_string(),
+            exchange_rate: 1.2,
+            fee: 0.0,
+            liquidity: 1_000_000,
+        });
+        router.add_edge(TradingEdge {
+            from_token: "D".to_string(),
+            to_token: "C".to_string(),
+            dex_name: "Large".to_string(),
+            exchange_rate: 1.0,
+            fee: 0.0,
+            liquidity: 1_000_000,
+        });
+
+        assert_eq!(router.scan_arbitrage_cycles(), 2);
+        let opportunities = router.arbitrage_opportunities(100.0);
+        assert_eq!(opportunities.len(), 2);
+        assert!(opportunities[0].expected_profit > opportunities[1].expected_profit);
+        assert_eq!(opportunities[0].cycle[0].dex_name, "Large");
+    }
+
+    #[test]
+    fn test_incremental_arbitrage_tracking() {
+        let mut router = PathRouter::new();
+        let edges = triangle_edges();
+
+        router.add_edge(edges[0].clone());
+        router.add_edge(edges[1].clone());
+        assert_eq!(router.scan_arbitrage_cycles(), 0);
+
+        // Closing the triangle creates the cycle without a rescan
+        router.add_edge(edges[2].clone());
+        assert_eq!(router.arbitrage_cycle_count(), 1);
+        assert_eq!(router.arbitrage_opportunities(1.0).len(), 1);
+
+        // Re-adding an equivalent edge does not duplicate the cycle
+        router.add_edge(edges[2].clone());
+        assert_eq!(router.arbitrage_cycle_count(), 1);
+
+        router.remove_dex_edges("Curve");
+        assert_eq!(router.arbitrage_cycle_count(), 0);
+        assert!(router.arbitrage_opportunities(1.0).is_empty());
+    }
+
+    #[test]
+    fn test_edge_closing_several_arbitrage_cycles() {
+        let edge = |from: &str, to: &str, exchange_rate: f64| TradingEdge {
+            from_token: from.to_string(),
+            to_token: to.to_string(),
+            dex_name: "Uniswap".to_string(),
+            exchange_rate,
+            fee: 0.0,
+            liquidity: 1_000_000,
+        };
+        let mut router = PathRouter::new();
+        router.add_edge(edge("B", "A", 1.0));
+        router.add_edge(edge("B", "C", 1.0));
+        router.add_edge(edge("C", "A", 1.0));
+        assert_eq!(router.scan_arbitrage_cycles(), 0);
+
+        // A -> B returns to A both directly and through C
+        router.add_edge(edge("A", "B", 1.1));
+        assert_eq!(router.arbitrage_cycle_count(), 2);
+        let mut lengths: Vec<usize> = router
+            .arbitrage_opportunities(1.0)
+            .iter()
+            .map(|opportunity| opportunity.cycle.len())
+            .collect();
+        lengths.sort();
+        assert_eq!(lengths, vec![2, 3]);
+    }
+
+    fn eth_usdc_router() -> PathRouter {
+        let mut router = PathRouter::new();
+        router.add_edge(TradingEdge {
+            from_token: "BTC".to_string(),
+            to_token: "ETH".to_string(),
+            dex_name: "Uniswap".to_string(),
+            exchange_rate: 13.5,
+            fee: 0.003,
+            liquidity: 1000000,
+        });
+        router.add_edge(TradingEdge {
+            from_token: "ETH".to_string(),
+            to_token: "USDC".to_string(),
+            dex_name: "SushiSwap".to_string(),
+            exchange_rate: 3200.0,
+            fee: 0.003,
+            liquidity: 50000000,
+        });
+        router.add_edge(TradingEdge {
+            from_token: "DAI".to_string(),
+            to_token: "USDT".to_string(),
+            dex_name: "Curve".to_string(),
+            exchange_rate: 1.0,
+            fee: 0.0004,
+            liquidity: 50000000,
+        });
+        router
+    }
+
+    #[test]
+    fn test_edge_versions_and_targeted_invalidation() {
+        let mut router = eth_usdc_router();
+        let btc = "BTC".to_string();
+        let eth = "ETH".to_string();
+        let usdc = "USDC".to_string();
+        let dai = "DAI".to_string();
+        let usdt = "USDT".to_string();
+
+        let version = router.edge_version(&eth, &usdc, "SushiSwap").unwrap();
+
+        router.find_best_path(&btc, &usdc, 1.0).unwrap();
+        router.find_best_path(&dai, &usdt, 1.0).unwrap();
+        assert_eq!(router.route_cache_len(), 2);
+
+        // Updating a rate replaces the edge, bumps its version and drops only the route using it
+        router.add_edge(TradingEdge {
+            from_token: eth.clone(),
+            to_token: usdc.clone(),
+            dex_name: "SushiSwap".to_string(),
+            exchange_rate: 3300.0,
+            fee: 0.003,
+            liquidity: 50000000,
+        });
+        assert_eq!(router.edge_count(), 3);
+        assert!(router.edge_version(&eth, &usdc, "SushiSwap").unwrap() > version);
+        assert_eq!(router.route_cache_len(), 1);
+        assert!(router
+            .route_cache
+            .contains_key(&(dai.clone(), usdt.clone())));
+
+        let path = router.find_best_path(&btc, &usdc, 1.0).unwrap().unwrap();
+        assert_eq!(path.total_exchange_rate, 13.5 * 3300.0);
+
+        // An unrelated new edge leaves both cached routes alone
+        router.add_edge(TradingEdge {
+            from_token: "USDT".to_string(),
+            to_token: "EUR".to_string(),
+            dex_name: "Curve".to_string(),
+            exchange_rate: 0.92,
+            fee: 0.001,
+            liquidity: 1000000,
+        });
+        assert_eq!(router.route_cache_len(), 2);
+
+        // Removing a DEX drops only the routes that use it
+        router.remove_dex_edges("Curve");
+        assert_eq!(router.route_cache_len(), 1);
+        assert!(router.edge_version(&dai, &usdt, "Curve").is_none());
+        assert_eq!(router.route_cache_stats().invalidations, 2);
+    }
+
+    #[test]
+    fn test_new_shortcut_invalidates_reachable_pairs() {
+        let mut router = eth_usdc_router();
+        let btc = "BTC".to_string();
+        let usdc = "USDC".to_string();
+
+        router.find_best_path(&btc, &usdc, 1.0).unwrap();
+        assert_eq!(router.route_cache_len(), 1);
+
+        router.add_edge(TradingEdge {
+            from_token: btc.clone(),
+            to_token: usdc.clone(),
+            dex_name: "Curve".to_string(),
+            exchange_rate: 45000.0,
+            fee: 0.001,
+            liquidity: 2000000,
+        });
+        assert_eq!(router.route_cache_len(), 0);
+
+        let path = router.find_best_path(&btc, &usdc, 1.0).unwrap().unwrap();
+        assert_eq!(path.edges.len(), 1);
+        assert_eq!(path.edges[0].dex_name, "Curve");
+    }
+
+    #[test]
+    fn test_route_cache_ttl_expiry() {
+        let mut router = eth_usdc_router();
+        let btc = "BTC".to_string();
+        let usdc = "USDC".to_string();
+
+        router.set_route_cache_ttl(Some(Duration::ZERO));
+        router.find_best_path(&btc, &usdc, 1.0).unwrap();
+        router.find_best_path(&btc, &usdc, 1.0).unwrap();
+
+        let stats = router.route_cache_stats();
+        assert_eq!(stats.hits, 0);
+        assert_eq!(stats.misses, 2);
+        assert_eq!(stats.expirations, 1);
+
+        router.set_route_cache_ttl(None);
+        router.find_best_path(&btc, &usdc, 1.0).unwrap();
+        assert_eq!(router.route_cache_stats().hits, 1);
+    }
+
+    #[test]
+    fn test_route_cache_metrics_exported() {
+        let mut manager = ObservabilityManager::new();
+        PathRouter::register_route_cache_metrics(&mut manager).unwrap();
+        let manager = Arc::new(manager);
+
+        let mut router = eth_usdc_router();
+        router.attach_observability(manager.clone());
+        let btc = "BTC".to_string();
+        let usdc = "USDC".to_string();
+
+        router.find_best_path(&btc, &usdc, 1.0).unwrap();
+        router.find_best_path(&btc, &usdc, 2.0).unwrap();
+        router.find_best_path(&btc, &usdc, 3.0).unwrap();
+
+        assert_eq!(
+            manager.get_counter_value(ROUTE_CACHE_HITS_METRIC).unwrap(),
+            2
+        );
+        assert_eq!(
+            manager
+                .get_counter_value(ROUTE_CACHE_MISSES_METRIC)
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            manager.get_gauge_value(ROUTE_CACHE_ENTRIES_METRIC).unwrap(),
+            1
+        );
+
+        router.remove_dex_edges("SushiSwap");
+        assert_eq!(
+            manager
+                .get_counter_value(ROUTE_CACHE_EVICTIONS_METRIC)
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            manager.get_gauge_value(ROUTE_CACHE_ENTRIES_METRIC).unwrap(),
+            0
+        );
+    }
 }
 
 /// Helper struct for path information used in heap-based selection
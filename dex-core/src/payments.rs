@@ -28,6 +28,9 @@ pub struct OneTapTransfer {
     pub payment_method: Option<PaymentMethod>,
     pub fiat_currency: Option<String>,
     pub timestamp: u64,
+    /// Sender's signature over [`UniversalPayments::transfer_digest`]
+    #[serde(default)]
+    pub signature: Vec<u8>,
 }
 
 /// Speed optimization settings for transactions
@@ -81,6 +84,8 @@ pub enum PaymentError {
     RampIntegrationFailed,
     TransactionFailed,
     InvalidAmount,
+    /// The sender's signature did not authorize the transfer
+    Unauthorized,
 }
 
 /// Universal Payments Module
@@ -105,6 +110,15 @@ impl UniversalPayments {
         self.wallets.insert(trader_id, wallet);
     }
 
+    /// Digest the sender signs to authorize `transfer`
+    pub fn transfer_digest(&self, transfer: &OneTapTransfer) -> Result<[u8; 32], PaymentError> {
+        let wallet = self
+            .wallets
+            .get(&transfer.from_user)
+            .ok_or(PaymentError::InsufficientFunds)?;
+        Ok(wallet.next_transaction_digest(&transfer.to_user, &transfer.token_id, transfer.amount))
+    }
+
     /// Execute a one-tap transfer
     /// This is the core implementation of the "One-Tap Transfers" feature
     pub fn one_tap_transfer(
@@ -151,16 +165,24 @@ impl UniversalPayments {
             )
             .map_err(|_| PaymentError::InsufficientFunds)?;
 
-        // Auto-sign transaction for one-tap experience
-        // In a real implementation, this would use biometric or other authentication
-        sender_wallet
-            .sign_transaction(transaction_id, transfer.from_user.clone())
-            .map_err(|_| PaymentError::TransactionFailed)?;
-
-        // Execute transaction immediately for instant experience
-        sender_wallet
-            .execute_transaction(transaction_id)
-            .map_err(|_| PaymentError::TransactionFailed)?;
+        // Approve with the sender's signature and execute immediately for
+        // instant experience, releasing the funds if either step fails
+        let executed = sender_wallet
+            .sign_transaction(
+                transaction_id,
+                transfer.from_user.clone(),
+                &transfer.signature,
+            )
+            .and_then(|()| sender_wallet.execute_transaction(transaction_id));
+        if let Err(err) = executed {
+            let _ = sender_wallet.cancel_transaction(transaction_id);
+            return Err(match err {
+                MultiSigError::InvalidSignature(_) | MultiSigError::NotParticipant => {
+                    PaymentError::Unauthorized
+                }
+                _ => PaymentError::TransactionFailed,
+            });
+        }
 
         // Generate result
         self.transaction_counter += 1;
@@ -278,6 +300,7 @@ fn get_current_timestamp() -> u64 {
 mod tests {
     use super::*;
     use crate::multisig_wallet::WalletParticipant;
+    use ed25519_dalek::{Signer, SigningKey};
 
     #[test]
     fn test_one_tap_transfer() {
@@ -300,15 +323,10 @@ mod tests {
         let mut payments = UniversalPayments::new(config);
 
         // Create wallet participants
-        let participant1 = WalletParticipant {
-            id: "user1".to_string(),
-            public_key: "pubkey1".to_string(),
-        };
-
-        let participant2 = WalletParticipant {
-            id: "user2".to_string(),
-            public_key: "pubkey2".to_string(),
-        };
+        let user1_key = SigningKey::from_bytes(&[1; 32]);
+        let participant1 = WalletParticipant::ed25519("user1", &user1_key.verifying_key());
+        let participant2 =
+            WalletParticipant::ed25519("user2", &SigningKey::from_bytes(&[2; 32]).verifying_key());
 
         // Create wallets
         let mut wallet1 =
@@ -324,7 +342,7 @@ mod tests {
         payments.register_wallet("user2".to_string(), wallet2);
 
         // Create one-tap transfer
-        let transfer = OneTapTransfer {
+        let mut transfer = OneTapTransfer {
             from_user: "user1".to_string(),
             to_user: "user2".to_string(),
             token_id: "BTC".to_string(),
@@ -332,8 +350,17 @@ mod tests {
             payment_method: None,
             fiat_currency: None,
             timestamp: get_current_timestamp(),
+            signature: Vec::new(),
         };
 
+        // Transfers without the sender's signature are rejected and release the funds
+        let result = payments.one_tap_transfer(transfer.clone());
+        assert!(matches!(result, Err(PaymentError::Unauthorized)));
+
+        // Sign the transfer digest
+        let digest = payments.transfer_digest(&transfer).unwrap();
+        transfer.signature = user1_key.sign(&digest).to_bytes().to_vec();
+
         // Execute transfer
         let result = payments.one_tap_transfer(transfer);
         assert!(result.is_ok());
@@ -369,6 +396,7 @@ mod tests {
             payment_method: None,
             fiat_currency: None,
             timestamp: get_current_timestamp(),
+            signature: Vec::new(),
         };
 
         let result = payments.one_tap_transfer(transfer);
@@ -404,6 +432,7 @@ mod tests {
                 payment_method: None,
                 fiat_currency: None,
                 timestamp: get_current_timestamp(),
+                signature: Vec::new(),
             },
             OneTapTransfer {
                 from_user: "user1".to_string(),
@@ -413,6 +442,7 @@ mod tests {
                 payment_method: None,
                 fiat_currency: None,
                 timestamp: get_current_timestamp(),
+                signature: Vec::new(),
             },
         ];
 
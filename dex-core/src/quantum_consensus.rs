@@ -7,9 +7,33 @@
 //! "Core Components,DEX Chain Core,Quantum Consensus,Rust + GPU + Quantum Consensus,Quantum-Resistant Consensus,High"
 //! "Core Components,Quantum Consensus (QBFT),Consensus,QVRF Leader Selection,Leader Selection,High"
 //! "Core Components,Quantum Consensus (QBFT),Consensus,Lattice BFT Core,BFT Core,High"
-
+//!
+//! Agreement on blocks runs through [`crate::consensus::bft`]: the engine hands
+//! out [`BftNode`]s over its stake-weighted validator set and appends blocks
+//! to shards only with a valid commit certificate.
+//!
+//! Leaders are drawn with probability proportional to stake from a randomness
+//! beacon. The leader of each round evaluates the [`QVRF`] on the round's
+//! election input, and the verified output becomes the next beacon, so the
+//! sequence of leaders is unpredictable in advance yet checkable by anyone
+//! holding the validators' public keys.
+//!
+//! Shards execute the transfers in their blocks against a per-shard ledger
+//! (see [`crate::consensus::cross_shard`]). Transfers to accounts on other
+//! shards emit receipts, which are relayed with Merkle proofs once the source
+//! block is final. Global finality never passes a block whose outbound
+//! receipts are not yet final on their target shards.
+
+use crate::consensus::bft::{BftConfig, BftError, BftNode, CommittedBlock, ValidatorSet};
+use crate::consensus::cross_shard::{
+    receipts_root, shard_of, BlockOutcome, CrossShardError, Receipt, ReceiptProof, ShardHeader,
+    ShardState,
+};
+use crate::crypto::vrf::{self, EcVrf, VrfError};
 use crate::types::{Block, Transaction, Validator};
-use std::collections::HashMap;
+use ed25519_dalek::SigningKey;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
 use std::result::Result;
 
 /// Custom error types for quantum consensus operations
@@ -25,6 +49,14 @@ pub enum QuantumConsensusError {
     BlockProposalFailed,
     #[error("Network communication error: {0}")]
     NetworkError(String),
+    #[error("BFT consensus error: {0}")]
+    Bft(BftError),
+    #[error("VRF error: {0}")]
+    Vrf(VrfError),
+    #[error("{0} is not the leader of the current round")]
+    NotLeader(String),
+    #[error("Cross-shard error: {0}")]
+    CrossShard(CrossShardError),
 }
 
 /// Quantum-Resistant Consensus Engine
@@ -32,6 +64,8 @@ pub struct QuantumConsensusEngine {
     validators: HashMap<String, Validator>,
     current_round: u64,
     current_leader: Option<String>,
+    /// Output of the last verified leader VRF, seeding leader selection
+    beacon: Vec<u8>,
     /// Shards for the 1,000,000 Shards implementation
     /// This implements the Priority 2 feature from DEX-OS-V1.csv:
     /// "2,Core Components,Quantum Consensus (QBFT),Consensus,1,000,000 Shards,Sharding,High"
@@ -49,6 +83,14 @@ pub struct Shard {
     pub validators: Vec<String>,
     pub blocks: Vec<Block>,
     pub state_root: Vec<u8>,
+    /// Account ledger the state root commits to
+    pub state: ShardState,
+    /// Header of every applied block, by height
+    pub headers: BTreeMap<u64, ShardHeader>,
+    /// Receipts emitted for other shards, by source height
+    pub outbox: BTreeMap<u64, Vec<Receipt>>,
+    /// Verified receipts waiting to be credited by the next block
+    pub inbox: Vec<Receipt>,
 }
 
 /// Tracks global finality across all shards
@@ -56,8 +98,20 @@ pub struct Shard {
 pub struct GlobalFinalityTracker {
     /// Maps shard IDs to their finalized block heights
     finalized_heights: HashMap<u64, u64>,
-    /// The globally finalized block height (minimum of all shard finalities)
+    /// The globally finalized block height (minimum of all shard finalities,
+    /// held below any block with unfinalized cross-shard receipts)
     global_finalized_height: u64,
+    /// Cross-shard receipts not yet final on their target shard, by ID
+    pending_receipts: HashMap<[u8; 32], PendingReceipt>,
+}
+
+/// Progress of a cross-shard receipt towards finality
+#[derive(Debug, Clone)]
+struct PendingReceipt {
+    /// Height of the block that emitted the receipt
+    source_height: u64,
+    /// Target shard and height of the block that credited the receipt
+    credited_at: Option<(u64, u64)>,
 }
 
 impl GlobalFinalityTracker {
@@ -66,6 +120,7 @@ impl GlobalFinalityTracker {
         Self {
             finalized_heights: HashMap::new(),
             global_finalized_height: 0,
+            pending_receipts: HashMap::new(),
         }
     }
 
@@ -75,13 +130,57 @@ impl GlobalFinalityTracker {
         self.update_global_finality();
     }
 
+    /// Record a receipt emitted at `source_height`. Global finality stays
+    /// below that height until the receipt is final on its target shard.
+    pub fn track_receipt(&mut self, receipt_id: [u8; 32], source_height: u64) {
+        self.pending_receipts.insert(
+            receipt_id,
+            PendingReceipt {
+                source_height,
+                credited_at: None,
+            },
+        );
+        self.update_global_finality();
+    }
+
+    /// Record that a receipt was credited by the block at `height` on
+    /// `target_shard`
+    pub fn receipt_credited(&mut self, receipt_id: &[u8; 32], target_shard: u64, height: u64) {
+        if let Some(pending) = self.pending_receipts.get_mut(receipt_id) {
+            pending.credited_at = Some((target_shard, height));
+        }
+        self.update_global_finality();
+    }
+
+    /// Number of receipts not yet final on their target shard
+    pub fn pending_receipt_count(&self) -> usize {
+        self.pending_receipts.len()
+    }
+
     /// Update the global finality based on all shard finalities
     fn update_global_finality(&mut self) {
+        let finalized_heights = &self.finalized_heights;
+        self.pending_receipts
+            .retain(|_, pending| match pending.credited_at {
+                Some((shard, height)) => finalized_heights
+                    .get(&shard)
+                    .is_none_or(|finalized| *finalized < height),
+                None => true,
+            });
+
         if self.finalized_heights.is_empty() {
             self.global_finalized_height = 0;
         } else {
             // Global finality is the minimum finalized height across all shards
-            self.global_finalized_height = *self.finalized_heights.values().min().unwrap_or(&0);
+            let shard_minimum = *self.finalized_heights.values().min().unwrap_or(&0);
+            // and excludes blocks whose receipts are still in flight
+            let receipt_limit = self
+                .pending_receipts
+                .values()
+                .map(|pending| pending.source_height.saturating_sub(1))
+                .min()
+                .unwrap_or(u64::MAX);
+            self.global_finalized_height = shard_minimum.min(receipt_limit);
         }
     }
 
@@ -103,6 +202,7 @@ impl QuantumConsensusEngine {
             validators: HashMap::new(),
             current_round: 0,
             current_leader: None,
+            beacon: GENESIS_BEACON.to_vec(),
             shards: HashMap::new(),
             finality_tracker: GlobalFinalityTracker::new(),
         }
@@ -131,16 +231,61 @@ impl QuantumConsensusEngine {
     /// Implement QVRF Leader Selection algorithm
     /// This implements the Priority 1 feature from DEX-OS-V1.csv:
     /// "Core Components,Quantum Consensus (QBFT),Consensus,QVRF Leader Selection,Leader Selection,High"
+    ///
+    /// Each validator is chosen with probability proportional to its stake,
+    /// using a hash of the beacon and the round as the draw.
     fn qvrf_leader_selection(&self) -> Result<String, QuantumConsensusError> {
-        if self.validators.is_empty() {
+        let mut candidates: Vec<&Validator> =
+            self.validators.values().filter(|v| v.stake > 0).collect();
+        candidates.sort_by(|a, b| a.id.cmp(&b.id));
+        let total_stake: u128 = candidates.iter().map(|v| v.stake as u128).sum();
+        if total_stake == 0 {
             return Err(QuantumConsensusError::LeaderSelectionFailed);
         }
 
-        // In a real implementation, this would use Quantum Verifiable Random Function
-        // For now, we'll use a simple round-robin approach for demonstration
-        let validator_ids: Vec<String> = self.validators.keys().cloned().collect();
-        let index = self.current_round as usize % validator_ids.len();
-        Ok(validator_ids[index].clone())
+        let digest = Sha256::digest(self.leader_election_input());
+        let mut draw_bytes = [0u8; 16];
+        draw_bytes.copy_from_slice(&digest[..16]);
+        let mut draw = u128::from_be_bytes(draw_bytes) % total_stake;
+        for validator in candidates {
+            if draw < validator.stake as u128 {
+                return Ok(validator.id.clone());
+            }
+            draw -= validator.stake as u128;
+        }
+        Err(QuantumConsensusError::LeaderSelectionFailed)
+    }
+
+    /// Input the leader of the current round evaluates its VRF on
+    pub fn leader_election_input(&self) -> Vec<u8> {
+        let mut input = b"DEX-OS leader election".to_vec();
+        input.extend_from_slice(&self.beacon);
+        input.extend_from_slice(&self.current_round.to_be_bytes());
+        input
+    }
+
+    /// Current randomness beacon
+    pub fn beacon(&self) -> &[u8] {
+        &self.beacon
+    }
+
+    /// Accept the current leader's VRF proof over [`Self::leader_election_input`]
+    /// and make its output the next beacon. The proof is checked against the
+    /// leader's registered public key, so only the selected leader can move
+    /// the beacon and it can only move it to the one output its key allows.
+    pub fn record_leader_proof(
+        &mut self,
+        leader_id: &str,
+        proof: &[u8],
+    ) -> Result<Vec<u8>, QuantumConsensusError> {
+        if self.qvrf_leader_selection()? != leader_id {
+            return Err(QuantumConsensusError::NotLeader(leader_id.to_string()));
+        }
+        let validator = &self.validators[leader_id];
+        let output = vrf::verify(&validator.public_key, &self.leader_election_input(), proof)
+            .map_err(QuantumConsensusError::Vrf)?;
+        self.beacon = output.to_vec();
+        Ok(self.beacon.clone())
     }
 
     /// Validate a block proposal using Lattice BFT Core
@@ -175,17 +320,47 @@ impl QuantumConsensusEngine {
         &self,
         transaction: &Transaction,
     ) -> Result<bool, QuantumConsensusError> {
-        // Basic transaction validation
-        // In a real implementation, this would include quantum-resistant signature verification
-        if transaction.from.is_empty() || transaction.to.is_empty() {
-            return Ok(false);
-        }
+        Ok(is_valid_transaction(transaction))
+    }
 
-        if transaction.amount <= 0 {
-            return Ok(false);
-        }
+    /// Stake-weighted validator set for BFT agreement
+    pub fn validator_set(&self) -> Result<ValidatorSet, QuantumConsensusError> {
+        let validators: Vec<Validator> = self.validators.values().cloned().collect();
+        ValidatorSet::new(&validators).map_err(QuantumConsensusError::Bft)
+    }
 
-        Ok(true)
+    /// BFT state machine for the validator `validator_id`, which signs with `key`
+    pub fn bft_node(
+        &self,
+        validator_id: &str,
+        key: SigningKey,
+        config: BftConfig,
+    ) -> Result<BftNode, QuantumConsensusError> {
+        BftNode::new(validator_id, key, self.validator_set()?, config)
+            .map_err(QuantumConsensusError::Bft)
+    }
+
+    /// Append a block decided by BFT agreement to a shard, after checking its
+    /// commit certificate against the current validator set, and advance the
+    /// shard's finality to it
+    pub fn commit_block(
+        &mut self,
+        shard_id: u64,
+        committed: &CommittedBlock,
+    ) -> Result<(), QuantumConsensusError> {
+        committed
+            .verify(&self.validator_set()?)
+            .map_err(QuantumConsensusError::Bft)?;
+        let height = committed.block.height;
+        if self
+            .get_shard_finalized_height(shard_id)
+            .is_some_and(|finalized| height <= finalized)
+        {
+            return Err(QuantumConsensusError::BlockProposalFailed);
+        }
+        self.add_block_to_shard(shard_id, committed.block.clone())?;
+        self.update_shard_finality(shard_id, height);
+        Ok(())
     }
 
     /// Initialize 1,000,000 shards
@@ -214,11 +389,16 @@ impl QuantumConsensusEngine {
                 shard_validators.push(validator_ids[validator_index].clone());
             }
 
+            let state = ShardState::default();
             let shard = Shard {
                 id: shard_id,
                 validators: shard_validators,
                 blocks: Vec::new(),
-                state_root: vec![0; 32], // Placeholder state root
+                state_root: state.state_root(),
+                state,
+                headers: BTreeMap::new(),
+                outbox: BTreeMap::new(),
+                inbox: Vec::new(),
             };
 
             self.shards.insert(shard_id, shard);
@@ -237,18 +417,163 @@ impl QuantumConsensusEngine {
         &self.shards
     }
 
-    /// Add a block to a specific shard
+    /// Add a block to a specific shard. The block first credits the
+    /// shard's verified inbound receipts, then executes its transfers; the
+    /// shard's state root and header are updated accordingly.
     pub fn add_block_to_shard(
         &mut self,
         shard_id: u64,
         block: Block,
+    ) -> Result<BlockOutcome, QuantumConsensusError> {
+        let num_shards = self.shards.len() as u64;
+        let shard = self
+            .shards
+            .get_mut(&shard_id)
+            .ok_or_else(|| QuantumConsensusError::NetworkError("Shard not found".to_string()))?;
+        if shard
+            .headers
+            .last_key_value()
+            .is_some_and(|(height, _)| block.height <= *height)
+        {
+            return Err(QuantumConsensusError::BlockProposalFailed);
+        }
+
+        let inbox = std::mem::take(&mut shard.inbox);
+        let outcome = shard
+            .state
+            .apply_block(shard_id, num_shards, &block, &inbox);
+        shard.state_root = shard.state.state_root();
+        shard.headers.insert(
+            block.height,
+            ShardHeader {
+                height: block.height,
+                state_root: shard.state_root.clone(),
+                receipts_root: receipts_root(&outcome.receipts),
+                receipt_count: outcome.receipts.len(),
+            },
+        );
+        if !outcome.receipts.is_empty() {
+            shard.outbox.insert(block.height, outcome.receipts.clone());
+        }
+        shard.blocks.push(block.clone());
+
+        for receipt in &outcome.receipts {
+            self.finality_tracker
+                .track_receipt(receipt.id(), block.height);
+        }
+        for id in &outcome.credited {
+            self.finality_tracker
+                .receipt_credited(id, shard_id, block.height);
+        }
+        Ok(outcome)
+    }
+
+    /// Credit `amount` to `account` on its home shard outside of any block,
+    /// as a genesis allocation
+    pub fn fund_account(
+        &mut self,
+        account: &str,
+        amount: i64,
     ) -> Result<(), QuantumConsensusError> {
+        let shard_id = self.shard_of(account)?;
         let shard = self
             .shards
             .get_mut(&shard_id)
             .ok_or_else(|| QuantumConsensusError::NetworkError("Shard not found".to_string()))?;
+        shard
+            .state
+            .credit(account, amount)
+            .map_err(QuantumConsensusError::CrossShard)?;
+        shard.state_root = shard.state.state_root();
+        Ok(())
+    }
+
+    /// Shard holding `account`
+    pub fn shard_of(&self, account: &str) -> Result<u64, QuantumConsensusError> {
+        if self.shards.is_empty() {
+            return Err(QuantumConsensusError::NetworkError(
+                "Shards not initialized".to_string(),
+            ));
+        }
+        Ok(shard_of(account, self.shards.len() as u64))
+    }
 
-        shard.blocks.push(block);
+    /// Balance of `account` on its home shard
+    pub fn balance(&self, account: &str) -> Result<i64, QuantumConsensusError> {
+        let shard_id = self.shard_of(account)?;
+        Ok(self
+            .shards
+            .get(&shard_id)
+            .map_or(0, |shard| shard.state.balance(account)))
+    }
+
+    /// Proofs for the receipts `source_shard` emitted at `height`, ready to
+    /// be relayed to their target shards
+    pub fn receipt_proofs(
+        &self,
+        source_shard: u64,
+        height: u64,
+    ) -> Result<Vec<ReceiptProof>, QuantumConsensusError> {
+        let shard = self
+            .shards
+            .get(&source_shard)
+            .ok_or_else(|| QuantumConsensusError::NetworkError("Shard not found".to_string()))?;
+        let receipts = shard
+            .outbox
+            .get(&height)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        (0..receipts.len())
+            .map(|index| {
+                ReceiptProof::new(receipts, index).map_err(QuantumConsensusError::CrossShard)
+            })
+            .collect()
+    }
+
+    /// Verify a relayed receipt against its source shard's finalized header
+    /// and queue it for crediting by the target shard's next block
+    pub fn submit_receipt(&mut self, proof: &ReceiptProof) -> Result<(), QuantumConsensusError> {
+        let receipt = &proof.receipt;
+        let source = self
+            .shards
+            .get(&receipt.source_shard)
+            .ok_or_else(|| QuantumConsensusError::NetworkError("Shard not found".to_string()))?;
+        let header =
+            source
+                .headers
+                .get(&receipt.source_height)
+                .ok_or(QuantumConsensusError::CrossShard(
+                    CrossShardError::UnknownHeader {
+                        shard: receipt.source_shard,
+                        height: receipt.source_height,
+                    },
+                ))?;
+        if self
+            .get_shard_finalized_height(receipt.source_shard)
+            .is_none_or(|finalized| finalized < receipt.source_height)
+        {
+            return Err(QuantumConsensusError::CrossShard(
+                CrossShardError::NotFinalized {
+                    shard: receipt.source_shard,
+                    height: receipt.source_height,
+                },
+            ));
+        }
+        proof
+            .verify(header)
+            .map_err(QuantumConsensusError::CrossShard)?;
+
+        let id = receipt.id();
+        let target = self
+            .shards
+            .get_mut(&receipt.target_shard)
+            .ok_or_else(|| QuantumConsensusError::NetworkError("Shard not found".to_string()))?;
+        if target.state.is_consumed(&id) || target.inbox.iter().any(|queued| queued.id() == id) {
+            return Err(QuantumConsensusError::CrossShard(
+                CrossShardError::AlreadyConsumed,
+            ));
+        }
+        target.inbox.push(receipt.clone());
         Ok(())
     }
 
@@ -303,6 +628,12 @@ impl QuantumConsensusEngine {
     }
 }
 
+/// Basic transaction validation shared by block validation and BFT proposals.
+/// In a real implementation, this would include quantum-resistant signature verification.
+pub(crate) fn is_valid_transaction(transaction: &Transaction) -> bool {
+    !transaction.from.is_empty() && !transaction.to.is_empty() && transaction.amount > 0
+}
+
 impl Default for QuantumConsensusEngine {
     fn default() -> Self {
         Self::new()
@@ -312,63 +643,86 @@ impl Default for QuantumConsensusEngine {
 /// QVRF (Quantum Verifiable Random Function) implementation
 /// This implements the Priority 1 feature from DEX-OS-V1.csv:
 /// "Core Components,Quantum Consensus (QBFT),Consensus,QVRF Leader Selection,Leader Selection,High"
+///
+/// Uses ECVRF over ed25519 by default, or ML-DSA-65 with the `pq-vrf` feature.
+/// See [`crate::crypto::vrf`].
 pub struct QVRF {
-    secret_key: Vec<u8>,
-    public_key: Vec<u8>,
+    key: QvrfKey,
+}
+
+enum QvrfKey {
+    Ecvrf(Box<EcVrf>),
+    #[cfg(feature = "pq-vrf")]
+    MlDsa(Box<vrf::MlDsaVrf>),
 }
 
 impl QVRF {
-    /// Create a new QVRF instance
-    pub fn new(secret_key: Vec<u8>, public_key: Vec<u8>) -> Self {
+    /// Create a new QVRF instance from a 32-byte ed25519 secret key
+    pub fn new(secret_key: &[u8]) -> Result<Self, QuantumConsensusError> {
+        let key = EcVrf::from_secret(secret_key).map_err(QuantumConsensusError::Vrf)?;
+        Ok(Self {
+            key: QvrfKey::Ecvrf(Box::new(key)),
+        })
+    }
+
+    /// Create a QVRF instance sharing a validator's signing key
+    pub fn from_signing_key(key: &SigningKey) -> Self {
         Self {
-            secret_key,
-            public_key,
+            key: QvrfKey::Ecvrf(Box::new(EcVrf::from_signing_key(key))),
         }
     }
 
-    /// Generate a verifiable random value
+    /// Create a post-quantum QVRF instance from a 32-byte seed
+    #[cfg(feature = "pq-vrf")]
+    pub fn new_post_quantum(seed: &[u8]) -> Result<Self, QuantumConsensusError> {
+        let key = vrf::MlDsaVrf::from_seed(seed).map_err(QuantumConsensusError::Vrf)?;
+        Ok(Self {
+            key: QvrfKey::MlDsa(Box::new(key)),
+        })
+    }
+
+    /// Public key others verify outputs with
+    pub fn public_key(&self) -> Vec<u8> {
+        match &self.key {
+            QvrfKey::Ecvrf(key) => key.public_key().to_vec(),
+            #[cfg(feature = "pq-vrf")]
+            QvrfKey::MlDsa(key) => key.public_key(),
+        }
+    }
+
+    /// Generate a verifiable random value and its proof
     pub fn generate(&self, input: &[u8]) -> Result<(Vec<u8>, Vec<u8>), QuantumConsensusError> {
-        // In a real implementation, this would use quantum-resistant cryptography
-        // For now, we'll use a placeholder implementation
-        let random_value = self.placeholder_qvrf_function(input);
-        let proof = self.placeholder_proof_function(input);
-        Ok((random_value, proof))
+        match &self.key {
+            QvrfKey::Ecvrf(key) => {
+                let (output, proof) = key.evaluate(input).map_err(QuantumConsensusError::Vrf)?;
+                Ok((output.to_vec(), proof.to_vec()))
+            }
+            #[cfg(feature = "pq-vrf")]
+            QvrfKey::MlDsa(key) => {
+                let (output, proof) = key.evaluate(input).map_err(QuantumConsensusError::Vrf)?;
+                Ok((output.to_vec(), proof))
+            }
+        }
     }
 
-    /// Verify a QVRF output
+    /// Verify a QVRF output using only the generator's public key
     pub fn verify(
-        &self,
+        public_key: &[u8],
         input: &[u8],
         output: &[u8],
         proof: &[u8],
     ) -> Result<bool, QuantumConsensusError> {
-        // In a real implementation, this would verify using quantum-resistant cryptography
-        // For now, we'll use a placeholder implementation
-        let (expected_output, expected_proof) = self.generate(input)?;
-        Ok(expected_output == output && expected_proof == proof)
-    }
-
-    /// Placeholder for QVRF function
-    fn placeholder_qvrf_function(&self, input: &[u8]) -> Vec<u8> {
-        // This is a placeholder - in a real implementation, this would use quantum-resistant cryptography
-        let mut result = Vec::new();
-        for (i, byte) in input.iter().enumerate() {
-            result.push(byte.wrapping_add(i as u8));
-        }
-        result
-    }
-
-    /// Placeholder for proof generation
-    fn placeholder_proof_function(&self, input: &[u8]) -> Vec<u8> {
-        // This is a placeholder - in a real implementation, this would generate a cryptographic proof
-        let mut result = Vec::new();
-        for (i, byte) in input.iter().enumerate() {
-            result.push(byte.wrapping_mul(i as u8 + 1));
+        match vrf::verify(public_key, input, proof) {
+            Ok(expected) => Ok(expected.as_slice() == output),
+            Err(VrfError::VerificationFailed) => Ok(false),
+            Err(err) => Err(QuantumConsensusError::Vrf(err)),
         }
-        result
     }
 }
 
+/// Beacon before any leader has revealed a VRF output
+const GENESIS_BEACON: [u8; 32] = [0u8; 32];
+
 /// Lattice BFT Core implementation
 /// This implements the Priority 1 feature from DEX-OS-V1.csv:
 /// "Core Components,Quantum Consensus (QBFT),Consensus,Lattice BFT Core,BFT Core,High"
@@ -558,14 +912,67 @@ mod tests {
 
     #[test]
     fn test_qvrf_functionality() {
-        let qvrf = QVRF::new(vec![1, 2, 3, 4], vec![5, 6, 7, 8]);
+        let qvrf = QVRF::new(&[1u8; 32]).unwrap();
         let input = b"test input";
 
         let (output, proof) = qvrf.generate(input).unwrap();
         assert!(!output.is_empty());
         assert!(!proof.is_empty());
 
-        assert!(qvrf.verify(input, &output, &proof).unwrap());
+        let public_key = qvrf.public_key();
+        assert!(QVRF::verify(&public_key, input, &output, &proof).unwrap());
+        assert!(!QVRF::verify(&public_key, b"other input", &output, &proof).unwrap());
+        let other = QVRF::new(&[2u8; 32]).unwrap();
+        assert!(!QVRF::verify(&other.public_key(), input, &output, &proof).unwrap());
+        assert!(QVRF::new(&[1, 2, 3, 4]).is_err());
+    }
+
+    #[test]
+    fn test_leader_selection_is_stake_weighted_and_follows_the_beacon() {
+        let keys: Vec<SigningKey> = (1..=3u8)
+            .map(|i| SigningKey::from_bytes(&[i; 32]))
+            .collect();
+        let mut engine = QuantumConsensusEngine::new();
+        for (i, (key, stake)) in keys.iter().zip([100, 300, 600]).enumerate() {
+            engine
+                .add_validator(Validator {
+                    id: format!("validator{}", i + 1),
+                    public_key: key.verifying_key().to_bytes().to_vec(),
+                    stake,
+                })
+                .unwrap();
+        }
+
+        // The leader's VRF output moves the beacon, and nobody else's can
+        for _ in 0..5 {
+            let leader = engine.get_current_leader().unwrap();
+            let index: usize = leader["validator".len()..].parse().unwrap();
+            let input = engine.leader_election_input();
+
+            let other = QVRF::from_signing_key(&keys[index % 3]);
+            let (_, forged) = other.generate(&input).unwrap();
+            let other_id = format!("validator{}", index % 3 + 1);
+            assert!(engine.record_leader_proof(&other_id, &forged).is_err());
+            assert!(engine.record_leader_proof(&leader, &forged).is_err());
+
+            let qvrf = QVRF::from_signing_key(&keys[index - 1]);
+            let (output, proof) = qvrf.generate(&input).unwrap();
+            assert_eq!(engine.record_leader_proof(&leader, &proof).unwrap(), output);
+            assert_eq!(engine.beacon(), output.as_slice());
+            assert_ne!(engine.leader_election_input(), input);
+            engine.current_round += 1;
+        }
+
+        let mut wins: HashMap<String, u32> = HashMap::new();
+        for _ in 0..1000 {
+            *wins
+                .entry(engine.get_current_leader().unwrap())
+                .or_default() += 1;
+            engine.current_round += 1;
+        }
+        assert!((50..150).contains(&wins["validator1"]));
+        assert!((240..360).contains(&wins["validator2"]));
+        assert!((530..670).contains(&wins["validator3"]));
     }
 
     #[test]
@@ -760,4 +1167,87 @@ mod tests {
 
         assert!(engine.process_block_with_sharding(999, block).is_err());
     }
+
+    #[test]
+    fn test_cross_shard_transfer_holds_back_global_finality() {
+        let mut engine = QuantumConsensusEngine::new();
+        engine
+            .add_validator(Validator {
+                id: "validator1".to_string(),
+                public_key: vec![1, 2, 3, 4],
+                stake: 1000,
+            })
+            .unwrap();
+        engine.initialize_shards(2).unwrap();
+        let on_shard = |shard: u64, tag: &str| {
+            (0..)
+                .map(|i| format!("{}-{}", tag, i))
+                .find(|account| engine.shard_of(account).unwrap() == shard)
+                .unwrap()
+        };
+        let alice = on_shard(0, "alice");
+        let bob = on_shard(1, "bob");
+        engine.fund_account(&alice, 100).unwrap();
+        let block = |height: u64, transactions: Vec<Transaction>| Block {
+            id: height,
+            height,
+            timestamp: height,
+            transactions,
+            previous_hash: vec![],
+            hash: vec![],
+            signature: vec![],
+        };
+
+        let root_before = engine.get_shard(0).unwrap().state_root.clone();
+        let transfer = Transaction {
+            from: alice.clone(),
+            to: bob.clone(),
+            amount: 40,
+            nonce: 1,
+            signature: vec![],
+        };
+        let outcome = engine
+            .add_block_to_shard(0, block(1, vec![transfer]))
+            .unwrap();
+        assert_eq!(outcome.receipts.len(), 1);
+        assert_ne!(engine.get_shard(0).unwrap().state_root, root_before);
+        assert_eq!(engine.balance(&alice).unwrap(), 60);
+        assert_eq!(engine.balance(&bob).unwrap(), 0);
+
+        // Receipts of unfinalized blocks are not accepted
+        let proof = engine.receipt_proofs(0, 1).unwrap().remove(0);
+        assert!(matches!(
+            engine.submit_receipt(&proof),
+            Err(QuantumConsensusError::CrossShard(
+                CrossShardError::NotFinalized { .. }
+            ))
+        ));
+
+        engine.add_block_to_shard(1, block(1, vec![])).unwrap();
+        engine.update_shard_finality(0, 1);
+        engine.update_shard_finality(1, 1);
+        assert_eq!(engine.get_global_finalized_height(), 0);
+
+        let mut forged = proof.clone();
+        forged.receipt.amount = 4_000;
+        assert!(engine.submit_receipt(&forged).is_err());
+        engine.submit_receipt(&proof).unwrap();
+        assert!(engine.submit_receipt(&proof).is_err());
+
+        engine.add_block_to_shard(1, block(2, vec![])).unwrap();
+        assert_eq!(engine.balance(&bob).unwrap(), 40);
+        assert_eq!(engine.get_global_finalized_height(), 0);
+        engine.update_shard_finality(1, 2);
+        assert_eq!(engine.get_global_finalized_height(), 1);
+        assert_eq!(engine.finality_tracker.pending_receipt_count(), 0);
+
+        // A credited receipt cannot be replayed
+        assert!(matches!(
+            engine.submit_receipt(&proof),
+            Err(QuantumConsensusError::CrossShard(
+                CrossShardError::AlreadyConsumed
+            ))
+        ));
+        assert!(engine.add_block_to_shard(1, block(2, vec![])).is_err());
+    }
 }
@@ -0,0 +1,730 @@
+//! Certificate chains, revocation lists and status queries
+//!
+//! A [`Certificate`] binds its `id` (the subject, e.g. a node ID) to the
+//! ed25519 public key in `data`. `issuer` names the certificate of the
+//! authority that vouches for it, and `signature` is that authority's ed25519
+//! signature over [`Certificate::tbs_bytes`].
+//!
+//! [`CertificateManager`] builds chains from a certificate through the
+//! intermediate authorities it knows up to one of its configured trust
+//! anchors, checking every signature, validity window and revocation on the
+//! way. Authorities publish signed [`RevocationList`]s, imported with
+//! [`CertificateManager::import_crl`], and answer status queries with signed
+//! [`StatusResponse`]s in the manner of OCSP.
+//!
+//! Implements the Priority 3 feature from DEX-OS-V2.csv:
+//! - Security,Security,Security,B+ Tree,Certificate Management,Medium
+
+use super::{Certificate, CertificateManager, SecurityError};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+/// Domain separation for certificate signatures
+const CERTIFICATE_DOMAIN: &[u8] = b"dexos-certificate-v1";
+/// Domain separation for revocation list signatures
+const CRL_DOMAIN: &[u8] = b"dexos-certificate-crl-v1";
+/// Domain separation for status response signatures
+const STATUS_DOMAIN: &[u8] = b"dexos-certificate-status-v1";
+
+/// Longest chain accepted, trust anchor included
+pub const MAX_CHAIN_LENGTH: usize = 8;
+
+/// Why a certificate was revoked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RevocationReason {
+    Unspecified,
+    KeyCompromise,
+    AuthorityCompromise,
+    Superseded,
+    CessationOfOperation,
+}
+
+impl RevocationReason {
+    fn code(self) -> u8 {
+        match self {
+            RevocationReason::Unspecified => 0,
+            RevocationReason::KeyCompromise => 1,
+            RevocationReason::AuthorityCompromise => 2,
+            RevocationReason::Superseded => 3,
+            RevocationReason::CessationOfOperation => 4,
+        }
+    }
+}
+
+/// One entry of a revocation list
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevokedCertificate {
+    /// ID of the revoked certificate
+    pub id: String,
+    /// When it was revoked
+    pub revoked_at: u64,
+    /// Why it was revoked
+    pub reason: RevocationReason,
+}
+
+/// Certificates revoked by one authority, signed by it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevocationList {
+    /// Certificate ID of the authority that issued the revoked certificates
+    pub issuer: String,
+    /// When the list was produced
+    pub this_update: u64,
+    /// When the next list is due; the list is stale from then on
+    pub next_update: u64,
+    /// Revoked certificates
+    pub revoked: Vec<RevokedCertificate>,
+    /// Authority's ed25519 signature over everything above
+    pub signature: Vec<u8>,
+}
+
+/// Revocation status of a certificate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CertificateStatus {
+    /// Known and not revoked
+    Good,
+    /// Revoked, with the time and reason when a revocation list gave them
+    Revoked {
+        revoked_at: Option<u64>,
+        reason: RevocationReason,
+    },
+    /// Not known, or its authority's revocation list is stale
+    Unknown,
+}
+
+/// Signed answer to a status query
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusResponse {
+    /// Certificate the answer is about
+    pub certificate_id: String,
+    /// Certificate ID of the authority answering, which issued the certificate
+    pub responder: String,
+    /// Status at `produced_at`
+    pub status: CertificateStatus,
+    /// When the answer was produced
+    pub produced_at: u64,
+    /// Until when the answer may be relied on
+    pub next_update: u64,
+    /// Responder's ed25519 signature over everything above
+    pub signature: Vec<u8>,
+}
+
+impl Certificate {
+    /// Certificate binding `id` to `public_key`, signed by the authority
+    /// holding the certificate `issuer` with `issuer_secret`
+    pub fn issue(
+        id: &str,
+        public_key: &[u8],
+        issuer: &str,
+        issuer_secret: &[u8],
+        valid_from: u64,
+        valid_to: u64,
+    ) -> Result<Self, SecurityError> {
+        verifying_key(id, public_key)?;
+        let mut certificate = Certificate {
+            id: id.to_string(),
+            data: public_key.to_vec(),
+            issuer: issuer.to_string(),
+            valid_from,
+            valid_to,
+            signature: vec![],
+            revoked: false,
+        };
+        certificate.signature = sign(issuer_secret, &certificate.tbs_bytes())?;
+        Ok(certificate)
+    }
+
+    /// Self-signed certificate for the key `secret`, as used for trust anchors
+    pub fn self_signed(
+        id: &str,
+        secret: &[u8],
+        valid_from: u64,
+        valid_to: u64,
+    ) -> Result<Self, SecurityError> {
+        let public_key = signing_key(secret)?.verifying_key().to_bytes();
+        Self::issue(id, &public_key, id, secret, valid_from, valid_to)
+    }
+
+    /// Bytes covered by the issuer's signature: everything but the signature
+    /// and the local revocation flag
+    pub fn tbs_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(
+            CERTIFICATE_DOMAIN.len() + self.id.len() + self.data.len() + self.issuer.len() + 40,
+        );
+        bytes.extend_from_slice(CERTIFICATE_DOMAIN);
+        push_field(&mut bytes, self.id.as_bytes());
+        push_field(&mut bytes, &self.data);
+        push_field(&mut bytes, self.issuer.as_bytes());
+        bytes.extend_from_slice(&self.valid_from.to_be_bytes());
+        bytes.extend_from_slice(&self.valid_to.to_be_bytes());
+        bytes
+    }
+
+    /// Whether the certificate names itself as issuer
+    pub fn is_self_signed(&self) -> bool {
+        self.issuer == self.id
+    }
+
+    /// Whether `now` falls in the validity period
+    pub fn is_current(&self, now: u64) -> bool {
+        now >= self.valid_from && now <= self.valid_to
+    }
+
+    /// Check the signature against the issuer's public key
+    fn verify_signed_by(&self, issuer: &Certificate) -> Result<(), SecurityError> {
+        if verify(&issuer.id, &issuer.data, &self.tbs_bytes(), &self.signature)? {
+            Ok(())
+        } else {
+            Err(SecurityError::InvalidCertificate(format!(
+                "{}: signature does not verify under {}",
+                self.id, issuer.id
+            )))
+        }
+    }
+}
+
+impl RevocationList {
+    /// Revocation list of `issuer`, signed with `issuer_secret`
+    pub fn issue(
+        issuer: &str,
+        issuer_secret: &[u8],
+        this_update: u64,
+        next_update: u64,
+        revoked: Vec<RevokedCertificate>,
+    ) -> Result<Self, SecurityError> {
+        let mut list = RevocationList {
+            issuer: issuer.to_string(),
+            this_update,
+            next_update,
+            revoked,
+            signature: vec![],
+        };
+        list.signature = sign(issuer_secret, &list.signing_bytes())?;
+        Ok(list)
+    }
+
+    /// Entry for `cert_id`, if it is revoked
+    pub fn entry(&self, cert_id: &str) -> Option<&RevokedCertificate> {
+        self.revoked.iter().find(|entry| entry.id == cert_id)
+    }
+
+    fn signing_bytes(&self) -> Vec<u8> {
+        let mut bytes = CRL_DOMAIN.to_vec();
+        push_field(&mut bytes, self.issuer.as_bytes());
+        bytes.extend_from_slice(&self.this_update.to_be_bytes());
+        bytes.extend_from_slice(&self.next_update.to_be_bytes());
+        bytes.extend_from_slice(&(self.revoked.len() as u64).to_be_bytes());
+        for entry in &self.revoked {
+            push_field(&mut bytes, entry.id.as_bytes());
+            bytes.extend_from_slice(&entry.revoked_at.to_be_bytes());
+            bytes.push(entry.reason.code());
+        }
+        bytes
+    }
+}
+
+impl StatusResponse {
+    fn signing_bytes(&self) -> Vec<u8> {
+        let mut bytes = STATUS_DOMAIN.to_vec();
+        push_field(&mut bytes, self.certificate_id.as_bytes());
+        push_field(&mut bytes, self.responder.as_bytes());
+        match &self.status {
+            CertificateStatus::Good => bytes.push(0),
+            CertificateStatus::Revoked { revoked_at, reason } => {
+                bytes.push(1);
+                bytes.extend_from_slice(&revoked_at.unwrap_or(u64::MAX).to_be_bytes());
+                bytes.push(reason.code());
+            }
+            CertificateStatus::Unknown => bytes.push(2),
+        }
+        bytes.extend_from_slice(&self.produced_at.to_be_bytes());
+        bytes.extend_from_slice(&self.next_update.to_be_bytes());
+        bytes
+    }
+}
+
+impl CertificateManager {
+    /// Trust `anchor` as a root of certificate chains. Self-signed anchors
+    /// must carry a valid self-signature.
+    pub fn add_trust_anchor(&mut self, anchor: Certificate) -> Result<(), SecurityError> {
+        verifying_key(&anchor.id, &anchor.data)?;
+        if anchor.is_self_signed() {
+            anchor.verify_signed_by(&anchor)?;
+        }
+        self.trust_anchors.insert(anchor.id.clone(), anchor);
+        Ok(())
+    }
+
+    /// Stop trusting the anchor `anchor_id`
+    pub fn remove_trust_anchor(&mut self, anchor_id: &str) -> bool {
+        self.crls.remove(anchor_id);
+        self.trust_anchors.remove(anchor_id).is_some()
+    }
+
+    /// Whether any trust anchor is configured
+    pub fn has_trust_anchors(&self) -> bool {
+        !self.trust_anchors.is_empty()
+    }
+
+    /// Add the certificate of an intermediate authority, which may issue
+    /// certificates once its own chain verifies
+    pub fn add_authority(&mut self, certificate: Certificate) -> Result<(), SecurityError> {
+        if self.authorities.contains_key(&certificate.id) {
+            return Err(SecurityError::CertificateAlreadyExists);
+        }
+        verifying_key(&certificate.id, &certificate.data)?;
+        self.authorities.insert(certificate.id.clone(), certificate);
+        Ok(())
+    }
+
+    /// Verify the chain of the stored certificate `cert_id` at `now`
+    pub fn verify_chain(&self, cert_id: &str, now: u64) -> Result<Vec<Certificate>, SecurityError> {
+        let certificate = self
+            .get_certificate(cert_id)
+            .ok_or(SecurityError::CertificateNotFound)?;
+        self.verify_certificate(&certificate, now)
+    }
+
+    /// Verify the chain of `certificate`, stored or presented by a peer, at
+    /// `now`. Returns the chain from the certificate up to its trust anchor.
+    ///
+    /// Every certificate must be current and correctly signed by the next,
+    /// and none may be revoked locally or by its issuer's revocation list.
+    /// A stale revocation list fails the chain rather than being ignored.
+    pub fn verify_certificate(
+        &self,
+        certificate: &Certificate,
+        now: u64,
+    ) -> Result<Vec<Certificate>, SecurityError> {
+        if self.trust_anchors.get(&certificate.id) == Some(certificate) {
+            check_current(certificate, now)?;
+            return Ok(vec![certificate.clone()]);
+        }
+
+        let mut chain = vec![certificate.clone()];
+        loop {
+            let current = chain.last().expect("chain is never empty");
+            check_current(current, now)?;
+            self.check_not_revoked(current, now)?;
+
+            if let Some(anchor) = self.trust_anchors.get(&current.issuer) {
+                current.verify_signed_by(anchor)?;
+                check_current(anchor, now)?;
+                chain.push(anchor.clone());
+                return Ok(chain);
+            }
+            if chain.len() + 1 >= MAX_CHAIN_LENGTH {
+                return Err(SecurityError::UntrustedCertificateChain(format!(
+                    "{}: chain longer than {} certificates",
+                    certificate.id, MAX_CHAIN_LENGTH
+                )));
+            }
+            let issuer = self.authorities.get(&current.issuer).ok_or_else(|| {
+                SecurityError::UntrustedCertificateChain(format!(
+                    "{}: issuer {} is neither a trust anchor nor a known authority",
+                    current.id, current.issuer
+                ))
+            })?;
+            current.verify_signed_by(&issuer)?;
+            if chain.iter().any(|link| link.id == issuer.id) {
+                return Err(SecurityError::UntrustedCertificateChain(format!(
+                    "{}: issuer loop through {}",
+                    certificate.id, issuer.id
+                )));
+            }
+            chain.push(issuer);
+        }
+    }
+
+    /// Import a revocation list signed by a trust anchor or by an authority
+    /// whose chain verifies. Lists older than the one already imported for
+    /// the same issuer, and lists already stale, are rejected.
+    pub fn import_crl(&mut self, crl: RevocationList, now: u64) -> Result<(), SecurityError> {
+        let issuer = self.verified_authority(&crl.issuer, now)?;
+        if !verify(
+            &issuer.id,
+            &issuer.data,
+            &crl.signing_bytes(),
+            &crl.signature,
+        )? {
+            return Err(SecurityError::InvalidRevocationList(format!(
+                "{}: signature does not verify",
+                crl.issuer
+            )));
+        }
+        if crl.this_update > now || crl.next_update <= now {
+            return Err(SecurityError::InvalidRevocationList(format!(
+                "{}: not valid at {}",
+                crl.issuer, now
+            )));
+        }
+        if let Some(existing) = self.crls.get(&crl.issuer) {
+            if crl.this_update < existing.this_update {
+                return Err(SecurityError::InvalidRevocationList(format!(
+                    "{}: older than the imported list",
+                    crl.issuer
+                )));
+            }
+        }
+        self.crls.insert(crl.issuer.clone(), crl);
+        Ok(())
+    }
+
+    /// Imported revocation list of `issuer`
+    pub fn revocation_list(&self, issuer: &str) -> Option<&RevocationList> {
+        self.crls.get(issuer)
+    }
+
+    /// Revocation status of the stored certificate or authority `cert_id`
+    pub fn certificate_status(&self, cert_id: &str, now: u64) -> CertificateStatus {
+        let Some(certificate) = self
+            .get_certificate(cert_id)
+            .or_else(|| self.authorities.get(&cert_id.to_string()))
+        else {
+            return CertificateStatus::Unknown;
+        };
+        let crl = self.crls.get(&certificate.issuer);
+        if let Some(entry) = crl.and_then(|crl| crl.entry(cert_id)) {
+            return CertificateStatus::Revoked {
+                revoked_at: Some(entry.revoked_at),
+                reason: entry.reason,
+            };
+        }
+        if certificate.revoked {
+            return CertificateStatus::Revoked {
+                revoked_at: None,
+                reason: RevocationReason::Unspecified,
+            };
+        }
+        if crl.is_some_and(|crl| crl.next_update <= now) {
+            return CertificateStatus::Unknown;
+        }
+        CertificateStatus::Good
+    }
+
+    /// Answer a status query for `cert_id` as its issuer, signing with
+    /// `responder_secret`. The answer may be relied on for `validity` seconds.
+    pub fn status_response(
+        &self,
+        cert_id: &str,
+        responder_secret: &[u8],
+        now: u64,
+        validity: u64,
+    ) -> Result<StatusResponse, SecurityError> {
+        let certificate = self
+            .get_certificate(cert_id)
+            .or_else(|| self.authorities.get(&cert_id.to_string()))
+            .ok_or(SecurityError::CertificateNotFound)?;
+        let responder = self.verified_authority(&certificate.issuer, now)?;
+        let responder_key = signing_key(responder_secret)?;
+        if responder_key.verifying_key().to_bytes()[..] != responder.data[..] {
+            return Err(SecurityError::InvalidStatusResponse(format!(
+                "responder key does not belong to {}",
+                responder.id
+            )));
+        }
+        let mut response = StatusResponse {
+            certificate_id: cert_id.to_string(),
+            responder: responder.id,
+            status: self.certificate_status(cert_id, now),
+            produced_at: now,
+            next_update: now.saturating_add(validity),
+            signature: vec![],
+        };
+        response.signature = responder_key
+            .sign(&response.signing_bytes())
+            .to_bytes()
+            .to_vec();
+        Ok(response)
+    }
+
+    /// Check a status answer about `certificate` and return the status it
+    /// vouches for. The answer must come from the certificate's issuer, whose
+    /// own chain must verify, and must be fresh at `now`.
+    pub fn verify_status_response(
+        &self,
+        certificate: &Certificate,
+        response: &StatusResponse,
+        now: u64,
+    ) -> Result<CertificateStatus, SecurityError> {
+        if response.certificate_id != certificate.id || response.responder != certificate.issuer {
+            return Err(SecurityError::InvalidStatusResponse(format!(
+                "answer about {} from {} does not match {} issued by {}",
+                response.certificate_id, response.responder, certificate.id, certificate.issuer
+            )));
+        }
+        if response.produced_at > now || response.next_update <= now {
+            return Err(SecurityError::InvalidStatusResponse(format!(
+                "{}: answer not valid at {}",
+                response.certificate_id, now
+            )));
+        }
+        let responder = self.verified_authority(&response.responder, now)?;
+        if !verify(
+            &responder.id,
+            &responder.data,
+            &response.signing_bytes(),
+            &response.signature,
+        )? {
+            return Err(SecurityError::InvalidStatusResponse(format!(
+                "{}: signature does not verify",
+                response.certificate_id
+            )));
+        }
+        Ok(response.status.clone())
+    }
+
+    /// Trust anchor or authority `issuer`, with its chain verified
+    fn verified_authority(&self, issuer: &str, now: u64) -> Result<Certificate, SecurityError> {
+        if let Some(anchor) = self.trust_anchors.get(issuer) {
+            check_current(anchor, now)?;
+            return Ok(anchor.clone());
+        }
+        let authority = self.authorities.get(&issuer.to_string()).ok_or_else(|| {
+            SecurityError::UntrustedCertificateChain(format!(
+                "{} is neither a trust anchor nor a known authority",
+                issuer
+            ))
+        })?;
+        self.verify_certificate(&authority, now)?;
+        Ok(authority)
+    }
+
+    /// Fail if `certificate` is revoked locally or by its issuer's list
+    fn check_not_revoked(&self, certificate: &Certificate, now: u64) -> Result<(), SecurityError> {
+        let stored_revoked = self
+            .get_certificate(&certificate.id)
+            .or_else(|| self.authorities.get(&certificate.id))
+            .is_some_and(|stored| stored.revoked);
+        if certificate.revoked || stored_revoked {
+            return Err(SecurityError::CertificateRevoked(certificate.id.clone()));
+        }
+        if let Some(crl) = self.crls.get(&certificate.issuer) {
+            if crl.next_update <= now {
+                return Err(SecurityError::StaleRevocationList(crl.issuer.clone()));
+            }
+            if crl.entry(&certificate.id).is_some() {
+                return Err(SecurityError::CertificateRevoked(certificate.id.clone()));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn check_current(certificate: &Certificate, now: u64) -> Result<(), SecurityError> {
+    if certificate.is_current(now) {
+        Ok(())
+    } else {
+        Err(SecurityError::CertificateExpired(certificate.id.clone()))
+    }
+}
+
+fn push_field(bytes: &mut Vec<u8>, field: &[u8]) {
+    bytes.extend_from_slice(&(field.len() as u64).to_be_bytes());
+    bytes.extend_from_slice(field);
+}
+
+fn signing_key(secret: &[u8]) -> Result<SigningKey, SecurityError> {
+    let secret: [u8; 32] = secret
+        .try_into()
+        .map_err(|_| SecurityError::InvalidKeyAlgorithm)?;
+    Ok(SigningKey::from_bytes(&secret))
+}
+
+fn sign(secret: &[u8], message: &[u8]) -> Result<Vec<u8>, SecurityError> {
+    Ok(signing_key(secret)?.sign(message).to_bytes().to_vec())
+}
+
+fn verifying_key(id: &str, public_key: &[u8]) -> Result<VerifyingKey, SecurityError> {
+    public_key
+        .try_into()
+        .ok()
+        .and_then(|bytes: &[u8; 32]| VerifyingKey::from_bytes(bytes).ok())
+        .ok_or_else(|| {
+            SecurityError::InvalidCertificate(format!("{}: not an ed25519 public key", id))
+        })
+}
+
+fn verify(
+    id: &str,
+    public_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> Result<bool, SecurityError> {
+    let key = verifying_key(id, public_key)?;
+    Ok(Signature::from_slice(signature)
+        .map(|signature| key.verify(message, &signature).is_ok())
+        .unwrap_or(false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::SecurityManager;
+
+    const NOW: u64 = 1_700_000_000;
+    const DAY: u64 = 86_400;
+
+    /// Root anchor, intermediate authority and a node certificate issued by it
+    fn hierarchy() -> (CertificateManager, Vec<u8>, Vec<u8>) {
+        let (root_secret, _) = SecurityManager::generate_key_pair();
+        let (ca_secret, ca_public) = SecurityManager::generate_key_pair();
+        let (_, node_public) = SecurityManager::generate_key_pair();
+
+        let mut manager = CertificateManager::new();
+        manager
+            .add_trust_anchor(Certificate::self_signed("root", &root_secret, 0, u64::MAX).unwrap())
+            .unwrap();
+        manager
+            .add_authority(
+                Certificate::issue("ops-ca", &ca_public, "root", &root_secret, 0, NOW + DAY)
+                    .unwrap(),
+            )
+            .unwrap();
+        manager
+            .add_certificate(
+                Certificate::issue("node-1", &node_public, "ops-ca", &ca_secret, 0, NOW + DAY)
+                    .unwrap(),
+            )
+            .unwrap();
+        (manager, root_secret, ca_secret)
+    }
+
+    #[test]
+    fn test_chain_verifies_up_to_trust_anchor() {
+        let (mut manager, _, ca_secret) = hierarchy();
+        let chain = manager.verify_chain("node-1", NOW).unwrap();
+        let ids: Vec<&str> = chain.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, ["node-1", "ops-ca", "root"]);
+
+        // Expiry anywhere in the chain fails it
+        assert!(matches!(
+            manager.verify_chain("node-1", NOW + 2 * DAY),
+            Err(SecurityError::CertificateExpired(id)) if id == "node-1"
+        ));
+
+        // A tampered certificate no longer verifies
+        let mut forged = manager.get_certificate("node-1").unwrap();
+        forged.data = SecurityManager::generate_key_pair().1;
+        assert!(matches!(
+            manager.verify_certificate(&forged, NOW),
+            Err(SecurityError::InvalidCertificate(_))
+        ));
+
+        // Certificates from an authority that does not chain to an anchor fail
+        let (rogue_secret, _) = SecurityManager::generate_key_pair();
+        let (_, node_public) = SecurityManager::generate_key_pair();
+        manager
+            .add_authority(
+                Certificate::self_signed("rogue-ca", &rogue_secret, 0, u64::MAX).unwrap(),
+            )
+            .unwrap();
+        let rogue = Certificate::issue(
+            "node-2",
+            &node_public,
+            "rogue-ca",
+            &rogue_secret,
+            0,
+            u64::MAX,
+        )
+        .unwrap();
+        assert!(matches!(
+            manager.verify_certificate(&rogue, NOW),
+            Err(SecurityError::UntrustedCertificateChain(_))
+        ));
+
+        // A certificate the intermediate issued verifies when presented
+        let presented =
+            Certificate::issue("node-3", &node_public, "ops-ca", &ca_secret, 0, NOW + DAY).unwrap();
+        assert_eq!(
+            manager.verify_certificate(&presented, NOW).unwrap().len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_revocation_lists_and_status_queries() {
+        let (mut manager, root_secret, ca_secret) = hierarchy();
+        assert_eq!(
+            manager.certificate_status("node-1", NOW),
+            CertificateStatus::Good
+        );
+        assert_eq!(
+            manager.certificate_status("node-9", NOW),
+            CertificateStatus::Unknown
+        );
+
+        let revoked = vec![RevokedCertificate {
+            id: "node-1".to_string(),
+            revoked_at: NOW - 10,
+            reason: RevocationReason::KeyCompromise,
+        }];
+        // Lists signed by anyone but the issuing authority are rejected
+        let forged =
+            RevocationList::issue("ops-ca", &root_secret, NOW - 10, NOW + DAY, revoked.clone())
+                .unwrap();
+        assert!(matches!(
+            manager.import_crl(forged, NOW),
+            Err(SecurityError::InvalidRevocationList(_))
+        ));
+
+        let older =
+            RevocationList::issue("ops-ca", &ca_secret, NOW - 20, NOW + DAY, vec![]).unwrap();
+        let crl =
+            RevocationList::issue("ops-ca", &ca_secret, NOW - 10, NOW + 3600, revoked).unwrap();
+        manager.import_crl(crl, NOW).unwrap();
+        assert!(matches!(
+            manager.verify_chain("node-1", NOW),
+            Err(SecurityError::CertificateRevoked(id)) if id == "node-1"
+        ));
+        assert!(!manager.is_certificate_valid("node-1"));
+        assert_eq!(
+            manager.certificate_status("node-1", NOW),
+            CertificateStatus::Revoked {
+                revoked_at: Some(NOW - 10),
+                reason: RevocationReason::KeyCompromise,
+            }
+        );
+        // Rolling back to an older list is refused
+        assert!(manager.import_crl(older, NOW).is_err());
+
+        // Status answers are signed by the issuer and checked by peers
+        let certificate = manager.get_certificate("node-1").unwrap();
+        let response = manager
+            .status_response("node-1", &ca_secret, NOW, 3600)
+            .unwrap();
+        assert!(matches!(
+            manager.verify_status_response(&certificate, &response, NOW),
+            Ok(CertificateStatus::Revoked { .. })
+        ));
+        assert!(manager
+            .verify_status_response(&certificate, &response, NOW + 3600)
+            .is_err());
+        let mut tampered = response.clone();
+        tampered.status = CertificateStatus::Good;
+        assert!(matches!(
+            manager.verify_status_response(&certificate, &tampered, NOW),
+            Err(SecurityError::InvalidStatusResponse(_))
+        ));
+        assert!(manager
+            .status_response("node-1", &root_secret, NOW, 3600)
+            .is_err());
+
+        // A stale list fails chains rather than being ignored
+        let (_, node_public) = SecurityManager::generate_key_pair();
+        manager
+            .add_certificate(
+                Certificate::issue("node-4", &node_public, "ops-ca", &ca_secret, 0, NOW + DAY)
+                    .unwrap(),
+            )
+            .unwrap();
+        assert!(manager.verify_chain("node-4", NOW).is_ok());
+        assert!(matches!(
+            manager.verify_chain("node-4", NOW + 7200),
+            Err(SecurityError::StaleRevocationList(issuer)) if issuer == "ops-ca"
+        ));
+        assert_eq!(
+            manager.certificate_status("node-4", NOW + 7200),
+            CertificateStatus::Unknown
+        );
+    }
+}
@@ -0,0 +1,704 @@
+//! Tamper-evident security event log
+//!
+//! Every [`SecurityEvent`] logged by an [`EventLogger`] carries its sequence
+//! number, the hash of the event before it and its own hash, computed over
+//! the canonical JSON of the event including that previous hash. Every
+//! `interval` events the logger signs a [`Checkpoint`] of the chain head with
+//! ed25519. Dropping, reordering or editing an event breaks the chain, and
+//! rewriting the chain from there on no longer matches the signed checkpoints.
+//! [`verify_log`] checks both, over events kept in memory or read back from
+//! the sinks' files.
+//!
+//! Events and checkpoints are handed to [`EventSink`]s as they are produced:
+//! [`JsonlSink`] writes rotating JSON Lines files that [`read_jsonl`] reads
+//! back for verification, [`SyslogSink`] appends RFC 5424 lines for log
+//! collectors, and `governance::audit::AuditSink` files each checkpointed
+//! batch as signed evidence in the audit store.
+//!
+//! Implements the Priority 3 feature from DEX-OS-V2.csv:
+//! - Security,Orderbook,Orderbook,Event Logging,Security Auditing,Medium
+
+use super::{
+    ClassificationLevel, EventLogger, Redactor, SecurityError, SecurityEvent, SeverityLevel,
+};
+use crate::identity::credentials::{canonical_json, format_timestamp};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// `previous_hash` of the first event of a log
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Domain separation for event hashes
+const EVENT_DOMAIN: &[u8] = b"dexos-security-event-v1";
+/// Domain separation for checkpoint signatures
+const CHECKPOINT_DOMAIN: &[u8] = b"dexos-security-checkpoint-v1";
+
+/// Syslog facility 13, "log audit"
+const SYSLOG_FACILITY: u8 = 13;
+/// Structured data ID of the chain parameters in syslog lines
+const SYSLOG_SD_ID: &str = "dexos@32473";
+
+/// Signed statement of the chain head after `sequence`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Sequence number of the last event covered
+    pub sequence: u64,
+    /// Hash of that event
+    pub head_hash: String,
+    /// When the checkpoint was signed
+    pub timestamp: u64,
+    /// Base64 ed25519 public key of the signer
+    pub public_key: String,
+    /// Base64 ed25519 signature over sequence, head hash and timestamp
+    pub signature: String,
+}
+
+impl Checkpoint {
+    fn signing_bytes(sequence: u64, head_hash: &str, timestamp: u64) -> Vec<u8> {
+        let mut bytes = CHECKPOINT_DOMAIN.to_vec();
+        bytes.extend_from_slice(&sequence.to_be_bytes());
+        bytes.extend_from_slice(head_hash.as_bytes());
+        bytes.extend_from_slice(&timestamp.to_be_bytes());
+        bytes
+    }
+
+    fn sign(signing_key: &SigningKey, sequence: u64, head_hash: &str, timestamp: u64) -> Self {
+        let signature = signing_key.sign(&Self::signing_bytes(sequence, head_hash, timestamp));
+        Self {
+            sequence,
+            head_hash: head_hash.to_string(),
+            timestamp,
+            public_key: STANDARD.encode(signing_key.verifying_key().to_bytes()),
+            signature: STANDARD.encode(signature.to_bytes()),
+        }
+    }
+
+    /// Whether the signature verifies under `public_key`
+    pub fn verify(&self, public_key: &VerifyingKey) -> bool {
+        STANDARD
+            .decode(&self.signature)
+            .ok()
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+            .is_some_and(|signature| {
+                public_key
+                    .verify(
+                        &Self::signing_bytes(self.sequence, &self.head_hash, self.timestamp),
+                        &signature,
+                    )
+                    .is_ok()
+            })
+    }
+}
+
+/// One line of an exported log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+pub enum LogRecord {
+    Event(SecurityEvent),
+    Checkpoint(Checkpoint),
+}
+
+/// Destination for events and checkpoints as they are produced
+pub trait EventSink: fmt::Debug + Send + Sync {
+    /// Write one record. Failures are counted by the logger, which keeps
+    /// logging.
+    fn record(&self, record: &LogRecord) -> Result<(), SecurityError>;
+}
+
+/// What [`verify_log`] established
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogVerification {
+    /// Sequence number of the first event checked
+    pub first_sequence: u64,
+    /// Sequence number of the last event checked
+    pub last_sequence: u64,
+    /// Number of events checked
+    pub events: usize,
+    /// Checkpoints whose signature and head hash matched the events
+    pub checkpoints: usize,
+    /// Whether the events start at the beginning of the log
+    pub from_genesis: bool,
+    /// Events after the last matching checkpoint, whose removal from the end
+    /// of the log could not be detected
+    pub unsealed: u64,
+}
+
+/// Hash of `event`, over its canonical JSON without the `hash` field
+pub fn event_hash(event: &SecurityEvent) -> String {
+    let mut value = serde_json::to_value(event).expect("security events serialize");
+    if let Some(object) = value.as_object_mut() {
+        object.remove("hash");
+    }
+    let mut hasher = Sha256::new();
+    hasher.update(EVENT_DOMAIN);
+    hasher.update(canonical_json(&value).as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Check that `events` form an unbroken chain and agree with every
+/// checkpoint signed by `public_key` that covers them.
+///
+/// `events` may start after the beginning of the log, as when older events
+/// were trimmed or rotated away. Checkpoints beyond the last event mean
+/// events were removed from the end and fail verification.
+pub fn verify_log(
+    events: &[SecurityEvent],
+    checkpoints: &[Checkpoint],
+    public_key: &[u8],
+) -> Result<LogVerification, SecurityError> {
+    let public_key = public_key
+        .try_into()
+        .ok()
+        .and_then(|bytes: &[u8; 32]| VerifyingKey::from_bytes(bytes).ok())
+        .ok_or(SecurityError::InvalidKeyAlgorithm)?;
+    let (Some(first), Some(last)) = (events.first(), events.last()) else {
+        return Err(SecurityError::EventLog("no events to verify".to_string()));
+    };
+
+    let mut previous: Option<&SecurityEvent> = None;
+    for event in events {
+        if let Some(previous) = previous {
+            if event.sequence != previous.sequence + 1 {
+                return Err(SecurityError::EventLogTampered(format!(
+                    "event {} follows event {}",
+                    event.sequence, previous.sequence
+                )));
+            }
+            if event.previous_hash != previous.hash {
+                return Err(SecurityError::EventLogTampered(format!(
+                    "event {} does not link to event {}",
+                    event.sequence, previous.sequence
+                )));
+            }
+        } else if event.sequence == 0 && event.previous_hash != GENESIS_HASH {
+            return Err(SecurityError::EventLogTampered(
+                "first event does not start from genesis".to_string(),
+            ));
+        }
+        if event_hash(event) != event.hash {
+            return Err(SecurityError::EventLogTampered(format!(
+                "event {} was modified",
+                event.sequence
+            )));
+        }
+        previous = Some(event);
+    }
+
+    let mut matched = 0;
+    let mut sealed_up_to = None;
+    for checkpoint in checkpoints {
+        if !checkpoint.verify(&public_key) {
+            return Err(SecurityError::EventLogTampered(format!(
+                "checkpoint at event {} has an invalid signature",
+                checkpoint.sequence
+            )));
+        }
+        if checkpoint.sequence < first.sequence {
+            continue;
+        }
+        if checkpoint.sequence > last.sequence {
+            return Err(SecurityError::EventLogTampered(format!(
+                "checkpoint covers event {} but the log ends at event {}",
+                checkpoint.sequence, last.sequence
+            )));
+        }
+        let covered = &events[(checkpoint.sequence - first.sequence) as usize];
+        if covered.hash != checkpoint.head_hash {
+            return Err(SecurityError::EventLogTampered(format!(
+                "event {} does not match its checkpoint",
+                checkpoint.sequence
+            )));
+        }
+        matched += 1;
+        sealed_up_to = sealed_up_to.max(Some(checkpoint.sequence));
+    }
+
+    Ok(LogVerification {
+        first_sequence: first.sequence,
+        last_sequence: last.sequence,
+        events: events.len(),
+        checkpoints: matched,
+        from_genesis: first.sequence == 0,
+        unsealed: match sealed_up_to {
+            Some(sequence) => last.sequence - sequence,
+            None => events.len() as u64,
+        },
+    })
+}
+
+/// Read events and checkpoints back from JSON Lines files, in order
+pub fn read_jsonl<P: AsRef<Path>>(
+    paths: &[P],
+) -> Result<(Vec<SecurityEvent>, Vec<Checkpoint>), SecurityError> {
+    let mut events = Vec::new();
+    let mut checkpoints = Vec::new();
+    for path in paths {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .map_err(|e| SecurityError::EventLog(format!("{}: {}", path.display(), e)))?;
+        for (number, line) in BufReader::new(file).lines().enumerate() {
+            let line =
+                line.map_err(|e| SecurityError::EventLog(format!("{}: {}", path.display(), e)))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record = serde_json::from_str(&line).map_err(|e| {
+                SecurityError::EventLog(format!("{}:{}: {}", path.display(), number + 1, e))
+            })?;
+            match record {
+                LogRecord::Event(event) => events.push(event),
+                LogRecord::Checkpoint(checkpoint) => checkpoints.push(checkpoint),
+            }
+        }
+    }
+    Ok((events, checkpoints))
+}
+
+impl EventLogger {
+    /// Sign a checkpoint every `interval` events with the ed25519 key
+    /// `signing_secret`
+    pub fn with_checkpoints(
+        mut self,
+        interval: u64,
+        signing_secret: &[u8],
+    ) -> Result<Self, SecurityError> {
+        let secret: [u8; 32] = signing_secret
+            .try_into()
+            .map_err(|_| SecurityError::InvalidKeyAlgorithm)?;
+        self.checkpoint_interval = interval.max(1);
+        self.checkpoint_key = Some(SigningKey::from_bytes(&secret));
+        Ok(self)
+    }
+
+    /// Also write events and checkpoints to `sink`
+    pub fn with_sink(mut self, sink: Arc<dyn EventSink>) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    /// Redact event descriptions and data with `redactor` at `level` before
+    /// they are chained and written to the sinks
+    pub fn with_redaction(mut self, redactor: Arc<Redactor>, level: ClassificationLevel) -> Self {
+        self.redaction = Some((redactor, level));
+        self
+    }
+
+    /// Public key checkpoints are signed with
+    pub fn checkpoint_public_key(&self) -> Option<Vec<u8>> {
+        self.checkpoint_key
+            .as_ref()
+            .map(|key| key.verifying_key().to_bytes().to_vec())
+    }
+
+    /// Hash of the latest event
+    pub fn head_hash(&self) -> &str {
+        &self.head_hash
+    }
+
+    /// Checkpoints covering the events still held
+    pub fn get_checkpoints(&self) -> &[Checkpoint] {
+        &self.checkpoints
+    }
+
+    /// Number of records the sinks failed to write
+    pub fn sink_errors(&self) -> u64 {
+        self.sink_errors
+    }
+
+    /// Sign a checkpoint of the current head now, e.g. before shutting down,
+    /// unless the head is already checkpointed
+    pub fn checkpoint(&mut self) -> Option<Checkpoint> {
+        let last = self.events.last()?.sequence;
+        if self
+            .checkpoints
+            .last()
+            .is_some_and(|checkpoint| checkpoint.sequence == last)
+        {
+            return None;
+        }
+        let checkpoint = Checkpoint::sign(
+            self.checkpoint_key.as_ref()?,
+            last,
+            &self.head_hash,
+            now_secs(),
+        );
+        self.checkpoints.push(checkpoint.clone());
+        self.dispatch(&LogRecord::Checkpoint(checkpoint.clone()));
+        Some(checkpoint)
+    }
+
+    /// Verify the events still held against the logger's checkpoints
+    pub fn verify(&self) -> Result<LogVerification, SecurityError> {
+        let public_key = self
+            .checkpoint_public_key()
+            .ok_or(SecurityError::InvalidKeyAlgorithm)?;
+        verify_log(&self.events, &self.checkpoints, &public_key)
+    }
+
+    /// Chain `event` onto the log, hand it to the sinks and checkpoint when
+    /// due
+    pub(super) fn append(&mut self, mut event: SecurityEvent) {
+        event.sequence = self.next_sequence;
+        event.previous_hash = self.head_hash.clone();
+        event.hash = event_hash(&event);
+        self.next_sequence += 1;
+        self.head_hash = event.hash.clone();
+        self.dispatch(&LogRecord::Event(event.clone()));
+        self.events.push(event);
+
+        if self.checkpoint_interval > 0
+            && self.next_sequence.is_multiple_of(self.checkpoint_interval)
+        {
+            self.checkpoint();
+        }
+
+        // Trim events if we exceed max_events, with the checkpoints only they
+        // were covered by
+        if self.events.len() > self.max_events {
+            self.events.drain(0..(self.events.len() - self.max_events));
+            let first = self.events.first().map_or(0, |event| event.sequence);
+            self.checkpoints
+                .retain(|checkpoint| checkpoint.sequence >= first);
+        }
+    }
+
+    fn dispatch(&mut self, record: &LogRecord) {
+        for sink in &self.sinks {
+            if let Err(err) = sink.record(record) {
+                self.sink_errors += 1;
+                crate::redacted_eprintln!("security event sink {:?} failed: {}", sink, err);
+            }
+        }
+    }
+}
+
+/// Writes records as JSON Lines, starting a new file once the current one
+/// reaches `max_bytes`. Files are named after the first event they hold, so
+/// listing them in name order lists them in log order.
+#[derive(Debug)]
+pub struct JsonlSink {
+    /// Directory the files are written to
+    dir: PathBuf,
+    /// File name prefix
+    prefix: String,
+    /// Size at which a new file is started
+    max_bytes: u64,
+    /// Current file and its size
+    current: Mutex<Option<(PathBuf, u64)>>,
+}
+
+impl JsonlSink {
+    pub fn new<P: Into<PathBuf>>(
+        dir: P,
+        prefix: &str,
+        max_bytes: u64,
+    ) -> Result<Self, SecurityError> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)
+            .map_err(|e| SecurityError::EventLog(format!("{}: {}", dir.display(), e)))?;
+        Ok(Self {
+            dir,
+            prefix: prefix.to_string(),
+            max_bytes,
+            current: Mutex::new(None),
+        })
+    }
+
+    /// Files written so far, oldest first
+    pub fn files(&self) -> Result<Vec<PathBuf>, SecurityError> {
+        let entries = fs::read_dir(&self.dir)
+            .map_err(|e| SecurityError::EventLog(format!("{}: {}", self.dir.display(), e)))?;
+        let prefix = format!("{}-", self.prefix);
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with(&prefix) && name.ends_with(".jsonl"))
+            })
+            .collect();
+        files.sort();
+        Ok(files)
+    }
+}
+
+impl EventSink for JsonlSink {
+    fn record(&self, record: &LogRecord) -> Result<(), SecurityError> {
+        let mut line =
+            serde_json::to_string(record).map_err(|e| SecurityError::EventLog(e.to_string()))?;
+        line.push('\n');
+
+        let mut current = self.current.lock().unwrap();
+        // Only events start a new file, so checkpoints stay with the events
+        // they cover
+        if let LogRecord::Event(event) = record {
+            let full = current
+                .as_ref()
+                .is_some_and(|(_, written)| *written + line.len() as u64 > self.max_bytes);
+            if current.is_none() || full {
+                let path = self
+                    .dir
+                    .join(format!("{}-{:020}.jsonl", self.prefix, event.sequence));
+                *current = Some((path, 0));
+            }
+        }
+        let Some((path, written)) = current.as_mut() else {
+            return Err(SecurityError::EventLog(
+                "checkpoint before any event".to_string(),
+            ));
+        };
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&*path)
+            .map_err(|e| SecurityError::EventLog(format!("{}: {}", path.display(), e)))?;
+        file.write_all(line.as_bytes())
+            .map_err(|e| SecurityError::EventLog(format!("{}: {}", path.display(), e)))?;
+        *written += line.len() as u64;
+        Ok(())
+    }
+}
+
+/// Appends RFC 5424 syslog lines to a file watched by a log collector. The
+/// chain fields travel as structured data so collectors keep them.
+#[derive(Debug)]
+pub struct SyslogSink {
+    /// File the lines are appended to
+    path: PathBuf,
+    /// HOSTNAME field
+    hostname: String,
+    /// APP-NAME field
+    app_name: String,
+    /// Serializes appends
+    lock: Mutex<()>,
+}
+
+impl SyslogSink {
+    pub fn new<P: Into<PathBuf>>(path: P, hostname: &str, app_name: &str) -> Self {
+        Self {
+            path: path.into(),
+            hostname: syslog_token(hostname),
+            app_name: syslog_token(app_name),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Format `record` as one syslog line, without the newline
+    pub fn format(&self, record: &LogRecord) -> String {
+        let (severity, timestamp, msg_id, params, message) = match record {
+            LogRecord::Event(event) => (
+                syslog_severity(&event.severity),
+                event.timestamp,
+                syslog_token(&format!("{:?}", event.event_type)),
+                vec![
+                    ("id", event.id.clone()),
+                    ("seq", event.sequence.to_string()),
+                    ("prev", event.previous_hash.clone()),
+                    ("hash", event.hash.clone()),
+                ],
+                event.description.replace(['\r', '\n'], " "),
+            ),
+            LogRecord::Checkpoint(checkpoint) => (
+                5,
+                checkpoint.timestamp,
+                "Checkpoint".to_string(),
+                vec![
+                    ("seq", checkpoint.sequence.to_string()),
+                    ("hash", checkpoint.head_hash.clone()),
+                    ("key", checkpoint.public_key.clone()),
+                    ("sig", checkpoint.signature.clone()),
+                ],
+                format!("checkpoint at event {}", checkpoint.sequence),
+            ),
+        };
+        let params: Vec<String> = params
+            .into_iter()
+            .map(|(name, value)| format!("{}=\"{}\"", name, escape_param(&value)))
+            .collect();
+        format!(
+            "<{}>1 {} {} {} - {} [{} {}] {}",
+            SYSLOG_FACILITY * 8 + severity,
+            format_timestamp(timestamp),
+            self.hostname,
+            self.app_name,
+            msg_id,
+            SYSLOG_SD_ID,
+            params.join(" "),
+            message
+        )
+    }
+}
+
+impl EventSink for SyslogSink {
+    fn record(&self, record: &LogRecord) -> Result<(), SecurityError> {
+        let line = self.format(record) + "\n";
+        let _guard = self.lock.lock().unwrap();
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(|e| SecurityError::EventLog(format!("{}: {}", self.path.display(), e)))
+    }
+}
+
+fn syslog_severity(severity: &SeverityLevel) -> u8 {
+    match severity {
+        SeverityLevel::Info => 6,
+        SeverityLevel::Warning => 4,
+        SeverityLevel::Error => 3,
+        SeverityLevel::Critical => 2,
+    }
+}
+
+/// Header fields are printable ASCII without spaces, or `-` when empty
+fn syslog_token(value: &str) -> String {
+    let token: String = value
+        .chars()
+        .filter(|c| c.is_ascii_graphic())
+        .take(48)
+        .collect();
+    if token.is_empty() {
+        "-".to_string()
+    } else {
+        token
+    }
+}
+
+/// PARAM-VALUE escaping of RFC 5424 section 6.3.3
+fn escape_param(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::{EventType, SecurityManager};
+    use std::collections::HashMap;
+
+    fn logger(interval: u64) -> (EventLogger, Vec<u8>) {
+        let (secret, public) = SecurityManager::generate_key_pair();
+        let logger = EventLogger::new(1000)
+            .with_checkpoints(interval, &secret)
+            .unwrap();
+        (logger, public)
+    }
+
+    fn log(logger: &mut EventLogger, description: &str) {
+        let mut data = HashMap::new();
+        data.insert("order".to_string(), description.to_string());
+        logger.log(
+            EventType::Transaction,
+            description.to_string(),
+            Some("alice".to_string()),
+            data,
+            None,
+            SeverityLevel::Info,
+        );
+    }
+
+    #[test]
+    fn test_chain_and_checkpoints_detect_tampering() {
+        let (mut logger, public) = logger(3);
+        for i in 0..7 {
+            log(&mut logger, &format!("order {}", i));
+        }
+        assert_eq!(logger.get_checkpoints().len(), 2);
+        let report = logger.verify().unwrap();
+        assert!(report.from_genesis);
+        assert_eq!(
+            (report.events, report.checkpoints, report.unsealed),
+            (7, 2, 1)
+        );
+
+        let events = logger.get_events().to_vec();
+        let checkpoints = logger.get_checkpoints().to_vec();
+
+        let mut edited = events.clone();
+        edited[1].description = "order 99".to_string();
+        assert!(matches!(
+            verify_log(&edited, &checkpoints, &public),
+            Err(SecurityError::EventLogTampered(_))
+        ));
+
+        let mut dropped = events.clone();
+        dropped.remove(3);
+        assert!(verify_log(&dropped, &checkpoints, &public).is_err());
+
+        // Removing the tail is caught by the checkpoint that covered it
+        assert!(verify_log(&events[..4], &checkpoints, &public).is_err());
+
+        // Rewriting the chain after an edit no longer matches the checkpoints
+        let mut rewritten = events.clone();
+        rewritten[4].description = "order 99".to_string();
+        for i in 4..rewritten.len() {
+            if i > 4 {
+                rewritten[i].previous_hash = rewritten[i - 1].hash.clone();
+            }
+            rewritten[i].hash = event_hash(&rewritten[i]);
+        }
+        assert!(verify_log(&rewritten, &checkpoints, &public).is_err());
+
+        // A suffix of the log still verifies on its own
+        let report = verify_log(&events[2..], &checkpoints, &public).unwrap();
+        assert!(!report.from_genesis);
+
+        // Closing the log seals the tail
+        assert!(logger.checkpoint().is_some());
+        assert!(logger.checkpoint().is_none());
+        assert_eq!(logger.verify().unwrap().unsealed, 0);
+    }
+
+    #[test]
+    fn test_sinks_write_rotating_jsonl_and_syslog() {
+        let dir = tempfile::tempdir().unwrap();
+        let jsonl = Arc::new(JsonlSink::new(dir.path().join("log"), "security", 600).unwrap());
+        let syslog = Arc::new(SyslogSink::new(
+            dir.path().join("security.syslog"),
+            "node-1",
+            "dex-os",
+        ));
+        let (logger, public) = logger(2);
+        let mut logger = logger.with_sink(jsonl.clone()).with_sink(syslog);
+        for i in 0..6 {
+            log(&mut logger, &format!("order \"{}\"]", i));
+        }
+        assert_eq!(logger.sink_errors(), 0);
+
+        let files = jsonl.files().unwrap();
+        assert!(files.len() > 1);
+        let (events, checkpoints) = read_jsonl(&files).unwrap();
+        assert_eq!(events, logger.get_events());
+        let report = verify_log(&events, &checkpoints, &public).unwrap();
+        assert_eq!((report.checkpoints, report.unsealed), (3, 0));
+
+        let lines = fs::read_to_string(dir.path().join("security.syslog")).unwrap();
+        let first = lines.lines().next().unwrap();
+        assert!(first.starts_with("<110>1 "));
+        assert!(first.contains(" node-1 dex-os - Transaction [dexos@32473 id="));
+        assert!(first.ends_with("] order \"0\"]"));
+        assert_eq!(lines.lines().count(), 9);
+    }
+}
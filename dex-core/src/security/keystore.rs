@@ -0,0 +1,419 @@
+//! Encrypted keystore for rotated keys
+//!
+//! This module implements part of the Priority 3 feature from DEX-OS-V2.csv:
+//! - Security,Security,Security,Hash Map,Key Rotation,Medium
+//!
+//! A keystore file is JSON listing the public metadata of every key in the
+//! clear and each private key sealed with XChaCha20-Poly1305. The wrapping key
+//! is derived per file from a random salt and either a password (Argon2id,
+//! with its cost parameters stored in the file and capped when read, so a
+//! doctored file cannot make opening it exhaust memory or CPU) or a 32-byte
+//! key-encryption
+//! key (HKDF-SHA256). A key's metadata is the associated data of its
+//! ciphertext, so entries cannot be edited or swapped without failing to open.
+//! Wrapping keys and decrypted private keys are zeroized when dropped.
+
+use super::{KeyPair, KeyUsage, SecurityError};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use hkdf::Hkdf;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// Version of the keystore file format
+pub const KEYSTORE_VERSION: u32 = 1;
+
+/// Domain separation for wrapping keys and associated data
+const KEYSTORE_DOMAIN: &[u8] = b"dexos-keystore-v1";
+
+/// Argon2id memory cost in KiB (OWASP minimum for Argon2id)
+const ARGON2_MEMORY_KIB: u32 = 19_456;
+/// Argon2id passes
+const ARGON2_ITERATIONS: u32 = 2;
+/// Argon2id lanes
+const ARGON2_PARALLELISM: u32 = 1;
+/// Highest Argon2id memory cost accepted from a file (1 GiB)
+const ARGON2_MAX_MEMORY_KIB: u32 = 1 << 20;
+/// Highest Argon2id pass count accepted from a file
+const ARGON2_MAX_ITERATIONS: u32 = 16;
+/// Highest Argon2id lane count accepted from a file
+const ARGON2_MAX_PARALLELISM: u32 = 16;
+
+/// Secret protecting a keystore
+pub enum KeyEncryptionKey<'a> {
+    /// Operator password, stretched with Argon2id
+    Password(&'a [u8]),
+    /// Key-encryption key from a KMS or HSM, expanded with HKDF
+    Kek(&'a [u8; 32]),
+}
+
+/// Serialized keystore
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EncryptedKeystore {
+    /// File format version
+    pub version: u32,
+    /// How the wrapping key is derived
+    pub kdf: KeystoreKdf,
+    /// Sealed keys
+    pub keys: Vec<EncryptedKey>,
+}
+
+/// Derivation of the wrapping key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "name", rename_all = "lowercase")]
+pub enum KeystoreKdf {
+    /// Password-based
+    Argon2id {
+        /// Base64 salt
+        salt: String,
+        /// Memory cost in KiB
+        memory_kib: u32,
+        /// Number of passes
+        iterations: u32,
+        /// Degree of parallelism
+        parallelism: u32,
+    },
+    /// Key-encryption-key based
+    Hkdf {
+        /// Base64 salt
+        salt: String,
+    },
+}
+
+/// A sealed private key and its public metadata
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EncryptedKey {
+    /// Public metadata, authenticated as associated data
+    pub metadata: KeyMetadata,
+    /// Base64 XChaCha20-Poly1305 nonce
+    pub nonce: String,
+    /// Base64 ciphertext of the private key
+    pub ciphertext: String,
+}
+
+/// Public information about a stored key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyMetadata {
+    /// User or service the key belongs to
+    pub owner: String,
+    /// Key identifier
+    pub key_id: String,
+    /// Base64 public key
+    pub public_key: String,
+    /// Creation timestamp
+    pub created_at: u64,
+    /// Expiration timestamp
+    pub expires_at: Option<u64>,
+    /// Key algorithm
+    pub algorithm: String,
+    /// Key usage
+    pub usage: KeyUsage,
+    /// Whether the key was rotated out and only kept in the history
+    pub retired: bool,
+}
+
+/// A decrypted keystore entry
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeystoreEntry {
+    /// User or service the key belongs to
+    pub owner: String,
+    /// The key pair
+    pub key: KeyPair,
+    /// Whether the key was rotated out and only kept in the history
+    pub retired: bool,
+}
+
+impl EncryptedKeystore {
+    /// Seal `entries` under a wrapping key derived from `kek` with a fresh salt
+    pub fn seal(entries: &[KeystoreEntry], kek: &KeyEncryptionKey) -> Result<Self, SecurityError> {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        let kdf = match kek {
+            KeyEncryptionKey::Password(_) => KeystoreKdf::Argon2id {
+                salt: STANDARD.encode(salt),
+                memory_kib: ARGON2_MEMORY_KIB,
+                iterations: ARGON2_ITERATIONS,
+                parallelism: ARGON2_PARALLELISM,
+            },
+            KeyEncryptionKey::Kek(_) => KeystoreKdf::Hkdf {
+                salt: STANDARD.encode(salt),
+            },
+        };
+        let wrapping_key = kdf.wrapping_key(kek)?;
+        let cipher = XChaCha20Poly1305::new(&Key::from(*wrapping_key));
+
+        let mut keys = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let metadata = KeyMetadata {
+                owner: entry.owner.clone(),
+                key_id: entry.key.key_id.clone(),
+                public_key: STANDARD.encode(&entry.key.public_key),
+                created_at: entry.key.created_at,
+                expires_at: entry.key.expires_at,
+                algorithm: entry.key.algorithm.clone(),
+                usage: entry.key.usage.clone(),
+                retired: entry.retired,
+            };
+            let mut nonce = [0u8; 24];
+            OsRng.fill_bytes(&mut nonce);
+            let ciphertext = cipher
+                .encrypt(
+                    &XNonce::from(nonce),
+                    Payload {
+                        msg: &entry.key.private_key,
+                        aad: &associated_data(&metadata),
+                    },
+                )
+                .map_err(|_| SecurityError::Keystore("failed to seal key".to_string()))?;
+            keys.push(EncryptedKey {
+                metadata,
+                nonce: STANDARD.encode(nonce),
+                ciphertext: STANDARD.encode(ciphertext),
+            });
+        }
+
+        Ok(Self {
+            version: KEYSTORE_VERSION,
+            kdf,
+            keys,
+        })
+    }
+
+    /// Decrypt every entry. Fails as a whole on a wrong secret or any tampering.
+    pub fn open(&self, kek: &KeyEncryptionKey) -> Result<Vec<KeystoreEntry>, SecurityError> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(SecurityError::Keystore(format!(
+                "unsupported keystore version {}",
+                self.version
+            )));
+        }
+        let wrapping_key = self.kdf.wrapping_key(kek)?;
+        let cipher = XChaCha20Poly1305::new(&Key::from(*wrapping_key));
+
+        let mut entries = Vec::with_capacity(self.keys.len());
+        for sealed in &self.keys {
+            let nonce = <[u8; 24]>::try_from(decode_field(&sealed.nonce)?.as_slice())
+                .map_err(|_| SecurityError::Keystore("invalid nonce".to_string()))?;
+            let private_key = Zeroizing::new(
+                cipher
+                    .decrypt(
+                        &XNonce::from(nonce),
+                        Payload {
+                            msg: &decode_field(&sealed.ciphertext)?,
+                            aad: &associated_data(&sealed.metadata),
+                        },
+                    )
+                    .map_err(|_| SecurityError::KeystoreDecryption)?,
+            );
+            let metadata = &sealed.metadata;
+            entries.push(KeystoreEntry {
+                owner: metadata.owner.clone(),
+                key: KeyPair {
+                    key_id: metadata.key_id.clone(),
+                    public_key: decode_field(&metadata.public_key)?,
+                    private_key: private_key.to_vec(),
+                    created_at: metadata.created_at,
+                    expires_at: metadata.expires_at,
+                    algorithm: metadata.algorithm.clone(),
+                    usage: metadata.usage.clone(),
+                },
+                retired: metadata.retired,
+            });
+        }
+        Ok(entries)
+    }
+
+    /// Write the keystore to `path`, replacing any previous file atomically.
+    /// The new contents are flushed to disk before they replace the old file,
+    /// and the directory afterwards, so a crash leaves one or the other.
+    pub fn write(&self, path: &Path) -> Result<(), SecurityError> {
+        let json =
+            serde_json::to_vec_pretty(self).map_err(|e| SecurityError::Keystore(e.to_string()))?;
+        let tmp = path.with_extension("tmp");
+        let mut file = File::create(&tmp).map_err(|e| SecurityError::Keystore(e.to_string()))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&tmp, fs::Permissions::from_mode(0o600))
+                .map_err(|e| SecurityError::Keystore(e.to_string()))?;
+        }
+        file.write_all(&json)
+            .and_then(|()| file.sync_all())
+            .map_err(|e| SecurityError::Keystore(e.to_string()))?;
+        drop(file);
+        fs::rename(&tmp, path).map_err(|e| SecurityError::Keystore(e.to_string()))?;
+        sync_parent_dir(path)
+    }
+
+    /// Read a keystore written by [`Self::write`]
+    pub fn read(path: &Path) -> Result<Self, SecurityError> {
+        let json = fs::read(path).map_err(|e| SecurityError::Keystore(e.to_string()))?;
+        serde_json::from_slice(&json).map_err(|e| SecurityError::Keystore(e.to_string()))
+    }
+}
+
+impl KeystoreKdf {
+    /// Derive the wrapping key; the secret must be of the kind the file was sealed with
+    fn wrapping_key(&self, kek: &KeyEncryptionKey) -> Result<Zeroizing<[u8; 32]>, SecurityError> {
+        let mut key = Zeroizing::new([0u8; 32]);
+        match (self, kek) {
+            (
+                KeystoreKdf::Argon2id {
+                    salt,
+                    memory_kib,
+                    iterations,
+                    parallelism,
+                },
+                KeyEncryptionKey::Password(password),
+            ) => {
+                if *memory_kib > ARGON2_MAX_MEMORY_KIB
+                    || *iterations > ARGON2_MAX_ITERATIONS
+                    || *parallelism > ARGON2_MAX_PARALLELISM
+                {
+                    return Err(SecurityError::Keystore(format!(
+                        "Argon2id parameters m={} t={} p={} exceed m={} t={} p={}",
+                        memory_kib,
+                        iterations,
+                        parallelism,
+                        ARGON2_MAX_MEMORY_KIB,
+                        ARGON2_MAX_ITERATIONS,
+                        ARGON2_MAX_PARALLELISM
+                    )));
+                }
+                let params = Params::new(*memory_kib, *iterations, *parallelism, Some(32))
+                    .map_err(|e| SecurityError::Keystore(e.to_string()))?;
+                Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                    .hash_password_into(password, &decode_field(salt)?, key.as_mut())
+                    .map_err(|e| SecurityError::Keystore(e.to_string()))?;
+            }
+            (KeystoreKdf::Hkdf { salt }, KeyEncryptionKey::Kek(kek)) => {
+                Hkdf::<Sha256>::new(Some(&decode_field(salt)?), kek.as_slice())
+                    .expand(KEYSTORE_DOMAIN, key.as_mut())
+                    .map_err(|e| SecurityError::Keystore(e.to_string()))?;
+            }
+            _ => return Err(SecurityError::KeystoreDecryption),
+        }
+        Ok(key)
+    }
+}
+
+/// `domain || JSON(metadata)`
+fn associated_data(metadata: &KeyMetadata) -> Vec<u8> {
+    let mut aad = KEYSTORE_DOMAIN.to_vec();
+    aad.extend(serde_json::to_vec(metadata).expect("key metadata serializes"));
+    aad
+}
+
+/// Persist a rename in `path`'s directory. Directories cannot be opened for
+/// syncing on Windows, where renames are journaled by the file system.
+fn sync_parent_dir(path: &Path) -> Result<(), SecurityError> {
+    #[cfg(unix)]
+    {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        File::open(dir)
+            .and_then(|dir| dir.sync_all())
+            .map_err(|e| SecurityError::Keystore(e.to_string()))?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+fn decode_field(encoded: &str) -> Result<Vec<u8>, SecurityError> {
+    STANDARD
+        .decode(encoded)
+        .map_err(|e| SecurityError::Keystore(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::KeyRotationManager;
+
+    #[test]
+    fn test_keystore_round_trip_and_tampering() {
+        let mut manager = KeyRotationManager::new(3600);
+        manager.rotate_keys("jwt").unwrap();
+        let current = manager.rotate_keys("jwt").unwrap();
+        let kek = [7u8; 32];
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keys.json");
+        manager
+            .save_keystore(&path, &KeyEncryptionKey::Kek(&kek))
+            .unwrap();
+
+        // Private keys never appear in the file
+        let file = fs::read_to_string(&path).unwrap();
+        assert!(!file.contains(&STANDARD.encode(&current.private_key)));
+
+        let mut restored = KeyRotationManager::new(3600);
+        restored
+            .load_keystore(&path, &KeyEncryptionKey::Kek(&kek))
+            .unwrap();
+        assert_eq!(restored.get_current_key("jwt"), Some(&current));
+        assert_eq!(restored.get_rotation_history("jwt").unwrap().len(), 1);
+
+        // Wrong secrets, wrong secret kinds and edited metadata are rejected
+        let keystore = EncryptedKeystore::read(&path).unwrap();
+        assert!(matches!(
+            keystore.open(&KeyEncryptionKey::Kek(&[8u8; 32])),
+            Err(SecurityError::KeystoreDecryption)
+        ));
+        assert!(matches!(
+            keystore.open(&KeyEncryptionKey::Password(b"hunter2")),
+            Err(SecurityError::KeystoreDecryption)
+        ));
+        let mut edited = keystore.clone();
+        edited.keys[0].metadata.retired = false;
+        assert!(matches!(
+            edited.open(&KeyEncryptionKey::Kek(&kek)),
+            Err(SecurityError::KeystoreDecryption)
+        ));
+    }
+
+    #[test]
+    fn test_password_keystore() {
+        let mut manager = KeyRotationManager::new(3600);
+        let key = manager.rotate_keys("jwt").unwrap();
+        let entries = vec![KeystoreEntry {
+            owner: "jwt".to_string(),
+            key: key.clone(),
+            retired: false,
+        }];
+
+        let keystore =
+            EncryptedKeystore::seal(&entries, &KeyEncryptionKey::Password(b"correct horse"))
+                .unwrap();
+        assert!(matches!(keystore.kdf, KeystoreKdf::Argon2id { .. }));
+        let opened = keystore
+            .open(&KeyEncryptionKey::Password(b"correct horse"))
+            .unwrap();
+        assert_eq!(opened, entries);
+        assert!(matches!(
+            keystore.open(&KeyEncryptionKey::Password(b"wrong horse")),
+            Err(SecurityError::KeystoreDecryption)
+        ));
+
+        // Oversized cost parameters are refused before any hashing
+        let mut doctored = keystore.clone();
+        if let KeystoreKdf::Argon2id { memory_kib, .. } = &mut doctored.kdf {
+            *memory_kib = u32::MAX;
+        }
+        assert!(matches!(
+            doctored.open(&KeyEncryptionKey::Password(b"correct horse")),
+            Err(SecurityError::Keystore(_))
+        ));
+    }
+}
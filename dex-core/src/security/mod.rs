@@ -8,6 +8,27 @@
 //! - Security,Security,Security,Regular Expressions,PII Detection,Medium
 //! - Security,Security,Security,Bloom Filter,Access Control,Medium
 //! - Security,Orderbook,Orderbook,Event Logging,Security Auditing,Medium
+//!
+//! Rotated keys can be persisted in an encrypted keystore (see [`keystore`]),
+//! and services that depend on a key subscribe to its rotation events.
+//! Certificates are verified in chains up to configured trust anchors, with
+//! revocation lists and status queries (see [`certificates`]). Security events
+//! form a hash chain with signed checkpoints (see [`event_log`]). Detected PII
+//! is masked or tokenized before text leaves the engine (see [`redaction`]).
+
+pub mod certificates;
+pub mod event_log;
+pub mod keystore;
+pub mod redaction;
+
+pub use certificates::{
+    CertificateStatus, RevocationList, RevocationReason, RevokedCertificate, StatusResponse,
+};
+pub use event_log::{
+    verify_log, Checkpoint, EventSink, JsonlSink, LogRecord, LogVerification, SyslogSink,
+};
+pub use keystore::{EncryptedKeystore, KeyEncryptionKey, KeystoreEntry};
+pub use redaction::{RedactingWriter, RedactionAction, RedactionPolicy, Redactor};
 
 use crate::types::{TokenId, TraderId};
 use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
@@ -16,7 +37,12 @@ use regex::Regex;
 use serde::{Deserialize, Serialize};
 use sha3::{Digest, Sha3_256};
 use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
 use thiserror::Error;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use zeroize::Zeroize;
 
 /// Security manager for the DEX-OS core engine
 #[derive(Debug, Clone)]
@@ -51,7 +77,7 @@ pub struct DigitalSignature {
 }
 
 /// Data classification levels
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum ClassificationLevel {
     Public,
     Internal,
@@ -78,6 +104,12 @@ pub struct DataClassification {
 pub struct CertificateManager {
     /// Certificates stored in a B+ tree for efficient operations
     certificates: BPlusTree<String, Certificate>,
+    /// Intermediate authorities allowed to issue certificates
+    authorities: BPlusTree<String, Certificate>,
+    /// Certificates trusted as chain roots, by ID
+    trust_anchors: HashMap<String, Certificate>,
+    /// Latest imported revocation list of each authority
+    crls: HashMap<String, RevocationList>,
 }
 
 /// Digital certificate
@@ -85,14 +117,15 @@ pub struct CertificateManager {
 pub struct Certificate {
     /// Certificate identifier
     pub id: String,
-    /// Certificate data
+    /// Certificate data: the subject's ed25519 public key for certificates
+    /// that are verified in chains
     pub data: Vec<u8>,
-    /// Issuer of the certificate
+    /// ID of the issuer's certificate
     pub issuer: String,
     /// Validity period
     pub valid_from: u64,
     pub valid_to: u64,
-    /// Signature of the certificate
+    /// Issuer's signature over [`Certificate::tbs_bytes`]
     pub signature: Vec<u8>,
     /// Whether the certificate is revoked
     pub revoked: bool,
@@ -111,14 +144,19 @@ pub struct KeyRotationManager {
     last_rotation: u64,
     /// Key rotation policy
     rotation_policy: RotationPolicy,
+    /// Rotation event subscribers by key owner
+    subscribers: HashMap<String, Vec<UnboundedSender<KeyRotationEvent>>>,
 }
 
-/// Key pair for cryptography
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Key pair for cryptography. The private key is wiped when the pair is dropped.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct KeyPair {
+    /// Key identifier, derived from the public key
+    #[serde(default)]
+    pub key_id: String,
     /// Public key
     pub public_key: Vec<u8>,
-    /// Private key (encrypted)
+    /// Private key
     pub private_key: Vec<u8>,
     /// Creation timestamp
     pub created_at: u64,
@@ -130,6 +168,49 @@ pub struct KeyPair {
     pub usage: KeyUsage,
 }
 
+impl KeyPair {
+    /// Key identifier for a public key: the first 8 bytes of its SHA3-256 hash in hex
+    pub fn key_id_for(public_key: &[u8]) -> String {
+        Sha3_256::digest(public_key)[..8]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
+
+impl fmt::Debug for KeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyPair")
+            .field("key_id", &self.key_id)
+            .field("public_key", &self.public_key)
+            .field("private_key", &"<redacted>")
+            .field("created_at", &self.created_at)
+            .field("expires_at", &self.expires_at)
+            .field("algorithm", &self.algorithm)
+            .field("usage", &self.usage)
+            .finish()
+    }
+}
+
+impl Drop for KeyPair {
+    fn drop(&mut self) {
+        self.private_key.zeroize();
+    }
+}
+
+/// Notification that a key owner's current key was replaced
+#[derive(Debug, Clone)]
+pub struct KeyRotationEvent {
+    /// User or service the key belongs to
+    pub owner: String,
+    /// The new current key
+    pub key: KeyPair,
+    /// Identifier of the key it replaced
+    pub previous_key_id: Option<String>,
+    /// Rotation timestamp
+    pub rotated_at: u64,
+}
+
 /// Key usage types
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum KeyUsage {
@@ -197,6 +278,22 @@ pub struct EventLogger {
     events: Vec<SecurityEvent>,
     /// Maximum number of events to store
     max_events: usize,
+    /// Sequence number of the next event
+    next_sequence: u64,
+    /// Hash of the latest event
+    head_hash: String,
+    /// Events between signed checkpoints, 0 for none
+    checkpoint_interval: u64,
+    /// Key checkpoints are signed with
+    checkpoint_key: Option<SigningKey>,
+    /// Checkpoints covering the events held
+    checkpoints: Vec<event_log::Checkpoint>,
+    /// Where events and checkpoints are also written
+    sinks: Vec<Arc<dyn event_log::EventSink>>,
+    /// Records the sinks failed to write
+    sink_errors: u64,
+    /// Redaction of event descriptions and data before they are chained
+    redaction: Option<(Arc<Redactor>, ClassificationLevel)>,
 }
 
 /// Security event for auditing
@@ -218,6 +315,15 @@ pub struct SecurityEvent {
     pub evidence: Option<Vec<u8>>,
     /// Severity level of the event
     pub severity: SeverityLevel,
+    /// Position of the event in the log
+    #[serde(default)]
+    pub sequence: u64,
+    /// Hash of the previous event
+    #[serde(default)]
+    pub previous_hash: String,
+    /// Hash of this event, chained onto `previous_hash`
+    #[serde(default)]
+    pub hash: String,
 }
 
 /// Types of security events
@@ -258,7 +364,9 @@ impl SecurityManager {
             certificates: CertificateManager::new(),
             key_rotation: KeyRotationManager::new(86400), // Rotate daily
             pii_detector: PIIDetector::new(),
-            event_logger: EventLogger::new(10000), // Store up to 10,000 events
+            // Store up to 10,000 events, with PII redacted like log lines
+            event_logger: EventLogger::new(10000)
+                .with_redaction(redaction::global(), redaction::LOG_CLASSIFICATION),
             access_control_filter: BloomFilter::default(),
         }
     }
@@ -351,6 +459,15 @@ impl SecurityManager {
         }
     }
 
+    /// Reinstate a classification recorded earlier, keeping its timestamp
+    pub fn restore_classification(&mut self, data_id: String, classification: DataClassification) {
+        self.access_control_filter.add(&classification.owner);
+        for user in &classification.acl {
+            self.access_control_filter.add(user);
+        }
+        self.data_classification.insert(data_id, classification);
+    }
+
     /// Update access control list for classified data
     pub fn update_data_acl(
         &mut self,
@@ -401,6 +518,11 @@ impl SecurityManager {
         }
     }
 
+    /// Classification recorded for a data item
+    pub fn data_classification(&self, data_id: &str) -> Option<&DataClassification> {
+        self.data_classification.get(data_id)
+    }
+
     /// Check if a user has access to classified data using Bloom filter for efficiency
     pub fn check_data_access(&self, data_id: &str, user: &TraderId) -> bool {
         // First use Bloom filter for quick negative check (if Bloom filter says no, then definitely no)
@@ -490,6 +612,18 @@ impl SecurityManager {
         )
     }
 
+    /// Replace the event logger, e.g. with one that signs checkpoints and
+    /// writes to sinks
+    pub fn with_event_logger(mut self, event_logger: EventLogger) -> Self {
+        self.event_logger = event_logger;
+        self
+    }
+
+    /// The event logger
+    pub fn event_logger(&self) -> &EventLogger {
+        &self.event_logger
+    }
+
     /// Get security events
     pub fn get_events(&self) -> &[SecurityEvent] {
         self.event_logger.get_events()
@@ -558,6 +692,9 @@ impl CertificateManager {
     pub fn new() -> Self {
         Self {
             certificates: BPlusTree::new(4), // B+ tree with order 4
+            authorities: BPlusTree::new(4),
+            trust_anchors: HashMap::new(),
+            crls: HashMap::new(),
         }
     }
 
@@ -602,7 +739,11 @@ impl CertificateManager {
                 .unwrap()
                 .as_secs();
 
-            !certificate.revoked && now >= certificate.valid_from && now <= certificate.valid_to
+            let listed = self
+                .crls
+                .get(&certificate.issuer)
+                .is_some_and(|crl| crl.entry(cert_id).is_some());
+            !certificate.revoked && !listed && certificate.is_current(now)
         } else {
             false
         }
@@ -629,43 +770,83 @@ impl KeyRotationManager {
             rotation_period,
             last_rotation: now,
             rotation_policy: RotationPolicy::default(),
+            subscribers: HashMap::new(),
         }
     }
 
+    /// Receive an event every time the key of `owner` rotates. The event
+    /// carries the new private key, so only the owner's consumers subscribe.
+    pub fn subscribe(&mut self, owner: &str) -> UnboundedReceiver<KeyRotationEvent> {
+        let (sender, receiver) = unbounded_channel();
+        self.subscribers
+            .entry(owner.to_string())
+            .or_default()
+            .push(sender);
+        receiver
+    }
+
     /// Rotate keys for a user with proper cryptographic implementation
     pub fn rotate_keys(&mut self, user_id: &str) -> Result<KeyPair, SecurityError> {
-        // Generate new Ed25519 key pair
-        let (private_key, public_key) = SecurityManager::generate_key_pair();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let new_keypair = self.signing_key(now);
+        self.install_key(user_id, new_keypair.clone(), now);
+        Ok(new_keypair)
+    }
 
+    /// Rotate the key of `user_id` and seal the keystore before subscribers
+    /// learn of the new key. If the keystore cannot be written the previous
+    /// key stays current and no event is sent, so consumers never sign with a
+    /// key that would be lost on restart.
+    pub fn rotate_keys_and_save(
+        &mut self,
+        user_id: &str,
+        path: &Path,
+        kek: &KeyEncryptionKey,
+    ) -> Result<KeyPair, SecurityError> {
         let now = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_secs();
 
-        let new_keypair = KeyPair {
+        let new_keypair = self.signing_key(now);
+        let previous_key = self.current_keys.get(user_id).cloned();
+        let last_rotation = self.last_rotation;
+        let previous_key_id = self.replace_key(user_id, new_keypair.clone(), now);
+        if let Err(err) = self.save_keystore(path, kek) {
+            match previous_key {
+                Some(previous_key) => {
+                    if let Some(history) = self.rotation_history.get_mut(user_id) {
+                        history.pop();
+                    }
+                    self.current_keys.insert(user_id.to_string(), previous_key);
+                }
+                None => {
+                    self.current_keys.remove(user_id);
+                }
+            }
+            self.last_rotation = last_rotation;
+            return Err(err);
+        }
+        self.notify(user_id, &new_keypair, previous_key_id, now);
+        Ok(new_keypair)
+    }
+
+    /// A fresh Ed25519 signing key valid for one rotation period
+    fn signing_key(&self, now: u64) -> KeyPair {
+        let (private_key, public_key) = SecurityManager::generate_key_pair();
+        KeyPair {
+            key_id: KeyPair::key_id_for(&public_key),
             public_key,
             private_key,
             created_at: now,
             expires_at: Some(now + self.rotation_period),
             algorithm: "Ed25519".to_string(),
             usage: KeyUsage::Signing,
-        };
-
-        // Store old key in history if it exists
-        if let Some(old_keypair) = self.current_keys.remove(user_id) {
-            self.rotation_history
-                .entry(user_id.to_string())
-                .or_insert_with(Vec::new)
-                .push(old_keypair);
         }
-
-        // Store new key as current
-        self.current_keys
-            .insert(user_id.to_string(), new_keypair.clone());
-
-        self.last_rotation = now;
-
-        Ok(new_keypair)
     }
 
     /// Rotate keys for a user with specific algorithm
@@ -690,6 +871,7 @@ impl KeyRotationManager {
         };
 
         let new_keypair = KeyPair {
+            key_id: KeyPair::key_id_for(&public_key),
             public_key,
             private_key,
             created_at: now,
@@ -698,21 +880,106 @@ impl KeyRotationManager {
             usage: key_usage,
         };
 
+        self.install_key(user_id, new_keypair.clone(), now);
+        Ok(new_keypair)
+    }
+
+    /// Make `keypair` the current key of `user_id`, move the old one to the
+    /// history and notify the owner's subscribers
+    fn install_key(&mut self, user_id: &str, keypair: KeyPair, now: u64) {
+        let previous_key_id = self.replace_key(user_id, keypair.clone(), now);
+        self.notify(user_id, &keypair, previous_key_id, now);
+    }
+
+    /// Make `keypair` the current key of `user_id` and move the old one to
+    /// the history, returning the old key's ID
+    fn replace_key(&mut self, user_id: &str, keypair: KeyPair, now: u64) -> Option<String> {
         // Store old key in history if it exists
-        if let Some(old_keypair) = self.current_keys.remove(user_id) {
+        let previous_key_id = self.current_keys.remove(user_id).map(|old_keypair| {
+            let key_id = old_keypair.key_id.clone();
             self.rotation_history
                 .entry(user_id.to_string())
-                .or_insert_with(Vec::new)
+                .or_default()
                 .push(old_keypair);
-        }
+            key_id
+        });
 
         // Store new key as current
-        self.current_keys
-            .insert(user_id.to_string(), new_keypair.clone());
-
+        self.current_keys.insert(user_id.to_string(), keypair);
         self.last_rotation = now;
+        previous_key_id
+    }
 
-        Ok(new_keypair)
+    /// Tell the subscribers of `user_id` that `keypair` is now current
+    fn notify(
+        &mut self,
+        user_id: &str,
+        keypair: &KeyPair,
+        previous_key_id: Option<String>,
+        now: u64,
+    ) {
+        if let Some(subscribers) = self.subscribers.get_mut(user_id) {
+            let event = KeyRotationEvent {
+                owner: user_id.to_string(),
+                key: keypair.clone(),
+                previous_key_id,
+                rotated_at: now,
+            };
+            // Drop subscribers whose receiver has gone away
+            subscribers.retain(|subscriber| subscriber.send(event.clone()).is_ok());
+        }
+    }
+
+    /// Seal the current keys and the rotation history into a keystore file
+    pub fn save_keystore(&self, path: &Path, kek: &KeyEncryptionKey) -> Result<(), SecurityError> {
+        let mut entries = Vec::new();
+        for (owner, history) in &self.rotation_history {
+            entries.extend(history.iter().map(|key| KeystoreEntry {
+                owner: owner.clone(),
+                key: key.clone(),
+                retired: true,
+            }));
+        }
+        entries.extend(self.current_keys.iter().map(|(owner, key)| KeystoreEntry {
+            owner: owner.clone(),
+            key: key.clone(),
+            retired: false,
+        }));
+        EncryptedKeystore::seal(&entries, kek)?.write(path)
+    }
+
+    /// Replace all keys with those of a keystore file. Subscribers are not
+    /// notified; loading restores state rather than rotating.
+    pub fn load_keystore(
+        &mut self,
+        path: &Path,
+        kek: &KeyEncryptionKey,
+    ) -> Result<(), SecurityError> {
+        let entries = EncryptedKeystore::read(path)?.open(kek)?;
+        let mut current_keys = HashMap::new();
+        let mut rotation_history: HashMap<String, Vec<KeyPair>> = HashMap::new();
+        for entry in entries {
+            if entry.retired {
+                rotation_history
+                    .entry(entry.owner)
+                    .or_default()
+                    .push(entry.key);
+            } else if current_keys
+                .insert(entry.owner.clone(), entry.key)
+                .is_some()
+            {
+                return Err(SecurityError::Keystore(format!(
+                    "several current keys for {}",
+                    entry.owner
+                )));
+            }
+        }
+        for history in rotation_history.values_mut() {
+            history.sort_by_key(|key| key.created_at);
+        }
+        self.current_keys = current_keys;
+        self.rotation_history = rotation_history;
+        Ok(())
     }
 
     /// Automatic key rotation based on policy
@@ -789,9 +1056,9 @@ impl PIIDetector {
             ("Email", r"[a-zA-Z0-9._%+-]+@[a-zA-Z0-9.-]+\.[a-zA-Z]{2,}"),
             (
                 "Phone",
-                r"(\+\d{1,3}[-.\s]?)?\(?\d{3}\)?[-.\s]?\d{3}[-.\s]?\d{4}",
+                r"(?:\+\d{1,3}[-.\s]?)?(?:\(\d{3}\)\s?|\b\d{3}[-.\s])\d{3}[-.\s]\d{4}\b|\+\d{1,3}(?:[-.\s]?\d{2,4}){2,5}\b",
             ),
-            ("SSN", r"\d{3}-?\d{2}-?\d{4}"),
+            ("SSN", r"\b\d{3}-\d{2}-\d{4}\b"),
             (
                 "CreditCard",
                 r"\b(?:\d{4}[-\s]?){3}\d{4}\b|\b\d{16}\b",
@@ -801,6 +1068,16 @@ impl PIIDetector {
                 "DOB",
                 r"\b(0[1-9]|1[0-2])[-/.](0[1-9]|[12]\d|3[01])[-/.](19|20)\d{2}\b",
             ),
+            // Ethereum, bech32 and legacy base58 Bitcoin addresses
+            (
+                "WalletAddress",
+                r"\b0x[a-fA-F0-9]{40}\b|\bbc1[ac-hj-np-z02-9]{11,71}\b|\b[13][a-km-zA-HJ-NP-Z1-9]{25,34}\b",
+            ),
+            // Checked against the ISO 13616 check digits in `detect`
+            (
+                "IBAN",
+                r"\b[A-Z]{2}\d{2}(?: ?[A-Z0-9]{4}){2,7}(?: ?[A-Z0-9]{1,3})?\b",
+            ),
         ];
 
         let patterns = specs
@@ -822,6 +1099,9 @@ impl PIIDetector {
         for pattern in &self.patterns {
             if let Some(regex) = &pattern.regex {
                 for mat in regex.find_iter(text) {
+                    if pattern.name == "IBAN" && !iban_checksum_valid(mat.as_str()) {
+                        continue;
+                    }
                     results.push(PIIDetectionResult {
                         pattern_name: pattern.name.clone(),
                         matched_text: mat.as_str().to_string(),
@@ -842,6 +1122,27 @@ impl Default for PIIDetector {
     }
 }
 
+/// ISO 13616 check: the IBAN with its first four characters moved to the end
+/// and letters read as 10..35 is 1 modulo 97
+fn iban_checksum_valid(iban: &str) -> bool {
+    let compact: Vec<char> = iban.chars().filter(|c| !c.is_whitespace()).collect();
+    if compact.len() < 15 || compact.len() > 34 {
+        return false;
+    }
+    let mut remainder = 0u32;
+    for c in compact[4..].iter().chain(&compact[..4]) {
+        let Some(value) = c.to_digit(36) else {
+            return false;
+        };
+        remainder = if value < 10 {
+            (remainder * 10 + value) % 97
+        } else {
+            (remainder * 100 + value) % 97
+        };
+    }
+    remainder == 1
+}
+
 /// Result of PII detection
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PIIDetectionResult {
@@ -861,6 +1162,14 @@ impl EventLogger {
         Self {
             events: Vec::new(),
             max_events,
+            next_sequence: 0,
+            head_hash: event_log::GENESIS_HASH.to_string(),
+            checkpoint_interval: 0,
+            checkpoint_key: None,
+            checkpoints: Vec::new(),
+            sinks: Vec::new(),
+            sink_errors: 0,
+            redaction: None,
         }
     }
 
@@ -879,7 +1188,17 @@ impl EventLogger {
             .unwrap()
             .as_secs();
 
-        let event_id = format!("event_{}", timestamp);
+        let event_id = format!("event_{}_{}", timestamp, self.next_sequence);
+
+        // Redact before chaining, so the hashes cover what is stored
+        let (description, data) = match &self.redaction {
+            Some((redactor, level)) => {
+                let mut data = data;
+                redactor.redact_map(&mut data, level);
+                (redactor.redact(&description, level), data)
+            }
+            None => (description, data),
+        };
 
         let event = SecurityEvent {
             id: event_id.clone(),
@@ -890,14 +1209,13 @@ impl EventLogger {
             data,
             evidence,
             severity,
+            sequence: 0,
+            previous_hash: String::new(),
+            hash: String::new(),
         };
 
-        self.events.push(event);
-
-        // Trim events if we exceed max_events
-        if self.events.len() > self.max_events {
-            self.events.drain(0..(self.events.len() - self.max_events));
-        }
+        // Chains the event, writes it to the sinks and trims old events
+        self.append(event);
 
         event_id
     }
@@ -988,6 +1306,28 @@ pub enum SecurityError {
     InvalidKeyAlgorithm,
     #[error("Key expired")]
     KeyExpired,
+    #[error("Keystore error: {0}")]
+    Keystore(String),
+    #[error("Keystore cannot be opened with this secret, or has been tampered with")]
+    KeystoreDecryption,
+    #[error("Invalid certificate: {0}")]
+    InvalidCertificate(String),
+    #[error("Certificate {0} is expired or not yet valid")]
+    CertificateExpired(String),
+    #[error("Certificate {0} is revoked")]
+    CertificateRevoked(String),
+    #[error("Untrusted certificate chain: {0}")]
+    UntrustedCertificateChain(String),
+    #[error("Invalid revocation list: {0}")]
+    InvalidRevocationList(String),
+    #[error("Revocation list of {0} is stale")]
+    StaleRevocationList(String),
+    #[error("Invalid status response: {0}")]
+    InvalidStatusResponse(String),
+    #[error("Event log error: {0}")]
+    EventLog(String),
+    #[error("Event log tampered: {0}")]
+    EventLogTampered(String),
 }
 
 #[cfg(test)]
@@ -1157,6 +1497,58 @@ mod tests {
         assert!(!manager.key_rotation.is_rotation_needed(user_id));
     }
 
+    #[test]
+    fn test_key_rotation_events() {
+        let mut manager = KeyRotationManager::new(3600);
+        let mut events = manager.subscribe("jwt");
+
+        let first = manager.rotate_keys("jwt").unwrap();
+        manager.rotate_keys("other").unwrap();
+        let second = manager.rotate_keys("jwt").unwrap();
+        assert_eq!(first.key_id, KeyPair::key_id_for(&first.public_key));
+        assert_ne!(first.key_id, second.key_id);
+
+        // Only the subscribed owner's rotations are delivered, in order
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.key, first);
+        assert_eq!(event.previous_key_id, None);
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.key.key_id, second.key_id);
+        assert_eq!(event.previous_key_id, Some(first.key_id.clone()));
+        assert!(events.try_recv().is_err());
+
+        // Private keys are not printed
+        assert!(!format!("{:?}", second).contains(&format!("{:?}", second.private_key)));
+    }
+
+    #[test]
+    fn test_rotation_is_announced_only_once_saved() {
+        let mut manager = KeyRotationManager::new(3600);
+        let mut events = manager.subscribe("jwt");
+        let first = manager.rotate_keys("jwt").unwrap();
+        events.try_recv().unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let kek = KeyEncryptionKey::Kek(&[7u8; 32]);
+
+        // An unwritable keystore leaves the previous key current and unannounced
+        let missing = dir.path().join("missing").join("keys.json");
+        assert!(manager.rotate_keys_and_save("jwt", &missing, &kek).is_err());
+        assert_eq!(manager.get_current_key("jwt"), Some(&first));
+        assert!(manager.get_rotation_history("jwt").unwrap().is_empty());
+        assert!(events.try_recv().is_err());
+
+        let path = dir.path().join("keys.json");
+        let second = manager.rotate_keys_and_save("jwt", &path, &kek).unwrap();
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.key.key_id, second.key_id);
+        assert_eq!(event.previous_key_id, Some(first.key_id.clone()));
+
+        let mut restored = KeyRotationManager::new(3600);
+        restored.load_keystore(&path, &kek).unwrap();
+        assert_eq!(restored.get_current_key("jwt"), Some(&second));
+    }
+
     #[test]
     fn test_pii_detection() {
         let manager = SecurityManager::new();
@@ -0,0 +1,534 @@
+//! PII redaction
+//!
+//! A [`Redactor`] runs the [`PIIDetector`] over text and rewrites every
+//! finding according to the [`RedactionPolicy`] of the [`ClassificationLevel`]
+//! the text is released at: kept, masked down to its last characters,
+//! replaced by a keyed token that stays the same for the same value (so logs
+//! can still be correlated), or replaced by the pattern name.
+//!
+//! Redaction is applied to API error responses, to log lines written with
+//! [`redacted_eprintln!`](crate::redacted_eprintln), to the data of
+//! [`SecurityEvent`](super::SecurityEvent)s before they are chained, and to
+//! indexed content. The security manager's event log and the indexer use the
+//! [`global`] redactor at [`LOG_CLASSIFICATION`] unless configured otherwise.
+//! [`RedactingWriter`] redacts any byte stream line by line.
+//!
+//! Implements the Priority 3 feature from DEX-OS-V2.csv:
+//! - Security,Security,Security,Regular Expressions,PII Detection,Medium
+
+use super::{ClassificationLevel, PIIDetectionResult, PIIDetector};
+use hkdf::hmac::{Hmac, Mac};
+use hkdf::Hkdf;
+use once_cell::sync::Lazy;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::{Arc, RwLock};
+use zeroize::Zeroizing;
+
+/// Classification of log lines written through [`write_stderr`]
+pub const LOG_CLASSIFICATION: ClassificationLevel = ClassificationLevel::Internal;
+
+/// Domain separation for token keys derived from a configured secret
+const TOKEN_KEY_DOMAIN: &[u8] = b"dexos-pii-token-key-v1";
+/// Bytes of the keyed hash shown in a token
+const TOKEN_BYTES: usize = 6;
+/// Characters left visible at the end of a masked value
+const MASK_VISIBLE: usize = 4;
+
+/// Process-wide redactor used for logs and API errors
+static GLOBAL: Lazy<RwLock<Arc<Redactor>>> = Lazy::new(|| RwLock::new(Arc::new(Redactor::new())));
+
+/// What to do with a detected value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RedactionAction {
+    /// Leave the value as it is
+    Keep,
+    /// Replace all but the last few letters and digits with `*`
+    Mask,
+    /// Replace with `[Pattern:token]`, the same token for the same value
+    Tokenize,
+    /// Replace with `[Pattern]`
+    Redact,
+}
+
+/// Actions applied at one classification level
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RedactionPolicy {
+    /// Action for patterns without an override
+    pub default: RedactionAction,
+    /// Actions by pattern name
+    pub overrides: HashMap<String, RedactionAction>,
+}
+
+impl RedactionPolicy {
+    /// Apply `default` to every pattern
+    pub fn new(default: RedactionAction) -> Self {
+        Self {
+            default,
+            overrides: HashMap::new(),
+        }
+    }
+
+    /// Apply `action` to the pattern `pattern_name`
+    pub fn with(mut self, pattern_name: &str, action: RedactionAction) -> Self {
+        self.overrides.insert(pattern_name.to_string(), action);
+        self
+    }
+
+    /// Action for the pattern `pattern_name`
+    pub fn action(&self, pattern_name: &str) -> RedactionAction {
+        self.overrides
+            .get(pattern_name)
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+/// Rewrites PII in text according to per-classification policies
+#[derive(Clone)]
+pub struct Redactor {
+    /// Finds the values to rewrite
+    detector: PIIDetector,
+    /// Policy of each classification level
+    policies: HashMap<ClassificationLevel, RedactionPolicy>,
+    /// Key of the token hashes
+    token_key: Zeroizing<[u8; 32]>,
+}
+
+impl Redactor {
+    /// Redactor with the default policies and a random token key, so tokens
+    /// are stable for the life of the process:
+    /// - `Public`: everything masked
+    /// - `Internal`: everything tokenized
+    /// - `Confidential`: wallet and IP addresses kept, the rest tokenized
+    /// - `Secret` and `TopSecret`: everything kept
+    pub fn new() -> Self {
+        let mut token_key = Zeroizing::new([0u8; 32]);
+        OsRng.fill_bytes(token_key.as_mut());
+        let policies = HashMap::from([
+            (
+                ClassificationLevel::Public,
+                RedactionPolicy::new(RedactionAction::Mask),
+            ),
+            (
+                ClassificationLevel::Internal,
+                RedactionPolicy::new(RedactionAction::Tokenize),
+            ),
+            (
+                ClassificationLevel::Confidential,
+                RedactionPolicy::new(RedactionAction::Tokenize)
+                    .with("WalletAddress", RedactionAction::Keep)
+                    .with("IPAddress", RedactionAction::Keep),
+            ),
+            (
+                ClassificationLevel::Secret,
+                RedactionPolicy::new(RedactionAction::Keep),
+            ),
+            (
+                ClassificationLevel::TopSecret,
+                RedactionPolicy::new(RedactionAction::Keep),
+            ),
+        ]);
+        Self {
+            detector: PIIDetector::new(),
+            policies,
+            token_key,
+        }
+    }
+
+    /// Derive the token key from `secret`, so tokens match across restarts
+    /// and across nodes sharing the secret
+    pub fn with_token_secret(mut self, secret: &[u8]) -> Self {
+        Hkdf::<Sha256>::new(Some(TOKEN_KEY_DOMAIN), secret)
+            .expand(b"token", self.token_key.as_mut())
+            .expect("32 bytes is a valid HKDF-SHA256 output length");
+        self
+    }
+
+    /// Use `policy` at `level`
+    pub fn with_policy(mut self, level: ClassificationLevel, policy: RedactionPolicy) -> Self {
+        self.policies.insert(level, policy);
+        self
+    }
+
+    /// Policy applied at `level`
+    pub fn policy(&self, level: &ClassificationLevel) -> &RedactionPolicy {
+        &self.policies[level]
+    }
+
+    /// Non-overlapping PII findings in `text`, in order. Where findings
+    /// overlap the one starting first wins, then the longest.
+    pub fn findings(&self, text: &str) -> Vec<PIIDetectionResult> {
+        let mut findings = self.detector.detect(text);
+        findings.sort_by(|a, b| a.start.cmp(&b.start).then(b.end.cmp(&a.end)));
+        let mut end = 0;
+        findings.retain(|finding| {
+            let keep = finding.start >= end;
+            if keep {
+                end = finding.end;
+            }
+            keep
+        });
+        findings
+    }
+
+    /// `text` with its PII rewritten for release at `level`
+    pub fn redact(&self, text: &str, level: &ClassificationLevel) -> String {
+        let policy = self.policy(level);
+        let mut redacted = String::with_capacity(text.len());
+        let mut cursor = 0;
+        for finding in self.findings(text) {
+            redacted.push_str(&text[cursor..finding.start]);
+            let value = &text[finding.start..finding.end];
+            match policy.action(&finding.pattern_name) {
+                RedactionAction::Keep => redacted.push_str(value),
+                RedactionAction::Mask => redacted.push_str(&mask(value)),
+                RedactionAction::Tokenize => {
+                    redacted.push_str(&self.token(&finding.pattern_name, value))
+                }
+                RedactionAction::Redact => {
+                    redacted.push_str(&format!("[{}]", finding.pattern_name))
+                }
+            }
+            cursor = finding.end;
+        }
+        redacted.push_str(&text[cursor..]);
+        redacted
+    }
+
+    /// Redact every value of `data` in place
+    pub fn redact_map(&self, data: &mut HashMap<String, String>, level: &ClassificationLevel) {
+        for value in data.values_mut() {
+            *value = self.redact(value, level);
+        }
+    }
+
+    /// `[Pattern:token]` for `value`, keyed so tokens cannot be reversed by
+    /// hashing guesses without the key
+    fn token(&self, pattern_name: &str, value: &str) -> String {
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(self.token_key.as_ref())
+            .expect("HMAC accepts any key length");
+        mac.update(pattern_name.as_bytes());
+        mac.update(&[0]);
+        mac.update(value.as_bytes());
+        let digest = mac.finalize().into_bytes();
+        let token: String = digest[..TOKEN_BYTES]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        format!("[{}:{}]", pattern_name, token)
+    }
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Redactor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Redactor")
+            .field("detector", &self.detector)
+            .field("policies", &self.policies)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Writer redacting each complete line before passing it on. A trailing
+/// partial line is held back until it is completed, flushed or dropped, so
+/// values split across writes are still found.
+pub struct RedactingWriter<W: Write> {
+    inner: W,
+    redactor: Arc<Redactor>,
+    level: ClassificationLevel,
+    pending: Vec<u8>,
+}
+
+impl<W: Write> RedactingWriter<W> {
+    pub fn new(inner: W, redactor: Arc<Redactor>, level: ClassificationLevel) -> Self {
+        Self {
+            inner,
+            redactor,
+            level,
+            pending: Vec::new(),
+        }
+    }
+
+    fn write_redacted(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let text = String::from_utf8_lossy(bytes);
+        self.inner
+            .write_all(self.redactor.redact(&text, &self.level).as_bytes())
+    }
+}
+
+impl<W: Write> Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        if let Some(last_newline) = self.pending.iter().rposition(|&byte| byte == b'\n') {
+            let lines: Vec<u8> = self.pending.drain(..=last_newline).collect();
+            self.write_redacted(&lines)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.pending.is_empty() {
+            let partial = std::mem::take(&mut self.pending);
+            self.write_redacted(&partial)?;
+        }
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for RedactingWriter<W> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// Process-wide redactor
+pub fn global() -> Arc<Redactor> {
+    GLOBAL.read().unwrap().clone()
+}
+
+/// Replace the process-wide redactor, e.g. with configured policies or a
+/// shared token secret
+pub fn set_global(redactor: Redactor) {
+    *GLOBAL.write().unwrap() = Arc::new(redactor);
+}
+
+/// Redact `text` with the process-wide redactor at `level`
+pub fn redact(text: &str, level: &ClassificationLevel) -> String {
+    global().redact(text, level)
+}
+
+/// Write a log line to stderr, redacted at [`LOG_CLASSIFICATION`]. Used by
+/// [`redacted_eprintln!`](crate::redacted_eprintln).
+pub fn write_stderr(args: fmt::Arguments<'_>) {
+    let mut stderr = RedactingWriter::new(io::stderr().lock(), global(), LOG_CLASSIFICATION);
+    let _ = writeln!(stderr, "{}", args);
+}
+
+/// `eprintln!` with the line redacted by the process-wide redactor
+#[macro_export]
+macro_rules! redacted_eprintln {
+    ($($arg:tt)*) => {
+        $crate::security::redaction::write_stderr(::std::format_args!($($arg)*))
+    };
+}
+
+/// All but the last [`MASK_VISIBLE`] letters and digits replaced with `*`,
+/// and all of them for short values; separators are kept
+fn mask(value: &str) -> String {
+    let alphanumeric = value.chars().filter(|c| c.is_alphanumeric()).count();
+    let visible = if alphanumeric > 2 * MASK_VISIBLE {
+        MASK_VISIBLE
+    } else {
+        0
+    };
+    let mut seen = 0;
+    value
+        .chars()
+        .map(|c| {
+            if !c.is_alphanumeric() {
+                return c;
+            }
+            seen += 1;
+            if seen > alphanumeric - visible {
+                c
+            } else {
+                '*'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "Withdraw to 0x52908400098527886E0F7030069857D2E4169EE7 from \
+                        GB82 WEST 1234 5698 7654 32, call +44 20 7946 0958 or mail bob@example.com";
+
+    #[test]
+    fn test_new_detectors() {
+        let detector = PIIDetector::new();
+        let names: Vec<(String, String)> = Redactor::new()
+            .findings(TEXT)
+            .into_iter()
+            .map(|finding| (finding.pattern_name, finding.matched_text))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                (
+                    "WalletAddress".to_string(),
+                    "0x52908400098527886E0F7030069857D2E4169EE7".to_string()
+                ),
+                (
+                    "IBAN".to_string(),
+                    "GB82 WEST 1234 5698 7654 32".to_string()
+                ),
+                ("Phone".to_string(), "+44 20 7946 0958".to_string()),
+                ("Email".to_string(), "bob@example.com".to_string()),
+            ]
+        );
+        assert!(detector
+            .detect("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq")
+            .iter()
+            .any(|finding| finding.pattern_name == "WalletAddress"));
+        // IBANs with a wrong check digit are not reported
+        assert!(!detector
+            .detect("GB83 WEST 1234 5698 7654 32")
+            .iter()
+            .any(|finding| finding.pattern_name == "IBAN"));
+    }
+
+    #[test]
+    fn test_plain_numbers_are_not_pii() {
+        let redactor = Redactor::new();
+        for text in [
+            "order 123456789 filled",
+            "order 1234567890 filled",
+            "at 1700000000 or 1700000000123",
+        ] {
+            assert_eq!(
+                redactor.redact(text, &ClassificationLevel::Public),
+                text,
+                "{}",
+                text
+            );
+        }
+        let names: Vec<String> = redactor
+            .findings("ssn 123-45-6789, call (555) 123-4567 or 555.123.4567")
+            .into_iter()
+            .map(|finding| finding.pattern_name)
+            .collect();
+        assert_eq!(names, ["SSN", "Phone", "Phone"]);
+    }
+
+    #[test]
+    fn test_policies_per_classification_level() {
+        let redactor = Redactor::new().with_token_secret(b"shared");
+        let public = redactor.redact(TEXT, &ClassificationLevel::Public);
+        assert!(public.contains(&format!("{}9EE7 ", "*".repeat(38))));
+        assert!(public.contains("**** **** **** **** **54 32"));
+        assert!(public.contains("+** ** **** 0958"));
+        assert!(public.contains("***@******e.com"));
+
+        let internal = redactor.redact(TEXT, &ClassificationLevel::Internal);
+        assert!(internal.contains("[Email:"));
+        // Tokens depend only on the value and the secret
+        let again = Redactor::new()
+            .with_token_secret(b"shared")
+            .redact(TEXT, &ClassificationLevel::Internal);
+        assert_eq!(internal, again);
+        assert_ne!(
+            internal,
+            Redactor::new().redact(TEXT, &ClassificationLevel::Internal)
+        );
+
+        let confidential = redactor.redact(TEXT, &ClassificationLevel::Confidential);
+        assert!(confidential.contains("0x52908400098527886E0F7030069857D2E4169EE7"));
+        assert!(!confidential.contains("bob@example.com"));
+        assert_eq!(redactor.redact(TEXT, &ClassificationLevel::Secret), TEXT);
+
+        let custom = redactor.with_policy(
+            ClassificationLevel::Public,
+            RedactionPolicy::new(RedactionAction::Redact).with("Phone", RedactionAction::Keep),
+        );
+        assert_eq!(
+            custom.redact(
+                "mail bob@example.com or +44 20 7946 0958",
+                &ClassificationLevel::Public
+            ),
+            "mail [Email] or +44 20 7946 0958"
+        );
+    }
+
+    #[test]
+    fn test_event_data_and_indexed_content_are_redacted() {
+        use crate::indexer::IndexerService;
+        use crate::security::{EventLogger, EventType, SeverityLevel};
+
+        let redactor = Arc::new(Redactor::new());
+        let mut logger =
+            EventLogger::new(10).with_redaction(redactor.clone(), ClassificationLevel::Internal);
+        let data = HashMap::from([("contact".to_string(), "bob@example.com".to_string())]);
+        logger.log(
+            EventType::LoginAttempt,
+            "login by bob@example.com".to_string(),
+            None,
+            data,
+            None,
+            SeverityLevel::Info,
+        );
+        let event = &logger.get_events()[0];
+        assert!(event.data["contact"].starts_with("[Email:"));
+        assert!(!event.description.contains("bob@example.com"));
+        assert_eq!(event.hash, crate::security::event_log::event_hash(event));
+
+        // Without configuration the security manager and the indexer still
+        // redact, with the global redactor
+        let mut manager = crate::security::SecurityManager::new();
+        manager.log_event_simple(
+            EventType::LoginAttempt,
+            "login by bob@example.com".to_string(),
+            None,
+            HashMap::new(),
+        );
+        assert!(manager.get_events()[0].description.contains("[Email:"));
+        let mut indexer = IndexerService::new(10);
+        indexer
+            .index_data(
+                "kyc".to_string(),
+                "mail bob@example.com".to_string(),
+                vec![],
+                1,
+                HashMap::new(),
+            )
+            .unwrap();
+        assert!(indexer.get_recent_entries(1)[0].content.contains("[Email:"));
+
+        let mut indexer =
+            IndexerService::new(10).with_redaction(redactor, ClassificationLevel::Public);
+        indexer
+            .index_data(
+                "kyc".to_string(),
+                "iban GB82 WEST 1234 5698 7654 32".to_string(),
+                vec![],
+                1,
+                HashMap::new(),
+            )
+            .unwrap();
+        assert_eq!(
+            indexer.get_recent_entries(1)[0].content,
+            "iban **** **** **** **** **54 32"
+        );
+    }
+
+    #[test]
+    fn test_redacting_writer_handles_split_writes() {
+        let redactor = Arc::new(Redactor::new().with_policy(
+            ClassificationLevel::Internal,
+            RedactionPolicy::new(RedactionAction::Redact),
+        ));
+        let mut out = Vec::new();
+        {
+            let mut writer =
+                RedactingWriter::new(&mut out, redactor, ClassificationLevel::Internal);
+            writer.write_all(b"login from alice@exa").unwrap();
+            writer.write_all(b"mple.com\nuser ").unwrap();
+            writer.write_all(b"bob@example.com").unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "login from [Email]\nuser [Email]"
+        );
+    }
+}
@@ -1,8 +1,12 @@
 //! Database layer for the DEX-OS core engine
 //!
 //! This module provides database functionality for persisting orders,
-//! trades, and other DEX-related data with sharding capabilities.
+//! trades, and other DEX-related data with sharding capabilities. Role
+//! grants and data classifications of the API's access control are kept in
+//! the primary database.
 
+use dex_core::governance::Grant;
+use dex_core::security::{ClassificationLevel, DataClassification};
 use dex_core::types::{Order, OrderId, Trade, TradeId, TraderId, TradingPair};
 use sqlx::{query, Row};
 use sqlx::{postgres::PgPoolOptions, PgPool};
@@ -36,6 +40,19 @@ impl DatabaseManager {
         })
     }
 
+    /// Create a manager whose pool only connects when it is first used, so
+    /// it can be built without a reachable database.
+    pub fn connect_lazy(database_url: &str) -> Result<Self, DatabaseError> {
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect_lazy(database_url)?;
+        Ok(Self {
+            primary_pool: pool,
+            shard_pools: HashMap::new(),
+            num_shards: 1,
+        })
+    }
+
     /// Establish a new connection with sharding support
     pub async fn connect_with_sharding(
         primary_database_url: &str,
@@ -82,6 +99,14 @@ impl DatabaseManager {
         }
     }
 
+    /// Close the primary and shard pools; later queries fail immediately
+    pub async fn close(&self) {
+        self.primary_pool.close().await;
+        for pool in self.shard_pools.values() {
+            pool.close().await;
+        }
+    }
+
     /// Initialize the database schema
     pub async fn initialize(&self) -> Result<(), DatabaseError> {
         // Run migrations on primary database
@@ -546,6 +571,116 @@ impl DatabaseManager {
 
         Ok(stats)
     }
+
+    /// Save a role grant, replacing any earlier grant of the same role
+    pub async fn save_grant(&self, grant: &Grant) -> Result<(), DatabaseError> {
+        query(
+            r#"
+            INSERT INTO role_grants (subject, role, granted_by, granted_at, expires_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (subject, role) DO UPDATE SET
+                granted_by = EXCLUDED.granted_by,
+                granted_at = EXCLUDED.granted_at,
+                expires_at = EXCLUDED.expires_at
+            "#,
+        )
+        .bind(&grant.subject)
+        .bind(&grant.role)
+        .bind(&grant.granted_by)
+        .bind(grant.granted_at as i64)
+        .bind(grant.expires_at.map(|expiry| expiry as i64))
+        .execute(&self.primary_pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Delete the grant of `role` to `subject`, returning whether it existed
+    pub async fn delete_grant(&self, subject: &str, role: &str) -> Result<bool, DatabaseError> {
+        let result = query("DELETE FROM role_grants WHERE subject = $1 AND role = $2")
+            .bind(subject)
+            .bind(role)
+            .execute(&self.primary_pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Load every saved role grant
+    pub async fn load_grants(&self) -> Result<Vec<Grant>, DatabaseError> {
+        let rows = query(
+            "SELECT subject, role, granted_by, granted_at, expires_at FROM role_grants ORDER BY granted_at",
+        )
+        .fetch_all(&self.primary_pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| Grant {
+                subject: row.get("subject"),
+                role: row.get("role"),
+                granted_by: row.get("granted_by"),
+                granted_at: row.get::<i64, _>("granted_at") as u64,
+                expires_at: row
+                    .get::<Option<i64>, _>("expires_at")
+                    .map(|expiry| expiry as u64),
+            })
+            .collect())
+    }
+
+    /// Save the classification of `data_id`, replacing any earlier one
+    pub async fn save_classification(
+        &self,
+        data_id: &str,
+        classification: &DataClassification,
+    ) -> Result<(), DatabaseError> {
+        let level = serde_json::to_value(&classification.level)
+            .map_err(|_| DatabaseError::DataIntegrityError)?;
+        query(
+            r#"
+            INSERT INTO data_classifications (data_id, level, owner, acl, timestamp)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (data_id) DO UPDATE SET
+                level = EXCLUDED.level,
+                owner = EXCLUDED.owner,
+                acl = EXCLUDED.acl,
+                timestamp = EXCLUDED.timestamp
+            "#,
+        )
+        .bind(data_id)
+        .bind(level.as_str())
+        .bind(&classification.owner)
+        .bind(&classification.acl)
+        .bind(classification.timestamp as i64)
+        .execute(&self.primary_pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Load every saved data classification, by data ID
+    pub async fn load_classifications(
+        &self,
+    ) -> Result<Vec<(String, DataClassification)>, DatabaseError> {
+        let rows = query("SELECT data_id, level, owner, acl, timestamp FROM data_classifications")
+            .fetch_all(&self.primary_pool)
+            .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let level: ClassificationLevel =
+                    serde_json::from_value(serde_json::Value::String(row.get("level")))
+                        .map_err(|_| DatabaseError::DataIntegrityError)?;
+                let classification = DataClassification {
+                    level,
+                    owner: row.get("owner"),
+                    acl: row.get("acl"),
+                    timestamp: row.get::<i64, _>("timestamp") as u64,
+                };
+                Ok((row.get("data_id"), classification))
+            })
+            .collect()
+    }
 }
 
 /// Statistics for a database shard
@@ -565,19 +700,3 @@ pub enum DatabaseError {
     #[error("Data integrity error")]
     DataIntegrityError,
 }
-
-#[cfg(test)]
-impl DatabaseManager {
-    /// Create a database manager backed by a lazily connected pool. Useful in tests that do
-    /// not exercise the database but need a handle for wiring filters.
-    pub fn connect_lazy(database_url: &str) -> Result<Self, DatabaseError> {
-        let pool = PgPoolOptions::new()
-            .max_connections(1)
-            .connect_lazy(database_url)?;
-        Ok(Self {
-            primary_pool: pool,
-            shard_pools: HashMap::new(),
-            num_shards: 1,
-        })
-    }
-}
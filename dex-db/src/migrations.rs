@@ -63,6 +63,33 @@ pub fn get_migrations() -> Vec<Migration> {
                 CREATE INDEX IF NOT EXISTS idx_trades_taker_order_id ON trades (taker_order_id)
             "#,
         },
+        Migration {
+            version: 5,
+            description: "Create role grants table",
+            sql: r#"
+                CREATE TABLE IF NOT EXISTS role_grants (
+                    subject TEXT NOT NULL,
+                    role TEXT NOT NULL,
+                    granted_by TEXT NOT NULL,
+                    granted_at BIGINT NOT NULL,
+                    expires_at BIGINT,
+                    PRIMARY KEY (subject, role)
+                )
+            "#,
+        },
+        Migration {
+            version: 6,
+            description: "Create data classifications table",
+            sql: r#"
+                CREATE TABLE IF NOT EXISTS data_classifications (
+                    data_id TEXT PRIMARY KEY,
+                    level TEXT NOT NULL,
+                    owner TEXT NOT NULL,
+                    acl TEXT[] NOT NULL,
+                    timestamp BIGINT NOT NULL
+                )
+            "#,
+        },
     ]
 }
 
@@ -327,7 +327,7 @@ mod tests {
         
         let did = did_result.unwrap();
         assert_eq!(did.id, trader_id);
-        assert_eq!(did.document.public_keys.len(), 1);
+        assert_eq!(did.document.public_keys.len(), 2);
         assert_eq!(did.document.public_keys[0].key_type, "Dilithium");
         
         // Register biometric data
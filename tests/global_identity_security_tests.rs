@@ -27,7 +27,7 @@ mod tests {
         
         let did = did_result.unwrap();
         assert_eq!(did.id, trader_id);
-        assert_eq!(did.document.public_keys.len(), 1);
+        assert_eq!(did.document.public_keys.len(), 2);
         assert_eq!(did.document.public_keys[0].key_type, "Dilithium");
         
         // Register biometric data
@@ -75,27 +75,24 @@ mod tests {
         assert!(identity_manager.get_identity(&trader_id).is_some(), "Identity should be stored");
         assert!(identity_manager.is_valid_identity(&trader_id), "Identity should be valid");
         
-        // Add trusted issuer
+        // Create an issuer DID and trust it
         let issuer_did = "trusted_issuer_did".to_string();
+        let (_, issuer_key) = identity_manager.create_did_with_keys(&issuer_did).expect("Failed to create issuer DID");
         identity_manager.add_trusted_issuer(issuer_did.clone());
         
-        // Create and add verifiable credential
-        let mut credential_data = HashMap::new();
-        credential_data.insert("name".to_string(), "John Doe".to_string());
-        credential_data.insert("kyc_level".to_string(), "verified".to_string());
-        
-        let credential = dex_core::identity::VerifiableCredential {
-            id: "credential_001".to_string(),
-            issuer: issuer_did,
-            subject: trader_id.clone(),
-            data: credential_data,
-            issued_at: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
-            expires_at: None,
-            signature: vec![1, 2, 3, 4, 5],
-        };
+        // Issue a signed verifiable credential and add it
+        let mut credential_data = std::collections::BTreeMap::new();
+        credential_data.insert("name".to_string(), "John Doe".into());
+        credential_data.insert("kyc_level".to_string(), "verified".into());
+        
+        let credential = dex_core::identity::VerifiableCredential::new(
+            "credential_001",
+            &issuer_did,
+            &trader_id,
+            dex_core::identity::KYC_CREDENTIAL_TYPE,
+            credential_data,
+        );
+        let credential = identity_manager.issue_credential(credential, &issuer_key).expect("Failed to issue credential");
         
         let credential_result = identity_manager.add_verifiable_credential(&trader_id, credential);
         assert!(credential_result.is_ok(), "Failed to add verifiable credential");
@@ -119,12 +116,12 @@ mod tests {
     fn test_quantum_secure_identity_feature() {
         // Test Dilithium keypair generation
         let (private_key, public_key) = QuantumSecureCrypto::generate_dilithium_keypair();
-        assert_eq!(private_key.len(), 32, "Private key should be 32 bytes");
-        assert_eq!(public_key.len(), 32, "Public key should be 32 bytes");
+        assert_eq!(private_key.len(), 32, "Private key seed should be 32 bytes");
+        assert_eq!(public_key.len(), 1952, "ML-DSA-65 public key should be 1952 bytes");
         
         // Test quantum-secure signing
         let data_to_sign = b"critical_identity_data_for_signing";
-        let signature = QuantumSecureCrypto::quantum_sign(&private_key, data_to_sign);
+        let signature = QuantumSecureCrypto::quantum_sign(&private_key, data_to_sign).unwrap();
         assert!(!signature.is_empty(), "Signature should not be empty");
         
         // Test quantum-secure verification with correct data
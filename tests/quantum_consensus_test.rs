@@ -174,21 +174,22 @@ fn test_quantum_consensus_error_handling() {
 /// Test QVRF cryptographic functions
 #[test]
 fn test_qvrf_cryptographic_functions() {
-    let qvrf = QVRF::new(vec![1, 2, 3, 4], vec![5, 6, 7, 8]);
+    let qvrf = QVRF::new(&[1u8; 32]).unwrap();
+    let public_key = qvrf.public_key();
     
     // Test generation and verification
     let input = b"test input for QVRF";
     let (output, proof) = qvrf.generate(input).unwrap();
     
-    // Verify the generated output
-    assert!(qvrf.verify(input, &output, &proof).unwrap());
+    // Verify the generated output with only the public key
+    assert!(QVRF::verify(&public_key, input, &output, &proof).unwrap());
     
     // Test with different input
     let different_input = b"different input";
     let (different_output, different_proof) = qvrf.generate(different_input).unwrap();
     
     // Should not verify with different input
-    assert!(!qvrf.verify(input, &different_output, &different_proof).unwrap());
+    assert!(!QVRF::verify(&public_key, input, &different_output, &different_proof).unwrap());
 }
 
 /// Test security-related validation
@@ -51,11 +51,13 @@ fn test_security__comprehensive__integration__works__on_request() {
             .as_secs(),
         origin: "security_module".to_string(),
         data_type: "zk_proof_audit".to_string(),
+        version: Default::default(),
+        signature: None,
     };
     
     // 5. Add the sync data to the gossip network
     tokio_test::block_on(async {
-        gossip_node.add_sync_data(sync_data).await;
+        gossip_node.add_sync_data(sync_data).await.unwrap();
         
         // 6. Verify the data was added
         let data_map = gossip_node.get_sync_data().await;
@@ -143,10 +145,12 @@ fn test_security__comprehensive__policy__validates__on_request() {
         timestamp: 1234567890,
         origin: "test_node".to_string(),
         data_type: "test".to_string(),
+        version: Default::default(),
+        signature: None,
     };
     
     tokio_test::block_on(async {
-        gossip_node.add_sync_data(sync_data.clone()).await;
+        gossip_node.add_sync_data(sync_data.clone()).await.unwrap();
         let retrieved_data = gossip_node.get_data_by_id("validation_test").await;
         assert!(retrieved_data.is_some());
         assert_eq!(retrieved_data.unwrap().id, sync_data.id);